@@ -1,3 +1,15 @@
 fn main() {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("trace") => {
+            phire_ui::trace_main(args.collect());
+            return;
+        }
+        Some("drive") => {
+            phire_ui::drive_main(args.collect());
+            return;
+        }
+        _ => {}
+    }
     phire_ui::quad_main();
 }
@@ -0,0 +1,29 @@
+// runs every script under phire-ui/scenarios through `phire-main drive`. It opens a window and needs a GL context,
+// so it's left out of a plain `cargo test`; machines with a display run it with `cargo test -- --ignored`
+
+use std::{path::Path, process::Command};
+
+#[test]
+#[ignore = "needs a display"]
+fn scenarios() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../phire-ui/scenarios");
+    let mut scripts: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|it| it.unwrap().path())
+        .filter(|it| it.extension().map_or(false, |ext| ext == "scene"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in {}", dir.display());
+    let failed: Vec<_> = scripts
+        .iter()
+        .filter(|script| {
+            !Command::new(env!("CARGO_BIN_EXE_phire-main"))
+                .arg("drive")
+                .arg(script)
+                .status()
+                .expect("failed to run phire-main")
+                .success()
+        })
+        .collect();
+    assert!(failed.is_empty(), "failed scenarios: {failed:?}");
+}
@@ -0,0 +1,24 @@
+unlocked = Achievement unlocked: { $name }
+unlocked-count = Unlocked
+total-plays = { $count } plays in total
+hidden-name = Hidden achievement
+hidden-desc = Keep playing to find out
+
+first-play-name = First Steps
+first-play-desc = Finish a chart
+plays-100-name = Regular
+plays-100-desc = Finish 100 plays
+plays-1000-name = Devotee
+plays-1000-desc = Finish 1000 plays
+first-fc-name = Full Combo
+first-fc-desc = Get a full combo on any chart
+first-ap-name = All Perfect
+first-ap-desc = Hit every note of a chart perfectly
+combo-1000-name = Unbroken
+combo-1000-desc = Reach a combo of 1000
+hard-fc-name = Summit
+hard-fc-desc = Get a full combo on a chart of difficulty 15 or higher
+fc-120fps-name = Silky Smooth
+fc-120fps-desc = Get a full combo while running at 120 FPS
+library-50-name = Collector
+library-50-desc = Have 50 charts in your library
@@ -0,0 +1,4 @@
+rating = Rating
+empty = Play some charts to get a rating
+hint = The best 27 plays and the best 3 all perfect plays count towards the rating
+trophies = Trophies
@@ -1,5 +1,6 @@
 time = Time
 rev-time = Time (rev)
+hot = Hottest
 name = Name
 rev-name = Name (rev)
 rating = Rating
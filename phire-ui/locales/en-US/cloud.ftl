@@ -0,0 +1,3 @@
+need-login = Log in to sync your data
+sync-failed = Failed to sync with the cloud
+sync-done = Synced with the cloud
@@ -15,3 +15,6 @@ chart-special = Special
 chart-unstable = Unstable
 
 list-empty = Nothing here
+illu-retry = Retry image
+
+slow-down = Too many requests, slowing down
@@ -0,0 +1,20 @@
+status-queued = Waiting
+status-paused = Paused
+status-chart = Downloading chart
+status-extract = Extracting
+status-saving = Saving
+status-done = Downloaded
+status-failed = Failed
+
+success = Downloaded { $name }
+failed = Failed to download { $name }
+request-failed = Request failed
+cancelled = Download cancelled
+
+empty = No downloads
+clear-finished = Clear finished
+pause = Pause
+resume = Resume
+retry = Retry
+cancel = Cancel
+remove = Remove
@@ -0,0 +1,21 @@
+loading = Loading chart
+load-failed = Failed to load the chart for editing
+cursor = Beat { $beat } · { $time }s · BPM { $bpm }
+line = Line { $id }
+no-lines = No lines
+tap = Tap
+drag = Drag
+flick = Flick
+hold = Hold
+snap = Snap 1/{ $div }
+hold-length = Hold { $beats } beats
+play = Play
+pause = Pause
+preview = Preview
+save = Save
+saved = Chart saved
+save-failed = Failed to save chart
+preview-failed = Failed to preview chart
+discard = Discard changes
+discard-content = The chart has unsaved changes. Leave the editor anyway?
+hint = Tap the grid to place a note, tap a note to remove it, drag to scroll.
@@ -3,6 +3,9 @@ play = Play
 event = Event
 respack = Respack
 settings = Settings
+best = Best
+downloads = Downloads
+lists = Lists
 
 not-opened = Not available yet
 not-logged-in = Not logged in
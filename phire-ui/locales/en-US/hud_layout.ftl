@@ -0,0 +1,8 @@
+pause = Pause
+score = Score
+combo = Combo
+bar = Progress bar
+
+scale-of = Size of { $name }
+reset = Reset
+reset-all = Reset all
@@ -7,3 +7,5 @@ import-success = Imported successfully
 import-failed = Import failed
 import-respack-success = Imported successfully
 import-respack-failed = Failed to import respack
+import-pack-success = Imported { $count } charts
+import-pack-partial = Imported { $count } charts, { $failed } could not be imported
@@ -16,3 +16,24 @@ search = Search
 offline-mode = Can't load online beatmaps in offline mode
 
 must-login = You must login to view online charts
+
+export = Export
+export-cancel = Cancel
+export-confirm = Export ({ $count })
+export-hint = Tap charts to select them for the pack
+export-none = No chart selected
+exporting = Exporting
+export-failed = Failed to export charts
+
+collection-all = All charts
+collection-new = New collection
+collection-rename = Rename collection
+collection-delete = Delete collection
+collection-delete-confirm = Delete the collection "{ $name }"? The charts in it are kept.
+collection-name-empty = The name can't be empty
+collection-add = Add to…
+collection-added = Added to { $name }
+collection-remove = Remove
+reorder = Reorder
+reorder-done = Done
+reorder-hint = Drag charts to rearrange the collection
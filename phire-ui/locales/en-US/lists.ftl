@@ -0,0 +1,10 @@
+load-failed = Failed to load the lists
+empty = No lists yet
+
+progress = Played { $played } / { $total }
+completed = Completed
+summary = { $downloaded } downloaded · { $played } played · { $total } charts
+
+download-all = Download all
+all-downloaded = Every chart in this list is already downloaded
+queued = Queued { $count } charts for download
@@ -84,3 +84,7 @@ msg-room-cycle = { $cycle ->
   [true] Room changed to cycling mode
   *[other] Room changed to normal mode
 }
+
+spectate-room = Spectate Room
+spectating = Spectating
+spectate-leaderboard = Live Standings
@@ -1,3 +1,9 @@
 now = Now:
 avg = Avg:
 estimated = Estimated Latency:
+
+wizard = Auto calibrate
+wizard-cancel = Cancel
+wizard-audio = Listen and tap on the beat without looking ({ $n }/{ $total })
+wizard-visual = Tap when the block reaches the line ({ $n }/{ $total })
+wizard-done = Offset set to { $offset }ms, judge offset { $judge }ms
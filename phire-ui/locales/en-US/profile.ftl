@@ -10,10 +10,41 @@ load-user-failed = Failed to fetch user info
 edit-avatar-success = Avatar updated
 edit-avatar-failed = Failed to update avatar
 
-uploading-avatar = Uploading avatar
+edit-banner-success = Banner updated
+edit-banner-failed = Failed to update banner
+edit-bio = Edit bio
+edit-bio-success = Bio updated
+edit-bio-failed = Failed to update bio
+bio-empty = Tap to write a bio
+bio-too-long = Bio can't be longer than { $max } characters
+
+image-invalid = Not a supported image
+image-too-large = Image can't be larger than { $max } MB
+image-too-small = Image must be at least { $min } pixels on each side
+
+uploading-avatar = Uploading avatar ({ $progress }%)
+uploading-banner = Uploading banner ({ $progress }%)
+saving-bio = Saving bio
 
 load-record-failed = Failed to load records
 
 last-login = Last login: { $time }
 badge-admin = Admin
 badge-sponsor = Sponsor
+badge-hint = Tap a badge to show it next to your name, tap it again to hide it
+edit-badge-success = Badge updated
+edit-badge-failed = Failed to update badge
+saving-badge = Saving badge
+
+stat-plays = Plays
+stat-avg-acc = Avg. accuracy
+stat-best-acc = Best accuracy
+stat-fc = Full combos
+stat-phi = All perfects
+recent-plays = Recent plays
+no-plays = No plays yet
+uploaded-charts = { $count ->
+  [one] 1 uploaded chart
+  *[other] { $count } uploaded charts
+}
+load-charts-failed = Failed to load uploaded charts
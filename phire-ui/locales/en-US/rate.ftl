@@ -9,3 +9,17 @@ lower-bound = Lower bound
 upper-bound = Upper bound
 
 filter-by-tags = Filter by tags
+remove = Remove
+
+comments = Comments
+comments-empty = No comments yet
+comments-load-failed = Failed to load comments
+comment-post = Comment
+comment-posted = Comment posted
+comment-edit = Edit
+comment-edited = { " " }(edited)
+comment-delete = Delete
+comment-delete-content = Delete this comment? This can't be undone.
+comment-report = Report
+comment-reported = Thanks, the comment was reported
+comment-failed = Action failed
@@ -10,3 +10,7 @@ info-content =
 
 cant-delete-builtin = Can't delete builtin respack
 deleted = Deleted
+
+layer-add = Stack
+layer-remove = Unstack
+layered-name = #{ $pos } { $name }
@@ -0,0 +1,5 @@
+title = Safe Mode
+content = The game didn't close properly last time. To keep it from crashing again, resource packs, chart shaders and videos are turned off until the next launch.
+keep = Keep settings
+reset-respack = Reset resource pack
+respack-reset = Resource pack reset to the default
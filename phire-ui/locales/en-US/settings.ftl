@@ -16,10 +16,45 @@ item-mp-sub = Enable multiplayer mode
 item-mp-addr = Multiplayer server
 item-mp-addr-sub = Server address, 'host:port'
 item-mp-addr-invalid = Invalid server address
+item-translate = Translate descriptions
+item-translate-sub = Show chart descriptions in your language, tap to see the original
+item-translate-api = Translation server
+item-translate-api-sub = A LibreTranslate compatible address
 item-lowq = Low quality mode
 item-lowq-sub = Enable this if the UI is laggy
+item-linear-color = Linear color
+item-linear-color-sub = Blends effects and tints the way editors do, keeps dark colors from banding. Needs OpenGL (ES) 3
+item-max-fps = Frame rate limit
+item-max-fps-sub = Caps how often the screen is redrawn to save battery
+item-max-fps-unlimited = Off
+item-vsync = VSync
+item-vsync-sub = Syncs frames to the display refresh, takes effect after a restart
+item-perf-overlay = Performance Overlay
+item-perf-overlay-sub = Shows frame times and note batching stats in a corner
+item-memory-overlay = Memory Overlay
+item-memory-overlay-sub = Shows texture, audio and chart memory, and what closed pages left behind
+item-instanced-notes = Instanced Notes
+item-instanced-notes-sub = Draws notes with GPU instancing where supported, turn off if notes look wrong
+item-live-stats = Stream Overlay
+item-live-stats-sub = Serves live stats and an OBS browser source at http://127.0.0.1:{ $port }
+item-live-stats-failed = Failed to start the overlay server
+item-record-badges = Best scores on chart cards
+item-record-badges-sub = Turn off for a clean chart list in screenshots
 item-insecure = Insecure mode
 item-insecure-sub = Enable this if you can't use online functionalities. Makes your connection insecure!
+item-cloud = Cloud sync
+item-cloud-sub = Keep settings, offsets and best records in your account
+item-cloud-sync = Sync
+item-cloud-sync-last = Last synced at { $time }
+item-cloud-sync-never = Never synced
+item-cloud-sync-now = Sync now
+item-cloud-syncing = Syncing…
+item-storage = Storage
+item-storage-sub = View and clean up space used by charts, respacks and caches
+item-storage-manage = Manage
+item-export = Export records
+item-export-sub = Save all local records as a spreadsheet or JSON file
+item-export-failed = Failed to export records
 
 item-adjust = Automatic time adjustment
 item-adjust-sub = Adjusts time dynamically to sync the music and chart
@@ -27,21 +62,76 @@ item-auto-latency = Auto Latency Adjustment
 item-auto-latency-sub = Dynamically estimate and adjust device latency in real time
 item-music = Music volume
 item-sfx = Sound effect volume
+item-sfx-limiter = Hitsound limiter
+item-sfx-limiter-sub = Soften dense chords instead of letting them clip
+item-sfx-voices = Hitsound voices
+item-sfx-voices-sub = Maximum hitsounds playing at once, further ones are skipped until one has faded
+item-premix = Pre-mix keysounds
+item-premix-sub = Mix autoplay and fake-note hitsounds into the music when loading, avoiding stutter on keysound-heavy charts
 item-bgm = BGM volume
+item-list-preview = Song previews in lists
+item-list-preview-sub = Tap a chart once to hear its preview, tap again to open it
 item-cali = Adjust offset
 item-exclusive-audio = Exclusive Audio
 item-exclusive-audio-sub = Use exclusive access to reduce latency, but may prevent the sound from being recorded
 item-audio-compatibility = Audio Compatibility Mode
 
 item-show-acc = Show real-time accuracy
+item-acc-hud-counts = Show judgement counts
+item-acc-hud-counts-sub = Live perfect/good/bad/miss counts and early/late hits
+item-acc-hud-position = Accuracy display position
+item-hud-layout = HUD layout
+item-hud-layout-sub = Drag the pause button, score, combo and progress bar around
+item-hud-layout-edit = Edit
+item-progress-bar = Progress bar
+progress-bar-top = Top
+progress-bar-circle = Around pause
+progress-bar-sections = Sections
+note-palette-off = Pack
+note-palette-high-contrast = High contrast
+note-palette-red-green = Red-green safe
+note-palette-blue-yellow = Blue-yellow safe
+hud-position-score = Under score
+hud-position-top-left = Top left
+hud-position-bottom-left = Bottom left
+hud-position-bottom-right = Bottom right
 item-dc-pause = Double tap to pause
+item-duck-on-miss = Duck music on misses
+item-duck-on-miss-sub = Lower the music and raise hitsounds for a moment after several misses in a row
 item-dhint = Highlight simul. notes
 item-dhint-sub = Notes that touch line simultaneously will be highlighted
+item-curved-holds = Curved holds
+item-curved-holds-sub = Holds bend to follow lines that rotate while they're coming in
 item-opt = Aggressive optimization
 item-opt-sub = Improves performance, but can cause incorrect behavior
 item-speed = Speed
 item-note-size = Note size
+item-note-palette = Note colors
+item-note-palette-sub = Recolor notes by type with colorblind-friendly palettes
+item-note-markers = Note shape markers
+item-note-markers-sub = Mark drags with a dot and flicks with an arrow
+item-hit-area = Hit area width
+item-hit-area-sub = Widens how far from a note a tap still counts
+item-full-screen-notes = Full-screen judgement for
+item-full-screen-notes-sub = These notes can be hit anywhere along their line
+note-tap = Tap
+note-drag = Drag
+note-flick = Flick
+note-hold = Hold
 item-render-extra = Enable Extra (Shader/Effect)
+item-judge-preset = Judgement windows
+item-judge-preset-sub = Records are only saved with the standard windows
+judge-preset-standard = Standard
+judge-preset-strict = Strict
+judge-preset-lenient = Lenient
+judge-preset-custom = Custom
+item-judge-perfect = Perfect window
+item-judge-good = Good window
+item-judge-bad = Bad window
+item-hold-leniency = Hold release leniency
+item-hold-leniency-sub = Letting go this early before a hold ends still counts. Records are not saved
+item-hold-no-penalty = No release penalty
+item-hold-no-penalty-sub = Holds are never missed once started. Records are not saved
 
 item-chart-debug-line = Chart Debug Mode - Line
 item-chart-debug-line-sub = Display line properties
@@ -49,6 +139,10 @@ item-chart-debug-note = Chart Debug Mode - Note
 item-chart-debug-note-sub = Display note properties
 item-touch-debug = Touch Debug Mode
 item-touch-debug-sub = Display touch points
+item-keyboard-lanes = Keyboard Lanes
+item-keyboard-lanes-sub = Map keys to screen strips instead of hitting the nearest note
+item-key-lanes = Lane Keys
+item-key-lanes-sub = One key per lane, from left to right
 item-chart_ratio = Chart Zoom Ratio
 item-fade = Note Fade Out/Fade In
 item-fade-sub = Fade out when less than 0 Fade in when greater than 0
@@ -61,6 +155,8 @@ item-rotation-mode = Rotation Mode
 item-rotation-flat-mode = Rotation Mode - Lay Flat
 item-rotation-flat-mode-sub = Orientation calibrates when the game starts. If its wrong, tap reset on the pause screen.
 item-shake-play-mode = Shake Mode
+item-hot-reload = Chart Hot Reload
+item-hot-reload-sub = Reload unpacked charts when their files change while playing
 
 load-cali-failed = Failed to load audio
 not-combo = Cannot be COMBO
@@ -1,11 +1,15 @@
 
 load-preview-failed = Failed to load song preview
+chart-preview = Preview
+chart-preview-stop = Stop
+chart-preview-failed = Failed to load the preview
 load-charts-failed = Failed to load beatmaps
 no-chart-for-download = This beatmap is not downloadable
 
 load-chart-failed = Failed to load the beatmap
 
 dl-cancel = Cancel
+dl-background = Hide
 dl-status-fetch = Fetching info
 dl-status-song = Downloading song
 dl-status-chart = Downloading chart
@@ -26,8 +30,24 @@ switched-to-offline = Switched to offline mode
 
 delete = Delete
 rate = Rate
+comments = Comments
 exercise = Exercise
 offset = Adjust offset
+chart-editor = Edit chart
+chart-editor-not-unpacked = Only unpacked charts can be edited
+convert-rpe = Export as RPE
+convert-pec = Export as PEC
+converting = Converting
+convert-done = Saved as { $file }
+convert-failed = Failed to convert chart
+convert-not-unpacked = Only unpacked charts can be converted
+
+play-online = Play online
+play-online-loading = Streaming chart
+play-online-failed = Failed to stream chart
+play-online-keep = Keep this chart in your library?
+play-online-discard = No
+play-online-save = Keep
 
 edit-cancel = Cancel
 edit-save = Save
@@ -59,17 +79,42 @@ uploading = Uploading
 upload-chart-failed = Failed to upload beatmap
 upload-success = Uploaded successfully, please wait for review!
 upload-failed = Upload failed
+upload-queued = Saved on this device, the record will be uploaded once the server can be reached
+upload-queued-done = { $count ->
+  [one] Uploaded a record saved while offline
+  *[other] Uploaded { $count } records saved while offline
+}
 
 ldb = Leaderboard
 ldb-load-failed = Failed to load leaderboard
 ldb-no-rank = None
 ldb-score = Score
 ldb-std = Purity
+ldb-global = Global
+ldb-friends = Friends
+
+history = My Records
+history-empty = No plays recorded yet
+history-score = Score
+history-acc = Accuracy
+history-best = Personal best
+history-detail = { $combo }x  { $perfect } / { $good } / { $bad } / { $miss }
+history-mods = Mods
+history-chart-updated = Chart updated
+previous-version = Previous version
+best-shot = Best result
+best-shot-load-failed = Failed to load the screenshot
 
 info-name = Name
 info-composer = Composer
 info-charter = Charter
 info-difficulty = Difficulty
+info-skills = Skill breakdown
+info-skills-loading = Analyzing the chart…
+skill-speed = Speed
+skill-stamina = Stamina
+skill-flick = Flicks
+skill-rhythm = Rhythm
 info-desc = Description
 info-rating = Rating
 info-type = Type
@@ -100,9 +145,20 @@ mods-flip-x-sub = Flip on the X-axis
 mods-fade-out = Fade out
 mods-fade-out-sub = Notes disappear when they approach the line
 mods-full-screen-judge = Full screen judge
+mods-sudden-death = Sudden death
+mods-sudden-death-sub = The play ends at the first judgement that isn't a perfect
+mods-no-fail = No fail
+mods-no-fail-sub = Only cancels sudden death, plays without it already go to the end
+mods-flip-y = Flip Y
+mods-flip-y-sub = Mirror the chart upside down
+mods-random = Random
+mods-random-sub = Shuffle where notes fall along each line
+mods-note-swap = Note swap
+mods-note-swap-sub = Taps become drags and drags become taps
 
 rate-failed = Rate failed
 rate-done = Rated successfully
+rate-removed = Rating removed
 
 need-update = Update needed
 need-update-info-only-content = The info of beatmap has updated. Do you want to sync the update?
@@ -0,0 +1,19 @@
+
+downloaded = Downloaded charts
+custom = Imported charts
+respack = Respacks
+image-cache = Image cache
+logs = Logs
+
+total = Total: { $size }
+calculating = Calculating...
+sort-by-size = By size
+clean = Clean
+clean-all = Clean all
+
+clean-confirm = Confirm Cleanup
+clean-confirm-content = All files in "{ $name }" will be deleted. This can't be undone.
+clean-all-confirm-content = All charts, respacks, caches and logs will be deleted. This can't be undone.
+cleaning = Cleaning up...
+clean-failed = Failed to clean up
+cleaned = Cleaned
@@ -3,6 +3,7 @@ filter = Filter by tags
 edit = Edit tags
 
 invalid-tag = Invalid tag
+invalid-range = Expected a range like 10-15
 
 wanted = Wanted
 unwanted = Unwanted
@@ -20,3 +21,12 @@ visual = Visual
 filter-me = Uploaded by me
 filter-unreviewed = Unreviewed
 filter-stabilize = Stabilize request
+filter-level = Level
+filter-notes = Notes
+filter-any = Any
+
+level-ez = Easy
+level-hd = Hard
+level-in = Insane
+level-at = Another
+level-sp = Special
@@ -0,0 +1,3 @@
+translating = Translating…
+show-original = Translated, tap to show original
+show-translated = Original, tap to show translation
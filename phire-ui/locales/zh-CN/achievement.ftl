@@ -0,0 +1,24 @@
+unlocked = 解锁成就：{ $name }
+unlocked-count = 已解锁
+total-plays = 累计游玩 { $count } 次
+hidden-name = 隐藏成就
+hidden-desc = 继续游玩以揭晓
+
+first-play-name = 初次启程
+first-play-desc = 完成一张谱面
+plays-100-name = 常客
+plays-100-desc = 累计完成 100 次游玩
+plays-1000-name = 痴迷
+plays-1000-desc = 累计完成 1000 次游玩
+first-fc-name = 全连
+first-fc-desc = 在任意谱面达成 Full Combo
+first-ap-name = 完美无瑕
+first-ap-desc = 以全 Perfect 完成一张谱面
+combo-1000-name = 连绵不绝
+combo-1000-desc = 达成 1000 连击
+hard-fc-name = 登顶
+hard-fc-desc = 在定数 15 及以上的谱面达成 Full Combo
+fc-120fps-name = 丝般顺滑
+fc-120fps-desc = 以 120 FPS 运行时达成 Full Combo
+library-50-name = 收藏家
+library-50-desc = 谱面库中拥有 50 张谱面
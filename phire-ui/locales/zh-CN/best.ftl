@@ -0,0 +1,4 @@
+rating = 评分
+empty = 游玩谱面后即可获得评分
+hint = 最好的 27 次游玩与最好的 3 次全 Perfect 游玩计入评分
+trophies = 成就
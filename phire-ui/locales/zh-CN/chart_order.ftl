@@ -1,5 +1,6 @@
 time = 从新到旧
 rev-time = 从旧到新
+hot = 最热门
 name = 名字正序
 rev-name = 名字倒序
 rating = 评分顺序
@@ -0,0 +1,3 @@
+need-login = 登录后才能同步数据
+sync-failed = 云同步失败
+sync-done = 云同步完成
@@ -15,3 +15,6 @@ chart-special = 特殊
 chart-unstable = 未上架
 
 list-empty = 空空如也
+illu-retry = 重新加载封面
+
+slow-down = 请求过于频繁，正在放慢速度
@@ -0,0 +1,20 @@
+status-queued = 等待中
+status-paused = 已暂停
+status-chart = 正在下载谱面
+status-extract = 正在解压
+status-saving = 正在保存
+status-done = 下载完成
+status-failed = 下载失败
+
+success = { $name } 下载完成
+failed = { $name } 下载失败
+request-failed = 请求失败
+cancelled = 已取消下载
+
+empty = 没有下载任务
+clear-finished = 清除已完成
+pause = 暂停
+resume = 继续
+retry = 重试
+cancel = 取消
+remove = 移除
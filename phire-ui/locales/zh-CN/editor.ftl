@@ -0,0 +1,21 @@
+loading = 正在加载谱面
+load-failed = 加载谱面失败
+cursor = 第 { $beat } 拍 · { $time } 秒 · BPM { $bpm }
+line = 判定线 { $id }
+no-lines = 没有判定线
+tap = Tap
+drag = Drag
+flick = Flick
+hold = Hold
+snap = 吸附 1/{ $div }
+hold-length = Hold 长度 { $beats } 拍
+play = 播放
+pause = 暂停
+preview = 预览
+save = 保存
+saved = 谱面已保存
+save-failed = 保存谱面失败
+preview-failed = 预览谱面失败
+discard = 放弃修改
+discard-content = 谱面有未保存的修改，确定要退出编辑器吗？
+hint = 点击网格放置音符，点击音符删除，拖动以滚动。
@@ -3,6 +3,9 @@ play = 游玩
 event = 活动
 respack = 资源包
 settings = 设置
+best = 最佳成绩
+downloads = 下载管理
+lists = 精选列表
 
 not-opened = 功能尚未开启
 not-logged-in = 未登录
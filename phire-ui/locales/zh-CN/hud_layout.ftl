@@ -0,0 +1,8 @@
+pause = 暂停
+score = 分数
+combo = 连击
+bar = 进度条
+
+scale-of = { $name }大小
+reset = 重置
+reset-all = 全部重置
@@ -7,3 +7,5 @@ import-success = 导入成功
 import-failed = 导入失败
 import-respack-success = 导入资源包成功
 import-respack-failed = 导入资源包失败
+import-pack-success = 已导入 { $count } 张谱面
+import-pack-partial = 已导入 { $count } 张谱面，{ $failed } 张导入失败
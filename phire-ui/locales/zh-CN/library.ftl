@@ -16,3 +16,24 @@ search = 搜索
 offline-mode = 离线模式下无法加载在线谱面
 
 must-login = 登录才可查看在线谱面
+
+export = 导出
+export-cancel = 取消
+export-confirm = 导出 ({ $count })
+export-hint = 点击谱面以选择要打包的谱面
+export-none = 未选择谱面
+exporting = 导出中
+export-failed = 导出谱面失败
+
+collection-all = 全部谱面
+collection-new = 新建收藏夹
+collection-rename = 重命名收藏夹
+collection-delete = 删除收藏夹
+collection-delete-confirm = 确定删除收藏夹「{ $name }」吗？其中的谱面会保留。
+collection-name-empty = 名称不能为空
+collection-add = 加入…
+collection-added = 已加入 { $name }
+collection-remove = 移出
+reorder = 排序
+reorder-done = 完成
+reorder-hint = 拖动谱面以调整收藏夹中的顺序
@@ -0,0 +1,10 @@
+load-failed = 加载列表失败
+empty = 暂无列表
+
+progress = 已游玩 { $played } / { $total }
+completed = 已完成
+summary = 已下载 { $downloaded } · 已游玩 { $played } · 共 { $total } 张谱面
+
+download-all = 全部下载
+all-downloaded = 列表中的谱面均已下载
+queued = 已将 { $count } 张谱面加入下载队列
@@ -84,3 +84,7 @@ msg-room-cycle = { $cycle ->
   [true] 房间已切换为循环模式
   *[other] 房间已切换为普通模式
 }
+
+spectate-room = 观战
+spectating = 观战中
+spectate-leaderboard = 实时排名
@@ -1,3 +1,9 @@
 now = 当前偏移:
 avg = 平均偏移:
 estimated = 预估延时:
+
+wizard = 自动校准
+wizard-cancel = 取消
+wizard-audio = 不看屏幕，跟着节拍点击（{ $n }/{ $total }）
+wizard-visual = 在方块到达线时点击（{ $n }/{ $total }）
+wizard-done = 偏移已设为 { $offset }ms，判定偏移 { $judge }ms
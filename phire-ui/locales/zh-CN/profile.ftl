@@ -10,10 +10,38 @@ load-user-failed = 加载用户信息失败
 edit-avatar-success = 头像已更新
 edit-avatar-failed = 上传头像失败
 
-uploading-avatar = 上传头像中
+edit-banner-success = 横幅已更新
+edit-banner-failed = 更新横幅失败
+edit-bio = 编辑简介
+edit-bio-success = 简介已更新
+edit-bio-failed = 更新简介失败
+bio-empty = 点击编写简介
+bio-too-long = 简介不能超过 { $max } 个字符
+
+image-invalid = 不支持的图片格式
+image-too-large = 图片不能大于 { $max } MB
+image-too-small = 图片每边至少需要 { $min } 像素
+
+uploading-avatar = 正在上传头像（{ $progress }%）
+uploading-banner = 正在上传横幅（{ $progress }%）
+saving-bio = 正在保存简介
 
 load-record-failed = 加载游玩记录失败
 
 last-login = 最近登录：{ $time }
 badge-admin = 管理员
 badge-sponsor = 赞助者
+badge-hint = 点击徽章以在名字旁展示，再次点击取消展示
+edit-badge-success = 徽章已更新
+edit-badge-failed = 更新徽章失败
+saving-badge = 正在保存徽章
+
+stat-plays = 游玩次数
+stat-avg-acc = 平均准度
+stat-best-acc = 最高准度
+stat-fc = Full Combo
+stat-phi = All Perfect
+recent-plays = 最近游玩
+no-plays = 暂无游玩记录
+uploaded-charts = 上传的谱面（{ $count }）
+load-charts-failed = 加载上传的谱面失败
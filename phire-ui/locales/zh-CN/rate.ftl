@@ -9,3 +9,17 @@ lower-bound = 最低评分
 upper-bound = 最高评分
 
 filter-by-tags = 按标签过滤
+remove = 取消评分
+
+comments = 评论
+comments-empty = 还没有评论
+comments-load-failed = 加载评论失败
+comment-post = 发表评论
+comment-posted = 评论已发表
+comment-edit = 编辑
+comment-edited = （已编辑）
+comment-delete = 删除
+comment-delete-content = 确定删除这条评论吗？此操作无法撤销。
+comment-report = 举报
+comment-reported = 已举报该评论，感谢反馈
+comment-failed = 操作失败
@@ -10,3 +10,7 @@ info-content =
 
 cant-delete-builtin = 不能删除内置资源包
 deleted = 已删除
+
+layer-add = 叠加
+layer-remove = 取消叠加
+layered-name = #{ $pos } { $name }
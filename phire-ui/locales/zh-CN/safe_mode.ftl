@@ -0,0 +1,5 @@
+title = 安全模式
+content = 上次游戏未能正常退出。为避免再次崩溃，本次启动将不加载资源包、谱面着色器和视频，下次启动时恢复。
+keep = 保留设置
+reset-respack = 重置资源包
+respack-reset = 已恢复默认资源包
@@ -16,10 +16,45 @@ item-mp-sub = 启用多人游戏
 item-mp-addr = 多人游戏服务器
 item-mp-addr-sub = 服务器地址，'主机:端口'
 item-mp-addr-invalid = 无效的服务器地址
+item-translate = 翻译简介
+item-translate-sub = 将谱面简介翻译为界面语言，点击可查看原文
+item-translate-api = 翻译服务器
+item-translate-api-sub = 兼容 LibreTranslate 的服务地址
 item-lowq = 低画质模式
 item-lowq-sub = 建议在画面卡顿时启用
+item-linear-color = 线性色彩
+item-linear-color-sub = 以与编辑器一致的方式混合特效与着色，避免暗色出现色带。需要 OpenGL (ES) 3
+item-max-fps = 帧率上限
+item-max-fps-sub = 限制画面刷新频率以节省电量
+item-max-fps-unlimited = 不限
+item-vsync = 垂直同步
+item-vsync-sub = 按显示器刷新率输出画面，重启后生效
+item-perf-overlay = 性能监视
+item-perf-overlay-sub = 在角落显示帧时间与音符批处理统计
+item-memory-overlay = 内存监视
+item-memory-overlay-sub = 显示纹理、音频与谱面占用的内存，以及关闭页面后残留的部分
+item-instanced-notes = 实例化音符渲染
+item-instanced-notes-sub = 设备支持时使用 GPU 实例化绘制音符，若音符显示异常请关闭
+item-live-stats = 直播叠加层
+item-live-stats-sub = 在 http://127.0.0.1:{ $port } 提供实时数据与 OBS 浏览器源
+item-live-stats-failed = 无法启动叠加层服务
+item-record-badges = 谱面卡片显示最佳成绩
+item-record-badges-sub = 关闭后截图时谱面列表更整洁
 item-insecure = 不安全模式
 item-insecure-sub = 当无法使用在线功能时可尝试该功能。这会使得你的连接不安全！
+item-cloud = 云同步
+item-cloud-sub = 将设置、谱面延迟与最佳成绩保存到账号中
+item-cloud-sync = 同步
+item-cloud-sync-last = 上次同步于 { $time }
+item-cloud-sync-never = 从未同步
+item-cloud-sync-now = 立即同步
+item-cloud-syncing = 同步中…
+item-storage = 存储空间
+item-storage-sub = 查看并清理谱面、资源包与缓存占用的空间
+item-storage-manage = 管理
+item-export = 导出成绩
+item-export-sub = 将所有本地成绩导出为表格或 JSON 文件
+item-export-failed = 导出成绩失败
 
 item-adjust = 自动对齐时间
 item-adjust-sub = 自动调整延迟以同步音乐和谱面
@@ -27,21 +62,76 @@ item-auto-latency = 自动调整延时
 item-auto-latency-sub = 实时估算设备延时并调整
 item-music = 音乐音量
 item-sfx = 音效音量
+item-sfx-limiter = 打击音限幅
+item-sfx-limiter-sub = 密集多押时压缩打击音，避免爆音失真
+item-sfx-voices = 打击音复音数
+item-sfx-voices-sub = 同时播放的打击音上限，超出时跳过新的打击音直到有声部结束
+item-premix = 预混音键音
+item-premix-sub = 加载时将自动播放与假音符的打击音混入音乐，减少键音密集谱面的卡顿
 item-bgm = BGM 音量
+item-list-preview = 列表歌曲预览
+item-list-preview-sub = 单击谱面试听预览，再次单击进入
 item-cali = 调整延迟
 item-exclusive-audio = 独占音频
 item-exclusive-audio-sub = 尝试独占输出设备，可以降低音频延时，但会导致声音无法被录制
 item-audio-compatibility = 音频兼容模式
 
 item-show-acc = 显示实时准度
+item-acc-hud-counts = 显示判定计数
+item-acc-hud-counts-sub = 实时显示 Perfect/Good/Bad/Miss 数量与早晚情况
+item-acc-hud-position = 准度显示位置
+item-hud-layout = 界面布局
+item-hud-layout-sub = 拖动调整暂停按钮、分数、连击与进度条的位置
+item-hud-layout-edit = 编辑
+item-progress-bar = 进度条样式
+progress-bar-top = 顶部
+progress-bar-circle = 环绕暂停键
+progress-bar-sections = 分段
+note-palette-off = 资源包
+note-palette-high-contrast = 高对比度
+note-palette-red-green = 红绿色盲
+note-palette-blue-yellow = 蓝黄色盲
+hud-position-score = 分数下方
+hud-position-top-left = 左上角
+hud-position-bottom-left = 左下角
+hud-position-bottom-right = 右下角
 item-dc-pause = 双击暂停
+item-duck-on-miss = 连续失误时压低音乐
+item-duck-on-miss-sub = 连续多次失误后短暂降低音乐音量并突出打击音，帮助找回节奏
 item-dhint = 双押提示
 item-dhint-sub = 同时触线的音符将会被高亮
+item-curved-holds = 弯曲长条
+item-curved-holds-sub = 判定线在长条下落时旋转，长条会随之弯曲
 item-opt = 激进优化
 item-opt-sub = 采用激进的优化策略，提升性能但可能导致部分谱面显示出错
 item-speed = 速度
 item-note-size = 音符大小
+item-note-palette = 音符配色
+item-note-palette-sub = 按类型以色盲友好的配色重新着色音符
+item-note-markers = 音符形状标记
+item-note-markers-sub = 用圆点标记 Drag，用箭头标记 Flick
+item-hit-area = 判定区宽度
+item-hit-area-sub = 扩大距离音符多远的点击仍然有效
+item-full-screen-notes = 全屏判定的音符
+item-full-screen-notes-sub = 这些音符在判定线上的任何位置都可击打
+note-tap = Tap
+note-drag = Drag
+note-flick = Flick
+note-hold = Hold
 item-render-extra = 显示额外内容 (着色器/特效)
+item-judge-preset = 判定区间
+item-judge-preset-sub = 仅在标准判定下保存成绩
+judge-preset-standard = 标准
+judge-preset-strict = 严格
+judge-preset-lenient = 宽松
+judge-preset-custom = 自定义
+item-judge-perfect = 完美判定区间
+item-judge-good = 良好判定区间
+item-judge-bad = 失败判定区间
+item-hold-leniency = Hold 松手宽容
+item-hold-leniency-sub = 在 Hold 结束前这么早松手仍然有效，不保存成绩
+item-hold-no-penalty = 松手不判 Miss
+item-hold-no-penalty-sub = Hold 开始后松手不会 Miss，不保存成绩
 
 item-chart-debug-line = 谱面调试 - 判定线
 item-chart-debug-line-sub = 显示判定线属性
@@ -49,6 +139,10 @@ item-chart-debug-note = 谱面调试 - 音符
 item-chart-debug-note-sub = 显示音符属性
 item-touch-debug = 触摸调试
 item-touch-debug-sub = 游玩过程中显示触摸点
+item-keyboard-lanes = 键盘分轨
+item-keyboard-lanes-sub = 按键对应屏幕上的竖直区域，而不是判定最近的音符
+item-key-lanes = 轨道按键
+item-key-lanes-sub = 每个按键对应一条轨道，从左到右排列
 item-chart_ratio = 谱面缩放倍率
 item-fade = 音符淡入淡出
 item-fade-sub = 小于 0 时下隐 大于 0 时上隐
@@ -61,6 +155,8 @@ item-rotation-mode = 旋转模式
 item-rotation-flat-mode = 旋转模式 - 平放
 item-rotation-flat-mode-sub = 此模式会根据开始游戏时的方向来矫正方向。如果方向错误，可在暂停游戏页重置方位
 item-shake-play-mode = 摇一摇模式
+item-hot-reload = 谱面热重载
+item-hot-reload-sub = 游玩时在未打包谱面的文件变化后重新加载
 
 load-cali-failed = 加载音频失败
 not-combo = 不能是 COMBO
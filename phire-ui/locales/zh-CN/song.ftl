@@ -1,11 +1,15 @@
 
 load-preview-failed = 加载歌曲预览失败
+chart-preview = 预览
+chart-preview-stop = 停止
+chart-preview-failed = 加载预览失败
 load-charts-failed = 加载难度失败
 no-chart-for-download = 该谱面不提供下载
 
 load-chart-failed = 加载谱面失败
 
 dl-cancel = 取消
+dl-background = 后台下载
 dl-status-fetch = 加载信息
 dl-status-song = 下载歌曲
 dl-status-chart = 下载谱面
@@ -26,8 +30,24 @@ switched-to-offline = 已切换到离线模式
 
 delete = 删除
 rate = 评分
+comments = 评论
 exercise = 练习
 offset = 调整延迟
+chart-editor = 编辑谱面
+chart-editor-not-unpacked = 仅支持编辑已解压的谱面
+convert-rpe = 导出为 RPE
+convert-pec = 导出为 PEC
+converting = 转换中
+convert-done = 已保存为 { $file }
+convert-failed = 谱面转换失败
+convert-not-unpacked = 仅支持转换已解压的谱面
+
+play-online = 在线游玩
+play-online-loading = 正在加载谱面
+play-online-failed = 在线加载谱面失败
+play-online-keep = 要将该谱面保存到本地吗？
+play-online-discard = 不保存
+play-online-save = 保存
 
 edit-cancel = 取消
 edit-save = 保存
@@ -58,17 +78,39 @@ uploading = 上传中…
 upload-chart-failed = 上传谱面失败
 upload-success = 上传成功，请等待审核！
 upload-failed = 上传失败
+upload-queued = 成绩已保存在本机，将在能连上服务器时自动上传
+upload-queued-done = 已上传 { $count } 条离线时保存的成绩
 
 ldb = 排行榜
 ldb-load-failed = 加载排行榜失败
 ldb-no-rank = 无
 ldb-score = 分数
 ldb-std = 无瑕度
+ldb-global = 全球
+ldb-friends = 好友
+
+history = 我的成绩
+history-empty = 暂无游玩记录
+history-score = 分数
+history-acc = 准确率
+history-best = 个人最佳
+history-detail = { $combo }x  { $perfect } / { $good } / { $bad } / { $miss }
+history-mods = 模组
+history-chart-updated = 谱面已更新
+previous-version = 旧版本
+best-shot = 最佳成绩
+best-shot-load-failed = 截图加载失败
 
 info-name = 名字
 info-composer = 曲师
 info-charter = 谱师
 info-difficulty = 难度
+info-skills = 能力构成
+info-skills-loading = 正在分析谱面…
+skill-speed = 速度
+skill-stamina = 耐力
+skill-flick = Flick
+skill-rhythm = 节奏
 info-desc = 简介
 info-rating = 评分
 info-type = 种类
@@ -99,9 +141,20 @@ mods-flip-x-sub = 在 X 轴上反转谱面
 mods-fade-out = 下隐
 mods-fade-out-sub = 音符在靠近判定线时会隐藏
 mods-full-screen-judge = 全屏判定
+mods-sudden-death = 一命通关
+mods-sudden-death-sub = 出现第一个非 Perfect 判定时立即结束
+mods-no-fail = 不会失败
+mods-no-fail-sub = 仅用于取消一命通关，不开启一命通关时本身就会打到结尾
+mods-flip-y = 上下翻转
+mods-flip-y-sub = 将谱面上下镜像
+mods-random = 随机
+mods-random-sub = 打乱每条判定线上音符的位置
+mods-note-swap = 音符互换
+mods-note-swap-sub = Tap 变为 Drag，Drag 变为 Tap
 
 rate-failed = 评分失败
 rate-done = 评分成功
+rate-removed = 已取消评分
 
 need-update = 谱面更新
 need-update-info-only-content = 谱面信息已更新，需要现在同步这些信息吗？
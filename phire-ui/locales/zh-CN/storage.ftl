@@ -0,0 +1,19 @@
+
+downloaded = 已下载谱面
+custom = 导入的谱面
+respack = 资源包
+image-cache = 图片缓存
+logs = 日志
+
+total = 总计：{ $size }
+calculating = 计算中...
+sort-by-size = 按大小
+clean = 清理
+clean-all = 全部清理
+
+clean-confirm = 确认清理
+clean-confirm-content = “{ $name }”中的所有文件将被删除，此操作无法撤销。
+clean-all-confirm-content = 所有谱面、资源包、缓存与日志将被删除，此操作无法撤销。
+cleaning = 清理中...
+clean-failed = 清理失败
+cleaned = 已清理
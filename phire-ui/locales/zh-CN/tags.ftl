@@ -3,6 +3,7 @@ filter = 按标签过滤
 edit = 编辑标签
 
 invalid-tag = 无效的标签
+invalid-range = 范围格式应为 10-15
 
 wanted = 想包含的标签
 unwanted = 不想包含的标签
@@ -20,3 +21,23 @@ visual = 观赏
 filter-me = 我上传的
 filter-unreviewed = 待审核
 filter-stabilize = 待 stable 审核
+filter-level = 定数
+filter-notes = 物量
+filter-any = 不限
+
+tag-regular = 常规
+tag-troll = 整活
+tag-plain = 纯配置
+tag-visual = 观赏
+tag-original = 原创
+tag-remix = 混音
+tag-anime = 动画
+tag-game = 游戏
+tag-vocaloid = 虚拟歌手
+tag-touhou = 东方
+
+level-ez = 简单
+level-hd = 困难
+level-in = 极难
+level-at = 另一面
+level-sp = 特殊
@@ -0,0 +1,3 @@
+translating = 正在翻译…
+show-original = 已翻译，点击查看原文
+show-translated = 原文，点击查看译文
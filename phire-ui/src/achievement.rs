@@ -0,0 +1,102 @@
+phire::tl_file!("achievement");
+
+use crate::{data::Data, get_data, get_data_mut, save_data};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use phire::scene::{show_message, SimpleRecord};
+use std::{borrow::Cow, collections::HashMap};
+
+// at least this frame rate counts as 120 FPS, displays a bit off 120 Hz shouldn't miss out
+const HIGH_FPS: f32 = 115.;
+const HARD_DIFFICULTY: f32 = 15.;
+
+pub struct Play<'a> {
+    pub record: &'a SimpleRecord,
+    pub difficulty: f32,
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    // shown as locked with no description until unlocked
+    pub hidden: bool,
+    // checked after every play with that play, and otherwise with `None`
+    check: fn(&Data, Option<&Play>) -> bool,
+}
+
+impl Achievement {
+    pub fn name(&self) -> Cow<'static, str> {
+        tl!(self.name)
+    }
+
+    pub fn description(&self) -> Cow<'static, str> {
+        tl!(self.description)
+    }
+
+    pub fn unlocked(&self, data: &Data) -> Option<DateTime<Utc>> {
+        data.achievements.get(self.id).copied()
+    }
+}
+
+macro_rules! achievement {
+    ($id:literal, $hidden:expr, $check:expr) => {
+        Achievement {
+            id: $id,
+            name: concat!($id, "-name"),
+            description: concat!($id, "-desc"),
+            hidden: $hidden,
+            check: $check,
+        }
+    };
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    achievement!("first-play", false, |data, _| data.play_count >= 1),
+    achievement!("plays-100", false, |data, _| data.play_count >= 100),
+    achievement!("plays-1000", false, |data, _| data.play_count >= 1000),
+    achievement!("first-fc", false, |_, play| play.map_or(false, |it| it.record.full_combo)),
+    achievement!("first-ap", false, |_, play| play.map_or(false, |it| it.record.accuracy >= 1.)),
+    achievement!("combo-1000", false, |_, play| play.map_or(false, |it| it.record.max_combo >= 1000)),
+    achievement!("hard-fc", false, |_, play| play.map_or(false, |it| it.record.full_combo && it.difficulty >= HARD_DIFFICULTY)),
+    achievement!("fc-120fps", true, |_, play| play.map_or(false, |it| it.record.full_combo && it.record.fps.map_or(false, |fps| fps >= HIGH_FPS))),
+    achievement!("library-50", false, |data, _| data.charts.len() >= 50),
+];
+
+// unlocks whatever the data (and the play, if any) qualifies for, with a toast for each
+fn evaluate(play: Option<&Play>) -> Result<()> {
+    let data = get_data();
+    let unlocked: Vec<_> = ACHIEVEMENTS
+        .iter()
+        .filter(|it| !data.achievements.contains_key(it.id) && (it.check)(data, play))
+        .collect();
+    if unlocked.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let data = get_data_mut();
+    for achievement in unlocked {
+        data.achievements.insert(achievement.id.to_owned(), now);
+        show_message(tl!("unlocked", "name" => achievement.name().into_owned())).ok();
+    }
+    save_data()
+}
+
+// called with every finished play that made it into the records
+pub fn on_play(play: Play) -> Result<()> {
+    get_data_mut().play_count += 1;
+    save_data()?;
+    evaluate(Some(&play))
+}
+
+// for achievements that only depend on the data, e.g. after charts are imported or downloaded
+pub fn check() -> Result<()> {
+    evaluate(None)
+}
+
+// both sides keep whatever they unlocked, with the earliest time
+pub fn merge(into: &mut HashMap<String, DateTime<Utc>>, other: &HashMap<String, DateTime<Utc>>) {
+    for (id, time) in other {
+        into.entry(id.clone()).and_modify(|it| *it = (*it).min(*time)).or_insert(*time);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{
+    client::{Chart, Ptr},
+    get_data,
+    scene::fs_from_path,
+};
+use anyhow::Result;
+use phire::{
+    core::MusicStream,
+    fs,
+    task::Task,
+    ui::{MemoryGuard, UI_AUDIO},
+};
+use sasa::{AudioClip, Frame, Music, MusicParams};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+const CROSSFADE_TIME: f32 = 0.6;
+// how loud a preview is relative to the music volume
+const PREVIEW_VOLUME: f32 = 0.7;
+// a card has to stay selected this long before its preview is loaded
+const LOAD_DELAY: f32 = 0.25;
+
+// set while a preview is audible, so that the menu background music can make room for it
+pub static PREVIEW_PLAYING: AtomicBool = AtomicBool::new(false);
+
+pub fn with_effects((mut frames, sample_rate): (Vec<Frame>, u32), range: Option<(f32, f32)>) -> Result<AudioClip> {
+    if let Some((begin, end)) = range {
+        frames.drain(((end * sample_rate as f32) as usize).min(frames.len())..);
+        frames.drain(..((begin * sample_rate as f32) as usize));
+    }
+    let len = (0.8 * sample_rate as f64) as usize;
+    let len = len.min(frames.len() / 2);
+    for (i, frame) in frames[..len].iter_mut().enumerate() {
+        let s = i as f32 / len as f32;
+        frame.0 *= s;
+        frame.1 *= s;
+    }
+    let st = frames.len() - len;
+    for (i, frame) in frames[st..].iter_mut().rev().enumerate() {
+        let s = i as f32 / len as f32;
+        frame.0 *= s;
+        frame.1 *= s;
+    }
+    Ok(AudioClip::from_raw(frames, sample_rate))
+}
+
+#[derive(Clone, PartialEq)]
+pub enum PreviewSource {
+    Local(String),
+    Remote(i32),
+}
+
+impl PreviewSource {
+    async fn load(self) -> Result<AudioClip> {
+        match self {
+            Self::Local(path) => {
+                let mut fs = fs_from_path(&path)?;
+                let info = fs::load_info(fs.as_mut()).await?;
+                let end = info.preview_end.unwrap_or(info.preview_start + 15.);
+                with_effects(MusicStream::decode_range(fs.load_file(&info.music).await?, info.preview_start, end)?, None)
+            }
+            Self::Remote(id) => {
+                let chart = Ptr::<Chart>::new(id).fetch().await?;
+                with_effects(AudioClip::decode(chart.preview.fetch().await?.to_vec())?, None)
+            }
+        }
+    }
+}
+
+struct Track {
+    music: Music,
+    volume: f32,
+    fading_out: bool,
+    _memory: MemoryGuard,
+}
+
+impl Track {
+    // returns false once the track has fully faded out
+    fn step(&mut self, dt: f32, max: f32) -> Result<bool> {
+        let delta = if self.fading_out { -dt } else { dt } / CROSSFADE_TIME;
+        self.volume = (self.volume + delta).clamp(0., 1.);
+        if self.fading_out && self.volume <= 0. {
+            return Ok(false);
+        }
+        self.music.set_amplifier(self.volume * max)?;
+        Ok(true)
+    }
+}
+
+// plays looped song previews and crossfades between them as the selection changes
+#[derive(Default)]
+pub struct PreviewPlayer {
+    source: Option<PreviewSource>,
+    selected_at: f32,
+    task: Option<Task<Result<AudioClip>>>,
+    tracks: Vec<Track>,
+    paused: bool,
+    last_t: Option<f32>,
+}
+
+impl PreviewPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn play(&mut self, source: PreviewSource, t: f32) {
+        if self.source.as_ref() == Some(&source) {
+            return;
+        }
+        self.fade_out_all();
+        self.source = Some(source);
+        self.selected_at = t;
+        self.task = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.fade_out_all();
+        self.source = None;
+        self.task = None;
+    }
+
+    pub fn pause(&mut self) -> Result<()> {
+        self.paused = true;
+        for track in &mut self.tracks {
+            track.music.pause()?;
+        }
+        PREVIEW_PLAYING.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn resume(&mut self) -> Result<()> {
+        self.paused = false;
+        self.last_t = None;
+        for track in &mut self.tracks {
+            track.music.play()?;
+        }
+        Ok(())
+    }
+
+    fn fade_out_all(&mut self) {
+        for track in &mut self.tracks {
+            track.fading_out = true;
+        }
+    }
+
+    pub fn update(&mut self, t: f32) -> Result<()> {
+        let dt = self.last_t.map_or(0., |it| (t - it).max(0.));
+        self.last_t = Some(t);
+        if self.paused {
+            return Ok(());
+        }
+        if self.task.is_none() && self.tracks.iter().all(|it| it.fading_out) && t > self.selected_at + LOAD_DELAY {
+            if let Some(source) = &self.source {
+                self.task = Some(Task::new(source.clone().load()));
+            }
+        }
+        if let Some(task) = &mut self.task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => {
+                        // previews are a nicety, a failed one just stays silent
+                        warn!("failed to load preview: {err:?}");
+                    }
+                    Ok(clip) => {
+                        let memory = MemoryGuard::audio(&clip);
+                        let mut music = UI_AUDIO.with(|it| {
+                            it.borrow_mut().create_music(
+                                clip,
+                                MusicParams {
+                                    amplifier: 0.,
+                                    loop_mix_time: 0.,
+                                    ..Default::default()
+                                },
+                            )
+                        })?;
+                        music.play()?;
+                        self.tracks.push(Track {
+                            music,
+                            volume: 0.,
+                            fading_out: false,
+                            _memory: memory,
+                        });
+                    }
+                }
+                // keep the finished task around so the same selection isn't loaded again
+            }
+        }
+        let max = get_data().config.volume_music * PREVIEW_VOLUME;
+        let mut kept = Vec::with_capacity(self.tracks.len());
+        for mut track in self.tracks.drain(..) {
+            if track.step(dt, max)? {
+                kept.push(track);
+            }
+        }
+        self.tracks = kept;
+        PREVIEW_PLAYING.store(self.tracks.iter().any(|it| !it.fading_out), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for PreviewPlayer {
+    fn drop(&mut self) {
+        if !self.tracks.is_empty() {
+            PREVIEW_PLAYING.store(false, Ordering::Relaxed);
+        }
+    }
+}
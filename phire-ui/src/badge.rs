@@ -0,0 +1,20 @@
+phire::tl_file!("profile");
+
+use macroquad::prelude::Color;
+use std::borrow::Cow;
+
+// badges come from the server by id; the ones the app knows are shown in the player's language
+pub fn badge_label(badge: &str) -> Cow<'_, str> {
+    match L10N_LOCAL.with(|it| it.borrow().try_format(&format!("badge-{badge}"))) {
+        Some(label) => label.into(),
+        None => badge.into(),
+    }
+}
+
+pub fn badge_color(badge: &str) -> Color {
+    Color::from_hex(match badge {
+        "admin" => 0xff673ab7,
+        "sponsor" => 0xffff7043,
+        _ => 0xff4fc3f7,
+    })
+}
@@ -0,0 +1,155 @@
+use crate::{
+    client::{recv_with, Client, Priority},
+    dir, get_data, get_data_mut, save_data,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use macroquad::prelude::*;
+use phire::{
+    ext::RectExt,
+    task::Task,
+    ui::{RectButton, Ui},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+// in real time seconds
+const POLL_INTERVAL: f32 = 300.;
+const BANNER_HEIGHT: f32 = 0.07;
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BannerKind {
+    #[default]
+    Info,
+    Maintenance,
+    Emergency,
+}
+
+impl BannerKind {
+    fn color(self) -> Color {
+        match self {
+            Self::Info => Color::new(0.08, 0.4, 0.75, 0.9),
+            Self::Maintenance => Color::new(0.94, 0.42, 0., 0.9),
+            Self::Emergency => Color::new(0.78, 0.16, 0.16, 0.9),
+        }
+    }
+}
+
+fn default_dismissible() -> bool {
+    true
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Banner {
+    pub id: String,
+    #[serde(default)]
+    pub kind: BannerKind,
+    pub content: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default = "default_dismissible")]
+    pub dismissible: bool,
+}
+
+fn cache_path() -> Result<String> {
+    Ok(format!("{}/banners.json", dir::cache()?))
+}
+
+fn current(banners: &[Banner]) -> Option<&Banner> {
+    let now = Utc::now();
+    let dismissed = &get_data().dismissed_banners;
+    banners
+        .iter()
+        .find(|it| it.expires_at.map_or(true, |time| time > now) && !dismissed.contains(&it.id))
+}
+
+// notices polled from the server and shown on top of the menus, the last response is kept for offline use
+pub struct Banners {
+    banners: Vec<Banner>,
+    task: Option<Task<Result<Vec<Banner>>>>,
+    last_poll: f32,
+    btn_close: RectButton,
+}
+
+impl Banners {
+    pub fn new() -> Self {
+        let banners = cache_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|it| serde_json::from_str(&it).ok())
+            .unwrap_or_default();
+        Self {
+            banners,
+            task: None,
+            last_poll: f32::NEG_INFINITY,
+            btn_close: RectButton::new(),
+        }
+    }
+
+    pub fn update(&mut self, rt: f32) {
+        if self.task.is_none() && !get_data().config.offline_mode && rt - self.last_poll > POLL_INTERVAL {
+            self.last_poll = rt;
+            self.task = Some(Task::new(async { Ok(recv_with(Client::get("/status/banners"), Priority::Prefetch).await?.json().await?) }));
+        }
+        if let Some(task) = &mut self.task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => {
+                        warn!("failed to fetch banners: {err:?}");
+                    }
+                    Ok(banners) => {
+                        if let Err(err) = cache_path().and_then(|path| Ok(std::fs::write(path, serde_json::to_string(&banners)?)?)) {
+                            warn!("failed to cache banners: {err:?}");
+                        }
+                        // forget dismissals of banners the server no longer sends
+                        let dismissed = &mut get_data_mut().dismissed_banners;
+                        let count = dismissed.len();
+                        dismissed.retain(|id| banners.iter().any(|it| &it.id == id));
+                        if dismissed.len() != count {
+                            let _ = save_data();
+                        }
+                        self.banners = banners;
+                    }
+                }
+                self.task = None;
+            }
+        }
+    }
+
+    pub fn touch(&mut self, touch: &Touch) -> bool {
+        let Some(banner) = current(&self.banners) else {
+            return false;
+        };
+        if banner.dismissible && self.btn_close.touch(touch) {
+            get_data_mut().dismissed_banners.insert(banner.id.clone());
+            if let Err(err) = save_data() {
+                warn!("failed to save dismissed banner: {err:?}");
+            }
+            return true;
+        }
+        false
+    }
+
+    pub fn render(&mut self, ui: &mut Ui) {
+        let Some(banner) = current(&self.banners) else {
+            return;
+        };
+        let r = Rect::new(-0.7, -ui.top + 0.02, 1.4, BANNER_HEIGHT);
+        ui.fill_path(&r.rounded(0.01), banner.kind.color());
+        let close_w = if banner.dismissible { 0.06 } else { 0. };
+        ui.text(&banner.content)
+            .pos(r.x + 0.02, r.center().y)
+            .anchor(0., 0.5)
+            .no_baseline()
+            .size(0.4)
+            .max_width(r.w - 0.04 - close_w)
+            .color(WHITE)
+            .draw();
+        if banner.dismissible {
+            let cr = Rect::new(r.right() - close_w / 2. - 0.01, r.center().y, 0., 0.).feather(BANNER_HEIGHT / 2.);
+            ui.text("×").pos(cr.center().x, cr.center().y).anchor(0.5, 0.5).no_baseline().size(0.6).color(WHITE).draw();
+            self.btn_close.set(ui, cr);
+        }
+    }
+}
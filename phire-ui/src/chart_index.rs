@@ -0,0 +1,140 @@
+// `index.json` in the chart folder: what was read from the info of every local chart, so that starting up and
+// searching the local tab don't open each folder again. An entry holds as long as neither the folder nor its
+// `info.yml` changed since, the places that write charts update it right away.
+
+use crate::{data::BriefChartInfo, dir};
+use anyhow::Result;
+use phire::info::ChartInfo;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, ops::DerefMut, path::Path, sync::Mutex, time::UNIX_EPOCH};
+use tracing::warn;
+
+static INDEX: Mutex<Option<HashMap<String, IndexEntry>>> = Mutex::new(None);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    pub info: BriefChartInfo,
+    pub illustration: String,
+    pub tags: Vec<String>,
+    stamp: u64,
+}
+
+impl IndexEntry {
+    fn new(info: &ChartInfo, stamp: u64) -> Self {
+        Self {
+            info: info.clone().into(),
+            illustration: info.illustration.clone(),
+            tags: info.tags.clone(),
+            stamp,
+        }
+    }
+}
+
+fn index_path() -> Result<String> {
+    Ok(format!("{}/index.json", dir::charts()?))
+}
+
+// last modification of the folder or its info, in seconds
+fn stamp(path: &Path) -> u64 {
+    [path.to_path_buf(), path.join("info.yml")]
+        .iter()
+        .filter_map(|it| it.metadata().ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok())
+        .map(|it| it.as_secs())
+        .max()
+        .unwrap_or_default()
+}
+
+fn with_index<R>(f: impl FnOnce(&mut HashMap<String, IndexEntry>) -> R) -> R {
+    let mut guard = INDEX.lock().unwrap();
+    let index = guard.get_or_insert_with(|| {
+        index_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    });
+    f(index)
+}
+
+fn save(index: &HashMap<String, IndexEntry>) {
+    let res = (|| -> Result<()> {
+        std::fs::write(index_path()?, serde_json::to_string(index)?)?;
+        Ok(())
+    })();
+    if let Err(err) = res {
+        warn!("failed to save chart index: {err:?}");
+    }
+}
+
+pub fn get(local_path: &str) -> Option<IndexEntry> {
+    with_index(|index| index.get(local_path).cloned())
+}
+
+// after a chart was imported, downloaded or had its info written
+pub fn update(local_path: &str, info: &ChartInfo) {
+    let stamp = match dir::charts() {
+        Ok(charts) => stamp(Path::new(&format!("{charts}/{local_path}"))),
+        Err(err) => {
+            warn!("failed to index {local_path}: {err:?}");
+            return;
+        }
+    };
+    with_index(|index| {
+        index.insert(local_path.to_owned(), IndexEntry::new(info, stamp));
+        save(index);
+    });
+}
+
+// `local_path` may also be a whole folder like `custom`, for when everything in it was deleted
+pub fn remove(local_path: &str) {
+    let folder = format!("{local_path}/");
+    with_index(|index| {
+        let len = index.len();
+        index.retain(|path, _| path != local_path && !path.starts_with(&folder));
+        if index.len() != len {
+            save(index);
+        }
+    });
+}
+
+// every chart folder, with only the ones that changed read again. Folders under `download/` that aren't named
+// after a chart id are downloads still being extracted
+pub async fn scan() -> Result<Vec<(String, IndexEntry)>> {
+    let mut known = with_index(std::mem::take);
+    let mut res = Vec::new();
+    let mut changed = false;
+    for (folder, dir) in [("custom", dir::custom_charts()?), ("download", dir::downloaded_charts()?)] {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if folder == "download" && name.parse::<i32>().is_err() {
+                continue;
+            }
+            let local_path = format!("{folder}/{name}");
+            let path = entry.path();
+            let stamp = stamp(&path);
+            match known.remove(&local_path) {
+                Some(entry) if entry.stamp == stamp => res.push((local_path, entry)),
+                _ => {
+                    changed = true;
+                    let Ok(mut fs) = phire::fs::fs_from_file(&path) else {
+                        continue;
+                    };
+                    let Ok(info) = phire::fs::load_info(fs.deref_mut()).await else {
+                        continue;
+                    };
+                    res.push((local_path, IndexEntry::new(&info, stamp)));
+                }
+            }
+        }
+    }
+    changed |= !known.is_empty();
+    with_index(|index| {
+        *index = res.iter().cloned().collect();
+        if changed {
+            save(index);
+        }
+    });
+    Ok(res)
+}
@@ -1,21 +1,25 @@
 use crate::{
+    audio_preview::{PreviewPlayer, PreviewSource},
+    chart_index,
     client::Chart,
     dir, get_data, get_data_mut,
     icons::Icons,
     page::{ChartItem, Fader, Illustration},
     save_data,
     scene::{SongScene, MP_PANEL},
+    tags::level_label,
 };
 use anyhow::Result;
 use macroquad::prelude::*;
 use phire::{
     core::Tweenable,
-    ext::{semi_black, semi_white, RectExt, SafeTexture, BLACK_TEXTURE},
+    ext::{semi_black, semi_white, RectExt, SafeTexture, ScaleType},
+    judge::icon_index,
     scene::{show_message, NextScene},
-    task::Task,
-    ui::{button_hit_large, DRectButton, Scroll, Ui},
+    ui::{button_hit_large, theme, DRectButton, RetryPanel, Scroll, Ui},
 };
 use std::{
+    collections::BTreeSet,
     ops::Range,
     path::Path,
     sync::{
@@ -23,7 +27,6 @@ use std::{
         Arc,
     },
 };
-use tokio::sync::Notify;
 
 pub static NEED_UPDATE: AtomicBool = AtomicBool::new(false);
 
@@ -35,6 +38,7 @@ pub struct ChartDisplayItem {
     chart: ChartItem,
     symbol: Option<char>,
     btn: DRectButton,
+    retry_btn: DRectButton,
 }
 
 impl ChartDisplayItem {
@@ -43,6 +47,7 @@ impl ChartDisplayItem {
             chart,
             symbol,
             btn: DRectButton::new(),
+            retry_btn: DRectButton::new(),
         }
     }
 
@@ -51,20 +56,11 @@ impl ChartDisplayItem {
             ChartItem {
                 info: chart.to_info(),
                 illu: {
-                    let notify = Arc::new(Notify::new());
-                    Illustration {
-                        texture: (BLACK_TEXTURE.clone(), BLACK_TEXTURE.clone()),
-                        notify: Arc::clone(&notify),
-                        task: Some(Task::new({
-                            let illu = chart.illustration.clone();
-                            async move {
-                                notify.notified().await;
-                                Ok((illu.load_thumbnail().await?, None))
-                            }
-                        })),
-                        loaded: Arc::default(),
-                        load_time: f32::NAN,
-                    }
+                    let illu = chart.illustration.clone();
+                    Illustration::remote(move || {
+                        let illu = illu.clone();
+                        async move { illu.load_thumbnail().await }
+                    })
                 },
                 local_path: None,
             },
@@ -79,6 +75,19 @@ impl ChartDisplayItem {
     }
 }
 
+// score, accuracy and full combo of the best local play, or of the synced one for charts not on this device
+fn best_record(chart: &ChartItem) -> Option<(u32, f32, bool)> {
+    let data = get_data();
+    let local = match &chart.local_path {
+        Some(path) => data.charts.iter().find(|it| &it.local_path == path),
+        None => chart.info.id.and_then(|id| data.charts.iter().find(|it| it.info.id == Some(id))),
+    };
+    local
+        .and_then(|it| it.record.as_ref())
+        .or_else(|| data.cloud_records.get(&chart.info.id?))
+        .map(|it| (it.score, it.accuracy, it.full_combo))
+}
+
 struct TransitState {
     id: u32,
     rect: Option<Rect>,
@@ -101,6 +110,19 @@ pub struct ChartsView {
 
     transit: Option<TransitState>,
     charts: Option<Vec<ChartDisplayItem>>,
+    // shown in place of the list when it could not be loaded
+    retry: RetryPanel,
+
+    selected: Option<u32>,
+    preview: PreviewPlayer,
+    // charts ticked for export, `None` outside of picking mode
+    picked: Option<BTreeSet<u32>>,
+    // in reordering mode charts are dragged around instead of opened, `dragging` is the touch and where its chart is now
+    reordering: bool,
+    dragging: Option<(u64, usize)>,
+    reordered: bool,
+    // global rect of the first cell as last rendered, to tell which cell a drag is over
+    first_cell: Rect,
 
     pub row_num: u32,
     pub row_height: f32,
@@ -121,6 +143,15 @@ impl ChartsView {
 
             transit: None,
             charts: None,
+            retry: RetryPanel::new(),
+
+            selected: None,
+            preview: PreviewPlayer::new(),
+            picked: None,
+            reordering: false,
+            dragging: None,
+            reordered: false,
+            first_cell: Rect::default(),
 
             row_num: 4,
             row_height: 0.3,
@@ -138,13 +169,133 @@ impl ChartsView {
 
     pub fn clear(&mut self) {
         self.charts = None;
+        self.retry.clear();
+        self.picked = None;
+        self.deselect();
     }
 
     pub fn set(&mut self, t: f32, charts: Vec<ChartDisplayItem>) {
         self.charts = Some(charts);
+        self.retry.clear();
+        self.picked = None;
+        self.dragging = None;
+        self.deselect();
         self.fader.sub(t);
     }
 
+    pub fn fail(&mut self, error: anyhow::Error) {
+        self.charts = None;
+        self.retry.fail(error);
+    }
+
+    // whether the player asked to load the list again after it failed
+    pub fn take_retry(&mut self) -> bool {
+        self.retry.take_retry()
+    }
+
+    pub fn start_picking(&mut self) {
+        self.deselect();
+        self.picked = Some(BTreeSet::new());
+    }
+
+    pub fn stop_picking(&mut self) {
+        self.picked = None;
+    }
+
+    pub fn picking(&self) -> bool {
+        self.picked.is_some()
+    }
+
+    pub fn picked_count(&self) -> usize {
+        self.picked.as_ref().map_or(0, |it| it.len())
+    }
+
+    // `(local_path, name)` of every picked chart that exists locally
+    pub fn picked_charts(&self) -> Vec<(String, String)> {
+        let (Some(picked), Some(charts)) = (&self.picked, &self.charts) else {
+            return Vec::new();
+        };
+        picked
+            .iter()
+            .filter_map(|id| charts.get(*id as usize))
+            .filter_map(|it| Some((it.chart.local_path.clone()?, it.chart.info.name.clone())))
+            .collect()
+    }
+
+    pub fn start_reordering(&mut self) {
+        self.deselect();
+        self.picked = None;
+        self.reordering = true;
+    }
+
+    pub fn stop_reordering(&mut self) {
+        self.reordering = false;
+        self.dragging = None;
+    }
+
+    pub fn reordering(&self) -> bool {
+        self.reordering
+    }
+
+    // local paths in the order they were dragged into, once something moved
+    pub fn take_reordered(&mut self) -> Option<Vec<String>> {
+        if !std::mem::take(&mut self.reordered) {
+            return None;
+        }
+        Some(self.charts.iter().flatten().filter_map(|it| it.chart.local_path.clone()).collect())
+    }
+
+    fn cell_at(&self, pos: Vec2, len: usize) -> Option<usize> {
+        let r = self.first_cell;
+        if len == 0 || r.w <= 0. || r.h <= 0. {
+            return None;
+        }
+        let col = ((pos.x - r.x) / r.w).floor().clamp(0., self.row_num as f32 - 1.) as usize;
+        let row = ((pos.y - r.y) / r.h).floor().max(0.) as usize;
+        Some((row * self.row_num as usize + col).min(len - 1))
+    }
+
+    fn touch_reorder(&mut self, touch: &Touch, t: f32) -> bool {
+        let len = self.charts.as_ref().map_or(0, |it| it.len());
+        match (touch.phase, self.dragging) {
+            (TouchPhase::Started, _) if self.scroll.contains(touch) => match self.cell_at(touch.position, len) {
+                Some(index) => {
+                    self.dragging = Some((touch.id, index));
+                    true
+                }
+                None => self.scroll.touch(touch, t),
+            },
+            (TouchPhase::Moved | TouchPhase::Stationary, Some((id, from))) if id == touch.id => {
+                if let Some(to) = self.cell_at(touch.position, len).filter(|it| *it != from) {
+                    let charts = self.charts.as_mut().unwrap();
+                    let item = charts.remove(from);
+                    charts.insert(to, item);
+                    self.dragging = Some((id, to));
+                    self.reordered = true;
+                }
+                true
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Some((id, _))) if id == touch.id => {
+                self.dragging = None;
+                true
+            }
+            _ => self.scroll.touch(touch, t),
+        }
+    }
+
+    fn deselect(&mut self) {
+        self.selected = None;
+        self.preview.stop();
+    }
+
+    pub fn pause(&mut self) -> Result<()> {
+        self.preview.pause()
+    }
+
+    pub fn resume(&mut self) -> Result<()> {
+        self.preview.resume()
+    }
+
     pub fn reset_scroll(&mut self) {
         self.scroll.y_scroller.offset = 0.;
     }
@@ -166,15 +317,31 @@ impl ChartsView {
     }
 
     pub fn touch(&mut self, touch: &Touch, t: f32, rt: f32) -> Result<bool> {
+        if self.retry.touch(touch, t) {
+            return Ok(true);
+        }
+        if self.reordering {
+            return Ok(self.touch_reorder(touch, t));
+        }
         if self.scroll.touch(touch, t) {
             return Ok(true);
         }
         if self.scroll.contains(touch) {
             if let Some(charts) = &mut self.charts {
                 for (id, item) in charts.iter_mut().enumerate() {
+                    if item.chart.illu.failed && item.retry_btn.touch(touch, t) {
+                        item.chart.illu.retry();
+                        return Ok(true);
+                    }
                     let chart = &item.chart;
                     if item.btn.touch(touch, t) {
                         button_hit_large();
+                        if let Some(picked) = &mut self.picked {
+                            if chart.local_path.is_some() && !picked.remove(&(id as u32)) {
+                                picked.insert(id as u32);
+                            }
+                            return Ok(true);
+                        }
                         let handled_by_mp = MP_PANEL.with(|it| {
                             if let Some(panel) = it.borrow_mut().as_mut() {
                                 if panel.in_room() {
@@ -193,6 +360,21 @@ impl ChartsView {
                         if handled_by_mp {
                             continue;
                         }
+                        if get_data().config.list_preview && self.selected != Some(id as u32) {
+                            let source = match (&chart.local_path, chart.info.id) {
+                                (Some(path), _) => Some(PreviewSource::Local(path.clone())),
+                                (None, Some(chart_id)) => Some(PreviewSource::Remote(chart_id)),
+                                _ => None,
+                            };
+                            self.selected = Some(id as u32);
+                            match source {
+                                Some(source) => self.preview.play(source, t),
+                                None => self.preview.stop(),
+                            }
+                            return Ok(true);
+                        }
+                        self.selected = None;
+                        self.preview.stop();
                         let download_path = chart.info.id.map(|it| format!("download/{it}"));
                         let scene = SongScene::new(
                             chart.clone(),
@@ -237,6 +419,7 @@ impl ChartsView {
     pub fn update(&mut self, t: f32) -> Result<bool> {
         let refreshed = self.can_refresh && self.scroll.y_scroller.pulled;
         self.scroll.update(t);
+        self.preview.update(t)?;
         if let Some(transit) = &mut self.transit {
             transit.chart.illu.settle(t);
             if t > transit.start_time + TRANSIT_TIME {
@@ -250,6 +433,7 @@ impl ChartsView {
                             format!("download/{}", item.chart.info.id.unwrap())
                         };
                         std::fs::remove_dir_all(format!("{}/{path}", dir::charts()?))?;
+                        chart_index::remove(&path);
                         data.charts.remove(data.find_chart_by_path(path.as_str()).unwrap());
                         save_data()?;
                         NEED_UPDATE.store(true, Ordering::SeqCst);
@@ -276,6 +460,10 @@ impl ChartsView {
         let content_size = (r.w, r.h);
         let range = self.charts_display_range(content_size);
         let Some(charts) = &mut self.charts else {
+            if self.retry.failed() {
+                self.retry.render(ui, r, t, alpha);
+                return;
+            }
             let ct = r.center();
             ui.loading(ct.x, ct.y, t, semi_white(alpha), ());
             return;
@@ -302,6 +490,7 @@ impl ChartsView {
                 let ch = self.row_height;
                 let p = CHART_PADDING;
                 let r = Rect::new(p, p, cw - p * 2., ch - p * 2.);
+                self.first_cell = ui.rect_to_global(Rect::new(0., 0., cw, ch));
                 self.fader.reset();
                 self.fader.for_sub(|f| {
                     ui.hgrids(content_size.0, ch, self.row_num, charts.len() as u32, |ui, id| {
@@ -333,9 +522,23 @@ impl ChartsView {
                                     }
                                 }
                             }
-                            ui.fill_path(&path, (semi_black(0.4 * c.a), (0., 0.), semi_black(0.8 * c.a), (0., ch)));
+                            ui.fill_path(&path, (theme().card(c.a), (0., 0.), semi_black(0.8 * c.a), (0., ch)));
+                            if self.selected == Some(id) || self.dragging.map_or(false, |it| it.1 == id as usize) {
+                                ui.stroke_path(&path, 0.006, semi_white(0.8 * c.a));
+                            }
+                            let picked = self.picked.as_ref().map(|it| it.contains(&id));
+                            if picked == Some(true) {
+                                ui.fill_path(&path, semi_white(0.15 * c.a));
+                                ui.stroke_path(&path, 0.008, c);
+                            }
+                            if item.chart.illu.failed {
+                                let ct = r.center();
+                                let (w, h) = (r.w * 0.5, 0.05 * r.w / cw);
+                                let br = Rect::new(ct.x - w / 2., ct.y - h / 2., w, h);
+                                item.retry_btn.render_text(ui, br, t, c.a, ttl!("illu-retry"), 0.4 * r.w / cw, false);
+                            }
                             let info = &item.chart.info;
-                            let mut level = info.level.clone();
+                            let mut level = level_label(&info.level).into_owned();
                             if !level.contains("Lv.") {
                                 use std::fmt::Write;
                                 write!(&mut level, " Lv.{}", info.difficulty as i32).unwrap();
@@ -356,13 +559,45 @@ impl ChartsView {
                                 },
                             );
                             t.draw();
+                            let mut name_width = r.w;
+                            if picked.is_none() && !get_data().hide_record_badges {
+                                if let Some((score, accuracy, full_combo)) = best_record(&item.chart) {
+                                    let s = 0.07 * r.w / cw;
+                                    let ir = Rect::new(r.right() - s - 0.012, r.bottom() - s - 0.012, s, s);
+                                    ui.fill_rect(ir, (*self.rank_icons[icon_index(score, full_combo)], ir, ScaleType::Fit, c));
+                                    let size = 0.32 * r.w / cw;
+                                    let tr = ui
+                                        .text(format!("{score:07}"))
+                                        .pos(ir.x - 0.006, ir.center().y)
+                                        .anchor(1., 1.)
+                                        .size(size)
+                                        .color(c)
+                                        .draw();
+                                    let ar = ui
+                                        .text(format!("{:.2}%", accuracy * 100.))
+                                        .pos(ir.x - 0.006, ir.center().y)
+                                        .anchor(1., 0.)
+                                        .size(size)
+                                        .color(Color { a: c.a * 0.7, ..c })
+                                        .draw();
+                                    name_width = (ir.x - tr.w.max(ar.w) - 0.02 - r.x).max(r.w / 3.);
+                                }
+                            }
                             ui.text(&info.name)
                                 .pos(r.x + 0.01, r.bottom() - 0.02)
-                                .max_width(r.w)
+                                .max_width(name_width)
                                 .anchor(0., 1.)
                                 .size(0.6 * r.w / cw)
                                 .color(c)
                                 .draw();
+                            if let Some(picked) = picked {
+                                let size = 0.12 * r.w / cw;
+                                let cr = Rect::new(r.right() - size - 0.016, r.bottom() - size - 0.016, size, size);
+                                ui.stroke_path(&cr.rounded(0.006), 0.004, c);
+                                if picked {
+                                    ui.fill_path(&cr.feather(-0.008).rounded(0.004), c);
+                                }
+                            }
                             if let Some(symbol) = item.symbol {
                                 ui.text(symbol.to_string())
                                     .pos(r.x + 0.01, r.y + 0.01)
@@ -1,15 +1,27 @@
 mod model;
 pub use model::*;
 
-use crate::{anti_addiction_action, get_data, get_data_mut, save_data};
+mod leaderboard;
+pub use leaderboard::{LdbItem, LdbScope, Leaderboard};
+
+mod scheduler;
+pub use scheduler::{schedule, take_slow_down, Priority};
+
+use crate::{anti_addiction_action, data::CloudSave, get_data, get_data_mut, save_data};
 use anyhow::{anyhow, bail, Context, Result};
 use arc_swap::ArcSwap;
+use bytes::Bytes;
 use once_cell::sync::Lazy;
-use phire::{l10n::LANG_IDENTS, scene::SimpleRecord};
+use phire::{l10n::LANG_IDENTS, scene::SimpleRecord, task::Task};
 use reqwest::{header, ClientBuilder, Method, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::{borrow::Cow, collections::HashMap, marker::PhantomData, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
 
 pub static CLIENT_TOKEN: Lazy<ArcSwap<Option<String>>> = Lazy::new(|| ArcSwap::from_pointee(None));
 
@@ -51,7 +63,11 @@ async fn set_access_token(access_token: &str) -> Result<()> {
 }
 
 pub async fn recv_raw(request: RequestBuilder) -> Result<Response> {
-    let response = request.send().await?;
+    recv_with(request, Priority::Interactive).await
+}
+
+pub async fn recv_with(request: RequestBuilder, priority: Priority) -> Result<Response> {
+    let response = schedule(request, priority).await?;
     if !response.status().is_success() {
         let status = response.status().as_str().to_owned();
         let text = response.text().await.context("failed to receive text")?;
@@ -136,8 +152,42 @@ impl Client {
         Ok(Some(value))
     }
 
+    // loads several objects with as few requests as possible, ids the server doesn't know are left out
+    pub async fn load_many<T: Object + 'static>(ids: &[i32]) -> Result<Vec<Arc<T>>> {
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let map = obtain_map_cache::<T>();
+            let mut guard = map.lock().unwrap();
+            let Some(actual_map) = guard.downcast_mut::<ObjectMap::<T>>() else { unreachable!() };
+            for &id in ids {
+                if let Some(value) = actual_map.get(&id) {
+                    found.insert(id, Arc::clone(value));
+                } else if !missing.contains(&id) {
+                    missing.push(id);
+                }
+            }
+        }
+        for chunk in missing.chunks(BATCH_SIZE) {
+            let ids = chunk.iter().map(i32::to_string).collect::<Vec<_>>().join(",");
+            let values: Vec<T> = recv_with(Self::get(format!("/{}/batch", T::QUERY_PATH)).query(&[("ids", ids)]), Priority::Prefetch)
+                .await?
+                .json()
+                .await?;
+            let map = obtain_map_cache::<T>();
+            let mut guard = map.lock().unwrap();
+            let Some(actual_map) = guard.downcast_mut::<ObjectMap::<T>>() else { unreachable!() };
+            for value in values {
+                let value = Arc::new(value);
+                actual_map.put(value.id(), Arc::clone(&value));
+                found.insert(value.id(), value);
+            }
+        }
+        Ok(ids.iter().filter_map(|id| found.get(id).cloned()).collect())
+    }
+
     async fn fetch_inner<T: Object>(id: i32) -> Result<Option<T>> {
-        let resp = Self::get(format!("/{}/{id}", T::QUERY_PATH)).send().await?;
+        let resp = schedule(Self::get(format!("/{}/{id}", T::QUERY_PATH)), Priority::Interactive).await?;
         if resp.status() == StatusCode::NOT_FOUND {
             return Ok(None);
         }
@@ -198,23 +248,125 @@ impl Client {
         Ok(recv_raw(Self::get("/me")).await?.json().await?)
     }
 
+    pub async fn get_save() -> Result<Option<CloudSave>> {
+        Ok(recv_raw(Self::get("/me/save")).await?.json().await?)
+    }
+
+    pub async fn put_save(save: &CloudSave) -> Result<()> {
+        recv_raw(Self::request(Method::PUT, "/me/save").json(save)).await?;
+        Ok(())
+    }
+
     pub async fn best_record(id: i32) -> Result<SimpleRecord> {
         Ok(recv_raw(Self::get(format!("/record/best/{id}"))).await?.json().await?)
     }
 
     pub async fn upload_file(name: &str, bytes: Vec<u8>) -> Result<String> {
+        Self::upload_file_with_progress(name, bytes, None).await
+    }
+
+    // `progress` goes from 0 to 1 as the body is handed over to the connection
+    pub async fn upload_file_with_progress(name: &str, bytes: Vec<u8>, progress: Option<Arc<Mutex<f32>>>) -> Result<String> {
         #[derive(Deserialize)]
         struct Resp {
             id: String,
         }
-        let resp: Resp = recv_raw(Self::request(Method::POST, format!("/upload/{name}")).body(bytes))
+        let bytes = Bytes::from(bytes);
+        let total = bytes.len();
+        let chunks: Vec<_> = (0..total)
+            .step_by(UPLOAD_CHUNK)
+            .map(|start| bytes.slice(start..(start + UPLOAD_CHUNK).min(total)))
+            .collect();
+        let mut sent = 0;
+        let stream = futures_util::stream::iter(chunks.into_iter().map(move |chunk| {
+            sent += chunk.len();
+            if let Some(progress) = &progress {
+                *progress.lock().unwrap() = sent as f32 / total as f32;
+            }
+            Ok::<_, std::io::Error>(chunk)
+        }));
+        let resp: Resp = recv_raw(
+            Self::request(Method::POST, format!("/upload/{name}"))
+                .header(header::CONTENT_LENGTH, total)
+                .body(reqwest::Body::wrap_stream(stream)),
+        )
+        .await?
+        .json()
+        .await?;
+        Ok(resp.id)
+    }
+
+    // `kind` is either "avatar" or "banner"
+    pub async fn edit_image(kind: &str, bytes: Vec<u8>, progress: Option<Arc<Mutex<f32>>>) -> Result<()> {
+        let id = Self::upload_file_with_progress(kind, bytes, progress).await?;
+        recv_raw(Self::post(format!("/edit/{kind}"), &json!({ "file": id }))).await?;
+        Ok(())
+    }
+
+    pub async fn edit_bio(bio: &str) -> Result<()> {
+        recv_raw(Self::post("/edit/bio", &json!({ "bio": bio }))).await?;
+        Ok(())
+    }
+
+    // `None` shows no badge at all
+    pub async fn edit_badge(badge: Option<&str>) -> Result<()> {
+        recv_raw(Self::post("/edit/badge", &json!({ "badge": badge }))).await?;
+        Ok(())
+    }
+
+    pub async fn user_stats(id: i32) -> Result<UserStats> {
+        Ok(recv_raw(Self::get(format!("/user/{id}/stats"))).await?.json().await?)
+    }
+
+    // the newest first
+    pub async fn recent_records(player: i32, limit: u32) -> Result<Vec<Record>> {
+        Ok(recv_raw(Self::get("/record").query(&[("player", player.to_string()), ("order", "-time".to_owned()), ("pageNum", limit.to_string())]))
             .await?
             .json()
-            .await?;
-        Ok(resp.id)
+            .await?)
+    }
+
+    pub async fn rate(chart: i32, score: i16) -> Result<()> {
+        recv_raw(Self::post(format!("/chart/{chart}/rate"), &json!({ "score": score }))).await?;
+        Ok(())
+    }
+
+    pub async fn unrate(chart: i32) -> Result<()> {
+        recv_raw(Self::delete(format!("/chart/{chart}/rate"))).await?;
+        Ok(())
+    }
+
+    pub async fn post_comment(chart: i32, content: &str) -> Result<Comment> {
+        Ok(recv_raw(Self::post("/comment", &json!({ "chart": chart, "content": content })))
+            .await?
+            .json()
+            .await?)
+    }
+
+    pub async fn edit_comment(id: i32, content: &str) -> Result<Comment> {
+        Ok(recv_raw(Self::request(Method::PATCH, format!("/comment/{id}")).json(&json!({ "content": content })))
+            .await?
+            .json()
+            .await?)
+    }
+
+    pub async fn delete_comment(id: i32) -> Result<()> {
+        recv_raw(Self::delete(format!("/comment/{id}"))).await?;
+        Ok(())
+    }
+
+    // flags the comment for moderators, `reason` is free text
+    pub async fn report_comment(id: i32, reason: &str) -> Result<()> {
+        recv_raw(Self::post(format!("/comment/{id}/report"), &json!({ "reason": reason }))).await?;
+        Ok(())
     }
 }
 
+// attempts per page before the error is reported
+const PAGE_RETRIES: u32 = 3;
+const BATCH_SIZE: usize = 50;
+const UPLOAD_CHUNK: usize = 64 * 1024;
+
 #[must_use]
 pub struct QueryBuilder<T> {
     queries: HashMap<Cow<'static, str>, Cow<'static, str>>,
@@ -223,6 +375,17 @@ pub struct QueryBuilder<T> {
     _phantom: PhantomData<T>,
 }
 
+impl<T> Clone for QueryBuilder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queries: self.queries.clone(),
+            page: self.page,
+            suffix: self.suffix,
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<T: Object> QueryBuilder<T> {
     pub fn query(mut self, key: impl Into<Cow<'static, str>>, value: impl Into<Cow<'static, str>>) -> Self {
         self.queries.insert(key.into(), value.into());
@@ -274,3 +437,106 @@ impl<T: Object> QueryBuilder<T> {
         Ok((res.results, res.count))
     }
 }
+
+// loads a list one page at a time for infinite scrolling, entries already seen are skipped in case the list
+// shifted between two pages
+pub struct Paginator<T: Object + 'static> {
+    query: QueryBuilder<T>,
+    page_num: u64,
+    next_page: u64,
+    total: Option<u64>,
+    items: Vec<T>,
+    seen: HashSet<i32>,
+    task: Option<Task<Result<(Vec<T>, u64)>>>,
+    // stops loading more after an error until reset
+    failed: bool,
+}
+
+impl<T: Object + 'static> Paginator<T> {
+    pub fn new(query: QueryBuilder<T>, page_num: u64) -> Self {
+        Self {
+            query,
+            page_num,
+            next_page: 0,
+            total: None,
+            items: Vec::new(),
+            seen: HashSet::new(),
+            task: None,
+            failed: false,
+        }
+    }
+
+    #[inline]
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    #[inline]
+    pub fn loading(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.total.map_or(false, |total| self.next_page * self.page_num >= total)
+    }
+
+    pub fn load_more(&mut self) {
+        if self.loading() || self.failed || self.exhausted() {
+            return;
+        }
+        let query = self.query.clone().page(self.next_page).page_num(self.page_num);
+        self.task = Some(Task::new(async move {
+            let mut tries = 1;
+            loop {
+                match query.clone().send().await {
+                    Err(_) if tries < PAGE_RETRIES => tries += 1,
+                    res => break res,
+                }
+            }
+        }));
+    }
+
+    pub fn reset(&mut self) {
+        self.next_page = 0;
+        self.total = None;
+        self.items.clear();
+        self.seen.clear();
+        self.task = None;
+        self.failed = false;
+    }
+
+    // local changes, so that what was just posted, edited or deleted shows without reloading the list
+    pub fn prepend(&mut self, item: T) {
+        if self.seen.insert(item.id()) {
+            self.items.insert(0, item);
+        }
+    }
+
+    pub fn replace(&mut self, item: T) {
+        if let Some(it) = self.items.iter_mut().find(|it| it.id() == item.id()) {
+            *it = item;
+        }
+    }
+
+    pub fn remove(&mut self, id: i32) {
+        self.items.retain(|it| it.id() != id);
+    }
+
+    // the entries a finished page added
+    pub fn poll(&mut self) -> Option<Result<&[T]>> {
+        let res = self.task.as_mut()?.take()?;
+        self.task = None;
+        self.failed = res.is_err();
+        Some(res.map(|(results, count)| {
+            self.total = Some(count);
+            self.next_page += 1;
+            let start = self.items.len();
+            for item in results {
+                if self.seen.insert(item.id()) {
+                    self.items.push(item);
+                }
+            }
+            &self.items[start..]
+        }))
+    }
+}
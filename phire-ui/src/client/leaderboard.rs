@@ -0,0 +1,219 @@
+use super::{recv_raw, Client, Record, PAGE_RETRIES};
+use crate::{dir, get_data};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use phire::{task::Task, ui::RectButton};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::warn;
+
+const PAGE_NUM: u64 = 30;
+// a cached leaderboard younger than this is shown without asking the server again
+const CACHE_TTL_MINUTES: i64 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LdbScope {
+    Global,
+    // only the players the account follows, and itself
+    Friends,
+}
+
+impl LdbScope {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Friends => "friends",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LdbItem {
+    #[serde(flatten)]
+    pub inner: Record,
+    pub rank: u32,
+    #[serde(skip, default)]
+    pub btn: RectButton,
+}
+
+#[derive(Deserialize)]
+struct LdbPage {
+    count: u64,
+    results: Vec<LdbItem>,
+    // the account's own entry, even when it's on a page that isn't loaded
+    me: Option<LdbItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    time: DateTime<Utc>,
+    count: u64,
+    pages: u64,
+    items: Vec<LdbItem>,
+    me: Option<u32>,
+    // who was logged in, another account's rank is of no use
+    account: Option<i32>,
+}
+
+async fn fetch_page(chart: i32, query: &[(&str, String)]) -> Result<LdbPage> {
+    Ok(recv_raw(Client::get(format!("/record/ldb/{chart}")).query(query)).await?.json().await?)
+}
+
+// the leaderboard of a chart, loaded a page at a time. What's loaded is kept in the cache folder so that coming
+// back to the chart shortly after doesn't fetch it again
+pub struct Leaderboard {
+    chart: i32,
+    std: bool,
+    scope: LdbScope,
+    items: Vec<LdbItem>,
+    seen: HashSet<i32>,
+    total: Option<u64>,
+    next_page: u64,
+    my_rank: Option<u32>,
+    task: Option<Task<Result<LdbPage>>>,
+    failed: bool,
+}
+
+impl Leaderboard {
+    pub fn new(chart: i32, std: bool, scope: LdbScope) -> Self {
+        let mut res = Self {
+            chart,
+            std,
+            scope,
+            items: Vec::new(),
+            seen: HashSet::new(),
+            total: None,
+            next_page: 0,
+            my_rank: None,
+            task: None,
+            failed: false,
+        };
+        // offline, an outdated leaderboard is better than none
+        let offline = get_data().config.offline_mode;
+        match res.load_cache() {
+            Some(cached) if offline || Utc::now() - cached.time < Duration::minutes(CACHE_TTL_MINUTES) => {
+                res.seen = cached.items.iter().map(|it| it.inner.id).collect();
+                res.items = cached.items;
+                res.total = Some(cached.count);
+                res.next_page = cached.pages;
+                res.my_rank = cached.me;
+            }
+            _ => res.load_more(),
+        }
+        res
+    }
+
+    fn cache_path(&self) -> Result<String> {
+        Ok(format!(
+            "{}/ldb-{}-{}-{}.json",
+            dir::cache()?,
+            self.chart,
+            if self.std { "std" } else { "score" },
+            self.scope.name()
+        ))
+    }
+
+    fn load_cache(&self) -> Option<Cached> {
+        let cached: Cached = serde_json::from_str(&std::fs::read_to_string(self.cache_path().ok()?).ok()?).ok()?;
+        (cached.account == get_data().me.as_ref().map(|it| it.id)).then_some(cached)
+    }
+
+    fn save_cache(&self) -> Result<()> {
+        let cached = Cached {
+            time: Utc::now(),
+            count: self.total.unwrap_or_default(),
+            pages: self.next_page,
+            items: self.items.clone(),
+            me: self.my_rank,
+            account: get_data().me.as_ref().map(|it| it.id),
+        };
+        std::fs::write(self.cache_path()?, serde_json::to_string(&cached)?)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn items(&self) -> &[LdbItem] {
+        &self.items
+    }
+
+    #[inline]
+    pub fn items_mut(&mut self) -> &mut [LdbItem] {
+        &mut self.items
+    }
+
+    // false until the first page arrives
+    #[inline]
+    pub fn loaded(&self) -> bool {
+        self.total.is_some()
+    }
+
+    #[inline]
+    pub fn loading(&self) -> bool {
+        self.task.is_some()
+    }
+
+    #[inline]
+    pub fn my_rank(&self) -> Option<u32> {
+        self.my_rank
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.total.map_or(false, |total| self.next_page * PAGE_NUM >= total)
+    }
+
+    pub fn load_more(&mut self) {
+        if self.loading() || self.failed || self.exhausted() || get_data().config.offline_mode {
+            return;
+        }
+        let (chart, page) = (self.chart, self.next_page);
+        let query = [
+            ("std", self.std.to_string()),
+            ("scope", self.scope.name().to_owned()),
+            ("page", (page + 1).to_string()),
+            ("pageNum", PAGE_NUM.to_string()),
+        ];
+        self.task = Some(Task::new(async move {
+            let mut tries = 1;
+            loop {
+                match fetch_page(chart, &query).await {
+                    Err(_) if tries < PAGE_RETRIES => tries += 1,
+                    res => break res,
+                }
+            }
+        }));
+    }
+
+    // drops what's loaded, cached or not, and fetches the first page again
+    pub fn refresh(&mut self) {
+        self.items.clear();
+        self.seen.clear();
+        self.total = None;
+        self.next_page = 0;
+        self.my_rank = None;
+        self.task = None;
+        self.failed = false;
+        self.load_more();
+    }
+
+    // the entries a finished page added
+    pub fn poll(&mut self) -> Option<Result<&[LdbItem]>> {
+        let res = self.task.as_mut()?.take()?;
+        self.task = None;
+        self.failed = res.is_err();
+        Some(res.map(|page| {
+            self.total = Some(page.count);
+            self.next_page += 1;
+            self.my_rank = page.me.map(|it| it.rank);
+            let start = self.items.len();
+            for item in page.results {
+                if self.seen.insert(item.inner.id) {
+                    self.items.push(item);
+                }
+            }
+            if let Err(err) = self.save_cache() {
+                warn!("failed to cache leaderboard: {err:?}");
+            }
+            &self.items[start..]
+        }))
+    }
+}
@@ -4,6 +4,12 @@ pub use chart::*;
 mod collection;
 pub use collection::*;
 
+mod comment;
+pub use comment::*;
+
+mod curation;
+pub use curation::*;
+
 mod event;
 pub use event::*;
 
@@ -0,0 +1,22 @@
+use super::{Object, Ptr, User};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Comment {
+    pub id: i32,
+    pub author: Ptr<User>,
+    pub chart: i32,
+    pub content: String,
+    // the author's rating of the chart, out of 10, if they rated it
+    pub score: Option<i16>,
+    pub time: DateTime<Utc>,
+    pub edited: Option<DateTime<Utc>>,
+}
+impl Object for Comment {
+    const QUERY_PATH: &'static str = "comment";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
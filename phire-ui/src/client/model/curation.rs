@@ -0,0 +1,21 @@
+use super::{Chart, Object};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+// a list of charts picked by the server, like a beginner pack or the monthly picks
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CuratedList {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub updated: DateTime<Utc>,
+    pub charts: Vec<Chart>,
+}
+impl Object for CuratedList {
+    const QUERY_PATH: &'static str = "curation";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
@@ -1,8 +1,8 @@
 use super::{Chart, Object, Ptr, User};
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Record {
     pub id: i32,
     pub player: Ptr<User>,
@@ -1,8 +1,12 @@
 use super::{File, Object};
-use crate::client::Client;
+use crate::{badge::badge_color, client::Client};
 use anyhow::Result;
 use bitflags::bitflags;
 use chrono::{DateTime, Utc};
+use futures_util::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
 use image::DynamicImage;
 use macroquad::prelude::Color;
 use once_cell::sync::Lazy;
@@ -84,6 +88,8 @@ pub struct User {
     pub id: i32,
     pub name: String,
     pub avatar: Option<File>,
+    #[serde(default)]
+    pub banner: Option<File>,
     pub badge: Option<String>,
     pub badges: Vec<String>,
     pub language: String,
@@ -113,18 +119,36 @@ impl User {
     }
 
     pub fn name_color(&self) -> Color {
-        Color::from_hex(if self.badges.iter().any(|it| it == "admin") {
-            0xff673ab7
+        if self.badges.iter().any(|it| it == "admin") {
+            badge_color("admin")
         } else if self.badges.iter().any(|it| it == "sponsor") {
-            0xffff7043
+            badge_color("sponsor")
         } else {
-            0xffffffff
-        })
+            Color::from_hex(0xffffffff)
+        }
+    }
+
+    // the badge picked to show next to the name, as long as it's still one the user has
+    pub fn shown_badge(&self) -> Option<&str> {
+        self.badge.as_deref().filter(|badge| self.badges.iter().any(|it| it == badge))
     }
 }
 
+// play statistics over every record of a player
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStats {
+    pub play_count: u32,
+    pub avg_accuracy: f32,
+    pub best_accuracy: f32,
+    pub full_combos: u32,
+    // all perfect
+    pub phis: u32,
+    pub chart_count: u32,
+}
+
 static TASKS: Lazy<Mutex<HashMap<i32, Task<Result<Option<DynamicImage>>>>>> = Lazy::new(Mutex::default);
-static RESULTS: Lazy<Mutex<HashMap<i32, (String, Color, Option<Option<SafeTexture>>)>>> = Lazy::new(Mutex::default);
+static RESULTS: Lazy<Mutex<HashMap<i32, (String, Color, Option<String>, Option<Option<SafeTexture>>)>>> = Lazy::new(Mutex::default);
 
 pub struct UserManager;
 
@@ -135,23 +159,54 @@ impl UserManager {
         Ok(())
     }
 
+    async fn fetch(id: i32) -> Result<Option<DynamicImage>> {
+        let user: Arc<User> = Client::load(id).await?;
+        RESULTS
+            .lock()
+            .await
+            .insert(id, (user.name.clone(), user.name_color(), user.shown_badge().map(str::to_owned), None));
+        if let Some(avatar) = &user.avatar {
+            Ok(Some(image::load_from_memory(&avatar.fetch().await?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn request(id: i32) {
         let mut tasks = TASKS.blocking_lock();
         if tasks.contains_key(&id) {
             return;
         }
-        tasks.insert(
-            id,
-            Task::new(async move {
-                let user: Arc<User> = Client::load(id).await?;
-                RESULTS.lock().await.insert(id, (user.name.clone(), user.name_color(), None));
-                if let Some(avatar) = &user.avatar {
-                    Ok(Some(image::load_from_memory(&avatar.fetch().await?)?))
-                } else {
-                    Ok(None)
-                }
-            }),
-        );
+        tasks.insert(id, Task::new(Self::fetch(id)));
+    }
+
+    // fetches the users of a list in one go; each one still falls back to a single request if the batch fails
+    pub fn request_many(ids: impl IntoIterator<Item = i32>) {
+        let mut tasks = TASKS.blocking_lock();
+        let mut ids: Vec<i32> = ids.into_iter().filter(|id| !tasks.contains_key(id)).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.is_empty() {
+            return;
+        }
+        let batch: Shared<BoxFuture<'static, Result<(), Arc<anyhow::Error>>>> = {
+            let ids = ids.clone();
+            async move { Client::load_many::<User>(&ids).await.map(drop).map_err(Arc::new) }
+                .boxed()
+                .shared()
+        };
+        for id in ids {
+            let batch = batch.clone();
+            tasks.insert(
+                id,
+                Task::new(async move {
+                    if let Err(err) = batch.await {
+                        warn!("failed to fetch users in batch: {err:?}");
+                    }
+                    Self::fetch(id).await
+                }),
+            );
+        }
     }
 
     pub fn name_and_color(id: i32) -> Option<(String, Color)> {
@@ -163,6 +218,10 @@ impl UserManager {
         }
     }
 
+    pub fn badge(id: i32) -> Option<String> {
+        RESULTS.blocking_lock().get(&id).and_then(|it| it.2.clone())
+    }
+
     pub fn get_avatar(id: i32) -> Option<Option<SafeTexture>> {
         let mut guard = TASKS.blocking_lock();
         if let Some(task) = guard.get_mut(&id) {
@@ -173,14 +232,14 @@ impl UserManager {
                         guard.remove(&id);
                     }
                     Ok(image) => {
-                        RESULTS.blocking_lock().get_mut(&id).unwrap().2 = Some(image.map(|it| SafeTexture::from(it).with_mipmap()));
+                        RESULTS.blocking_lock().get_mut(&id).unwrap().3 = Some(image.map(|it| SafeTexture::from(it).with_mipmap()));
                     }
                 }
             }
         } else {
             drop(guard);
         }
-        RESULTS.blocking_lock().get(&id).and_then(|it| it.2.clone())
+        RESULTS.blocking_lock().get(&id).and_then(|it| it.3.clone())
     }
 
     pub fn opt_avatar(id: i32, tex: &SafeTexture) -> Result<Option<SafeTexture>, SafeTexture> {
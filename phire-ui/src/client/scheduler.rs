@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use reqwest::{header::HeaderMap, RequestBuilder, Response, StatusCode};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+const MAX_RETRIES: u32 = 3;
+// used when a 429 comes without a Retry-After header
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(2);
+// how often a waiting prefetch checks whether interactive requests are done
+const PREFETCH_YIELD: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    // something the user is waiting on
+    Interactive,
+    // background loading that may be held back
+    Prefetch,
+}
+
+#[derive(Default)]
+struct Limits {
+    blocked_until: Option<Instant>,
+    remaining: Option<u32>,
+    reset_at: Option<Instant>,
+}
+
+static LIMITS: Lazy<Mutex<Limits>> = Lazy::new(Mutex::default);
+static INTERACTIVE: AtomicUsize = AtomicUsize::new(0);
+static SLOW_DOWN: AtomicBool = AtomicBool::new(false);
+
+struct InteractiveGuard;
+
+impl InteractiveGuard {
+    fn new() -> Self {
+        INTERACTIVE.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for InteractiveGuard {
+    fn drop(&mut self) {
+        INTERACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+fn record(headers: &HeaderMap) {
+    let now = Instant::now();
+    let mut limits = LIMITS.lock().unwrap();
+    if let Some(remaining) = header(headers, "x-ratelimit-remaining") {
+        limits.remaining = Some(remaining);
+    }
+    if let Some(reset) = header::<f64>(headers, "x-ratelimit-reset") {
+        limits.reset_at = Some(now + Duration::from_secs_f64(reset.max(0.)));
+    }
+}
+
+fn block(headers: &HeaderMap) {
+    let after = header::<f64>(headers, "retry-after").map_or(DEFAULT_BACKOFF, |it| Duration::from_secs_f64(it.max(0.)));
+    let until = Instant::now() + after;
+    let mut limits = LIMITS.lock().unwrap();
+    limits.blocked_until = Some(limits.blocked_until.map_or(until, |it| it.max(until)));
+}
+
+fn delay(priority: Priority) -> Option<Duration> {
+    let now = Instant::now();
+    let limits = LIMITS.lock().unwrap();
+    if let Some(until) = limits.blocked_until.filter(|it| *it > now) {
+        return Some(until - now);
+    }
+    if limits.remaining == Some(0) {
+        if let Some(reset) = limits.reset_at.filter(|it| *it > now) {
+            return Some(reset - now);
+        }
+    }
+    if priority == Priority::Prefetch && INTERACTIVE.load(Ordering::SeqCst) != 0 {
+        return Some(PREFETCH_YIELD);
+    }
+    None
+}
+
+// set when an interactive request had to wait for the rate limit, taken by the UI to tell the user
+pub fn take_slow_down() -> bool {
+    SLOW_DOWN.swap(false, Ordering::Relaxed)
+}
+
+pub async fn schedule(request: RequestBuilder, priority: Priority) -> Result<Response> {
+    let _guard = (priority == Priority::Interactive).then(InteractiveGuard::new);
+    let mut request = Some(request);
+    for _ in 0..=MAX_RETRIES {
+        while let Some(delay) = delay(priority) {
+            tokio::time::sleep(delay).await;
+        }
+        let current = request.take().unwrap();
+        // bodies that can't be cloned only get one attempt
+        request = current.try_clone();
+        let response = current.send().await?;
+        record(response.headers());
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+        block(response.headers());
+        if priority == Priority::Interactive {
+            SLOW_DOWN.store(true, Ordering::Relaxed);
+        }
+        if request.is_none() {
+            break;
+        }
+    }
+    Err(anyhow!("request failed (429): rate limited"))
+}
@@ -0,0 +1,92 @@
+phire::tl_file!("cloud");
+
+use crate::{achievement, charts_view::NEED_UPDATE, client::Client, data::CloudSave, get_data, get_data_mut, save_data};
+use anyhow::Result;
+use phire::{
+    scene::{show_error, show_message},
+    task::Task,
+};
+use std::{cell::RefCell, sync::atomic::Ordering};
+use tracing::warn;
+
+thread_local! {
+    // the task and whether the player asked for it
+    static SYNC_TASK: RefCell<Option<(Task<Result<CloudSave>>, bool)>> = RefCell::new(None);
+}
+
+// settings go to whichever side changed them last, records and achievements are merged by keeping the best of both
+async fn sync(local: CloudSave) -> Result<CloudSave> {
+    let Some(remote) = Client::get_save().await? else {
+        Client::put_save(&local).await?;
+        return Ok(local);
+    };
+    let (mut merged, other) = if remote.updated > local.updated {
+        (remote.clone(), local)
+    } else {
+        (local, remote.clone())
+    };
+    merged.play_count = merged.play_count.max(other.play_count);
+    achievement::merge(&mut merged.achievements, &other.achievements);
+    let other = other.records;
+    for (id, record) in other {
+        merged
+            .records
+            .entry(id)
+            .and_modify(|it| {
+                it.update(&record);
+            })
+            .or_insert(record);
+    }
+    if serde_json::to_value(&merged)? != serde_json::to_value(&remote)? {
+        Client::put_save(&merged).await?;
+    }
+    Ok(merged)
+}
+
+pub fn start_sync(manual: bool) {
+    let data = get_data();
+    if data.me.is_none() || data.config.offline_mode {
+        if manual {
+            show_message(tl!("need-login")).error();
+        }
+        return;
+    }
+    SYNC_TASK.with(|it| {
+        let mut it = it.borrow_mut();
+        if it.is_none() {
+            *it = Some((Task::new(sync(data.to_cloud_save())), manual));
+        }
+    });
+}
+
+pub fn syncing() -> bool {
+    SYNC_TASK.with(|it| it.borrow().is_some())
+}
+
+pub fn update() -> Result<()> {
+    let Some((res, manual)) = SYNC_TASK.with(|it| {
+        let mut it = it.borrow_mut();
+        let res = it.as_mut()?.0.take()?;
+        Some((res, it.take().unwrap().1))
+    }) else {
+        return Ok(());
+    };
+    match res {
+        Err(err) => {
+            if manual {
+                show_error(err.context(tl!("sync-failed")));
+            } else {
+                warn!("cloud sync failed: {err:?}");
+            }
+        }
+        Ok(save) => {
+            get_data_mut().apply_cloud_save(save)?;
+            save_data()?;
+            NEED_UPDATE.store(true, Ordering::Relaxed);
+            if manual {
+                show_message(tl!("sync-done")).ok();
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::{
+    achievement, chart_index,
     client::{Ptr, User},
     dir,
 };
@@ -10,7 +11,13 @@ use phire::{
     scene::SimpleRecord,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, ops::DerefMut, path::Path};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    fs::OpenOptions,
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
 
 fn default_score_total() -> u32 {
     1_000_000
@@ -62,8 +69,130 @@ pub struct LocalChart {
     pub info: BriefChartInfo,
     pub local_path: String,
     pub record: Option<SimpleRecord>,
+    // md5 of the chart file `record` was set on
+    #[serde(default)]
+    pub record_chart_hash: Option<String>,
+    #[serde(default)]
+    pub mods: Mods,
+}
+
+// one line per completed play in `data/history.jsonl`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayHistory {
+    pub local_path: String,
+    pub time: DateTime<Utc>,
+    pub score: u32,
+    pub accuracy: f32,
+    pub full_combo: bool,
+    // last modification of the chart file when played, used to tell apart attempts on different versions
+    pub chart_version: Option<DateTime<Utc>>,
+    // md5 of the chart file, unlike `chart_version` it only changes with the content
+    #[serde(default)]
+    pub chart_hash: Option<String>,
+    #[serde(default)]
+    pub max_combo: u32,
     #[serde(default)]
     pub mods: Mods,
+    // perfect, good, bad and miss, all zero for entries saved before they were tracked
+    #[serde(default)]
+    pub counts: [u32; 4],
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub random_seed: Option<u64>,
+}
+
+impl PlayHistory {
+    fn path() -> Result<String> {
+        Ok(format!("{}/history.jsonl", dir::root()?))
+    }
+
+    pub fn append(&self) -> Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(Self::path()?)?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(local_path: &str) -> Result<Vec<Self>> {
+        let mut res = Self::load_all()?;
+        res.retain(|it| it.local_path == local_path);
+        Ok(res)
+    }
+
+    pub fn load_all() -> Result<Vec<Self>> {
+        let path = Self::path()?;
+        if !Path::new(&path).exists() {
+            return Ok(Vec::new());
+        }
+        let mut res = Vec::new();
+        for line in BufReader::new(std::fs::File::open(path)?).lines() {
+            let Ok(entry) = serde_json::from_str::<Self>(&line?) else {
+                continue;
+            };
+            res.push(entry);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedRecord<'a> {
+    // `best` for the record kept of a chart, `play` for an entry of the play history
+    kind: &'static str,
+    // only known for plays
+    time: Option<DateTime<Utc>>,
+    id: Option<i32>,
+    name: &'a str,
+    level: &'a str,
+    difficulty: f32,
+    charter: &'a str,
+    composer: &'a str,
+    local_path: &'a str,
+    score: u32,
+    accuracy: f32,
+    full_combo: bool,
+}
+
+// a named group of local charts, kept in the order they were arranged in
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    // local paths, charts deleted since are skipped when it's shown
+    pub charts: Vec<String>,
+}
+
+// what the online chart list was last searched with, restored when the library is opened again
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartFilters {
+    pub search: String,
+    // index into `ORDERS`
+    pub order: usize,
+    pub tags: Vec<String>,
+    pub unwanted: Vec<String>,
+    pub division: String,
+    // in tenths of a star, like the rating dialog
+    pub rating: (i16, i16),
+    pub level: Option<(f32, f32)>,
+    pub notes: Option<(u32, u32)>,
+}
+
+impl Default for ChartFilters {
+    fn default() -> Self {
+        Self {
+            search: String::new(),
+            order: 0,
+            tags: Vec::new(),
+            unwanted: Vec::new(),
+            division: "regular".to_owned(),
+            rating: (3, 10),
+            level: None,
+            notes: None,
+        }
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -78,7 +207,45 @@ pub struct Data {
     pub tokens: Option<(String, String)>,
     pub respacks: Vec<String>,
     pub respack_id: usize,
+    // packs stacked from the top down, used in place of `respack_id` when not empty
+    pub respack_layers: Vec<String>,
     pub accept_invalid_cert: bool,
+    // output latency measured on first run, in seconds
+    pub latency_probe: Option<f32>,
+    // offsets adjusted from the pause menu, keyed by local path
+    pub chart_offsets: HashMap<String, f32>,
+    // background blur adjusted from the pause menu, same keys
+    pub chart_blurs: HashMap<String, f32>,
+    pub dismissed_banners: HashSet<String>,
+    // keeps the chart list free of best score badges, e.g. for screenshots
+    pub hide_record_badges: bool,
+    pub cloud_sync: bool,
+    // last change to the synced settings, `None` until something is changed on this device
+    pub sync_updated: Option<DateTime<Utc>>,
+    sync_hash: Option<u64>,
+    pub last_synced: Option<DateTime<Utc>>,
+    // best records from the cloud for charts that are not on this device (yet), keyed by chart id
+    pub cloud_records: HashMap<i32, SimpleRecord>,
+    // finished plays across all charts, for achievements
+    pub play_count: u32,
+    // unlock times, keyed by achievement id
+    pub achievements: HashMap<String, DateTime<Utc>>,
+    pub chart_filters: ChartFilters,
+    pub collections: Vec<Collection>,
+}
+
+// what gets stored on the account: settings, per-chart offsets, bests of online charts and achievements
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudSave {
+    pub updated: DateTime<Utc>,
+    pub config: Config,
+    pub chart_offsets: HashMap<String, f32>,
+    pub records: HashMap<i32, SimpleRecord>,
+    #[serde(default)]
+    pub play_count: u32,
+    #[serde(default)]
+    pub achievements: HashMap<String, DateTime<Utc>>,
 }
 
 impl Data {
@@ -86,50 +253,19 @@ impl Data {
         let charts = dir::charts()?;
         self.charts.retain(|it| Path::new(&format!("{}/{}", charts, it.local_path)).exists());
         let occurred: HashSet<_> = self.charts.iter().map(|it| it.local_path.clone()).collect();
-        for entry in std::fs::read_dir(dir::custom_charts()?)? {
-            let entry = entry?;
-            let filename = entry.file_name();
-            let filename = filename.to_str().unwrap();
-            let filename = format!("custom/{filename}");
-            if occurred.contains(&filename) {
-                continue;
-            }
-            let path = entry.path();
-            let Ok(mut fs) = phire::fs::fs_from_file(&path) else {
+        for (local_path, entry) in chart_index::scan().await? {
+            if occurred.contains(&local_path) {
                 continue;
-            };
-            let result = phire::fs::load_info(fs.deref_mut()).await;
-            if let Ok(info) = result {
-                self.charts.push(LocalChart {
-                    info: BriefChartInfo { id: None, ..info.into() },
-                    local_path: filename,
-                    record: None,
-                    mods: Mods::default(),
-                });
-            }
-        }
-        for entry in std::fs::read_dir(dir::downloaded_charts()?)? {
-            let entry = entry?;
-            let filename = entry.file_name();
-            let filename = filename.to_str().unwrap();
-            let filename = format!("download/{filename}");
-            let Ok(id): Result<i32, _> = filename.parse() else { continue };
-            if occurred.contains(&filename) {
-                continue;
-            }
-            let path = entry.path();
-            let Ok(mut fs) = phire::fs::fs_from_file(&path) else {
-                continue;
-            };
-            let result = phire::fs::load_info(fs.deref_mut()).await;
-            if let Ok(info) = result {
-                self.charts.push(LocalChart {
-                    info: BriefChartInfo { id: Some(id), ..info.into() },
-                    local_path: filename,
-                    record: None,
-                    mods: Mods::default(),
-                });
             }
+            // downloads are named after the chart id, the scan skips the ones that aren't
+            let id = local_path.strip_prefix("download/").and_then(|it| it.parse().ok());
+            self.charts.push(LocalChart {
+                info: BriefChartInfo { id, ..entry.info },
+                local_path,
+                record: None,
+                record_chart_hash: None,
+                mods: Mods::default(),
+            });
         }
         if let Some(res_pack_path) = &mut self.config.res_pack_path {
             if res_pack_path.starts_with('/') {
@@ -141,7 +277,161 @@ impl Data {
         Ok(())
     }
 
+    fn settings_hash(&self) -> Result<u64> {
+        let offsets: BTreeMap<_, _> = self.chart_offsets.iter().map(|(k, v)| (k, v.to_bits())).collect();
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(&self.config)?.hash(&mut hasher);
+        offsets.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    // bumps `sync_updated` if settings changed since the last save
+    pub fn touch_sync(&mut self) -> Result<()> {
+        let hash = self.settings_hash()?;
+        if self.sync_hash.map_or(false, |it| it != hash) {
+            self.sync_updated = Some(Utc::now());
+        }
+        self.sync_hash = Some(hash);
+        Ok(())
+    }
+
+    pub fn to_cloud_save(&self) -> CloudSave {
+        let mut records = self.cloud_records.clone();
+        for chart in &self.charts {
+            let (Some(id), Some(record)) = (chart.info.id, &chart.record) else {
+                continue;
+            };
+            let record = SimpleRecord {
+                deltas: Vec::new(),
+                ..record.clone()
+            };
+            records.entry(id).and_modify(|it| {
+                it.update(&record);
+            }).or_insert(record);
+        }
+        CloudSave {
+            updated: self.sync_updated.unwrap_or_default(),
+            config: self.config.clone(),
+            chart_offsets: self.chart_offsets.clone(),
+            records,
+            play_count: self.play_count,
+            achievements: self.achievements.clone(),
+        }
+    }
+
+    // takes over settings from the cloud if they are newer, and merges records either way
+    pub fn apply_cloud_save(&mut self, save: CloudSave) -> Result<()> {
+        if self.sync_updated.map_or(true, |it| it < save.updated) {
+            let local = std::mem::replace(&mut self.config, save.config);
+            // these depend on the device rather than the player
+            self.config.offset = local.offset;
+            self.config.audio_buffer_size = local.audio_buffer_size;
+            #[cfg(target_os = "android")]
+            {
+                self.config.audio_compatibility = local.audio_compatibility;
+            }
+            self.config.res_pack_path = local.res_pack_path;
+            self.config.sample_count = local.sample_count;
+            self.config.offline_mode = local.offline_mode;
+            self.config.player_name = local.player_name;
+            self.config.player_rks = local.player_rks;
+            self.config.init();
+            self.chart_offsets = save.chart_offsets;
+            self.sync_updated = Some(save.updated);
+            self.sync_hash = Some(self.settings_hash()?);
+        }
+        for chart in &mut self.charts {
+            let Some(remote) = chart.info.id.and_then(|id| save.records.get(&id)) else {
+                continue;
+            };
+            match &mut chart.record {
+                Some(record) => {
+                    record.update(remote);
+                }
+                None => chart.record = Some(remote.clone()),
+            }
+        }
+        self.cloud_records = save.records;
+        self.play_count = self.play_count.max(save.play_count);
+        achievement::merge(&mut self.achievements, &save.achievements);
+        self.last_synced = Some(Utc::now());
+        Ok(())
+    }
+
+    // sets the resource pack paths of a config about to be played with
+    pub fn apply_respacks(&self, config: &mut Config) -> Result<()> {
+        let dir = dir::respacks()?;
+        let mut paths: Vec<String> = if self.respack_layers.is_empty() {
+            self.respack_id.checked_sub(1).map(|id| format!("{dir}/{}", self.respacks[id])).into_iter().collect()
+        } else {
+            self.respack_layers
+                .iter()
+                .filter(|it| self.respacks.contains(it))
+                .map(|it| format!("{dir}/{it}"))
+                .collect()
+        };
+        config.res_pack_path = (!paths.is_empty()).then(|| paths.remove(0));
+        config.res_pack_fallbacks = paths;
+        Ok(())
+    }
+
     pub fn find_chart_by_path(&self, local_path: &str) -> Option<usize> {
         self.charts.iter().position(|local| local.local_path == local_path)
     }
+
+    #[cfg(not(target_os = "android"))]
+    fn exported_records<'a>(&'a self, history: &'a [PlayHistory]) -> impl Iterator<Item = ExportedRecord<'a>> {
+        let best = self.charts.iter().filter_map(|chart| {
+            let record = chart.record.as_ref()?;
+            Some(ExportedRecord {
+                kind: "best",
+                time: None,
+                id: chart.info.id,
+                name: &chart.info.name,
+                level: &chart.info.level,
+                difficulty: chart.info.difficulty,
+                charter: &chart.info.charter,
+                composer: &chart.info.composer,
+                local_path: &chart.local_path,
+                score: record.score,
+                accuracy: record.accuracy,
+                full_combo: record.full_combo,
+            })
+        });
+        let plays = history.iter().map(|entry| {
+            // plays of charts deleted since are kept, without what was known about the chart
+            let chart = self.charts.iter().find(|it| it.local_path == entry.local_path);
+            ExportedRecord {
+                kind: "play",
+                time: Some(entry.time),
+                id: chart.and_then(|it| it.info.id),
+                name: chart.map_or("", |it| &it.info.name),
+                level: chart.map_or("", |it| &it.info.level),
+                difficulty: chart.map_or(0., |it| it.info.difficulty),
+                charter: chart.map_or("", |it| &it.info.charter),
+                composer: chart.map_or("", |it| &it.info.composer),
+                local_path: &entry.local_path,
+                score: entry.score,
+                accuracy: entry.accuracy,
+                full_combo: entry.full_combo,
+            }
+        });
+        best.chain(plays)
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn export_records_csv(&self) -> Result<Vec<u8>> {
+        let history = PlayHistory::load_all()?;
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for record in self.exported_records(&history) {
+            writer.serialize(record)?;
+        }
+        Ok(writer.into_inner()?)
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn export_records_json(&self) -> Result<Vec<u8>> {
+        let history = PlayHistory::load_all()?;
+        Ok(serde_json::to_vec_pretty(&self.exported_records(&history).collect::<Vec<_>>())?)
+    }
 }
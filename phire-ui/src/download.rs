@@ -0,0 +1,432 @@
+phire::tl_file!("download");
+
+use crate::{
+    achievement, chart_index,
+    charts_view::NEED_UPDATE,
+    client::{basic_client_builder, Chart, CACHE_DIR, CLIENT_TOKEN},
+    data::{BriefChartInfo, LocalChart},
+    dir, get_data, get_data_mut, save_data,
+};
+use anyhow::{bail, Context, Result};
+use futures_util::StreamExt;
+use phire::{
+    config::Mods,
+    ext::unzip_into,
+    info::ChartInfo,
+    scene::{show_error, show_message},
+    task::Task,
+};
+use reqwest::{header, RequestBuilder, StatusCode};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+// downloads running at the same time, the rest wait in the queue
+const MAX_PARALLEL: usize = 2;
+// how often a cancelled download is checked for having stopped before its files are removed
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+const CONTROL_RUN: u8 = 0;
+const CONTROL_PAUSE: u8 = 1;
+const CONTROL_CANCEL: u8 = 2;
+// the chart is being moved into place, which can't be stopped halfway
+const CONTROL_COMMIT: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DownloadState {
+    Queued,
+    Running,
+    Paused,
+    Failed,
+    Done,
+}
+
+pub struct Progress {
+    status: Mutex<Cow<'static, str>>,
+    downloaded: AtomicU64,
+    total: AtomicU64,
+    control: AtomicU8,
+}
+
+impl Progress {
+    fn new() -> Self {
+        Self {
+            status: Mutex::new(tl!("status-queued")),
+            downloaded: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            control: AtomicU8::new(CONTROL_RUN),
+        }
+    }
+
+    fn set_status(&self, status: Cow<'static, str>) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    pub fn status(&self) -> Cow<'static, str> {
+        self.status.lock().unwrap().clone()
+    }
+
+    // None while the size is unknown
+    pub fn ratio(&self) -> Option<f32> {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return None;
+        }
+        Some((self.downloaded.load(Ordering::Relaxed).min(total) as f64 / total as f64) as f32)
+    }
+
+    fn stopped(&self) -> bool {
+        self.control.load(Ordering::Relaxed) != CONTROL_RUN
+    }
+}
+
+pub struct DownloadItem {
+    pub info: BriefChartInfo,
+    entity: Chart,
+    // the local chart this download replaces, if it's an update
+    update_of: Option<String>,
+    pub state: DownloadState,
+    pub error: Option<String>,
+    pub progress: Arc<Progress>,
+    // where the finished chart ended up
+    pub local_path: Option<String>,
+    task: Option<Task<Result<LocalChart>>>,
+    // the folder the running download extracts to
+    extract_to: Option<String>,
+}
+
+impl DownloadItem {
+    pub fn id(&self) -> i32 {
+        self.entity.id
+    }
+}
+
+#[derive(Default)]
+pub struct DownloadManager {
+    items: Vec<DownloadItem>,
+}
+
+thread_local! {
+    static MANAGER: RefCell<DownloadManager> = RefCell::default();
+}
+
+pub fn with_manager<R>(f: impl FnOnce(&mut DownloadManager) -> R) -> R {
+    MANAGER.with(|it| f(&mut it.borrow_mut()))
+}
+
+fn partial_path(id: i32) -> Result<PathBuf> {
+    let dir = format!("{}/download", dir::cache()?);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Path::new(&dir).join(format!("{id}.part")))
+}
+
+impl DownloadManager {
+    pub fn items(&self) -> &[DownloadItem] {
+        &self.items
+    }
+
+    pub fn get(&self, id: i32) -> Option<&DownloadItem> {
+        self.items.iter().find(|it| it.id() == id)
+    }
+
+    fn get_mut(&mut self, id: i32) -> Option<&mut DownloadItem> {
+        self.items.iter_mut().find(|it| it.id() == id)
+    }
+
+    // queues a chart, or brings an existing entry for it back into the queue
+    pub fn enqueue(&mut self, info: BriefChartInfo, entity: Chart, update_of: Option<String>) {
+        let id = entity.id;
+        if let Some(item) = self.get_mut(id) {
+            if matches!(item.state, DownloadState::Queued | DownloadState::Running) {
+                return;
+            }
+            item.info = info;
+            item.entity = entity;
+            item.update_of = update_of;
+            item.state = DownloadState::Queued;
+            item.error = None;
+            item.local_path = None;
+            item.progress = Arc::new(Progress::new());
+            return;
+        }
+        self.items.push(DownloadItem {
+            info,
+            entity,
+            update_of,
+            state: DownloadState::Queued,
+            error: None,
+            progress: Arc::new(Progress::new()),
+            local_path: None,
+            task: None,
+            extract_to: None,
+        });
+    }
+
+    pub fn pause(&mut self, id: i32) {
+        let Some(item) = self.get_mut(id) else { return };
+        match item.state {
+            DownloadState::Running => {
+                let _ = item
+                    .progress
+                    .control
+                    .compare_exchange(CONTROL_RUN, CONTROL_PAUSE, Ordering::AcqRel, Ordering::Acquire);
+            }
+            DownloadState::Queued => {
+                item.state = DownloadState::Paused;
+                item.progress.set_status(tl!("status-paused"));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn resume(&mut self, id: i32) {
+        let Some(item) = self.get_mut(id) else { return };
+        if matches!(item.state, DownloadState::Paused | DownloadState::Failed) {
+            item.state = DownloadState::Queued;
+            item.error = None;
+            item.progress.control.store(CONTROL_RUN, Ordering::Relaxed);
+            item.progress.set_status(tl!("status-queued"));
+        }
+    }
+
+    // stops the download and forgets about it, partial data included
+    pub fn cancel(&mut self, id: i32) {
+        let Some(pos) = self.items.iter().position(|it| it.id() == id) else { return };
+        let item = &self.items[pos];
+        if item.task.is_some()
+            && item
+                .progress
+                .control
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |it| (it != CONTROL_COMMIT).then_some(CONTROL_CANCEL))
+                .is_err()
+        {
+            // too late, the chart is already replacing what's on disk
+            return;
+        }
+        let item = self.items.remove(pos);
+        let partial = partial_path(id).ok();
+        let extract_to = item.extract_to;
+        let Some(task) = item.task else {
+            if let Some(partial) = partial {
+                let _ = std::fs::remove_file(partial);
+            }
+            return;
+        };
+        task.abort();
+        // the task only stops at its next await and may still have the files open until then
+        tokio::spawn(async move {
+            while !task.finished() {
+                tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+            }
+            if let Some(partial) = partial {
+                let _ = tokio::fs::remove_file(partial).await;
+            }
+            if let Some(extract_to) = extract_to {
+                let _ = tokio::fs::remove_dir_all(extract_to).await;
+            }
+        });
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.items.retain(|it| it.state != DownloadState::Done);
+    }
+
+    fn start(item: &mut DownloadItem) -> Result<()> {
+        item.state = DownloadState::Running;
+        item.progress.control.store(CONTROL_RUN, Ordering::Relaxed);
+        let partial = partial_path(item.id())?;
+        let extract_to = format!("{}/{}", dir::downloaded_charts()?, Uuid::new_v4());
+        let charts_dir = dir::charts()?;
+        item.extract_to = Some(extract_to.clone());
+        item.task = Some(Task::new(download(item.entity.clone(), Arc::clone(&item.progress), partial, extract_to, charts_dir)));
+        Ok(())
+    }
+
+    fn finish(item: &mut DownloadItem, chart: LocalChart) -> Result<()> {
+        item.info = chart.info.clone();
+        item.local_path = Some(item.update_of.clone().unwrap_or_else(|| chart.local_path.clone()));
+        let data = get_data_mut();
+        if let Some(index) = item.update_of.as_deref().and_then(|it| get_data().find_chart_by_path(it)) {
+            data.charts[index].info = chart.info;
+        } else if let Some(index) = get_data().find_chart_by_path(&chart.local_path) {
+            data.charts[index].info = chart.info;
+        } else {
+            data.charts.push(chart);
+        }
+        NEED_UPDATE.store(true, Ordering::Relaxed);
+        save_data()?;
+        achievement::check()
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        for item in &mut self.items {
+            let Some(task) = &mut item.task else { continue };
+            let Some(res) = task.take() else { continue };
+            item.task = None;
+            item.extract_to = None;
+            match res {
+                Ok(chart) => {
+                    Self::finish(item, chart)?;
+                    item.state = DownloadState::Done;
+                    item.progress.set_status(tl!("status-done"));
+                    show_message(tl!("success", "name" => item.info.name.as_str())).ok();
+                }
+                Err(_) if item.progress.control.load(Ordering::Relaxed) == CONTROL_PAUSE => {
+                    item.state = DownloadState::Paused;
+                    item.progress.set_status(tl!("status-paused"));
+                }
+                Err(err) => {
+                    item.state = DownloadState::Failed;
+                    item.error = Some(format!("{err:?}"));
+                    item.progress.set_status(tl!("status-failed"));
+                    show_error(err.context(tl!("failed", "name" => item.info.name.as_str())));
+                }
+            }
+        }
+        let mut running = self.items.iter().filter(|it| it.state == DownloadState::Running).count();
+        for item in &mut self.items {
+            if running >= MAX_PARALLEL {
+                break;
+            }
+            if item.state == DownloadState::Queued {
+                Self::start(item)?;
+                running += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn file_request(url: &str) -> Result<RequestBuilder> {
+    let req = basic_client_builder().build()?.get(url);
+    Ok(if let Some(token) = CLIENT_TOKEN.load().as_ref() {
+        req.header("Authorization", format!("Bearer {token}"))
+    } else {
+        req
+    })
+}
+
+pub fn prefetch_key(url: &str) -> String {
+    format!("prefetch:{url}")
+}
+
+pub fn update() -> Result<()> {
+    with_manager(|it| it.update())
+}
+
+// appends the remaining bytes of `url` to `partial`, picking up where a previous attempt stopped
+async fn fetch_partial(url: &str, partial: &Path, progress: &Progress) -> Result<()> {
+    let mut offset = tokio::fs::metadata(partial).await.map_or(0, |it| it.len());
+    let req = file_request(url)?;
+    let req = if offset > 0 { req.header(header::RANGE, format!("bytes={offset}-")) } else { req };
+    let res = req.send().await.with_context(|| tl!("request-failed"))?;
+    if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // nothing left to fetch
+        progress.total.store(offset, Ordering::Relaxed);
+        progress.downloaded.store(offset, Ordering::Relaxed);
+        return Ok(());
+    }
+    let res = res.error_for_status()?;
+    let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(partial).await?;
+    if res.status() != StatusCode::PARTIAL_CONTENT {
+        // the server ignored the range, start over
+        file.set_len(0).await?;
+        offset = 0;
+    }
+    progress.total.store(res.content_length().map_or(0, |it| it + offset), Ordering::Relaxed);
+    progress.downloaded.store(offset, Ordering::Relaxed);
+    let mut stream = res.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        if progress.stopped() {
+            file.flush().await?;
+            bail!("stopped");
+        }
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        progress.downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+async fn download(entity: Chart, progress: Arc<Progress>, partial: PathBuf, extract_to: String, charts_dir: String) -> Result<LocalChart> {
+    let key = prefetch_key(&entity.file.url);
+    if let Ok(data) = cacache::read(&*CACHE_DIR, &key).await {
+        tokio::fs::write(&partial, &data).await?;
+        let _ = cacache::remove(&*CACHE_DIR, &key).await;
+    } else {
+        progress.set_status(tl!("status-chart"));
+        fetch_partial(&entity.file.url, &partial, &progress).await?;
+    }
+
+    progress.set_status(tl!("status-extract"));
+    let extract_to = Path::new(&extract_to);
+    tokio::fs::create_dir(extract_to).await?;
+    let dir = phire::dir::Dir::new(extract_to)?;
+    let res = (|| -> Result<ChartInfo> {
+        unzip_into(std::io::BufReader::new(std::fs::File::open(&partial)?), &dir, false)?;
+        progress.set_status(tl!("status-saving"));
+        let mut info: ChartInfo = serde_yaml::from_reader(dir.open("info.yml")?)?;
+        info.id = Some(entity.id);
+        info.created = Some(entity.created);
+        info.updated = Some(entity.updated);
+        info.chart_updated = Some(entity.chart_updated);
+        info.uploader = Some(entity.uploader.id);
+        serde_yaml::to_writer(dir.create("info.yml")?, &info)?;
+        Ok(info)
+    })();
+    drop(dir);
+    // past this a cancel is refused, so it can't interrupt moving the chart into place
+    let res = res.and_then(|info| {
+        if progress
+            .control
+            .compare_exchange(CONTROL_RUN, CONTROL_COMMIT, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            bail!("stopped")
+        }
+        Ok(info)
+    });
+    let info = match res {
+        Ok(info) => info,
+        Err(err) => {
+            let _ = tokio::fs::remove_dir_all(extract_to).await;
+            if !progress.stopped() {
+                // a broken archive shouldn't be resumed
+                let _ = tokio::fs::remove_file(&partial).await;
+            }
+            return Err(err);
+        }
+    };
+
+    let local_path = format!("download/{}", entity.id);
+    let to_path = format!("{charts_dir}/{local_path}");
+    let to_path = Path::new(&to_path);
+    if to_path.exists() {
+        if to_path.is_file() {
+            tokio::fs::remove_file(to_path).await?;
+        } else {
+            tokio::fs::remove_dir_all(to_path).await?;
+        }
+    }
+    tokio::fs::rename(extract_to, to_path).await?;
+    let _ = tokio::fs::remove_file(&partial).await;
+    chart_index::update(&local_path, &info);
+
+    Ok(LocalChart {
+        info: entity.to_info(),
+        local_path,
+        record: None,
+        record_chart_hash: None,
+        mods: Mods::default(),
+    })
+}
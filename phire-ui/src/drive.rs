@@ -0,0 +1,220 @@
+// `drive <script> [--update]`: plays a scripted session against a fresh data folder, with synthetic touches, and
+// compares screenshots with the ones stored next to the script. `--update` stores them instead, a shot without a
+// stored reference fails.
+//
+// Script lines, `#` starting a comment:
+//   wait <seconds>
+//   tap <x> <y>
+//   drag <x0> <y0> <x1> <y1> <seconds>
+//   import <chart file, relative to the script>
+//   shot <name>
+// Positions are in UI coordinates, x in -1..1 and y growing downwards from the center, like the rects in the pages.
+
+use crate::{cloud, data::Data, download, scene::MainScene, set_data, sync_data, DATA_PATH};
+use anyhow::{bail, Context, Result};
+use image::{Rgba, RgbaImage};
+use macroquad::prelude::*;
+use phire::{
+    core::init_assets,
+    ext::screenshot,
+    judge::inject_touch,
+    scene::return_file,
+    time::TimeManager,
+    ui::{FontArc, TextPainter},
+    Main,
+};
+use std::path::{Path, PathBuf};
+
+// a channel may be off by this much before the pixel counts as different, text antialiasing varies between drivers
+const CHANNEL_TOLERANCE: u8 = 8;
+// and this share of the pixels may differ before the shot fails
+const PIXEL_TOLERANCE: f64 = 0.005;
+const TOUCH_ID: u64 = 0x5c21;
+
+enum Step {
+    Wait(f64),
+    Tap(Vec2),
+    Drag(Vec2, Vec2, f64),
+    Import(PathBuf),
+    Shot(String),
+}
+
+fn parse(script: &str, base: &Path) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap();
+        let args: Vec<_> = words.collect();
+        let num = |i: usize| -> Result<f32> { Ok(args.get(i).context("missing argument")?.parse()?) };
+        let step = (|| -> Result<Step> {
+            Ok(match command {
+                "wait" => Step::Wait(num(0)? as f64),
+                "tap" => Step::Tap(vec2(num(0)?, num(1)?)),
+                "drag" => Step::Drag(vec2(num(0)?, num(1)?), vec2(num(2)?, num(3)?), num(4)? as f64),
+                "import" => Step::Import(base.join(args.first().context("missing path")?)),
+                "shot" => Step::Shot(args.first().context("missing name")?.to_string()),
+                _ => bail!("unknown command `{command}`"),
+            })
+        })()
+        .with_context(|| format!("line {}", index + 1))?;
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+// the inverse of the transform touches go through before reaching the UI
+fn to_screen(pos: Vec2) -> Vec2 {
+    let (w, h) = (screen_width(), screen_height());
+    vec2((pos.x + 1.) / 2. * w, (pos.y * w / h + 1.) / 2. * h)
+}
+
+fn touch(phase: TouchPhase, pos: Vec2) {
+    inject_touch(Touch {
+        id: TOUCH_ID,
+        phase,
+        position: to_screen(pos),
+        time: f64::NEG_INFINITY,
+    });
+}
+
+struct Driver {
+    main: Main,
+    painter: TextPainter,
+}
+
+impl Driver {
+    // runs a frame, returning what it drew if asked to
+    async fn frame(&mut self, capture: bool) -> Result<Option<RgbaImage>> {
+        self.main.update()?;
+        download::update()?;
+        cloud::update()?;
+        self.main.render(&mut self.painter)?;
+        let shot = capture.then(screenshot);
+        next_frame().await;
+        Ok(shot)
+    }
+
+    async fn wait(&mut self, seconds: f64) -> Result<()> {
+        let end = get_time() + seconds;
+        while get_time() < end {
+            self.frame(false).await?;
+        }
+        Ok(())
+    }
+}
+
+// share of the pixels that differ
+fn difference(expected: &RgbaImage, actual: &RgbaImage) -> f64 {
+    if expected.dimensions() != actual.dimensions() {
+        return 1.;
+    }
+    let close = |a: &Rgba<u8>, b: &Rgba<u8>| a.0.iter().zip(b.0.iter()).all(|(x, y)| x.abs_diff(*y) <= CHANNEL_TOLERANCE);
+    let differ = expected.pixels().zip(actual.pixels()).filter(|(a, b)| !close(a, b)).count();
+    differ as f64 / (actual.width() * actual.height()).max(1) as f64
+}
+
+async fn run(script: &Path, update: bool) -> Result<bool> {
+    let base = script.parent().unwrap_or(Path::new("."));
+    let steps = parse(&std::fs::read_to_string(script).with_context(|| format!("failed to read {}", script.display()))?, base)?;
+
+    init_assets();
+    let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build()?;
+    let _guard = rt.enter();
+
+    // a fresh folder every run, so that nothing from an earlier session shows up in the shots
+    let data_dir = std::env::temp_dir().join(format!("phira-drive-{}", std::process::id()));
+    std::fs::create_dir_all(&data_dir)?;
+    *DATA_PATH.lock().unwrap() = Some(data_dir.to_string_lossy().into_owned());
+    let mut data = Data::default();
+    data.language = Some("en-US".to_owned());
+    data.config.offline_mode = true;
+    data.init().await?;
+    set_data(data);
+    sync_data();
+
+    let mut driver = Driver {
+        painter: TextPainter::new(FontArc::try_from_vec(load_file("font.ttf").await?)?),
+        main: Main::new(Box::new(MainScene::new().await?), TimeManager::default(), None).await?,
+    };
+    let mut passed = true;
+    for step in steps {
+        match step {
+            Step::Wait(seconds) => driver.wait(seconds).await?,
+            Step::Tap(pos) => {
+                touch(TouchPhase::Started, pos);
+                driver.frame(false).await?;
+                touch(TouchPhase::Ended, pos);
+                driver.frame(false).await?;
+            }
+            Step::Drag(from, to, seconds) => {
+                touch(TouchPhase::Started, from);
+                driver.frame(false).await?;
+                let start = get_time();
+                loop {
+                    let p = ((get_time() - start) / seconds).min(1.) as f32;
+                    touch(TouchPhase::Moved, from.lerp(to, p));
+                    driver.frame(false).await?;
+                    if p >= 1. {
+                        break;
+                    }
+                }
+                touch(TouchPhase::Ended, to);
+                driver.frame(false).await?;
+            }
+            // what the file picker hands over when a chart is chosen from the library
+            Step::Import(path) => return_file("_import".to_owned(), path.to_string_lossy().into_owned()),
+            Step::Shot(name) => {
+                let actual = driver.frame(true).await?.unwrap();
+                let path = base.join(format!("{name}.png"));
+                if update {
+                    actual.save(&path)?;
+                    println!("{name}: stored");
+                    continue;
+                }
+                if !path.exists() {
+                    passed = false;
+                    let out = base.join(format!("{name}.actual.png"));
+                    actual.save(&out)?;
+                    println!("{name}: no reference shot, check {} and run with --update to keep it", out.display());
+                    continue;
+                }
+                let expected = image::open(&path)?.into_rgba8();
+                let diff = difference(&expected, &actual);
+                if diff > PIXEL_TOLERANCE {
+                    passed = false;
+                    let out = base.join(format!("{name}.actual.png"));
+                    actual.save(&out)?;
+                    println!("{name}: {:.2}% of the pixels differ, see {}", diff * 100., out.display());
+                } else {
+                    println!("{name}: ok");
+                }
+            }
+        }
+    }
+    std::fs::remove_dir_all(&data_dir).ok();
+    Ok(passed)
+}
+
+pub fn main(args: Vec<String>) {
+    macroquad::Window::from_config(phire::build_conf(), async move {
+        let code = match args.first() {
+            Some(script) => match run(Path::new(script), args.iter().any(|it| it == "--update")).await {
+                Ok(true) => 0,
+                Ok(false) => 2,
+                Err(err) => {
+                    eprintln!("Error: {err:?}");
+                    1
+                }
+            },
+            None => {
+                eprintln!("usage: drive <script> [--update]");
+                1
+            }
+        };
+        std::process::exit(code);
+    });
+}
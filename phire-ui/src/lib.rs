@@ -3,9 +3,17 @@ phire::tl_file!("common" ttl crate::);
 #[cfg(feature = "closed")]
 mod inner;
 
+mod achievement;
+mod audio_preview;
+mod badge;
+mod banner;
+mod chart_index;
 mod charts_view;
 mod client;
+mod cloud;
 mod data;
+mod download;
+mod drive;
 mod icons;
 mod images;
 mod login;
@@ -13,9 +21,14 @@ mod mp;
 mod page;
 mod popup;
 mod rate;
+mod rks;
+mod safe_mode;
 mod scene;
 mod tags;
+mod theme;
+mod translate;
 mod uml;
+mod upload;
 
 use anyhow::Result;
 use data::Data;
@@ -23,24 +36,27 @@ use macroquad::prelude::*;
 use phire::{
     build_conf,
     core::init_assets,
+    ext::precise_sleep,
     l10n::{set_prefered_locale, GLOBAL, LANGS},
     log,
-    scene::{show_error, show_message},
+    scene::show_message,
     time::TimeManager,
-    ui::{FontArc, TextPainter},
+    ui::{perf_overlay_enabled, set_memory_overlay, set_perf_overlay, FontArc, TextPainter},
     gyro::{GYRO, GyroData},
     Main,
 };
 use scene::MainScene;
 use std::{collections::VecDeque, sync::{mpsc, Mutex}, time::Instant};
 use nalgebra::{UnitQuaternion, Vector3};
-use tracing::{error, debug, info};
+use tracing::{error, debug, info, warn};
 
 static ACTIVITY_LIFECYCLE: Mutex<Option<mpsc::Sender<bool>>> = Mutex::new(None);
 static ACTIVITY_FOUCUS: Mutex<Option<mpsc::Sender<bool>>> = Mutex::new(None);
 static ANTI_ADDICTION_CALLBACK: Mutex<Option<mpsc::Sender<i32>>> = Mutex::new(None);
 static DATA_PATH: Mutex<Option<String>> = Mutex::new(None);
 static CACHE_DIR: Mutex<Option<String>> = Mutex::new(None);
+// frame time of the menus while left alone
+const IDLE_FRAME_TIME: f64 = 1. / 10.;
 pub static mut DATA: Option<Data> = None;
 
 #[cfg(feature = "closed")]
@@ -55,6 +71,8 @@ pub fn sync_data() {
         get_data_mut().language = Some(LANGS[GLOBAL.order.lock().unwrap()[0]].to_owned());
     }
     let _ = client::set_access_token_sync(get_data().tokens.as_ref().map(|it| &*it.0));
+    set_perf_overlay(get_data().config.perf_overlay);
+    set_memory_overlay(get_data().config.memory_overlay);
 }
 
 pub fn set_data(data: Data) {
@@ -72,6 +90,7 @@ pub fn get_data_mut() -> &'static mut Data {
 }
 
 pub fn save_data() -> Result<()> {
+    get_data_mut().touch_sync()?;
     std::fs::write(format!("{}/data.json", dir::root()?), serde_json::to_string(get_data())?)?;
     Ok(())
 }
@@ -121,6 +140,22 @@ mod dir {
     pub fn respacks() -> Result<String> {
         ensure("data/respack")
     }
+
+    pub fn theme() -> Result<String> {
+        ensure("data/theme")
+    }
+
+    pub fn logs() -> Result<String> {
+        ensure("data/logs")
+    }
+
+    pub fn screenshots() -> Result<String> {
+        ensure("data/screenshots")
+    }
+
+    pub fn deltas() -> Result<String> {
+        ensure("data/deltas")
+    }
 }
 
 async fn the_main() -> Result<()> {
@@ -153,6 +188,10 @@ async fn the_main() -> Result<()> {
         *CACHE_DIR.lock().unwrap() = Some("Caches".to_owned());
     }
 
+    if let Err(err) = dir::logs().and_then(|dir| log::log_to_file(&dir)) {
+        warn!("failed to open log file: {err:?}");
+    }
+
     let dir = dir::root()?;
     let mut data: Data = std::fs::read_to_string(format!("{dir}/data.json"))
         .map_err(anyhow::Error::new)
@@ -161,6 +200,37 @@ async fn the_main() -> Result<()> {
     data.init().await?;
     set_data(data);
     sync_data();
+    safe_mode::check();
+    if let Err(err) = theme::load_theme() {
+        error!("failed to load theme: {err:?}");
+    }
+    if get_data().cloud_sync {
+        cloud::start_sync(false);
+    }
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    if get_data().config.live_stats {
+        if let Err(err) = phire::live::start(get_data().config.live_stats_port) {
+            error!("failed to start live stats server: {err:?}");
+        }
+    }
+
+    match get_data().latency_probe {
+        Some(latency) => info!("audio latency probed on first run: {:.1}ms", latency * 1000.),
+        None => match phire::ext::probe_latency(&get_data().config).await {
+            Ok(latency) => {
+                let latency = latency as f32;
+                info!("audio latency probe: {:.1}ms", latency * 1000.);
+                let data = get_data_mut();
+                data.latency_probe = Some(latency);
+                // only fill in the offset if the player hasn't calibrated it already
+                if data.config.offset == 0. {
+                    data.config.offset = latency;
+                }
+                save_data()?;
+            }
+            Err(err) => error!("audio latency probe failed: {err:?}"),
+        },
+    }
 
     let activity_lifecycle = {
         let (tx, rx) = mpsc::channel();
@@ -189,7 +259,15 @@ async fn the_main() -> Result<()> {
         anti_addiction_action("startup", Some(format!("Phigros-{}", me.id)));
     }
 
-    let font = FontArc::try_from_vec(load_file("font.ttf").await?)?;
+    let font = match theme::theme_font().map(FontArc::try_from_vec) {
+        Some(Ok(font)) => font,
+        other => {
+            if let Some(Err(err)) = other {
+                error!("invalid theme font: {err:?}");
+            }
+            FontArc::try_from_vec(load_file("font.ttf").await?)?
+        }
+    };
     let mut painter = TextPainter::new(font);
 
     let mut main = Main::new(Box::new(MainScene::new().await?), TimeManager::default(), None).await?;
@@ -202,12 +280,25 @@ async fn the_main() -> Result<()> {
 
     let mut exit_time = f64::INFINITY;
 
+    // closing the window has to go through the end of the loop, or it'd look like a crash next time
+    prevent_quit();
+
     'app: loop {
         let frame_start = tm.real_time();
         let res = || -> Result<()> {
             main.update()?;
+            download::update()?;
+            cloud::update()?;
+            upload::update()?;
             main.render(&mut painter)?;
+            // the overlay can also be switched from the pause menu
+            if perf_overlay_enabled() != get_data().config.perf_overlay {
+                get_data_mut().config.perf_overlay = perf_overlay_enabled();
+                save_data()?;
+            }
             if let Ok(paused) = activity_lifecycle.try_recv() {
+                // the system may kill us any time in the background, which isn't a crash
+                safe_mode::mark_running(!paused);
                 if paused {
                     main.pause()?;
                 } else {
@@ -225,9 +316,9 @@ async fn the_main() -> Result<()> {
         if let Err(err) = res {
             //error!("uncaught error: {err:?}");
             eprint!("uncaught error: {err:?}");
-            show_error(err);
+            main.show_error(err);
         }
-        if main.should_exit() {
+        if main.should_exit() || is_quit_requested() {
             break 'app;
         }
 
@@ -283,6 +374,13 @@ async fn the_main() -> Result<()> {
         }
 
         next_frame().await;
+        // nothing moves on screen while idle, so there's no point drawing at full rate
+        let max_fps = get_data().config.max_fps;
+        let frame_time = if main.idle() { IDLE_FRAME_TIME } else { 0. }.max(if max_fps > 0 { 1. / max_fps as f64 } else { 0. });
+        let rest = frame_time - (tm.real_time() - frame_start);
+        if rest > 0. {
+            precise_sleep(rest);
+        }
         #[cfg(not(feature = "play"))]
         let flash_end = tm.real_time();
 
@@ -302,25 +400,92 @@ async fn the_main() -> Result<()> {
             info!("| AVG: {}|{} NOW: {}|{}, MIN: {}", real_fps, avg_fps, real_now_fps, now_fps, min_fps);
         }
     }
+    safe_mode::mark_running(false);
     Ok(())
 }
 
 fn show_and_exit(msg: &str) {
     phire::ui::Dialog::simple(msg)
         .buttons(vec!["确定".to_owned()])
-        .listener(|_| std::process::exit(0))
+        .listener(|_| {
+            safe_mode::mark_running(false);
+            std::process::exit(0)
+        })
         .show();
 }
 
+// the swap interval is fixed when the window is created, so the saved config is peeked at before anything else
+fn window_conf() -> macroquad::window::Conf {
+    let mut conf = build_conf();
+    let vsync = dir::root()
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(format!("{dir}/data.json")).ok())
+        .and_then(|s| serde_json::from_str::<Data>(&s).ok())
+        .map_or(true, |it| it.config.vsync);
+    conf.platform.swap_interval = Some(vsync as i32);
+    conf
+}
+
 #[no_mangle]
 pub extern "C" fn quad_main() {
-    macroquad::Window::from_config(build_conf(), async {
+    macroquad::Window::from_config(window_conf(), async {
         if let Err(err) = the_main().await {
             error!("Error: {:?}", err);
         }
     });
 }
 
+// `trace <chart> [output]`: prints the autoplay judgement trace of a chart folder or zip as json and exits,
+// with a non-zero code if the chart can't be loaded or doesn't reach the full score. Runs without a window
+pub fn trace_main(args: Vec<String>) {
+    let res = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|rt| rt.block_on(trace_chart(&args)));
+    let code = match res {
+        Ok(true) => 0,
+        Ok(false) => 2,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            1
+        }
+    };
+    std::process::exit(code);
+}
+
+pub fn drive_main(args: Vec<String>) {
+    drive::main(args);
+}
+
+async fn trace_chart(args: &[String]) -> Result<bool> {
+    use anyhow::Context;
+    use phire::{
+        config::Config,
+        fs,
+        judge::trace::{autoplay_report, load_chart},
+    };
+
+    let Some(path) = args.first() else {
+        anyhow::bail!("usage: trace <chart> [output]");
+    };
+    let mut fs = fs::fs_from_file(std::path::Path::new(path))?;
+    let info = fs::load_info(fs.as_mut()).await?;
+    let config = Config {
+        render_extra: false,
+        ..Default::default()
+    };
+    let mut chart = load_chart(fs.as_mut(), &info, &config).await?;
+    let report = autoplay_report(&mut chart, &config);
+    let json = serde_json::to_string_pretty(&report)?;
+    match args.get(1) {
+        Some(output) => std::fs::write(output, json).with_context(|| format!("failed to write to {output}"))?,
+        None => println!("{json}"),
+    }
+    Ok(report.full_score)
+}
+
 fn on_pause_resume(pause: bool) {
     if let Some(tx) = ACTIVITY_LIFECYCLE.lock().unwrap().as_mut() {
         let _ = tx.send(pause);
@@ -418,6 +583,32 @@ pub unsafe extern "C" fn Java_quad_1native_QuadNative_markImportRespack(_: *mut
     CHOSEN_FILE.lock().unwrap().0 = Some("_import_respack".to_owned());
 }
 
+// called by the app delegate for a .pez or .zip shared to phire or opened from the Files app, e.g. from
+// `application:openURL:options:`; the file goes through the same import as the ones picked in the library.
+// The Xcode project lives outside this repository, so this is behind `ios-open-in` for the builds whose project
+// declares the document types and forwards the URLs; without that the system never offers phire for them
+#[cfg(all(target_os = "ios", feature = "ios-open-in"))]
+#[no_mangle]
+pub unsafe extern "C" fn phire_open_url(url: phire::objc::ObjcId) {
+    use phire::scene::{copy_shared_file, CHOSEN_FILE};
+
+    let Some(path) = copy_shared_file(url) else {
+        return;
+    };
+    let id = if is_respack(&path) { "_import_respack" } else { "_import" };
+    info!("opening shared file {path} as {id}");
+    *CHOSEN_FILE.lock().unwrap() = (Some(id.to_owned()), Some(path));
+}
+
+// both are zips with an info.yml, but only resource packs bring their own hit effect
+#[cfg(all(target_os = "ios", feature = "ios-open-in"))]
+fn is_respack(path: &str) -> bool {
+    std::fs::File::open(path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(std::io::BufReader::new(file)).ok())
+        .map_or(false, |mut zip| zip.by_name("hit_fx.png").is_ok())
+}
+
 #[cfg(target_os = "android")]
 #[no_mangle]
 pub unsafe extern "C" fn Java_quad_1native_QuadNative_setInputText(_: *mut std::ffi::c_void, _: *const std::ffi::c_void, text: ndk_sys::jstring) {
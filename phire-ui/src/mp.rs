@@ -2,3 +2,6 @@ phire::tl_file!("multiplayer" mtl);
 
 mod panel;
 pub use panel::MPPanel;
+
+mod spectate;
+pub use spectate::SpectateBoard;
@@ -1,5 +1,6 @@
 use super::mtl;
 use crate::{
+    badge::{badge_color, badge_label},
     client::{Chart, Ptr, UserManager},
     dir, get_data,
     mp::L10N_LOCAL,
@@ -74,6 +75,7 @@ pub struct MPPanel {
     create_room_task: Option<Task<Result<()>>>,
     join_room_btn: DRectButton,
     join_room_task: Option<Task<Result<RoomState>>>,
+    spectate_room_btn: DRectButton,
     leave_room_btn: DRectButton,
 
     disconnect_btn: DRectButton,
@@ -99,6 +101,8 @@ pub struct MPPanel {
     game_start_consumed: bool,
     need_upload: bool,
     entered: bool,
+    // joined as a monitor, the game is watched through autoplay with the players' live judgements
+    spectating: bool,
 
     next_scene: Option<NextScene>,
 
@@ -130,6 +134,7 @@ impl MPPanel {
             create_room_task: None,
             join_room_btn: DRectButton::new(),
             join_room_task: None,
+            spectate_room_btn: DRectButton::new(),
             leave_room_btn: DRectButton::new(),
 
             disconnect_btn: DRectButton::new(),
@@ -154,6 +159,7 @@ impl MPPanel {
             game_start_consumed: false,
             need_upload: false,
             entered: false,
+            spectating: false,
 
             next_scene: None,
 
@@ -359,7 +365,7 @@ impl MPPanel {
                 }
                 if self.user_list_btn.touch(touch, t) {
                     self.user_list_p.goto(1., t, USER_LIST_TRANSIT);
-                    client.blocking_state().unwrap().users.keys().copied().for_each(UserManager::request);
+                    UserManager::request_many(client.blocking_state().unwrap().users.keys().copied());
                 }
             } else {
                 if self.create_room_btn.touch(touch, t) {
@@ -370,6 +376,10 @@ impl MPPanel {
                     request_input("join_room", "", mtl!("join-room"));
                     return true;
                 }
+                if self.spectate_room_btn.touch(touch, t) {
+                    request_input("spectate_room", "", mtl!("spectate-room"));
+                    return true;
+                }
                 if self.disconnect_btn.touch(touch, t) {
                     self.client = None;
                     self.msgs.clear();
@@ -461,14 +471,15 @@ impl MPPanel {
                     self.game_start_consumed = true;
                     let id = self.chart_id.unwrap();
                     RECORD_ID.store(-1, Ordering::Relaxed);
-                    self.need_upload = true;
+                    self.need_upload = !self.spectating;
                     self.entered = false;
                     self.scene_task = SongScene::global_launch(
                         Some(id),
                         &format!("download/{id}"),
-                        Mods::default(),
+                        if self.spectating { Mods::AUTOPLAY } else { Mods::default() },
                         GameMode::NoRetry,
                         self.client.as_ref().map(Arc::clone),
+                        self.spectating,
                     )?;
                 }
             } else {
@@ -477,6 +488,9 @@ impl MPPanel {
             if let Some(RoomState::SelectChart(chart)) = state {
                 self.chart_id = chart;
             }
+            if state.is_none() && self.join_room_task.is_none() {
+                self.spectating = false;
+            }
         }
         if let Some(task) = &mut self.connect_task {
             if let Some(res) = task.take() {
@@ -573,6 +587,7 @@ impl MPPanel {
             if let Some(res) = task.take() {
                 match res {
                     Err(err) => {
+                        self.spectating = false;
                         show_error(err.context(mtl!("join-room-failed")));
                     }
                     Ok(state) => {
@@ -593,11 +608,13 @@ impl MPPanel {
                 "room_id" => {
                     self.create_room(text.try_into().with_context(|| mtl!("create-invalid-id"))?);
                 }
-                "join_room" => {
+                "join_room" | "spectate_room" => {
                     let client = self.clone_client();
+                    let monitor = id == "spectate_room";
                     if let Ok(id) = text.try_into() {
+                        self.spectating = monitor;
                         self.join_room_task = Some(Task::new(async move {
-                            client.join_room(id, false).await?;
+                            client.join_room(id, monitor).await?;
                             client.room_state().await.ok_or_else(|| anyhow!("expected room state"))
                         }));
                     } else {
@@ -655,6 +672,14 @@ impl MPPanel {
                         .size(0.44)
                         .color(semi_white(0.4))
                         .draw();
+                    if self.spectating {
+                        ui.text(mtl!("spectating"))
+                            .pos(r.right() - 0.02, r.y + 0.07)
+                            .anchor(1., 0.)
+                            .size(0.44)
+                            .color(semi_white(0.4))
+                            .draw();
+                    }
                 }
                 let tr = ui.text(mtl!("multiplayer")).pos(0.05, 0.05).draw();
                 let r = Rect::new(r.x, tr.bottom(), r.w, r.bottom() - tr.bottom()).feather(-0.02);
@@ -754,6 +779,7 @@ impl MPPanel {
         } else {
             btns.push((&mut self.create_room_btn, mtl!("create-room").into_owned()));
             btns.push((&mut self.join_room_btn, mtl!("join-room").into_owned()));
+            btns.push((&mut self.spectate_room_btn, mtl!("spectate-room").into_owned()));
             btns.push((&mut self.disconnect_btn, mtl!("disconnect").into_owned()));
         }
         for (btn, text) in btns {
@@ -783,13 +809,20 @@ impl MPPanel {
                         let r = Rect::new(j as f32 * w + o, i as f32 * h, w, h);
                         let Some(user) =  iter.next() else { unreachable!() };
                         ui.avatar(r.x + 0.07, r.center().y, 0.05, c, t, UserManager::opt_avatar(user.id, &self.icon_user));
-                        ui.text(user.name)
+                        let badge = UserManager::badge(user.id);
+                        let bw = badge.as_ref().map_or(0., |it| ui.badge_width(&badge_label(it), 0.4) + 0.02);
+                        let nr = ui
+                            .text(user.name)
                             .pos(r.x + 0.14, r.center().y)
                             .anchor(0., 0.5)
                             .no_baseline()
+                            .max_width(r.w - 0.16 - bw)
                             .size(0.7)
                             .color(c)
                             .draw();
+                        if let Some(badge) = badge {
+                            ui.badge(&badge_label(&badge), nr.right() + 0.02, r.center().y, 0.4, Color { a: c.a, ..badge_color(&badge) });
+                        }
                     }
                 }
             });
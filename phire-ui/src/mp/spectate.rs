@@ -0,0 +1,130 @@
+use super::{mtl, L10N_LOCAL};
+use macroquad::prelude::*;
+use phira_mp_client::Client;
+use phira_mp_common::Judgement;
+use phire::{
+    ext::{semi_black, semi_white, RectExt},
+    judge::Judge,
+    ui::Ui,
+};
+
+struct PlayerStat {
+    id: i32,
+    name: String,
+    counts: [u32; 4],
+    combo: u32,
+    max_combo: u32,
+}
+
+impl PlayerStat {
+    fn commit(&mut self, judgement: Judgement) {
+        let index = match judgement {
+            Judgement::Perfect => 0,
+            Judgement::Good => 1,
+            Judgement::Bad => 2,
+            Judgement::Miss => 3,
+            // hold heads, the final judgement comes when the hold ends
+            Judgement::HoldPerfect | Judgement::HoldGood => return,
+        };
+        self.counts[index] += 1;
+        if index < 2 {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo);
+        } else {
+            self.combo = 0;
+        }
+    }
+
+    fn accuracy(&self, num_of_notes: u32) -> f64 {
+        (self.counts[0] as f64 + self.counts[1] as f64 * 0.65) / num_of_notes.max(1) as f64
+    }
+
+    fn score(&self, num_of_notes: u32) -> u32 {
+        if num_of_notes != 0 && self.counts[0] == num_of_notes {
+            return 1_000_000;
+        }
+        ((0.9 * self.accuracy(num_of_notes) + self.max_combo as f64 / num_of_notes.max(1) as f64 * 0.1) * 1_000_000.).round() as u32
+    }
+}
+
+// realtime standings of the players in the room, fed by the live judge events the server relays to monitors
+pub struct SpectateBoard {
+    players: Vec<PlayerStat>,
+    num_of_notes: u32,
+}
+
+impl SpectateBoard {
+    pub fn new(client: &Client) -> Self {
+        let players = client
+            .blocking_state()
+            .map(|state| {
+                state
+                    .users
+                    .values()
+                    .filter(|it| !it.monitor)
+                    .map(|it| PlayerStat {
+                        id: it.id,
+                        name: it.name.clone(),
+                        counts: [0; 4],
+                        combo: 0,
+                        max_combo: 0,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { players, num_of_notes: 0 }
+    }
+
+    pub fn update(&mut self, client: &Client, judge: &Judge) {
+        if self.num_of_notes == 0 {
+            self.num_of_notes = judge.result().num_of_notes;
+        }
+        for player in &mut self.players {
+            let live = client.live_player(player.id);
+            let events = std::mem::take(&mut *live.judge_events.blocking_lock());
+            for event in events {
+                player.commit(event.judgement);
+            }
+        }
+        let n = self.num_of_notes;
+        self.players.sort_by_key(|it| std::cmp::Reverse(it.score(n)));
+    }
+
+    pub fn render(&self, ui: &mut Ui, alpha: f32) {
+        let n = self.num_of_notes;
+        let c = semi_white(alpha);
+        let row = 0.08;
+        let r = Rect::new(-0.98, -row * (self.players.len() + 1) as f32 / 2., 0.5, row * (self.players.len() + 1) as f32);
+        ui.fill_path(&r.rounded(0.01), semi_black(0.4 * alpha));
+        ui.text(mtl!("spectate-leaderboard"))
+            .pos(r.x + 0.02, r.y + row / 2.)
+            .anchor(0., 0.5)
+            .no_baseline()
+            .size(0.45)
+            .color(semi_white(0.7 * alpha))
+            .draw();
+        for (i, player) in self.players.iter().enumerate() {
+            let cy = r.y + row * (i as f32 + 1.5);
+            ui.text(format!("#{} {}", i + 1, player.name))
+                .pos(r.x + 0.02, cy)
+                .anchor(0., 0.5)
+                .no_baseline()
+                .size(0.45)
+                .max_width(0.24)
+                .color(c)
+                .draw();
+            ui.text(format!("{:07}", player.score(n)))
+                .pos(r.right() - 0.02, cy - 0.005)
+                .anchor(1., 1.)
+                .size(0.45)
+                .color(c)
+                .draw();
+            ui.text(format!("{:.2}%  {}x", player.accuracy(n) * 100., player.combo))
+                .pos(r.right() - 0.02, cy)
+                .anchor(1., 0.)
+                .size(0.3)
+                .color(semi_white(0.7 * alpha))
+                .draw();
+        }
+    }
+}
@@ -1,12 +1,24 @@
+mod best;
+pub use best::BestPage;
+
+mod downloads;
+pub use downloads::DownloadsPage;
+
 mod event;
 pub use event::EventPage;
 
 mod home;
 pub use home::HomePage;
 
+mod hud_layout;
+pub use hud_layout::HudLayoutPage;
+
 mod library;
 pub use library::LibraryPage;
 
+mod lists;
+pub use lists::ListsPage;
+
 mod message;
 pub use message::MessagePage;
 
@@ -18,9 +30,16 @@ pub use respack::{ResPackItem, ResPackPage};
 
 mod settings;
 pub use settings::SettingsPage;
+
+mod storage;
+pub use storage::StoragePage;
+
+mod trophies;
+pub use trophies::TrophyPage;
 use tokio::sync::Notify;
 
 use crate::{
+    chart_index,
     client::File,
     data::BriefChartInfo,
     dir, get_data,
@@ -40,7 +59,7 @@ use phire::{
     ui::{FontArc, IntoShading, Shading, TextPainter, Ui},
 };
 use std::{
-    any::Any, borrow::Cow, ops::DerefMut, path::PathBuf, sync::{Arc, Mutex}
+    any::Any, borrow::Cow, future::Future, ops::DerefMut, path::PathBuf, sync::{Arc, Mutex}
 };
 use tracing::warn;
 
@@ -48,12 +67,17 @@ pub fn thumbnail_path(path: &str) -> Result<PathBuf> {
     Ok(format!("{}/{}", dir::cache_image_local()?, path.replace('/', "_")).into())
 }
 
-pub fn illustration_task(notify: Arc<Notify>, path: String) -> Task<Result<(DynamicImage, Option<DynamicImage>)>> {
+pub type IllustrationTask = Task<Result<(DynamicImage, Option<DynamicImage>)>>;
+
+pub fn illustration_task(notify: Arc<Notify>, path: String) -> IllustrationTask {
     Task::new(async move {
         notify.notified().await;
         let mut fs = fs_from_path(&path)?;
-        let info = fs::load_info(fs.deref_mut()).await?;
-        let image = image::load_from_memory(&fs.load_file(&info.illustration).await?)?;
+        let illustration = match chart_index::get(&path) {
+            Some(entry) => entry.illustration,
+            None => fs::load_info(fs.deref_mut()).await?.illustration,
+        };
+        let image = image::load_from_memory(&fs.load_file(&illustration).await?)?;
         let thumbnail = Images::local_or_else(thumbnail_path(&path)?, async { Ok(Images::thumbnail(&image)) }).await?;
         Ok((thumbnail, Some(image)))
     })
@@ -75,6 +99,8 @@ pub fn load_local(order: &(ChartOrder, bool)) -> Vec<ChartItem> {
                     task: Some(illustration_task(notify, it.local_path.clone())),
                     loaded: Arc::default(),
                     load_time: f32::NAN,
+                    reload: None,
+                    failed: false,
                 }
             },
         })
@@ -90,9 +116,12 @@ pub fn load_local(order: &(ChartOrder, bool)) -> Vec<ChartItem> {
 pub struct Illustration {
     pub texture: (SafeTexture, SafeTexture),
     pub notify: Arc<Notify>,
-    pub task: Option<Task<Result<(DynamicImage, Option<DynamicImage>)>>>,
+    pub task: Option<IllustrationTask>,
     pub loaded: Arc<Mutex<Option<(SafeTexture, SafeTexture)>>>,
     pub load_time: f32,
+    // starts the load over, for illustrations fetched over the network
+    pub reload: Option<Arc<dyn Fn() -> IllustrationTask + Send + Sync>>,
+    pub failed: bool,
 }
 
 impl Illustration {
@@ -109,6 +138,44 @@ impl Illustration {
             })),
             loaded: Arc::default(),
             load_time: f32::NAN,
+            reload: None,
+            failed: false,
+        }
+    }
+
+    // waits to be notified before loading like the others, but can be retried when loading fails
+    pub fn remote<F>(load: impl Fn() -> F + Send + Sync + 'static) -> Self
+    where
+        F: Future<Output = Result<DynamicImage>> + Send + 'static,
+    {
+        let load = Arc::new(load);
+        let notify = Arc::new(Notify::new());
+        Self {
+            texture: (BLACK_TEXTURE.clone(), BLACK_TEXTURE.clone()),
+            notify: Arc::clone(&notify),
+            task: Some(Task::new({
+                let load = Arc::clone(&load);
+                async move {
+                    notify.notified().await;
+                    Ok((load().await?, None))
+                }
+            })),
+            loaded: Arc::default(),
+            load_time: f32::NAN,
+            reload: Some(Arc::new(move || {
+                let future = load();
+                Task::new(async move { Ok((future.await?, None)) })
+            })),
+            failed: false,
+        }
+    }
+
+    pub fn retry(&mut self) {
+        if let Some(reload) = &self.reload {
+            *self.loaded.lock().unwrap() = None;
+            self.task = Some(reload());
+            self.failed = false;
+            self.load_time = f32::NAN;
         }
     }
 
@@ -122,6 +189,7 @@ impl Illustration {
                 match illu {
                     Err(err) => {
                         warn!("failed to load illustration: {:?}", err);
+                        self.failed = self.reload.is_some();
                     }
                     Ok(illu) => {
                         self.texture = Images::into_texture(illu);
@@ -423,4 +491,8 @@ pub trait Page {
     fn on_back_pressed(&mut self, _s: &mut SharedState) -> bool {
         false
     }
+    // false while something on the page moves on its own
+    fn idle(&self) -> bool {
+        true
+    }
 }
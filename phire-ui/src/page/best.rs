@@ -0,0 +1,189 @@
+phire::tl_file!("best");
+
+use super::{NextPage, Page, SharedState, TrophyPage};
+use crate::{
+    get_data,
+    rks::{RksEntry, RksSummary},
+    tags::level_label,
+};
+use anyhow::Result;
+use macroquad::prelude::*;
+use phire::{
+    ext::{semi_black, semi_white, RectExt},
+    ui::{theme, DRectButton, Scroll, Ui},
+};
+
+const ROW_HEIGHT: f32 = 0.1;
+const PHI_COLOR: Color = Color::new(1., 0.84, 0.3, 1.);
+
+pub struct BestPage {
+    summary: RksSummary,
+    scroll: Scroll,
+    trophy_btn: DRectButton,
+    next_page: Option<NextPage>,
+}
+
+impl BestPage {
+    pub fn new() -> Self {
+        Self {
+            summary: RksSummary::compute(&get_data().charts),
+            scroll: Scroll::new(),
+            trophy_btn: DRectButton::new(),
+            next_page: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.summary = RksSummary::compute(&get_data().charts);
+    }
+}
+
+fn render_entry(ui: &mut Ui, r: Rect, rank: String, entry: &RksEntry, c: Color) {
+    ui.fill_path(&r.rounded(0.01), semi_black(c.a * 0.3));
+    let cy = r.center().y;
+    let rank_color = if entry.all_perfect() { Color { a: c.a, ..PHI_COLOR } } else { semi_white(c.a * 0.7) };
+    ui.text(rank)
+        .pos(r.x + 0.02, cy)
+        .anchor(0., 0.5)
+        .no_baseline()
+        .size(0.5)
+        .color(rank_color)
+        .draw();
+    ui.text(&entry.name)
+        .pos(r.x + 0.12, cy - 0.004)
+        .anchor(0., 1.)
+        .size(0.5)
+        .max_width(r.w * 0.5)
+        .color(c)
+        .draw();
+    ui.text(format!("{}  ({:.1})", level_label(&entry.level), entry.difficulty))
+        .pos(r.x + 0.12, cy + 0.004)
+        .anchor(0., 0.)
+        .size(0.35)
+        .color(semi_white(c.a * 0.6))
+        .draw();
+    let rt = r.right() - 0.02;
+    ui.text(format!("{:.2}", entry.rks))
+        .pos(rt, cy)
+        .anchor(1., 0.5)
+        .no_baseline()
+        .size(0.6)
+        .color(c)
+        .draw();
+    let suffix = if entry.all_perfect() {
+        "  AP"
+    } else if entry.full_combo {
+        "  FC"
+    } else {
+        ""
+    };
+    ui.text(format!("{:07}  {:.2}%{suffix}", entry.score, entry.accuracy * 100.))
+        .pos(rt - 0.14, cy)
+        .anchor(1., 0.5)
+        .no_baseline()
+        .size(0.4)
+        .color(semi_white(c.a * 0.8))
+        .draw();
+}
+
+impl Page for BestPage {
+    fn label(&self) -> std::borrow::Cow<'static, str> {
+        "BEST".into()
+    }
+
+    fn enter(&mut self, _s: &mut SharedState) -> Result<()> {
+        self.refresh();
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        self.refresh();
+        Ok(())
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        if self.trophy_btn.touch(touch, s.t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(TrophyPage::new())));
+            return Ok(true);
+        }
+        Ok(self.scroll.touch(touch, s.t))
+    }
+
+    fn update(&mut self, s: &mut SharedState) -> Result<()> {
+        self.scroll.update(s.t);
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        let cr = ui.content_rect();
+        let d = 0.29;
+        let side = Rect::new(cr.x, cr.y, d - 0.02, cr.h);
+        let list = Rect::new(cr.x + d, cr.y, cr.w - d, cr.h);
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&side.rounded(0.02), theme().card(c.a));
+            let ct = side.center();
+            ui.text(tl!("rating"))
+                .pos(ct.x, side.y + 0.05)
+                .anchor(0.5, 0.)
+                .size(0.5)
+                .color(semi_white(c.a * 0.7))
+                .draw();
+            ui.text(format!("{:.2}", self.summary.rks))
+                .pos(ct.x, side.y + 0.12)
+                .anchor(0.5, 0.)
+                .size(1.2)
+                .color(c)
+                .draw();
+            let br = Rect::new(side.x + 0.03, side.y + 0.3, side.w - 0.06, 0.07);
+            self.trophy_btn.render_text(ui, br, t, c.a, tl!("trophies"), 0.45, false);
+            ui.text(tl!("hint"))
+                .pos(ct.x, side.bottom() - 0.04)
+                .anchor(0.5, 1.)
+                .size(0.35)
+                .max_width(side.w - 0.04)
+                .multiline()
+                .color(semi_white(c.a * 0.5))
+                .draw();
+        });
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&list.rounded(0.02), theme().card(c.a));
+            if self.summary.best.is_empty() {
+                let ct = list.center();
+                ui.text(tl!("empty")).pos(ct.x, ct.y).anchor(0.5, 0.5).no_baseline().size(0.6).color(c).draw();
+                return;
+            }
+            let pad = 0.02;
+            ui.scope(|ui| {
+                ui.dx(list.x + pad);
+                ui.dy(list.y + pad);
+                let w = list.w - pad * 2.;
+                self.scroll.size((w, list.h - pad * 2.));
+                self.scroll.render(ui, |ui| {
+                    let r = Rect::new(0., 0., w, ROW_HEIGHT);
+                    let mut h = 0.;
+                    for (index, entry) in self.summary.phi.iter().enumerate() {
+                        render_entry(ui, r, format!("P{}", index + 1), entry, c);
+                        ui.dy(ROW_HEIGHT + pad / 2.);
+                        h += ROW_HEIGHT + pad / 2.;
+                    }
+                    if !self.summary.phi.is_empty() {
+                        ui.dy(pad);
+                        h += pad;
+                    }
+                    for (index, entry) in self.summary.best.iter().enumerate() {
+                        render_entry(ui, r, format!("#{}", index + 1), entry, c);
+                        ui.dy(ROW_HEIGHT + pad / 2.);
+                        h += ROW_HEIGHT + pad / 2.;
+                    }
+                    (w, h)
+                });
+            });
+        });
+        Ok(())
+    }
+
+    fn next_page(&mut self) -> NextPage {
+        self.next_page.take().unwrap_or_default()
+    }
+}
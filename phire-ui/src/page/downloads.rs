@@ -0,0 +1,171 @@
+phire::tl_file!("download");
+
+use super::{Page, SharedState};
+use crate::download::{with_manager, DownloadState};
+use anyhow::Result;
+use macroquad::prelude::*;
+use phire::{
+    ext::{semi_black, semi_white, RectExt},
+    scene::show_message,
+    ui::{theme, DRectButton, Scroll, Ui},
+};
+
+const ROW_HEIGHT: f32 = 0.14;
+const BUTTON_WIDTH: f32 = 0.16;
+const FAILED_COLOR: Color = Color::new(1., 0.45, 0.45, 1.);
+
+struct RowButtons {
+    toggle: DRectButton,
+    remove: DRectButton,
+}
+
+pub struct DownloadsPage {
+    scroll: Scroll,
+    rows: Vec<RowButtons>,
+    clear_btn: DRectButton,
+}
+
+impl DownloadsPage {
+    pub fn new() -> Self {
+        Self {
+            scroll: Scroll::new(),
+            rows: Vec::new(),
+            clear_btn: DRectButton::new(),
+        }
+    }
+}
+
+impl Default for RowButtons {
+    fn default() -> Self {
+        Self {
+            toggle: DRectButton::new(),
+            remove: DRectButton::new(),
+        }
+    }
+}
+
+impl Page for DownloadsPage {
+    fn label(&self) -> std::borrow::Cow<'static, str> {
+        "DOWNLOADS".into()
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        let t = s.t;
+        if self.clear_btn.touch(touch, t) {
+            with_manager(|it| it.clear_finished());
+            return Ok(true);
+        }
+        if self.scroll.touch(touch, t) {
+            return Ok(true);
+        }
+        if !self.scroll.contains(touch) {
+            return Ok(false);
+        }
+        let ids: Vec<_> = with_manager(|it| it.items().iter().map(|it| (it.id(), it.state)).collect());
+        for ((id, state), row) in ids.into_iter().zip(self.rows.iter_mut()) {
+            if row.toggle.touch(touch, t) {
+                with_manager(|it| match state {
+                    DownloadState::Queued | DownloadState::Running => it.pause(id),
+                    DownloadState::Paused | DownloadState::Failed => it.resume(id),
+                    DownloadState::Done => {}
+                });
+                return Ok(true);
+            }
+            if row.remove.touch(touch, t) {
+                with_manager(|it| it.cancel(id));
+                if state != DownloadState::Done {
+                    show_message(tl!("cancelled")).ok();
+                }
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn update(&mut self, s: &mut SharedState) -> Result<()> {
+        self.scroll.update(s.t);
+        let len = with_manager(|it| it.items().len());
+        self.rows.resize_with(len, RowButtons::default);
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        let cr = ui.content_rect();
+        let bh = 0.07;
+        let clear_rect = Rect::new(cr.right() - 0.3, cr.bottom() - bh, 0.3, bh);
+        let cr = Rect { h: cr.h - bh - 0.02, ..cr };
+        s.render_fader(ui, |ui, c| {
+            self.clear_btn.render_text(ui, clear_rect, t, c.a, tl!("clear-finished"), 0.5, false);
+            ui.fill_path(&cr.rounded(0.02), theme().card(c.a));
+            with_manager(|manager| {
+                let items = manager.items();
+                if items.is_empty() {
+                    let ct = cr.center();
+                    ui.text(tl!("empty")).pos(ct.x, ct.y).anchor(0.5, 0.5).no_baseline().size(0.6).color(c).draw();
+                    return;
+                }
+                let pad = 0.02;
+                ui.scope(|ui| {
+                    ui.dx(cr.x + pad);
+                    ui.dy(cr.y + pad);
+                    let w = cr.w - pad * 2.;
+                    self.scroll.size((w, cr.h - pad * 2.));
+                    self.scroll.render(ui, |ui| {
+                        let mut h = 0.;
+                        for (item, row) in items.iter().zip(self.rows.iter_mut()) {
+                            let r = Rect::new(0., 0., w, ROW_HEIGHT);
+                            ui.fill_path(&r.rounded(0.01), semi_black(c.a * 0.3));
+                            ui.text(&item.info.name)
+                                .pos(r.x + 0.02, r.y + 0.02)
+                                .size(0.5)
+                                .max_width(w - BUTTON_WIDTH * 2. - 0.08)
+                                .color(c)
+                                .draw();
+                            let failed = item.state == DownloadState::Failed;
+                            let ratio = item.progress.ratio();
+                            let status = match (item.state, ratio, &item.error) {
+                                (DownloadState::Running, Some(ratio), _) => format!("{}  {:.0}%", item.progress.status(), ratio * 100.),
+                                (DownloadState::Failed, _, Some(error)) => format!("{}: {}", item.progress.status(), error.lines().next().unwrap_or_default()),
+                                _ => item.progress.status().into_owned(),
+                            };
+                            ui.text(status)
+                                .pos(r.x + 0.02, r.y + 0.075)
+                                .size(0.35)
+                                .max_width(w - BUTTON_WIDTH * 2. - 0.08)
+                                .color(if failed { Color { a: c.a, ..FAILED_COLOR } } else { semi_white(c.a * 0.7) })
+                                .draw();
+                            let bar = Rect::new(r.x + 0.02, r.bottom() - 0.02, w - BUTTON_WIDTH * 2. - 0.08, 0.006);
+                            ui.fill_rect(bar, semi_white(c.a * 0.2));
+                            let filled = match item.state {
+                                DownloadState::Done => 1.,
+                                _ => ratio.unwrap_or(0.),
+                            };
+                            ui.fill_rect(Rect { w: bar.w * filled, ..bar }, semi_white(c.a * 0.8));
+
+                            let br = Rect::new(r.right() - BUTTON_WIDTH - 0.02, r.center().y - 0.035, BUTTON_WIDTH, 0.07);
+                            let remove = if item.state == DownloadState::Done { tl!("remove") } else { tl!("cancel") };
+                            row.remove.render_text(ui, br, t, c.a, remove, 0.45, false);
+                            let br = Rect { x: br.x - BUTTON_WIDTH - 0.02, ..br };
+                            let toggle = match item.state {
+                                DownloadState::Queued | DownloadState::Running => Some(tl!("pause")),
+                                DownloadState::Paused => Some(tl!("resume")),
+                                DownloadState::Failed => Some(tl!("retry")),
+                                DownloadState::Done => None,
+                            };
+                            if let Some(toggle) = toggle {
+                                row.toggle.render_text(ui, br, t, c.a, toggle, 0.45, false);
+                            } else {
+                                row.toggle.invalidate();
+                            }
+                            ui.dy(ROW_HEIGHT + pad / 2.);
+                            h += ROW_HEIGHT + pad / 2.;
+                        }
+                        (w, h)
+                    });
+                });
+            });
+        });
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use super::{Illustration, Page, SharedState};
 use crate::{
-    client::{Client, Event},
+    client::{Client, Event, Paginator},
     icons::Icons,
     scene::EventScene,
 };
@@ -11,13 +11,13 @@ use phire::{
     core::Tweenable,
     ext::{semi_black, semi_white, RectExt, SafeTexture, ScaleType},
     scene::{show_error, NextScene},
-    task::Task,
     ui::{button_hit_large, DRectButton, RectButton, Scroll, Ui},
 };
 use std::{borrow::Cow, sync::Arc};
 
 const TRANSIT_TIME: f32 = 0.5;
 const ILLU_FEATHER: f32 = 0.4;
+const PAGE_NUM: u64 = 10;
 
 struct Item {
     event: Event,
@@ -37,7 +37,7 @@ impl Item {
 }
 
 pub struct EventPage {
-    fetch_task: Option<Task<Result<Vec<Event>>>>,
+    paginator: Paginator<Event>,
     scroll: Scroll,
     events: Option<Vec<Item>>,
     index: usize,
@@ -60,8 +60,10 @@ impl EventPage {
     pub const LB_PAD: f32 = 0.05;
 
     pub fn new(icons: Arc<Icons>, rank_icons: [SafeTexture; 8]) -> Self {
+        let mut paginator = Paginator::new(Client::query(), PAGE_NUM);
+        paginator.load_more();
         Self {
-            fetch_task: Some(Task::new(async move { Ok(Client::query().send().await?.0) })),
+            paginator,
             scroll: Scroll::new(),
             events: None,
             index: 0,
@@ -82,7 +84,7 @@ impl EventPage {
     }
 
     fn loading(&self) -> bool {
-        self.fetch_task.is_some()
+        self.events.is_none() && self.paginator.loading()
     }
 }
 
@@ -139,20 +141,21 @@ impl Page for EventPage {
                 item.illu.settle(t);
             }
         }
-        if let Some(task) = &mut self.fetch_task {
-            if let Some(res) = task.take() {
-                match res {
-                    Err(err) => {
-                        use crate::scene::event::{tl, L10N_LOCAL};
-                        show_error(err.context(tl!("load-list-failed")));
-                    }
-                    Ok(val) => {
-                        self.events = Some(val.into_iter().map(Item::new).collect());
-                    }
+        if let Some(res) = self.paginator.poll() {
+            match res {
+                Err(err) => {
+                    use crate::scene::event::{tl, L10N_LOCAL};
+                    show_error(err.context(tl!("load-list-failed")));
+                }
+                Ok(new) => {
+                    self.events.get_or_insert_with(Vec::new).extend(new.iter().cloned().map(Item::new));
                 }
-                self.fetch_task = None;
             }
         }
+        // fetch the next page before the last loaded event comes into view
+        if self.events.as_ref().map_or(false, |it| self.index + 2 >= it.len()) {
+            self.paginator.load_more();
+        }
         Ok(())
     }
 
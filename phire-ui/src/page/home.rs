@@ -2,26 +2,27 @@ phire::tl_file!("home");
 
 use std::{sync::Arc};
 
-use super::{LibraryPage, NextPage, Page, ResPackPage, SFader, SettingsPage, SharedState};
+use super::{BestPage, DownloadsPage, EventPage, LibraryPage, ListsPage, NextPage, Page, ResPackPage, SFader, SettingsPage, SharedState, TrophyPage};
 use crate::{
-    client::{recv_raw, Client, LoginParams, User, UserManager},
+    client::{recv_raw, recv_with, Client, LoginParams, Priority, User, UserManager},
     dir, get_data, get_data_mut,
     icons::Icons,
     login::Login,
     save_data,
     scene::ProfileScene,
     sync_data,
+    uml::{parse_uml, Uml},
 };
 use ::rand::{random, rng, Rng};
 use anyhow::Result;
 use image::DynamicImage;
 use macroquad::prelude::*;
 use phire::{
-    ext::{semi_black, semi_white, RectExt, SafeTexture, ScaleType},
+    ext::{open_url, semi_white, RectExt, SafeTexture, ScaleType},
     info::ChartInfo,
     scene::{show_error, NextScene},
     task::Task,
-    ui::{button_hit_large, rounded_rect, DRectButton, Ui},
+    ui::{button_hit_large, rounded_rect, theme, DRectButton, Ui},
 };
 use serde::Deserialize;
 use tracing::warn;
@@ -29,6 +30,11 @@ use tracing::warn;
 const BOARD_SWITCH_TIME: f32 = 4.;
 const BOARD_TRANSIT_TIME: f32 = 1.2;
 
+// the last layout the server sent, shown while offline or before the new one arrives
+fn layout_cache_path() -> Result<String> {
+    Ok(format!("{}/home.uml", dir::cache()?))
+}
+
 pub struct HomePage {
     character: SafeTexture,
     icons: Arc<Icons>,
@@ -38,6 +44,9 @@ pub struct HomePage {
     btn_respack: DRectButton,
     // btn_msg: DRectButton,
     btn_settings: DRectButton,
+    btn_best: DRectButton,
+    btn_downloads: DRectButton,
+    btn_lists: DRectButton,
     btn_user: DRectButton,
 
     next_page: Option<NextPage>,
@@ -57,6 +66,11 @@ pub struct HomePage {
 
     has_new_task: Option<Task<Result<bool>>>,
     has_new: bool,
+
+    // banners, shelves and event cards laid out by the server on top of the built-in buttons
+    layout_task: Option<Task<Result<String>>>,
+    layout_source: Option<String>,
+    layout: Option<Uml>,
 }
 
 impl HomePage {
@@ -76,6 +90,17 @@ impl HomePage {
         } else {
             None
         };
+        let layout_task = if get_data().config.offline_mode {
+            None
+        } else {
+            Some(Task::new(async {
+                Ok(recv_raw(Client::get("/home/uml").query(&[("version", env!("CARGO_PKG_VERSION"))]))
+                    .await?
+                    .text()
+                    .await?)
+            }))
+        };
+        let layout_source = std::fs::read_to_string(layout_cache_path()?).ok();
         Ok(Self {
             character,
             icons: Arc::new(Icons::new().await?),
@@ -85,6 +110,9 @@ impl HomePage {
             btn_respack: DRectButton::new().with_radius(0.00).with_delta(-0.003).with_elevation(0.000).no_sound(),
             // btn_msg: DRectButton::new().with_radius(0.03).with_delta(-0.003).with_elevation(0.002),
             btn_settings: DRectButton::new().with_radius(0.00).with_delta(-0.003).with_elevation(0.000),
+            btn_best: DRectButton::new().with_radius(0.00).with_delta(-0.003).with_elevation(0.000),
+            btn_downloads: DRectButton::new().with_radius(0.00).with_delta(-0.003).with_elevation(0.000),
+            btn_lists: DRectButton::new().with_radius(0.00).with_delta(-0.003).with_elevation(0.000),
             btn_user: DRectButton::new().with_delta(-0.000),
 
             next_page: None,
@@ -104,6 +132,10 @@ impl HomePage {
 
             has_new_task: None,
             has_new: false,
+
+            layout_task,
+            layout_source,
+            layout: None,
         })
     }
 }
@@ -116,13 +148,55 @@ impl HomePage {
             struct Resp {
                 has: bool,
             }
-            let resp: Resp = recv_raw(Client::get("/message/has_new").query(&[("checked", time)]))
+            let resp: Resp = recv_with(Client::get("/message/has_new").query(&[("checked", time)]), Priority::Prefetch)
                 .await?
                 .json()
                 .await?;
             Ok(resp.has)
         }));
     }
+
+    fn layout_action(&mut self, action: &str, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        match action {
+            "play" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(LibraryPage::new(Arc::clone(&self.icons), s.icons.clone())?)));
+            }
+            "respack" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(ResPackPage::new(Arc::clone(&self.icons))?)));
+            }
+            "settings" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(SettingsPage::new(self.icons.lang.clone()))));
+            }
+            "best" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(BestPage::new())));
+            }
+            "downloads" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(DownloadsPage::new())));
+            }
+            "trophies" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(TrophyPage::new())));
+            }
+            "lists" => {
+                self.next_page = Some(NextPage::Overlay(Box::new(ListsPage::new(Arc::clone(&self.icons), s.icons.clone()))));
+            }
+            "event" => {
+                if get_data().me.is_none() {
+                    self.login.enter(t);
+                } else {
+                    self.next_page = Some(NextPage::Overlay(Box::new(EventPage::new(Arc::clone(&self.icons), s.icons.clone()))));
+                }
+            }
+            x => {
+                if let Some(url) = x.strip_prefix("open:") {
+                    open_url(url)?;
+                } else {
+                    warn!("unknown home layout action: {x}");
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Page for HomePage {
@@ -147,6 +221,15 @@ impl Page for HomePage {
         if self.login.touch(touch, s.t) {
             return Ok(true);
         }
+        if let Some(layout) = &mut self.layout {
+            let mut action = None;
+            if layout.touch(touch, t, s.rt, &mut action)? {
+                if let Some(action) = action {
+                    self.layout_action(&action, s)?;
+                }
+                return Ok(true);
+            }
+        }
         if self.btn_play.touch(touch, t) {
             button_hit_large();
             self.next_page = Some(NextPage::Overlay(Box::new(LibraryPage::new(Arc::clone(&self.icons), s.icons.clone())?)));
@@ -174,6 +257,18 @@ impl Page for HomePage {
             self.next_page = Some(NextPage::Overlay(Box::new(SettingsPage::new(self.icons.lang.clone()))));
             return Ok(true);
         }
+        if self.btn_best.touch(touch, t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(BestPage::new())));
+            return Ok(true);
+        }
+        if self.btn_downloads.touch(touch, t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(DownloadsPage::new())));
+            return Ok(true);
+        }
+        if self.btn_lists.touch(touch, t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(ListsPage::new(Arc::clone(&self.icons), s.icons.clone()))));
+            return Ok(true);
+        }
         if self.btn_user.touch(touch, t) {
             if let Some(me) = &get_data().me {
                 self.need_back = true;
@@ -251,6 +346,28 @@ impl Page for HomePage {
                 self.board_task = None;
             }
         }
+        if let Some(task) = &mut self.layout_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => {
+                        warn!("failed to load home layout, using the cached one: {:?}", err);
+                    }
+                    Ok(source) => {
+                        if let Err(err) = std::fs::write(layout_cache_path()?, &source) {
+                            warn!("failed to cache home layout: {:?}", err);
+                        }
+                        self.layout_source = Some(source);
+                    }
+                }
+                self.layout_task = None;
+            }
+        }
+        if let Some(source) = self.layout_source.take() {
+            match parse_uml(&source, &self.icons, &s.icons) {
+                Ok(layout) => self.layout = Some(layout),
+                Err(err) => warn!("invalid home layout: {err}"),
+            }
+        }
         if let Some(task) = &mut self.has_new_task {
             if let Some(res) = task.take() {
                 match res {
@@ -281,7 +398,7 @@ impl Page for HomePage {
         // let top = s.render_fader(ui, |ui, c| {
         //     let r = Rect::new(0.60, -0.30, 0.3, 0.2);
         //     let top = r.bottom();
-        //     let (r, path) = self.btn_play.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
+        //     let (r, path) = self.btn_play.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
         //     if let Some(cur) = &self.board_tex {
         //         let p = (t - self.board_last_time) / BOARD_TRANSIT_TIME;
         //         if p > 1. {
@@ -314,7 +431,7 @@ impl Page for HomePage {
 
         let text_and_icon = |ui: &mut Ui, r: Rect, btn: &mut DRectButton, text, icon, c: Color| {
             let ow = r.w;
-            let (r, _) = btn.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
+            let (r, _) = btn.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
             let ir = Rect::new(r.x + 0.02, r.bottom() - 0.08, 0.14, 0.14);
             ui.text(text).pos(r.x + 0.026, r.y + 0.026).size(0.6 * r.w / ow).color(c).draw();
             ui.fill_rect(
@@ -347,7 +464,7 @@ impl Page for HomePage {
 
         s.render_fader(ui, |ui, c| {
             // let r = Rect::new(lf, top, 0.11, 0.11);
-            // let (r, _) = self.btn_msg.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
+            // let (r, _) = self.btn_msg.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
             // let r = r.feather(-0.01);
             // ui.fill_rect(r, (*self.icons.msg, r, ScaleType::Fit, c));
             // if self.has_new {
@@ -356,10 +473,16 @@ impl Page for HomePage {
             // }
 
             let r = Rect::new(r.left(), r.bottom() + 0.02, 1.3, 0.2);
-            // let (r, _) = self.btn_settings.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
+            // let (r, _) = self.btn_settings.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
             // let r = r.feather(0.004);
             // ui.fill_rect(r, (*self.icons.settings, r, ScaleType::Fit, c));
             text_and_icon(ui, r, &mut self.btn_settings, tl!("settings"), *self.icons.settings, c);
+            let r = Rect::new(r.left(), r.bottom() + 0.02, 0.42, 0.12);
+            text_and_icon(ui, r, &mut self.btn_best, tl!("best"), *self.icons.medal, c);
+            let r = Rect::new(r.right() + 0.02, r.y, 0.42, 0.12);
+            text_and_icon(ui, r, &mut self.btn_lists, tl!("lists"), *self.icons.star, c);
+            let r = Rect::new(r.right() + 0.02, r.y, 0.42, 0.12);
+            text_and_icon(ui, r, &mut self.btn_downloads, tl!("downloads"), *self.icons.download, c);
         });
 
         s.fader.roll_back();
@@ -400,6 +523,19 @@ impl Page for HomePage {
                     .draw();
             }
         });
+        if let Some(layout) = &mut self.layout {
+            let rt = s.rt;
+            let top = ui.top;
+            let logged_in = get_data().me.is_some() as u32 as f32;
+            ui.scope(|ui| {
+                ui.dx(-1.);
+                ui.dy(-top);
+                if let Err(err) = layout.render(ui, t, rt, &[("t", t), ("top", top), ("logged_in", logged_in)]) {
+                    warn!("failed to render home layout: {:?}", err);
+                }
+            });
+            layout.render_top(ui, t, rt)?;
+        }
         self.login.render(ui, t);
         self.sf.render(ui, t);
         Ok(())
@@ -410,6 +546,9 @@ impl Page for HomePage {
     }
 
     fn next_scene(&mut self, s: &mut SharedState) -> NextScene {
-        self.sf.next_scene(s.t).unwrap_or_default()
+        self.sf
+            .next_scene(s.t)
+            .or_else(|| self.layout.as_ref().and_then(|it| it.next_scene()))
+            .unwrap_or_default()
     }
 }
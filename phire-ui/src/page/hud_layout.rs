@@ -0,0 +1,233 @@
+phire::tl_file!("hud_layout");
+
+use super::{Page, SharedState};
+use crate::{get_data, get_data_mut, save_data};
+use anyhow::Result;
+use macroquad::prelude::*;
+use phire::{
+    config::{HudLayout, HudTransform},
+    core::UIElement,
+    ext::{semi_black, semi_white, RectExt},
+    ui::{DRectButton, Slider, Ui},
+};
+use std::borrow::Cow;
+
+// the preview is laid out in the same units the game uses, with a 16:9 chart
+const ASPECT: f32 = 16. / 9.;
+
+struct HudItem {
+    element: UIElement,
+    // default bounds and the point it's scaled about, in game units
+    rect: Rect,
+    anchor: Vec2,
+}
+
+impl HudItem {
+    fn label(&self) -> Cow<'static, str> {
+        match self.element {
+            UIElement::Pause => tl!("pause"),
+            UIElement::Score => tl!("score"),
+            UIElement::Bar => tl!("bar"),
+            _ => tl!("combo"),
+        }
+    }
+
+    fn placed(&self, layout: HudTransform) -> Rect {
+        let s = layout.scale;
+        Rect::new(
+            self.anchor.x + (self.rect.x - self.anchor.x) * s + layout.dx,
+            self.anchor.y + (self.rect.y - self.anchor.y) * s + layout.dy,
+            self.rect.w * s,
+            self.rect.h * s,
+        )
+    }
+}
+
+// mirrors the placement in GameScene::ui
+fn hud_items() -> Vec<HudItem> {
+    let scale_ratio = 1.777777;
+    let top = -1.;
+    let eps = 2e-2;
+    let margin = 0.0425 * scale_ratio;
+    let pause_w = 0.011 * scale_ratio;
+    let pause_h = pause_w * 3.5;
+    let pause = vec2(-ASPECT + 0.0525 * scale_ratio - pause_w * 1.5, top + eps * 3.6454);
+    let score = vec2(ASPECT - margin, top + eps * 2.8125);
+    let combo_y = top + eps * 1.55 + 0.08;
+    let bar_height = 0.04;
+    vec![
+        HudItem {
+            element: UIElement::Pause,
+            rect: Rect::new(pause.x, pause.y, pause_w * 3., pause_h),
+            anchor: pause,
+        },
+        HudItem {
+            element: UIElement::Score,
+            rect: Rect::new(score.x - 0.5, score.y, 0.5, 0.12),
+            anchor: score,
+        },
+        HudItem {
+            element: UIElement::Combo,
+            rect: Rect::new(-0.25, combo_y - 0.08, 0.5, 0.22),
+            anchor: vec2(0., combo_y),
+        },
+        HudItem {
+            element: UIElement::Bar,
+            rect: Rect::new(-ASPECT, top, ASPECT * 2., bar_height),
+            anchor: vec2(-ASPECT, top + eps / 2.),
+        },
+    ]
+}
+
+pub struct HudLayoutPage {
+    items: Vec<HudItem>,
+    selected: usize,
+    // touch id, touch position and element offset when the drag started
+    drag: Option<(u64, Vec2, Vec2)>,
+    preview: Rect,
+
+    scale_slider: Slider,
+    reset_btn: DRectButton,
+    reset_all_btn: DRectButton,
+}
+
+impl HudLayoutPage {
+    pub fn new() -> Self {
+        Self {
+            items: hud_items(),
+            selected: 0,
+            drag: None,
+            preview: Rect::default(),
+
+            scale_slider: Slider::new(0.5..2.0, 0.05),
+            reset_btn: DRectButton::new(),
+            reset_all_btn: DRectButton::new(),
+        }
+    }
+
+    fn layout() -> &'static mut HudLayout {
+        &mut get_data_mut().config.hud_layout
+    }
+
+    fn selected_mut(&self) -> &'static mut HudTransform {
+        Self::layout().get_mut(self.items[self.selected].element).unwrap()
+    }
+
+    // game units per preview unit
+    fn unit(&self) -> f32 {
+        2. / self.preview.h
+    }
+
+    fn to_game(&self, p: Vec2) -> Vec2 {
+        (p - self.preview.center()) * self.unit()
+    }
+
+}
+
+impl Page for HudLayoutPage {
+    fn label(&self) -> Cow<'static, str> {
+        "HUD LAYOUT".into()
+    }
+
+    fn exit(&mut self) -> Result<()> {
+        save_data()
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        let t = s.t;
+        if let Some((id, start, origin)) = self.drag {
+            if touch.id == id {
+                let delta = self.to_game(touch.position) - self.to_game(start);
+                let layout = self.selected_mut();
+                layout.dx = origin.x + delta.x;
+                layout.dy = origin.y + delta.y;
+                if matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+                    self.drag = None;
+                    save_data()?;
+                }
+                return Ok(true);
+            }
+        }
+        let layout = self.selected_mut();
+        if let Some(changed) = self.scale_slider.touch(touch, t, &mut layout.scale) {
+            if changed {
+                save_data()?;
+            }
+            return Ok(true);
+        }
+        if self.reset_btn.touch(touch, t) {
+            *self.selected_mut() = HudTransform::IDENTITY;
+            save_data()?;
+            return Ok(true);
+        }
+        if self.reset_all_btn.touch(touch, t) {
+            *Self::layout() = HudLayout::default();
+            save_data()?;
+            return Ok(true);
+        }
+        if touch.phase == TouchPhase::Started && self.preview.contains(touch.position) {
+            let p = self.to_game(touch.position);
+            let layout = *Self::layout();
+            // topmost first, so the small elements can still be picked above the bar
+            if let Some(index) = self.items.iter().rposition(|it| it.placed(layout.get(it.element)).feather(0.03).contains(p)) {
+                self.selected = index;
+                let layout = self.selected_mut();
+                self.drag = Some((touch.id, touch.position, vec2(layout.dx, layout.dy)));
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn update(&mut self, _s: &mut SharedState) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        let r = ui.content_rect();
+        let bottom_h = 0.14;
+        let avail = Rect::new(r.x, r.y, r.w, r.h - bottom_h - 0.02);
+        let h = avail.h.min(avail.w / ASPECT);
+        self.preview = Rect::new(avail.center().x - h * ASPECT / 2., avail.y, h * ASPECT, h);
+        let pr = self.preview;
+        let (ct, k) = (pr.center(), 1. / self.unit());
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&pr.rounded(0.01), semi_black(0.6 * c.a));
+            let layout = get_data().config.hud_layout;
+            for (i, item) in self.items.iter().enumerate() {
+                let rect = item.placed(layout.get(item.element));
+                let rect = Rect::new(ct.x + rect.x * k, ct.y + rect.y * k, rect.w * k, rect.h * k);
+                let chosen = i == self.selected;
+                ui.fill_path(&rect.rounded(0.005), semi_white(c.a * if chosen { 0.5 } else { 0.2 }));
+                ui.text(item.label())
+                    .pos(rect.center().x, rect.center().y)
+                    .anchor(0.5, 0.5)
+                    .no_baseline()
+                    .size(0.35)
+                    .max_width(rect.w.max(0.2))
+                    .color(c)
+                    .draw();
+            }
+
+            let cy = pr.bottom() + 0.02 + bottom_h / 2.;
+            let item = &self.items[self.selected];
+            ui.text(tl!("scale-of", "name" => item.label().into_owned()))
+                .pos(r.x + 0.04, cy)
+                .anchor(0., 0.5)
+                .no_baseline()
+                .size(0.5)
+                .color(c)
+                .draw();
+            let scale = layout.get(item.element).scale;
+            let w = 0.26;
+            let br = Rect::new(r.right() - w, cy - 0.04, w, 0.08);
+            self.reset_all_btn.render_text(ui, br, t, c.a, tl!("reset-all"), 0.5, false);
+            let br = Rect::new(br.x - w - 0.02, br.y, w, br.h);
+            self.reset_btn.render_text(ui, br, t, c.a, tl!("reset"), 0.5, false);
+            let sr = Rect::new(br.x - 0.6, cy - 0.03, 0.45, 0.06);
+            self.scale_slider.render(ui, sr, t, c, scale, format!("{scale:.2}x"));
+        });
+        Ok(())
+    }
+}
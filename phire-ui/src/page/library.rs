@@ -1,34 +1,77 @@
 phire::tl_file!("library");
 
-use super::{Page, SharedState};
+use super::{ChartItem, Page, SharedState};
 use crate::{
+    chart_index,
     charts_view::{ChartDisplayItem, ChartsView, NEED_UPDATE},
     client::{Chart, Client},
-    get_data,
+    data::{ChartFilters, Collection},
+    get_data, get_data_mut,
     icons::Icons,
     popup::Popup,
     rate::RateDialog,
-    scene::{ChartOrder, ORDERS},
+    save_data,
+    scene::{confirm_dialog, export_pack, ChartOrder, ORDERS},
     tags::TagsDialog,
 };
 use anyhow::{anyhow, Result};
 use macroquad::prelude::*;
 use phire::{
-    ext::{semi_black, JoinToString, RectExt, SafeTexture, ScaleType},
-    scene::{request_file, request_input, return_input, show_error, show_message, take_input, NextScene},
+    ext::{share_file, JoinToString, RectExt, SafeTexture, ScaleType},
+    scene::{request_file, request_input, return_input, show_error, show_message, take_input, FileKind, NextScene},
     task::Task,
-    ui::{button_hit, DRectButton, RectButton, Ui},
+    ui::{button_hit, theme, DRectButton, RectButton, Ui},
 };
 use std::{
     any::Any,
     borrow::Cow,
     ops::Deref,
-    sync::{atomic::Ordering, Arc},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 use tap::Tap;
+use tracing::warn;
 
 const PAGE_NUM: u64 = 28;
 
+// `charter:<name>` and `#<tag>` words narrow the search, the rest is matched against titles
+fn split_search(search: &str) -> (String, Option<String>, Vec<String>) {
+    let mut words = Vec::new();
+    let mut charter = None;
+    let mut tags = Vec::new();
+    for word in search.split_whitespace() {
+        if let Some(name) = word.strip_prefix("charter:").filter(|it| !it.is_empty()) {
+            charter = Some(name.to_owned());
+        } else if let Some(tag) = word.strip_prefix('#').filter(|it| !it.is_empty()) {
+            tags.push(tag.to_owned());
+        } else {
+            words.push(word);
+        }
+    }
+    (words.join(" "), charter, tags)
+}
+
+// the same search over the local tab, with the info and tags kept in the chart index
+fn local_matches(chart: &ChartItem, words: &str, charter: Option<&str>, tags: &[String]) -> bool {
+    let contains = |text: &str, part: &str| text.to_lowercase().contains(&part.to_lowercase());
+    let info = &chart.info;
+    if !words.is_empty() && ![&info.name, &info.charter, &info.composer].iter().any(|it| contains(it, words)) {
+        return false;
+    }
+    if charter.map_or(false, |charter| !contains(&info.charter, charter)) {
+        return false;
+    }
+    if tags.is_empty() {
+        return true;
+    }
+    let Some(entry) = chart.local_path.as_deref().and_then(chart_index::get) else {
+        return false;
+    };
+    tags.iter().all(|tag| entry.tags.iter().any(|it| it.eq_ignore_ascii_case(tag)))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum ChartListType {
     Local,
@@ -61,6 +104,8 @@ pub struct LibraryPage {
     icons: Arc<Icons>,
 
     import_btn: DRectButton,
+    export_btn: DRectButton,
+    export_task: Option<Task<Result<String>>>,
 
     search_btn: DRectButton,
     search_str: String,
@@ -77,13 +122,25 @@ pub struct LibraryPage {
     rating: RateDialog,
     rating_last_show: bool,
     filter_show_tag: bool,
+
+    // the collection the local tab is narrowed to, an index into `Data::collections`
+    collection: Option<usize>,
+    collection_btn: DRectButton,
+    collection_menu: Popup,
+    need_show_collection_menu: bool,
+    should_delete_collection: Arc<AtomicBool>,
+    reorder_btn: DRectButton,
+    // adds the picked charts to a collection, or removes them from the one shown
+    collect_btn: DRectButton,
+    collect_menu: Popup,
+    need_show_collect_menu: bool,
 }
 
 impl LibraryPage {
     pub fn new(icons: Arc<Icons>, rank_icons: [SafeTexture; 8]) -> Result<Self> {
         NEED_UPDATE.store(true, Ordering::Relaxed);
         let icon_star = icons.star.clone();
-        Ok(Self {
+        let mut res = Self {
             btn_local: DRectButton::new(),
             btn_ranked: DRectButton::new(),
             btn_special: DRectButton::new(),
@@ -103,6 +160,8 @@ impl LibraryPage {
             icons,
 
             import_btn: DRectButton::new(),
+            export_btn: DRectButton::new(),
+            export_task: None,
 
             search_btn: DRectButton::new(),
             search_str: String::new(),
@@ -122,7 +181,87 @@ impl LibraryPage {
             }),
             rating_last_show: false,
             filter_show_tag: true,
-        })
+
+            collection: None,
+            collection_btn: DRectButton::new(),
+            collection_menu: Popup::new(),
+            need_show_collection_menu: false,
+            should_delete_collection: Arc::default(),
+            reorder_btn: DRectButton::new(),
+            collect_btn: DRectButton::new(),
+            collect_menu: Popup::new(),
+            need_show_collect_menu: false,
+        };
+        res.restore_filters(get_data().chart_filters.clone());
+        Ok(res)
+    }
+
+    fn restore_filters(&mut self, filters: ChartFilters) {
+        self.search_str = filters.search;
+        self.current_order = filters.order.min(ORDERS.len() - 1);
+        self.tags.tags.set(filters.tags);
+        self.tags.unwanted.as_mut().unwrap().set(filters.unwanted);
+        self.tags.set_division(&filters.division);
+        self.tags.level = filters.level;
+        self.tags.notes = filters.notes;
+        self.rating.rate.score = filters.rating.0;
+        self.rating.rate_upper.as_mut().unwrap().score = filters.rating.1;
+    }
+
+    fn save_filters(&self) {
+        get_data_mut().chart_filters = ChartFilters {
+            search: self.search_str.clone(),
+            order: self.current_order,
+            tags: self.tags.tags.tags().to_vec(),
+            unwanted: self.tags.unwanted.as_ref().unwrap().tags().to_vec(),
+            division: self.tags.division.to_owned(),
+            rating: (self.rating.rate.score, self.rating.rate_upper.as_ref().unwrap().score),
+            level: self.tags.level,
+            notes: self.tags.notes,
+        };
+        if let Err(err) = save_data() {
+            warn!("failed to save chart filters: {err:?}");
+        }
+    }
+
+    fn collection_name(&self) -> Option<String> {
+        get_data().collections.get(self.collection?).map(|it| it.name.clone())
+    }
+
+    // all charts, every collection, then the actions: new, and rename and delete for the one shown
+    fn collection_options(&self) -> Vec<String> {
+        let mut options = vec![tl!("collection-all").into_owned()];
+        options.extend(get_data().collections.iter().map(|it| it.name.clone()));
+        options.push(tl!("collection-new").into_owned());
+        if self.collection.is_some() {
+            options.push(tl!("collection-rename").into_owned());
+            options.push(tl!("collection-delete").into_owned());
+        }
+        options
+    }
+
+    fn set_collection(&mut self, s: &SharedState, collection: Option<usize>) {
+        self.charts_view.stop_reordering();
+        self.collection = collection;
+        self.charts_view.reset_scroll();
+        self.sync_local(s);
+    }
+
+    fn add_to_collection(&mut self, index: usize) {
+        let paths: Vec<_> = self.charts_view.picked_charts().into_iter().map(|it| it.0).collect();
+        let data = get_data_mut();
+        let collection = &mut data.collections[index];
+        for path in paths {
+            if !collection.charts.contains(&path) {
+                collection.charts.push(path);
+            }
+        }
+        let name = collection.name.clone();
+        if let Err(err) = save_data() {
+            warn!("failed to save collections: {err:?}");
+        }
+        self.charts_view.stop_picking();
+        show_message(tl!("collection-added", "name" => name));
     }
 }
 
@@ -155,8 +294,9 @@ impl LibraryPage {
         }
         self.charts_view.reset_scroll();
         self.charts_view.clear();
+        self.save_filters();
         let page = self.current_page;
-        let search = self.search_str.clone();
+        let (search, charter, search_tags) = split_search(&self.search_str);
         let order = {
             let (order, mut rev) = ORDERS[self.current_order];
             let order = match order {
@@ -166,6 +306,7 @@ impl LibraryPage {
                 }
                 ChartOrder::Name => "name",
                 ChartOrder::Rating => "rating",
+                ChartOrder::Hot => "hot",
             };
             if rev {
                 format!("-{order}")
@@ -179,10 +320,13 @@ impl LibraryPage {
             .tags()
             .iter()
             .cloned()
+            .chain(search_tags)
             .chain(self.tags.unwanted.as_ref().unwrap().tags().iter().map(|it| format!("-{it}")))
             .join(",");
         let division = self.tags.division;
         let rating_range = format!("{},{}", self.rating.rate.score as f32 / 10., self.rating.rate_upper.as_ref().unwrap().score as f32 / 10.);
+        let level_range = self.tags.level.map(|(low, high)| format!("{low},{high}"));
+        let notes_range = self.tags.notes.map(|(low, high)| format!("{low},{high}"));
         let popular = matches!(self.chosen, ChartListType::Popular);
         let typ = match self.chosen {
             ChartListType::Ranked => 0,
@@ -203,6 +347,15 @@ impl LibraryPage {
                 q = q.suffix("/popular");
             } else {
                 q = q.search(search).order(order).tags(tags).query("rating", rating_range);
+                if let Some(charter) = charter {
+                    q = q.query("charter", charter);
+                }
+                if let Some(range) = level_range {
+                    q = q.query("difficulty", range);
+                }
+                if let Some(range) = notes_range {
+                    q = q.query("notes", range);
+                }
             }
             if let Some(me) = by_me {
                 q = q.query("uploader", me.to_string());
@@ -229,6 +382,7 @@ impl LibraryPage {
     fn switch_to_type(&mut self, s: &mut SharedState, ty: ChartListType) {
         if self.chosen != ty {
             self.chosen = ty;
+            self.charts_view.stop_reordering();
             self.charts_view.reset_scroll();
             if ty == ChartListType::Local {
                 self.sync_local(s);
@@ -239,12 +393,109 @@ impl LibraryPage {
         }
     }
 
+    // `charts_local` comes newest first
     fn sync_local(&mut self, s: &SharedState) {
         if self.chosen == ChartListType::Local {
             self.charts_view.can_refresh = false;
-            self.charts_view
-                .set(s.t, s.charts_local.iter().map(|it| ChartDisplayItem::new(it.clone(), None)).collect());
+            let collection = self.collection.and_then(|it| get_data().collections.get(it));
+            // a collection keeps its own order, which is what gets dragged around
+            let charts: Vec<_> = match collection {
+                Some(collection) => collection
+                    .charts
+                    .iter()
+                    .filter_map(|path| s.charts_local.iter().find(|it| it.local_path.as_ref() == Some(path)))
+                    .collect(),
+                None => s.charts_local.iter().collect(),
+            };
+            let mut charts: Vec<_> = if self.charts_view.reordering() {
+                charts.into_iter().cloned().collect()
+            } else {
+                let (words, charter, tags) = split_search(&self.search_str);
+                charts
+                    .into_iter()
+                    .filter(|it| local_matches(it, &words, charter.as_deref(), &tags))
+                    .cloned()
+                    .collect()
+            };
+            if !self.charts_view.reordering() {
+                let (order, rev) = ORDERS[self.current_order];
+                if matches!(order, ChartOrder::Name) {
+                    charts.sort_by(|x, y| x.info.name.cmp(&y.info.name));
+                }
+                if rev {
+                    charts.reverse();
+                }
+            }
+            self.charts_view.set(s.t, charts.into_iter().map(|it| ChartDisplayItem::new(it, None)).collect());
+        }
+    }
+
+    // after the search or the order changed
+    fn search_changed(&mut self, s: &SharedState) {
+        if self.chosen == ChartListType::Local {
+            self.save_filters();
+            self.charts_view.reset_scroll();
+            self.sync_local(s);
+        } else {
+            self.current_page = 0;
+            self.load_online();
+        }
+    }
+
+    fn touch_search(&mut self, touch: &Touch, s: &SharedState) -> bool {
+        let t = s.t;
+        if !self.search_str.is_empty() && self.search_clr_btn.touch(touch) {
+            button_hit();
+            self.search_str.clear();
+            self.search_changed(s);
+            return true;
+        }
+        if !self.search_clr_btn.rect.contains(touch.position) && self.search_btn.touch(touch, t) {
+            request_input("search", &self.search_str, tl!("search"));
+            return true;
+        }
+        if self.order_btn.touch(touch, t) {
+            self.need_show_order_menu = true;
+            return true;
+        }
+        false
+    }
+
+    // the search bar ending at the right of `r`, then the order button left of it; returns where the next button goes
+    fn render_search(&mut self, ui: &mut Ui, c: Color, t: f32, mut r: Rect) -> Rect {
+        if self.search_str.is_empty() {
+            r.x += r.h;
+            r.w -= r.h;
+        }
+        let rt = r.right();
+        self.search_btn.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
+        let mut ir = r.feather(-0.01);
+        ir.w = ir.h;
+        if !self.search_str.is_empty() {
+            ui.fill_rect(ir, (*self.icons.close, ir, ScaleType::Fit, c));
+            self.search_clr_btn.set(ui, ir);
+            ir.x += ir.w;
+        }
+        ui.fill_rect(ir, (*self.icons.search, ir, ScaleType::Fit, c));
+        ui.text(&self.search_str)
+            .pos(ir.right() + 0.01, ir.center().y)
+            .anchor(0., 0.5)
+            .no_baseline()
+            .size(0.6)
+            .max_width(rt - ir.right() - 0.02)
+            .color(c)
+            .draw();
+        let mut r = Rect::new(r.x - r.h - 0.02, r.y, r.h, r.h);
+        let (cr, _) = self.order_btn.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
+        ui.fill_rect(cr, (*self.icons.order, cr, ScaleType::Fit, c));
+        if self.need_show_order_menu {
+            self.need_show_order_menu = false;
+            self.order_menu.set_bottom(true);
+            self.order_menu.set_selected(self.current_order);
+            self.order_menu.show(ui, t, Rect::new(r.x, r.bottom() + 0.02, 0.3, 0.4));
         }
+        r.x -= r.w + 0.02;
+        r
     }
 }
 
@@ -266,9 +517,11 @@ impl Page for LibraryPage {
 
     fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
         let t = s.t;
-        if self.order_menu.showing() {
-            self.order_menu.touch(touch, t);
-            return Ok(true);
+        for menu in [&mut self.order_menu, &mut self.collection_menu, &mut self.collect_menu] {
+            if menu.showing() {
+                menu.touch(touch, t);
+                return Ok(true);
+            }
         }
         if self.tags.touch(touch, t) {
             return Ok(true);
@@ -276,7 +529,7 @@ impl Page for LibraryPage {
         if self.rating.touch(touch, t) {
             return Ok(true);
         }
-        if self.charts_view.transiting() {
+        if self.charts_view.transiting() || self.export_task.is_some() {
             return Ok(true);
         }
         if self.btn_local.touch(touch, t) {
@@ -322,25 +575,67 @@ impl Page for LibraryPage {
         }
         match self.chosen {
             ChartListType::Local => {
-                if self.import_btn.touch(touch, t) {
-                    request_file("_import");
+                if self.charts_view.reordering() {
+                    if self.reorder_btn.touch(touch, t) {
+                        self.charts_view.stop_reordering();
+                        self.sync_local(s);
+                    }
                     return Ok(true);
                 }
-            }
-            ChartListType::Ranked | ChartListType::Special | ChartListType::Unstable => {
-                if !self.search_str.is_empty() && self.search_clr_btn.touch(touch) {
-                    button_hit();
-                    self.search_str.clear();
-                    self.current_page = 0;
-                    self.load_online();
+                if !self.charts_view.picking() && self.touch_search(touch, s) {
                     return Ok(true);
                 }
-                if !self.search_clr_btn.rect.contains(touch.position) && self.search_btn.touch(touch, t) {
-                    request_input("search", &self.search_str, tl!("search"));
-                    return Ok(true);
+                if self.charts_view.picking() {
+                    if self.export_btn.touch(touch, t) {
+                        self.charts_view.stop_picking();
+                        return Ok(true);
+                    }
+                    if self.collect_btn.touch(touch, t) {
+                        if self.charts_view.picked_count() == 0 {
+                            show_message(tl!("export-none")).warn();
+                        } else if let Some(index) = self.collection {
+                            let picked: Vec<_> = self.charts_view.picked_charts().into_iter().map(|it| it.0).collect();
+                            get_data_mut().collections[index].charts.retain(|it| !picked.contains(it));
+                            save_data()?;
+                            self.sync_local(s);
+                        } else {
+                            self.need_show_collect_menu = true;
+                        }
+                        return Ok(true);
+                    }
+                    if self.import_btn.touch(touch, t) {
+                        let charts = self.charts_view.picked_charts();
+                        if charts.is_empty() {
+                            show_message(tl!("export-none")).warn();
+                        } else {
+                            self.export_task = Some(Task::new(export_pack(charts)));
+                        }
+                        return Ok(true);
+                    }
+                } else {
+                    if self.collection_btn.touch(touch, t) {
+                        self.need_show_collection_menu = true;
+                        return Ok(true);
+                    }
+                    if self.collection.is_some() && self.reorder_btn.touch(touch, t) {
+                        self.charts_view.start_reordering();
+                        self.sync_local(s);
+                        show_message(tl!("reorder-hint"));
+                        return Ok(true);
+                    }
+                    if self.export_btn.touch(touch, t) {
+                        self.charts_view.start_picking();
+                        show_message(tl!("export-hint"));
+                        return Ok(true);
+                    }
+                    if self.import_btn.touch(touch, t) {
+                        request_file("_import", FileKind::Chart);
+                        return Ok(true);
+                    }
                 }
-                if self.order_btn.touch(touch, t) {
-                    self.need_show_order_menu = true;
+            }
+            ChartListType::Ranked | ChartListType::Special | ChartListType::Unstable => {
+                if self.touch_search(touch, s) {
                     return Ok(true);
                 }
                 if self.filter_btn.touch(touch, t) {
@@ -379,10 +674,13 @@ impl Page for LibraryPage {
         }
         self.tags_last_show = self.tags.showing();
         self.rating_last_show = self.rating.showing();
+        if self.charts_view.take_retry() {
+            self.load_online();
+        }
         if let Some(task) = &mut self.online_task {
             if let Some(res) = task.take() {
                 match res {
-                    Err(err) => show_error(err.context(tl!("failed-to-load-online"))),
+                    Err(err) => self.charts_view.fail(err.context(tl!("failed-to-load-online"))),
                     Ok(res) => {
                         self.online_total_page = res.2;
                         self.charts_view.set(t, res.0);
@@ -391,7 +689,18 @@ impl Page for LibraryPage {
                 self.online_task = None;
             }
         }
+        if let Some(task) = &mut self.export_task {
+            if let Some(res) = task.take() {
+                match res.and_then(|path| share_file(&path)) {
+                    Err(err) => show_error(err.context(tl!("export-failed"))),
+                    Ok(_) => self.charts_view.stop_picking(),
+                }
+                self.export_task = None;
+            }
+        }
         self.order_menu.update(t);
+        self.collection_menu.update(t);
+        self.collect_menu.update(t);
         for chart in &mut s.charts_local {
             chart.illu.settle(t);
         }
@@ -402,19 +711,82 @@ impl Page for LibraryPage {
             s.reload_local_charts();
             self.sync_local(s);
         }
+        if let Some(order) = self.charts_view.take_reordered() {
+            if let Some(collection) = self.collection.and_then(|it| get_data_mut().collections.get_mut(it)) {
+                // charts that aren't on this device any more stay, at the end
+                let rest: Vec<_> = collection.charts.iter().filter(|it| !order.contains(it)).cloned().collect();
+                collection.charts = order.into_iter().chain(rest).collect();
+                save_data()?;
+            }
+        }
+        if self.should_delete_collection.fetch_and(false, Ordering::Relaxed) {
+            if let Some(index) = self.collection {
+                get_data_mut().collections.remove(index);
+                save_data()?;
+                self.set_collection(s, None);
+            }
+        }
         if let Some((id, text)) = take_input() {
-            if id == "search" {
-                self.search_str = text;
-                self.current_page = 0;
-                self.load_online();
-            } else {
-                return_input(id, text);
+            let name = text.trim();
+            match id.as_str() {
+                "search" => {
+                    self.search_str = text;
+                    self.search_changed(s);
+                }
+                "collection_new" | "collection_new_picked" if name.is_empty() => {
+                    show_message(tl!("collection-name-empty")).error();
+                }
+                "collection_new" | "collection_new_picked" => {
+                    let data = get_data_mut();
+                    data.collections.push(Collection {
+                        name: name.to_owned(),
+                        charts: Vec::new(),
+                    });
+                    let index = data.collections.len() - 1;
+                    if id == "collection_new" {
+                        save_data()?;
+                        self.set_collection(s, Some(index));
+                    } else {
+                        self.add_to_collection(index);
+                    }
+                }
+                "collection_rename" if !name.is_empty() => {
+                    if let Some(collection) = self.collection.and_then(|it| get_data_mut().collections.get_mut(it)) {
+                        collection.name = name.to_owned();
+                        save_data()?;
+                    }
+                }
+                "collection_rename" => {
+                    show_message(tl!("collection-name-empty")).error();
+                }
+                _ => return_input(id, text),
             }
         }
         if self.order_menu.changed() {
             self.current_order = self.order_menu.selected();
-            self.current_page = 0;
-            self.load_online();
+            self.search_changed(s);
+        }
+        if self.collection_menu.changed() {
+            let count = get_data().collections.len();
+            match self.collection_menu.selected() {
+                0 => self.set_collection(s, None),
+                i if i <= count => self.set_collection(s, Some(i - 1)),
+                i if i == count + 1 => request_input("collection_new", "", tl!("collection-new")),
+                i if i == count + 2 => request_input("collection_rename", &self.collection_name().unwrap_or_default(), tl!("collection-rename")),
+                _ => confirm_dialog(
+                    tl!("collection-delete"),
+                    tl!("collection-delete-confirm", "name" => self.collection_name().unwrap_or_default()),
+                    Arc::clone(&self.should_delete_collection),
+                ),
+            }
+        }
+        if self.collect_menu.changed() {
+            let index = self.collect_menu.selected();
+            if index < get_data().collections.len() {
+                self.add_to_collection(index);
+            } else {
+                request_input("collection_new_picked", "", tl!("collection-new"));
+            }
         }
         Ok(())
     }
@@ -445,51 +817,61 @@ impl Page for LibraryPage {
                 s.render_fader(ui, |ui, c| {
                     let w = 0.24;
                     let r = Rect::new(r.right() - w, -ui.top + 0.04, w, r.y + ui.top - 0.06);
-                    self.import_btn.render_text(ui, r, t, c.a, tl!("import"), 0.6, false);
+                    let picking = self.charts_view.picking();
+                    if picking {
+                        let count = self.charts_view.picked_count();
+                        self.import_btn
+                            .render_text(ui, r, t, c.a, tl!("export-confirm", "count" => count), 0.6, count > 0);
+                    } else {
+                        self.import_btn.render_text(ui, r, t, c.a, tl!("import"), 0.6, false);
+                    }
+                    let r = Rect { x: r.x - w - 0.02, ..r };
+                    self.export_btn
+                        .render_text(ui, r, t, c.a, if picking { tl!("export-cancel") } else { tl!("export") }, 0.6, false);
+                    let mut r = Rect { x: r.x - w - 0.02, ..r };
+                    if picking {
+                        if self.collection.is_some() {
+                            self.collect_btn.render_text(ui, r, t, c.a, tl!("collection-remove"), 0.6, false);
+                        } else {
+                            self.collect_btn.render_text(ui, r, t, c.a, tl!("collection-add"), 0.6, false);
+                        }
+                        if self.need_show_collect_menu {
+                            self.need_show_collect_menu = false;
+                            let mut options: Vec<_> = get_data().collections.iter().map(|it| it.name.clone()).collect();
+                            options.push(tl!("collection-new").into_owned());
+                            self.collect_menu.set_options(options);
+                            self.collect_menu.set_bottom(true);
+                            self.collect_menu.set_selected(usize::MAX);
+                            self.collect_menu.show(ui, t, Rect::new(r.x, r.bottom() + 0.02, r.w, 0.4));
+                        }
+                        return;
+                    }
+                    if self.collection.is_some() {
+                        let reordering = self.charts_view.reordering();
+                        self.reorder_btn
+                            .render_text(ui, r, t, c.a, if reordering { tl!("reorder-done") } else { tl!("reorder") }, 0.6, reordering);
+                        r.x -= w + 0.02;
+                    }
+                    let cw = 0.3;
+                    r = Rect { x: r.right() - 0.4, w: 0.4, ..r };
+                    let r = self.render_search(ui, c, t, r);
+                    let r = Rect { x: r.right() - cw, w: cw, ..r };
+                    let name = self.collection_name().map_or_else(|| tl!("collection-all"), Cow::Owned);
+                    self.collection_btn.render_text(ui, r, t, c.a, name, 0.5, self.collection.is_some());
+                    if self.need_show_collection_menu {
+                        self.need_show_collection_menu = false;
+                        self.collection_menu.set_options(self.collection_options());
+                        self.collection_menu.set_bottom(true);
+                        self.collection_menu.set_selected(self.collection.map_or(0, |it| it + 1));
+                        self.collection_menu.show(ui, t, Rect::new(r.x, r.bottom() + 0.02, 0.4, 0.5));
+                    }
                 });
             }
             ChartListType::Ranked | ChartListType::Special | ChartListType::Unstable => {
                 s.render_fader(ui, |ui, c| {
-                    let empty = self.search_str.is_empty();
                     let w = 0.53;
-                    let mut r = Rect::new(r.right() - w, -ui.top + 0.04, w, r.y + ui.top - 0.06);
-                    if empty {
-                        r.x += r.h;
-                        r.w -= r.h;
-                    }
-                    let rt = r.right();
-                    self.search_btn.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
-                    let mut r = r.feather(-0.01);
-                    r.w = r.h;
-                    if !empty {
-                        ui.fill_rect(r, (*self.icons.close, r, ScaleType::Fit, c));
-                        self.search_clr_btn.set(ui, r);
-                        r.x += r.w;
-                    }
-                    ui.fill_rect(r, (*self.icons.search, r, ScaleType::Fit, c));
-                    ui.text(&self.search_str)
-                        .pos(r.right() + 0.01, r.center().y)
-                        .anchor(0., 0.5)
-                        .no_baseline()
-                        .size(0.6)
-                        .max_width(rt - r.right() - 0.02)
-                        .color(c)
-                        .draw();
-                    let mut r = r.feather(0.01);
-                    r.x = 1. - w - r.w - 0.05;
-                    if empty {
-                        r.x += r.w;
-                    }
-                    let (cr, _) = self.order_btn.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
-                    ui.fill_rect(cr, (*self.icons.order, cr, ScaleType::Fit, c));
-                    if self.need_show_order_menu {
-                        self.need_show_order_menu = false;
-                        self.order_menu.set_bottom(true);
-                        self.order_menu.set_selected(self.current_order);
-                        self.order_menu.show(ui, t, Rect::new(r.x, r.bottom() + 0.02, 0.3, 0.4));
-                    }
-                    r.x -= r.w + 0.02;
-                    let (cr, _) = self.filter_btn.render_shadow(ui, r, t, c.a, |_| semi_black(0.4 * c.a));
+                    let r = self.render_search(ui, c, t, Rect::new(r.right() - w, -ui.top + 0.04, w, r.y + ui.top - 0.06));
+                    let (cr, _) = self.filter_btn.render_shadow(ui, r, t, c.a, |_| theme().card(c.a));
                     let cr = cr.feather(-0.005);
                     ui.fill_rect(cr, (*self.icons.filter, cr, ScaleType::Fit, c));
                 });
@@ -498,7 +880,7 @@ impl Page for LibraryPage {
         }
         s.fader.render(ui, t, |ui, c| {
             let path = r.rounded(0.00);
-            ui.fill_path(&path, semi_black(0.4 * c.a));
+            ui.fill_path(&path, theme().card(c.a));
             self.render_charts(ui, c, s.t, r.feather(-0.01));
         });
         if !matches!(self.chosen, ChartListType::Local) {
@@ -525,11 +907,24 @@ impl Page for LibraryPage {
         }
         self.charts_view.render_top(ui, t);
         self.order_menu.render(ui, t, 1.);
+        self.collection_menu.render(ui, t, 1.);
+        self.collect_menu.render(ui, t, 1.);
         self.tags.render(ui, t);
         self.rating.render(ui, t);
+        if self.export_task.is_some() {
+            ui.full_loading(tl!("exporting"), t);
+        }
         Ok(())
     }
 
+    fn pause(&mut self) -> Result<()> {
+        self.charts_view.pause()
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        self.charts_view.resume()
+    }
+
     fn next_scene(&mut self, _s: &mut SharedState) -> NextScene {
         self.charts_view.next_scene().unwrap_or_default()
     }
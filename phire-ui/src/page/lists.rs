@@ -0,0 +1,318 @@
+phire::tl_file!("lists");
+
+use super::{Page, SharedState};
+use crate::{
+    charts_view::{ChartDisplayItem, ChartsView},
+    client::{Client, CuratedList, Paginator},
+    download::with_manager,
+    get_data,
+    icons::Icons,
+};
+use anyhow::Result;
+use macroquad::prelude::*;
+use phire::{
+    ext::{semi_black, semi_white, RectExt, SafeTexture},
+    scene::{show_error, show_message, NextScene},
+    ui::{theme, DRectButton, Scroll, Ui},
+};
+use std::{any::Any, borrow::Cow, sync::Arc};
+
+const PAGE_NUM: u64 = 20;
+const ROW_HEIGHT: f32 = 0.12;
+const SIDE_WIDTH: f32 = 0.5;
+const HEADER_HEIGHT: f32 = 0.17;
+const DONE_COLOR: Color = Color::new(0.55, 0.9, 0.55, 1.);
+
+// how far the player got through a list, worked out from the local charts and records every time
+#[derive(Clone, Copy, Default)]
+struct Completion {
+    total: usize,
+    downloaded: usize,
+    played: usize,
+}
+
+impl Completion {
+    fn of(list: &CuratedList) -> Self {
+        let data = get_data();
+        let mut res = Self {
+            total: list.charts.len(),
+            ..Default::default()
+        };
+        for chart in &list.charts {
+            let local = data.charts.iter().find(|it| it.info.id == Some(chart.id));
+            if local.is_some() {
+                res.downloaded += 1;
+            }
+            if local.map_or(false, |it| it.record.is_some()) || data.cloud_records.contains_key(&chart.id) {
+                res.played += 1;
+            }
+        }
+        res
+    }
+
+    fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.
+        } else {
+            self.played as f32 / self.total as f32
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.total > 0 && self.played == self.total
+    }
+}
+
+struct Item {
+    list: CuratedList,
+    completion: Completion,
+    btn: DRectButton,
+}
+
+pub struct ListsPage {
+    paginator: Paginator<CuratedList>,
+    lists: Option<Vec<Item>>,
+    selected: Option<usize>,
+    scroll: Scroll,
+    charts_view: ChartsView,
+    download_btn: DRectButton,
+}
+
+impl ListsPage {
+    pub fn new(icons: Arc<Icons>, rank_icons: [SafeTexture; 8]) -> Self {
+        let mut paginator = Paginator::new(Client::query(), PAGE_NUM);
+        paginator.load_more();
+        let mut charts_view = ChartsView::new(icons, rank_icons);
+        charts_view.row_num = 3;
+        Self {
+            paginator,
+            lists: None,
+            selected: None,
+            scroll: Scroll::new(),
+            charts_view,
+            download_btn: DRectButton::new(),
+        }
+    }
+
+    fn select(&mut self, index: usize, t: f32) {
+        let Some(item) = self.lists.as_ref().and_then(|it| it.get(index)) else { return };
+        self.selected = Some(index);
+        self.charts_view.reset_scroll();
+        self.charts_view.set(t, item.list.charts.iter().map(ChartDisplayItem::from_remote).collect());
+    }
+
+    fn refresh_completion(&mut self) {
+        for item in self.lists.iter_mut().flatten() {
+            item.completion = Completion::of(&item.list);
+        }
+    }
+
+    // queues every chart of the list that isn't on this device yet
+    fn download_all(&self) {
+        let Some(item) = self.selected.and_then(|index| self.lists.as_ref()?.get(index)) else { return };
+        let data = get_data();
+        let missing: Vec<_> = item
+            .list
+            .charts
+            .iter()
+            .filter(|chart| !data.charts.iter().any(|it| it.info.id == Some(chart.id)))
+            .collect();
+        if missing.is_empty() {
+            show_message(tl!("all-downloaded")).ok();
+            return;
+        }
+        with_manager(|manager| {
+            for chart in &missing {
+                manager.enqueue(chart.to_info(), (*chart).clone(), None);
+            }
+        });
+        show_message(tl!("queued", "count" => missing.len())).ok();
+    }
+}
+
+impl Page for ListsPage {
+    fn label(&self) -> Cow<'static, str> {
+        "LISTS".into()
+    }
+
+    fn on_result(&mut self, res: Box<dyn Any>, s: &mut SharedState) -> Result<()> {
+        if let Ok(delete) = res.downcast::<bool>() {
+            self.charts_view.on_result(s.t, *delete);
+        }
+        self.refresh_completion();
+        Ok(())
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        let t = s.t;
+        if self.charts_view.transiting() {
+            return Ok(true);
+        }
+        if self.selected.is_some() && self.download_btn.touch(touch, t) {
+            self.download_all();
+            return Ok(true);
+        }
+        if self.scroll.touch(touch, t) {
+            return Ok(true);
+        }
+        if let Some(index) = self
+            .lists
+            .iter_mut()
+            .flatten()
+            .position(|item| item.btn.touch(touch, t))
+        {
+            if self.selected != Some(index) {
+                self.select(index, t);
+            }
+            return Ok(true);
+        }
+        if self.charts_view.touch(touch, t, s.rt)? {
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn update(&mut self, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        self.scroll.update(t);
+        if let Some(res) = self.paginator.poll() {
+            match res {
+                Err(err) => show_error(err.context(tl!("load-failed"))),
+                Ok(new) => {
+                    let lists = self.lists.get_or_insert_with(Vec::new);
+                    lists.extend(new.iter().cloned().map(|list| Item {
+                        completion: Completion::of(&list),
+                        list,
+                        btn: DRectButton::new(),
+                    }));
+                }
+            }
+            if self.selected.is_none() && self.lists.as_ref().map_or(false, |it| !it.is_empty()) {
+                self.select(0, t);
+            }
+        }
+        // there are only ever a handful of lists, so all pages are fetched one after another
+        self.paginator.load_more();
+        self.charts_view.update(t)?;
+        if self.charts_view.need_update() {
+            s.reload_local_charts();
+            self.refresh_completion();
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        let cr = ui.content_rect();
+        let side = Rect::new(cr.x, cr.y, SIDE_WIDTH - 0.02, cr.h);
+        let main = Rect::new(cr.x + SIDE_WIDTH, cr.y, cr.w - SIDE_WIDTH, cr.h);
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&side.rounded(0.02), theme().card(c.a));
+            let Some(lists) = &mut self.lists else { return };
+            if lists.is_empty() {
+                let ct = side.center();
+                ui.text(tl!("empty")).pos(ct.x, ct.y).anchor(0.5, 0.5).no_baseline().size(0.5).color(c).draw();
+                return;
+            }
+            let pad = 0.02;
+            ui.scope(|ui| {
+                ui.dx(side.x + pad);
+                ui.dy(side.y + pad);
+                let w = side.w - pad * 2.;
+                self.scroll.size((w, side.h - pad * 2.));
+                self.scroll.render(ui, |ui| {
+                    let mut h = 0.;
+                    for (index, item) in lists.iter_mut().enumerate() {
+                        let r = Rect::new(0., 0., w, ROW_HEIGHT);
+                        let chosen = self.selected == Some(index);
+                        let (r, _) = item.btn.render_shadow(ui, r, t, c.a, |_| theme().button_fill(chosen, c.a * 0.6));
+                        let text_color = theme().button_text(chosen, c.a);
+                        ui.text(&item.list.name)
+                            .pos(r.x + 0.02, r.y + 0.02)
+                            .size(0.45)
+                            .max_width(r.w - 0.04)
+                            .color(text_color)
+                            .draw();
+                        let done = item.completion.done();
+                        let progress = if done {
+                            tl!("completed")
+                        } else {
+                            tl!("progress", "played" => item.completion.played, "total" => item.completion.total).into()
+                        };
+                        ui.text(progress)
+                            .pos(r.x + 0.02, r.y + 0.065)
+                            .size(0.3)
+                            .color(if done { Color { a: c.a, ..DONE_COLOR } } else { Color { a: text_color.a * 0.7, ..text_color } })
+                            .draw();
+                        let bar = Rect::new(r.x + 0.02, r.bottom() - 0.018, r.w - 0.04, 0.005);
+                        ui.fill_rect(bar, semi_black(c.a * 0.2));
+                        ui.fill_rect(
+                            Rect { w: bar.w * item.completion.ratio(), ..bar },
+                            if done { Color { a: c.a, ..DONE_COLOR } } else { semi_white(c.a * 0.8) },
+                        );
+                        ui.dy(ROW_HEIGHT + 0.01);
+                        h += ROW_HEIGHT + 0.01;
+                    }
+                    (w, h)
+                });
+            });
+        });
+        s.render_fader(ui, |ui, c| {
+            let Some(item) = self.selected.and_then(|index| self.lists.as_ref()?.get(index)) else {
+                self.download_btn.invalidate();
+                return;
+            };
+            let header = Rect::new(main.x, main.y, main.w, HEADER_HEIGHT);
+            ui.fill_path(&header.rounded(0.02), theme().card(c.a));
+            let bw = 0.3;
+            let text_w = header.w - bw - 0.08;
+            ui.text(&item.list.name)
+                .pos(header.x + 0.03, header.y + 0.025)
+                .size(0.6)
+                .max_width(text_w)
+                .color(c)
+                .draw();
+            ui.text(&item.list.description)
+                .pos(header.x + 0.03, header.y + 0.085)
+                .size(0.35)
+                .max_width(text_w)
+                .multiline()
+                .color(semi_white(c.a * 0.7))
+                .draw();
+            let Completion { total, downloaded, played } = item.completion;
+            ui.text(tl!("summary", "downloaded" => downloaded, "played" => played, "total" => total))
+                .pos(header.right() - 0.03, header.y + 0.025)
+                .anchor(1., 0.)
+                .size(0.35)
+                .color(semi_white(c.a * 0.7))
+                .draw();
+            let br = Rect::new(header.right() - bw - 0.03, header.bottom() - 0.09, bw, 0.065);
+            self.download_btn.render_text(ui, br, t, c.a, tl!("download-all"), 0.45, false);
+        });
+        if self.selected.is_some() {
+            let r = Rect::new(main.x, main.y + HEADER_HEIGHT + 0.02, main.w, main.h - HEADER_HEIGHT - 0.02);
+            s.render_fader(ui, |ui, c| {
+                ui.fill_path(&r.rounded(0.02), theme().card(c.a));
+                self.charts_view.render(ui, r, c.a, t);
+            });
+        }
+        if self.lists.is_none() && self.paginator.loading() {
+            ui.full_loading_simple(t);
+        }
+        self.charts_view.render_top(ui, t);
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<()> {
+        self.charts_view.pause()
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        self.refresh_completion();
+        self.charts_view.resume()
+    }
+
+    fn next_scene(&mut self, _s: &mut SharedState) -> NextScene {
+        self.charts_view.next_scene().unwrap_or_default()
+    }
+}
@@ -9,10 +9,10 @@ use anyhow::Result;
 use chrono::Local;
 use macroquad::prelude::*;
 use phire::{
-    ext::{semi_black, semi_white, RectExt},
+    ext::{semi_white, RectExt},
     scene::show_error,
     task::Task,
-    ui::{DRectButton, Scroll, Ui},
+    ui::{theme, DRectButton, Scroll, Ui},
 };
 
 pub struct MessagePage {
@@ -127,7 +127,7 @@ impl Page for MessagePage {
         cr.w -= d;
         let r = Rect::new(-0.92, cr.y, 0.47, cr.h);
         s.render_fader(ui, |ui, c| {
-            ui.fill_path(&r.rounded(0.02), semi_black(c.a * 0.4));
+            ui.fill_path(&r.rounded(0.02), theme().card(c.a));
             let ct = r.center();
             let pad = 0.014;
             self.btns_scroll.size((r.w, r.h - pad));
@@ -165,7 +165,7 @@ impl Page for MessagePage {
             }
         });
         s.render_fader(ui, |ui, c| {
-            ui.fill_path(&cr.rounded(0.02), semi_black(c.a * 0.4));
+            ui.fill_path(&cr.rounded(0.02), theme().card(c.a));
             let pad = 0.03;
             ui.dx(cr.x + pad + 0.01);
             ui.dy(cr.y + pad);
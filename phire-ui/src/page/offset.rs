@@ -7,12 +7,28 @@ use anyhow::{Context, Result};
 use macroquad::prelude::*;
 use phire::{
     core::ResourcePack,
-    ext::{create_audio_manger, get_latency, push_frame_time, screen_aspect, semi_black, RectExt},
+    ext::{create_audio_manger, get_latency, push_frame_time, screen_aspect, RectExt},
     time::TimeManager,
-    ui::{Slider, Ui}
+    ui::{theme, DRectButton, Slider, Ui}
 };
 use sasa::{AudioClip, AudioManager, Music, MusicParams, PlaySfxParams, Sfx};
 
+const WIZARD_TAPS: usize = 12;
+
+// taps are first collected against the metronome alone, then against a silent visual cue;
+// the difference is the audio offset and the visual result is what the judge sees late
+enum Wizard {
+    Idle,
+    Audio(Vec<f32>),
+    Visual(f32, Vec<f32>),
+    Done(f32, f32),
+}
+
+fn median(mut v: Vec<f32>) -> f32 {
+    v.sort_by(|a, b| a.total_cmp(b));
+    v[v.len() / 2]
+}
+
 pub struct OffsetPage {
     audio: AudioManager,
     cali: Music,
@@ -22,6 +38,8 @@ pub struct OffsetPage {
     color: Color,
 
     slider: Slider,
+    wizard: Wizard,
+    wizard_btn: DRectButton,
 
     touched: bool,
     touch: Option<(f32, f32)>,
@@ -49,9 +67,10 @@ impl OffsetPage {
         let mut tm = TimeManager::new(1., true);
         tm.force = 3e-2;
 
-        let respack = ResourcePack::from_path(config.res_pack_path.as_ref())
+        let mut respack = ResourcePack::from_layers(config.res_pack_path.as_deref(), &config.res_pack_fallbacks)
             .await
             .context("Failed to load resource pack")?;
+        respack.recolor_notes(config);
 
         let frame_times: VecDeque<f64> = VecDeque::new();
         let latency_record: VecDeque<f32> = VecDeque::new();
@@ -64,6 +83,8 @@ impl OffsetPage {
             color: respack.info.fx_perfect(),
 
             slider: Slider::new(-200.0..800.0, 1.),
+            wizard: Wizard::Idle,
+            wizard_btn: DRectButton::new(),
 
             touched: false,
             touch: None,
@@ -72,6 +93,42 @@ impl OffsetPage {
             latency_record,
         })
     }
+
+    fn wizard_tap(&mut self, latency: f32) -> Result<()> {
+        // a tap closer to the previous or next beat is a stray one
+        if latency.abs() >= 0.5 {
+            return Ok(());
+        }
+        match &mut self.wizard {
+            Wizard::Audio(samples) => {
+                samples.push(latency);
+                if samples.len() == WIZARD_TAPS {
+                    let audio = median(std::mem::take(samples));
+                    self.wizard = Wizard::Visual(audio, Vec::new());
+                    self.cali.set_amplifier(0.)?;
+                }
+            }
+            Wizard::Visual(audio, samples) => {
+                samples.push(latency);
+                if samples.len() == WIZARD_TAPS {
+                    let audio = *audio;
+                    let visual = median(std::mem::take(samples));
+                    let config = &mut get_data_mut().config;
+                    config.offset = (config.offset + audio - visual).clamp(-0.2, 0.8);
+                    config.touch_offset = visual;
+                    self.wizard = Wizard::Done(config.offset, visual);
+                    self.cali.set_amplifier(config.volume_music)?;
+                    save_data()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn wizard_running(&self) -> bool {
+        matches!(self.wizard, Wizard::Audio(_) | Wizard::Visual(..))
+    }
 }
 
 impl Page for OffsetPage {
@@ -84,6 +141,10 @@ impl Page for OffsetPage {
     }
 
     fn exit(&mut self) -> Result<()> {
+        if self.wizard_running() {
+            self.wizard = Wizard::Idle;
+            self.cali.set_amplifier(get_data().config.volume_music)?;
+        }
         save_data()?;
         Ok(())
     }
@@ -116,6 +177,15 @@ impl Page for OffsetPage {
             config.offset = offset / 1000.;
             return Ok(true);
         }
+        if self.wizard_btn.touch(touch, t) {
+            if self.wizard_running() {
+                self.wizard = Wizard::Idle;
+                self.cali.set_amplifier(config.volume_music)?;
+            } else {
+                self.wizard = Wizard::Audio(Vec::new());
+            }
+            return Ok(true);
+        }
         let x = touch.position.x;
         let y = touch.position.y * screen_aspect();
         if touch.phase == TouchPhase::Started
@@ -158,12 +228,12 @@ impl Page for OffsetPage {
         let t = s.t;
         let aspect = 1. / screen_aspect();
         let config = &get_data().config;
-        s.render_fader(ui, |ui, c| {
+        s.render_fader(ui, |ui, c| -> Result<()> {
             let lf = -0.97;
             let mut r = ui.content_rect();
             r.w += r.x - lf;
             r.x = lf;
-            ui.fill_path(&r.rounded(0.00), semi_black(c.a * 0.4));
+            ui.fill_path(&r.rounded(0.00), theme().card(c.a));
             let ct = r.center();
             let hw = 0.3 * aspect * 1.7777777;
             let hh = 0.0075;
@@ -191,18 +261,29 @@ impl Page for OffsetPage {
                 t -= 2.;
             }
             let latency = t - 1.;
+            if let Wizard::Visual(..) = self.wizard {
+                if latency.abs() < 1. {
+                    let bw = hh * 4.;
+                    ui.fill_rect(Rect::new(latency * 0.9 - bw / 2., ct.y - aspect * 0.4 - hw / 4., bw, hw / 2.), Color { a: c.a, ..self.color });
+                }
+            }
             if self.touched {
                 self.touch = Some((latency, ot));
-                if latency.abs() < 0.200 {
-                    self.latency_record.push_back(latency);
-                    if self.latency_record.len() > 10 {
-                        self.latency_record.pop_front();
+                self.touched = false;
+                if self.wizard_running() {
+                    // hearing the hitsound would pull the taps towards it
+                    self.wizard_tap(latency)?;
+                } else {
+                    if latency.abs() < 0.200 {
+                        self.latency_record.push_back(latency);
+                        if self.latency_record.len() > 10 {
+                            self.latency_record.pop_front();
+                        }
                     }
+                    self.cali_hit.play(PlaySfxParams {
+                        amplifier: config.volume_sfx,
+                    })?;
                 }
-                self.touched = false;
-                self.cali_hit.play(PlaySfxParams {
-                    amplifier: config.volume_sfx,
-                }).unwrap();
             }
 
             if let Some((latency, time)) = self.touch {
@@ -233,7 +314,13 @@ impl Page for OffsetPage {
             } else {
                 self.latency_record.iter().sum::<f32>() / self.latency_record.len() as f32
             };
-            ui.text(format!("{} {:.0}ms", tl!("avg"), avg_latency * 1000.))
+            let status = match &self.wizard {
+                Wizard::Idle => format!("{} {:.0}ms", tl!("avg"), avg_latency * 1000.),
+                Wizard::Audio(samples) => tl!("wizard-audio", "n" => samples.len(), "total" => WIZARD_TAPS).into_owned(),
+                Wizard::Visual(_, samples) => tl!("wizard-visual", "n" => samples.len(), "total" => WIZARD_TAPS).into_owned(),
+                Wizard::Done(offset, judge) => tl!("wizard-done", "offset" => format!("{:.0}", offset * 1000.), "judge" => format!("{:.0}", judge * 1000.)).into_owned(),
+            };
+            ui.text(status)
                 .pos(0.0, ct.y + aspect * 0.4)
                 .anchor(0.5, 1.)
                 .size(0.5)
@@ -243,11 +330,14 @@ impl Page for OffsetPage {
             let offset = config.offset * 1000.;
             self.slider
                 .render(ui, Rect::new(-0.08, ct.y + aspect * 0.1 - 0.2 / 2., 0.45, 0.2), ot, c, offset, format!("{offset:.0}ms"));
+            let label = if self.wizard_running() { tl!("wizard-cancel") } else { tl!("wizard") };
+            self.wizard_btn.render_text(ui, Rect::new(0.55, ct.y + aspect * 0.1 - 0.04, 0.35, 0.08), ot, c.a, label, 0.5, false);
 
             if config.auto_tweak_offset {
                 push_frame_time(&mut self.frame_times, self.tm.real_time());
             }
-        });
+            Ok(())
+        })?;
 
         fn calculate_pos(x: f32) -> f32 {
             let base = (x.abs() * 9.0) + 1.0;
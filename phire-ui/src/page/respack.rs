@@ -12,11 +12,12 @@ use macroquad::prelude::*;
 use phire::{
     core::{NoteStyle, ParticleEmitter, ResPackInfo, ResourcePack},
     ext::{create_audio_manger, poll_future, semi_black, LocalTask, RectExt, SafeTexture, ScaleType},
-    scene::{request_file, show_error, show_message},
-    ui::{DRectButton, Dialog, Scroll, Ui},
+    scene::{request_file, show_error, show_message, FileKind},
+    ui::{theme, DRectButton, Dialog, Scroll, Ui},
 };
 use sasa::{AudioManager, PlaySfxParams, Sfx};
 use std::{
+    borrow::Cow,
     fs::File,
     path::{Path, PathBuf},
     sync::{
@@ -55,9 +56,15 @@ impl ResPackItem {
         if let Some(loaded) = self.loaded.take() {
             self.load_task = Some(Box::pin(async move { Ok(loaded) }));
         } else {
-            self.load_task = Some(Box::pin(ResourcePack::from_path(self.path.clone())));
+            // over the built-in pack, so that packs replacing only some assets can be previewed as well
+            let path = self.path.as_ref().map(|it| it.display().to_string());
+            self.load_task = Some(Box::pin(async move { ResourcePack::from_layers(path.as_deref(), &[]).await }));
         }
     }
+
+    fn dir_name(&self) -> Option<String> {
+        Some(self.path.as_ref()?.file_name()?.to_string_lossy().into_owned())
+    }
 }
 
 pub struct ResPackPage {
@@ -71,6 +78,7 @@ pub struct ResPackPage {
 
     info_btn: DRectButton,
     delete_btn: DRectButton,
+    layer_btn: DRectButton,
 
     should_delete: Arc<AtomicBool>,
 
@@ -106,6 +114,7 @@ impl ResPackPage {
             icons,
 
             info_btn: delete_btn.clone(),
+            layer_btn: delete_btn.clone(),
             delete_btn,
 
             should_delete: Arc::new(AtomicBool::default()),
@@ -118,7 +127,7 @@ impl ResPackPage {
 }
 
 impl Page for ResPackPage {
-    fn label(&self) -> std::borrow::Cow<'static, str> {
+    fn label(&self) -> Cow<'static, str> {
         "RESPACK".into()
     }
 
@@ -128,7 +137,7 @@ impl Page for ResPackPage {
             return Ok(true);
         }
         if self.import_btn.touch(touch, t) {
-            request_file("_import_respack");
+            request_file("_import_respack", FileKind::ResPack);
             return Ok(true);
         }
         if self.items[self.index].load_task.is_none() {
@@ -152,6 +161,18 @@ impl Page for ResPackPage {
             .show();
             return Ok(true);
         }
+        if self.index != 0 && self.layer_btn.touch(touch, t) {
+            if let Some(name) = self.items[self.index].dir_name() {
+                let layers = &mut get_data_mut().respack_layers;
+                if let Some(pos) = layers.iter().position(|it| *it == name) {
+                    layers.remove(pos);
+                } else {
+                    layers.push(name);
+                }
+                save_data()?;
+            }
+            return Ok(true);
+        }
         if self.delete_btn.touch(touch, t) {
             if self.index == 0 {
                 show_message(tl!("cant-delete-builtin")).error();
@@ -167,6 +188,9 @@ impl Page for ResPackPage {
         let t = s.t;
         self.btns_scroll.update(t);
         let item = &mut self.items[self.index];
+        if let Some(res_pack) = &mut item.loaded {
+            res_pack.animate(t);
+        }
         if let Some(task) = &mut item.load_task {
             if let Some(res) = poll_future(task.as_mut()) {
                 match res {
@@ -188,6 +212,9 @@ impl Page for ResPackPage {
         }
         if self.should_delete.fetch_and(false, Ordering::Relaxed) {
             std::fs::remove_dir_all(self.items[self.index].path.as_ref().unwrap())?;
+            if let Some(name) = self.items[self.index].dir_name() {
+                get_data_mut().respack_layers.retain(|it| *it != name);
+            }
             self.items.remove(self.index);
             get_data_mut().respacks.remove(self.index - 1);
             self.index -= 1;
@@ -210,7 +237,7 @@ impl Page for ResPackPage {
         cr.w -= d;
         let r = Rect::new(-0.92, cr.y, 0.47, cr.h);
         s.render_fader(ui, |ui, c| {
-            ui.fill_path(&r.rounded(0.00), semi_black(c.a * 0.4));
+            ui.fill_path(&r.rounded(0.00), theme().card(c.a));
             let pad = 0.02;
             self.btns_scroll.size((r.w, r.h - pad));
             ui.dx(r.x);
@@ -219,8 +246,14 @@ impl Page for ResPackPage {
                 let w = r.w - pad * 2.;
                 let mut h = 0.;
                 let r = Rect::new(pad, 0., r.w - pad * 2., 0.1);
+                let layers = &get_data().respack_layers;
                 for (index, item) in self.items.iter_mut().enumerate() {
-                    item.btn.render_text(ui, r, t, c.a, &item.name, 0.7, index == self.index);
+                    let layer = item.dir_name().and_then(|name| layers.iter().position(|it| *it == name));
+                    let text: Cow<str> = match layer {
+                        Some(pos) => tl!("layered-name", "name" => item.name.clone(), "pos" => pos + 1).into(),
+                        None => item.name.as_str().into(),
+                    };
+                    item.btn.render_text(ui, r, t, c.a, text, 0.7, index == self.index);
                     ui.dy(r.h + pad);
                     h += r.h + pad;
                 }
@@ -231,7 +264,7 @@ impl Page for ResPackPage {
             });
         });
         s.render_fader(ui, |ui, c| {
-            ui.fill_path(&cr.rounded(0.00), semi_black(c.a * 0.4));
+            ui.fill_path(&cr.rounded(0.00), theme().card(c.a));
             let item = &self.items[self.index];
             if let Some(res_pack) = &item.loaded {
                 let width = 0.16;
@@ -348,7 +381,7 @@ impl Page for ResPackPage {
                     ui.fill_rect(r, (tex, r, ScaleType::Fit, c));
                 } else if irnd != self.last_round {
                     if let Some(emitter) = &mut self.emitter {
-                        emitter.emit_at(vec2(cx, line), 0., fx_color);
+                        emitter.emit_at(vec2(cx, line), 0., fx_color, irnd % 2 != 0);
                     }
                     if let Some(sfxs) = &mut self.sfxs {
                         let _ = sfxs[(irnd % 3) as usize].play(PlaySfxParams::default());
@@ -376,6 +409,13 @@ impl Page for ResPackPage {
                 let r = r.feather(-0.02);
                 ui.fill_rect(r, (*self.icons.info, r, ScaleType::Fit, c));
             }
+            if self.index != 0 {
+                let layered = item.dir_name().map_or(false, |name| get_data().respack_layers.contains(&name));
+                let w = 0.26;
+                let r = Rect::new(tr.x - w - 0.02, tr.y, w, tr.h);
+                self.layer_btn
+                    .render_text(ui, r, t, c.a, if layered { tl!("layer-remove") } else { tl!("layer-add") }, 0.5, layered);
+            }
         });
         Ok(())
     }
@@ -1,16 +1,22 @@
 phire::tl_file!("settings");
 
-use super::{NextPage, OffsetPage, Page, SharedState};
-use crate::{get_data, get_data_mut, popup::ChooseButton, save_data, scene::BGM_VOLUME_UPDATED, sync_data};
+use super::{HudLayoutPage, NextPage, OffsetPage, Page, SharedState, StoragePage};
+use crate::{cloud, get_data, get_data_mut, popup::ChooseButton, save_data, scene::BGM_VOLUME_UPDATED, sync_data};
 use anyhow::Result;
+use chrono::Local;
 use macroquad::prelude::*;
 use phire::{
-    ext::{poll_future, semi_black, validate_combo, LocalTask, RectExt, SafeTexture, ScaleType},
+    config::{FullScreenNotes, HudPosition, JudgePreset, NotePalette, ProgressBarStyle},
+    ext::{poll_future, validate_combo, LocalTask, RectExt, SafeTexture, ScaleType},
     l10n::{LanguageIdentifier, LANG_IDENTS, LANG_NAMES},
     scene::{request_input, return_input, show_error, show_message, take_input},
-    ui::{DRectButton, Scroll, Slider, Ui},
+    ui::{set_memory_overlay, set_perf_overlay, theme, DRectButton, Scroll, Slider, Ui},
 };
 use std::{borrow::Cow, net::ToSocketAddrs, sync::atomic::Ordering};
+#[cfg(not(target_os = "android"))]
+use crate::dir;
+#[cfg(not(target_os = "android"))]
+use phire::ext::share_file;
 
 const ITEM_HEIGHT: f32 = 0.15;
 
@@ -174,7 +180,7 @@ impl Page for SettingsPage {
         let r = ui.content_rect();
         s.fader.render(ui, t, |ui, c| {
             let path = r.rounded(0.00);
-            ui.fill_path(&path, semi_black(0.4 * c.a));
+            ui.fill_path(&path, theme().card(c.a));
             let r = r.feather(-0.01);
             self.scroll.size((r.w, r.h));
             ui.scope(|ui| {
@@ -207,10 +213,12 @@ impl Page for SettingsPage {
     }
 
     fn next_page(&mut self) -> NextPage {
-        if matches!(self.chosen, SettingListType::Audio) {
-            return self.list_audio.next_page().unwrap_or_default();
+        match self.chosen {
+            SettingListType::General => self.list_general.next_page().unwrap_or_default(),
+            SettingListType::Audio => self.list_audio.next_page().unwrap_or_default(),
+            SettingListType::Chart => self.list_chart.next_page().unwrap_or_default(),
+            _ => NextPage::None,
         }
-        NextPage::None
     }
 }
 
@@ -278,8 +286,29 @@ struct GeneralList {
     offline_btn: DRectButton,
     mp_btn: DRectButton,
     mp_addr_btn: DRectButton,
+    translate_btn: DRectButton,
+    translate_api_btn: DRectButton,
     lowq_btn: DRectButton,
+    linear_btn: DRectButton,
+    fps_slider: Slider,
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    vsync_btn: DRectButton,
+    perf_btn: DRectButton,
+    memory_btn: DRectButton,
+    instanced_btn: DRectButton,
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    live_btn: DRectButton,
+    badges_btn: DRectButton,
     insecure_btn: DRectButton,
+    cloud_btn: DRectButton,
+    cloud_sync_btn: DRectButton,
+    storage_btn: DRectButton,
+    #[cfg(not(target_os = "android"))]
+    export_csv_btn: DRectButton,
+    #[cfg(not(target_os = "android"))]
+    export_json_btn: DRectButton,
+
+    next_page: Option<NextPage>,
 }
 
 impl GeneralList {
@@ -300,8 +329,29 @@ impl GeneralList {
             offline_btn: DRectButton::new(),
             mp_btn: DRectButton::new(),
             mp_addr_btn: DRectButton::new(),
+            translate_btn: DRectButton::new(),
+            translate_api_btn: DRectButton::new(),
             lowq_btn: DRectButton::new(),
+            linear_btn: DRectButton::new(),
+            fps_slider: Slider::new(0.0..240.0, 30.),
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            vsync_btn: DRectButton::new(),
+            perf_btn: DRectButton::new(),
+            memory_btn: DRectButton::new(),
+            instanced_btn: DRectButton::new(),
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            live_btn: DRectButton::new(),
+            badges_btn: DRectButton::new(),
             insecure_btn: DRectButton::new(),
+            cloud_btn: DRectButton::new(),
+            cloud_sync_btn: DRectButton::new(),
+            storage_btn: DRectButton::new(),
+            #[cfg(not(target_os = "android"))]
+            export_csv_btn: DRectButton::new(),
+            #[cfg(not(target_os = "android"))]
+            export_json_btn: DRectButton::new(),
+
+            next_page: None,
         }
     }
 
@@ -330,17 +380,115 @@ impl GeneralList {
             request_input("mp_addr", &config.mp_address, tl!("item-mp-addr"));
             return Ok(Some(true));
         }
+        if self.translate_btn.touch(touch, t) {
+            config.translate ^= true;
+            return Ok(Some(true));
+        }
+        if self.translate_api_btn.touch(touch, t) {
+            request_input("translate_api", &config.translate_api, tl!("item-translate-api"));
+            return Ok(Some(true));
+        }
         if self.lowq_btn.touch(touch, t) {
             config.sample_count = if config.sample_count == 1 { 2 } else { 1 };
             return Ok(Some(true));
         }
+        if self.linear_btn.touch(touch, t) {
+            config.linear_color ^= true;
+            return Ok(Some(true));
+        }
+        let mut fps = config.max_fps as f32;
+        if let wt @ Some(_) = self.fps_slider.touch(touch, t, &mut fps) {
+            config.max_fps = fps.round() as u32;
+            return Ok(wt);
+        }
+        #[cfg(not(any(target_os = "android", target_os = "ios")))]
+        if self.vsync_btn.touch(touch, t) {
+            config.vsync ^= true;
+            return Ok(Some(true));
+        }
+        if self.perf_btn.touch(touch, t) {
+            config.perf_overlay ^= true;
+            set_perf_overlay(config.perf_overlay);
+            return Ok(Some(true));
+        }
+        if self.memory_btn.touch(touch, t) {
+            config.memory_overlay ^= true;
+            set_memory_overlay(config.memory_overlay);
+            return Ok(Some(true));
+        }
+        if self.instanced_btn.touch(touch, t) {
+            config.instanced_notes ^= true;
+            return Ok(Some(true));
+        }
+        #[cfg(not(any(target_os = "android", target_os = "ios")))]
+        if self.live_btn.touch(touch, t) {
+            if config.live_stats {
+                phire::live::stop();
+                config.live_stats = false;
+            } else {
+                match phire::live::start(config.live_stats_port) {
+                    Ok(()) => config.live_stats = true,
+                    Err(err) => show_error(err.context(tl!("item-live-stats-failed"))),
+                }
+            }
+            return Ok(Some(true));
+        }
+        if self.badges_btn.touch(touch, t) {
+            data.hide_record_badges ^= true;
+            return Ok(Some(true));
+        }
         if self.insecure_btn.touch(touch, t) {
             data.accept_invalid_cert ^= true;
             return Ok(Some(true));
         }
+        if self.cloud_btn.touch(touch, t) {
+            data.cloud_sync ^= true;
+            if data.cloud_sync {
+                cloud::start_sync(true);
+            }
+            return Ok(Some(true));
+        }
+        if self.cloud_sync_btn.touch(touch, t) {
+            if !cloud::syncing() {
+                cloud::start_sync(true);
+            }
+            return Ok(Some(false));
+        }
+        if self.storage_btn.touch(touch, t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(StoragePage::new())));
+            return Ok(Some(false));
+        }
+        #[cfg(not(target_os = "android"))]
+        if self.export_csv_btn.touch(touch, t) {
+            Self::export_records(false);
+            return Ok(Some(false));
+        }
+        #[cfg(not(target_os = "android"))]
+        if self.export_json_btn.touch(touch, t) {
+            Self::export_records(true);
+            return Ok(Some(false));
+        }
         Ok(None)
     }
 
+    #[cfg(not(target_os = "android"))]
+    fn export_records(json: bool) {
+        let res: Result<()> = (|| {
+            let data = get_data();
+            let (bytes, ext) = if json {
+                (data.export_records_json()?, "json")
+            } else {
+                (data.export_records_csv()?, "csv")
+            };
+            let path = format!("{}/records.{ext}", dir::cache()?);
+            std::fs::write(&path, bytes)?;
+            share_file(&path)
+        })();
+        if let Err(err) = res {
+            show_error(err.context(tl!("item-export-failed")));
+        }
+    }
+
     pub fn update(&mut self, t: f32) -> Result<bool> {
         self.lang_btn.update(t);
         let data = get_data_mut();
@@ -358,6 +506,9 @@ impl GeneralList {
                     data.config.mp_address = text;
                     return Ok(true);
                 }
+            } else if id == "translate_api" {
+                data.config.translate_api = text.trim().to_owned();
+                return Ok(true);
             } else {
                 return_input(id, text);
             }
@@ -398,24 +549,102 @@ impl GeneralList {
             render_title(ui, c, tl!("item-mp-addr"), Some(tl!("item-mp-addr-sub")));
             self.mp_addr_btn.render_text(ui, rr, t, c.a, &config.mp_address, 0.4, false);
         }
+        item! {
+            render_title(ui, c, tl!("item-translate"), Some(tl!("item-translate-sub")));
+            render_switch(ui, rr, t, c, &mut self.translate_btn, config.translate);
+        }
+        item! {
+            render_title(ui, c, tl!("item-translate-api"), Some(tl!("item-translate-api-sub")));
+            self.translate_api_btn.render_text(ui, rr, t, c.a, &config.translate_api, 0.4, false);
+        }
         item! {
             render_title(ui, c, tl!("item-lowq"), Some(tl!("item-lowq-sub")));
             render_switch(ui, rr, t, c, &mut self.lowq_btn, config.sample_count == 1);
         }
+        item! {
+            render_title(ui, c, tl!("item-linear-color"), Some(tl!("item-linear-color-sub")));
+            render_switch(ui, rr, t, c, &mut self.linear_btn, config.linear_color);
+        }
+        item! {
+            render_title(ui, c, tl!("item-max-fps"), Some(tl!("item-max-fps-sub")));
+            let text = if config.max_fps == 0 { tl!("item-max-fps-unlimited").into_owned() } else { config.max_fps.to_string() };
+            self.fps_slider.render(ui, rr, t, c, config.max_fps as f32, text);
+        }
+        #[cfg(not(any(target_os = "android", target_os = "ios")))]
+        item! {
+            render_title(ui, c, tl!("item-vsync"), Some(tl!("item-vsync-sub")));
+            render_switch(ui, rr, t, c, &mut self.vsync_btn, config.vsync);
+        }
+        item! {
+            render_title(ui, c, tl!("item-perf-overlay"), Some(tl!("item-perf-overlay-sub")));
+            render_switch(ui, rr, t, c, &mut self.perf_btn, config.perf_overlay);
+        }
+        item! {
+            render_title(ui, c, tl!("item-memory-overlay"), Some(tl!("item-memory-overlay-sub")));
+            render_switch(ui, rr, t, c, &mut self.memory_btn, config.memory_overlay);
+        }
+        item! {
+            render_title(ui, c, tl!("item-instanced-notes"), Some(tl!("item-instanced-notes-sub")));
+            render_switch(ui, rr, t, c, &mut self.instanced_btn, config.instanced_notes);
+        }
+        #[cfg(not(any(target_os = "android", target_os = "ios")))]
+        item! {
+            render_title(ui, c, tl!("item-live-stats"), Some(Cow::Owned(tl!("item-live-stats-sub", "port" => config.live_stats_port.to_string()))));
+            render_switch(ui, rr, t, c, &mut self.live_btn, config.live_stats);
+        }
+        item! {
+            render_title(ui, c, tl!("item-record-badges"), Some(tl!("item-record-badges-sub")));
+            render_switch(ui, rr, t, c, &mut self.badges_btn, !data.hide_record_badges);
+        }
         item! {
             render_title(ui, c, tl!("item-insecure"), Some(tl!("item-insecure-sub")));
             render_switch(ui, rr, t, c, &mut self.insecure_btn, data.accept_invalid_cert);
         }
+        item! {
+            render_title(ui, c, tl!("item-cloud"), Some(tl!("item-cloud-sub")));
+            render_switch(ui, rr, t, c, &mut self.cloud_btn, data.cloud_sync);
+        }
+        item! {
+            let sub = match data.last_synced {
+                Some(time) => Cow::Owned(tl!("item-cloud-sync-last", "time" => time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())),
+                None => tl!("item-cloud-sync-never"),
+            };
+            render_title(ui, c, tl!("item-cloud-sync"), Some(sub));
+            let text = if cloud::syncing() { tl!("item-cloud-syncing") } else { tl!("item-cloud-sync-now") };
+            self.cloud_sync_btn.render_text(ui, rr, t, c.a, text, 0.5, false);
+        }
+        item! {
+            render_title(ui, c, tl!("item-storage"), Some(tl!("item-storage-sub")));
+            self.storage_btn.render_text(ui, rr, t, c.a, tl!("item-storage-manage"), 0.5, false);
+        }
+        // the Android activity has nothing to share the file with
+        #[cfg(not(target_os = "android"))]
+        item! {
+            render_title(ui, c, tl!("item-export"), Some(tl!("item-export-sub")));
+            let mut r = rr;
+            r.w = (r.w - 0.01) / 2.;
+            self.export_csv_btn.render_text(ui, r, t, c.a, "CSV", 0.5, false);
+            r.x += r.w + 0.01;
+            self.export_json_btn.render_text(ui, r, t, c.a, "JSON", 0.5, false);
+        }
         self.lang_btn.render_top(ui, t, c.a);
         (w, h)
     }
+
+    pub fn next_page(&mut self) -> Option<NextPage> {
+        self.next_page.take()
+    }
 }
 
 struct AudioList {
     adjust_btn: DRectButton,
     music_slider: Slider,
     sfx_slider: Slider,
+    limiter_btn: DRectButton,
+    voices_slider: Slider,
+    premix_btn: DRectButton,
     bgm_slider: Slider,
+    list_preview_btn: DRectButton,
     cali_btn: DRectButton,
     #[cfg(target_os = "android")]
     audio_compatibility_btn: DRectButton,
@@ -430,7 +659,11 @@ impl AudioList {
             adjust_btn: DRectButton::new(),
             music_slider: Slider::new(0.0..2.0, 0.05),
             sfx_slider: Slider::new(0.0..2.0, 0.05),
+            limiter_btn: DRectButton::new(),
+            voices_slider: Slider::new(4.0..64.0, 1.),
+            premix_btn: DRectButton::new(),
             bgm_slider: Slider::new(0.0..2.0, 0.05),
+            list_preview_btn: DRectButton::new(),
             cali_btn: DRectButton::new(),
             #[cfg(target_os = "android")]
             audio_compatibility_btn: DRectButton::new(),
@@ -457,6 +690,19 @@ impl AudioList {
         if let wt @ Some(_) = self.sfx_slider.touch(touch, t, &mut config.volume_sfx) {
             return Ok(wt);
         }
+        if self.limiter_btn.touch(touch, t) {
+            config.sfx_limiter ^= true;
+            return Ok(Some(true));
+        }
+        let mut voices = config.max_sfx_voices as f32;
+        if let wt @ Some(_) = self.voices_slider.touch(touch, t, &mut voices) {
+            config.max_sfx_voices = voices.round() as u32;
+            return Ok(wt);
+        }
+        if self.premix_btn.touch(touch, t) {
+            config.premix_hitsounds ^= true;
+            return Ok(Some(true));
+        }
         let old = config.volume_bgm;
         if let wt @ Some(_) = self.bgm_slider.touch(touch, t, &mut config.volume_bgm) {
             if (config.volume_bgm - old).abs() > 0.001 {
@@ -464,6 +710,10 @@ impl AudioList {
             }
             return Ok(wt);
         }
+        if self.list_preview_btn.touch(touch, t) {
+            config.list_preview ^= true;
+            return Ok(Some(true));
+        }
         if self.cali_btn.touch(touch, t) {
             self.cali_task = Some(Box::pin(OffsetPage::new()));
             return Ok(Some(false));
@@ -517,10 +767,26 @@ impl AudioList {
             render_title(ui, c, tl!("item-sfx"), None);
             self.sfx_slider.render(ui, rr, t, c, config.volume_sfx, format!("{:.2}", config.volume_sfx));
         }
+        item! {
+            render_title(ui, c, tl!("item-sfx-limiter"), Some(tl!("item-sfx-limiter-sub")));
+            render_switch(ui, rr, t, c, &mut self.limiter_btn, config.sfx_limiter);
+        }
+        item! {
+            render_title(ui, c, tl!("item-sfx-voices"), Some(tl!("item-sfx-voices-sub")));
+            self.voices_slider.render(ui, rr, t, c, config.max_sfx_voices as f32, config.max_sfx_voices.to_string());
+        }
+        item! {
+            render_title(ui, c, tl!("item-premix"), Some(tl!("item-premix-sub")));
+            render_switch(ui, rr, t, c, &mut self.premix_btn, config.premix_hitsounds);
+        }
         item! {
             render_title(ui, c, tl!("item-bgm"), None);
             self.bgm_slider.render(ui, rr, t, c, config.volume_bgm, format!("{:.2}", config.volume_bgm));
         }
+        item! {
+            render_title(ui, c, tl!("item-list-preview"), Some(tl!("item-list-preview-sub")));
+            render_switch(ui, rr, t, c, &mut self.list_preview_btn, config.list_preview);
+        }
         item! {
             render_title(ui, c, tl!("item-cali"), None);
             self.cali_btn.render_text(ui, rr, t, c.a, format!("{:.0}ms", config.offset * 1000.), 0.5, true);
@@ -538,30 +804,97 @@ impl AudioList {
     }
 }
 
+const HUD_POSITION_LABELS: [&str; 4] = ["hud-position-score", "hud-position-top-left", "hud-position-bottom-left", "hud-position-bottom-right"];
+const PROGRESS_BAR_LABELS: [&str; 3] = ["progress-bar-top", "progress-bar-circle", "progress-bar-sections"];
+const NOTE_PALETTE_LABELS: [&str; 4] = ["note-palette-off", "note-palette-high-contrast", "note-palette-red-green", "note-palette-blue-yellow"];
+const JUDGE_PRESET_LABELS: [&str; 4] = ["judge-preset-standard", "judge-preset-strict", "judge-preset-lenient", "judge-preset-custom"];
+const FULL_SCREEN_NOTES: [(FullScreenNotes, &str); 4] = [
+    (FullScreenNotes::TAP, "note-tap"),
+    (FullScreenNotes::DRAG, "note-drag"),
+    (FullScreenNotes::FLICK, "note-flick"),
+    (FullScreenNotes::HOLD, "note-hold"),
+];
+
 struct ChartList {
     show_acc_btn: DRectButton,
+    acc_hud_counts_btn: DRectButton,
+    acc_hud_position_btn: ChooseButton,
+    hud_layout_btn: DRectButton,
+    progress_bar_btn: ChooseButton,
     dc_pause_btn: DRectButton,
+    duck_btn: DRectButton,
     dhint_btn: DRectButton,
+    curved_holds_btn: DRectButton,
     opt_btn: DRectButton,
     speed_slider: Slider,
     size_slider: Slider,
+    note_palette_btn: ChooseButton,
+    note_markers_btn: DRectButton,
+    hit_area_slider: Slider,
+    full_screen_btns: [DRectButton; 4],
     render_extra_btn: DRectButton,
+    judge_preset_btn: ChooseButton,
+    perfect_slider: Slider,
+    good_slider: Slider,
+    bad_slider: Slider,
+    hold_leniency_slider: Slider,
+    hold_no_penalty_btn: DRectButton,
+
+    next_page: Option<NextPage>,
 }
 
 impl ChartList {
     pub fn new() -> Self {
         Self {
             show_acc_btn: DRectButton::new(),
+            acc_hud_counts_btn: DRectButton::new(),
+            acc_hud_position_btn: ChooseButton::new()
+                .with_options(HUD_POSITION_LABELS.iter().map(|it| tl!(it).into_owned()).collect())
+                .with_selected(HudPosition::ALL.iter().position(|it| *it == get_data().config.acc_hud_position).unwrap_or_default()),
+            hud_layout_btn: DRectButton::new(),
+            progress_bar_btn: ChooseButton::new()
+                .with_options(PROGRESS_BAR_LABELS.iter().map(|it| tl!(it).into_owned()).collect())
+                .with_selected(ProgressBarStyle::ALL.iter().position(|it| *it == get_data().config.progress_bar_style).unwrap_or_default()),
             dc_pause_btn: DRectButton::new(),
+            duck_btn: DRectButton::new(),
             dhint_btn: DRectButton::new(),
+            curved_holds_btn: DRectButton::new(),
             opt_btn: DRectButton::new(),
             speed_slider: Slider::new(0.1..2.0, 0.05),
             size_slider: Slider::new(0.0..5.0, 0.005),
+            note_palette_btn: ChooseButton::new()
+                .with_options(NOTE_PALETTE_LABELS.iter().map(|it| tl!(it).into_owned()).collect())
+                .with_selected(NotePalette::ALL.iter().position(|it| *it == get_data().config.note_palette).unwrap_or_default()),
+            note_markers_btn: DRectButton::new(),
+            hit_area_slider: Slider::new(0.5..3.0, 0.05),
+            full_screen_btns: [(); 4].map(|_| DRectButton::new()),
             render_extra_btn: DRectButton::new(),
+            judge_preset_btn: ChooseButton::new()
+                .with_options(JUDGE_PRESET_LABELS.iter().map(|it| tl!(it).into_owned()).collect())
+                .with_selected(JudgePreset::ALL.iter().position(|it| *it == get_data().config.judge_preset).unwrap_or_default()),
+            perfect_slider: Slider::new(0.01..0.3, 0.005),
+            good_slider: Slider::new(0.01..0.3, 0.005),
+            bad_slider: Slider::new(0.01..0.3, 0.005),
+            hold_leniency_slider: Slider::new(0.0..0.5, 0.01),
+            hold_no_penalty_btn: DRectButton::new(),
+
+            next_page: None,
         }
     }
 
-    pub fn top_touch(&mut self, _touch: &Touch, _t: f32) -> bool {
+    pub fn top_touch(&mut self, touch: &Touch, t: f32) -> bool {
+        if self.acc_hud_position_btn.top_touch(touch, t) {
+            return true;
+        }
+        if self.progress_bar_btn.top_touch(touch, t) {
+            return true;
+        }
+        if self.note_palette_btn.top_touch(touch, t) {
+            return true;
+        }
+        if self.judge_preset_btn.top_touch(touch, t) {
+            return true;
+        }
         false
     }
 
@@ -572,14 +905,38 @@ impl ChartList {
             config.show_acc ^= true;
             return Ok(Some(true));
         }
+        if config.show_acc {
+            if self.acc_hud_counts_btn.touch(touch, t) {
+                config.acc_hud_counts ^= true;
+                return Ok(Some(true));
+            }
+            if self.acc_hud_position_btn.touch(touch, t) {
+                return Ok(Some(false));
+            }
+        }
+        if self.hud_layout_btn.touch(touch, t) {
+            self.next_page = Some(NextPage::Overlay(Box::new(HudLayoutPage::new())));
+            return Ok(Some(false));
+        }
+        if self.progress_bar_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
         if self.dc_pause_btn.touch(touch, t) {
             config.double_click_to_pause ^= true;
             return Ok(Some(true));
         }
+        if self.duck_btn.touch(touch, t) {
+            config.duck_on_miss ^= true;
+            return Ok(Some(true));
+        }
         if self.dhint_btn.touch(touch, t) {
             config.render_double_hint ^= true;
             return Ok(Some(true));
         }
+        if self.curved_holds_btn.touch(touch, t) {
+            config.curved_holds ^= true;
+            return Ok(Some(true));
+        }
         if self.opt_btn.touch(touch, t) {
             config.aggressive ^= true;
             return Ok(Some(true));
@@ -590,14 +947,78 @@ impl ChartList {
         if let wt @ Some(_) = self.size_slider.touch(touch, t, &mut config.note_scale) {
             return Ok(wt);
         }
+        if self.note_palette_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
+        if self.note_markers_btn.touch(touch, t) {
+            config.note_shape_markers ^= true;
+            return Ok(Some(true));
+        }
+        if let wt @ Some(_) = self.hit_area_slider.touch(touch, t, &mut config.hit_area_scale) {
+            return Ok(wt);
+        }
+        for (btn, (flag, _)) in self.full_screen_btns.iter_mut().zip(FULL_SCREEN_NOTES) {
+            if btn.touch(touch, t) {
+                config.full_screen_notes.toggle(flag);
+                return Ok(Some(true));
+            }
+        }
         if self.render_extra_btn.touch(touch, t) {
             config.render_extra ^= true;
             return Ok(Some(true));
         }
+        if self.judge_preset_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
+        if config.judge_preset == JudgePreset::Custom {
+            let windows = &mut config.custom_judge_windows;
+            if let wt @ Some(_) = self.perfect_slider.touch(touch, t, &mut windows.perfect) {
+                windows.good = windows.good.max(windows.perfect);
+                windows.bad = windows.bad.max(windows.good);
+                return Ok(wt);
+            }
+            if let wt @ Some(_) = self.good_slider.touch(touch, t, &mut windows.good) {
+                windows.perfect = windows.perfect.min(windows.good);
+                windows.bad = windows.bad.max(windows.good);
+                return Ok(wt);
+            }
+            if let wt @ Some(_) = self.bad_slider.touch(touch, t, &mut windows.bad) {
+                windows.good = windows.good.min(windows.bad);
+                windows.perfect = windows.perfect.min(windows.good);
+                return Ok(wt);
+            }
+        }
+        if let wt @ Some(_) = self.hold_leniency_slider.touch(touch, t, &mut config.hold_release_leniency) {
+            return Ok(wt);
+        }
+        if self.hold_no_penalty_btn.touch(touch, t) {
+            config.hold_no_release_penalty ^= true;
+            return Ok(Some(true));
+        }
         Ok(None)
     }
 
-    pub fn update(&mut self, _t: f32) -> Result<bool> {
+    pub fn update(&mut self, t: f32) -> Result<bool> {
+        self.acc_hud_position_btn.update(t);
+        if self.acc_hud_position_btn.changed() {
+            get_data_mut().config.acc_hud_position = HudPosition::ALL[self.acc_hud_position_btn.selected()];
+            return Ok(true);
+        }
+        self.progress_bar_btn.update(t);
+        if self.progress_bar_btn.changed() {
+            get_data_mut().config.progress_bar_style = ProgressBarStyle::ALL[self.progress_bar_btn.selected()];
+            return Ok(true);
+        }
+        self.note_palette_btn.update(t);
+        if self.note_palette_btn.changed() {
+            get_data_mut().config.note_palette = NotePalette::ALL[self.note_palette_btn.selected()];
+            return Ok(true);
+        }
+        self.judge_preset_btn.update(t);
+        if self.judge_preset_btn.changed() {
+            get_data_mut().config.judge_preset = JudgePreset::ALL[self.judge_preset_btn.selected()];
+            return Ok(true);
+        }
         Ok(false)
     }
 
@@ -619,14 +1040,40 @@ impl ChartList {
             render_title(ui, c, tl!("item-show-acc"), None);
             render_switch(ui, rr, t, c, &mut self.show_acc_btn, config.show_acc);
         }
+        if config.show_acc {
+            item! {
+                render_title(ui, c, tl!("item-acc-hud-counts"), Some(tl!("item-acc-hud-counts-sub")));
+                render_switch(ui, rr, t, c, &mut self.acc_hud_counts_btn, config.acc_hud_counts);
+            }
+            item! {
+                render_title(ui, c, tl!("item-acc-hud-position"), None);
+                self.acc_hud_position_btn.render(ui, rr, t, c.a);
+            }
+        }
+        item! {
+            render_title(ui, c, tl!("item-hud-layout"), Some(tl!("item-hud-layout-sub")));
+            self.hud_layout_btn.render_text(ui, rr, t, c.a, tl!("item-hud-layout-edit"), 0.5, false);
+        }
+        item! {
+            render_title(ui, c, tl!("item-progress-bar"), None);
+            self.progress_bar_btn.render(ui, rr, t, c.a);
+        }
         item! {
             render_title(ui, c, tl!("item-dc-pause"), None);
             render_switch(ui, rr, t, c, &mut self.dc_pause_btn, config.double_click_to_pause);
         }
+        item! {
+            render_title(ui, c, tl!("item-duck-on-miss"), Some(tl!("item-duck-on-miss-sub")));
+            render_switch(ui, rr, t, c, &mut self.duck_btn, config.duck_on_miss);
+        }
         item! {
             render_title(ui, c, tl!("item-dhint"), Some(tl!("item-dhint-sub")));
             render_switch(ui, rr, t, c, &mut self.dhint_btn, config.render_double_hint);
         }
+        item! {
+            render_title(ui, c, tl!("item-curved-holds"), Some(tl!("item-curved-holds-sub")));
+            render_switch(ui, rr, t, c, &mut self.curved_holds_btn, config.curved_holds);
+        }
         item! {
             render_title(ui, c, tl!("item-opt"), Some(tl!("item-opt-sub")));
             render_switch(ui, rr, t, c, &mut self.opt_btn, config.aggressive);
@@ -639,18 +1086,77 @@ impl ChartList {
             render_title(ui, c, tl!("item-note-size"), None);
             self.size_slider.render(ui, rr, t,c, config.note_scale, format!("{:.3}", config.note_scale));
         }
+        item! {
+            render_title(ui, c, tl!("item-note-palette"), Some(tl!("item-note-palette-sub")));
+            self.note_palette_btn.render(ui, rr, t, c.a);
+        }
+        item! {
+            render_title(ui, c, tl!("item-note-markers"), Some(tl!("item-note-markers-sub")));
+            render_switch(ui, rr, t, c, &mut self.note_markers_btn, config.note_shape_markers);
+        }
+        item! {
+            render_title(ui, c, tl!("item-hit-area"), Some(tl!("item-hit-area-sub")));
+            self.hit_area_slider.render(ui, rr, t, c, config.hit_area_scale, format!("{:.2}x", config.hit_area_scale));
+        }
+        item! {
+            render_title(ui, c, tl!("item-full-screen-notes"), Some(tl!("item-full-screen-notes-sub")));
+            let pad = 0.01;
+            let bw = (rr.w * 1.6 - pad * 3.) / 4.;
+            let mut r = Rect::new(rr.right() - bw * 4. - pad * 3., rr.y, bw, rr.h);
+            for (btn, (flag, label)) in self.full_screen_btns.iter_mut().zip(FULL_SCREEN_NOTES) {
+                btn.render_text(ui, r, t, c.a, tl!(label), 0.4, config.full_screen_notes.contains(flag));
+                r.x += bw + pad;
+            }
+        }
         item! {
             render_title(ui, c, tl!("item-render-extra"), None);
             render_switch(ui, rr, t, c, &mut self.render_extra_btn, config.render_extra);
         }
+        item! {
+            render_title(ui, c, tl!("item-judge-preset"), Some(tl!("item-judge-preset-sub")));
+            self.judge_preset_btn.render(ui, rr, t, c.a);
+        }
+        if config.judge_preset == JudgePreset::Custom {
+            let windows = &config.custom_judge_windows;
+            item! {
+                render_title(ui, c, tl!("item-judge-perfect"), None);
+                self.perfect_slider.render(ui, rr, t, c, windows.perfect, format!("{:.0}ms", windows.perfect * 1000.));
+            }
+            item! {
+                render_title(ui, c, tl!("item-judge-good"), None);
+                self.good_slider.render(ui, rr, t, c, windows.good, format!("{:.0}ms", windows.good * 1000.));
+            }
+            item! {
+                render_title(ui, c, tl!("item-judge-bad"), None);
+                self.bad_slider.render(ui, rr, t, c, windows.bad, format!("{:.0}ms", windows.bad * 1000.));
+            }
+        }
+        item! {
+            render_title(ui, c, tl!("item-hold-leniency"), Some(tl!("item-hold-leniency-sub")));
+            self.hold_leniency_slider.render(ui, rr, t, c, config.hold_release_leniency, format!("{:.0}ms", config.hold_release_leniency * 1000.));
+        }
+        item! {
+            render_title(ui, c, tl!("item-hold-no-penalty"), Some(tl!("item-hold-no-penalty-sub")));
+            render_switch(ui, rr, t, c, &mut self.hold_no_penalty_btn, config.hold_no_release_penalty);
+        }
+        self.acc_hud_position_btn.render_top(ui, t, c.a);
+        self.progress_bar_btn.render_top(ui, t, c.a);
+        self.note_palette_btn.render_top(ui, t, c.a);
+        self.judge_preset_btn.render_top(ui, t, c.a);
         (w, h)
     }
+
+    pub fn next_page(&mut self) -> Option<NextPage> {
+        self.next_page.take()
+    }
 }
 
 struct OtherList {
     chart_debug_line_slider: Slider,
     chart_debug_note_slider: Slider,
     touch_debug_btn: DRectButton,
+    keyboard_lanes_btn: DRectButton,
+    key_lanes_btn: DRectButton,
     chart_ratio_slider: Slider,
     fade_slider: Slider,
     watermark: DRectButton,
@@ -661,6 +1167,7 @@ struct OtherList {
     rotation_flat_mode: DRectButton,
     #[cfg(feature = "play")]
     shake_play_mode_btn: DRectButton,
+    hot_reload_btn: DRectButton,
 }
 
 impl OtherList {
@@ -669,6 +1176,8 @@ impl OtherList {
             chart_debug_line_slider: Slider::new(0.0..1.0, 0.05),
             chart_debug_note_slider: Slider::new(0.0..1.0, 0.05),
             touch_debug_btn: DRectButton::new(),
+            keyboard_lanes_btn: DRectButton::new(),
+            key_lanes_btn: DRectButton::new(),
             chart_ratio_slider: Slider::new(0.05..1.0, 0.05),
             fade_slider: Slider::new(-2.0..2.0, 0.05),
             watermark: DRectButton::new(),
@@ -679,6 +1188,7 @@ impl OtherList {
             rotation_flat_mode: DRectButton::new(),
             #[cfg(feature = "play")]
             shake_play_mode_btn: DRectButton::new(),
+            hot_reload_btn: DRectButton::new(),
         }
     }
 
@@ -699,6 +1209,14 @@ impl OtherList {
             config.touch_debug ^= true;
             return Ok(Some(true));
         }
+        if self.keyboard_lanes_btn.touch(touch, t) {
+            config.keyboard_lanes ^= true;
+            return Ok(Some(true));
+        }
+        if self.key_lanes_btn.touch(touch, t) {
+            request_input("key_lanes", &config.key_lanes, tl!("item-key-lanes"));
+            return Ok(Some(true));
+        }
         if let wt @ Some(_) = self.chart_ratio_slider.touch(touch, t, &mut config.chart_ratio) {
             return Ok(wt);
         }
@@ -746,6 +1264,10 @@ impl OtherList {
             config.shake_play_mode ^= true;
             return Ok(Some(true));
         }
+        if self.hot_reload_btn.touch(touch, t) {
+            config.hot_reload ^= true;
+            return Ok(Some(true));
+        }
         Ok(None)
     }
 
@@ -755,6 +1277,9 @@ impl OtherList {
             if id == "watermark" {
                 data.config.watermark = text;
                 return Ok(true);
+            } else if id == "key_lanes" {
+                data.config.key_lanes = text.trim().to_uppercase();
+                return Ok(true);
             } else {
                 return_input(id, text);
             }
@@ -800,6 +1325,14 @@ impl OtherList {
             render_title(ui, c, tl!("item-touch-debug"), Some(tl!("item-touch-debug-sub")));
             render_switch(ui, rr, t, c, &mut self.touch_debug_btn, config.touch_debug);
         }
+        item! {
+            render_title(ui, c, tl!("item-keyboard-lanes"), Some(tl!("item-keyboard-lanes-sub")));
+            render_switch(ui, rr, t, c, &mut self.keyboard_lanes_btn, config.keyboard_lanes);
+        }
+        item! {
+            render_title(ui, c, tl!("item-key-lanes"), Some(tl!("item-key-lanes-sub")));
+            self.key_lanes_btn.render_text(ui, rr, t, c.a, &config.key_lanes, 0.4, false);
+        }
         item! {
             render_title(ui, c, tl!("item-chart_ratio"), None);
             self.chart_ratio_slider.render(ui, rr, t,c, config.chart_ratio, format!("{:.2}", config.chart_ratio));
@@ -837,6 +1370,10 @@ impl OtherList {
             render_title(ui, c, tl!("item-shake-play-mode"), None);
             render_switch(ui, rr, t, c, &mut self.shake_play_mode_btn, config.shake_play_mode);
         }
+        item! {
+            render_title(ui, c, tl!("item-hot-reload"), Some(tl!("item-hot-reload-sub")));
+            render_switch(ui, rr, t, c, &mut self.hot_reload_btn, config.hot_reload);
+        }
         (w, h)
     }
 }
@@ -0,0 +1,332 @@
+phire::tl_file!("storage");
+
+use super::{Page, SharedState};
+use crate::{chart_index, charts_view::NEED_UPDATE, dir, get_data_mut, save_data, scene::confirm_dialog};
+use anyhow::Result;
+use macroquad::prelude::*;
+use phire::{
+    ext::RectExt,
+    log,
+    scene::{show_error, show_message},
+    task::Task,
+    ui::{theme, DRectButton, Scroll, Ui},
+};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+const ITEM_HEIGHT: f32 = 0.15;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StorageKind {
+    Downloaded,
+    Custom,
+    Respack,
+    ImageCache,
+    Logs,
+}
+
+impl StorageKind {
+    const ALL: [StorageKind; 5] = [Self::Downloaded, Self::Custom, Self::Respack, Self::ImageCache, Self::Logs];
+
+    fn dir(&self) -> Result<String> {
+        match self {
+            Self::Downloaded => dir::downloaded_charts(),
+            Self::Custom => dir::custom_charts(),
+            Self::Respack => dir::respacks(),
+            Self::ImageCache => dir::cache_image_local(),
+            Self::Logs => dir::logs(),
+        }
+    }
+
+    fn label(&self) -> Cow<'static, str> {
+        match self {
+            Self::Downloaded => tl!("downloaded"),
+            Self::Custom => tl!("custom"),
+            Self::Respack => tl!("respack"),
+            Self::ImageCache => tl!("image-cache"),
+            Self::Logs => tl!("logs"),
+        }
+    }
+
+    // removes everything under the folder but the log being written, run off the UI thread
+    fn clean_files(dir: &str) -> Result<()> {
+        let current_log = log::log_file().map(PathBuf::from);
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                std::fs::remove_dir_all(path)?;
+            } else if current_log.as_ref() != Some(&path) {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // drops the references kept in data to what `clean_files` removed
+    fn forget(&self) -> Result<()> {
+        let data = get_data_mut();
+        match self {
+            Self::Downloaded => {
+                data.charts.retain(|it| !it.local_path.starts_with("download/"));
+                chart_index::remove("download");
+            }
+            Self::Custom => {
+                data.charts.retain(|it| !it.local_path.starts_with("custom/"));
+                chart_index::remove("custom");
+            }
+            Self::Respack => {
+                data.respacks.clear();
+                data.respack_id = 0;
+            }
+            _ => return Ok(()),
+        }
+        save_data()
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|it| it.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut size = size as f64 / 1024.;
+    let mut unit = 0;
+    while size >= 1024. && unit + 1 < UNITS.len() {
+        size /= 1024.;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+struct StorageItem {
+    kind: StorageKind,
+    size: Option<u64>,
+    btn: DRectButton,
+}
+
+pub struct StoragePage {
+    items: Vec<StorageItem>,
+    scroll: Scroll,
+
+    sort_btn: DRectButton,
+    sort_by_size: bool,
+    clean_all_btn: DRectButton,
+
+    size_task: Option<Task<Vec<(StorageKind, u64)>>>,
+
+    should_clean: Arc<AtomicBool>,
+    clean_target: Vec<StorageKind>,
+    clean_task: Option<Task<Vec<(StorageKind, Result<()>)>>>,
+}
+
+impl StoragePage {
+    pub fn new() -> Self {
+        let mut res = Self {
+            items: StorageKind::ALL
+                .into_iter()
+                .map(|kind| StorageItem {
+                    kind,
+                    size: None,
+                    btn: DRectButton::new(),
+                })
+                .collect(),
+            scroll: Scroll::new(),
+
+            sort_btn: DRectButton::new(),
+            sort_by_size: false,
+            clean_all_btn: DRectButton::new(),
+
+            size_task: None,
+
+            should_clean: Arc::default(),
+            clean_target: Vec::new(),
+            clean_task: None,
+        };
+        res.refresh();
+        res
+    }
+
+    fn refresh(&mut self) {
+        for item in &mut self.items {
+            item.size = None;
+        }
+        let dirs: Vec<_> = StorageKind::ALL.into_iter().filter_map(|kind| Some((kind, kind.dir().ok()?))).collect();
+        self.size_task = Some(Task::new(async move {
+            dirs.into_iter().map(|(kind, dir)| (kind, dir_size(Path::new(&dir)))).collect()
+        }));
+    }
+
+    fn sort(&mut self) {
+        if self.sort_by_size {
+            self.items.sort_by(|x, y| y.size.unwrap_or_default().cmp(&x.size.unwrap_or_default()));
+        } else {
+            self.items
+                .sort_by_key(|it| StorageKind::ALL.iter().position(|kind| *kind == it.kind).unwrap_or_default());
+        }
+    }
+
+    fn total(&self) -> Option<u64> {
+        self.items.iter().map(|it| it.size).sum()
+    }
+
+    fn request_clean(&mut self, target: Vec<StorageKind>) {
+        let content = if target.len() == 1 {
+            tl!("clean-confirm-content", "name" => target[0].label().into_owned())
+        } else {
+            tl!("clean-all-confirm-content").into_owned()
+        };
+        self.clean_target = target;
+        confirm_dialog(tl!("clean-confirm"), content, Arc::clone(&self.should_clean));
+    }
+}
+
+impl Page for StoragePage {
+    fn label(&self) -> Cow<'static, str> {
+        "STORAGE".into()
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        let t = s.t;
+        if self.sort_btn.touch(touch, t) {
+            self.sort_by_size ^= true;
+            self.sort();
+            return Ok(true);
+        }
+        if self.clean_all_btn.touch(touch, t) && self.clean_task.is_none() {
+            self.request_clean(StorageKind::ALL.to_vec());
+            return Ok(true);
+        }
+        if self.scroll.touch(touch, t) {
+            return Ok(true);
+        }
+        if let Some(kind) = self
+            .items
+            .iter_mut()
+            .find(|it| it.btn.touch(touch, t))
+            .map(|it| it.kind)
+            .filter(|_| self.clean_task.is_none())
+        {
+            self.request_clean(vec![kind]);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn update(&mut self, s: &mut SharedState) -> Result<()> {
+        self.scroll.update(s.t);
+        if let Some(task) = &mut self.size_task {
+            if let Some(sizes) = task.take() {
+                for (kind, size) in sizes {
+                    if let Some(item) = self.items.iter_mut().find(|it| it.kind == kind) {
+                        item.size = Some(size);
+                    }
+                }
+                self.sort();
+                self.size_task = None;
+            }
+        }
+        if self.should_clean.fetch_and(false, Ordering::Relaxed) {
+            let dirs: Vec<_> = std::mem::take(&mut self.clean_target).into_iter().map(|kind| (kind, kind.dir())).collect();
+            show_message(tl!("cleaning"));
+            self.clean_task = Some(Task::new(async move {
+                dirs.into_iter()
+                    .map(|(kind, dir)| (kind, dir.and_then(|dir| StorageKind::clean_files(&dir))))
+                    .collect()
+            }));
+        }
+        if let Some(task) = &mut self.clean_task {
+            if let Some(results) = task.take() {
+                self.clean_task = None;
+                // data is only touched here on the UI thread, and only for the folders that were emptied
+                let res: Result<()> = results.into_iter().try_for_each(|(kind, res)| res.and_then(|_| kind.forget()));
+                match res {
+                    Err(err) => show_error(err.context(tl!("clean-failed"))),
+                    Ok(_) => {
+                        show_message(tl!("cleaned")).ok();
+                    }
+                }
+                s.reload_local_charts();
+                NEED_UPDATE.store(true, Ordering::Relaxed);
+                self.refresh();
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let t = s.t;
+        let r = ui.content_rect();
+        s.render_fader(ui, |ui, c| {
+            let h = r.y + ui.top - 0.06;
+            let total = self.total().map_or_else(|| tl!("calculating").into_owned(), |it| tl!("total", "size" => format_size(it)));
+            ui.text(total)
+                .pos(r.x, -ui.top + 0.04 + h / 2.)
+                .anchor(0., 0.5)
+                .no_baseline()
+                .size(0.6)
+                .color(c)
+                .draw();
+            let w = 0.3;
+            let mut br = Rect::new(r.right() - w, -ui.top + 0.04, w, h);
+            self.clean_all_btn.render_text(ui, br, t, c.a, tl!("clean-all"), 0.6, false);
+            br.x -= w + 0.02;
+            self.sort_btn.render_text(ui, br, t, c.a, tl!("sort-by-size"), 0.6, self.sort_by_size);
+        });
+        s.fader.render(ui, t, |ui, c| {
+            ui.fill_path(&r.rounded(0.00), theme().card(c.a));
+            let r = r.feather(-0.01);
+            self.scroll.size((r.w, r.h));
+            ui.scope(|ui| {
+                ui.dx(r.x);
+                ui.dy(r.y);
+                self.scroll.render(ui, |ui| {
+                    let mut h = 0.;
+                    let rh = ITEM_HEIGHT * 2. / 3.;
+                    let br = Rect::new(r.w - 0.3, (ITEM_HEIGHT - rh) / 2., 0.26, rh);
+                    for item in &mut self.items {
+                        ui.text(item.kind.label())
+                            .pos(0.06, ITEM_HEIGHT / 2.)
+                            .anchor(0., 0.5)
+                            .no_baseline()
+                            .size(0.6)
+                            .color(c)
+                            .draw();
+                        let size = item.size.map_or_else(|| "...".to_owned(), format_size);
+                        ui.text(size)
+                            .pos(br.x - 0.04, ITEM_HEIGHT / 2.)
+                            .anchor(1., 0.5)
+                            .no_baseline()
+                            .size(0.5)
+                            .color(Color { a: c.a * 0.6, ..c })
+                            .draw();
+                        item.btn.render_text(ui, br, t, c.a, tl!("clean"), 0.5, false);
+                        ui.dy(ITEM_HEIGHT);
+                        h += ITEM_HEIGHT;
+                    }
+                    (r.w, h)
+                });
+            });
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+phire::tl_file!("achievement");
+
+use super::{Page, SharedState};
+use crate::{achievement::ACHIEVEMENTS, get_data};
+use anyhow::Result;
+use chrono::Local;
+use macroquad::prelude::*;
+use phire::{
+    ext::{semi_black, semi_white, RectExt},
+    ui::{theme, Scroll, Ui},
+};
+
+const ROW_HEIGHT: f32 = 0.12;
+const UNLOCKED_COLOR: Color = Color::new(1., 0.84, 0.3, 1.);
+
+pub struct TrophyPage {
+    scroll: Scroll,
+}
+
+impl TrophyPage {
+    pub fn new() -> Self {
+        Self { scroll: Scroll::new() }
+    }
+}
+
+impl Page for TrophyPage {
+    fn label(&self) -> std::borrow::Cow<'static, str> {
+        "TROPHIES".into()
+    }
+
+    fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
+        Ok(self.scroll.touch(touch, s.t))
+    }
+
+    fn update(&mut self, s: &mut SharedState) -> Result<()> {
+        self.scroll.update(s.t);
+        Ok(())
+    }
+
+    fn render(&mut self, ui: &mut Ui, s: &mut SharedState) -> Result<()> {
+        let cr = ui.content_rect();
+        let d = 0.29;
+        let side = Rect::new(cr.x, cr.y, d - 0.02, cr.h);
+        let list = Rect::new(cr.x + d, cr.y, cr.w - d, cr.h);
+        let data = get_data();
+        let unlocked = ACHIEVEMENTS.iter().filter(|it| it.unlocked(data).is_some()).count();
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&side.rounded(0.02), theme().card(c.a));
+            let ct = side.center();
+            ui.text(tl!("unlocked-count"))
+                .pos(ct.x, side.y + 0.05)
+                .anchor(0.5, 0.)
+                .size(0.5)
+                .color(semi_white(c.a * 0.7))
+                .draw();
+            ui.text(format!("{unlocked} / {}", ACHIEVEMENTS.len()))
+                .pos(ct.x, side.y + 0.12)
+                .anchor(0.5, 0.)
+                .size(1.)
+                .color(c)
+                .draw();
+            ui.text(tl!("total-plays", "count" => data.play_count))
+                .pos(ct.x, side.y + 0.27)
+                .anchor(0.5, 0.)
+                .size(0.4)
+                .color(semi_white(c.a * 0.7))
+                .draw();
+        });
+        s.render_fader(ui, |ui, c| {
+            ui.fill_path(&list.rounded(0.02), theme().card(c.a));
+            let pad = 0.02;
+            ui.scope(|ui| {
+                ui.dx(list.x + pad);
+                ui.dy(list.y + pad);
+                let w = list.w - pad * 2.;
+                self.scroll.size((w, list.h - pad * 2.));
+                self.scroll.render(ui, |ui| {
+                    let r = Rect::new(0., 0., w, ROW_HEIGHT);
+                    let mut h = 0.;
+                    for achievement in ACHIEVEMENTS {
+                        let time = achievement.unlocked(data);
+                        ui.fill_path(&r.rounded(0.01), semi_black(c.a * if time.is_some() { 0.3 } else { 0.15 }));
+                        ui.fill_circle(
+                            r.x + 0.045,
+                            r.center().y,
+                            0.025,
+                            if time.is_some() { Color { a: c.a, ..UNLOCKED_COLOR } } else { semi_white(c.a * 0.2) },
+                        );
+                        let secret = achievement.hidden && time.is_none();
+                        let text_color = if time.is_some() { c } else { semi_white(c.a * 0.5) };
+                        ui.text(if secret { tl!("hidden-name") } else { achievement.name() })
+                            .pos(r.x + 0.08, r.y + 0.02)
+                            .size(0.5)
+                            .max_width(r.w * 0.6)
+                            .color(text_color)
+                            .draw();
+                        ui.text(if secret { tl!("hidden-desc") } else { achievement.description() })
+                            .pos(r.x + 0.08, r.y + 0.07)
+                            .size(0.35)
+                            .max_width(r.w - 0.1)
+                            .color(semi_white(text_color.a * 0.7))
+                            .draw();
+                        if let Some(time) = time {
+                            ui.text(time.with_timezone(&Local).format("%Y-%m-%d").to_string())
+                                .pos(r.right() - 0.02, r.y + 0.02)
+                                .anchor(1., 0.)
+                                .size(0.35)
+                                .color(semi_white(c.a * 0.6))
+                                .draw();
+                        }
+                        ui.dy(ROW_HEIGHT + pad / 2.);
+                        h += ROW_HEIGHT + pad / 2.;
+                    }
+                    (w, h)
+                });
+            });
+        });
+        Ok(())
+    }
+}
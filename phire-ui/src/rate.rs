@@ -1,12 +1,28 @@
 phire::tl_file!("rate");
 
-use crate::page::Fader;
+use crate::{
+    client::{Client, Comment, Paginator, UserManager},
+    get_data,
+    page::Fader,
+    scene::confirm_dialog,
+};
+use anyhow::Result;
 use macroquad::prelude::*;
 use phire::{
     ext::{semi_black, semi_white, RectExt, SafeTexture, ScaleType},
-    ui::{DRectButton, Ui},
+    scene::{request_input, show_error, show_message},
+    task::Task,
+    ui::{DRectButton, Scroll, Ui},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
 };
 
+const PAGE_NUM: u64 = 20;
+// the next page is fetched this far before the end of the list comes into view
+const PRELOAD: f32 = 0.5;
+
 pub struct Rate {
     pub score: i16,
 
@@ -83,8 +99,12 @@ pub struct RateDialog {
     btn_cancel: DRectButton,
     btn_confirm: DRectButton,
     btn_tags: DRectButton,
+    btn_remove: DRectButton,
     pub confirmed: Option<bool>,
     pub show_tags: bool,
+    // whether there's a rating of the account's to remove
+    pub rated: bool,
+    pub removed: bool,
 
     pub rate: Rate,
     pub rate_upper: Option<Rate>,
@@ -101,8 +121,11 @@ impl RateDialog {
             btn_cancel: DRectButton::new(),
             btn_confirm: DRectButton::new(),
             btn_tags: DRectButton::new(),
+            btn_remove: DRectButton::new(),
             confirmed: None,
             show_tags: false,
+            rated: false,
+            removed: false,
 
             rate: Rate::new(),
             rate_upper: if range { Some(Rate::new()) } else { None },
@@ -151,6 +174,10 @@ impl RateDialog {
                 self.dismiss(t);
                 return true;
             }
+            if self.rated && self.btn_remove.touch(touch, t) {
+                self.removed = true;
+                return true;
+            }
             self.rate.touch(touch);
             if let Some(upper) = &mut self.rate_upper {
                 upper.touch(touch);
@@ -218,8 +245,13 @@ impl RateDialog {
                     });
                     let pad = 0.02;
                     if self.rate_upper.is_none() {
-                        let bw = (wr.w - pad * 3.) / 2.;
+                        let n = if self.rated { 3. } else { 2. };
+                        let bw = (wr.w - pad * (n + 1.)) / n;
                         let mut r = Rect::new(wr.x + pad, wr.bottom() - 0.02 - bh, bw, bh);
+                        if self.rated {
+                            self.btn_remove.render_text(ui, r, t, c.a, tl!("remove"), 0.5, false);
+                            r.x += bw + pad;
+                        }
                         self.btn_cancel.render_text(ui, r, t, c.a, tl!("cancel"), 0.5, true);
                         r.x += bw + pad;
                         self.btn_confirm.render_text(ui, r, t, c.a, tl!("confirm"), 0.5, true);
@@ -232,3 +264,226 @@ impl RateDialog {
         }
     }
 }
+
+enum CommentAction {
+    Posted(Comment),
+    Edited(Comment),
+    Deleted(i32),
+    Reported,
+}
+
+// the comments under a chart, newest first, with posting, editing and deleting one's own and reporting the others
+pub struct Comments {
+    chart: i32,
+    paginator: Paginator<Comment>,
+    scroll: Scroll,
+    // edit and delete, or report
+    btns: Vec<(DRectButton, DRectButton)>,
+    btn_post: DRectButton,
+
+    icon_user: SafeTexture,
+    icon_star: SafeTexture,
+
+    // the comment an input or a confirmation is about
+    target: Option<i32>,
+    should_delete: Arc<AtomicBool>,
+    task: Option<Task<Result<CommentAction>>>,
+}
+
+impl Comments {
+    pub fn new(chart: i32, icon_user: SafeTexture, icon_star: SafeTexture) -> Self {
+        let mut paginator = Paginator::new(Client::query().query("chart", chart.to_string()).order("-time"), PAGE_NUM);
+        paginator.load_more();
+        Self {
+            chart,
+            paginator,
+            scroll: Scroll::new(),
+            btns: Vec::new(),
+            btn_post: DRectButton::new(),
+
+            icon_user,
+            icon_star,
+
+            target: None,
+            should_delete: Arc::default(),
+            task: None,
+        }
+    }
+
+    pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
+        if self.task.is_some() {
+            return true;
+        }
+        if get_data().me.is_some() && self.btn_post.touch(touch, t) {
+            request_input("comment-post", "", tl!("comment-post"));
+            return true;
+        }
+        if self.scroll.touch(touch, t) {
+            return true;
+        }
+        let me = get_data().me.as_ref().map(|it| it.id);
+        for (comment, (btn_action, btn_delete)) in self.paginator.items().iter().zip(self.btns.iter_mut()) {
+            let mine = Some(comment.author.id) == me;
+            if me.is_some() && btn_action.touch(touch, t) {
+                self.target = Some(comment.id);
+                if mine {
+                    request_input("comment-edit", &comment.content, tl!("comment-edit"));
+                } else {
+                    request_input("comment-report", "", tl!("comment-report"));
+                }
+                return true;
+            }
+            if mine && btn_delete.touch(touch, t) {
+                self.target = Some(comment.id);
+                confirm_dialog(tl!("comment-delete"), tl!("comment-delete-content"), self.should_delete.clone());
+                return true;
+            }
+        }
+        false
+    }
+
+    // `id` is one of the input ids requested above
+    pub fn on_input(&mut self, id: &str, text: String) {
+        let chart = self.chart;
+        let target = self.target.take();
+        self.task = Some(match (id, target) {
+            ("comment-post", _) => Task::new(async move { Ok(CommentAction::Posted(Client::post_comment(chart, &text).await?)) }),
+            ("comment-edit", Some(id)) => Task::new(async move { Ok(CommentAction::Edited(Client::edit_comment(id, &text).await?)) }),
+            ("comment-report", Some(id)) => Task::new(async move {
+                Client::report_comment(id, &text).await?;
+                Ok(CommentAction::Reported)
+            }),
+            _ => return,
+        });
+    }
+
+    pub fn update(&mut self, t: f32) {
+        self.scroll.update(t);
+        if self.scroll.y_scroller.pulled {
+            self.scroll.y_scroller.offset = 0.;
+            self.paginator.reset();
+            self.paginator.load_more();
+        }
+        if let Some(res) = self.paginator.poll() {
+            match res {
+                Err(err) => show_error(err.context(tl!("comments-load-failed"))),
+                Ok(new) => UserManager::request_many(new.iter().map(|it| it.author.id)),
+            }
+        }
+        if self.scroll.y_scroller.remaining() < PRELOAD {
+            self.paginator.load_more();
+        }
+        if self.should_delete.fetch_and(false, Ordering::Relaxed) {
+            if let Some(id) = self.target.take() {
+                self.task = Some(Task::new(async move {
+                    Client::delete_comment(id).await?;
+                    Ok(CommentAction::Deleted(id))
+                }));
+            }
+        }
+        if let Some(task) = &mut self.task {
+            if let Some(res) = task.take() {
+                self.task = None;
+                match res {
+                    Err(err) => show_error(err.context(tl!("comment-failed"))),
+                    Ok(CommentAction::Posted(comment)) => {
+                        self.paginator.prepend(comment);
+                        self.scroll.y_scroller.offset = 0.;
+                        show_message(tl!("comment-posted")).ok();
+                    }
+                    Ok(CommentAction::Edited(comment)) => self.paginator.replace(comment),
+                    Ok(CommentAction::Deleted(id)) => self.paginator.remove(id),
+                    Ok(CommentAction::Reported) => {
+                        show_message(tl!("comment-reported")).ok();
+                    }
+                }
+            }
+        }
+        self.btns.resize_with(self.paginator.items().len(), Default::default);
+    }
+
+    pub fn render(&mut self, ui: &mut Ui, width: f32, t: f32) {
+        let pad = 0.03;
+        let me = get_data().me.as_ref().map(|it| it.id);
+        let title = ui.text(tl!("comments")).size(0.8).draw();
+        if me.is_some() {
+            self.btn_post
+                .render_text(ui, Rect::new(width - pad - 0.24, -0.01, 0.23, 0.09), t, 1., tl!("comment-post"), 0.6, true);
+        }
+        ui.dy(title.h + 0.04);
+        self.scroll.size((width - pad, ui.top * 2. - title.h - 0.1));
+        let items = self.paginator.items();
+        let loading = self.paginator.loading() || self.task.is_some();
+        let (btns, icon_user, icon_star) = (&mut self.btns, &self.icon_user, &self.icon_star);
+        self.scroll.render(ui, |ui| {
+            let mw = width - pad * 3.;
+            if items.is_empty() {
+                if loading {
+                    ui.loading(mw / 2., 0.1, t, WHITE, ());
+                } else {
+                    ui.text(tl!("comments-empty")).size(0.5).color(semi_white(0.6)).draw();
+                }
+                return (width, 0.2);
+            }
+            let mut h = 0.;
+            for (comment, (btn_action, btn_delete)) in items.iter().zip(btns.iter_mut()) {
+                let s = 0.035;
+                let r = ui.avatar(s, s, s, WHITE, t, UserManager::opt_avatar(comment.author.id, icon_user));
+                if let Some((name, color)) = UserManager::name_and_color(comment.author.id) {
+                    ui.text(name)
+                        .pos(r.right() + 0.02, r.center().y)
+                        .anchor(0., 0.5)
+                        .no_baseline()
+                        .max_width(mw / 2.)
+                        .size(0.45)
+                        .color(color)
+                        .draw();
+                }
+                let mut time = comment.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string();
+                if comment.edited.is_some() {
+                    time += &tl!("comment-edited");
+                }
+                let tr = ui
+                    .text(time)
+                    .pos(mw, r.center().y)
+                    .anchor(1., 0.5)
+                    .no_baseline()
+                    .size(0.35)
+                    .color(semi_white(0.6))
+                    .draw();
+                if let Some(score) = comment.score.filter(|it| *it > 0) {
+                    let sr = ui
+                        .text(format!("{:.1}", score as f32 / 2.))
+                        .pos(tr.x - 0.03, r.center().y)
+                        .anchor(1., 0.5)
+                        .no_baseline()
+                        .size(0.35)
+                        .draw();
+                    let ir = Rect::new(sr.x - 0.035, sr.center().y - 0.015, 0.03, 0.03);
+                    ui.fill_rect(ir, (**icon_star, ir, ScaleType::Fit, WHITE));
+                }
+                let mut y = r.bottom() + 0.02;
+                y += ui.text(&comment.content).pos(0., y).size(0.45).multiline().max_width(mw).draw().h + 0.015;
+                let mine = Some(comment.author.id) == me;
+                if me.is_some() {
+                    let (bw, bh) = (0.14, 0.06);
+                    let mut br = Rect::new(mw - bw, y, bw, bh);
+                    if mine {
+                        btn_delete.render_text(ui, br, t, 1., tl!("comment-delete"), 0.4, false);
+                        br.x -= bw + 0.02;
+                    }
+                    btn_action.render_text(ui, br, t, 1., if mine { tl!("comment-edit") } else { tl!("comment-report") }, 0.4, false);
+                    y += bh;
+                }
+                y += 0.04;
+                ui.dy(y);
+                h += y;
+            }
+            if loading {
+                ui.loading(mw / 2., 0.05, t, WHITE, ());
+                h += 0.1;
+            }
+            (width, h)
+        });
+    }
+}
@@ -0,0 +1,62 @@
+use crate::data::LocalChart;
+
+// the best 27 plays plus the best 3 all perfect plays make up the rating
+const BEST_COUNT: usize = 27;
+const PHI_COUNT: usize = 3;
+
+pub fn chart_rks(difficulty: f32, accuracy: f32) -> f32 {
+    if accuracy < 0.7 {
+        return 0.;
+    }
+    let factor = (accuracy * 100. - 55.) / 45.;
+    difficulty * factor * factor
+}
+
+#[derive(Clone)]
+pub struct RksEntry {
+    pub name: String,
+    pub level: String,
+    pub difficulty: f32,
+    pub score: u32,
+    pub accuracy: f32,
+    pub full_combo: bool,
+    pub rks: f32,
+}
+
+impl RksEntry {
+    pub fn all_perfect(&self) -> bool {
+        self.accuracy >= 1.
+    }
+}
+
+pub struct RksSummary {
+    pub best: Vec<RksEntry>,
+    pub phi: Vec<RksEntry>,
+    pub rks: f32,
+}
+
+impl RksSummary {
+    pub fn compute(charts: &[LocalChart]) -> Self {
+        let mut entries: Vec<_> = charts
+            .iter()
+            .filter_map(|chart| {
+                let record = chart.record.as_ref()?;
+                Some(RksEntry {
+                    name: chart.info.name.clone(),
+                    level: chart.info.level.clone(),
+                    difficulty: chart.info.difficulty,
+                    score: record.score,
+                    accuracy: record.accuracy,
+                    full_combo: record.full_combo,
+                    rks: chart_rks(chart.info.difficulty, record.accuracy),
+                })
+            })
+            .filter(|it| it.rks > 0.)
+            .collect();
+        entries.sort_by(|a, b| b.rks.total_cmp(&a.rks));
+        let phi: Vec<_> = entries.iter().filter(|it| it.all_perfect()).take(PHI_COUNT).cloned().collect();
+        entries.truncate(BEST_COUNT);
+        let rks = (entries.iter().chain(phi.iter()).map(|it| it.rks).sum::<f32>()) / (BEST_COUNT + PHI_COUNT) as f32;
+        Self { best: entries, phi, rks }
+    }
+}
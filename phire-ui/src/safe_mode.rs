@@ -0,0 +1,52 @@
+phire::tl_file!("safe_mode");
+
+use crate::{dir, get_data_mut, save_data};
+use phire::{scene::show_message, ui::Dialog};
+use tracing::{error, warn};
+
+// exists while the app is in the foreground, so finding it at startup means the last launch didn't exit cleanly
+fn sentinel() -> Option<String> {
+    dir::root().ok().map(|it| format!("{it}/running"))
+}
+
+pub fn mark_running(running: bool) {
+    let Some(path) = sentinel() else { return };
+    let res = if running {
+        std::fs::write(&path, [])
+    } else {
+        std::fs::remove_file(&path).or_else(|err| if err.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(err) })
+    };
+    if let Err(err) = res {
+        error!("failed to update the launch sentinel: {err:?}");
+    }
+}
+
+// called once the data is loaded; after a crash the session starts in safe mode, otherwise it's a no-op
+pub fn check() {
+    let crashed = sentinel().map_or(false, |it| std::path::Path::new(&it).exists());
+    mark_running(true);
+    if !crashed {
+        return;
+    }
+    warn!("the last launch didn't exit cleanly, starting in safe mode");
+    let config = &mut get_data_mut().config;
+    config.safe_mode = true;
+    let has_respack = config.res_pack_path.is_some() || !config.res_pack_fallbacks.is_empty();
+    let mut dialog = Dialog::plain(tl!("title"), tl!("content"));
+    if has_respack {
+        dialog = dialog
+            .buttons(vec![tl!("keep").to_string(), tl!("reset-respack").to_string()])
+            .listener(|pos| {
+                if pos == 1 {
+                    let config = &mut get_data_mut().config;
+                    config.res_pack_path = None;
+                    config.res_pack_fallbacks.clear();
+                    if let Err(err) = save_data() {
+                        error!("failed to save data: {err:?}");
+                    }
+                    show_message(tl!("respack-reset")).ok();
+                }
+            });
+    }
+    dialog.show();
+}
@@ -3,6 +3,9 @@ phire::tl_file!("import" itl);
 mod chart_order;
 pub use chart_order::{ChartOrder, ORDERS};
 
+mod editor;
+pub use editor::EditorScene;
+
 pub(crate) mod event;
 pub use event::EventScene;
 
@@ -15,7 +18,14 @@ pub use song::{Downloading, SongScene, RECORD_ID};
 mod profile;
 pub use profile::ProfileScene;
 
-use crate::{client::UserManager, data::LocalChart, dir, get_data, page::Fader};
+use crate::{
+    badge::{badge_color, badge_label},
+    chart_index,
+    client::UserManager,
+    data::LocalChart,
+    dir, get_data,
+    page::Fader,
+};
 use anyhow::{bail, Context, Result};
 use phire::{
     config::Mods,
@@ -25,6 +35,7 @@ use phire::{
 };
 use std::{
     cell::RefCell,
+    collections::HashSet,
     fs::File,
     io::{BufReader, Write},
     path::Path,
@@ -33,7 +44,10 @@ use std::{
         Arc,
     },
 };
+use tracing::warn;
 use uuid::Uuid;
+use walkdir::WalkDir;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
 
 thread_local! {
     pub static TEX_BACKGROUND: RefCell<Option<SafeTexture>> = RefCell::new(None);
@@ -66,22 +80,30 @@ pub fn confirm_delete(res: Arc<AtomicBool>) {
 }
 
 pub async fn import_chart(path: String) -> Result<LocalChart> {
-    async fn inner(dir: &Path, id: Uuid, path: String) -> Result<LocalChart> {
-        let path = Path::new(&path);
-        if !path.exists() || !path.is_file() {
-            bail!("not a file");
-        }
+    let path = Path::new(&path);
+    if !path.exists() || !path.is_file() {
+        bail!("not a file");
+    }
+    let file = File::open(path)?;
+    register_chart(move |dir| unzip_into(BufReader::new(file), dir, true)).await
+}
+
+// creates a fresh folder under `custom/`, lets `fill` put the chart files there and validates the result
+async fn register_chart(fill: impl FnOnce(&phire::dir::Dir) -> Result<()>) -> Result<LocalChart> {
+    async fn inner(dir: &Path, id: Uuid, fill: impl FnOnce(&phire::dir::Dir) -> Result<()>) -> Result<LocalChart> {
         let dir = phire::dir::Dir::new(dir)?;
-        unzip_into(BufReader::new(File::open(path)?), &dir, true)?;
+        fill(&dir)?;
         let local_path = format!("custom/{id}");
         let mut fs = fs_from_path(&local_path)?;
         let mut info = fs::load_info(fs.as_mut()).await.with_context(|| itl!("info-fail"))?;
         fs::fix_info(fs.as_mut(), &mut info).await.with_context(|| itl!("invalid-chart"))?;
         dir.create("info.yml")?.write_all(serde_yaml::to_string(&info)?.as_bytes())?;
+        chart_index::update(&local_path, &info);
         Ok(LocalChart {
             info: info.into(),
             local_path,
             record: None,
+            record_chart_hash: None,
             mods: Mods::default(),
         })
     }
@@ -93,7 +115,7 @@ pub async fn import_chart(path: String) -> Result<LocalChart> {
     }
     let dir = dir.join(id.to_string());
     std::fs::create_dir(&dir)?;
-    match inner(&dir, id, path).await {
+    match inner(&dir, id, fill).await {
         Err(err) => {
             std::fs::remove_dir_all(dir)?;
             Err(err)
@@ -102,6 +124,143 @@ pub async fn import_chart(path: String) -> Result<LocalChart> {
     }
 }
 
+fn is_zip(path: &Path) -> bool {
+    path.extension().map_or(false, |it| it.eq_ignore_ascii_case("zip"))
+}
+
+// a pack holds nothing but chart folders and chart zips at its top level
+fn is_pack(path: &Path) -> Result<bool> {
+    let zip = ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let names: Vec<_> = zip.file_names().collect();
+    let root = names
+        .iter()
+        .min_by_key(|it| it.len())
+        .filter(|root| root.ends_with('/') && names.iter().all(|it| it.starts_with(**root)))
+        .map_or("", |it| *it);
+    let mut entries = HashSet::new();
+    for name in &names {
+        let rest = &name[root.len()..];
+        if rest.is_empty() {
+            continue;
+        }
+        match rest.split_once('/') {
+            Some((folder, _)) => {
+                entries.insert(folder);
+            }
+            None if is_zip(Path::new(rest)) => {
+                entries.insert(rest);
+            }
+            None => return Ok(false),
+        }
+    }
+    Ok(!entries.is_empty())
+}
+
+fn copy_dir_into(from: &Path, to: &phire::dir::Dir) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let name = entry.path().strip_prefix(from)?;
+        if name.as_os_str().is_empty() {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            to.create_dir_all(name)?;
+        } else {
+            std::io::copy(&mut File::open(entry.path())?, &mut to.create(name)?)?;
+        }
+    }
+    Ok(())
+}
+
+async fn import_pack(path: &Path) -> Result<(Vec<LocalChart>, usize)> {
+    let tmp = format!("{}/import-pack", dir::cache()?);
+    let tmp = Path::new(&tmp);
+    if tmp.exists() {
+        std::fs::remove_dir_all(tmp)?;
+    }
+    std::fs::create_dir_all(tmp)?;
+    let res = async {
+        unzip_into(BufReader::new(File::open(path)?), &phire::dir::Dir::new(tmp)?, true)?;
+        let mut entries = std::fs::read_dir(tmp)?.map(|it| Ok(it?.path())).collect::<Result<Vec<_>>>()?;
+        entries.sort();
+        let (mut charts, mut failed) = (Vec::new(), 0);
+        for entry in entries {
+            let res = if entry.is_dir() {
+                register_chart(|dir| copy_dir_into(&entry, dir)).await
+            } else if is_zip(&entry) {
+                import_chart(entry.display().to_string()).await
+            } else {
+                continue;
+            };
+            match res {
+                Ok(chart) => charts.push(chart),
+                Err(err) => {
+                    warn!("failed to import {} from pack: {err:?}", entry.display());
+                    failed += 1;
+                }
+            }
+        }
+        if charts.is_empty() {
+            bail!("no valid chart in pack");
+        }
+        Ok::<_, anyhow::Error>((charts, failed))
+    }
+    .await;
+    std::fs::remove_dir_all(tmp)?;
+    res
+}
+
+// imports either a single chart or a whole pack; also returns how many charts of the pack were skipped
+pub async fn import_charts(path: String) -> Result<(Vec<LocalChart>, usize)> {
+    let p = Path::new(&path);
+    if !p.exists() || !p.is_file() {
+        bail!("not a file");
+    }
+    if is_pack(p).unwrap_or(false) {
+        import_pack(p).await
+    } else {
+        Ok((vec![import_chart(path).await?], 0))
+    }
+}
+
+// bundles local charts (`(local_path, name)`) into one zip in the cache and returns its path
+pub async fn export_pack(charts: Vec<(String, String)>) -> Result<String> {
+    let path = format!("{}/charts.zip", dir::cache()?);
+    let root = dir::charts()?;
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let options = FileOptions::<()>::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+    let mut used = HashSet::new();
+    for (local_path, name) in charts {
+        let base: String = name
+            .chars()
+            .map(|it| if it.is_alphanumeric() || " -_()".contains(it) { it } else { '_' })
+            .collect();
+        let base = if base.trim().is_empty() { "chart".to_owned() } else { base.trim().to_owned() };
+        let mut folder = base.clone();
+        let mut index = 1;
+        while !used.insert(folder.clone()) {
+            index += 1;
+            folder = format!("{base} ({index})");
+        }
+        let src = Path::new(&root).join(&local_path);
+        #[allow(deprecated)]
+        for entry in WalkDir::new(&src) {
+            let entry = entry?;
+            let name = Path::new(&folder).join(entry.path().strip_prefix(&src)?);
+            if entry.file_type().is_file() {
+                zip.start_file_from_path(name, options)?;
+                std::io::copy(&mut File::open(entry.path())?, &mut zip)?;
+            } else {
+                zip.add_directory_from_path(name, options)?;
+            }
+        }
+    }
+    zip.finish()?;
+    Ok(path)
+}
+
 pub struct LdbDisplayItem<'a> {
     pub player_id: i32,
     pub rank: u32,
@@ -186,14 +345,20 @@ pub fn render_ldb<'a>(
                     rt -= r.w + 0.03;
                     let lt = 0.24;
                     if let Some((name, color)) = UserManager::name_and_color(item.player_id) {
-                        ui.text(name)
+                        let badge = UserManager::badge(item.player_id).map(|it| (badge_label(&it).into_owned(), badge_color(&it)));
+                        let bw = badge.as_ref().map_or(0., |(label, _)| ui.badge_width(label, 0.3) + 0.01);
+                        let r = ui
+                            .text(name)
                             .pos(lt, s / 2.)
                             .anchor(0., 0.5)
                             .no_baseline()
-                            .max_width(rt - lt - 0.01)
+                            .max_width(rt - lt - 0.01 - bw)
                             .size(0.5)
                             .color(Color { a: c.a, ..color })
                             .draw();
+                        if let Some((label, color)) = badge {
+                            ui.badge(&label, r.right() + 0.01, s / 2., 0.3, Color { a: c.a, ..color });
+                        }
                     }
                 });
                 ui.dy(s);
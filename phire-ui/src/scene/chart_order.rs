@@ -7,6 +7,8 @@ pub enum ChartOrder {
     Default,
     Name,
     Rating,
+    // downloads and plays lately, only known to the server
+    Hot,
 }
 
 impl ChartOrder {
@@ -26,16 +28,17 @@ impl ChartOrder {
             Self::Name => {
                 charts.sort_by(|x, y| f(x).info.name.cmp(&f(y).info.name));
             }
-            Self::Rating => {}
+            Self::Rating | Self::Hot => {}
         }
     }
 }
 
-const ORDER_NUM: usize = 6;
-const ORDER_LABELS: [&str; ORDER_NUM] = ["time", "rev-time", "rating", "rev-rating", "name", "rev-name"];
+const ORDER_NUM: usize = 7;
+const ORDER_LABELS: [&str; ORDER_NUM] = ["time", "rev-time", "hot", "rating", "rev-rating", "name", "rev-name"];
 pub static ORDERS: [(ChartOrder, bool); ORDER_NUM] = [
     (ChartOrder::Default, false),
     (ChartOrder::Default, true),
+    (ChartOrder::Hot, true),
     (ChartOrder::Rating, true),
     (ChartOrder::Rating, false),
     (ChartOrder::Name, false),
@@ -0,0 +1,599 @@
+phire::tl_file!("editor");
+
+use super::{confirm_dialog, fs_from_path};
+use crate::{chart_index, dir, get_data, icons::Icons};
+use anyhow::{Context, Result};
+use macroquad::prelude::*;
+use phire::{
+    config::Mods,
+    core::{ChartExtra, LoadProgress},
+    ext::{poll_future, semi_black, semi_white, LocalTask, RectExt, ScaleType},
+    fs,
+    info::{ChartFormat, ChartInfo},
+    judge::rules::NoteClass,
+    parse::{
+        edit::{EditNote, RPEDocument},
+        export, parse_rpe, RPE_WIDTH,
+    },
+    scene::{show_error, show_message, GameMode, GameScene, LoadingScene, LocalSceneTask, NextScene, Scene},
+    time::TimeManager,
+    ui::{button_hit, DRectButton, RectButton, Ui, UI_AUDIO},
+};
+use sasa::{AudioClip, Music, MusicParams};
+use std::{
+    any::Any,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+// divisions of a beat the grid can snap to
+const SNAPS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+// in beats
+const HOLD_LENGTHS: [f32; 6] = [0.25, 0.5, 1., 2., 4., 8.];
+const BEAT_HEIGHT: f32 = 0.32;
+// horizontal grid, the line is split into this many steps
+const X_STEPS: f32 = 16.;
+const PANEL_WIDTH: f32 = 0.42;
+// the cursor sits this far above the bottom of the timeline
+const CURSOR_MARGIN: f32 = 0.14;
+// a touch moving further than this scrolls the timeline instead of placing a note
+const TAP_SLOP: f32 = 0.015;
+const NOTE_HEIGHT: f32 = 0.018;
+// file the chart is saved to when the original isn't RPE
+const CONVERTED_NAME: &str = "edited.json";
+
+fn note_color(class: NoteClass) -> Color {
+    match class {
+        NoteClass::Click => Color::from_hex(0x0ac3ff),
+        NoteClass::Drag => Color::from_hex(0xf0ed69),
+        NoteClass::Flick => Color::from_hex(0xfe4365),
+        NoteClass::Hold => Color::from_hex(0x3c8cff),
+    }
+}
+
+fn class_name(class: NoteClass) -> &'static str {
+    match class {
+        NoteClass::Click => "tap",
+        NoteClass::Drag => "drag",
+        NoteClass::Flick => "flick",
+        NoteClass::Hold => "hold",
+    }
+}
+
+struct Loaded {
+    info: ChartInfo,
+    doc: RPEDocument,
+    music: AudioClip,
+    // whether the chart file itself is RPE and can be written in place
+    rpe: bool,
+}
+
+async fn load(local_path: String) -> Result<Loaded> {
+    let mut fs = fs_from_path(&local_path)?;
+    let info = fs::load_info(fs.as_mut()).await?;
+    let bytes = GameScene::load_chart_bytes(fs.as_mut(), &info).await?;
+    let text = String::from_utf8_lossy(&bytes);
+    let rpe = match info.format {
+        Some(ChartFormat::Rpe) => true,
+        Some(_) => false,
+        None => text.starts_with('{') && text.contains("\"META\""),
+    };
+    let source = if rpe {
+        text.into_owned()
+    } else {
+        let mut config = get_data().config.clone();
+        config.render_extra = false;
+        let (chart, _) = GameScene::load_chart(fs.as_mut(), &info, &config).await?;
+        export::to_rpe(&chart, &info)?
+    };
+    let doc = RPEDocument::parse(&source)?;
+    let music = AudioClip::new(fs.load_file(&info.music).await.context("failed to load music")?)?;
+    Ok(Loaded { info, doc, music, rpe })
+}
+
+struct Drag {
+    id: u64,
+    start: Vec2,
+    start_beat: f32,
+    moved: bool,
+}
+
+pub struct EditorScene {
+    local_path: String,
+    icons: Arc<Icons>,
+
+    load_task: LocalTask<Result<Loaded>>,
+    loaded: Option<Loaded>,
+    music: Option<Music>,
+
+    line: usize,
+    class: NoteClass,
+    snap: usize,
+    hold_length: usize,
+    // cursor position on the timeline, in beats
+    beat: f32,
+    drag: Option<Drag>,
+    timeline: Rect,
+    dirty: bool,
+    should_exit: Arc<AtomicBool>,
+
+    back_btn: RectButton,
+    prev_line_btn: DRectButton,
+    next_line_btn: DRectButton,
+    class_btns: [DRectButton; 4],
+    snap_btn: DRectButton,
+    hold_btn: DRectButton,
+    play_btn: DRectButton,
+    preview_btn: DRectButton,
+    save_btn: DRectButton,
+
+    scene_task: LocalSceneTask,
+    next_scene: Option<NextScene>,
+}
+
+impl EditorScene {
+    pub fn new(local_path: String, icons: Arc<Icons>) -> Self {
+        Self {
+            load_task: Some(Box::pin(load(local_path.clone()))),
+            local_path,
+            icons,
+
+            loaded: None,
+            music: None,
+
+            line: 0,
+            class: NoteClass::Click,
+            snap: 3,
+            hold_length: 2,
+            beat: 0.,
+            drag: None,
+            timeline: Rect::default(),
+            dirty: false,
+            should_exit: Arc::default(),
+
+            back_btn: RectButton::new(),
+            prev_line_btn: DRectButton::new(),
+            next_line_btn: DRectButton::new(),
+            class_btns: std::array::from_fn(|_| DRectButton::new()),
+            snap_btn: DRectButton::new(),
+            hold_btn: DRectButton::new(),
+            play_btn: DRectButton::new(),
+            preview_btn: DRectButton::new(),
+            save_btn: DRectButton::new(),
+
+            scene_task: None,
+            next_scene: None,
+        }
+    }
+
+    fn playing(&self) -> bool {
+        self.music.as_ref().map_or(false, |it| !it.paused())
+    }
+
+    // music time minus this is chart time
+    fn music_offset(&self) -> f32 {
+        self.loaded.as_ref().map_or(0., |it| it.doc.offset() + it.info.offset)
+    }
+
+    fn toggle_play(&mut self) -> Result<()> {
+        let offset = self.music_offset();
+        let Some(loaded) = &mut self.loaded else { return Ok(()) };
+        let time = loaded.doc.time(self.beat);
+        let Some(music) = &mut self.music else { return Ok(()) };
+        if music.paused() {
+            music.seek_to((time + offset).max(0.) as f64)?;
+            music.play()?;
+        } else {
+            music.pause()?;
+        }
+        Ok(())
+    }
+
+    fn snapped(&self, beat: f32) -> f32 {
+        let div = SNAPS[self.snap] as f32;
+        (beat * div).round() / div
+    }
+
+    fn x_to_screen(&self, x: f32) -> f32 {
+        let r = self.timeline;
+        r.center().x + x / (RPE_WIDTH / 2.) * (r.w / 2. - 0.04)
+    }
+
+    fn beat_to_screen(&self, beat: f32) -> f32 {
+        self.timeline.bottom() - CURSOR_MARGIN - (beat - self.beat) * BEAT_HEIGHT
+    }
+
+    // removes the note under the point, or places one there
+    fn tap(&mut self, pos: Vec2) {
+        let Some(loaded) = &self.loaded else { return };
+        let hit = loaded.doc.notes(self.line).iter().rposition(|note| {
+            let x = self.x_to_screen(note.x);
+            let (top, bottom) = (self.beat_to_screen(note.end_beat), self.beat_to_screen(note.beat));
+            (pos.x - x).abs() < 0.06 && pos.y >= top - NOTE_HEIGHT * 1.5 && pos.y <= bottom + NOTE_HEIGHT * 1.5
+        });
+        let r = self.timeline;
+        let step = RPE_WIDTH / X_STEPS;
+        let x = ((pos.x - r.center().x) / (r.w / 2. - 0.04) * (RPE_WIDTH / 2.) / step).round() * step;
+        let beat = self.snapped(self.beat + (self.beat_to_screen(self.beat) - pos.y) / BEAT_HEIGHT);
+        let note = EditNote {
+            class: self.class,
+            beat,
+            end_beat: if self.class == NoteClass::Hold { beat + HOLD_LENGTHS[self.hold_length] } else { beat },
+            x,
+            above: true,
+        };
+        let Some(loaded) = &mut self.loaded else { return };
+        if let Some(index) = hit {
+            loaded.doc.remove_note(self.line, index);
+        } else if beat >= 0. {
+            loaded.doc.add_note(self.line, note);
+        } else {
+            return;
+        }
+        button_hit();
+        self.dirty = true;
+    }
+
+    fn timeline_touch(&mut self, touch: &Touch) -> bool {
+        match touch.phase {
+            TouchPhase::Started => {
+                if self.drag.is_some() || !self.timeline.contains(touch.position) {
+                    return false;
+                }
+                self.drag = Some(Drag {
+                    id: touch.id,
+                    start: touch.position,
+                    start_beat: self.beat,
+                    moved: false,
+                });
+                true
+            }
+            TouchPhase::Moved | TouchPhase::Stationary => {
+                let playing = self.playing();
+                let Some(drag) = self.drag.as_mut().filter(|it| it.id == touch.id) else {
+                    return false;
+                };
+                if (touch.position - drag.start).length() > TAP_SLOP {
+                    drag.moved = true;
+                }
+                if drag.moved && !playing {
+                    self.beat = (drag.start_beat + (touch.position.y - drag.start.y) / BEAT_HEIGHT).max(0.);
+                }
+                true
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if self.drag.as_ref().map_or(true, |it| it.id != touch.id) {
+                    return false;
+                }
+                let drag = self.drag.take().unwrap();
+                if !drag.moved && touch.phase == TouchPhase::Ended {
+                    self.tap(touch.position);
+                }
+                true
+            }
+        }
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let Some(loaded) = &mut self.loaded else { return Ok(()) };
+        let dir = phire::dir::Dir::new(format!("{}/{}", dir::charts()?, self.local_path))?;
+        let json = loaded.doc.to_json()?;
+        if loaded.rpe {
+            dir.create(&loaded.info.chart)?.write_all(json.as_bytes())?;
+        } else {
+            // the original is kept, info.yml is pointed at the converted chart instead
+            dir.create(CONVERTED_NAME)?.write_all(json.as_bytes())?;
+            loaded.info.chart = CONVERTED_NAME.to_owned();
+            loaded.info.format = Some(ChartFormat::Rpe);
+            dir.create("info.yml")?.write_all(serde_yaml::to_string(&loaded.info)?.as_bytes())?;
+            chart_index::update(&self.local_path, &loaded.info);
+            loaded.rpe = true;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    // autoplays the chart as edited so far, starting from the cursor
+    fn preview(&mut self) -> Result<()> {
+        let Some(loaded) = &mut self.loaded else { return Ok(()) };
+        if let Some(music) = &mut self.music {
+            music.pause()?;
+        }
+        let json = loaded.doc.to_json()?;
+        let info = loaded.info.clone();
+        let start = loaded.doc.time(self.beat);
+        let mut fs = fs_from_path(&self.local_path)?;
+        let mut config = get_data().config.clone();
+        config.mods = Mods::AUTOPLAY;
+        config.play_start_time = start.max(0.);
+        config.chart_offset = get_data().chart_offsets.get(&self.local_path).copied().unwrap_or_default();
+        get_data().apply_respacks(&mut config)?;
+        self.scene_task = Some(Box::pin(async move {
+            let mut chart = parse_rpe(&json, fs.as_mut(), ChartExtra::default()).await?;
+            chart.load_textures(fs.as_mut(), &LoadProgress::default()).await?;
+            LoadingScene::new(Some((chart, ChartFormat::Rpe)), GameMode::Normal, info, &config, fs, None, None, None, None)
+                .await
+                .map(|it| NextScene::Overlay(Box::new(it)))
+        }));
+        Ok(())
+    }
+
+    fn exit(&mut self) {
+        if self.dirty {
+            confirm_dialog(tl!("discard"), tl!("discard-content"), self.should_exit.clone());
+        } else {
+            self.next_scene = Some(NextScene::Pop);
+        }
+    }
+}
+
+impl Scene for EditorScene {
+    fn pause(&mut self, _tm: &mut TimeManager) -> Result<()> {
+        if let Some(music) = &mut self.music {
+            music.pause()?;
+        }
+        Ok(())
+    }
+
+    fn on_result(&mut self, _tm: &mut TimeManager, result: Box<dyn Any>) -> Result<()> {
+        if let Ok(error) = result.downcast::<anyhow::Error>() {
+            show_error(error.context(tl!("preview-failed")));
+        }
+        Ok(())
+    }
+
+    fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        let t = tm.now() as f32;
+        if self.load_task.is_some() || self.scene_task.is_some() {
+            return Ok(true);
+        }
+        if self.back_btn.touch(touch) {
+            button_hit();
+            self.exit();
+            return Ok(true);
+        }
+        if self.loaded.is_none() {
+            return Ok(false);
+        }
+        let line_count = self.loaded.as_ref().map_or(1, |it| it.doc.line_count()).max(1);
+        if self.prev_line_btn.touch(touch, t) {
+            self.line = (self.line + line_count - 1) % line_count;
+            return Ok(true);
+        }
+        if self.next_line_btn.touch(touch, t) {
+            self.line = (self.line + 1) % line_count;
+            return Ok(true);
+        }
+        for (btn, class) in self.class_btns.iter_mut().zip([NoteClass::Click, NoteClass::Drag, NoteClass::Flick, NoteClass::Hold]) {
+            if btn.touch(touch, t) {
+                self.class = class;
+                return Ok(true);
+            }
+        }
+        if self.snap_btn.touch(touch, t) {
+            self.snap = (self.snap + 1) % SNAPS.len();
+            return Ok(true);
+        }
+        if self.hold_btn.touch(touch, t) {
+            self.hold_length = (self.hold_length + 1) % HOLD_LENGTHS.len();
+            return Ok(true);
+        }
+        if self.play_btn.touch(touch, t) {
+            self.toggle_play()?;
+            return Ok(true);
+        }
+        if self.preview_btn.touch(touch, t) {
+            self.preview()?;
+            return Ok(true);
+        }
+        if self.save_btn.touch(touch, t) {
+            match self.save() {
+                Ok(()) => show_message(tl!("saved")).ok(),
+                Err(err) => show_error(err.context(tl!("save-failed"))),
+            }
+            return Ok(true);
+        }
+        if self.loaded.as_ref().map_or(false, |it| it.doc.line_count() > 0) && self.timeline_touch(touch) {
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn update(&mut self, _tm: &mut TimeManager) -> Result<()> {
+        if let Some(task) = &mut self.load_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.load_task = None;
+                match res {
+                    Err(err) => {
+                        show_error(err.context(tl!("load-failed")));
+                        self.next_scene = Some(NextScene::Pop);
+                    }
+                    Ok(loaded) => {
+                        let mut music = UI_AUDIO.with(|it| {
+                            it.borrow_mut().create_music(
+                                loaded.music.clone(),
+                                MusicParams {
+                                    amplifier: get_data().config.volume_music as _,
+                                    ..Default::default()
+                                },
+                            )
+                        })?;
+                        music.pause()?;
+                        self.music = Some(music);
+                        self.loaded = Some(loaded);
+                    }
+                }
+            }
+        }
+        if let Some(task) = &mut self.scene_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.scene_task = None;
+                match res {
+                    Err(err) => show_error(err.context(tl!("preview-failed"))),
+                    Ok(scene) => self.next_scene = Some(scene),
+                }
+            }
+        }
+        if self.should_exit.fetch_and(false, Ordering::Relaxed) {
+            self.next_scene = Some(NextScene::Pop);
+        }
+        if self.playing() {
+            let time = self.music.as_ref().unwrap().position() - self.music_offset();
+            if let Some(loaded) = &mut self.loaded {
+                self.beat = loaded.doc.beat(time).max(0.);
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        set_camera(&ui.camera());
+        let t = tm.now() as f32;
+        ui.fill_rect(ui.screen_rect(), Color::from_hex(0x161a22));
+
+        let r = ui.back_rect();
+        self.back_btn.set(ui, r);
+        ui.fill_rect(r, (*self.icons.back, r, ScaleType::Fit, WHITE));
+
+        let beat = self.beat;
+        let Some((time, bpm)) = self.loaded.as_mut().map(|it| (it.doc.time(beat), it.doc.bpm_at(beat))) else {
+            ui.full_loading(tl!("loading"), t);
+            return Ok(());
+        };
+        let loaded = self.loaded.as_ref().unwrap();
+        let top = ui.top;
+        let name_r = ui.text(&loaded.info.name).pos(r.right() + 0.02, r.y).size(0.7).max_width(0.8).draw();
+        ui.text(tl!("cursor", "beat" => format!("{:.2}", self.beat), "time" => format!("{:.2}", time), "bpm" => format!("{bpm:.1}")))
+            .pos(name_r.x, name_r.bottom() + 0.01)
+            .size(0.4)
+            .color(semi_white(0.7))
+            .draw();
+
+        // side panel
+        let (x, w) = (-0.97, PANEL_WIDTH);
+        let (bh, pad) = (0.075, 0.02);
+        let mut y = r.bottom() + 0.06;
+        let line_count = loaded.doc.line_count();
+        let sw = bh * 1.2;
+        self.prev_line_btn.render_text(ui, Rect::new(x, y, sw, bh), t, 1., "<", 0.6, false);
+        self.next_line_btn.render_text(ui, Rect::new(x + w - sw, y, sw, bh), t, 1., ">", 0.6, false);
+        let line_label = if line_count == 0 {
+            tl!("no-lines").into_owned()
+        } else {
+            let name = loaded.doc.line_name(self.line);
+            if name.is_empty() || name == "Untitled" {
+                tl!("line", "id" => self.line)
+            } else {
+                format!("{} · {name}", tl!("line", "id" => self.line))
+            }
+        };
+        ui.text(line_label)
+            .pos(x + w / 2., y + bh / 2.)
+            .anchor(0.5, 0.5)
+            .no_baseline()
+            .size(0.45)
+            .max_width(w - sw * 2. - 0.02)
+            .draw();
+        y += bh + pad;
+        let hw = (w - pad) / 2.;
+        for (i, class) in [NoteClass::Click, NoteClass::Drag, NoteClass::Flick, NoteClass::Hold].into_iter().enumerate() {
+            let br = Rect::new(x + (i % 2) as f32 * (hw + pad), y + (i / 2) as f32 * (bh + pad), hw, bh);
+            self.class_btns[i].render_text(ui, br, t, 1., tl!(class_name(class)), 0.5, self.class == class);
+            ui.fill_rect(Rect::new(br.x + 0.012, br.center().y - 0.012, 0.006, 0.024), note_color(class));
+        }
+        y += (bh + pad) * 2.;
+        self.snap_btn
+            .render_text(ui, Rect::new(x, y, w, bh), t, 1., tl!("snap", "div" => SNAPS[self.snap]), 0.45, false);
+        y += bh + pad;
+        let hold = HOLD_LENGTHS[self.hold_length];
+        self.hold_btn
+            .render_text(ui, Rect::new(x, y, w, bh), t, 1., tl!("hold-length", "beats" => hold.to_string()), 0.45, false);
+        y += bh + pad;
+        let playing = self.playing();
+        self.play_btn
+            .render_text(ui, Rect::new(x, y, w, bh), t, 1., if playing { tl!("pause") } else { tl!("play") }, 0.5, playing);
+        y += bh + pad;
+        self.preview_btn.render_text(ui, Rect::new(x, y, w, bh), t, 1., tl!("preview"), 0.5, false);
+        y += bh + pad;
+        self.save_btn.render_text(ui, Rect::new(x, y, w, bh), t, 1., tl!("save"), 0.5, self.dirty);
+        ui.text(tl!("hint"))
+            .pos(x, top - 0.03)
+            .anchor(0., 1.)
+            .size(0.32)
+            .max_width(w)
+            .multiline()
+            .color(semi_white(0.5))
+            .draw();
+
+        // timeline
+        let area = Rect::new(x + w + 0.04, -top + 0.03, 0.97 - (x + w + 0.04), top * 2. - 0.06);
+        self.timeline = area;
+        ui.fill_path(&area.rounded(0.02), semi_black(0.5));
+        let cursor_y = area.bottom() - CURSOR_MARGIN;
+        let div = SNAPS[self.snap] as f32;
+        let first = ((self.beat - CURSOR_MARGIN / BEAT_HEIGHT) * div).floor() as i32;
+        let last = ((self.beat + (cursor_y - area.y) / BEAT_HEIGHT) * div).ceil() as i32;
+        for i in first.max(0)..=last {
+            let beat = i as f32 / div;
+            let gy = self.beat_to_screen(beat);
+            if gy < area.y || gy > area.bottom() {
+                continue;
+            }
+            let whole = i % div as i32 == 0;
+            ui.fill_rect(Rect::new(area.x, gy - 0.001, area.w, 0.002), semi_white(if whole { 0.35 } else { 0.12 }));
+            if whole {
+                ui.text(format!("{}", i / div as i32))
+                    .pos(area.x + 0.01, gy - 0.005)
+                    .anchor(0., 1.)
+                    .size(0.3)
+                    .color(semi_white(0.5))
+                    .draw();
+            }
+        }
+        for i in 0..=X_STEPS as i32 {
+            let gx = self.x_to_screen((i as f32 / X_STEPS - 0.5) * RPE_WIDTH);
+            let a = if i * 2 == X_STEPS as i32 { 0.3 } else { 0.07 };
+            ui.fill_rect(Rect::new(gx - 0.001, area.y, 0.002, area.h), semi_white(a));
+        }
+        let notes = if line_count == 0 { &[] } else { loaded.doc.notes(self.line) };
+        for note in notes {
+            let nx = self.x_to_screen(note.x);
+            let (y0, y1) = (self.beat_to_screen(note.beat), self.beat_to_screen(note.end_beat));
+            if y0 < area.y - NOTE_HEIGHT || y1 > area.bottom() + NOTE_HEIGHT {
+                continue;
+            }
+            let color = note_color(note.class);
+            let nw = 0.1;
+            if note.class == NoteClass::Hold {
+                let (body_top, body_bottom) = (y1.max(area.y), y0.min(area.bottom()));
+                ui.fill_rect(Rect::new(nx - nw * 0.35, body_top, nw * 0.7, body_bottom - body_top), Color { a: 0.5, ..color });
+            }
+            if y0 <= area.bottom() {
+                ui.fill_path(&Rect::new(nx - nw / 2., y0 - NOTE_HEIGHT / 2., nw, NOTE_HEIGHT).rounded(0.004), color);
+            }
+            if !note.above {
+                ui.fill_rect(Rect::new(nx - nw / 2., y0 + NOTE_HEIGHT / 2., nw, 0.003), semi_white(0.6));
+            }
+        }
+        ui.fill_rect(Rect::new(area.x, cursor_y - 0.002, area.w, 0.004), Color::from_hex(0xffe082));
+
+        if self.scene_task.is_some() {
+            ui.full_loading_simple(t);
+        }
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        if let Some(scene) = self.next_scene.take() {
+            if let Some(music) = &mut self.music {
+                let _ = music.pause();
+            }
+            scene
+        } else {
+            NextScene::default()
+        }
+    }
+}
@@ -338,9 +338,7 @@ impl Scene for EventScene {
                         show_error(err.context(tl!("load-ldb-failed")));
                     }
                     Ok(ldb) => {
-                        for item in ldb.iter() {
-                            UserManager::request(item.player);
-                        }
+                        UserManager::request_many(ldb.iter().map(|it| it.player));
                         self.ldb = Some(ldb);
                     }
                 }
@@ -1,12 +1,19 @@
-use super::{import_chart, itl, L10N_LOCAL};
+use super::{import_charts, itl, L10N_LOCAL};
 use crate::{
+    achievement,
+    audio_preview::PREVIEW_PLAYING,
+    banner::Banners,
     charts_view::NEED_UPDATE,
+    client::take_slow_down,
     data::LocalChart,
-    dir, get_data, get_data_mut,
+    dir,
+    download::{with_manager, DownloadState},
+    get_data, get_data_mut,
     mp::MPPanel,
     page::{HomePage, NextPage, Page, ResPackItem, SharedState, MAX_ROTATE_RATE, RESTORE_RATE, ROT_SCALE_X, ROT_SCALE_Y},
     save_data,
     scene::{TEX_BACKGROUND, TEX_BACKGROUND_BLUR, TEX_ICON_BACK},
+    theme::theme_background,
 };
 use anyhow::{anyhow, Context, Result};
 use macroquad::prelude::*;
@@ -23,9 +30,12 @@ use sasa::{AudioClip, Music};
 use std::{
     any::Any, cell::RefCell, fs::File, io::BufReader, sync::atomic::{AtomicBool, Ordering}, thread_local, time::{Duration, Instant}
 };
+use tracing::warn;
 use uuid::Uuid;
 
 const LOW_PASS: f32 = 0.95;
+// how quickly the menu music gets out of the way of a song preview
+const BGM_DUCK_TIME: f32 = 0.6;
 
 pub static BGM_VOLUME_UPDATED: AtomicBool = AtomicBool::new(false);
 
@@ -43,6 +53,7 @@ pub struct MainScene {
     state: SharedState,
 
     bgm: Option<Music>,
+    bgm_ducked: bool,
 
     background: SafeTexture,
     background_blur: SafeTexture,
@@ -50,8 +61,9 @@ pub struct MainScene {
     icon_back: SafeTexture,
 
     pages: Vec<Box<dyn Page>>,
+    banners: Banners,
 
-    import_task: Option<Task<Result<LocalChart>>>,
+    import_task: Option<Task<Result<(Vec<LocalChart>, usize)>>>,
 
     mp_btn: RectButton,
     mp_icon: SafeTexture,
@@ -99,9 +111,20 @@ impl MainScene {
         load_sfx!(UI_BTN_HITSOUND, "button.ogg");
         load_sfx!(UI_SWITCH_SOUND, "switch.ogg");
 
-        let background: SafeTexture = load_texture("background.png").await?.into();
-
-        let image = image::load_from_memory(&load_file("background.png").await?).context("Failed to decode image")?;
+        let themed = theme_background().and_then(|bytes| match image::load_from_memory(&bytes) {
+            Ok(image) => Some(image),
+            Err(err) => {
+                warn!("invalid theme background: {err:?}");
+                None
+            }
+        });
+        let (background, image): (SafeTexture, _) = match themed {
+            Some(image) => (image.clone().into(), image),
+            None => (
+                load_texture("background.png").await?.into(),
+                image::load_from_memory(&load_file("background.png").await?).context("Failed to decode image")?,
+            ),
+        };
         let background_blur = blur_image(image, 80.)?;
 
         let icon_back: SafeTexture = load_texture("back.png").await?.into();
@@ -121,6 +144,7 @@ impl MainScene {
             state,
 
             bgm,
+            bgm_ducked: false,
 
             background: TEX_BACKGROUND.with(|it| it.borrow().clone().unwrap()),
             background_blur: TEX_BACKGROUND_BLUR.with(|it| it.borrow().clone().unwrap()),
@@ -129,6 +153,7 @@ impl MainScene {
             icon_back: TEX_ICON_BACK.with(|it| it.borrow().clone().unwrap()),
 
             pages: Vec::new(),
+            banners: Banners::new(),
 
             import_task: None,
 
@@ -181,7 +206,9 @@ impl Scene for MainScene {
 
     fn resume(&mut self, tm: &mut TimeManager) -> Result<()> {
         if let Some(bgm) = &mut self.bgm {
-            bgm.play()?;
+            if !self.bgm_ducked {
+                bgm.play()?;
+            }
         }
         self.state.update(tm);
         self.pages.last_mut().unwrap().resume()?;
@@ -242,6 +269,10 @@ impl Scene for MainScene {
             }
         }
 
+        if self.banners.touch(touch) {
+            return Ok(true);
+        }
+
         let s = &mut self.state;
         s.update(tm);
         if self.btn_back.touch(touch) && self.pages.len() > 1 {
@@ -273,6 +304,10 @@ impl Scene for MainScene {
                 }
             })?;
         }
+        self.banners.update(tm.real_time() as _);
+        if take_slow_down() {
+            show_message(ttl!("slow-down")).warn();
+        }
         let s = &mut self.state;
         s.update(tm);
         if s.fader.transiting() {
@@ -310,6 +345,15 @@ impl Scene for MainScene {
             if BGM_VOLUME_UPDATED.fetch_and(false, Ordering::Relaxed) {
                 bgm.set_amplifier(get_data().config.volume_bgm)?;
             }
+            let duck = PREVIEW_PLAYING.load(Ordering::Relaxed);
+            if duck != self.bgm_ducked {
+                self.bgm_ducked = duck;
+                if duck {
+                    let _ = bgm.fade_out(BGM_DUCK_TIME);
+                } else if self.pages.last().unwrap().can_play_bgm() {
+                    let _ = bgm.fade_in(BGM_DUCK_TIME);
+                }
+            }
         }
         if let Some(task) = &mut self.import_task {
             if let Some(res) = task.take() {
@@ -317,10 +361,17 @@ impl Scene for MainScene {
                     Err(err) => {
                         show_error(err.context(itl!("import-failed")));
                     }
-                    Ok(chart) => {
-                        show_message(itl!("import-success")).ok();
-                        get_data_mut().charts.push(chart);
+                    Ok((charts, failed)) => {
+                        if failed > 0 {
+                            show_message(itl!("import-pack-partial", "count" => charts.len(), "failed" => failed)).warn();
+                        } else if charts.len() > 1 {
+                            show_message(itl!("import-pack-success", "count" => charts.len())).ok();
+                        } else {
+                            show_message(itl!("import-success")).ok();
+                        }
+                        get_data_mut().charts.extend(charts);
                         save_data()?;
+                        achievement::check()?;
                         self.state.reload_local_charts();
                         NEED_UPDATE.store(true, Ordering::Relaxed);
                     }
@@ -331,7 +382,7 @@ impl Scene for MainScene {
         if let Some((id, file)) = take_file() {
             match id.as_str() {
                 "_import" => {
-                    self.import_task = Some(Task::new(import_chart(file)));
+                    self.import_task = Some(Task::new(import_charts(file)));
                 }
                 "_import_respack" => {
                     let item: Result<ResPackItem> = (|| {
@@ -435,6 +486,8 @@ impl Scene for MainScene {
             ui.scissor(None);
         }
 
+        self.banners.render(ui);
+
         if get_data().config.mp_enabled {
             let r = 0.06;
             self.mp_btn_pos.y = self.mp_btn_pos.y.clamp(-ui.top, ui.top);
@@ -458,6 +511,17 @@ impl Scene for MainScene {
         Ok(())
     }
 
+    fn idle(&self) -> bool {
+        !self.state.fader.transiting()
+            // the background drifts back after the device is moved
+            && self.state.gyro_offset.length() < 1e-3
+            && self.import_task.is_none()
+            && self.mp_move.is_none()
+            && !get_data().config.mp_enabled
+            && !with_manager(|it| it.items().iter().any(|it| it.state == DownloadState::Running))
+            && self.pages.last().unwrap().idle()
+    }
+
     fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
         let res = MP_PANEL
             .with(|it| it.borrow_mut().as_mut().and_then(|it| it.next_scene()))
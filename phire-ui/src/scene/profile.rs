@@ -3,28 +3,66 @@ phire::tl_file!("profile");
 use super::{confirm_delete, TEX_BACKGROUND, TEX_ICON_BACK};
 use crate::{
     anti_addiction_action,
-    client::{recv_raw, Client, Record, User, UserManager},
+    badge::{badge_color, badge_label},
+    client::{schedule, Chart, Client, Priority, Record, User, UserManager, UserStats},
     get_data, get_data_mut,
     page::{Fader, Illustration, SFader},
     save_data, sync_data,
 };
 use anyhow::Result;
 use chrono::Local;
+use image::{imageops::FilterType, DynamicImage, ImageOutputFormat};
 use macroquad::prelude::*;
 use phire::{
-    ext::{semi_black, semi_white, RectExt, SafeTexture, ScaleType, BLACK_TEXTURE},
+    ext::{poll_future, semi_black, semi_white, LocalTask, RectExt, SafeTexture, ScaleType, BLACK_TEXTURE},
     judge::icon_index,
-    scene::{request_file, return_file, show_error, show_message, take_file, NextScene, Scene},
+    scene::{pick_file, request_input, return_input, show_error, show_message, take_input, FileKind, NextScene, PickedFile, Scene},
     task::Task,
     time::TimeManager,
-    ui::{button_hit, rounded_rect_shadow, DRectButton, RectButton, Scroll, ShadowConfig, Ui},
+    ui::{button_hit, rounded_rect, rounded_rect_shadow, DRectButton, RectButton, Scroll, ShadowConfig, Ui},
 };
-use serde_json::json;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    io::Cursor,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 use tokio::sync::Notify;
+use tracing::warn;
+
+const AVATAR_SIZE: (u32, u32) = (512, 512);
+const BANNER_SIZE: (u32, u32) = (1500, 500);
+const MAX_IMAGE_BYTES: usize = 16 << 20;
+const MIN_IMAGE_SIDE: u32 = 64;
+const MAX_BIO_LENGTH: usize = 500;
+const RECENT_PLAYS: u32 = 20;
+const UPLOADED_CHARTS: u64 = 20;
+const CARD_HEIGHT: f32 = 0.2;
+
+// crops the picked image to the shape the server expects and scales it down, so that only what's shown gets uploaded
+fn prepare_image(bytes: &[u8], (w, h): (u32, u32)) -> Result<Vec<u8>> {
+    if bytes.len() > MAX_IMAGE_BYTES {
+        tl!(bail "image-too-large", "max" => MAX_IMAGE_BYTES >> 20);
+    }
+    let image = image::load_from_memory(bytes).map_err(|_| tl!(err "image-invalid"))?;
+    if image.width() < MIN_IMAGE_SIDE || image.height() < MIN_IMAGE_SIDE {
+        tl!(bail "image-too-small", "min" => MIN_IMAGE_SIDE);
+    }
+    let ratio = w as f32 / h as f32;
+    let (cw, ch) = if image.width() as f32 / image.height() as f32 > ratio {
+        ((image.height() as f32 * ratio) as u32, image.height())
+    } else {
+        (image.width(), (image.width() as f32 / ratio) as u32)
+    };
+    let mut image = image.crop_imm((image.width() - cw) / 2, (image.height() - ch) / 2, cw, ch);
+    if cw > w {
+        image = image.resize_exact(w, h, FilterType::Lanczos3);
+    }
+    let mut res = Vec::new();
+    DynamicImage::ImageRgb8(image.to_rgb8()).write_to(&mut Cursor::new(&mut res), ImageOutputFormat::Jpeg(90))?;
+    Ok(res)
+}
 
 struct RecordItem {
     record: Record,
@@ -33,10 +71,52 @@ struct RecordItem {
     illu: Illustration,
 }
 
+struct ChartItem {
+    chart: Chart,
+    btn: DRectButton,
+    illu: Illustration,
+}
+
+// a tile of the grids on the right, records and uploaded charts look alike
+fn render_card(ui: &mut Ui, r: Rect, t: f32, c: Color, btn: &mut DRectButton, illu: &Illustration, icon: Option<&SafeTexture>, title: Option<&str>, detail: &str) {
+    let pad = 0.02;
+    illu.notify();
+    let (r, path) = btn.render_shadow(ui, r, t, c.a, |r| (*illu.texture.0, r, ScaleType::CropCenter, c));
+    ui.fill_path(&path, semi_black(0.6));
+    let s = r.h - pad * 2.;
+    let mut lf = r.x + pad;
+    if let Some(icon) = icon {
+        let ir = Rect::new(lf, r.y + pad, s, s);
+        ui.fill_rect(ir, (**icon, ir, ScaleType::Fit, c));
+        lf = ir.right();
+    }
+    lf += 0.02;
+    if let Some(title) = title {
+        ui.text(title).pos(lf, r.y + pad).max_width(r.right() - lf - 0.03).size(0.56).color(c).draw();
+    }
+    ui.text(detail)
+        .pos(lf, r.bottom() - pad - 0.02)
+        .anchor(0., 1.)
+        .size(0.6)
+        .color(Color { a: c.a * 0.6, ..c })
+        .draw();
+}
+
+// lays `n` cards out two a row from `y`, `f` gets the rect of each and whether it's in view; returns the height
+fn card_grid(ui: &mut Ui, w: f32, y: f32, o: f32, n: usize, mut f: impl FnMut(&mut Ui, usize, Rect, bool)) -> f32 {
+    let pad = 0.02;
+    for i in 0..n {
+        let r = Rect::new((i % 2) as f32 * w / 2. + pad, y + (i / 2) as f32 * CARD_HEIGHT, w / 2. - pad * 2., CARD_HEIGHT - pad * 2.);
+        let visible = r.y - o <= ui.top * 2. && r.bottom() - o >= 0.;
+        f(ui, i, r, visible);
+    }
+    CARD_HEIGHT * ((n + 1) / 2) as f32
+}
+
 pub struct ProfileScene {
     id: i32,
     user: Option<Arc<User>>,
-    user_badges: Vec<String>,
+    badge_btns: Vec<RectButton>,
 
     background: SafeTexture,
 
@@ -50,7 +130,17 @@ pub struct ProfileScene {
     load_task: Option<Task<Result<Arc<User>>>>,
 
     avatar_btn: RectButton,
-    avatar_task: Option<Task<Result<()>>>,
+    banner_btn: RectButton,
+    bio_btn: RectButton,
+    // what is being edited, along with the upload progress for images
+    edit_task: Option<(&'static str, Task<Result<()>>)>,
+    // which image is being picked
+    pick_kind: &'static str,
+    pick_task: LocalTask<Option<PickedFile>>,
+    upload_progress: Arc<Mutex<f32>>,
+
+    banner: Option<SafeTexture>,
+    banner_task: Option<Task<Result<DynamicImage>>>,
 
     should_delete: Arc<AtomicBool>,
     delete_task: Option<Task<Result<()>>>,
@@ -58,6 +148,10 @@ pub struct ProfileScene {
     scroll: Scroll,
     record_task: Option<Task<Result<Vec<RecordItem>>>>,
     record_items: Option<Vec<RecordItem>>,
+    stats_task: Option<Task<Result<UserStats>>>,
+    stats: Option<UserStats>,
+    chart_task: Option<Task<Result<(Vec<Chart>, u64)>>>,
+    chart_items: Option<Vec<ChartItem>>,
 
     sf: SFader,
     fader: Fader,
@@ -73,7 +167,7 @@ impl ProfileScene {
         Self {
             id,
             user: None,
-            user_badges: Vec::new(),
+            badge_btns: Vec::new(),
 
             background: TEX_BACKGROUND.with(|it| it.borrow().clone().unwrap()),
 
@@ -87,15 +181,23 @@ impl ProfileScene {
             load_task,
 
             avatar_btn: RectButton::new(),
-            avatar_task: None,
+            banner_btn: RectButton::new(),
+            bio_btn: RectButton::new(),
+            edit_task: None,
+            pick_kind: "avatar",
+            pick_task: None,
+            upload_progress: Arc::default(),
+
+            banner: None,
+            banner_task: None,
 
             should_delete: Arc::default(),
             delete_task: None,
 
             scroll: Scroll::new(),
             record_task: Some(Task::new(async move {
-                let records: Vec<Record> = recv_raw(Client::get(format!("/record?player={id}"))).await?.json().await?;
-                Ok(records
+                Ok(Client::recent_records(id, RECENT_PLAYS)
+                    .await?
                     .into_iter()
                     .map(|it| {
                         let illu = {
@@ -113,6 +215,8 @@ impl ProfileScene {
                                 })),
                                 loaded: Arc::default(),
                                 load_time: f32::NAN,
+                                reload: None,
+                                failed: false,
                             }
                         };
                         let chart = it.chart.clone();
@@ -126,6 +230,16 @@ impl ProfileScene {
                     .collect())
             })),
             record_items: None,
+            stats_task: Some(Task::new(Client::user_stats(id))),
+            stats: None,
+            chart_task: Some(Task::new(
+                Client::query::<Chart>()
+                    .query("uploader", id.to_string())
+                    .order("-updated")
+                    .page_num(UPLOADED_CHARTS)
+                    .send(),
+            )),
+            chart_items: None,
 
             sf: SFader::new(),
             fader: Fader::new().with_distance(0.12),
@@ -151,46 +265,80 @@ impl Scene for ProfileScene {
                 match res {
                     Err(err) => show_error(err.context(tl!("load-user-failed"))),
                     Ok(res) => {
-                        self.user_badges.clear();
-                        for badge in &res.badges {
-                            match badge.as_str() {
-                                "admin" => self.user_badges.push(tl!("badge-admin").into_owned()),
-                                "sponsor" => self.user_badges.push(tl!("badge-sponsor").into_owned()),
-                                _ => {}
-                            }
-                        }
+                        self.badge_btns = vec![RectButton::new(); res.badges.len()];
+                        self.banner = None;
+                        self.banner_task = res.banner.clone().map(|file| Task::new(async move { file.load_image().await }));
                         self.user = Some(res);
                     }
                 }
                 self.load_task = None;
             }
         }
-        if let Some((id, file)) = take_file() {
-            if id == "avatar" {
-                self.avatar_task = Some(Task::new(async move {
-                    let id = Client::upload_file("avatar", std::fs::read(file)?).await?;
-                    recv_raw(Client::post("/edit/avatar", &json!({ "file": id }))).await?;
-                    Ok(())
-                }));
+        if let Some(task) = &mut self.banner_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => warn!("failed to load banner: {err:?}"),
+                    Ok(image) => self.banner = Some(image.into()),
+                }
+                self.banner_task = None;
+            }
+        }
+        if let Some(task) = &mut self.pick_task {
+            if let Some(picked) = poll_future(task.as_mut()) {
+                let kind = self.pick_kind;
+                self.pick_task = None;
+                if let Some(file) = picked {
+                    let size = if kind == "avatar" { AVATAR_SIZE } else { BANNER_SIZE };
+                    *self.upload_progress.lock().unwrap() = 0.;
+                    let progress = Arc::clone(&self.upload_progress);
+                    self.edit_task = Some((
+                        kind,
+                        Task::new(async move {
+                            let bytes = prepare_image(&file.read()?, size)?;
+                            Client::edit_image(kind, bytes, Some(progress)).await
+                        }),
+                    ));
+                }
+            }
+        }
+        if let Some((id, text)) = take_input() {
+            if id == "bio" {
+                if text.chars().count() > MAX_BIO_LENGTH {
+                    show_message(tl!("bio-too-long", "max" => MAX_BIO_LENGTH)).error();
+                } else {
+                    self.edit_task = Some(("bio", Task::new(async move { Client::edit_bio(&text).await })));
+                }
             } else {
-                return_file(id, file);
+                return_input(id, text);
             }
         }
-        if let Some(task) = &mut self.avatar_task {
+        if let Some((kind, task)) = &mut self.edit_task {
             if let Some(res) = task.take() {
                 match res {
                     Err(err) => {
-                        show_error(err.context(tl!("edit-avatar-failed")));
+                        show_error(err.context(match *kind {
+                            "avatar" => tl!("edit-avatar-failed"),
+                            "banner" => tl!("edit-banner-failed"),
+                            "badge" => tl!("edit-badge-failed"),
+                            _ => tl!("edit-bio-failed"),
+                        }));
                     }
                     Ok(_) => {
-                        show_message(tl!("edit-avatar-success")).ok();
+                        show_message(match *kind {
+                            "avatar" => tl!("edit-avatar-success"),
+                            "banner" => tl!("edit-banner-success"),
+                            "badge" => tl!("edit-badge-success"),
+                            _ => tl!("edit-bio-success"),
+                        })
+                        .ok();
                         let id = get_data().me.as_ref().unwrap().id;
                         Client::clear_cache::<User>(id)?;
                         UserManager::clear_cache(id)?;
                         UserManager::request(id);
+                        self.load_task = Some(Task::new(Client::load(id)));
                     }
                 }
-                self.avatar_task = None;
+                self.edit_task = None;
             }
         }
 
@@ -221,9 +369,46 @@ impl Scene for ProfileScene {
             }
         }
 
+        if let Some(task) = &mut self.stats_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => warn!("failed to load stats: {err:?}"),
+                    Ok(stats) => self.stats = Some(stats),
+                }
+                self.stats_task = None;
+            }
+        }
+
+        if let Some(task) = &mut self.chart_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => show_error(err.context(tl!("load-charts-failed"))),
+                    Ok((charts, _)) => {
+                        self.chart_items = Some(
+                            charts
+                                .into_iter()
+                                .map(|chart| {
+                                    let file = chart.illustration.clone();
+                                    ChartItem {
+                                        chart,
+                                        btn: DRectButton::new(),
+                                        illu: Illustration::remote(move || {
+                                            let file = file.clone();
+                                            async move { file.load_thumbnail().await }
+                                        }),
+                                    }
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+                self.chart_task = None;
+            }
+        }
+
         if self.should_delete.fetch_and(false, Ordering::Relaxed) {
             self.delete_task = Some(Task::new(async move {
-                Client::post("/delete-account", &()).send().await?.error_for_status()?;
+                schedule(Client::post("/delete-account", &()), Priority::Interactive).await?.error_for_status()?;
                 Ok(())
             }));
         }
@@ -233,12 +418,17 @@ impl Scene for ProfileScene {
                 item.illu.settle(t);
             }
         }
+        if let Some(items) = &mut self.chart_items {
+            for item in items {
+                item.illu.settle(t);
+            }
+        }
 
         Ok(())
     }
 
     fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
-        if self.avatar_task.is_some() {
+        if self.edit_task.is_some() {
             return Ok(true);
         }
         let t = tm.now() as f32;
@@ -261,9 +451,30 @@ impl Scene for ProfileScene {
             confirm_delete(Arc::clone(&self.should_delete));
             return Ok(true);
         }
-        if get_data().me.as_ref().map_or(false, |it| it.id == self.id) && self.avatar_btn.touch(touch) {
-            request_file("avatar");
-            return Ok(true);
+        if get_data().me.as_ref().map_or(false, |it| it.id == self.id) {
+            if self.avatar_btn.touch(touch) {
+                self.pick_kind = "avatar";
+                self.pick_task = Some(Box::pin(pick_file(FileKind::Image)));
+                return Ok(true);
+            }
+            if self.bio_btn.touch(touch) {
+                let bio = self.user.as_ref().and_then(|it| it.bio.clone()).unwrap_or_default();
+                request_input("bio", &bio, tl!("edit-bio"));
+                return Ok(true);
+            }
+            if self.banner_btn.touch(touch) {
+                self.pick_kind = "banner";
+                self.pick_task = Some(Box::pin(pick_file(FileKind::Image)));
+                return Ok(true);
+            }
+            if let Some(user) = &self.user {
+                if let Some(index) = self.badge_btns.iter_mut().position(|it| it.touch(touch)) {
+                    // tapping the badge already shown takes it off
+                    let badge = Some(user.badges[index].clone()).filter(|it| user.shown_badge() != Some(it.as_str()));
+                    self.edit_task = Some(("badge", Task::new(async move { Client::edit_badge(badge.as_deref()).await })));
+                    return Ok(true);
+                }
+            }
         }
 
         if self.scroll.touch(touch, t) {
@@ -278,6 +489,14 @@ impl Scene for ProfileScene {
                 }
             }
         }
+        if let Some(items) = &mut self.chart_items {
+            for item in items {
+                if item.btn.touch(touch, t) {
+                    self.scroll.y_scroller.halt();
+                    return Ok(true);
+                }
+            }
+        }
 
         Ok(false)
     }
@@ -310,6 +529,11 @@ impl Scene for ProfileScene {
             let mw = r.w - pad * 2.;
             let cx = r.center().x;
             let radius = 0.12;
+            let br = Rect::new(r.x, r.y, r.w, r.w / 3.);
+            if let Some(banner) = &self.banner {
+                rounded_rect(ui, br, 0.02, |ui| ui.fill_rect(br, (**banner, br, ScaleType::CropCenter, WHITE)));
+            }
+            self.banner_btn.set(ui, br);
             let r = ui.avatar(cx, r.y + radius + 0.05, radius, WHITE, t, UserManager::opt_avatar(self.id, &self.icon_user));
             self.avatar_btn.set(ui, r);
             let r = ui
@@ -326,21 +550,49 @@ impl Scene for ProfileScene {
                 .pos(cx, r.bottom() + 0.01)
                 .anchor(0.5, 0.)
                 .draw();
+            let is_me = get_data().me.as_ref().map_or(false, |it| it.id == self.id);
+            let bio = user.bio.as_deref().filter(|it| !it.is_empty());
             let mut r = ui
-                .text(user.bio.as_deref().unwrap_or(""))
+                .text(match bio {
+                    Some(bio) => bio.into(),
+                    None if is_me => tl!("bio-empty"),
+                    None => "".into(),
+                })
                 .pos(cx, r.bottom() + 0.01)
                 .anchor(0.5, 0.)
                 .multiline()
                 .max_width(mw)
                 .size(0.4)
+                .color(if bio.is_some() { WHITE } else { semi_white(0.6) })
                 .draw();
-            if !self.user_badges.is_empty() {
-                r = ui
-                    .text(self.user_badges.join(" "))
-                    .pos(cx, r.bottom() + 0.01)
-                    .anchor(0.5, 0.)
-                    .size(0.5)
-                    .draw();
+            self.bio_btn.set(ui, Rect::new(r.x - 0.01, r.y - 0.01, r.w + 0.02, r.h + 0.02));
+            if !user.badges.is_empty() {
+                let (size, gap) = (0.4, 0.01);
+                let shown = user.shown_badge();
+                let labels: Vec<_> = user.badges.iter().map(|it| badge_label(it)).collect();
+                let total = labels.iter().map(|it| ui.badge_width(it, size)).sum::<f32>() + gap * (labels.len() - 1) as f32;
+                let mut x = cx - total / 2.;
+                let cy = r.bottom() + 0.04;
+                let mut bottom = r.bottom();
+                for ((badge, label), btn) in user.badges.iter().zip(&labels).zip(&mut self.badge_btns) {
+                    let color = badge_color(badge);
+                    let color = if shown.map_or(true, |it| it == badge) { color } else { Color { a: 0.4, ..color } };
+                    let pill = ui.badge(label, x, cy, size, color);
+                    btn.set(ui, pill);
+                    x = pill.right() + gap;
+                    bottom = bottom.max(pill.bottom());
+                }
+                r = Rect { h: bottom - r.y, ..r };
+                if is_me {
+                    r = ui
+                        .text(tl!("badge-hint"))
+                        .pos(cx, r.bottom() + 0.01)
+                        .anchor(0.5, 0.)
+                        .max_width(mw)
+                        .size(0.3)
+                        .color(semi_white(0.6))
+                        .draw();
+                }
             }
             let r = ui
                 .text(tl!("last-login", "time" => user.last_login.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()))
@@ -362,6 +614,9 @@ impl Scene for ProfileScene {
 
         let r = Rect::new(r.right() + 0.05, r.y, 0.9 - r.right(), 1.5);
         if let Some(items) = &mut self.record_items {
+            let stats = self.stats.as_ref();
+            let charts = &mut self.chart_items;
+            let rank_icons = &self.rank_icons;
             self.fader.reset();
             self.fader.for_sub(|f| {
                 ui.scope(|ui| {
@@ -370,45 +625,70 @@ impl Scene for ProfileScene {
                     let o = self.scroll.y_scroller.offset;
                     self.scroll.size((r.w, ui.top * 2.));
                     self.scroll.render(ui, |ui| {
-                        let n = items.len();
-                        let h = 0.2;
-                        let pad = 0.02;
-                        let mut iter = items.iter_mut();
-                        for i in 0..((n + 1) / 2) {
-                            for j in 0..(n - i * 2).min(2) {
-                                let Some(item) = iter.next() else { unreachable!() };
-                                f.render(ui, t, |ui, c| {
-                                    let r = Rect::new(j as f32 * r.w / 2. + pad, r.y + ui.top + i as f32 * h, r.w / 2. - pad * 2., h - pad * 2.);
-                                    if r.y - o > ui.top * 2. || r.bottom() - o < 0. {
-                                        return;
-                                    }
-                                    item.illu.notify();
-                                    let (r, path) = item
-                                        .btn
-                                        .render_shadow(ui, r, t, c.a, |r| (*item.illu.texture.0, r, ScaleType::CropCenter, c));
-                                    ui.fill_path(&path, semi_black(0.6));
-
-                                    let icon = icon_index(item.record.score as _, item.record.full_combo);
-                                    let s = r.h - pad * 2.;
-                                    let ir = Rect::new(r.x + pad, r.y + pad, s, s);
-                                    ui.fill_rect(ir, (*self.rank_icons[icon], ir, ScaleType::Fit, c));
-
-                                    let lf = ir.right() + 0.02;
-
-                                    if let Some(Ok(name)) = item.name.get().as_ref() {
-                                        ui.text(name).pos(lf, ir.y).max_width(r.right() - lf - 0.03).size(0.56).color(c).draw();
-                                    }
-
-                                    ui.text(format!("{:07} {}", item.record.score, if item.record.full_combo { "[FC]" } else { "" }))
-                                        .pos(lf, ir.bottom() - 0.02)
-                                        .anchor(0., 1.)
-                                        .size(0.6)
+                        let mut y = r.y + ui.top;
+                        if let Some(stats) = stats {
+                            let entries = [
+                                (stats.play_count.to_string(), tl!("stat-plays")),
+                                (format!("{:.2}%", stats.avg_accuracy * 100.), tl!("stat-avg-acc")),
+                                (format!("{:.2}%", stats.best_accuracy * 100.), tl!("stat-best-acc")),
+                                (stats.full_combos.to_string(), tl!("stat-fc")),
+                                (stats.phis.to_string(), tl!("stat-phi")),
+                            ];
+                            let cw = r.w / entries.len() as f32;
+                            f.render(ui, t, |ui, c| {
+                                for (i, (value, label)) in entries.into_iter().enumerate() {
+                                    let cx = cw * (i as f32 + 0.5);
+                                    let vr = ui.text(value).pos(cx, y).anchor(0.5, 0.).size(0.6).color(c).draw();
+                                    ui.text(label)
+                                        .pos(cx, vr.bottom() + 0.01)
+                                        .anchor(0.5, 0.)
+                                        .size(0.35)
                                         .color(Color { a: c.a * 0.6, ..c })
                                         .draw();
+                                }
+                            });
+                            y += 0.14;
+                        }
+
+                        let header = |ui: &mut Ui, f: &mut Fader, y: &mut f32, text: &str| {
+                            f.render(ui, t, |ui, c| {
+                                ui.text(text).pos(0.02, *y).size(0.6).color(c).draw();
+                            });
+                            *y += 0.07;
+                        };
+                        header(ui, f, &mut y, &tl!("recent-plays"));
+                        if items.is_empty() {
+                            ui.text(tl!("no-plays")).pos(0.02, y).size(0.45).color(semi_white(0.6)).draw();
+                            y += 0.08;
+                        }
+                        y += card_grid(ui, r.w, y, o, items.len(), |ui, i, cr, visible| {
+                            let item = &mut items[i];
+                            let record = &item.record;
+                            let icon = &rank_icons[icon_index(record.score as _, record.full_combo)];
+                            let title = item.name.get().as_ref().and_then(|it| it.as_ref().ok()).cloned();
+                            let detail = format!("{:07} {:.2}%{}", record.score, record.accuracy * 100., if record.full_combo { " [FC]" } else { "" });
+                            f.render(ui, t, |ui, c| {
+                                if visible {
+                                    render_card(ui, cr, t, c, &mut item.btn, &item.illu, Some(icon), title.as_deref(), &detail);
+                                }
+                            });
+                        });
+
+                        if let Some(charts) = charts.as_mut().filter(|it| !it.is_empty()) {
+                            y += 0.02;
+                            let count = stats.map_or(charts.len() as u32, |it| it.chart_count);
+                            header(ui, f, &mut y, &tl!("uploaded-charts", "count" => count));
+                            y += card_grid(ui, r.w, y, o, charts.len(), |ui, i, cr, visible| {
+                                let item = &mut charts[i];
+                                let detail = format!("{} {:.1}", item.chart.level, item.chart.difficulty);
+                                f.render(ui, t, |ui, c| {
+                                    if visible {
+                                        render_card(ui, cr, t, c, &mut item.btn, &item.illu, None, Some(&item.chart.name), &detail);
+                                    }
                                 });
-                            }
+                            });
                         }
-                        (r.w, r.y + ui.top + h * ((n + 1) / 2) as f32 + 0.04)
+                        (r.w, y + 0.04)
                     })
                 });
             });
@@ -419,8 +699,15 @@ impl Scene for ProfileScene {
 
         self.sf.render(ui, t);
 
-        if self.avatar_task.is_some() {
-            ui.full_loading(tl!("uploading-avatar"), t);
+        if let Some((kind, _)) = &self.edit_task {
+            let progress = format!("{:.0}", *self.upload_progress.lock().unwrap() * 100.);
+            let text = match *kind {
+                "avatar" => tl!("uploading-avatar", "progress" => progress).into(),
+                "banner" => tl!("uploading-banner", "progress" => progress).into(),
+                "badge" => tl!("saving-badge"),
+                _ => tl!("saving-bio"),
+            };
+            ui.full_loading(text, t);
         }
         Ok(())
     }
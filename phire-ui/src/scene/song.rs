@@ -1,66 +1,79 @@
 phire::tl_file!("song");
 
-use super::{confirm_delete, confirm_dialog, fs_from_path, render_ldb, LdbDisplayItem, ProfileScene};
+use super::{confirm_delete, confirm_dialog, fs_from_path, render_ldb, EditorScene, LdbDisplayItem, ProfileScene};
 use crate::{
+    achievement::{self, Play},
+    audio_preview::with_effects,
+    chart_index,
     charts_view::NEED_UPDATE,
-    client::{basic_client_builder, recv_raw, Chart, Client, Permissions, Ptr, Record, UserManager, CLIENT_TOKEN},
-    data::{BriefChartInfo, LocalChart},
+    client::{recv_raw, Chart, Client, LdbScope, Leaderboard, Permissions, Ptr, UserManager, CACHE_DIR},
+    data::{BriefChartInfo, PlayHistory},
+    download::{file_request, prefetch_key, with_manager, DownloadState},
     dir, get_data, get_data_mut,
     icons::Icons,
+    mp::SpectateBoard,
     page::{thumbnail_path, ChartItem, Fader, Illustration, SFader},
     popup::Popup,
-    rate::RateDialog,
+    rate::{Comments, RateDialog},
     save_data,
-    tags::TagsDialog,
+    tags::{level_label, tag_label, TagsDialog},
+    translate::TranslatedText,
+    upload::{self, PendingUpload, RecordPayload},
 };
-use ::rand::{rng, Rng};
+use ::rand::{random, rng, Rng};
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
+use image::{ImageFormat, RgbaImage};
 use macroquad::prelude::*;
 use phira_mp_common::{ClientCommand, CompactPos, JudgeEvent, TouchFrame};
 use phire::{
     config::Mods,
-    core::Tweenable,
-    ext::{poll_future, semi_black, semi_white, unzip_into, JoinToString, LocalTask, RectExt, SafeTexture, ScaleType},
-    fs,
+    core::{estimate_skills, MusicStream, SkillProfile, Tweenable},
+    ext::{poll_future, semi_black, semi_white, JoinToString, LocalTask, RectExt, SafeTexture, ScaleType},
+    fs::{self, FileSystem, ZipFileSystem},
     info::ChartInfo,
     judge::{icon_index, Judge},
+    parse::export,
     scene::{
-        request_input, return_input, show_error, show_message, take_input, BasicPlayer, GameMode, LoadingScene, LocalSceneTask, NextScene,
-        RecordUpdateState, Scene, SimpleRecord, UpdateFn,
+        request_input, return_input, show_error, show_message, take_input, BasicPlayer, ChartPreview, GameMode, GameScene, LoadingScene, LocalSceneTask, NextScene,
+        RecordUpdateState, RenderFn, Scene, SimpleRecord, UpdateFn,
     },
     task::Task,
     time::TimeManager,
-    ui::{button_hit, render_chart_info, ChartInfoEdit, DRectButton, Dialog, LoadingParams, RectButton, Scroll, Ui, UI_AUDIO},
+    ui::{button_hit, render_chart_info, ChartInfoEdit, DRectButton, Dialog, LoadingParams, MemoryGuard, RectButton, Scroll, Ui, UI_AUDIO},
 };
 use reqwest::Method;
-use sasa::{AudioClip, Frame, Music, MusicParams};
-use serde::{Deserialize, Serialize};
+use sasa::{AudioClip, Music, MusicParams};
+use serde::Deserialize;
 use serde_json::json;
 use std::{
     any::Any,
     borrow::Cow,
+    cell::RefCell,
     collections::{hash_map, HashMap, VecDeque},
     fs::File,
     io::{Cursor, Write},
     path::Path,
+    rc::Rc,
     sync::{
         atomic::{AtomicBool, AtomicI32, Ordering},
-        Arc, Mutex, Weak,
+        Arc,
     },
     thread_local,
 };
 use tokio::net::TcpStream;
 use tracing::warn;
-use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
 const FADE_IN_TIME: f32 = 0.3;
 const EDIT_TRANSIT: f32 = 0.32;
+// a leaderboard row is 0.14 high
+const LDB_PRELOAD: f32 = 0.5;
 
 static CONFIRM_UPLOAD: AtomicBool = AtomicBool::new(false);
+static KEEP_ONLINE: AtomicBool = AtomicBool::new(false);
 pub static RECORD_ID: AtomicI32 = AtomicI32::new(-1);
 
 fn create_music(clip: AudioClip) -> Result<Music> {
@@ -78,85 +91,109 @@ fn create_music(clip: AudioClip) -> Result<Music> {
     Ok(music)
 }
 
-fn with_effects((mut frames, sample_rate): (Vec<Frame>, u32), range: Option<(f32, f32)>) -> Result<AudioClip> {
-    if let Some((begin, end)) = range {
-        frames.drain(((end * sample_rate as f32) as usize).min(frames.len())..);
-        frames.drain(..((begin * sample_rate as f32) as usize));
-    }
-    let len = (0.8 * sample_rate as f64) as usize;
-    let len = len.min(frames.len() / 2);
-    for (i, frame) in frames[..len].iter_mut().enumerate() {
-        let s = i as f32 / len as f32;
-        frame.0 *= s;
-        frame.1 *= s;
+// charts larger than this are left to the actual download
+const PREFETCH_LIMIT: u64 = 8 << 20;
+
+// pulls a small chart file into the http cache while the page is open, so that downloading it afterwards is instant;
+// dropping it stops the transfer
+struct Prefetch {
+    cancel: Arc<AtomicBool>,
+    _task: Task<()>,
+}
+
+impl Prefetch {
+    fn new(url: String) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task = Task::new({
+            let cancel = Arc::clone(&cancel);
+            async move {
+                let res: Result<()> = async {
+                    let key = prefetch_key(&url);
+                    if cacache::metadata(&*CACHE_DIR, &key).await?.is_some() {
+                        return Ok(());
+                    }
+                    let res = file_request(&url)?.send().await?.error_for_status()?;
+                    if res.content_length().map_or(true, |it| it > PREFETCH_LIMIT) {
+                        return Ok(());
+                    }
+                    let mut bytes = Vec::new();
+                    let mut stream = res.bytes_stream();
+                    while let Some(chunk) = stream.next().await {
+                        if cancel.load(Ordering::Relaxed) {
+                            return Ok(());
+                        }
+                        bytes.extend_from_slice(&chunk?);
+                    }
+                    cacache::write(&*CACHE_DIR, &key, &bytes).await?;
+                    Ok(())
+                }
+                .await;
+                if let Err(err) = res {
+                    warn!("failed to prefetch chart: {err:?}");
+                }
+            }
+        });
+        Self { cancel, _task: task }
     }
-    let st = frames.len() - len;
-    for (i, frame) in frames[st..].iter_mut().rev().enumerate() {
-        let s = i as f32 / len as f32;
-        frame.0 *= s;
-        frame.1 *= s;
+}
+
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
     }
-    Ok(AudioClip::from_raw(frames, sample_rate))
 }
 
 pub struct Downloading {
-    info: BriefChartInfo,
-    local_path: Option<String>,
+    id: i32,
     loading_last: f32,
     cancel_download_btn: DRectButton,
-    status: Arc<Mutex<Cow<'static, str>>>,
-    prog: Arc<Mutex<Option<f32>>>,
-    task: Task<Result<LocalChart>>,
+    background_btn: DRectButton,
 }
 
 impl Downloading {
+    pub fn new(id: i32) -> Self {
+        Self {
+            id,
+            loading_last: 0.,
+            cancel_download_btn: DRectButton::new(),
+            background_btn: DRectButton::new(),
+        }
+    }
+
+    // true once the overlay should go away; the download itself only stops when cancelled
     pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
-        self.cancel_download_btn.touch(touch, t)
+        if self.cancel_download_btn.touch(touch, t) {
+            with_manager(|it| it.cancel(self.id));
+            return true;
+        }
+        self.background_btn.touch(touch, t)
     }
 
     pub fn render(&mut self, ui: &mut Ui, t: f32) {
+        let Some((status, ratio)) = with_manager(|it| it.get(self.id).map(|it| (it.progress.status(), it.progress.ratio()))) else {
+            return;
+        };
         ui.fill_rect(ui.screen_rect(), semi_black(0.6));
-        ui.loading(0., -0.06, t, WHITE, (*self.prog.lock().unwrap(), &mut self.loading_last));
-        ui.text(self.status.lock().unwrap().clone())
-            .pos(0., 0.02)
-            .anchor(0.5, 0.)
-            .size(0.6)
-            .draw();
+        ui.loading(0., -0.06, t, WHITE, (ratio, &mut self.loading_last));
+        ui.text(status).pos(0., 0.02).anchor(0.5, 0.).size(0.6).draw();
         let size = 0.7;
-        let r = ui.text(tl!("dl-cancel")).pos(0., 0.12).anchor(0.5, 0.).size(size).measure().feather(0.02);
+        let r = ui.text(tl!("dl-cancel")).pos(-0.02, 0.12).anchor(1., 0.).size(size).measure().feather(0.02);
         self.cancel_download_btn.render_text(ui, r, t, 1., tl!("dl-cancel"), 0.6, true);
+        let r = ui.text(tl!("dl-background")).pos(0.02, 0.12).anchor(0., 0.).size(size).measure().feather(0.02);
+        self.background_btn.render_text(ui, r, t, 1., tl!("dl-background"), 0.6, true);
     }
 
+    // Some(true) when the chart is ready, Some(false) if the download failed or went away
     pub fn check(&mut self) -> Result<Option<bool>> {
-        if let Some(res) = self.task.take() {
-            match res {
-                Err(err) => {
-                    let path = format!("{}/{}", dir::downloaded_charts()?, self.info.id.unwrap());
-                    let path = Path::new(&path);
-                    if path.exists() {
-                        std::fs::remove_dir_all(path)?;
-                    }
-                    show_error(err.context(tl!("dl-failed")));
-                    Ok(Some(false))
-                }
-                Ok(chart) => {
-                    self.info = chart.info.clone();
-                    if let Some(local_path) = &self.local_path {
-                        // update
-                        SongScene::global_update_chart_info(local_path, self.info.clone())?;
-                    } else {
-                        NEED_UPDATE.store(true, Ordering::Relaxed);
-                        self.local_path = Some(chart.local_path.clone());
-                        get_data_mut().charts.push(chart);
-                    }
-                    save_data()?;
-                    show_message(tl!("dl-success")).ok();
-                    Ok(Some(true))
-                }
-            }
-        } else {
-            Ok(None)
-        }
+        Ok(with_manager(|it| match it.get(self.id).map(|it| it.state) {
+            None | Some(DownloadState::Failed) => Some(false),
+            Some(DownloadState::Done) => Some(true),
+            _ => None,
+        }))
+    }
+
+    pub fn local_path(&self) -> Option<String> {
+        with_manager(|it| it.get(self.id).and_then(|it| it.local_path.clone()))
     }
 }
 
@@ -165,6 +202,8 @@ enum SideContent {
     Leaderboard,
     Info,
     Mods,
+    History,
+    Comments,
 }
 
 impl SideContent {
@@ -174,22 +213,142 @@ impl SideContent {
             Self::Leaderboard => 0.94,
             Self::Info => 0.75,
             Self::Mods => 0.8,
+            Self::History => 0.9,
+            Self::Comments => 0.9,
         }
     }
 }
 
-#[derive(Deserialize)]
-struct StableR {
-    status: i8,
+const HISTORY_ACC_COLOR: Color = Color::new(0.55, 0.82, 1., 1.);
+const HISTORY_VERSION_COLOR: Color = Color::new(1., 0.6, 0.25, 0.8);
+const HISTORY_BEST_COLOR: Color = Color::new(1., 0.84, 0.3, 0.8);
+
+// score and accuracy share the same axis, both as a fraction of the maximum
+fn best_shot_path(local_path: &str) -> Option<String> {
+    Some(format!("{}/{:x}.png", dir::screenshots().ok()?, md5::compute(local_path)))
+}
+
+// hit deltas of the best play, as a JSON array
+fn best_deltas_path(local_path: &str) -> Option<String> {
+    Some(format!("{}/{:x}.json", dir::deltas().ok()?, md5::compute(local_path)))
+}
+
+fn load_best_shot(path: &str) -> Result<SafeTexture> {
+    let image = image::open(path)?.into_rgba8();
+    Ok(Texture2D::from_rgba8(image.width() as _, image.height() as _, &image).into())
+}
+
+// unknown hashes are given the benefit of the doubt
+fn is_previous_version(hash: &Option<String>, current: &Option<String>) -> bool {
+    hash.is_some() && current.is_some() && hash != current
+}
+
+fn render_history_graph(ui: &mut Ui, r: Rect, history: &[PlayHistory]) {
+    use lyon::{math::point, path::Path};
+    ui.fill_rect(r, semi_black(0.3));
+    let n = history.len();
+    let x = |i: usize| if n == 1 { r.center().x } else { r.x + r.w * i as f32 / (n - 1) as f32 };
+    for (i, pair) in history.windows(2).enumerate() {
+        if pair[0].chart_version != pair[1].chart_version {
+            let x = (x(i) + x(i + 1)) / 2.;
+            ui.fill_rect(Rect::new(x - 0.002, r.y, 0.004, r.h), HISTORY_VERSION_COLOR);
+        }
+    }
+    let low = history
+        .iter()
+        .map(|it| (it.score as f32 / 1_000_000.).min(it.accuracy))
+        .fold(1., f32::min);
+    let low = ((low * 20.).floor() / 20.).min(0.95);
+    let y = |v: f32| r.bottom() - (v - low) / (1. - low) * r.h * 0.9 - r.h * 0.05;
+    // personal best progression, stepping up whenever a play beats it
+    if n > 1 {
+        let mut p = Path::builder();
+        let mut best = history[0].score as f32 / 1_000_000.;
+        p.begin(point(x(0), y(best)));
+        for (i, entry) in history.iter().enumerate().skip(1) {
+            let score = entry.score as f32 / 1_000_000.;
+            if score > best {
+                p.line_to(point(x(i), y(best)));
+                best = score;
+            }
+            p.line_to(point(x(i), y(best)));
+        }
+        p.end(false);
+        ui.stroke_path(&p.build(), 0.008, HISTORY_BEST_COLOR);
+    }
+    for (color, values) in [
+        (WHITE, history.iter().map(|it| it.score as f32 / 1_000_000.).collect::<Vec<_>>()),
+        (HISTORY_ACC_COLOR, history.iter().map(|it| it.accuracy).collect()),
+    ] {
+        if n > 1 {
+            let mut p = Path::builder();
+            p.begin(point(x(0), y(values[0])));
+            for (i, v) in values.iter().enumerate().skip(1) {
+                p.line_to(point(x(i), y(*v)));
+            }
+            p.end(false);
+            ui.stroke_path(&p.build(), 0.005, color);
+        }
+        for (i, v) in values.iter().enumerate() {
+            ui.fill_circle(x(i), y(*v), 0.008, color);
+        }
+    }
+    ui.text(format!("{:.0}%", low * 100.))
+        .pos(r.x + 0.01, r.bottom() - 0.01)
+        .anchor(0., 1.)
+        .size(0.3)
+        .color(semi_white(0.5))
+        .draw();
+}
+
+const SKILL_LABELS: [&str; 4] = ["skill-speed", "skill-stamina", "skill-flick", "skill-rhythm"];
+
+// four axes clockwise from the top, with the outline of a maxed out chart behind
+fn render_skill_radar(ui: &mut Ui, ct: Vec2, radius: f32, skills: &SkillProfile) {
+    use lyon::{math::point, path::Path};
+    let dirs = [vec2(0., -1.), vec2(1., 0.), vec2(0., 1.), vec2(-1., 0.)];
+    let polygon = |values: [f32; 4]| {
+        let mut p = Path::builder();
+        for (i, (dir, v)) in dirs.iter().zip(values).enumerate() {
+            let pt = ct + *dir * radius * v.max(0.02);
+            if i == 0 {
+                p.begin(point(pt.x, pt.y));
+            } else {
+                p.line_to(point(pt.x, pt.y));
+            }
+        }
+        p.end(true);
+        p.build()
+    };
+    ui.fill_path(&polygon([1.; 4]), semi_black(0.3));
+    ui.stroke_path(&polygon([0.5; 4]), 0.003, semi_white(0.2));
+    for dir in dirs {
+        let end = ct + dir * radius;
+        let mut p = Path::builder();
+        p.begin(point(ct.x, ct.y));
+        p.line_to(point(end.x, end.y));
+        p.end(false);
+        ui.stroke_path(&p.build(), 0.003, semi_white(0.2));
+    }
+    let values = skills.values();
+    let shape = polygon(values);
+    ui.fill_path(&shape, Color { a: 0.35, ..HISTORY_ACC_COLOR });
+    ui.stroke_path(&shape, 0.005, HISTORY_ACC_COLOR);
+    for ((dir, label), v) in dirs.iter().zip(SKILL_LABELS).zip(values) {
+        let pos = ct + *dir * (radius + 0.03);
+        ui.text(format!("{} {:.0}", tl!(label), v * 100.))
+            .pos(pos.x, pos.y)
+            .anchor(0.5 - dir.x * 0.5, 0.5 - dir.y * 0.5)
+            .no_baseline()
+            .size(0.35)
+            .color(semi_white(0.8))
+            .draw();
+    }
 }
 
 #[derive(Deserialize)]
-struct LdbItem {
-    #[serde(flatten)]
-    pub inner: Record,
-    pub rank: u32,
-    #[serde(skip, default)]
-    pub btn: RectButton,
+struct StableR {
+    status: i8,
 }
 
 pub struct SongScene {
@@ -205,18 +364,38 @@ pub struct SongScene {
     next_scene: Option<NextScene>,
 
     preview: Option<Music>,
+    _preview_memory: Option<MemoryGuard>,
     preview_task: Option<Task<Result<AudioClip>>>,
 
     load_task: Option<Task<Result<Option<Arc<Chart>>>>>,
     entity: Option<Chart>,
+    prefetch: Option<Prefetch>,
     info: BriefChartInfo,
     local_path: Option<String>,
 
     downloading: Option<Downloading>,
     loading_last: f32,
 
+    online_task: Option<Task<Result<Vec<u8>>>>,
+    played_online: bool,
+
     rank_icons: [SafeTexture; 8],
     record: Option<SimpleRecord>,
+    record_chart_hash: Option<String>,
+    record_btn: RectButton,
+    chart_hash: Option<String>,
+    chart_hash_task: Option<Task<Result<String>>>,
+    // newest modification time in the chart folder, standing in for the update time of local charts
+    local_version: Option<DateTime<Utc>>,
+    local_version_task: Option<Task<Result<Option<DateTime<Utc>>>>>,
+
+    history: Vec<PlayHistory>,
+    history_scroll: Scroll,
+    // the result screen of the personal best
+    best_shot: Option<String>,
+    best_shot_btn: DRectButton,
+    best_shot_task: Option<Task<Result<String>>>,
+    best_shot_view: Option<SafeTexture>,
 
     fetch_best_task: Option<Task<Result<SimpleRecord>>>,
 
@@ -241,16 +420,19 @@ pub struct SongScene {
     save_task: Option<Task<Result<(ChartInfo, AudioClip)>>>,
     upload_task: Option<Task<Result<BriefChartInfo>>>,
 
-    ldb: Option<(Option<u32>, Vec<LdbItem>)>,
-    ldb_task: Option<Task<Result<Vec<LdbItem>>>>,
+    ldb: Option<Leaderboard>,
     ldb_btn: RectButton,
     ldb_scroll: Scroll,
     ldb_fader: Fader,
     ldb_type_btn: DRectButton,
     ldb_std: bool,
+    ldb_scope_btn: DRectButton,
+    ldb_scope: LdbScope,
 
     info_btn: RectButton,
     info_scroll: Scroll,
+    skills: Option<SkillProfile>,
+    skills_task: LocalTask<Result<SkillProfile>>,
 
     review_task: Option<Task<Result<String>>>,
     chart_should_delete: Arc<AtomicBool>,
@@ -259,7 +441,9 @@ pub struct SongScene {
     tags: TagsDialog,
 
     rate_dialog: RateDialog,
-    rate_task: Option<Task<Result<()>>>,
+    // resolves to the new rating, 0 when it was removed
+    rate_task: Option<Task<Result<i16>>>,
+    comments: Option<Comments>,
 
     should_update: Arc<AtomicBool>,
 
@@ -270,8 +454,15 @@ pub struct SongScene {
     should_stabilize: Arc<AtomicBool>,
 
     scene_task: LocalTask<Result<NextScene>>,
+    convert_task: LocalTask<Result<String>>,
 
     uploader_btn: RectButton,
+    desc: TranslatedText,
+
+    // an autoplayed slice of the chart shown in place without leaving the page
+    chart_preview: Option<ChartPreview>,
+    chart_preview_task: LocalTask<Result<ChartPreview>>,
+    chart_preview_btn: DRectButton,
 
     sf: SFader,
 }
@@ -305,15 +496,37 @@ impl SongScene {
                 })),
                 loaded: Arc::default(),
                 load_time: f32::NAN,
+                reload: None,
+                failed: false,
             }
         } else {
             chart.illu
         };
-        let record = get_data()
+        let (record, record_chart_hash) = get_data()
             .charts
             .iter()
             .find(|it| Some(&it.local_path) == local_path.as_ref())
-            .and_then(|it| it.record.clone());
+            .map(|it| (it.record.clone(), it.record_chart_hash.clone()))
+            .unwrap_or_default();
+        let chart_hash_task = local_path.as_ref().map(|path| {
+            let path = path.clone();
+            let chart = chart.info.chart.clone();
+            Task::new(async move {
+                let bytes = fs_from_path(&path)?.load_file(&chart).await?;
+                Ok(format!("{:x}", md5::compute(bytes)))
+            })
+        });
+        let local_version_task = local_path.as_ref().filter(|_| chart.info.chart_updated.is_none()).map(|path| {
+            let path = path.clone();
+            Task::new(fs::spawn_task(move || {
+                Ok(WalkDir::new(format!("{}/{path}", dir::charts()?))
+                    .into_iter()
+                    .filter_map(|it| it.ok()?.metadata().ok()?.modified().ok())
+                    .max()
+                    .map(Into::into))
+            }))
+        });
+        let history = local_path.as_deref().and_then(|it| PlayHistory::load(it).ok()).unwrap_or_default();
         let fetch_best_task = if get_data().me.is_some() {
             chart.info.id.map(|id| Task::new(Client::best_record(id)))
         } else {
@@ -335,16 +548,15 @@ impl SongScene {
             next_scene: None,
 
             preview: None,
+            _preview_memory: None,
             preview_task: Some(Task::new({
                 let local_path = local_path.clone();
                 async move {
                     if let Some(path) = local_path {
                         let mut fs = fs_from_path(&path)?;
                         let info = fs::load_info(fs.as_mut()).await?;
-                        with_effects(
-                            AudioClip::decode(fs.load_file(&info.music).await?)?,
-                            Some((info.preview_start, info.preview_end.unwrap_or(info.preview_start + 15.))),
-                        )
+                        let end = info.preview_end.unwrap_or(info.preview_start + 15.);
+                        with_effects(MusicStream::decode_range(fs.load_file(&info.music).await?, info.preview_start, end)?, None)
                     } else {
                         let chart = Ptr::<Chart>::new(id.unwrap()).fetch().await?;
                         with_effects(AudioClip::decode(chart.preview.fetch().await?.to_vec())?, None)
@@ -358,14 +570,31 @@ impl SongScene {
                 id.map(|it| Task::new(async move { Ptr::new(it).fetch_opt().await }))
             },
             entity: None,
+            prefetch: None,
             info: chart.info,
             local_path,
 
             downloading: None,
             loading_last: 0.,
 
+            online_task: None,
+            played_online: false,
+
             rank_icons,
             record,
+            record_chart_hash,
+            record_btn: RectButton::new(),
+            chart_hash: None,
+            chart_hash_task,
+            local_version: None,
+            local_version_task,
+
+            history,
+            history_scroll: Scroll::new(),
+            best_shot: local_path.as_deref().and_then(best_shot_path).filter(|it| Path::new(it).exists()),
+            best_shot_btn: DRectButton::new(),
+            best_shot_task: None,
+            best_shot_view: None,
 
             fetch_best_task,
 
@@ -391,15 +620,18 @@ impl SongScene {
             upload_task: None,
 
             ldb: None,
-            ldb_task: None,
             ldb_btn: RectButton::new(),
             ldb_scroll: Scroll::new(),
             ldb_fader: Fader::new().with_distance(0.12),
             ldb_type_btn: DRectButton::new(),
             ldb_std: false,
+            ldb_scope_btn: DRectButton::new(),
+            ldb_scope: LdbScope::Global,
 
             info_btn: RectButton::new(),
             info_scroll: Scroll::new(),
+            skills: None,
+            skills_task: None,
 
             review_task: None,
             chart_should_delete: Arc::default(),
@@ -409,6 +641,7 @@ impl SongScene {
 
             rate_dialog: RateDialog::new(icon_star, false),
             rate_task: None,
+            comments: None,
 
             should_update: Arc::default(),
 
@@ -432,8 +665,14 @@ impl SongScene {
             should_stabilize: Arc::default(),
 
             scene_task: None,
+            convert_task: None,
 
             uploader_btn: RectButton::new(),
+            desc: TranslatedText::new(String::new()),
+
+            chart_preview: None,
+            chart_preview_task: None,
+            chart_preview_btn: DRectButton::new(),
 
             sf: SFader::new(),
         }
@@ -451,116 +690,72 @@ impl SongScene {
     }
 
     pub fn global_start_download(chart: BriefChartInfo, entity: Chart, local_path: Option<String>) -> Result<Downloading> {
-        let progress = Arc::new(Mutex::new(None));
-        let prog_wk = Arc::downgrade(&progress);
-        let status = Arc::new(Mutex::new(tl!("dl-status-fetch")));
-        let status_shared = Arc::clone(&status);
-        Ok(Downloading {
-            info: chart.clone(),
-            local_path,
-            loading_last: 0.,
-            cancel_download_btn: DRectButton::new(),
-            prog: progress,
-            status: status_shared,
-            task: Task::new({
-                let path = format!("{}/{}", dir::downloaded_charts()?, Uuid::new_v4());
-                async move {
-                    let path = std::path::Path::new(&path);
-                    tokio::fs::create_dir(path).await?;
-                    let dir = phire::dir::Dir::new(path)?;
-
-                    let chart = chart;
-                    async fn download(mut file: impl Write, url: &str, prog_wk: &Weak<Mutex<Option<f32>>>) -> Result<()> {
-                        let Some(prog) = prog_wk.upgrade() else { return Ok(()) };
-                        *prog.lock().unwrap() = None;
-                        let req = basic_client_builder().build().unwrap().get(url);
-                        let req = if let Some(token) = CLIENT_TOKEN.load().as_ref() {
-                            req.header("Authorization", format!("Bearer {token}"))
-                        } else {
-                            req
-                        };
-                        let res = req.send().await.with_context(|| tl!("request-failed"))?.error_for_status()?;
-                        let size = res.content_length();
-                        let mut stream = res.bytes_stream();
-                        let mut count = 0;
-                        while let Some(chunk) = stream.next().await {
-                            let chunk = chunk?;
-                            file.write_all(&chunk)?;
-                            count += chunk.len() as u64;
-                            if let Some(size) = size {
-                                *prog.lock().unwrap() = Some(count.min(size) as f32 / size as f32);
-                            }
-                            if prog_wk.strong_count() == 1 {
-                                // cancelled
-                                break;
-                            }
-                        }
-                        Ok(())
-                    }
-
-                    *status.lock().unwrap() = tl!("dl-status-chart");
-                    let mut bytes = Vec::new();
-                    download(Cursor::new(&mut bytes), &entity.file.url, &prog_wk).await?;
-                    *status.lock().unwrap() = tl!("dl-status-extract");
-                    if prog_wk.strong_count() != 0 {
-                        unzip_into(Cursor::new(bytes), &dir, false)?;
-                    }
-                    *status.lock().unwrap() = tl!("dl-status-saving");
-                    if let Some(prog) = prog_wk.upgrade() {
-                        *prog.lock().unwrap() = None;
-                    }
-                    let mut info: ChartInfo = serde_yaml::from_reader(dir.open("info.yml")?)?;
-                    info.id = Some(entity.id);
-                    info.created = Some(entity.created);
-                    info.updated = Some(entity.updated);
-                    info.chart_updated = Some(entity.chart_updated);
-                    info.uploader = Some(entity.uploader.id);
-                    serde_yaml::to_writer(dir.create("info.yml")?, &info)?;
-
-                    if prog_wk.strong_count() == 0 {
-                        // cancelled
-                        drop(dir);
-                        tokio::fs::remove_dir_all(&path).await?;
-                    }
+        let id = entity.id;
+        with_manager(|it| it.enqueue(chart, entity, local_path));
+        Ok(Downloading::new(id))
+    }
 
-                    let local_path = format!("download/{}", chart.id.unwrap());
-                    let to_path = format!("{}/{local_path}", dir::charts()?);
-                    let to_path = Path::new(&to_path);
-                    if to_path.exists() {
-                        if to_path.is_file() {
-                            tokio::fs::remove_file(to_path).await?;
-                        } else {
-                            tokio::fs::remove_dir_all(to_path).await?;
-                        }
-                    }
-                    tokio::fs::rename(path, to_path).await?;
+    // a cached leaderboard is reused unless `refresh` is set
+    fn load_ldb(&mut self, refresh: bool) {
+        let Some(id) = self.info.id else { return };
+        let mut ldb = Leaderboard::new(id, self.ldb_std, self.ldb_scope);
+        if refresh {
+            ldb.refresh();
+        }
+        if ldb.loaded() {
+            UserManager::request_many(ldb.items().iter().map(|it| it.inner.player.id));
+        }
+        self.ldb = Some(ldb);
+    }
 
-                    Ok(LocalChart {
-                        info: entity.to_info(),
-                        local_path,
-                        record: None,
-                        mods: Mods::default(),
-                    })
-                }
-            }),
-        })
+    fn chart_version(&self) -> Option<DateTime<Utc>> {
+        self.info.chart_updated.or(self.local_version)
     }
 
-    fn load_ldb(&mut self) {
-        if get_data().config.offline_mode {
+    fn add_history(&mut self, rec: &SimpleRecord) {
+        let Some(local_path) = self.local_path.clone() else {
             return;
+        };
+        let entry = PlayHistory {
+            local_path,
+            time: Utc::now(),
+            score: rec.score,
+            accuracy: rec.accuracy,
+            full_combo: rec.full_combo,
+            chart_version: self.chart_version(),
+            chart_hash: self.chart_hash.clone(),
+            max_combo: rec.max_combo,
+            mods: self.mods,
+            counts: rec.counts,
+            speed: rec.speed,
+            random_seed: rec.random_seed,
+        };
+        if let Err(err) = entry.append() {
+            warn!("failed to save play history: {err:?}");
         }
-        let Some(id) = self.info.id else { return };
-        self.ldb = None;
-        let std = self.ldb_std;
-        self.ldb_task = Some(Task::new(async move {
-            Ok(recv_raw(Client::get(format!("/record/list15/{id}")).query(&[("std", std)]))
-                .await?
-                .json()
-                .await?)
+        self.history.push(entry);
+    }
+
+    fn save_best_shot(&mut self, shot: Arc<RgbaImage>) {
+        let Some(path) = self.local_path.as_deref().and_then(best_shot_path) else {
+            return;
+        };
+        self.best_shot_task = Some(Task::new(async move {
+            shot.save_with_format(&path, ImageFormat::Png)?;
+            Ok(path)
         }));
     }
 
+    fn save_best_deltas(&self, deltas: &[f32]) {
+        let Some(path) = self.local_path.as_deref().and_then(best_deltas_path) else {
+            return;
+        };
+        let res: Result<()> = (|| Ok(std::fs::write(path, serde_json::to_vec(deltas)?)?))();
+        if let Err(err) = res {
+            warn!("failed to save hit deltas: {err:?}");
+        }
+    }
+
     fn update_record(&mut self, new_rec: SimpleRecord) -> Result<()> {
         let chart = get_data_mut()
             .charts
@@ -574,15 +769,25 @@ impl SongScene {
             }
             return Ok(());
         };
-        if let Some(rec) = &mut chart.record {
-            if rec.update(&new_rec) {
+        // a record set on another version of the chart doesn't compete with this one
+        let outdated = is_previous_version(&chart.record_chart_hash, &self.chart_hash);
+        match &mut chart.record {
+            Some(rec) if !outdated => {
+                if rec.update(&new_rec) {
+                    save_data()?;
+                }
+            }
+            _ => {
+                chart.record = Some(new_rec);
                 save_data()?;
             }
-        } else {
-            chart.record = Some(new_rec);
+        }
+        if chart.record_chart_hash.is_none() || outdated {
+            chart.record_chart_hash = self.chart_hash.clone();
             save_data()?;
         }
         self.record = chart.record.clone();
+        self.record_chart_hash = chart.record_chart_hash.clone();
         Ok(())
     }
 
@@ -593,10 +798,19 @@ impl SongScene {
         }
         if self.info.id.is_some() {
             self.menu_options.push("rate");
+            if !get_data().config.offline_mode {
+                self.menu_options.push("comments");
+            }
+        }
+        if self.local_path.is_none() && self.entity.is_some() && !get_data().config.offline_mode {
+            self.menu_options.push("play-online");
         }
         if self.local_path.is_some() {
             self.menu_options.push("exercise");
             self.menu_options.push("offset");
+            self.menu_options.push("chart-editor");
+            self.menu_options.push("convert-rpe");
+            self.menu_options.push("convert-pec");
         }
         let perms = get_data().me.as_ref().map(|it| it.perms()).unwrap_or_default();
         let is_uploader = get_data()
@@ -633,11 +847,107 @@ impl SongScene {
         self.menu.set_options(self.menu_options.iter().map(|it| tl!(it).into_owned()).collect());
     }
 
+    fn start_chart_preview(&mut self) -> Result<()> {
+        let local_path = self.local_path.clone().unwrap();
+        let mut fs = fs_from_path(&local_path)?;
+        let mut config = get_data().config.clone();
+        config.mods = self.mods;
+        config.chart_offset = get_data().chart_offsets.get(&local_path).copied().unwrap_or_default();
+        get_data().apply_respacks(&mut config)?;
+        let background = self.illu.texture.1.clone();
+        self.chart_preview_task = Some(Box::pin(async move {
+            let info = fs::load_info(fs.as_mut()).await?;
+            ChartPreview::new(info, config, fs, background.clone(), background).await
+        }));
+        if let Some(music) = &mut self.preview {
+            music.pause()?;
+        }
+        Ok(())
+    }
+
+    fn stop_chart_preview(&mut self) -> Result<()> {
+        self.chart_preview = None;
+        self.chart_preview_task = None;
+        if let Some(music) = &mut self.preview {
+            music.play()?;
+        }
+        Ok(())
+    }
+
     fn launch(&mut self, mode: GameMode) -> Result<()> {
-        self.scene_task = Self::global_launch(self.info.id, self.local_path.as_ref().unwrap(), self.mods, mode, None)?;
+        self.scene_task = Self::global_launch(self.info.id, self.local_path.as_ref().unwrap(), self.mods, mode, None, false)?;
         Ok(())
     }
 
+    // the estimate needs the whole chart parsed, so it waits until the info panel is opened
+    fn load_skills(&mut self) -> Result<()> {
+        if self.skills.is_some() || self.skills_task.is_some() {
+            return Ok(());
+        }
+        let Some(local_path) = &self.local_path else {
+            return Ok(());
+        };
+        let mut fs = fs_from_path(local_path)?;
+        self.skills_task = Some(Box::pin(async move {
+            let info = fs::load_info(fs.as_mut()).await?;
+            let mut config = get_data().config.clone();
+            config.render_extra = false;
+            config.mods = Mods::empty();
+            let (chart, _) = GameScene::load_chart(fs.as_mut(), &info, &config).await?;
+            Ok(estimate_skills(&chart))
+        }));
+        Ok(())
+    }
+
+    // writes the chart next to the original in the other format, info.yml is left untouched
+    fn convert(&mut self, rpe: bool) -> Result<()> {
+        let local_path = self.local_path.clone().unwrap();
+        let dir = format!("{}/{local_path}", dir::charts()?);
+        if !Path::new(&dir).is_dir() {
+            show_message(tl!("convert-not-unpacked")).error();
+            return Ok(());
+        }
+        let mut fs = fs_from_path(&local_path)?;
+        self.convert_task = Some(Box::pin(async move {
+            let info = fs::load_info(fs.as_mut()).await?;
+            // the chart as written, without the mods picked for playing
+            let mut config = get_data().config.clone();
+            config.render_extra = false;
+            config.mods = Mods::empty();
+            let (chart, _) = GameScene::load_chart(fs.as_mut(), &info, &config).await?;
+            let (name, content) = if rpe {
+                ("converted.json", export::to_rpe(&chart, &info)?)
+            } else {
+                ("converted.pec", export::to_pec(&chart)?)
+            };
+            std::fs::write(format!("{dir}/{name}"), content)?;
+            Ok(name.to_owned())
+        }));
+        Ok(())
+    }
+
+    // streams the chart archive instead of installing it; it stays in the http cache so that keeping it later is instant
+    fn play_online(&mut self) {
+        let Some(entity) = &self.entity else { return };
+        let url = entity.file.url.clone();
+        self.online_task = Some(Task::new(async move {
+            let key = prefetch_key(&url);
+            if let Ok(bytes) = cacache::read(&*CACHE_DIR, &key).await {
+                return Ok(bytes);
+            }
+            let bytes = file_request(&url)?
+                .send()
+                .await
+                .with_context(|| tl!("request-failed"))?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec();
+            cacache::write(&*CACHE_DIR, &key, &bytes).await?;
+            Ok(bytes)
+        }));
+    }
+
     #[must_use]
     pub fn global_launch(
         id: Option<i32>,
@@ -645,19 +955,44 @@ impl SongScene {
         mods: Mods,
         mode: GameMode,
         client: Option<Arc<phira_mp_client::Client>>,
+        spectate: bool,
+    ) -> Result<LocalSceneTask> {
+        Self::launch_fs(id, fs_from_path(local_path)?, local_path, mods, mode, client, spectate)
+    }
+
+    // `offset_key` is where the chart offset gets persisted, normally the local path
+    fn launch_fs(
+        id: Option<i32>,
+        mut fs: Box<dyn FileSystem + Send + Sync>,
+        offset_key: &str,
+        mods: Mods,
+        mode: GameMode,
+        client: Option<Arc<phira_mp_client::Client>>,
+        spectate: bool,
     ) -> Result<LocalSceneTask> {
-        let mut fs = fs_from_path(local_path)?;
         #[cfg(feature = "closed")]
         let rated = {
             let config = &get_data().config;
             !config.offline_mode && id.is_some() && !mods.contains(Mods::AUTOPLAY) && config.speed >= 1.0 - 1e-3
+                && config.standard_judge()
+                && !mods.intersects(Mods::FLIP_Y | Mods::RANDOM | Mods::NOTE_SWAP)
         };
         #[cfg(not(feature = "closed"))]
         let rated = false;
         if !rated && id.is_some() && mode == GameMode::Normal {
             show_message(tl!("warn-unrated")).warn();
         }
+        let mut render_fn: Option<RenderFn> = None;
         let update_fn = client.and_then(|mut client| {
+            if spectate {
+                let board = Rc::new(RefCell::new(SpectateBoard::new(&client)));
+                render_fn = Some(Box::new({
+                    let board = Rc::clone(&board);
+                    move |ui, alpha| board.borrow().render(ui, alpha)
+                }));
+                let update_fn: UpdateFn = Box::new(move |_, _, judge| board.borrow_mut().update(&client, judge));
+                return Some(update_fn);
+            }
             let live = client.blocking_state().unwrap().live;
             let token = get_data().tokens.as_ref().map(|it| it.0.clone()).unwrap();
             let addr = get_data().config.mp_address.clone();
@@ -755,25 +1090,60 @@ impl SongScene {
             };
             update_fn
         });
+        // persist the offset and blur once they're adjusted from the pause menu
+        let chart_offset = get_data().chart_offsets.get(offset_key).copied().unwrap_or_default();
+        let chart_blur = get_data().chart_blurs.get(offset_key).copied();
+        let update_fn: Option<UpdateFn> = Some(Box::new({
+            let local_path = offset_key.to_owned();
+            let mut saved = chart_offset;
+            // whatever the chart started with, only changes from there are saved
+            let mut saved_blur = None;
+            let mut inner = update_fn;
+            move |t, res, judge| {
+                if let Some(inner) = &mut inner {
+                    inner(t, res, judge);
+                }
+                let mut changed = false;
+                if res.config.chart_offset != saved {
+                    saved = res.config.chart_offset;
+                    get_data_mut().chart_offsets.insert(local_path.clone(), saved);
+                    changed = true;
+                }
+                let blur = *saved_blur.get_or_insert(res.info.background_blur);
+                if res.info.background_blur != blur {
+                    saved_blur = Some(res.info.background_blur);
+                    if let Some(blur) = res.info.background_blur {
+                        get_data_mut().chart_blurs.insert(local_path.clone(), blur);
+                    }
+                    changed = true;
+                }
+                if changed {
+                    if let Err(err) = save_data() {
+                        warn!("failed to save chart offset: {err:?}");
+                    }
+                }
+            }
+        }));
         Ok(Some(Box::pin(async move {
             let mut info = fs::load_info(fs.as_mut()).await?;
             info.id = id;
+            if chart_blur.is_some() {
+                info.background_blur = chart_blur;
+            }
             let mut config = get_data().config.clone();
             config.player_name = get_data()
                 .me
                 .as_ref()
                 .map(|it| it.name.clone())
                 .unwrap_or_else(|| tl!("guest").to_string());
-            config.res_pack_path = {
-                let id = get_data().respack_id;
-                if id == 0 {
-                    None
-                } else {
-                    Some(format!("{}/{}", dir::respacks()?, get_data().respacks[id - 1]))
-                }
-            };
+            get_data().apply_respacks(&mut config)?;
+            let respacks_dir = dir::respacks()?;
+            config.res_pack_choices = get_data().respacks.iter().map(|it| format!("{respacks_dir}/{it}")).collect();
             let chart_updated = info.chart_updated;
+            let chart_hash = self.chart_hash.clone();
             config.mods = mods;
+            config.random_seed = random();
+            config.chart_offset = chart_offset;
             LoadingScene::new(
                 None,
                 mode,
@@ -785,45 +1155,16 @@ impl SongScene {
                     id: it.id,
                     rks: it.rks,
                 }),
-                Some(Arc::new(move |data| {
+                Some(Arc::new(move |data, record| {
+                    let upload = upload::submit(PendingUpload::new(data, RecordPayload::new(id.unwrap(), chart_updated, chart_hash.clone(), record)));
                     Task::new(async move {
-                        #[derive(Serialize)]
-                        #[serde(rename_all = "camelCase")]
-                        struct Req {
-                            chart: i32,
-                            token: String,
-                            chart_updated: Option<DateTime<Utc>>,
-                        }
-                        #[derive(Deserialize)]
-                        #[serde(rename_all = "camelCase")]
-                        struct Resp {
-                            id: i32,
-                            exp_delta: f64,
-                            new_best: bool,
-                            improvement: u32,
-                            new_rks: f32,
-                        }
-                        let resp: Resp = recv_raw(Client::post(
-                            "/play/upload",
-                            &Req {
-                                chart: id.unwrap(),
-                                token: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, data),
-                                chart_updated,
-                            },
-                        ))
-                        .await?
-                        .json()
-                        .await?;
+                        let resp = upload.await?;
                         RECORD_ID.store(resp.id, Ordering::Relaxed);
-                        Ok(RecordUpdateState {
-                            best: resp.new_best,
-                            improvement: resp.improvement,
-                            gain_exp: resp.exp_delta as f32,
-                            new_rks: resp.new_rks,
-                        })
+                        Ok(RecordUpdateState::from(&resp))
                     })
                 })),
                 update_fn,
+                render_fn,
             )
             .await
             .map(|it| NextScene::Overlay(Box::new(it)))
@@ -911,6 +1252,18 @@ impl SongScene {
             0.6,
             true,
         );
+        self.ldb_scope_btn.render_text(
+            ui,
+            Rect::new(width - 0.49, -0.01, 0.23, 0.09),
+            rt,
+            1.,
+            match self.ldb_scope {
+                LdbScope::Global => tl!("ldb-global"),
+                LdbScope::Friends => tl!("ldb-friends"),
+            },
+            0.6,
+            true,
+        );
         render_ldb(
             ui,
             &tl!("ldb"),
@@ -919,8 +1272,8 @@ impl SongScene {
             &mut self.ldb_scroll,
             &mut self.ldb_fader,
             &self.icons.user,
-            self.ldb.as_mut().map(|it| {
-                it.1.iter_mut().map(|it| LdbDisplayItem {
+            self.ldb.as_mut().filter(|it| it.loaded()).map(|it| {
+                it.items_mut().iter_mut().map(|it| LdbDisplayItem {
                     player_id: it.inner.player.id,
                     rank: it.rank,
                     score: if self.ldb_std {
@@ -972,18 +1325,35 @@ impl SongScene {
                 dy!(0.14);
             }
             let mw = width - pad * 3.;
-            let mut item = |title: Cow<'_, str>, content: Cow<'_, str>| {
-                dy!(ui.text(title).size(0.4).color(semi_white(0.7)).draw().h + 0.02);
-                dy!(ui.text(content).pos(pad, 0.).size(0.6).multiline().max_width(mw).draw().h + 0.03);
-            };
-            item(tl!("info-name"), self.info.name.as_str().into());
-            item(tl!("info-composer"), self.info.composer.as_str().into());
-            item(tl!("info-charter"), self.info.charter.as_str().into());
-            item(tl!("info-difficulty"), format!("{} ({:.1})", self.info.level, self.info.difficulty).into());
-            item(tl!("info-desc"), self.info.intro.as_str().into());
+            macro_rules! item {
+                ($title:expr, $content:expr) => {{
+                    let title: Cow<'_, str> = $title;
+                    let content: Cow<'_, str> = $content;
+                    dy!(ui.text(title).size(0.4).color(semi_white(0.7)).draw().h + 0.02);
+                    dy!(ui.text(content).pos(pad, 0.).size(0.6).multiline().max_width(mw).draw().h + 0.03);
+                }};
+            }
+            item!(tl!("info-name"), self.info.name.as_str().into());
+            item!(tl!("info-composer"), self.info.composer.as_str().into());
+            item!(tl!("info-charter"), self.info.charter.as_str().into());
+            item!(tl!("info-difficulty"), format!("{} ({:.1})", level_label(&self.info.level), self.info.difficulty).into());
+            if let Some(skills) = &self.skills {
+                dy!(ui.text(tl!("info-skills")).size(0.4).color(semi_white(0.7)).draw().h + 0.02);
+                let radius = 0.12;
+                render_skill_radar(ui, vec2(mw / 2. + pad, radius + 0.05), radius, skills);
+                dy!(radius * 2. + 0.13);
+            } else if self.skills_task.is_some() {
+                item!(tl!("info-skills"), tl!("info-skills-loading"));
+            }
+            dy!(ui.text(tl!("info-desc")).size(0.4).color(semi_white(0.7)).draw().h + 0.02);
+            let desc_h = ui.scope(|ui| {
+                ui.dx(pad);
+                self.desc.render(ui, mw, 0.6, WHITE)
+            });
+            dy!(desc_h + 0.03);
             if let Some(entity) = &self.entity {
-                item(tl!("info-rating"), entity.rating.map_or(Cow::Borrowed("NaN"), |r| format!("{:.2} / 5.00", r * 5.).into()));
-                item(
+                item!(tl!("info-rating"), entity.rating.map_or(Cow::Borrowed("NaN"), |r| format!("{:.2} / 5.00", r * 5.).into()));
+                item!(
                     tl!("info-type"),
                     format!(
                         "{}{}",
@@ -996,15 +1366,112 @@ impl SongScene {
                     )
                     .into(),
                 );
-                item(tl!("info-tags"), entity.tags.iter().map(|it| format!("#{it}")).join(" ").into());
+                item!(tl!("info-tags"), entity.tags.iter().map(|it| format!("#{}", tag_label(it))).join(" ").into());
             }
             if let Some(id) = self.info.id {
-                item("ID".into(), id.to_string().into());
+                item!("ID".into(), id.to_string().into());
             }
             (width, h)
         });
     }
 
+    fn side_history(&mut self, ui: &mut Ui, rt: f32) {
+        let pad = 0.03;
+        ui.dx(pad);
+        ui.dy(0.03);
+        let width = self.side_content.width() - pad;
+        if self.best_shot.is_some() {
+            self.best_shot_btn
+                .render_text(ui, Rect::new(width - pad - 0.24, -0.01, 0.23, 0.09), rt, 1., tl!("best-shot"), 0.6, true);
+        }
+        self.history_scroll.size((width - pad, ui.top * 2. - 0.06));
+        let history = &self.history;
+        let chart_hash = &self.chart_hash;
+        self.history_scroll.render(ui, |ui| {
+            let mut h = 0.;
+            macro_rules! dy {
+                ($e:expr) => {{
+                    let dy = $e;
+                    h += dy;
+                    ui.dy(dy);
+                }};
+            }
+            dy!(ui.text(tl!("history")).size(0.8).draw().h + 0.03);
+            if history.is_empty() {
+                ui.text(tl!("history-empty")).size(0.5).color(semi_white(0.6)).draw();
+                return (width, h + 0.1);
+            }
+            let r = Rect::new(0., 0., width - pad * 3., 0.4);
+            render_history_graph(ui, r, history);
+            dy!(r.h + 0.02);
+            let mut x = 0.;
+            for (i, (color, text)) in [
+                (WHITE, tl!("history-score")),
+                (HISTORY_ACC_COLOR, tl!("history-acc")),
+                (HISTORY_BEST_COLOR, tl!("history-best")),
+                (HISTORY_VERSION_COLOR, tl!("history-chart-updated")),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if i == 2 {
+                    x = 0.;
+                    dy!(0.05);
+                }
+                ui.fill_rect(Rect::new(x, 0.012, 0.03, 0.012), color);
+                x += ui.text(text).pos(x + 0.04, 0.).size(0.35).color(semi_white(0.8)).draw().w + 0.08;
+            }
+            dy!(0.06);
+            for entry in history.iter().rev() {
+                let r = ui
+                    .text(entry.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .size(0.4)
+                    .color(semi_white(0.7))
+                    .draw();
+                if is_previous_version(&entry.chart_hash, chart_hash) {
+                    ui.text(tl!("previous-version"))
+                        .pos(r.right() + 0.02, 0.)
+                        .size(0.4)
+                        .color(HISTORY_VERSION_COLOR)
+                        .draw();
+                }
+                let score = (entry.score as f64 / 1_000_000.0 * self.info.score_total as f64) as u32;
+                let text = format!("{score:07}  {:.2}%{}", entry.accuracy * 100., if entry.full_combo { "  FC" } else { "" });
+                ui.text(text).pos(width - pad * 3., 0.).anchor(1., 0.).size(0.4).draw();
+                if entry.counts.iter().any(|it| *it != 0) {
+                    dy!(0.045);
+                    let [perfect, good, bad, miss] = entry.counts;
+                    let mut text = tl!("history-detail", "combo" => entry.max_combo, "perfect" => perfect, "good" => good, "bad" => bad, "miss" => miss).into_owned();
+                    if !entry.mods.is_empty() {
+                        text += "  ";
+                        text += &tl!("history-mods");
+                    }
+                    if let Some(speed) = entry.speed {
+                        text += &format!("  {speed:.2}x");
+                    }
+                    ui.text(text)
+                        .pos(width - pad * 3., 0.)
+                        .anchor(1., 0.)
+                        .size(0.3)
+                        .color(semi_white(0.6))
+                        .draw();
+                }
+                dy!(0.06);
+            }
+            (width, h)
+        });
+    }
+
+    fn side_comments(&mut self, ui: &mut Ui, rt: f32) {
+        let pad = 0.03;
+        ui.dx(pad);
+        ui.dy(0.03);
+        let width = self.side_content.width() - pad;
+        if let Some(comments) = &mut self.comments {
+            comments.render(ui, width, rt);
+        }
+    }
+
     fn side_mods(&mut self, ui: &mut Ui, rt: f32) {
         let pad = 0.03;
         ui.dx(pad);
@@ -1084,6 +1551,11 @@ impl SongScene {
             item(tl!("mods-flip-x"), Some(tl!("mods-flip-x-sub")), Mods::FLIP_X);
             item(tl!("mods-fade-out"), Some(tl!("mods-fade-out-sub")), Mods::FADE_OUT);
             item(tl!("mods-full-screen-judge"), None, Mods::FULL_SCREEN_JUDGE);
+            item(tl!("mods-sudden-death"), Some(tl!("mods-sudden-death-sub")), Mods::SUDDEN_DEATH);
+            item(tl!("mods-no-fail"), Some(tl!("mods-no-fail-sub")), Mods::NO_FAIL);
+            item(tl!("mods-flip-y"), Some(tl!("mods-flip-y-sub")), Mods::FLIP_Y);
+            item(tl!("mods-random"), Some(tl!("mods-random-sub")), Mods::RANDOM);
+            item(tl!("mods-note-swap"), Some(tl!("mods-note-swap-sub")), Mods::NOTE_SWAP);
             (width, h)
         });
     }
@@ -1114,6 +1586,7 @@ impl SongScene {
             for (name, bytes) in patches.into_iter() {
                 dir.create(name)?.write_all(&bytes)?;
             }
+            chart_index::update(&path, &info);
             Ok((info, preview))
         }));
     }
@@ -1138,8 +1611,32 @@ impl Scene for SongScene {
                 if self.my_rate_score == Some(0) && rng().random_ratio(2, 5) {
                     self.rate_dialog.enter(tm.real_time() as _);
                 }
+                self.add_history(&rec);
+                achievement::on_play(Play {
+                    record: &rec,
+                    difficulty: self.info.difficulty,
+                })?;
+                let before = self.record.as_ref().map(|it| it.score);
+                let shot = rec.screenshot.clone();
+                let deltas = rec.deltas.clone();
                 self.update_record(*rec)?;
-                self.load_ldb();
+                if self.record.as_ref().map(|it| it.score) != before {
+                    if let Some(shot) = shot {
+                        self.save_best_shot(shot);
+                    }
+                    self.save_best_deltas(&deltas);
+                }
+                self.load_ldb(true);
+                if std::mem::take(&mut self.played_online) && self.local_path.is_none() {
+                    Dialog::simple(tl!("play-online-keep"))
+                        .buttons(vec![tl!("play-online-discard").to_string(), tl!("play-online-save").to_string()])
+                        .listener(|pos| {
+                            if pos == 1 {
+                                KEEP_ONLINE.store(true, Ordering::SeqCst);
+                            }
+                        })
+                        .show();
+                }
                 return Ok(());
             }
         };
@@ -1157,6 +1654,7 @@ impl Scene for SongScene {
                     let mut info: ChartInfo = serde_yaml::from_reader(&dir.open("info.yml")?)?;
                     info.offset = offset;
                     dir.create("info.yml")?.write_all(serde_yaml::to_string(&info)?.as_bytes())?;
+                    chart_index::update(self.local_path.as_ref().unwrap(), &info);
                     let path = thumbnail_path(self.local_path.as_ref().unwrap())?;
                     if path.exists() {
                         std::fs::remove_file(path)?;
@@ -1174,6 +1672,9 @@ impl Scene for SongScene {
         if let Some(preview) = &mut self.preview {
             preview.pause()?;
         }
+        if let Some(preview) = &mut self.chart_preview {
+            preview.pause()?;
+        }
         Ok(())
     }
 
@@ -1188,7 +1689,7 @@ impl Scene for SongScene {
         if self.first_in {
             self.first_in = false;
             tm.seek_to(-FADE_IN_TIME as _);
-            self.load_ldb();
+            self.load_ldb(false);
         }
         if let Some(music) = &mut self.preview {
             music.seek_to(0.)?;
@@ -1201,6 +1702,8 @@ impl Scene for SongScene {
     fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
         let t = tm.now() as f32;
         if self.scene_task.is_some()
+            || self.online_task.is_some()
+            || self.convert_task.is_some()
             || self.save_task.is_some()
             || self.upload_task.is_some()
             || self.review_task.is_some()
@@ -1225,6 +1728,12 @@ impl Scene for SongScene {
         if self.rate_dialog.touch(touch, rt) {
             return Ok(true);
         }
+        if self.best_shot_view.is_some() {
+            if touch.phase == TouchPhase::Started {
+                self.best_shot_view = None;
+            }
+            return Ok(true);
+        }
         if self.menu.showing() {
             self.menu.touch(touch, t);
             return Ok(true);
@@ -1252,14 +1761,23 @@ impl Scene for SongScene {
                         if self.ldb_type_btn.touch(touch, t) {
                             self.ldb_std ^= true;
                             self.ldb_scroll.y_scroller.offset = 0.;
-                            self.load_ldb();
+                            self.load_ldb(false);
+                            return Ok(true);
+                        }
+                        if self.ldb_scope_btn.touch(touch, t) {
+                            self.ldb_scope = match self.ldb_scope {
+                                LdbScope::Global => LdbScope::Friends,
+                                LdbScope::Friends => LdbScope::Global,
+                            };
+                            self.ldb_scroll.y_scroller.offset = 0.;
+                            self.load_ldb(false);
                             return Ok(true);
                         }
                         if self.ldb_scroll.touch(touch, t) {
                             return Ok(true);
                         }
-                        if let Some((_, ldb)) = &mut self.ldb {
-                            for item in ldb {
+                        if let Some(ldb) = &mut self.ldb {
+                            for item in ldb.items_mut() {
                                 if item.btn.touch(touch) {
                                     button_hit();
                                     self.sf
@@ -1273,6 +1791,9 @@ impl Scene for SongScene {
                         if self.info_scroll.touch(touch, t) {
                             return Ok(true);
                         }
+                        if self.desc.touch(touch) {
+                            return Ok(true);
+                        }
                         if self.uploader_btn.touch(touch) {
                             button_hit();
                             self.sf.goto(
@@ -1282,6 +1803,27 @@ impl Scene for SongScene {
                             return Ok(true);
                         }
                     }
+                    SideContent::History => {
+                        if let Some(path) = &self.best_shot {
+                            if self.best_shot_btn.touch(touch, t) {
+                                match load_best_shot(path) {
+                                    Ok(tex) => self.best_shot_view = Some(tex),
+                                    Err(err) => show_error(err.context(tl!("best-shot-load-failed"))),
+                                }
+                                return Ok(true);
+                            }
+                        }
+                        if self.history_scroll.touch(touch, t) {
+                            return Ok(true);
+                        }
+                    }
+                    SideContent::Comments => {
+                        if let Some(comments) = &mut self.comments {
+                            if comments.touch(touch, t) {
+                                return Ok(true);
+                            }
+                        }
+                    }
                     SideContent::Mods => {
                         if self.mod_scroll.touch(touch, t) {
                             return Ok(true);
@@ -1303,6 +1845,14 @@ impl Scene for SongScene {
             self.next_scene = Some(NextScene::PopWithResult(Box::new(false)));
             return Ok(true);
         }
+        if self.local_path.is_some() && self.chart_preview_btn.touch(touch, t) {
+            if self.chart_preview.is_some() || self.chart_preview_task.is_some() {
+                self.stop_chart_preview()?;
+            } else {
+                self.start_chart_preview()?;
+            }
+            return Ok(true);
+        }
         if self.play_btn.touch(touch, t) {
             if self.local_path.is_some() {
                 self.launch(GameMode::Normal)?;
@@ -1338,6 +1888,13 @@ impl Scene for SongScene {
             self.side_content = SideContent::Leaderboard;
             self.side_enter_time = tm.real_time() as _;
         }
+        if self.local_path.is_some() && self.record_btn.touch(touch) {
+            button_hit();
+            self.history_scroll.y_scroller.offset = 0.;
+            self.side_content = SideContent::History;
+            self.side_enter_time = tm.real_time() as _;
+            return Ok(true);
+        }
         if self.info_btn.touch(touch) {
             button_hit();
             if let Some(uploader) = &self.info.uploader {
@@ -1345,6 +1902,7 @@ impl Scene for SongScene {
             }
             self.side_content = SideContent::Info;
             self.side_enter_time = tm.real_time() as _;
+            self.load_skills()?;
             return Ok(true);
         }
 
@@ -1382,14 +1940,16 @@ impl Scene for SongScene {
             if let Some(id) = self.info.id {
                 let score = self.rate_dialog.rate.score;
                 self.rate_task = Some(Task::new(async move {
-                    recv_raw(Client::post(
-                        format!("/chart/{id}/rate"),
-                        &json!({
-                            "score": score,
-                        }),
-                    ))
-                    .await?;
-                    Ok(())
+                    Client::rate(id, score).await?;
+                    Ok(score)
+                }));
+            }
+        }
+        if std::mem::take(&mut self.rate_dialog.removed) {
+            if let Some(id) = self.info.id {
+                self.rate_task = Some(Task::new(async move {
+                    Client::unrate(id).await?;
+                    Ok(0)
                 }));
             }
         }
@@ -1405,6 +1965,10 @@ impl Scene for SongScene {
                     Ok(chart) => {
                         if let Some(chart) = chart {
                             self.entity = Some(chart.as_ref().clone());
+                            let outdated = self.info.chart_updated.map_or(false, |it| it != chart.chart_updated);
+                            if self.local_path.is_none() || outdated {
+                                self.prefetch = Some(Prefetch::new(chart.file.url.clone()));
+                            }
                             if self
                                 .info
                                 .updated
@@ -1450,6 +2014,7 @@ impl Scene for SongScene {
                         show_error(err.context(tl!("load-preview-failed")));
                     }
                     Ok(clip) => {
+                        self._preview_memory = Some(MemoryGuard::audio(&clip));
                         self.preview = Some(create_music(clip)?);
                     }
                 }
@@ -1457,11 +2022,20 @@ impl Scene for SongScene {
             }
         }
         if let Some(dl) = &mut self.downloading {
-            if dl.check()?.is_some() {
-                self.local_path = dl.local_path.take();
+            if let Some(done) = dl.check()? {
+                if done {
+                    self.local_path = dl.local_path();
+                }
                 self.update_menu();
                 self.downloading = None;
             }
+        } else if let (None, Some(id)) = (&self.local_path, self.info.id) {
+            // the download may have been sent to the background earlier
+            let path = with_manager(|it| it.get(id).filter(|it| it.state == DownloadState::Done).and_then(|it| it.local_path.clone()));
+            if path.is_some() {
+                self.local_path = path;
+                self.update_menu();
+            }
         }
         if let Some(task) = &mut self.scene_task {
             if let Some(res) = poll_future(task.as_mut()) {
@@ -1484,6 +2058,70 @@ impl Scene for SongScene {
                 self.scene_task = None;
             }
         }
+        if let Some(task) = &mut self.chart_preview_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.chart_preview_task = None;
+                match res {
+                    Err(err) => {
+                        show_error(err.context(tl!("chart-preview-failed")));
+                        self.stop_chart_preview()?;
+                    }
+                    Ok(preview) => self.chart_preview = Some(preview),
+                }
+            }
+        }
+        if let Some(preview) = &mut self.chart_preview {
+            if !preview.update()? {
+                self.stop_chart_preview()?;
+            }
+        }
+        if let Some(task) = &mut self.convert_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                match res {
+                    Err(err) => show_error(err.context(tl!("convert-failed"))),
+                    Ok(name) => {
+                        show_message(tl!("convert-done", "file" => name)).ok();
+                    }
+                }
+                self.convert_task = None;
+            }
+        }
+        if let Some(task) = &mut self.best_shot_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => warn!("failed to save best screenshot: {err:?}"),
+                    Ok(path) => self.best_shot = Some(path),
+                }
+                self.best_shot_task = None;
+            }
+        }
+        if let Some(task) = &mut self.skills_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                match res {
+                    Err(err) => warn!("failed to estimate chart skills: {err:?}"),
+                    Ok(skills) => self.skills = Some(skills),
+                }
+                self.skills_task = None;
+            }
+        }
+        if let Some(task) = &mut self.chart_hash_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => warn!("failed to hash chart: {err:?}"),
+                    Ok(hash) => self.chart_hash = Some(hash),
+                }
+                self.chart_hash_task = None;
+            }
+        }
+        if let Some(task) = &mut self.local_version_task {
+            if let Some(res) = task.take() {
+                match res {
+                    Err(err) => warn!("failed to get chart version: {err:?}"),
+                    Ok(version) => self.local_version = version,
+                }
+                self.local_version_task = None;
+            }
+        }
         if let Some(task) = &mut self.fetch_best_task {
             if let Some(res) = task.take() {
                 match res {
@@ -1506,12 +2144,33 @@ impl Scene for SongScene {
                 "rate" => {
                     self.rate_dialog.enter(tm.real_time() as _);
                 }
+                "comments" => {
+                    let id = self.info.id.unwrap();
+                    let icons = &self.icons;
+                    self.comments.get_or_insert_with(|| Comments::new(id, icons.user.clone(), icons.star.clone()));
+                    self.side_content = SideContent::Comments;
+                    self.side_enter_time = tm.real_time() as _;
+                }
+                "play-online" => {
+                    self.play_online();
+                }
                 "exercise" => {
                     self.launch(GameMode::Exercise)?;
                 }
                 "offset" => {
                     self.launch(GameMode::TweakOffset)?;
                 }
+                "chart-editor" => {
+                    let local_path = self.local_path.clone().unwrap();
+                    if Path::new(&format!("{}/{local_path}", dir::charts()?)).is_dir() {
+                        self.next_scene = Some(NextScene::Overlay(Box::new(EditorScene::new(local_path, self.icons.clone()))));
+                    } else {
+                        show_message(tl!("chart-editor-not-unpacked")).error();
+                    }
+                }
+                "convert-rpe" | "convert-pec" => {
+                    self.convert(option == "convert-rpe")?;
+                }
                 "review-approve" => {
                     let id = self.info.id.unwrap();
                     self.review_task = Some(Task::new(async move {
@@ -1605,6 +2264,7 @@ impl Scene for SongScene {
                         if let Some(preview) = &mut self.preview {
                             preview.pause()?;
                         }
+                        self._preview_memory = Some(MemoryGuard::audio(&preview));
                         self.preview = Some(create_music(preview)?);
                         self.info = info.into();
                         self.update_chart_info()?;
@@ -1637,9 +2297,15 @@ impl Scene for SongScene {
             SideContent::Leaderboard => {
                 if self.ldb_scroll.y_scroller.pulled {
                     self.ldb_scroll.y_scroller.offset = 0.;
-                    self.load_ldb();
+                    self.load_ldb(true);
                 }
                 self.ldb_scroll.update(t);
+                // the next page is fetched a few entries before the end comes into view
+                if let Some(ldb) = &mut self.ldb {
+                    if ldb.loaded() && self.ldb_scroll.y_scroller.remaining() < LDB_PRELOAD {
+                        ldb.load_more();
+                    }
+                }
             }
             SideContent::Info => {
                 self.info_scroll.update(t);
@@ -1647,6 +2313,34 @@ impl Scene for SongScene {
             SideContent::Mods => {
                 self.mod_scroll.update(t);
             }
+            SideContent::History => {
+                self.history_scroll.update(t);
+            }
+            SideContent::Comments => {
+                if let Some(comments) = &mut self.comments {
+                    comments.update(t);
+                }
+            }
+        }
+        if self.desc.original() != self.info.intro {
+            self.desc = TranslatedText::new(self.info.intro.clone());
+        }
+        self.desc.update();
+        if let Some(task) = &mut self.online_task {
+            if let Some(res) = task.take() {
+                self.online_task = None;
+                match res.and_then(ZipFileSystem::new) {
+                    Err(err) => show_error(err.context(tl!("play-online-failed"))),
+                    Ok(fs) => {
+                        let offset_key = format!("download/{}", self.info.id.unwrap());
+                        self.scene_task = Self::launch_fs(self.info.id, Box::new(fs), &offset_key, self.mods, GameMode::Normal, None, false)?;
+                        self.played_online = true;
+                    }
+                }
+            }
+        }
+        if KEEP_ONLINE.fetch_and(false, Ordering::Relaxed) && self.local_path.is_none() {
+            self.start_download()?;
         }
         if CONFIRM_UPLOAD.fetch_and(false, Ordering::Relaxed) {
             let path = self.local_path.clone().unwrap();
@@ -1726,25 +2420,20 @@ impl Scene for SongScene {
                 }
             }));
         }
-        if let Some(task) = &mut self.ldb_task {
-            if let Some(res) = task.take() {
+        if let Some(ldb) = &mut self.ldb {
+            let first = !ldb.loaded();
+            if let Some(res) = ldb.poll() {
                 match res {
                     Err(err) => {
                         show_error(err.context(tl!("ldb-load-failed")));
                     }
-                    Ok(items) => {
-                        let rank = get_data()
-                            .me
-                            .as_ref()
-                            .and_then(|me| items.iter().find(|it| it.inner.player.id == me.id).map(|it| it.rank));
-                        for item in &items {
-                            UserManager::request(item.inner.player.id);
+                    Ok(new) => {
+                        UserManager::request_many(new.iter().map(|it| it.inner.player.id));
+                        if first {
+                            self.ldb_fader.sub(tm.real_time() as _);
                         }
-                        self.ldb = Some((rank, items));
-                        self.ldb_fader.sub(tm.real_time() as _);
                     }
                 }
-                self.ldb_task = None;
             }
         }
         if let Some((id, text)) = take_input() {
@@ -1797,6 +2486,11 @@ impl Scene for SongScene {
                         .into())
                     }));
                 }
+                "comment-post" | "comment-edit" | "comment-report" => {
+                    if let Some(comments) = &mut self.comments {
+                        comments.on_input(&id, text);
+                    }
+                }
                 _ => return_input(id, text),
             }
         }
@@ -1845,8 +2539,10 @@ impl Scene for SongScene {
                     Err(err) => {
                         show_error(err.context(tl!("rate-failed")));
                     }
-                    Ok(_) => {
-                        show_message(tl!("rate-done")).ok();
+                    Ok(score) => {
+                        show_message(if score == 0 { tl!("rate-removed") } else { tl!("rate-done") }).ok();
+                        self.my_rate_score = Some(score);
+                        self.rate_dialog.rated = score != 0;
                     }
                 }
                 self.rate_dialog.dismiss(rt);
@@ -1864,6 +2560,7 @@ impl Scene for SongScene {
                     }
                     Ok(score) => {
                         self.rate_dialog.rate.score = score;
+                        self.rate_dialog.rated = score != 0;
                         self.my_rate_score = Some(score);
                     }
                 }
@@ -1912,6 +2609,7 @@ impl Scene for SongScene {
         let score = self.record.as_ref().map(|it| it.score).unwrap_or_default();
         let score = (score as f64 / 1_000_000.0 * self.info.score_total as f64) as u32;
         let accuracy = self.record.as_ref().map(|it| it.accuracy).unwrap_or_default();
+        let icon_rect = r;
         let r = ui
             .text(format!("{score:07}"))
             .pos(r.right() + 0.01, r.center().y)
@@ -1919,19 +2617,30 @@ impl Scene for SongScene {
             .size(1.2)
             .color(c)
             .draw();
-        ui.text(format!("{:.2}%", accuracy * 100.))
+        let acc = ui
+            .text(format!("{:.2}%", accuracy * 100.))
             .pos(r.x, r.bottom() + 0.01)
             .anchor(0., 0.)
             .size(0.7)
             .color(semi_white(0.7 * c.a))
             .draw();
+        if self.record.is_some() && is_previous_version(&self.record_chart_hash, &self.chart_hash) {
+            ui.text(tl!("previous-version"))
+                .pos(acc.right() + 0.02, acc.center().y)
+                .anchor(0., 0.5)
+                .no_baseline()
+                .size(0.5)
+                .color(Color { a: c.a, ..HISTORY_VERSION_COLOR })
+                .draw();
+        }
+        self.record_btn.set(ui, Rect::new(icon_rect.x, r.y, r.right() - icon_rect.x, icon_rect.bottom() - r.y));
 
         if self.info.id.is_some() {
             let h = 0.09;
             let mut r = Rect::new(r.x, r.y - h, h, h);
             ui.fill_rect(r, (*self.icons.ldb, r, ScaleType::Fit, c));
-            if let Some((rank, _)) = &self.ldb {
-                ui.text(if let Some(rank) = rank {
+            if let Some(ldb) = self.ldb.as_ref().filter(|it| it.loaded()) {
+                ui.text(if let Some(rank) = ldb.my_rank() {
                     format!("#{rank}")
                 } else {
                     tl!("ldb-no-rank").into_owned()
@@ -1979,6 +2688,27 @@ impl Scene for SongScene {
             ),
         );
 
+        if self.local_path.is_some() {
+            let play_rect = r;
+            let w = 0.22;
+            let r = Rect::new(1. - pad - 0.26 - 0.03 - w, ui.top - pad - 0.09, w, 0.09);
+            let active = self.chart_preview.is_some() || self.chart_preview_task.is_some();
+            self.chart_preview_btn
+                .render_text(ui, r, t, c.a, if active { tl!("chart-preview-stop") } else { tl!("chart-preview") }, 0.5, active);
+            if active {
+                let w = 0.64;
+                let r = Rect::new(1. - pad - w, play_rect.y - 0.1 - w * 9. / 16., w, w * 9. / 16.);
+                ui.fill_path(&r.feather(0.006).rounded(0.01), semi_black(0.6 * c.a));
+                if let Some(preview) = &mut self.chart_preview {
+                    preview.render(ui, r, c.a)?;
+                    ui.fill_rect(Rect::new(r.x, r.bottom() + 0.006, r.w * preview.progress(), 0.006), semi_white(0.8 * c.a));
+                } else {
+                    let ct = r.center();
+                    ui.loading(ct.x, ct.y, t, c, ());
+                }
+            }
+        }
+
         ui.scope(|ui| {
             ui.dx(1. - 0.03);
             ui.dy(-ui.top + 0.03);
@@ -2037,6 +2767,14 @@ impl Scene for SongScene {
                         self.side_mods(ui, rt);
                         Ok(())
                     }
+                    SideContent::History => {
+                        self.side_history(ui, rt);
+                        Ok(())
+                    }
+                    SideContent::Comments => {
+                        self.side_comments(ui, rt);
+                        Ok(())
+                    }
                 }
             })?;
         }
@@ -2052,12 +2790,24 @@ impl Scene for SongScene {
         if self.review_task.is_some() {
             ui.full_loading(tl!("review-doing"), t);
         }
+        if self.convert_task.is_some() {
+            ui.full_loading(tl!("converting"), t);
+        }
+        if self.online_task.is_some() {
+            ui.full_loading(tl!("play-online-loading"), t);
+        }
         if self.edit_tags_task.is_some() || self.rate_task.is_some() {
             ui.full_loading("", t);
         }
         let rt = tm.real_time() as f32;
         self.tags.render(ui, rt);
         self.rate_dialog.render(ui, rt);
+        if let Some(tex) = &self.best_shot_view {
+            let r = ui.screen_rect();
+            ui.fill_rect(r, semi_black(0.8));
+            let r = r.feather(-0.05);
+            ui.fill_rect(r, (**tex, r, ScaleType::Fit));
+        }
 
         self.sf.render(ui, t);
 
@@ -2069,6 +2819,8 @@ impl Scene for SongScene {
             if let Some(music) = &mut self.preview {
                 let _ = music.pause();
             }
+            self.chart_preview = None;
+            self.chart_preview_task = None;
             scene
         } else {
             NextScene::default()
@@ -8,9 +8,50 @@ use phire::{
     ui::{DRectButton, Scroll, Ui},
 };
 use smallvec::{smallvec, SmallVec};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
 
 const DIVISION_TAGS: &[&str] = &["regular", "troll", "plain", "visual"];
 
+// `10-15`, or a single `12` for both ends; empty for no limit
+fn parse_range<T: FromStr + PartialOrd + Copy>(s: &str) -> Option<Option<(T, T)>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(None);
+    }
+    let (low, high) = s.split_once('-').unwrap_or((s, s));
+    let (low, high) = (low.trim().parse().ok()?, high.trim().parse().ok()?);
+    (low <= high).then_some(Some((low, high)))
+}
+
+fn range_text<T: Display + PartialEq>(range: &Option<(T, T)>) -> String {
+    match range {
+        Some((low, high)) if low == high => low.to_string(),
+        Some((low, high)) => format!("{low}-{high}"),
+        None => String::new(),
+    }
+}
+
+fn lookup(key: &str) -> Option<String> {
+    L10N_LOCAL.with(|it| it.borrow().try_format(key))
+}
+
+// server tags are shown in the player's language, with the tag itself kept alongside
+pub fn tag_label(tag: &str) -> Cow<'_, str> {
+    match lookup(&format!("tag-{}", tag.to_lowercase())) {
+        Some(label) if !label.eq_ignore_ascii_case(tag) => format!("{label} ({tag})").into(),
+        _ => tag.into(),
+    }
+}
+
+// the same for the level type in front of a level, like the "IN" of "IN Lv.15"
+pub fn level_label(level: &str) -> Cow<'_, str> {
+    let (kind, rest) = level.split_once(' ').unwrap_or((level, ""));
+    match lookup(&format!("level-{}", kind.to_lowercase())) {
+        Some(label) if !label.eq_ignore_ascii_case(kind) => format!("{label} ({kind}) {rest}").trim_end().to_owned().into(),
+        _ => level.into(),
+    }
+}
+
 pub struct Tags {
     input_id: &'static str,
     tags: Vec<String>,
@@ -94,7 +135,7 @@ impl Tags {
             x += w + (margin + pad) * 2.;
         };
         for (tag, btn) in self.tags.iter().zip(self.btns.iter_mut()) {
-            draw(btn, tag);
+            draw(btn, &tag_label(tag));
         }
         draw(&mut self.add, "+");
         h + row_height
@@ -122,6 +163,11 @@ pub struct TagsDialog {
     pub division: &'static str,
     div_btns: Vec<DRectButton>,
 
+    pub level: Option<(f32, f32)>,
+    btn_level: DRectButton,
+    pub notes: Option<(u32, u32)>,
+    btn_notes: DRectButton,
+
     pub btn_me: DRectButton,
     pub show_me: bool,
     pub btn_unreviewed: DRectButton,
@@ -150,6 +196,11 @@ impl TagsDialog {
             division: DIVISION_TAGS[0],
             div_btns: DIVISION_TAGS.iter().map(|_| DRectButton::new()).collect(),
 
+            level: None,
+            btn_level: DRectButton::new(),
+            notes: None,
+            btn_notes: DRectButton::new(),
+
             btn_me: DRectButton::new(),
             show_me: false,
             btn_unreviewed: DRectButton::new(),
@@ -170,6 +221,10 @@ impl TagsDialog {
         self.division = self.tags.set(tags);
     }
 
+    pub fn set_division(&mut self, division: &str) {
+        self.division = DIVISION_TAGS.iter().find(|it| **it == division).unwrap_or(&DIVISION_TAGS[0]);
+    }
+
     pub fn showing(&self) -> bool {
         self.show
     }
@@ -231,6 +286,14 @@ impl TagsDialog {
                 self.show_stabilize ^= true;
                 return true;
             }
+            if self.btn_level.touch(touch, t) {
+                request_input("filter_level", &range_text(&self.level), tl!("filter-level"));
+                return true;
+            }
+            if self.btn_notes.touch(touch, t) {
+                request_input("filter_notes", &range_text(&self.notes), tl!("filter-notes"));
+                return true;
+            }
             if self.btn_cancel.touch(touch, t) {
                 self.confirmed = Some(false);
                 self.dismiss(t);
@@ -264,6 +327,14 @@ impl TagsDialog {
                 "add_tag_unwanted" => {
                     self.unwanted.as_mut().unwrap().try_add(text.trim());
                 }
+                "filter_level" => match parse_range(&text) {
+                    Some(range) => self.level = range,
+                    None => show_message(tl!("invalid-range")).error(),
+                },
+                "filter_notes" => match parse_range(&text) {
+                    Some(range) => self.notes = range,
+                    None => show_message(tl!("invalid-range")).error(),
+                },
                 _ => {
                     return_input(id, text);
                 }
@@ -319,6 +390,21 @@ impl TagsDialog {
                                 let dh = bh + 0.01;
                                 h += dh;
                                 ui.dy(dh);
+
+                                let bw = mw / 2.;
+                                let mut r = Rect::new(pad / 2., 0., bw, bh).nonuniform_feather(-0.01, -0.004);
+                                let any = tl!("filter-any");
+                                for (btn, text, range) in [
+                                    (&mut self.btn_level, "filter-level", range_text(&self.level)),
+                                    (&mut self.btn_notes, "filter-notes", range_text(&self.notes)),
+                                ] {
+                                    let on = !range.is_empty();
+                                    let value = if on { range } else { any.to_string() };
+                                    btn.render_text(ui, r, t, c.a, format!("{}: {value}", tl!(text)), 0.5, on);
+                                    r.x += bw;
+                                }
+                                h += dh;
+                                ui.dy(dh);
                             }
                             if self.unwanted.is_some() {
                                 let th = ui.text(tl!("wanted")).size(0.5).color(c).draw().h + 0.01;
@@ -0,0 +1,40 @@
+// the launcher theme lives in data/theme: colors in theme.yml, plus an optional font.ttf and background.png
+
+use crate::dir;
+use anyhow::Result;
+use phire::ui::{set_theme, Theme, ThemeInfo};
+use std::{fs::File, path::Path};
+use tracing::{info, warn};
+
+fn theme_file(name: &str) -> Option<Vec<u8>> {
+    let path = Path::new(&dir::theme().ok()?).join(name);
+    if !path.exists() {
+        return None;
+    }
+    match std::fs::read(&path) {
+        Ok(bytes) => Some(bytes),
+        Err(err) => {
+            warn!("failed to read theme file {}: {err:?}", path.display());
+            None
+        }
+    }
+}
+
+pub fn load_theme() -> Result<()> {
+    let path = Path::new(&dir::theme()?).join("theme.yml");
+    if !path.exists() {
+        return Ok(());
+    }
+    let info: ThemeInfo = serde_yaml::from_reader(File::open(path)?)?;
+    info!("loaded theme {} by {}", info.name, info.author);
+    set_theme(Theme::from(&info));
+    Ok(())
+}
+
+pub fn theme_font() -> Option<Vec<u8>> {
+    theme_file("font.ttf")
+}
+
+pub fn theme_background() -> Option<Vec<u8>> {
+    theme_file("background.png")
+}
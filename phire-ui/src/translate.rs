@@ -0,0 +1,173 @@
+phire::tl_file!("translate");
+
+use crate::{client::basic_client_builder, get_data};
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use macroquad::prelude::*;
+use once_cell::sync::Lazy;
+use phire::{
+    ext::semi_white,
+    l10n::{locale_order, LANGS},
+    task::Task,
+    ui::{RectButton, Ui},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+pub trait TranslationProvider: Send + Sync {
+    // `target` is a language tag like `en-US` or `zh-CN`
+    fn translate(&self, text: String, target: String) -> BoxFuture<'static, Result<String>>;
+}
+
+// any server speaking the LibreTranslate API
+pub struct LibreTranslate {
+    url: String,
+}
+
+impl LibreTranslate {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl TranslationProvider for LibreTranslate {
+    fn translate(&self, text: String, target: String) -> BoxFuture<'static, Result<String>> {
+        let url = format!("{}/translate", self.url.trim_end_matches('/'));
+        Box::pin(async move {
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Resp {
+                translated_text: String,
+            }
+            // LibreTranslate only knows about traditional chinese as a region variant
+            let target = match target.as_str() {
+                "zh-TW" => "zt".to_owned(),
+                _ => target.split('-').next().unwrap_or_default().to_owned(),
+            };
+            let resp: Resp = basic_client_builder()
+                .build()?
+                .post(url)
+                .json(&json!({
+                    "q": text,
+                    "source": "auto",
+                    "target": target,
+                    "format": "text",
+                }))
+                .send()
+                .await
+                .context("failed to reach translation service")?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(resp.translated_text)
+        })
+    }
+}
+
+static CACHE: Lazy<Mutex<HashMap<(String, String), String>>> = Lazy::new(Mutex::default);
+
+fn provider() -> Option<Arc<dyn TranslationProvider>> {
+    let config = &get_data().config;
+    if !config.translate {
+        return None;
+    }
+    if config.translate_api.is_empty() {
+        return None;
+    }
+    Some(Arc::new(LibreTranslate::new(config.translate_api.clone())))
+}
+
+fn target_language() -> String {
+    LANGS[locale_order()[0]].to_owned()
+}
+
+// None if translation is turned off
+pub fn translate(text: String) -> Option<Task<Result<String>>> {
+    let provider = provider()?;
+    let target = target_language();
+    let key = (target.clone(), text.clone());
+    if let Some(cached) = CACHE.lock().unwrap().get(&key).cloned() {
+        return Some(Task::new(async move { Ok(cached) }));
+    }
+    Some(Task::new(async move {
+        let translated = provider.translate(text, target).await?;
+        CACHE.lock().unwrap().insert(key, translated.clone());
+        Ok(translated)
+    }))
+}
+
+// user written text shown in the UI language when possible; tapping it switches back to the original
+pub struct TranslatedText {
+    original: String,
+    translated: Option<String>,
+    task: Option<Task<Result<String>>>,
+    show_original: bool,
+    btn: RectButton,
+}
+
+impl TranslatedText {
+    pub fn new(original: String) -> Self {
+        let task = if original.trim().is_empty() { None } else { translate(original.clone()) };
+        Self {
+            original,
+            translated: None,
+            task,
+            show_original: false,
+            btn: RectButton::new(),
+        }
+    }
+
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    pub fn touch(&mut self, touch: &Touch) -> bool {
+        if self.translated.is_some() && self.btn.touch(touch) {
+            self.show_original ^= true;
+            return true;
+        }
+        false
+    }
+
+    pub fn update(&mut self) {
+        if let Some(task) = &mut self.task {
+            if let Some(res) = task.take() {
+                match res {
+                    // nothing to show if the text was already in the right language
+                    Ok(text) if text.trim() != self.original.trim() => self.translated = Some(text),
+                    Ok(_) => {}
+                    Err(err) => warn!("failed to translate: {err:?}"),
+                }
+                self.task = None;
+            }
+        }
+    }
+
+    // returns the height used
+    pub fn render(&mut self, ui: &mut Ui, max_width: f32, size: f32, c: Color) -> f32 {
+        let text = match (&self.translated, self.show_original) {
+            (Some(translated), false) => translated.as_str(),
+            _ => self.original.as_str(),
+        };
+        let r = ui.text(text).size(size).multiline().max_width(max_width).color(c).draw();
+        let mut h = r.h;
+        let hint = if self.task.is_some() {
+            Some(tl!("translating"))
+        } else if self.translated.is_some() {
+            Some(if self.show_original { tl!("show-translated") } else { tl!("show-original") })
+        } else {
+            None
+        };
+        if let Some(hint) = hint {
+            h += 0.01;
+            h += ui.text(hint).pos(0., h).size(size * 0.6).color(semi_white(c.a * 0.5)).draw().h;
+        }
+        self.btn.set(ui, Rect::new(0., 0., max_width, h));
+        h
+    }
+}
@@ -0,0 +1,227 @@
+phire::tl_file!("song");
+
+use crate::{
+    client::{schedule, Client, Priority},
+    dir, get_data,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use macroquad::time::get_time;
+use phire::{
+    config::Mods,
+    scene::{show_message, RecordUpdateState, SimpleRecord},
+    task::Task,
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashSet, future::Future, sync::Mutex};
+use tracing::warn;
+
+// seconds between attempts at what's left in the queue
+const RETRY_INTERVAL: f64 = 60.;
+
+// held while the queue file is read and written back
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+// tokens of the plays being sent right now, so that a retry doesn't send one twice
+static IN_FLIGHT: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+thread_local! {
+    static RETRY: RefCell<(Option<Task<Result<usize>>>, f64)> = RefCell::new((None, f64::NEG_INFINITY));
+}
+
+// everything the server is told about the play besides the record data itself
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordPayload {
+    chart: i32,
+    chart_updated: Option<DateTime<Utc>>,
+    // md5 of the chart file, when it's done hashing by the time the play ends
+    chart_hash: Option<String>,
+    score: u32,
+    accuracy: f32,
+    max_combo: u32,
+    full_combo: bool,
+    counts: [u32; 4],
+    mods: Mods,
+    speed: Option<f32>,
+    played: DateTime<Utc>,
+}
+
+impl RecordPayload {
+    pub fn new(chart: i32, chart_updated: Option<DateTime<Utc>>, chart_hash: Option<String>, record: &SimpleRecord) -> Self {
+        Self {
+            chart,
+            chart_updated,
+            chart_hash,
+            score: record.score,
+            accuracy: record.accuracy,
+            max_combo: record.max_combo,
+            full_combo: record.full_combo,
+            counts: record.counts,
+            mods: record.chart_mods,
+            speed: record.speed,
+            played: Utc::now(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingUpload {
+    // the encoded record data, which also tells two uploads of the same play apart
+    token: String,
+    record: RecordPayload,
+}
+
+impl PendingUpload {
+    pub fn new(data: Vec<u8>, record: RecordPayload) -> Self {
+        Self {
+            token: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, data),
+            record,
+        }
+    }
+}
+
+// what `/play/upload` takes
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadBody<'a> {
+    chart: i32,
+    token: &'a str,
+    chart_updated: Option<DateTime<Utc>>,
+}
+
+impl<'a> UploadBody<'a> {
+    fn new(pending: &'a PendingUpload) -> UploadBody<'a> {
+        Self {
+            chart: pending.record.chart,
+            token: &pending.token,
+            chart_updated: pending.record.chart_updated,
+        }
+    }
+}
+
+fn queue_path() -> Result<String> {
+    Ok(format!("{}/upload_queue.json", dir::root()?))
+}
+
+fn load_queue() -> Result<Vec<PendingUpload>> {
+    match std::fs::read_to_string(queue_path()?) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn edit_queue(f: impl FnOnce(&mut Vec<PendingUpload>)) -> Result<()> {
+    let _guard = QUEUE_LOCK.lock().unwrap();
+    let mut queue = load_queue()?;
+    f(&mut queue);
+    std::fs::write(queue_path()?, serde_json::to_string(&queue)?)?;
+    Ok(())
+}
+
+fn claim(token: &str) -> bool {
+    IN_FLIGHT.lock().unwrap().get_or_insert_with(HashSet::new).insert(token.to_owned())
+}
+
+fn release(token: &str) {
+    if let Some(set) = IN_FLIGHT.lock().unwrap().as_mut() {
+        set.remove(token);
+    }
+}
+
+// the outer error means the server couldn't be reached and the play stays queued, the inner one that it was turned down
+async fn send(pending: &PendingUpload, priority: Priority) -> Result<Result<UploadResponse>> {
+    let body = UploadBody::new(pending);
+    let response = schedule(Client::post("/play/upload", &body), priority).await?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        let err = anyhow!("request failed ({status}): {text}");
+        return if status.is_client_error() { Ok(Err(err)) } else { Err(err) };
+    }
+    Ok(Ok(response.json().await?))
+}
+
+// sends the play now, leaving it in the queue for later if the server is out of reach
+pub fn submit(pending: PendingUpload) -> impl Future<Output = Result<UploadResponse>> {
+    let queued = tl!("upload-queued").into_owned();
+    async move {
+        edit_queue(|queue| {
+            if !queue.iter().any(|it| it.token == pending.token) {
+                queue.push(pending.clone());
+            }
+        })?;
+        if !claim(&pending.token) {
+            return Err(anyhow!(queued));
+        }
+        let res = send(&pending, Priority::Interactive).await;
+        release(&pending.token);
+        match res {
+            Ok(res) => {
+                edit_queue(|queue| queue.retain(|it| it.token != pending.token))?;
+                res
+            }
+            Err(err) => Err(err.context(queued)),
+        }
+    }
+}
+
+// tries everything still queued, returns how many went through
+async fn retry() -> Result<usize> {
+    let queue = {
+        let _guard = QUEUE_LOCK.lock().unwrap();
+        load_queue()?
+    };
+    let mut done = 0;
+    for pending in queue {
+        if !claim(&pending.token) {
+            continue;
+        }
+        let res = send(&pending, Priority::Prefetch).await;
+        release(&pending.token);
+        match res {
+            Ok(res) => {
+                if let Err(err) = res {
+                    warn!("queued record rejected: {err:?}");
+                } else {
+                    done += 1;
+                }
+                edit_queue(|queue| queue.retain(|it| it.token != pending.token))?;
+            }
+            // still offline, the rest can wait for the next round
+            Err(err) => {
+                warn!("queued record upload failed: {err:?}");
+                break;
+            }
+        }
+    }
+    Ok(done)
+}
+
+pub fn update() -> Result<()> {
+    RETRY.with(|it| {
+        let mut it = it.borrow_mut();
+        if let Some(task) = &mut it.0 {
+            if let Some(res) = task.take() {
+                it.0 = None;
+                match res {
+                    Ok(0) => {}
+                    Ok(count) => {
+                        show_message(tl!("upload-queued-done", "count" => count)).ok();
+                    }
+                    Err(err) => warn!("failed to retry queued records: {err:?}"),
+                }
+            }
+            return;
+        }
+        let data = get_data();
+        let now = get_time();
+        if now - it.1 < RETRY_INTERVAL || data.me.is_none() || data.config.offline_mode {
+            return;
+        }
+        it.1 = now;
+        if queue_path().map_or(false, |it| std::path::Path::new(&it).exists()) {
+            it.0 = Some(Task::new(retry()));
+        }
+    });
+    Ok(())
+}
@@ -19,10 +19,15 @@ aspect-hint = Aspect ratio can be either real number or texts like "w:h"
 score-total = Total score
 hold-partial-cover = Hold tail cover
 note-uniform-scale = Note uniform scale
+custom-blur = Custom background blur
 dim = Background dim
+blur = Background blur
+letterbox-blur = Blurred letterbox
+letterbox-blur-radius = Letterbox blur
 chart-file = Beatmap
 music-file = Music
 illu-file = Illustration
+letterbox-file = Letterbox art
 tip = Tip
 intro = Introduction
 tags = Tags
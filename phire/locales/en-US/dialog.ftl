@@ -5,3 +5,4 @@ ok = OK
 error = Error
 error-copy = Copy error
 error-copied = Copied
+retry = Retry
@@ -5,6 +5,7 @@ offset-cancel = Cancel
 offset-reset = Reset
 offset-save = Save
 speed = Speed
+chart-offset = Chart offset: { $offset }ms
 
 ex-time-out-of-range = Time is out of range
 ex-invalid-format = Invalid format
@@ -13,3 +14,15 @@ ex-time-start = Time started
 ex-time-end = Time stopped
 
 shake-to-resume = Shake to continue playing
+
+hot-reloaded = Chart reloaded
+hot-reload-failed = Failed to reload chart
+
+skip-intro = Skip intro
+
+respack = Resource pack: { $name }
+respack-loading = Loading resource pack…
+respack-load-failed = Failed to load resource pack
+bg-blur = Background blur: { $blur }
+perf-overlay-on = Performance overlay: On
+perf-overlay-off = Performance overlay: Off
@@ -42,4 +42,6 @@ line-location = On line #{ $lid }
 shader-load-failed = Cannot load shader from { $path }
 shader-not-found = Cannot find preset shader { $shader }
 effect-location = In effect #{ $id }
+extension-location = In extra section "{ $name }"
 video-load-failed = Failed to read video from { $path }
+image-load-failed = Failed to read image from { $path }
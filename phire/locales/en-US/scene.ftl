@@ -4,4 +4,10 @@ input-msg = Please input text
 input-hint = text
 
 read-file-failed = Failed to read file
+file-any = All files
+file-chart = Chart packages
+file-respack = Resource packs
+file-chart-file = Chart files
+file-image = Images
+file-audio = Audio
 pasted = Pasted from clipboard
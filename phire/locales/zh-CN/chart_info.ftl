@@ -19,10 +19,15 @@ aspect-hint = 宽高比可以直接填小数，也可以是 w:h 的形式（英
 score-total = 总分
 hold-partial-cover = Hold 尾部遮罩
 note-uniform-scale = 音符等比缩放
+custom-blur = 自定义背景模糊
 dim = 背景昏暗
+blur = 背景模糊
+letterbox-blur = 模糊边框
+letterbox-blur-radius = 边框模糊
 chart-file = 谱面文件
 music-file = 音乐文件
 illu-file = 插图文件
+letterbox-file = 边框图片
 tip = Tip
 intro = 简介
 tags = 标签
@@ -5,3 +5,4 @@ ok = 确定
 error = 错误
 error-copy = 复制错误详情
 error-copied = 复制成功
+retry = 重试
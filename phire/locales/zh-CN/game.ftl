@@ -5,6 +5,7 @@ offset-cancel = 取消
 offset-reset = 重置
 offset-save = 保存
 speed = 速度
+chart-offset = 谱面延迟：{ $offset }ms
 
 ex-time-out-of-range = 时间不在范围内
 ex-invalid-format = 格式有误
@@ -13,3 +14,15 @@ ex-time-start = 开始时间
 ex-time-end = 结束时间
 
 shake-to-resume = 摇一摇继续游玩
+
+hot-reloaded = 谱面已重新加载
+hot-reload-failed = 重新加载谱面失败
+
+skip-intro = 跳过前奏
+
+respack = 资源包：{ $name }
+respack-loading = 正在加载资源包…
+respack-load-failed = 加载资源包失败
+bg-blur = 背景模糊：{ $blur }
+perf-overlay-on = 性能监视：开
+perf-overlay-off = 性能监视：关
@@ -42,4 +42,6 @@ line-location = #{ $lid } 行中
 shader-load-failed = 无法从 { $path } 中加载 shader
 shader-not-found = 未找到预置 shader { $shader }
 effect-location = #{ $id } 号 effect 中
+extension-location = 扩展段 "{ $name }" 中
 video-load-failed = 从 { $path } 中加载视频失败
+image-load-failed = 从 { $path } 中加载图片失败
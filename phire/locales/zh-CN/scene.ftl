@@ -4,4 +4,10 @@ input-msg = 请输入文字
 input-hint = 文字
 
 read-file-failed = 读取文件失败
+file-any = 所有文件
+file-chart = 谱面包
+file-respack = 资源包
+file-chart-file = 谱面文件
+file-image = 图片
+file-audio = 音频
 pasted = 从剪贴板加载成功
@@ -3,7 +3,7 @@ use crate::{
         Anim, AnimVector, BezierTween, BpmList, Chart, ChartExtra, ChartSettings, ClampedTween, CtrlObject, JudgeLine, JudgeLineCache, JudgeLineKind,
         Keyframe, Note, NoteKind, Object, StaticTween, Tweenable, UIElement,
     },
-    judge::{HitSound, JudgeStatus},
+    judge::{HitSound, HitSoundTune, JudgeStatus},
     parse::process_lines,
 };
 use anyhow::{bail, Result};
@@ -372,9 +372,14 @@ impl BinaryData for CtrlObject {
     }
 }
 
+// set on the kind byte of notes whose hitsound volume or pitch is tuned, the two follow at the end of the note
+const NOTE_TUNED: u8 = 0x80;
+
 impl BinaryData for Note {
     fn read_binary<R: Read>(r: &mut BinaryReader<R>) -> Result<Self> {
-        let kind = match r.read::<u8>()? {
+        let kind = r.read::<u8>()?;
+        let tuned = kind & NOTE_TUNED != 0;
+        let kind = match kind & !NOTE_TUNED {
             0 => NoteKind::Click,
             1 => NoteKind::Hold {
                 end_time: r.read()?,
@@ -386,10 +391,11 @@ impl BinaryData for Note {
             _ => bail!("invalid note kind"),
         };
         let hitsound = HitSound::default_from_kind(&kind);
-        Ok(Self {
+        let mut note = Self {
             object: r.read()?,
             kind,
             hitsound,
+            hitsound_tune: HitSoundTune::default(),
             time: r.time()?,
             height: r.read()?,
             speed: if r.read()? { r.read::<f32>()? } else { 1. },
@@ -401,23 +407,32 @@ impl BinaryData for Note {
             color: r.read()?,
             hit_fx_color: r.read()?,
             protected: false,
-        })
+        };
+        if tuned {
+            note.hitsound_tune = HitSoundTune {
+                volume: r.read()?,
+                pitch: r.read()?,
+            };
+        }
+        Ok(note)
     }
 
     fn write_binary<W: Write>(&self, w: &mut BinaryWriter<W>) -> Result<()> {
         w.write(&self.object)?;
+        let tuned = self.hitsound_tune != HitSoundTune::default();
+        let flag = if tuned { NOTE_TUNED } else { 0 };
         match self.kind {
             NoteKind::Click => {
-                w.write_val(0_u8)?;
+                w.write_val(flag)?;
             }
             NoteKind::Hold { end_time, end_height, end_speed } => {
-                w.write_val(1_u8)?;
+                w.write_val(1_u8 | flag)?;
                 w.write_val(end_time)?;
                 w.write_val(end_height)?;
                 w.write_val(end_speed)?;
             }
-            NoteKind::Flick => w.write_val(2_u8)?,
-            NoteKind::Drag => w.write_val(3_u8)?,
+            NoteKind::Flick => w.write_val(2_u8 | flag)?,
+            NoteKind::Drag => w.write_val(3_u8 | flag)?,
         }
         w.time(self.time)?;
         w.write_val(self.height)?;
@@ -432,6 +447,10 @@ impl BinaryData for Note {
         w.write_val(self.judge_scale)?;
         w.write(&self.color)?;
         w.write(&self.hit_fx_color)?;
+        if tuned {
+            w.write_val(self.hitsound_tune.volume)?;
+            w.write_val(self.hitsound_tune.pitch)?;
+        }
         Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::core::UIElement;
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +12,24 @@ bitflags! {
         const FLIP_X = 2;
         const FADE_OUT = 4;
         const FULL_SCREEN_JUDGE = 8;
+        // only cancels SUDDEN_DEATH, a play without it never fails to begin with
+        const NO_FAIL = 16;
+        const SUDDEN_DEATH = 32;
+        const FLIP_Y = 64;
+        const RANDOM = 128;
+        const NOTE_SWAP = 256;
+    }
+}
+
+bitflags! {
+    // note types that can be hit anywhere along the line, like FULL_SCREEN_JUDGE does for every note
+    #[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Debug)]
+    #[serde(transparent)]
+    pub struct FullScreenNotes: i32 {
+        const TAP = 1;
+        const DRAG = 2;
+        const FLICK = 4;
+        const HOLD = 8;
     }
 }
 
@@ -40,6 +59,188 @@ impl fmt::Display for ChallengeModeColor {
     }
 }
 
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+pub struct JudgeWindows {
+    pub perfect: f32,
+    pub good: f32,
+    pub bad: f32,
+}
+
+impl JudgeWindows {
+    pub const STANDARD: Self = Self { perfect: 0.08, good: 0.18, bad: 0.22 };
+    pub const STRICT: Self = Self { perfect: 0.04, good: 0.075, bad: 0.14 };
+    pub const LENIENT: Self = Self { perfect: 0.1, good: 0.2, bad: 0.25 };
+}
+
+impl Default for JudgeWindows {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JudgePreset {
+    #[default]
+    Standard,
+    Strict,
+    Lenient,
+    Custom,
+}
+
+impl JudgePreset {
+    pub const ALL: [JudgePreset; 4] = [Self::Standard, Self::Strict, Self::Lenient, Self::Custom];
+}
+
+impl fmt::Display for JudgePreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            JudgePreset::Standard => "standard",
+            JudgePreset::Strict   => "strict",
+            JudgePreset::Lenient  => "lenient",
+            JudgePreset::Custom   => "custom",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HudPosition {
+    #[default]
+    Score,
+    TopLeft,
+    BottomLeft,
+    BottomRight,
+}
+
+impl HudPosition {
+    pub const ALL: [HudPosition; 4] = [Self::Score, Self::TopLeft, Self::BottomLeft, Self::BottomRight];
+}
+
+impl fmt::Display for HudPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            HudPosition::Score       => "score",
+            HudPosition::TopLeft     => "topLeft",
+            HudPosition::BottomLeft  => "bottomLeft",
+            HudPosition::BottomRight => "bottomRight",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// how the ends of plain judge lines are drawn
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+// recolors notes by kind whatever the resource pack looks like
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotePalette {
+    #[default]
+    Off,
+    HighContrast,
+    RedGreen,
+    BlueYellow,
+}
+
+impl NotePalette {
+    pub const ALL: [NotePalette; 4] = [Self::Off, Self::HighContrast, Self::RedGreen, Self::BlueYellow];
+}
+
+impl fmt::Display for NotePalette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            NotePalette::Off          => "off",
+            NotePalette::HighContrast => "highContrast",
+            NotePalette::RedGreen     => "redGreen",
+            NotePalette::BlueYellow   => "blueYellow",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgressBarStyle {
+    #[default]
+    Top,
+    Circle,
+    Sections,
+}
+
+impl ProgressBarStyle {
+    pub const ALL: [ProgressBarStyle; 3] = [Self::Top, Self::Circle, Self::Sections];
+}
+
+impl fmt::Display for ProgressBarStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ProgressBarStyle::Top      => "top",
+            ProgressBarStyle::Circle   => "circle",
+            ProgressBarStyle::Sections => "sections",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct HudTransform {
+    pub dx: f32,
+    pub dy: f32,
+    pub scale: f32,
+}
+
+impl HudTransform {
+    pub const IDENTITY: Self = Self { dx: 0., dy: 0., scale: 1. };
+}
+
+impl Default for HudTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// user adjustments applied on top of the default HUD placement, offsets are in game units
+#[derive(Clone, Copy, Deserialize, Serialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct HudLayout {
+    pub pause: HudTransform,
+    pub score: HudTransform,
+    pub combo: HudTransform,
+    pub bar: HudTransform,
+}
+
+impl HudLayout {
+    pub fn get(&self, element: UIElement) -> HudTransform {
+        match element {
+            UIElement::Pause => self.pause,
+            UIElement::Score => self.score,
+            UIElement::ComboNumber | UIElement::Combo => self.combo,
+            UIElement::Bar => self.bar,
+            _ => HudTransform::IDENTITY,
+        }
+    }
+
+    pub fn get_mut(&mut self, element: UIElement) -> Option<&mut HudTransform> {
+        match element {
+            UIElement::Pause => Some(&mut self.pause),
+            UIElement::Score => Some(&mut self.score),
+            UIElement::ComboNumber | UIElement::Combo => Some(&mut self.combo),
+            UIElement::Bar => Some(&mut self.bar),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
@@ -59,25 +260,72 @@ pub struct Config {
     pub all_good: bool,
     pub all_bad: bool,
     pub double_click_to_pause: bool,
+    pub full_screen_notes: FullScreenNotes,
     pub fxaa: bool,
+    pub hit_area_scale: f32,
+    pub hot_reload: bool,
     pub interactive: bool,
     pub note_scale: f32,
+    pub note_palette: NotePalette,
+    // marks drags and flicks with a shape so they don't rely on color alone
+    pub note_shape_markers: bool,
     pub mods: Mods,
     pub mp_enabled: bool,
     pub mp_address: String,
     pub offline_mode: bool,
     pub offset: f32,
+    // per-chart offset on top of `offset`, filled in by the launcher for each play
+    #[serde(skip)]
+    pub chart_offset: f32,
     pub particle: bool,
     pub player_name: String,
     pub player_rks: f32,
+    // shuffle seed for the RANDOM mod, also filled in by the launcher
+    #[serde(skip)]
+    pub random_seed: u64,
     pub res_pack_path: Option<String>,
+    // packs under `res_pack_path` that fill in what it lacks, from the top down
+    pub res_pack_fallbacks: Vec<String>,
+    // installed packs the pause menu can switch between, the built-in one aside
+    #[serde(skip)]
+    pub res_pack_choices: Vec<String>,
+    // set by the launcher for the session after a crash, leaves out the resource pack, chart shaders and videos
+    #[serde(skip)]
+    pub safe_mode: bool,
     pub sample_count: u32,
+    // blends in linear space through an sRGB chart target, falls back to the usual drawing without GL 3
+    pub linear_color: bool,
+    // frames per second the main loop is held to, 0 for no limit
+    pub max_fps: u32,
+    // applied when the window is created, so it takes a restart
+    pub vsync: bool,
+    pub perf_overlay: bool,
+    // texture, audio and chart memory, and what closed scenes left behind
+    pub memory_overlay: bool,
+    // falls back to plain note meshes when the device can't instance
+    pub instanced_notes: bool,
+    // serves live stats on localhost for stream overlays, desktop only
+    pub live_stats: bool,
+    pub live_stats_port: u16,
     pub show_acc: bool,
+    pub acc_hud_counts: bool,
+    pub acc_hud_position: HudPosition,
+    pub hud_layout: HudLayout,
+    pub progress_bar_style: ProgressBarStyle,
+    pub duck_on_miss: bool,
     pub speed: f32,
     pub touch_debug: bool,
+    pub keyboard_lanes: bool,
+    pub key_lanes: String,
     pub volume_music: f32,
     pub volume_sfx: f32,
     pub volume_bgm: f32,
+    pub list_preview: bool,
+    pub translate: bool,
+    pub translate_api: String,
+    pub sfx_limiter: bool,
+    pub max_sfx_voices: u32,
+    pub premix_hitsounds: bool,
     pub watermark: String,
     pub roman: bool,
     pub chinese: bool,
@@ -89,11 +337,23 @@ pub struct Config {
     pub autoplay: Option<bool>,
 
     pub judge_offset: f32,
+    // how late the player's taps land, measured by the offset wizard and taken off touch times when judging
+    pub touch_offset: f32,
+    pub judge_preset: JudgePreset,
+    pub custom_judge_windows: JudgeWindows,
+    // seconds before a hold's end from which letting go no longer misses it
+    pub hold_release_leniency: f32,
+    pub hold_no_release_penalty: bool,
 
     pub render_line: bool,
     pub render_line_extra: bool,
+    // override what the resource pack sets, which in turn overrides the usual thin line with flat ends
+    pub line_width: Option<f32>,
+    pub line_cap: Option<LineCap>,
     pub render_note: bool,
     pub render_double_hint: bool,
+    // holds bend along with their line's rotation over the time they take to reach it
+    pub curved_holds: bool,
     pub render_ui_pause: bool,
     pub render_ui_name: bool,
     pub render_ui_level: bool,
@@ -142,25 +402,56 @@ impl Default for Config {
             all_good: false,
             all_bad: false,
             double_click_to_pause: true,
+            full_screen_notes: FullScreenNotes::empty(),
             fxaa: false,
+            hit_area_scale: 1.0,
+            hot_reload: false,
             interactive: true,
             mods: Mods::default(),
             mp_address: "mp2.phira.cn:12345".to_owned(),
             mp_enabled: false,
             note_scale: 1.0,
+            note_palette: NotePalette::Off,
+            note_shape_markers: false,
             offline_mode: false,
             offset: 0.0,
+            chart_offset: 0.0,
             particle: true,
             player_name: "Guest".to_string(),
             player_rks: 15.,
+            random_seed: 0,
             res_pack_path: None,
+            res_pack_fallbacks: Vec::new(),
+            res_pack_choices: Vec::new(),
+            safe_mode: false,
             sample_count: 1,
+            linear_color: false,
+            max_fps: 0,
+            vsync: true,
+            perf_overlay: false,
+            memory_overlay: false,
+            instanced_notes: true,
+            live_stats: false,
+            live_stats_port: 7680,
             show_acc: false,
+            acc_hud_counts: false,
+            acc_hud_position: HudPosition::Score,
+            hud_layout: HudLayout::default(),
+            progress_bar_style: ProgressBarStyle::Top,
+            duck_on_miss: false,
             speed: 1.0,
             touch_debug: false,
+            keyboard_lanes: false,
+            key_lanes: "SDFJKL".to_owned(),
             volume_music: 1.0,
             volume_sfx: 0.0,
             volume_bgm: 1.0,
+            list_preview: true,
+            translate: false,
+            translate_api: String::new(),
+            sfx_limiter: false,
+            max_sfx_voices: 32,
+            premix_hitsounds: false,
             watermark: "".to_string(),
             roman: false,
             chinese: false,
@@ -171,11 +462,19 @@ impl Default for Config {
             autoplay: None,
 
             judge_offset: 0.,
+            touch_offset: 0.,
+            judge_preset: JudgePreset::Standard,
+            custom_judge_windows: JudgeWindows::STANDARD,
+            hold_release_leniency: 0.,
+            hold_no_release_penalty: false,
 
             render_line: true,
             render_line_extra: true,
+            line_width: None,
+            line_cap: None,
             render_note: true,
             render_double_hint: true,
+            curved_holds: false,
             render_ui_pause: true,
             render_ui_name: true,
             render_ui_level: true,
@@ -229,4 +528,32 @@ impl Config {
     pub fn full_scrrn_judge(&self) -> bool {
         self.has_mod(Mods::FULL_SCREEN_JUDGE)
     }
+
+    // NO_FAIL wins over SUDDEN_DEATH, and autoplay can't fail anyway
+    pub fn sudden_death(&self) -> bool {
+        self.has_mod(Mods::SUDDEN_DEATH) && !self.has_mod(Mods::NO_FAIL) && !self.autoplay()
+    }
+
+    // mods applied to the chart when it is loaded, plays with any of them don't count online
+    pub fn chart_mods(&self) -> Mods {
+        self.mods & (Mods::FLIP_Y | Mods::RANDOM | Mods::NOTE_SWAP)
+    }
+
+    pub fn judge_windows(&self) -> JudgeWindows {
+        match self.judge_preset {
+            JudgePreset::Standard => JudgeWindows::STANDARD,
+            JudgePreset::Strict => JudgeWindows::STRICT,
+            JudgePreset::Lenient => JudgeWindows::LENIENT,
+            JudgePreset::Custom => self.custom_judge_windows,
+        }
+    }
+
+    // records are only kept when nothing makes the judge more lenient than usual
+    pub fn standard_judge(&self) -> bool {
+        self.judge_preset == JudgePreset::Standard
+            && self.hold_release_leniency <= 0.
+            && !self.hold_no_release_penalty
+            && (self.hit_area_scale - 1.).abs() < 1e-3
+            && self.full_screen_notes.is_empty()
+    }
 }
@@ -12,31 +12,66 @@ pub type Matrix = nalgebra::Matrix3<f32>;
 mod anim;
 pub use anim::{Anim, AnimFloat, AnimVector, Keyframe};
 
+mod atlas;
+pub use atlas::{NoteAtlas, NoteSwatches};
+
+mod attachment;
+pub use attachment::{Attachment, AttachmentKind};
+
+mod blur;
+pub use blur::BackgroundBlur;
+
 mod chart;
 pub use chart::{Chart, ChartExtra, ChartSettings, HitSoundMap};
 
 mod effect;
 pub use effect::{Effect, Uniform};
 
+mod extension;
+pub use extension::{extra_handler, register_extra_handler, ExtraHandler, ExtraSection};
+
+mod instanced;
+pub use instanced::{InstancedNotes, NoteInstance};
+
+mod intro;
+pub use intro::{first_note_lead_in, quiet_intro};
+
 mod line;
 pub use line::{GifFrames, JudgeLine, JudgeLineCache, JudgeLineKind, UIElement};
 
 mod note;
 use macroquad::prelude::set_pc_assets_folder;
-pub use note::{BadNote, Note, NoteKind, HitSound, RenderConfig};
+pub use note::{BadNote, HoldFx, Note, NoteKind, HitSound, RenderConfig, UpdateContext};
 
 mod object;
 pub use object::{CtrlObject, Object};
 
+mod progress;
+pub use progress::{LoadProgress, LoadStage};
+
 mod render;
-pub use render::{copy_fbo, internal_id, MSRenderTarget};
+pub use render::{copy_fbo, internal_id, srgb_supported, use_base_material, LinearColor, MSRenderTarget};
 
 mod resource;
-pub use resource::{NoteStyle, ParticleEmitter, ResPackInfo, Resource, ResourcePack, SfxMap, BUFFER_SIZE, DPI_VALUE};
+pub use resource::{NoteStyle, ParticleEmitter, ResPackInfo, Resource, ResourcePack, HitsoundBus, PitchedSfxMap, SfxMap, BUFFER_SIZE, DPI_VALUE};
+
+#[cfg(feature = "script")]
+mod script;
+#[cfg(feature = "script")]
+pub use script::ChartScript;
+
+mod skill;
+pub use skill::{estimate_skills, SkillProfile};
 
 mod smooth;
 pub use smooth::Smooth;
 
+mod stream;
+pub use stream::{MusicStream, MusicTrack, StreamedMusic};
+
+mod stretch;
+pub use stretch::{time_stretch, STRETCH_RANGE};
+
 mod tween;
 pub use tween::{easing_from, BezierTween, ClampedTween, StaticTween, TweenFunction, TweenId, TweenMajor, TweenMinor, Tweenable, TWEEN_FUNCTIONS};
 
@@ -119,6 +154,22 @@ impl BpmList {
         }
     }
 
+    // (beat, bpm) pairs this list was built from, None for per-line pgr timing
+    pub fn ranges(&self) -> Option<Vec<(f32, f32)>> {
+        if self.per_line_bpm_storage {
+            return None;
+        }
+        Some(self.elements.iter().map(|(beats, _, bpm)| (*beats, *bpm)).collect())
+    }
+
+    // times where the bpm changes, used to split the chart into sections
+    pub fn change_times(&self) -> Vec<f32> {
+        if self.per_line_bpm_storage {
+            return Vec::new();
+        }
+        self.elements.iter().skip(1).map(|(_, time, _)| *time).collect()
+    }
+
     pub fn time_beats(&mut self, beats: f32) -> f32 {
         while let Some(kf) = self.elements.get(self.cursor + 1) {
             if kf.0 > beats {
@@ -151,6 +202,12 @@ impl BpmList {
         beats + (time - start_time) / (60. / bpm)
     }
 
+    // same as `now_bpm` but leaves the cursor alone, so that lines updating in parallel can share the list
+    pub fn bpm_at(&self, time: f32) -> f32 {
+        let index = self.elements.partition_point(|it| it.1 <= time);
+        self.elements[index.saturating_sub(1)].2
+    }
+
     pub fn now_bpm(&mut self, time: f32) -> f32 {
         while let Some(kf) = self.elements.get(self.cursor + 1) {
             if kf.1 > time {
@@ -63,6 +63,11 @@ impl<T: Tweenable> Anim<T> {
         self.keyframes.is_empty() && self.next.is_none()
     }
 
+    // keyframes of this animation and the ones chained to it
+    pub fn heap_bytes(&self) -> usize {
+        self.keyframes.len() * std::mem::size_of::<Keyframe<T>>() + self.next.as_ref().map_or(0, |it| std::mem::size_of::<Self>() + it.heap_bytes())
+    }
+
     pub fn chain(elements: Vec<Anim<T>>) -> Self {
         if elements.is_empty() {
             return Self::default();
@@ -125,6 +130,27 @@ impl<T: Tweenable> Anim<T> {
         })
     }
 
+    // the value at `time` without moving the cursor, for looking ahead of the current time
+    pub fn at(&self, time: f32) -> Option<T> {
+        if self.keyframes.is_empty() {
+            return None;
+        }
+        let i = self.keyframes.partition_point(|it| it.time <= time).saturating_sub(1);
+        let now = if i == self.keyframes.len() - 1 {
+            self.keyframes[i].value.clone()
+        } else {
+            let kf1 = &self.keyframes[i];
+            let kf2 = &self.keyframes[i + 1];
+            let t = (time - kf1.time) / (kf2.time - kf1.time);
+            T::tween(&kf1.value, &kf2.value, kf1.tween.y(t))
+        };
+        Some(if let Some(next) = &self.next {
+            T::add(&now, &next.at(time).unwrap())
+        } else {
+            now
+        })
+    }
+
     pub fn map_value(&mut self, mut f: impl FnMut(T) -> T) {
         self.keyframes.iter_mut().for_each(|it| it.value = f(it.value.clone()));
         if let Some(next) = &mut self.next {
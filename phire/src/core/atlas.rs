@@ -0,0 +1,168 @@
+// every note texture of a resource pack packed into one, so that a layer of notes is drawn as a single batch
+// instead of switching textures between kinds, styles and animation frames
+
+use super::NoteStyle;
+use crate::ext::SafeTexture;
+use macroquad::prelude::*;
+use miniquad::gl::{GLuint, GL_LINEAR, GL_NEAREST};
+use std::collections::HashMap;
+use tracing::info;
+
+// edge pixels are repeated this far around every texture, so that filtering never picks up a neighbor
+const PADDING: u32 = 2;
+// the largest texture every GLES 2 device we care about can hold
+const MAX_SIZE: u32 = 4096;
+
+fn gl_id(texture: &Texture2D) -> GLuint {
+    texture.raw_miniquad_texture_handle().gl_internal_id()
+}
+
+pub struct NoteAtlas {
+    texture: SafeTexture,
+    // where each original texture ended up, in texture coordinates of the atlas
+    regions: HashMap<GLuint, Rect>,
+}
+
+impl NoteAtlas {
+    // None when the textures don't fit, notes are then drawn from their own textures as before
+    pub fn new(styles: &[&NoteStyle]) -> Option<Self> {
+        let mut images: Vec<(GLuint, Image)> = Vec::new();
+        for texture in styles.iter().flat_map(|it| it.atlas_textures()) {
+            let id = gl_id(texture);
+            if images.iter().all(|it| it.0 != id) {
+                images.push((id, texture.get_texture_data()));
+            }
+        }
+        let ((width, height), places) = pack(&images)?;
+        let mut atlas = Image::gen_image_color(width as u16, height as u16, Color::new(0., 0., 0., 0.));
+        let mut regions = HashMap::with_capacity(images.len());
+        for ((id, image), (x, y)) in images.iter().zip(places) {
+            blit_padded(&mut atlas, image, x, y);
+            regions.insert(
+                *id,
+                Rect::new(
+                    x as f32 / width as f32,
+                    y as f32 / height as f32,
+                    image.width as f32 / width as f32,
+                    image.height as f32 / height as f32,
+                ),
+            );
+        }
+        info!("packed {} note textures into a {width}x{height} atlas", images.len());
+        Some(Self {
+            texture: SafeTexture::from(Texture2D::from_image(&atlas)).with_filter(GL_LINEAR),
+            regions,
+        })
+    }
+
+    // moves the texture coordinates into the atlas and returns the texture to draw with instead
+    pub fn remap(&self, texture: GLuint, vertices: &mut [Vertex; 4]) -> GLuint {
+        let Some(r) = self.regions.get(&texture) else {
+            return texture;
+        };
+        for vertex in vertices {
+            vertex.uv = vec2(r.x + vertex.uv.x * r.w, r.y + vertex.uv.y * r.h);
+        }
+        gl_id(&self.texture)
+    }
+}
+
+// shelf packing, tallest first, on the narrowest power of two that keeps the atlas roughly square
+fn pack(images: &[(GLuint, Image)]) -> Option<((u32, u32), Vec<(u32, u32)>)> {
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(images[*i].1.height));
+    let widest = images.iter().map(|it| it.1.width as u32 + PADDING * 2).max()?;
+    let mut width = widest.next_power_of_two();
+    while width <= MAX_SIZE {
+        let mut places = vec![(0, 0); images.len()];
+        let (mut x, mut y, mut shelf) = (0, 0, 0);
+        for &i in &order {
+            let image = &images[i].1;
+            let (w, h) = (image.width as u32 + PADDING * 2, image.height as u32 + PADDING * 2);
+            if x + w > width {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+            places[i] = (x + PADDING, y + PADDING);
+            x += w;
+            shelf = shelf.max(h);
+        }
+        let height = y + shelf;
+        if height <= width || (width == MAX_SIZE && height <= MAX_SIZE) {
+            return Some(((width, height), places));
+        }
+        width *= 2;
+    }
+    None
+}
+
+fn blit_padded(dst: &mut Image, src: &Image, x: u32, y: u32) {
+    let (w, h) = (src.width as i32, src.height as i32);
+    let pad = PADDING as i32;
+    for dy in -pad..h + pad {
+        let sy = dy.clamp(0, h - 1);
+        for dx in -pad..w + pad {
+            let sx = dx.clamp(0, w - 1);
+            let from = ((sy * w + sx) * 4) as usize;
+            let to = (((y as i32 + dy) * dst.width as i32 + x as i32 + dx) * 4) as usize;
+            dst.bytes[to..to + 4].copy_from_slice(&src.bytes[from..from + 4]);
+        }
+    }
+}
+
+// one texel per note texture holding its average color, what notes too small to show any detail are drawn with
+pub struct NoteSwatches {
+    texture: SafeTexture,
+    texels: HashMap<GLuint, Vec2>,
+}
+
+impl NoteSwatches {
+    pub fn new(styles: &[&NoteStyle]) -> Self {
+        let mut colors: Vec<(GLuint, [u8; 4])> = Vec::new();
+        for texture in styles.iter().flat_map(|it| it.atlas_textures().chain(it.hold_body.as_ref())) {
+            let id = gl_id(texture);
+            if colors.iter().all(|it| it.0 != id) {
+                colors.push((id, average(&texture.get_texture_data())));
+            }
+        }
+        let width = colors.len().max(1);
+        let mut bytes = vec![0; width * 4];
+        let mut texels = HashMap::with_capacity(colors.len());
+        for (i, (id, color)) in colors.iter().enumerate() {
+            bytes[i * 4..i * 4 + 4].copy_from_slice(color);
+            texels.insert(*id, vec2((i as f32 + 0.5) / width as f32, 0.5));
+        }
+        Self {
+            texture: SafeTexture::from(Texture2D::from_rgba8(width as u16, 1, &bytes)).with_filter(GL_NEAREST),
+            texels,
+        }
+    }
+
+    // points every vertex at the texture's swatch and returns the texture to draw with, None for unknown textures
+    pub fn remap(&self, texture: GLuint, vertices: &mut [Vertex; 4]) -> Option<GLuint> {
+        let uv = *self.texels.get(&texture)?;
+        for vertex in vertices {
+            vertex.uv = uv;
+        }
+        Some(gl_id(&self.texture))
+    }
+}
+
+// weighted by alpha and made opaque, a note a few pixels wide is mostly its color, not its transparent margins
+fn average(image: &Image) -> [u8; 4] {
+    let mut sum = [0f64; 3];
+    let mut weight = 0f64;
+    for px in image.bytes.chunks_exact(4) {
+        let a = px[3] as f64;
+        for (s, c) in sum.iter_mut().zip(px) {
+            *s += *c as f64 * a;
+        }
+        weight += a;
+    }
+    if weight == 0. {
+        return [0; 4];
+    }
+    let [r, g, b] = sum.map(|it| (it / weight).round() as u8);
+    [r, g, b, 255]
+}
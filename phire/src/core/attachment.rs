@@ -0,0 +1,109 @@
+use super::{Anim, Matrix, Resource, Vector};
+use crate::{
+    ext::{SafeTexture, ScaleType},
+    ui::Ui,
+};
+use macroquad::prelude::*;
+use std::ops::Range;
+
+pub enum AttachmentKind {
+    Image(SafeTexture),
+    Text(String),
+}
+
+// an image or a piece of text from extra.json, drawn over the chart or following a line
+pub struct Attachment {
+    kind: AttachmentKind,
+    time_range: Range<f32>,
+    // index of the line it follows, full screen when None
+    pub attach: Option<usize>,
+    scale_type: ScaleType,
+    pub alpha: Anim<f32>,
+    pub position: Anim<Vec2>,
+    pub scale: Anim<f32>,
+    pub color: Anim<Color>,
+}
+
+impl Attachment {
+    pub fn new(kind: AttachmentKind, time_range: Range<f32>, attach: Option<usize>, scale_type: ScaleType) -> Self {
+        Self {
+            kind,
+            time_range,
+            attach,
+            scale_type,
+            alpha: Anim::default(),
+            position: Anim::default(),
+            scale: Anim::default(),
+            color: Anim::default(),
+        }
+    }
+
+    pub fn update(&mut self, t: f32) {
+        self.alpha.set_time(t);
+        self.position.set_time(t);
+        self.scale.set_time(t);
+        self.color.set_time(t);
+    }
+
+    fn visible(&self, res: &Resource) -> bool {
+        self.time_range.contains(&res.time)
+    }
+
+    fn color(&self, alpha: f32) -> Color {
+        let mut color = self.color.now_opt().unwrap_or(WHITE);
+        color.a *= self.alpha.now_opt().unwrap_or(1.) * alpha;
+        color
+    }
+
+    // images cover the chart area the same way full screen videos do, then get moved and scaled
+    pub fn render(&self, ui: &mut Ui, res: &Resource) {
+        if !self.visible(res) {
+            return;
+        }
+        let color = self.color(res.alpha);
+        let p = self.position.now_opt().unwrap_or_default();
+        let s = self.scale.now_opt().unwrap_or(1.);
+        match &self.kind {
+            AttachmentKind::Image(tex) => {
+                let top = 1. / res.aspect_ratio;
+                let r = Rect::new(p.x - s, p.y - top * s, s * 2., top * s * 2.);
+                ui.fill_rect(r, (**tex, r, self.scale_type, color));
+            }
+            AttachmentKind::Text(text) => {
+                ui.text(text).pos(p.x, p.y).anchor(0.5, 0.5).size(s).color(color).multiline().draw();
+            }
+        }
+    }
+
+    // drawn in the space of the attached line, sized by pixels like texture lines
+    pub fn render_attached(&self, ui: &mut Ui, res: &mut Resource, alpha: f32) {
+        if !self.visible(res) {
+            return;
+        }
+        let color = self.color(alpha);
+        let p = self.position.now_opt().unwrap_or_default();
+        let s = self.scale.now_opt().unwrap_or(1.);
+        match &self.kind {
+            AttachmentKind::Image(tex) => {
+                let size = vec2(tex.width(), tex.height()) * s;
+                draw_texture_ex(
+                    **tex,
+                    p.x - size.x / 2.,
+                    p.y - size.y / 2.,
+                    color,
+                    DrawTextureParams {
+                        dest_size: Some(size),
+                        flip_y: true,
+                        ..Default::default()
+                    },
+                );
+            }
+            AttachmentKind::Text(text) => {
+                // line space is flipped vertically
+                res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(1., -1.)), |_| {
+                    ui.text(text).pos(p.x, -p.y).anchor(0.5, 0.5).size(s).color(color).multiline().draw();
+                });
+            }
+        }
+    }
+}
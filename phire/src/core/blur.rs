@@ -0,0 +1,137 @@
+// the blurred background behind the chart, drawn on the GPU so that the strength can change while playing
+
+use crate::ext::SafeTexture;
+use anyhow::Result;
+use macroquad::prelude::*;
+
+// longer side of the blurred copy, it gets stretched over the screen anyway
+const BLUR_SIZE: f32 = 512.;
+// at most this many samples per standard deviation, wider blurs take sparser samples
+const TAPS_PER_SIGMA: f32 = 4.;
+
+pub struct BackgroundBlur {
+    source: SafeTexture,
+    material: Material,
+    targets: [RenderTarget; 2],
+    size: (u32, u32),
+    radius: f32,
+}
+
+impl BackgroundBlur {
+    pub fn new(source: SafeTexture) -> Result<Self> {
+        let (w, h) = (source.width().max(1.), source.height().max(1.));
+        let scale = (BLUR_SIZE / w.max(h)).min(1.);
+        let size = (((w * scale).round() as u32).max(1), ((h * scale).round() as u32).max(1));
+        let targets = [(); 2].map(|_| {
+            let target = render_target(size.0, size.1);
+            target.texture.set_filter(FilterMode::Linear);
+            target
+        });
+        let material = load_material(
+            shader::VERTEX,
+            shader::FRAGMENT,
+            MaterialParams {
+                uniforms: vec![("direction".to_owned(), UniformType::Float2), ("sigma".to_owned(), UniformType::Float1)],
+                ..Default::default()
+            },
+        )?;
+        Ok(Self {
+            source,
+            material,
+            targets,
+            size,
+            radius: f32::NAN,
+        })
+    }
+
+    // `radius` is in pixels of the source image, like `bg_blurriness`; the result is redrawn only when it changes
+    pub fn texture(&mut self, radius: f32) -> Texture2D {
+        if radius <= 0. {
+            return *self.source;
+        }
+        if self.radius == radius {
+            return self.targets[1].texture;
+        }
+        self.radius = radius;
+
+        let (w, h) = (self.size.0 as f32, self.size.1 as f32);
+        let sigma = radius * w / self.source.width().max(1.);
+        let spacing = (sigma / TAPS_PER_SIGMA).max(1.);
+        unsafe { get_internal_gl() }.flush();
+        push_camera_state();
+        self.material.set_uniform("sigma", sigma / spacing);
+        // horizontal pass into the first target, then vertical into the second
+        let passes = [(*self.source, vec2(spacing / w, 0.)), (self.targets[0].texture, vec2(0., spacing / h))];
+        for (target, (from, direction)) in self.targets.iter().zip(passes) {
+            set_camera(&Camera2D {
+                zoom: vec2(1., 1.),
+                render_target: Some(*target),
+                ..Default::default()
+            });
+            clear_background(BLACK);
+            self.material.set_uniform("direction", direction);
+            gl_use_material(self.material);
+            draw_texture_ex(
+                from,
+                -1.,
+                -1.,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(2., 2.)),
+                    ..Default::default()
+                },
+            );
+            gl_use_default_material();
+            unsafe { get_internal_gl() }.flush();
+        }
+        pop_camera_state();
+        self.targets[1].texture
+    }
+}
+
+impl Drop for BackgroundBlur {
+    fn drop(&mut self) {
+        self.material.delete();
+        for target in &self.targets {
+            target.delete();
+        }
+    }
+}
+
+mod shader {
+    pub const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying vec2 uv;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}"#;
+
+    pub const FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 direction;
+uniform float sigma;
+
+void main() {
+    vec3 sum = vec3(0.0);
+    float total = 0.0;
+    for (int i = -12; i <= 12; ++i) {
+        float x = float(i);
+        float weight = exp(-x * x / (2.0 * sigma * sigma));
+        sum += texture2D(Texture, uv + direction * x).rgb * weight;
+        total += weight;
+    }
+    gl_FragColor = vec4(sum / total, 1.0);
+}"#;
+}
@@ -1,20 +1,41 @@
 crate::tl_file!("parser");
 
+#[cfg(feature = "script")]
+use super::ChartScript;
 #[cfg(feature = "video")]
 use super::Video;
-use super::{BpmList, Effect, JudgeLine, JudgeLineKind, Matrix, Resource, UIElement, Vector};
-use crate::{core::Object, fs::FileSystem, judge::JudgeStatus, ui::Ui};
+use super::{Attachment, BpmList, Effect, ExtraSection, HitSound, HoldFx, JudgeLine, JudgeLineCache, JudgeLineKind, LoadProgress, LoadStage, Matrix, NoteKind, Resource, UIElement, UpdateContext, Vector};
+use crate::{
+    config::Mods,
+    core::Object,
+    fs::{spawn_task, FileSystem},
+    judge::JudgeStatus,
+    ui::{MemoryGuard, MemoryKind, Ui},
+};
 use anyhow::{Context, Result};
 use macroquad::prelude::*;
+use ::rand::seq::SliceRandom;
+use rand_pcg::{rand_core::SeedableRng, Pcg32};
 use sasa::AudioClip;
+use rayon::prelude::*;
 use std::{cell::RefCell, collections::HashMap};
 
+// below this many notes on screen, spreading the lines over threads costs more than it saves
+const PARALLEL_UPDATE_NOTES: usize = 2048;
+
 #[derive(Default)]
 pub struct ChartExtra {
     pub effects: Vec<Effect>,
     pub global_effects: Vec<Effect>,
     #[cfg(feature = "video")]
     pub videos: Vec<Video>,
+    pub attachments: Vec<Attachment>,
+    #[cfg(feature = "script")]
+    pub script: Option<ChartScript>,
+    // sections of extra.json nothing here knows about, as they were
+    pub sections: HashMap<String, serde_json::Value>,
+    // the sections a registered handler took care of
+    pub extensions: Vec<Box<dyn ExtraSection>>,
 }
 
 #[derive(Default)]
@@ -35,6 +56,8 @@ pub struct Chart {
     pub order: Vec<usize>,
     pub attach_ui: [Option<usize>; 7],
     pub hitsounds: HitSoundMap,
+    // lines, notes and their animations
+    _memory: MemoryGuard,
 }
 
 impl Chart {
@@ -51,6 +74,10 @@ impl Chart {
             })
             .collect::<Vec<_>>();
         order.sort_by_key(|it| (lines[*it].z_index, *it));
+        let memory = MemoryGuard::new(
+            MemoryKind::Chart,
+            lines.capacity() * std::mem::size_of::<JudgeLine>() + lines.iter().map(JudgeLine::heap_bytes).sum::<usize>(),
+        );
         Self {
             offset,
             lines,
@@ -61,6 +88,7 @@ impl Chart {
             order,
             attach_ui,
             hitsounds,
+            _memory: memory,
         }
     }
 
@@ -87,15 +115,60 @@ impl Chart {
         }
     }
 
-    pub async fn load_textures(&mut self, fs: &mut dyn FileSystem) -> Result<()> {
+    pub async fn load_textures(&mut self, fs: &mut dyn FileSystem, progress: &LoadProgress) -> Result<()> {
+        let total = self.lines.iter().filter(|it| matches!(it.kind, JudgeLineKind::Texture(..))).count();
+        progress.enter(LoadStage::Textures, total as u32);
         for line in &mut self.lines {
             if let JudgeLineKind::Texture(tex, path) = &mut line.kind {
-                *tex = image::load_from_memory(&fs.load_file(path).await.with_context(|| format!("failed to load illustration {path}"))?)?.into();
+                let bytes = fs.load_file(path).await.with_context(|| format!("failed to load illustration {path}"))?;
+                // decoding is the slow part, only the upload has to happen here
+                *tex = spawn_task(move || Ok(image::load_from_memory(&bytes)?)).await?.into();
+                progress.step();
             }
         }
         Ok(())
     }
 
+    // the mods that rewrite the chart itself, applied right after parsing
+    // `seed` makes RANDOM shuffle the same way every time
+    pub fn apply_mods(&mut self, mods: Mods, seed: u64) {
+        if mods.contains(Mods::FLIP_Y) {
+            for line in self.lines.iter_mut().filter(|it| it.attach_ui.is_none()) {
+                line.object.translation.1.map_value(|v| -v);
+                line.object.rotation.map_value(|v| -v);
+                for note in &mut line.notes {
+                    note.above = !note.above;
+                }
+                line.cache = JudgeLineCache::new(&mut line.notes);
+            }
+        }
+        if mods.contains(Mods::RANDOM) {
+            let mut rng = Pcg32::seed_from_u64(seed);
+            for line in &mut self.lines {
+                let mut notes: Vec<_> = line.notes.iter_mut().filter(|it| !it.fake).collect();
+                let mut xs: Vec<_> = notes.iter_mut().map(|it| std::mem::take(&mut it.object.translation.0)).collect();
+                xs.shuffle(&mut rng);
+                for (note, x) in notes.into_iter().zip(xs) {
+                    note.object.translation.0 = x;
+                }
+            }
+        }
+        if mods.contains(Mods::NOTE_SWAP) {
+            for note in self.lines.iter_mut().flat_map(|it| it.notes.iter_mut()) {
+                let kind = match note.kind {
+                    NoteKind::Click => NoteKind::Drag,
+                    NoteKind::Drag => NoteKind::Click,
+                    _ => continue,
+                };
+                // custom hitsounds stay, the default one follows the type
+                if note.hitsound == HitSound::default_from_kind(&note.kind) {
+                    note.hitsound = HitSound::default_from_kind(&kind);
+                }
+                note.kind = kind;
+            }
+        }
+    }
+
     pub fn reset(&mut self) {
         self.lines
             .iter_mut()
@@ -110,36 +183,142 @@ impl Chart {
         }
         #[cfg(feature = "video")]
         for video in &mut self.extra.videos {
-            if let Err(err) = video.reset() {
-                crate::scene::show_error(err.context(tl!("video-load-failed", "path" => video.video_file.path().to_string_lossy())));
+            video.reset();
+        }
+        #[cfg(feature = "script")]
+        if let Some(script) = &mut self.extra.script {
+            script.reset(&mut self.lines);
+        }
+        for extension in &mut self.extra.extensions {
+            extension.reset();
+        }
+    }
+
+    // `t` is in chart time
+    pub fn seek_videos(&mut self, t: f32) {
+        #[cfg(feature = "video")]
+        for video in &mut self.extra.videos {
+            video.seek_to(t);
+        }
+    }
+
+    // brings back the notes at or after `t` after seeking backwards, `t` is in chart time
+    pub fn rewind(&mut self, t: f32) {
+        for line in &mut self.lines {
+            for note in line.notes.iter_mut().filter(|note| note.time >= t) {
+                note.judge = JudgeStatus::NotJudged;
+                note.protected = false;
+            }
+            line.cache.reset(&mut line.notes);
+        }
+    }
+
+    pub fn judge_snapshot(&self) -> Vec<Vec<JudgeStatus>> {
+        self.lines.iter().map(|line| line.notes.iter().map(|note| note.judge.clone()).collect()).collect()
+    }
+
+    // puts back the judge state taken with `judge_snapshot`
+    pub fn restore_judge(&mut self, snapshot: Vec<Vec<JudgeStatus>>) {
+        for (line, judges) in self.lines.iter_mut().zip(snapshot) {
+            for (note, judge) in line.notes.iter_mut().zip(judges) {
+                note.judge = judge;
             }
+            line.cache.reset(&mut line.notes);
         }
     }
 
+    // replaces the parsed content in place, notes before `time` are treated as judged
+    pub fn reload(&mut self, chart: Chart, time: f32) {
+        *self = chart;
+        self.reset();
+        self.lines
+            .iter_mut()
+            .flat_map(|it| it.notes.iter_mut())
+            .filter(|note| note.time < time)
+            .for_each(|note| note.judge = JudgeStatus::Judged);
+    }
+
+    pub fn texture_paths(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match &line.kind {
+            JudgeLineKind::Texture(_, path) => Some(path.as_str()),
+            _ => None,
+        })
+    }
+
     pub fn update(&mut self, res: &mut Resource) {
         for line in &mut self.lines {
             line.object.set_time(res.time);
         }
         // TODO optimize
         let trs = self.lines.iter().map(|it| it.now_transform(res, &self.lines)).collect::<Vec<_>>();
-        let mut guard = self.bpm_list.borrow_mut();
-        for (index, (line, tr)) in self.lines.iter_mut().zip(trs).enumerate() {
-            line.update(res, tr, &mut guard, index);
-        }
+        let guard = self.bpm_list.borrow();
+        let notes: usize = self.lines.iter().map(|it| it.cache.update_order.len()).sum();
+        let ctx = UpdateContext::new(res);
+        let bpm_list = &*guard;
+        let fx: Vec<HoldFx> = if notes >= PARALLEL_UPDATE_NOTES {
+            self.lines
+                .par_iter_mut()
+                .zip(trs)
+                .enumerate()
+                .flat_map_iter(|(index, (line, tr))| line.update(&ctx, tr, bpm_list, index))
+                .collect()
+        } else {
+            self.lines
+                .iter_mut()
+                .zip(trs)
+                .enumerate()
+                .flat_map(|(index, (line, tr))| line.update(&ctx, tr, bpm_list, index))
+                .collect()
+        };
         drop(guard);
+        // emitting touches the shared particle emitters, so it waits until every line is done
+        for it in fx {
+            res.with_model(it.model, |res| res.emit_at_origin(it.rotation, it.color, it.good));
+        }
         for effect in &mut self.extra.effects {
             effect.update(res);
         }
+        for attachment in &mut self.extra.attachments {
+            attachment.update(res.time);
+        }
+        for extension in &mut self.extra.extensions {
+            extension.update(res);
+        }
     }
 
     pub fn render(&self, ui: &mut Ui, res: &mut Resource) {
-        #[cfg(feature = "video")]
         res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(if res.config.flip_x() { -1. } else { 1. }, 1.)), |res| {
-            for video in &self.extra.videos {
+            #[cfg(feature = "video")]
+            for video in self.extra.videos.iter().filter(|it| it.attach.is_none()) {
                 video.render(res);
             }
+            for attachment in self.extra.attachments.iter().filter(|it| it.attach.is_none()) {
+                attachment.render(ui, res);
+            }
+            for extension in &self.extra.extensions {
+                extension.render(ui, res);
+            }
         });
         res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(if res.config.flip_x() { -1. } else { 1. }, -1.)), |res| {
+            #[cfg(feature = "video")]
+            for video in &self.extra.videos {
+                let Some(line) = video.attach.and_then(|id| self.lines.get(id)) else {
+                    continue;
+                };
+                let alpha = line.object.now_alpha().max(0.) * res.alpha;
+                res.with_model(line.now_transform(res, &self.lines), |res| {
+                    res.with_model(line.object.now_scale(), |res| res.apply_model(|res| video.render_attached(res, alpha)));
+                });
+            }
+            for attachment in &self.extra.attachments {
+                let Some(line) = attachment.attach.and_then(|id| self.lines.get(id)) else {
+                    continue;
+                };
+                let alpha = line.object.now_alpha().max(0.) * res.alpha;
+                res.with_model(line.now_transform(res, &self.lines), |res| {
+                    res.with_model(line.object.now_scale(), |res| res.apply_model(|res| attachment.render_attached(ui, res, alpha)));
+                });
+            }
             let mut guard = self.bpm_list.borrow_mut();
             for id in &self.order {
                 self.lines[*id].render(ui, res, &self.lines, &mut guard, &self.settings, *id);
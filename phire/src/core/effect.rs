@@ -1,4 +1,4 @@
-use super::{Anim, Resource, Tweenable};
+use super::{use_base_material, Anim, Resource, Tweenable};
 use crate::ext::{get_viewport, screen_aspect};
 use anyhow::{anyhow, bail, Result};
 use macroquad::prelude::*;
@@ -66,6 +66,10 @@ impl UniformValue for Vec2 {
     const UNIFORM_TYPE: UniformType = UniformType::Float2;
 }
 
+impl UniformValue for Vec3 {
+    const UNIFORM_TYPE: UniformType = UniformType::Float3;
+}
+
 impl UniformValue for Color {
     const UNIFORM_TYPE: UniformType = UniformType::Float4;
 }
@@ -134,6 +138,13 @@ impl Effect {
                         let (x, y) = value.split_once(',').ok_or_else(|| anyhow!("Expected x,y"))?;
                         vec2(x.trim().parse()?, y.trim().parse()?)
                     })),
+                    "vec3" => Box::new((name, {
+                        let values: Vec<_> = value.split(',').map(|it| it.trim()).collect();
+                        if values.len() != 3 {
+                            bail!("Expected x,y,z");
+                        }
+                        vec3(values[0].parse()?, values[1].parse()?, values[2].parse()?)
+                    })),
                     "vec4" => Box::new((name, {
                         let values: Vec<_> = value.split(',').map(|it| it.trim()).collect();
                         if values.len() != 4 {
@@ -217,7 +228,7 @@ impl Effect {
         gl_use_material(self.material);
         let top = 1. / if self.global { screen_aspect() } else { res.aspect_ratio };
         draw_rectangle(-1., -top, 2., top * 2., WHITE);
-        gl_use_default_material();
+        use_base_material();
     }
 }
 
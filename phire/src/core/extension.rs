@@ -0,0 +1,36 @@
+// extra.json may carry sections this version of the parser knows nothing about. They are kept as raw
+// JSON in `ChartExtra::sections`, and a feature module can register a handler for a section so that it
+// takes part in the chart without the parser having to know about it
+
+use super::{BpmList, Resource};
+use crate::ui::Ui;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+// what a handler makes of its section, driven along with the rest of the chart
+pub trait ExtraSection {
+    fn reset(&mut self) {}
+    fn update(&mut self, _res: &mut Resource) {}
+    fn render(&self, _ui: &mut Ui, _res: &mut Resource) {}
+}
+
+pub trait ExtraHandler: Send + Sync {
+    // `bpm` is the one of extra.json, for converting beats to time
+    fn load(&self, value: &Value, bpm: &mut BpmList) -> Result<Box<dyn ExtraSection>>;
+}
+
+static HANDLERS: Lazy<RwLock<HashMap<&'static str, Arc<dyn ExtraHandler>>>> = Lazy::new(RwLock::default);
+
+// `section` is the key in extra.json; a later registration for the same key replaces the earlier one
+pub fn register_extra_handler(section: &'static str, handler: impl ExtraHandler + 'static) {
+    HANDLERS.write().unwrap().insert(section, Arc::new(handler));
+}
+
+pub fn extra_handler(section: &str) -> Option<Arc<dyn ExtraHandler>> {
+    HANDLERS.read().unwrap().get(section).cloned()
+}
@@ -0,0 +1,194 @@
+// draws the batched notes with GPU instancing, one instance per note quad instead of four vertices and six
+// indices, so that dense charts upload far less every frame. Only used when the context supports instancing,
+// `NoteBuffer` falls back to plain meshes otherwise.
+
+use macroquad::{prelude::*, window::miniquad::*};
+use miniquad::gl::GLuint;
+use tracing::warn;
+
+// instances uploaded and drawn at once
+pub const CHUNK_SIZE: usize = 16384;
+
+// the four corners as already transformed by the note, along with the part of the texture to sample
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct NoteInstance {
+    p01: [f32; 4],
+    p23: [f32; 4],
+    source: [f32; 4],
+    color: [u8; 4],
+}
+
+impl NoteInstance {
+    pub fn new(vertices: &[Vertex; 4]) -> Self {
+        let [a, b, c, d] = vertices.map(|it| it.position);
+        let (from, to) = (vertices[0].uv, vertices[2].uv);
+        Self {
+            p01: [a.x, a.y, b.x, b.y],
+            p23: [c.x, c.y, d.x, d.y],
+            source: [from.x, from.y, to.x - from.x, to.y - from.y],
+            color: vertices[0].color,
+        }
+    }
+}
+
+pub struct InstancedNotes {
+    pipeline: Pipeline,
+    bindings: Bindings,
+}
+
+impl InstancedNotes {
+    // None when the context can't instance, e.g. WebGL 1 or GLES 2 without the extension
+    pub fn new() -> Option<Self> {
+        let InternalGlContext { quad_context: ctx, .. } = unsafe { get_internal_gl() };
+        if !ctx.features().instancing {
+            return None;
+        }
+
+        #[rustfmt::skip]
+        let vertices: [f32; 24] = [
+            1., 0., 0., 0., 0., 0.,
+            0., 1., 0., 0., 1., 0.,
+            0., 0., 1., 0., 1., 1.,
+            0., 0., 0., 1., 0., 1.,
+        ];
+        let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+        let bindings = Bindings {
+            vertex_buffers: vec![
+                Buffer::immutable(ctx, BufferType::VertexBuffer, &vertices),
+                Buffer::stream(ctx, BufferType::VertexBuffer, CHUNK_SIZE * std::mem::size_of::<NoteInstance>()),
+            ],
+            index_buffer: Buffer::immutable(ctx, BufferType::IndexBuffer, &indices),
+            images: vec![Texture::empty()],
+        };
+
+        let shader = match Shader::new(ctx, shader::VERTEX, shader::FRAGMENT, shader::meta()) {
+            Ok(shader) => shader,
+            Err(err) => {
+                warn!("instanced notes unavailable: {err:?}");
+                return None;
+            }
+        };
+        let pipeline = Pipeline::with_params(
+            ctx,
+            &[
+                BufferLayout::default(),
+                BufferLayout {
+                    step_func: VertexStep::PerInstance,
+                    ..Default::default()
+                },
+            ],
+            &[
+                VertexAttribute::with_buffer("corner", VertexFormat::Float4, 0),
+                VertexAttribute::with_buffer("unit_uv", VertexFormat::Float2, 0),
+                VertexAttribute::with_buffer("p01", VertexFormat::Float4, 1),
+                VertexAttribute::with_buffer("p23", VertexFormat::Float4, 1),
+                VertexAttribute::with_buffer("source", VertexFormat::Float4, 1),
+                VertexAttribute::with_buffer("color0", VertexFormat::Byte4, 1),
+            ],
+            shader,
+            PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                )),
+                alpha_blend: Some(BlendState::new(Equation::Add, BlendFactor::Zero, BlendFactor::One)),
+                ..Default::default()
+            },
+        );
+        Some(Self { pipeline, bindings })
+    }
+
+    // draws every batch in order, returns the number of draw calls
+    pub fn draw<'a>(&mut self, batches: impl Iterator<Item = (GLuint, &'a [NoteInstance])>) -> u32 {
+        let mut gl = unsafe { get_internal_gl() };
+        gl.flush();
+        let InternalGlContext { quad_context: ctx, quad_gl } = gl;
+
+        if let Some(pass) = quad_gl.get_active_render_pass() {
+            ctx.begin_pass(pass, PassAction::Nothing);
+        } else {
+            ctx.begin_default_pass(PassAction::Nothing);
+        }
+        ctx.apply_pipeline(&self.pipeline);
+        let (x, y, w, h) = quad_gl
+            .get_viewport()
+            .unwrap_or_else(|| (0, 0, screen_width() as _, screen_height() as _));
+        ctx.apply_viewport(x, y, w, h);
+
+        let uniforms = shader::Uniforms {
+            projection: quad_gl.get_projection_matrix(),
+        };
+        let mut draw_calls = 0;
+        for (texture, instances) in batches {
+            self.bindings.images[0] = unsafe { Texture::from_raw_id(texture, TextureFormat::RGBA8) };
+            for chunk in instances.chunks(CHUNK_SIZE) {
+                self.bindings.vertex_buffers[1].update(ctx, chunk);
+                ctx.apply_bindings(&self.bindings);
+                ctx.apply_uniforms(&uniforms);
+                ctx.draw(0, 6, chunk.len() as i32);
+                draw_calls += 1;
+            }
+        }
+        ctx.end_render_pass();
+        draw_calls
+    }
+}
+
+impl Drop for InstancedNotes {
+    fn drop(&mut self) {
+        for buffer in &self.bindings.vertex_buffers {
+            buffer.delete();
+        }
+        self.bindings.index_buffer.delete();
+    }
+}
+
+mod shader {
+    use super::*;
+
+    pub const VERTEX: &str = r#"#version 100
+attribute vec4 corner;
+attribute vec2 unit_uv;
+attribute vec4 p01;
+attribute vec4 p23;
+attribute vec4 source;
+attribute vec4 color0;
+
+varying lowp vec4 color;
+varying mediump vec2 uv;
+
+uniform mat4 Projection;
+
+void main() {
+    vec2 pos = p01.xy * corner.x + p01.zw * corner.y + p23.xy * corner.z + p23.zw * corner.w;
+    gl_Position = Projection * vec4(pos, 0, 1);
+    color = color0 / 255.0;
+    uv = source.xy + source.zw * unit_uv;
+}"#;
+
+    pub const FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying mediump vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
+}"#;
+
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec!["Texture".to_string()],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![UniformDesc::new("Projection", UniformType::Mat4)],
+            },
+        }
+    }
+
+    #[repr(C)]
+    pub struct Uniforms {
+        pub projection: Mat4,
+    }
+}
@@ -0,0 +1,59 @@
+// finding where the music actually starts, so that long quiet intros can be skipped
+
+use super::Chart;
+use sasa::{AudioClip, Frame};
+
+const WINDOW_SECS: f32 = 0.05;
+// the loudness of a window is estimated from every few frames
+const STRIDE: usize = 8;
+// around -40 dBFS
+const SILENCE: f32 = 0.01;
+// quiet relative to the track as a whole
+const RELATIVE: f32 = 0.1;
+
+// least lead-in left before the first note after skipping, in seconds
+const MIN_LEAD: f32 = 1.5;
+
+fn power(frames: &[Frame]) -> f32 {
+    let (mut sum, mut n) = (0., 0);
+    for frame in frames.iter().step_by(STRIDE) {
+        sum += (frame.0 * frame.0 + frame.1 * frame.1) / 2.;
+        n += 1;
+    }
+    if n == 0 {
+        0.
+    } else {
+        sum / n as f32
+    }
+}
+
+// seconds from the start of the clip until its loudness first rises above the quiet threshold
+pub fn quiet_intro(clip: &AudioClip) -> f32 {
+    let frames = clip.frames();
+    let len = ((clip.sample_rate() as f32 * WINDOW_SECS) as usize).max(1);
+    let overall = power(frames).sqrt();
+    let threshold = SILENCE.max(overall * RELATIVE);
+    let quiet = frames.chunks(len).take_while(|window| power(window).sqrt() < threshold).count();
+    (quiet * len).min(frames.len()) as f32 / clip.sample_rate() as f32
+}
+
+// chart time a little before the first real note, landing on a beat when the chart has a global bpm list
+pub fn first_note_lead_in(chart: &Chart) -> Option<f32> {
+    let first = chart
+        .lines
+        .iter()
+        .flat_map(|it| it.notes.iter())
+        .filter(|it| !it.fake)
+        .map(|it| it.time)
+        .min_by(f32::total_cmp)?;
+    let mut bpm_list = chart.bpm_list.borrow_mut();
+    let target = if bpm_list.ranges().is_some() {
+        let beat = bpm_list.beat(first);
+        let spb = 60. / bpm_list.now_bpm(first);
+        let beats = (MIN_LEAD / spb).ceil().max(1.);
+        bpm_list.time_beats(beat - beats).min(first - MIN_LEAD)
+    } else {
+        first - MIN_LEAD
+    };
+    Some(target)
+}
@@ -1,18 +1,55 @@
-use super::{chart::ChartSettings, object::CtrlObject, Anim, AnimFloat, BpmList, Matrix, Note, Object, Point, RenderConfig, Resource, Vector};
+use super::{chart::ChartSettings, object::CtrlObject, Anim, AnimFloat, BpmList, HoldFx, Matrix, Note, Object, Point, RenderConfig, Resource, UpdateContext, Vector};
 use crate::{
-    config::Mods,
+    config::{LineCap, Mods},
     core::NoteKind,
     ext::{get_viewport, parse_alpha, NotNanExt, SafeTexture},
-    judge::{JudgeStatus, LIMIT_BAD},
+    judge::JudgeStatus,
     ui::Ui,
 };
 use macroquad::prelude::*;
 use miniquad::{RenderPass, Texture, TextureParams, TextureWrap};
 use nalgebra::Rotation2;
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::{cell::RefCell, f32::consts::PI};
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
+// vertices along each round end
+const CAP_SEGMENTS: usize = 8;
+
+// a plain line from -len to len, drawn in the line's scaled space. The scale event stretches the width along with it,
+// the ends are made wider or narrower in x by as much so that they keep their shape
+fn draw_judge_line(len: f32, width: f32, cap: LineCap, scale: Vector, color: Color) {
+    if scale.x == 0. || scale.y == 0. {
+        return;
+    }
+    let h = width / 2.;
+    let ext = h * (scale.y / scale.x).abs();
+    let mut outline = Vec::with_capacity(CAP_SEGMENTS * 2 + 2);
+    match cap {
+        LineCap::Butt | LineCap::Square => {
+            let len = if cap == LineCap::Square { len + ext } else { len };
+            outline.extend([(len, -h), (len, h), (-len, h), (-len, -h)]);
+        }
+        LineCap::Round => {
+            for (cx, from) in [(len, -PI / 2.), (-len, PI / 2.)] {
+                for i in 0..=CAP_SEGMENTS {
+                    let a = from + PI * i as f32 / CAP_SEGMENTS as f32;
+                    outline.push((cx + ext * a.cos(), h * a.sin()));
+                }
+            }
+        }
+    }
+    // convex around the origin, a fan from it covers each pixel once so translucent lines stay even
+    let mut vertices = vec![Vertex::new(0., 0., 0., 0., 0., color)];
+    vertices.extend(outline.iter().map(|&(x, y)| Vertex::new(x, y, 0., 0., 0., color)));
+    let n = outline.len() as u16;
+    let indices: Vec<u16> = (0..n).flat_map(|i| [0, i + 1, (i + 1) % n + 1]).collect();
+    let gl = unsafe { get_internal_gl() }.quad_gl;
+    gl.texture(None);
+    gl.draw_mode(DrawMode::Triangles);
+    gl.geometry(&vertices, &indices);
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum UIElement {
@@ -84,6 +121,44 @@ pub enum JudgeLineKind {
     Paint(Anim<f32>, RefCell<(Option<RenderPass>, bool)>),
 }
 
+enum Cull {
+    Draw,
+    Skip,
+    // every later note of the same speed is off screen too
+    Stop,
+}
+
+// where a note starts and ends relative to the line, in the units of the culling bounds
+fn note_span(note: &Note, height: &mut AnimFloat, line_height: f32, time: f32, aspect_ratio: f32, speed: f32) -> (f32, f32) {
+    let y = note.object.translation.1.now();
+    match note.kind {
+        NoteKind::Hold { end_time, end_height, .. } => {
+            // a hold being held stays pinned to the line
+            height.set_time(if time < end_time { time.min(note.time) } else { time });
+            let from = (note.height - height.now() + y) / aspect_ratio * speed;
+            let to = (end_height - line_height + y) / aspect_ratio * speed;
+            (from, to)
+        }
+        _ => {
+            let h = (note.height - line_height + y) / aspect_ratio * speed;
+            (h, h)
+        }
+    }
+}
+
+// notes of one speed come sorted by height, so they move away from the line in the direction of the speed:
+// up the screen when it's positive, down when it's negative and not at all when it's zero
+fn cull(from: f32, to: f32, speed: f32, below: f32, above: f32) -> Cull {
+    let (lo, hi) = if from <= to { (from, to) } else { (to, from) };
+    if speed > 0. && lo > above || speed < 0. && hi < below {
+        Cull::Stop
+    } else if hi < below || lo > above {
+        Cull::Skip
+    } else {
+        Cull::Draw
+    }
+}
+
 #[derive(Clone)]
 pub struct JudgeLineCache {
     update_order: Vec<u32>,
@@ -160,36 +235,47 @@ unsafe impl Sync for JudgeLine {}
 unsafe impl Send for JudgeLine {}
 
 impl JudgeLine {
-    pub fn update(&mut self, res: &mut Resource, tr: Matrix, bpm_list: &mut BpmList, index: usize) {
-        // self.object.set_time(res.time); // this is done by chart, chart has to calculate transform for us
+    pub fn heap_bytes(&self) -> usize {
+        self.object.heap_bytes()
+            + self.color.heap_bytes()
+            + self.height.heap_bytes()
+            + self.incline.heap_bytes()
+            + self.notes.capacity() * std::mem::size_of::<Note>()
+            + self.notes.iter().map(Note::heap_bytes).sum::<usize>()
+    }
+
+    // only touches this line, anything shared is returned for the chart to apply afterwards
+    pub fn update(&mut self, ctx: &UpdateContext, tr: Matrix, bpm_list: &BpmList, index: usize) -> Vec<HoldFx> {
+        // self.object.set_time(ctx.time); // this is done by chart, chart has to calculate transform for us
         let rot = self.object.rotation.now();
-        self.height.set_time(res.time);
+        self.height.set_time(ctx.time);
         let line_height = self.height.now();
         let mut ctrl_obj = self.ctrl_obj.borrow_mut();
+        let mut fx = Vec::new();
         self.cache.update_order.retain(|id| {
             let note = &mut self.notes[*id as usize];
-            note.update(res, rot, &tr, &mut ctrl_obj, line_height, bpm_list, index);
+            fx.extend(note.update(ctx, rot, &tr, &mut ctrl_obj, line_height, bpm_list, index));
             !note.dead()
         });
         drop(ctrl_obj);
         match &mut self.kind {
             JudgeLineKind::Text(anim) => {
-                anim.set_time(res.time);
+                anim.set_time(ctx.time);
             }
             JudgeLineKind::Paint(anim, ..) => {
-                anim.set_time(res.time);
+                anim.set_time(ctx.time);
             }
             JudgeLineKind::TextureGif(anim, ..) => {
-                anim.set_time(res.time);
+                anim.set_time(ctx.time);
             }
             _ => {}
         }
-        self.color.set_time(res.time);
+        self.color.set_time(ctx.time);
 
         let not_judge = |index: usize| {
             match self.notes[index].kind {
                 NoteKind::Hold { end_time, .. } => {
-                    matches!(self.notes[index].judge, JudgeStatus::Judged) && res.time > end_time
+                    matches!(self.notes[index].judge, JudgeStatus::Judged) && ctx.time > end_time
                 },
                 _ => {
                     matches!(self.notes[index].judge, JudgeStatus::Judged)
@@ -224,6 +310,7 @@ impl JudgeLine {
             }
             true
         });
+        fx
     }
 
     pub fn fetch_pos(&self, res: &Resource, lines: &[JudgeLine]) -> Vector {
@@ -266,7 +353,9 @@ impl JudgeLine {
                                 return;
                             }
                             let len = res.info.line_length;
-                            draw_line(-len, 0., len, 0., 0.0075, color);
+                            let width = res.config.line_width.unwrap_or(res.res_pack.info.line_width);
+                            let cap = res.config.line_cap.unwrap_or(res.res_pack.info.line_cap);
+                            draw_judge_line(len, width, cap, self.object.scale.now_with_def(1.0, 1.0), color);
                         }
                     }
                     JudgeLineKind::Texture(texture, _) => {
@@ -400,9 +489,10 @@ impl JudgeLine {
                 invisible_time: f32::INFINITY,
                 draw_below: self.show_below,
                 incline_sin: self.incline.now_opt().map(|it| it.to_radians().sin()).unwrap_or_default(),
+                line_rotation: &self.object.rotation,
             };
             if res.config.has_mod(Mods::FADE_OUT) {
-                config.invisible_time = LIMIT_BAD;
+                config.invisible_time = res.config.judge_windows().bad;
             }
             let mut line_set_debug_alpha = false;
             if alpha < 0.0 {
@@ -456,36 +546,11 @@ impl JudgeLine {
                             continue;
                         }
                         if agg {
-                            let line_height = match note.kind {
-                                NoteKind::Hold { end_time, .. } => {
-                                    let time = if res.time < end_time {
-                                        res.time.min(note.time)
-                                    } else {
-                                        res.time
-                                    };
-                                    height.set_time(time);
-                                    height.now()
-                                }
-                                _ => {
-                                    config.line_height
-                                }
-                            };
-                            let note_height = (note.height - line_height + note.object.translation.1.now()) / res.aspect_ratio * speed;
-                            match note.kind {   
-                                NoteKind::Hold { end_height, .. } => {
-                                    if end_height < height_below {
-                                        continue;
-                                    }
-                                    
-                                },
-                                _ => {
-                                    if note_height < height_below {
-                                        continue;
-                                    }
-                                }
-                            }
-                            if note_height > height_above {
-                                break;
+                            let (from, to) = note_span(note, &mut height, config.line_height, res.time, res.aspect_ratio, speed);
+                            match cull(from, to, speed, height_below, height_above) {
+                                Cull::Draw => {}
+                                Cull::Skip => continue,
+                                Cull::Stop => break,
                             }
                         }
                         note.render(ui, res, &mut config, bpm_list, line_set_debug_alpha, id, height_above);
@@ -503,36 +568,12 @@ impl JudgeLine {
                                 continue;
                             }
                             if agg {
-                                let line_height = match note.kind {
-                                    NoteKind::Hold { end_time, .. } => {
-                                        let time = if res.time < end_time {
-                                            res.time.min(note.time)
-                                        } else {
-                                            res.time
-                                        };
-                                        height.set_time(time);
-                                        height.now()
-                                    }
-                                    _ => {
-                                        config.line_height
-                                    }
-                                };
-                                let note_height = (note.height - line_height + note.object.translation.1.now()) / res.aspect_ratio * speed;
-                                match note.kind {   
-                                    NoteKind::Hold { end_height, .. } => {
-                                        if end_height < height_below {
-                                            continue;
-                                        }
-                                        
-                                    },
-                                    _ => {
-                                        if note_height < height_below {
-                                            continue;
-                                        }
-                                    }
-                                }
-                                if note_height > -height_below {
-                                    break;
+                                // mirrored, so the visible range flips as well
+                                let (from, to) = note_span(note, &mut height, config.line_height, res.time, res.aspect_ratio, speed);
+                                match cull(from, to, speed, -height_above, -height_below) {
+                                    Cull::Draw => {}
+                                    Cull::Skip => continue,
+                                    Cull::Stop => break,
                                 }
                             }
                             note.render(ui, res, &mut config, bpm_list, line_set_debug_alpha, id, -height_below);
@@ -605,7 +646,9 @@ impl JudgeLine {
                         } else {
                             format!(" anc:{} {}", self.anchor[0], self.anchor[1])
                         };
-                        let color = if line_height_ulp > 0.018518519 { // 10px error in 1080P
+                        let color = if res.inspected_line == Some(id) {
+                            Color::new(0.4, 0.9, 1., parse_alpha(alpha, res.alpha, 0.15, true))
+                        } else if line_height_ulp > 0.018518519 { // 10px error in 1080P
                             Color::new(1., 0., 0., parse_alpha(alpha, res.alpha, 0.15, res.config.chart_debug_line > 0.))
                         } else if line_height_ulp > 0.0018518519 { // 1px error in 1080P
                             Color::new(1., 1., 0., parse_alpha(alpha, res.alpha, 0.15, res.config.chart_debug_line > 0.))
@@ -624,3 +667,86 @@ impl JudgeLine {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{cull, note_span, Cull};
+    use crate::{
+        core::{Anim, AnimFloat, Note, NoteKind, Object},
+        judge::{HitSound, HitSoundTune, JudgeStatus},
+    };
+
+    fn note(kind: NoteKind, height: f32, speed: f32) -> Note {
+        Note {
+            object: Object::default(),
+            hitsound: HitSound::default_from_kind(&kind),
+            kind,
+            hitsound_tune: HitSoundTune::default(),
+            time: 1.,
+            height,
+            speed,
+            above: true,
+            multiple_hint: false,
+            fake: false,
+            judge: JudgeStatus::NotJudged,
+            judge_scale: 1.,
+            color: Anim::default(),
+            hit_fx_color: Anim::default(),
+            protected: false,
+        }
+    }
+
+    fn span(note: &Note, speed: f32) -> (f32, f32) {
+        note_span(note, &mut AnimFloat::fixed(0.), 0., 0., 1., speed)
+    }
+
+    #[test]
+    fn positive_speed() {
+        assert!(matches!(cull(0.5, 0.5, 1., -1., 1.), Cull::Draw));
+        assert!(matches!(cull(2., 2., 1., -1., 1.), Cull::Stop));
+        // behind the line, later notes may still come into view
+        assert!(matches!(cull(-2., -2., 1., -1., 1.), Cull::Skip));
+    }
+
+    #[test]
+    fn negative_speed() {
+        let (from, to) = span(&note(NoteKind::Click, 2., 1.), -1.);
+        assert_eq!((from, to), (-2., -2.));
+        assert!(matches!(cull(from, to, -1., -1., 1.), Cull::Stop));
+        let (from, to) = span(&note(NoteKind::Click, 0.5, 1.), -1.);
+        assert!(matches!(cull(from, to, -1., -1., 1.), Cull::Draw));
+        // past the top while moving down, the notes after it are further down and may be in view
+        assert!(matches!(cull(2., 2., -1., -1., 1.), Cull::Skip));
+    }
+
+    #[test]
+    fn zero_speed_never_stops() {
+        assert!(matches!(cull(2., 2., 0., -1., 1.), Cull::Skip));
+        assert!(matches!(cull(-2., -2., 0., -1., 1.), Cull::Skip));
+    }
+
+    #[test]
+    fn mixed_direction() {
+        // a hold running from below the line to far above it covers the whole screen
+        let hold = note(
+            NoteKind::Hold {
+                end_time: 2.,
+                end_height: 3.,
+                end_speed: None,
+            },
+            -3.,
+            1.,
+        );
+        let (from, to) = span(&hold, 1.);
+        assert_eq!((from, to), (-3., 3.));
+        assert!(matches!(cull(from, to, 1., -1., 1.), Cull::Draw));
+        // negated it ends before it starts, the span is the same either way round
+        let (from, to) = span(&hold, -1.);
+        assert_eq!((from, to), (3., -3.));
+        assert!(matches!(cull(from, to, -1., -1., 1.), Cull::Draw));
+        // reversed and all below the screen while moving down
+        assert!(matches!(cull(-1.5, -3., -1., -1., 1.), Cull::Stop));
+        // reversed and all above it while moving up
+        assert!(matches!(cull(3., 1.5, 1., -1., 1.), Cull::Stop));
+    }
+}
@@ -7,13 +7,20 @@ use crate::{
 
 
 use macroquad::prelude::*;
+use nalgebra::Rotation2;
 pub use crate::{
-    judge::HitSound,
+    judge::{HitSound, HitSoundTune},
 };
 
 //const HOLD_PARTICLE_INTERVAL: f32 = 0.15;
 const FADEOUT_TIME: f32 = 0.16;
 const BAD_TIME: f32 = 0.5;
+// notes narrower than this on screen are drawn in a flat color, and holds as a single strip
+const LOD_PIXELS: f32 = 4.;
+// pieces a curved hold's body is cut into
+const HOLD_SEGMENTS: usize = 16;
+// degrees the line has to turn over a hold before it's drawn curved
+const HOLD_BEND_THRESHOLD: f32 = 0.5;
 
 #[derive(Clone, Debug)]
 pub enum NoteKind {
@@ -38,6 +45,7 @@ pub struct Note {
     pub object: Object,
     pub kind: NoteKind,
     pub hitsound: HitSound,
+    pub hitsound_tune: HitSoundTune,
     pub time: f32,
     pub height: f32,
     pub speed: f32,
@@ -55,6 +63,48 @@ pub struct Note {
 unsafe impl Sync for Note {}
 unsafe impl Send for Note {}
 
+// hit effect of a hold that's still being held, emitted by the chart once all lines are updated
+pub struct HoldFx {
+    pub model: Matrix,
+    pub rotation: f32,
+    pub color: Color,
+    pub good: bool,
+}
+
+// what lines and notes read of the resource while updating, copied out so that lines can be updated on other threads
+#[derive(Clone, Copy)]
+pub struct UpdateContext {
+    pub time: f32,
+    pub aspect_ratio: f32,
+    pub speed: f32,
+    pub play_start_time: f32,
+    pub disable_hit_fx: bool,
+    pub all_good: bool,
+    pub all_bad: bool,
+    pub hold_particle_interval_ratio: f32,
+    pub note_uniform_scale: bool,
+    pub fx_perfect: Color,
+    pub fx_good: Color,
+}
+
+impl UpdateContext {
+    pub fn new(res: &Resource) -> Self {
+        Self {
+            time: res.time,
+            aspect_ratio: res.aspect_ratio,
+            speed: res.config.speed,
+            play_start_time: res.config.play_start_time,
+            disable_hit_fx: res.disable_hit_fx,
+            all_good: res.config.all_good,
+            all_bad: res.config.all_bad,
+            hold_particle_interval_ratio: res.info.hold_particle_interval_ratio,
+            note_uniform_scale: res.info.note_uniform_scale,
+            fx_perfect: res.res_pack.info.fx_perfect(),
+            fx_good: res.res_pack.info.fx_good(),
+        }
+    }
+}
+
 pub struct RenderConfig<'a> {
     pub settings: &'a ChartSettings,
     pub ctrl_obj: &'a mut CtrlObject,
@@ -63,6 +113,7 @@ pub struct RenderConfig<'a> {
     pub invisible_time: f32,
     pub draw_below: bool,
     pub incline_sin: f32,
+    pub line_rotation: &'a Anim<f32>,
 }
 
 fn draw_tex(res: &Resource, texture: Texture2D, order: i8, x: f32, y: f32, color: Color, mut params: DrawTextureParams, clip: bool) {
@@ -87,8 +138,19 @@ fn draw_tex(res: &Resource, texture: Texture2D, order: i8, x: f32, y: f32, color
     params.flip_y = true;
     draw_tex_pts(res, texture, order, p, color, params);
 }
+// length in screen space to pixels of the chart viewport
+fn to_pixels(res: &Resource, len: f32) -> f32 {
+    len * res.camera.viewport.map_or_else(screen_width, |it| it.2 as f32) * res.config.chart_ratio / 2.
+}
+
+// on-screen width of a note `scale` wide in the current model
+fn note_pixels(res: &Resource, scale: f32) -> f32 {
+    to_pixels(res, (res.world_to_screen(Point::new(scale, 0.)) - res.world_to_screen(Point::new(-scale, 0.))).norm())
+}
+
 fn draw_tex_pts(res: &Resource, texture: Texture2D, order: i8, p: [Point; 4], color: Color, params: DrawTextureParams) {
     let mut p = p.map(|it| res.world_to_screen(it));
+    let lod = to_pixels(res, (p[1] - p[0]).norm()) < LOD_PIXELS;
     if p[0].x.min(p[1].x.min(p[2].x.min(p[3].x))) > 1. / res.config.chart_ratio
         || p[0].x.max(p[1].x.max(p[2].x.max(p[3].x))) < -1. / res.config.chart_ratio
         || p[0].y.min(p[1].y.min(p[2].y.min(p[3].y))) > 1. / res.config.chart_ratio
@@ -108,15 +170,16 @@ fn draw_tex_pts(res: &Resource, texture: Texture2D, order: i8, p: [Point; 4], co
     }
 
     #[rustfmt::skip]
-    let vertices = [
+    let mut vertices = [
         Vertex::new(p[0].x, p[0].y, 0., sx     , sy     , color),
         Vertex::new(p[1].x, p[1].y, 0., sx + sw, sy     , color),
         Vertex::new(p[2].x, p[2].y, 0., sx + sw, sy + sh, color),
         Vertex::new(p[3].x, p[3].y, 0., sx     , sy + sh, color),
     ];
-    res.note_buffer
-        .borrow_mut()
-        .push((order, texture.raw_miniquad_texture_handle().gl_internal_id()), vertices);
+    let texture = texture.raw_miniquad_texture_handle().gl_internal_id();
+    let swatch = if lod { res.res_pack.note_swatches.remap(texture, &mut vertices) } else { None };
+    let texture = swatch.unwrap_or_else(|| res.res_pack.note_atlas.as_ref().map_or(texture, |it| it.remap(texture, &mut vertices)));
+    res.note_buffer.borrow_mut().push((order, texture), vertices);
 }
 
 fn draw_center(res: &Resource, tex: Texture2D, order: i8, scale: f32, color: Color) {
@@ -137,30 +200,35 @@ fn draw_center(res: &Resource, tex: Texture2D, order: i8, scale: f32, color: Col
 }
 
 impl Note {
+    pub fn heap_bytes(&self) -> usize {
+        self.object.heap_bytes() + self.color.heap_bytes() + self.hit_fx_color.heap_bytes()
+    }
+
     pub fn rotation(&self, line: &JudgeLine) -> f32 {
         line.object.rotation.now() + if self.above { 0. } else { 180. }
     }
 
-    pub fn update(&mut self, res: &mut Resource, parent_rot: f32, parent_tr: &Matrix, ctrl_obj: &mut CtrlObject, line_height: f32, bpm_list: &mut BpmList, index: usize) {
-        if self.time < res.config.play_start_time || res.disable_hit_fx {
-            return;
+    pub fn update(&mut self, ctx: &UpdateContext, parent_rot: f32, parent_tr: &Matrix, ctrl_obj: &mut CtrlObject, line_height: f32, bpm_list: &BpmList, index: usize) -> Option<HoldFx> {
+        if self.time < ctx.play_start_time || ctx.disable_hit_fx {
+            return None;
         }
-        self.object.set_time(res.time);
+        self.object.set_time(ctx.time);
         //let mut _immediate_particle = false;
         let color = if let JudgeStatus::Hold(perfect, ref mut at, ..) = self.judge {
-            if res.time >= *at {
+            if ctx.time >= *at {
                 //_immediate_particle = true;
-                let beat = 30. / bpm_list.now_bpm(
+                let beat = 30. / bpm_list.bpm_at(
                     if bpm_list.per_line_bpm_storage { index as f32 } else { self.time }
                 );
                 //println!("{} {} {}", index, bpm_list.now_bpm(index as f32), beat);
-                *at = res.time + beat * res.info.hold_particle_interval_ratio / res.config.speed; //HOLD_PARTICLE_INTERVAL
+                *at = ctx.time + beat * ctx.hold_particle_interval_ratio / ctx.speed; //HOLD_PARTICLE_INTERVAL
+                let good = !perfect || ctx.all_good || ctx.all_bad;
                 Some(if let Some(color) = self.hit_fx_color.now_opt() {
-                    color
-                } else if perfect && !res.config.all_good && !res.config.all_bad {
-                    res.res_pack.info.fx_perfect()
+                    (color, good)
+                } else if !good {
+                    (ctx.fx_perfect, good)
                 } else {
-                    res.res_pack.info.fx_good()
+                    (ctx.fx_good, good)
                 })
             } else {
                 None
@@ -169,13 +237,15 @@ impl Note {
             None
         };
 
-        if let Some(color) = color {
-            self.init_ctrl_obj(ctrl_obj, line_height);
-            let rotation = if self.above { 0. } else { 180. };
-            res.with_model(parent_tr * self.now_transform(res, ctrl_obj, 0., 0., false, false), |res| {
-                res.emit_at_origin(parent_rot + rotation, color)
-            });
-        }
+        let (color, good) = color?;
+        self.init_ctrl_obj(ctrl_obj, line_height);
+        let rotation = if self.above { 0. } else { 180. };
+        Some(HoldFx {
+            model: parent_tr * self.transform(ctx.aspect_ratio, ctx.note_uniform_scale, ctrl_obj, 0., 0., false, false),
+            rotation: parent_rot + rotation,
+            color,
+            good,
+        })
     }
     
 
@@ -189,8 +259,13 @@ impl Note {
     }
 
     pub fn now_transform(&self, res: &Resource, ctrl_obj: &CtrlObject, base: f32, incline_sin: f32, can_scale_x: bool, can_scale_y: bool) -> Matrix {
-        let incline_val = 1. - incline_sin * (base * res.aspect_ratio + self.object.translation.1.now()) * RPE_HEIGHT / 2. / 360.;
-        let mut tr = self.object.now_translation(res);
+        self.transform(res.aspect_ratio, res.info.note_uniform_scale, ctrl_obj, base, incline_sin, can_scale_x, can_scale_y)
+    }
+
+    fn transform(&self, aspect_ratio: f32, note_uniform_scale: bool, ctrl_obj: &CtrlObject, base: f32, incline_sin: f32, can_scale_x: bool, can_scale_y: bool) -> Matrix {
+        let incline_val = 1. - incline_sin * (base * aspect_ratio + self.object.translation.1.now()) * RPE_HEIGHT / 2. / 360.;
+        let mut tr = self.object.translation.now();
+        tr.y /= aspect_ratio;
         tr.x *= incline_val * ctrl_obj.pos.now_opt().unwrap_or(1.);
         tr.y += base;
         let mut scale = self.object.scale.now_with_def(1.0, 1.0);
@@ -198,13 +273,32 @@ impl Note {
             scale.x = 1.0;
         };
         scale.x *= ctrl_obj.size.now_opt().unwrap_or(1.0);
-        if !res.info.note_uniform_scale || !can_scale_y {
+        if !note_uniform_scale || !can_scale_y {
             scale.y = 1.0;
         };
         scale.y *= ctrl_obj.size.now_opt().unwrap_or(1.0);
         self.object.now_rotation().append_nonuniform_scaling(&scale).append_translation(&tr)
     }
 
+    // corrections in the hold's own frame for evenly spaced points from `from` to `to`, turning each by how far the
+    // line will have rotated once that part of the hold reaches it. None when the line barely turns meanwhile
+    fn hold_bends(&self, tr: &Matrix, rotation: &Anim<f32>, from: f32, to: f32) -> Option<Vec<Matrix>> {
+        if rotation.is_default() {
+            return None;
+        }
+        let now = rotation.now();
+        let deltas: Vec<f32> = (0..=HOLD_SEGMENTS)
+            .map(|i| rotation.at(from + (to - from) * i as f32 / HOLD_SEGMENTS as f32).unwrap_or(now) - now)
+            .collect();
+        if deltas.iter().all(|it| it.abs() < HOLD_BEND_THRESHOLD) {
+            return None;
+        }
+        let inverse = tr.try_inverse()?;
+        // notes below the line are drawn mirrored, which turns rotations the other way
+        let sign = if self.above { 1. } else { -1. };
+        Some(deltas.into_iter().map(|it| inverse * Rotation2::new((it * sign).to_radians()).to_homogeneous() * tr).collect())
+    }
+
     pub fn render(&self, ui: &mut Ui, res: &mut Resource, config: &mut RenderConfig, bpm_list: &mut BpmList, line_set_debug_alpha: bool, line_id: usize, height_above: f32) {
         if config.appear_before.is_finite() {
         //if config.appear_before.is_finite() && !matches!(self.kind, NoteKind::Hold { .. }) {
@@ -322,7 +416,9 @@ impl Note {
             }
             NoteKind::Hold { end_time, end_height, end_speed } => {
                 if self.fake && res.time >= end_time { return };
-                res.with_model(self.now_transform(res, ctrl_obj, 0., 0., true, false), |res| {
+                let line_rotation = config.line_rotation;
+                let tr = self.now_transform(res, ctrl_obj, 0., 0., true, false);
+                res.with_model(tr, |res| {
                     if matches!(self.judge, JudgeStatus::Judged) {
                         // miss
                         color.a *= 0.5;
@@ -373,45 +469,118 @@ impl Note {
 
                     let tex = &style.hold;
                     let ratio = style.hold_ratio();
+                    if note_pixels(res, scale) < LOD_PIXELS {
+                        // head, body and tail merged, they'd be a few pixels of the same color anyway
+                        let head = style.hold_head_rect();
+                        let head = head.h / head.w * scale * ratio * if res.res_pack.info.hold_compact { 1. } else { 2. };
+                        let tail = style.hold_tail_rect();
+                        let tail = tail.h / tail.w * scale * ratio * if res.res_pack.info.hold_compact { 1. } else { 2. };
+                        let start = if res.time < self.time || res.res_pack.info.hold_keep_head { bottom - head } else { bottom };
+                        draw_tex(
+                            res,
+                            **tex,
+                            order,
+                            -scale,
+                            start,
+                            color,
+                            DrawTextureParams {
+                                source: Some(style.hold_body_rect()),
+                                dest_size: Some(vec2(scale * 2., top + tail - start)),
+                                ..Default::default()
+                            },
+                            clip,
+                        );
+                        return;
+                    }
+                    let bends = if res.config.curved_holds { self.hold_bends(&tr, line_rotation, time, end_time) } else { None };
                     // body
                     // TODO (end_height - height) is not always total height
-                    draw_tex(
-                        res,
-                        **(if res.res_pack.info.hold_repeat {
-                            style.hold_body.as_ref().unwrap()
-                        } else {
-                            tex
-                        }),
-                        order,
-                        -scale,
-                        bottom,
-                        color,
-                        DrawTextureParams {
-                            source: Some({
-                                if res.res_pack.info.hold_repeat {
-                                    let hold_body = style.hold_body.as_ref().unwrap();
-                                    let width = hold_body.width();
-                                    let height = hold_body.height();
-                                    Rect::new(0., 0., 1., (top - bottom) / scale / 2. * width / height)
-                                } else {
-                                    style.hold_body_rect()
-                                }
-                            }),
-                            dest_size: Some(vec2(scale * 2., top - bottom)),
-                            ..Default::default()
-                        },
-                        clip,
-                    );
+                    let body_tex = **(if res.res_pack.info.hold_repeat {
+                        style.hold_body.as_ref().unwrap()
+                    } else {
+                        tex
+                    });
+                    let body_source = if res.res_pack.info.hold_repeat {
+                        let hold_body = style.hold_body.as_ref().unwrap();
+                        let width = hold_body.width();
+                        let height = hold_body.height();
+                        Rect::new(0., 0., 1., (top - bottom) / scale / 2. * width / height)
+                    } else {
+                        style.hold_body_rect()
+                    };
+                    if let Some(bends) = bends.as_ref().filter(|_| top > bottom) {
+                        for (i, pair) in bends.windows(2).enumerate() {
+                            let f0 = i as f32 / HOLD_SEGMENTS as f32;
+                            let f1 = (i + 1) as f32 / HOLD_SEGMENTS as f32;
+                            let y0 = bottom + (top - bottom) * f0;
+                            let y1 = bottom + (top - bottom) * f1;
+                            let p = [
+                                pair[0].transform_point(&Point::new(-scale, y0)),
+                                pair[0].transform_point(&Point::new(scale, y0)),
+                                pair[1].transform_point(&Point::new(scale, y1)),
+                                pair[1].transform_point(&Point::new(-scale, y1)),
+                            ];
+                            let Rect { x, y, w, h } = body_source;
+                            draw_tex_pts(
+                                res,
+                                body_tex,
+                                order,
+                                p,
+                                color,
+                                DrawTextureParams {
+                                    source: Some(Rect::new(x, y + h * (1. - f1), w, h * (f1 - f0))),
+                                    flip_y: true,
+                                    ..Default::default()
+                                },
+                            );
+                        }
+                    } else {
+                        draw_tex(
+                            res,
+                            body_tex,
+                            order,
+                            -scale,
+                            bottom,
+                            color,
+                            DrawTextureParams {
+                                source: Some(body_source),
+                                dest_size: Some(vec2(scale * 2., top - bottom)),
+                                ..Default::default()
+                            },
+                            clip,
+                        );
+                    }
                     // head
                     if res.time < self.time || res.res_pack.info.hold_keep_head {
                         let r = style.hold_head_rect();
                         let hf = vec2(scale, r.h / r.w * scale * ratio);
+                        res.with_model(bends.as_ref().map_or(Matrix::identity(), |it| it[0]), |res| {
+                            draw_tex(
+                                res,
+                                **tex,
+                                order,
+                                -scale,
+                                bottom - if res.res_pack.info.hold_compact { hf.y } else { hf.y * 2. },
+                                color,
+                                DrawTextureParams {
+                                    source: Some(r),
+                                    dest_size: Some(hf * 2.),
+                                    ..Default::default()
+                                },
+                                clip,
+                            );
+                        });
+                    }
+                    // tail
+                    let r = style.hold_tail_rect();
+                    let hf = vec2(scale, r.h / r.w * scale * ratio);
+                    res.with_model(bends.as_ref().map_or(Matrix::identity(), |it| it[HOLD_SEGMENTS]), |res| {
                         draw_tex(
                             res,
                             **tex,
                             order,
                             -scale,
-                            bottom - if res.res_pack.info.hold_compact { hf.y } else { hf.y * 2. },
+                            top - if res.res_pack.info.hold_compact { hf.y } else { 0. },
                             color,
                             DrawTextureParams {
                                 source: Some(r),
@@ -420,24 +589,7 @@ impl Note {
                             },
                             clip,
                         );
-                    }
-                    // tail
-                    let r = style.hold_tail_rect();
-                    let hf = vec2(scale, r.h / r.w * scale * ratio);
-                    draw_tex(
-                        res,
-                        **tex,
-                        order,
-                        -scale,
-                        top - if res.res_pack.info.hold_compact { hf.y } else { 0. },
-                        color,
-                        DrawTextureParams {
-                            source: Some(r),
-                            dest_size: Some(hf * 2.),
-                            ..Default::default()
-                        },
-                        clip,
-                    );
+                    });
                 });
             }
             NoteKind::Flick => {
@@ -11,6 +11,15 @@ pub struct Object {
 }
 
 impl Object {
+    pub fn heap_bytes(&self) -> usize {
+        self.alpha.heap_bytes()
+            + self.scale.0.heap_bytes()
+            + self.scale.1.heap_bytes()
+            + self.rotation.heap_bytes()
+            + self.translation.0.heap_bytes()
+            + self.translation.1.heap_bytes()
+    }
+
     pub fn is_default(&self) -> bool {
         self.alpha.is_default()
             && self.scale.0.is_default()
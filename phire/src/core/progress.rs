@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum LoadStage {
+    Parse,
+    Textures,
+    ResPack,
+    Audio,
+}
+
+impl LoadStage {
+    pub const COUNT: usize = 4;
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Parse,
+            1 => Self::Textures,
+            2 => Self::ResPack,
+            _ => Self::Audio,
+        }
+    }
+
+    pub fn label(&self, chinese: bool) -> &'static str {
+        match (self, chinese) {
+            (Self::Parse, false) => "Parsing chart",
+            (Self::Parse, true) => "解析谱面",
+            (Self::Textures, false) => "Loading textures",
+            (Self::Textures, true) => "加载贴图",
+            (Self::ResPack, false) => "Loading resource pack",
+            (Self::ResPack, true) => "加载资源包",
+            (Self::Audio, false) => "Decoding audio",
+            (Self::Audio, true) => "解码音频",
+        }
+    }
+}
+
+// written by the loading task as it goes, read by the loading scene every frame
+#[derive(Default)]
+pub struct LoadProgress {
+    stage: AtomicU8,
+    done: AtomicU32,
+    total: AtomicU32,
+}
+
+impl LoadProgress {
+    // enters `stage`, which is then split into `total` steps
+    pub fn enter(&self, stage: LoadStage, total: u32) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+        self.stage.store(stage as u8, Ordering::Relaxed);
+    }
+
+    pub fn step(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stage(&self) -> LoadStage {
+        LoadStage::from_u8(self.stage.load(Ordering::Relaxed))
+    }
+
+    // progress within the current stage
+    pub fn stage_ratio(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.;
+        }
+        (self.done.load(Ordering::Relaxed) as f32 / total as f32).min(1.)
+    }
+
+    // the stages count as equal parts of the whole
+    pub fn ratio(&self) -> f32 {
+        (self.stage() as u8 as f32 + self.stage_ratio()) / LoadStage::COUNT as f32
+    }
+}
@@ -1,17 +1,41 @@
 use macroquad::{
+    material::{gl_use_default_material, gl_use_material, load_material, Material, MaterialParams},
     texture::{RenderTarget, Texture2D},
     window::get_internal_gl,
 };
-use miniquad::{gl::GLuint, RenderPass, Texture, TextureFormat};
+use miniquad::{
+    gl::{GLenum, GLuint},
+    BlendFactor, BlendState, BlendValue, Equation, PipelineParams, RenderPass, Texture, TextureFormat,
+};
+use std::{cell::Cell, ffi::CStr};
+use tracing::warn;
+
+// missing from the bindings
+const GL_SRGB8_ALPHA8: GLenum = 0x8C43;
+const GL_FRAMEBUFFER_SRGB: GLenum = 0x8DB9;
+
+thread_local! {
+    static BASE_MATERIAL: Cell<Option<Material>> = const { Cell::new(None) };
+}
 
 pub struct MSRenderTarget {
     dim: (u32, u32),
     fbo: GLuint,
     rbo: GLuint,
+    srgb: bool,
     dummy: RenderTarget,
     output: [Option<RenderTarget>; 2],
 }
 
+// gives a render texture sRGB storage, so that it's blended into in linear space without dark colors banding
+fn make_srgb(texture: Texture, dim: (u32, u32)) {
+    unsafe {
+        use miniquad::gl::*;
+        glBindTexture(GL_TEXTURE_2D, texture.gl_internal_id());
+        glTexImage2D(GL_TEXTURE_2D, 0, GL_SRGB8_ALPHA8 as _, dim.0 as _, dim.1 as _, 0, GL_RGBA, GL_UNSIGNED_BYTE, std::ptr::null());
+    }
+}
+
 pub fn copy_fbo(src: GLuint, dst: GLuint, dim: (u32, u32)) -> bool {
     unsafe {
         use miniquad::gl::*;
@@ -28,14 +52,14 @@ pub fn internal_id(target: RenderTarget) -> GLuint {
 }
 
 impl MSRenderTarget {
-    pub fn new(dim: (u32, u32), samples: u32) -> Self {
+    pub fn new(dim: (u32, u32), samples: u32, srgb: bool) -> Self {
         let mut fbo = 0;
         let mut rbo = 0;
         unsafe {
             use miniquad::gl::*;
             glGenRenderbuffers(1, &mut rbo as *mut _);
             glBindRenderbuffer(GL_RENDERBUFFER, rbo);
-            glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples as _, GL_RGB8, dim.0 as _, dim.1 as _);
+            glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples as _, if srgb { GL_SRGB8_ALPHA8 } else { GL_RGB8 }, dim.0 as _, dim.1 as _);
             glGenFramebuffers(1, &mut fbo as *mut _);
             glBindFramebuffer(GL_FRAMEBUFFER, fbo);
             glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, rbo);
@@ -50,12 +74,16 @@ impl MSRenderTarget {
                 ..Default::default()
             },
         );
+        if srgb {
+            make_srgb(texture, dim);
+        }
         let render_pass = RenderPass::new(gl.quad_context, texture, None);
         let dummy_render_pass = RenderPass::from_raw(gl.quad_context, fbo, texture);
         Self {
             dim,
             fbo,
             rbo,
+            srgb,
             dummy: RenderTarget {
                 texture: Texture2D::from_miniquad_texture(texture),
                 render_pass: dummy_render_pass,
@@ -87,6 +115,9 @@ impl MSRenderTarget {
                     ..Default::default()
                 },
             );
+            if self.srgb {
+                make_srgb(texture, self.dim);
+            }
             let render_pass = RenderPass::new(gl.quad_context, texture, None);
             self.output[0] = Some(RenderTarget {
                 texture: Texture2D::from_miniquad_texture(texture),
@@ -121,3 +152,154 @@ impl Drop for MSRenderTarget {
         }
     }
 }
+
+// sRGB render targets came with GL 3 and GLES 3
+pub fn srgb_supported() -> bool {
+    let version = unsafe {
+        use miniquad::gl::*;
+        let ptr = glGetString(GL_VERSION);
+        if ptr.is_null() {
+            return false;
+        }
+        CStr::from_ptr(ptr as *const _).to_string_lossy().into_owned()
+    };
+    version
+        .trim_start_matches("OpenGL ES ")
+        .chars()
+        .next()
+        .and_then(|it| it.to_digit(10))
+        .map_or(false, |major| major >= 3)
+}
+
+// what's drawn with when no other material is in use, the one that linearizes colors while drawing in linear color.
+// Materials of their own should go back to this instead of the default one
+pub fn use_base_material() {
+    match BASE_MATERIAL.with(Cell::get) {
+        Some(material) => gl_use_material(material),
+        None => gl_use_default_material(),
+    }
+}
+
+// drawing into an sRGB chart target: textures and vertex colors are sRGB, so they're turned linear on the way in,
+// blending is then done on linear values, and the target is encoded back when it's shown
+pub struct LinearColor {
+    input: Material,
+    output: Material,
+    // desktop GL only writes sRGB when asked to
+    desktop: bool,
+}
+
+impl LinearColor {
+    pub fn new() -> Option<Self> {
+        if !srgb_supported() {
+            warn!("sRGB render targets aren't supported, drawing in gamma space");
+            return None;
+        }
+        let load = |fragment| {
+            load_material(
+                shader::VERTEX,
+                fragment,
+                MaterialParams {
+                    pipeline_params: PipelineParams {
+                        color_blend: Some(BlendState::new(
+                            Equation::Add,
+                            BlendFactor::Value(BlendValue::SourceAlpha),
+                            BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                        )),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .map_err(|err| warn!("failed to load linear color shaders: {err:?}"))
+            .ok()
+        };
+        let desktop = unsafe {
+            use miniquad::gl::*;
+            let ptr = glGetString(GL_VERSION);
+            !ptr.is_null() && !CStr::from_ptr(ptr as *const _).to_string_lossy().starts_with("OpenGL ES")
+        };
+        Some(Self {
+            input: load(shader::LINEARIZE)?,
+            output: load(shader::ENCODE)?,
+            desktop,
+        })
+    }
+
+    pub fn begin(&self) {
+        unsafe { get_internal_gl() }.flush();
+        if self.desktop {
+            unsafe { miniquad::gl::glEnable(GL_FRAMEBUFFER_SRGB) };
+        }
+        BASE_MATERIAL.with(|it| it.set(Some(self.input)));
+        gl_use_material(self.input);
+    }
+
+    pub fn end(&self) {
+        unsafe { get_internal_gl() }.flush();
+        if self.desktop {
+            unsafe { miniquad::gl::glDisable(GL_FRAMEBUFFER_SRGB) };
+        }
+        BASE_MATERIAL.with(|it| it.set(None));
+        gl_use_default_material();
+    }
+
+    // for drawing the finished target onto a plain framebuffer
+    pub fn output(&self) -> Material {
+        self.output
+    }
+}
+
+impl Drop for LinearColor {
+    fn drop(&mut self) {
+        self.input.delete();
+        self.output.delete();
+    }
+}
+
+mod shader {
+    pub const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying mediump vec2 uv;
+varying mediump vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+    // a plain power curve, close enough to the sRGB one and it keeps `tex * color` the same either way round
+    pub const LINEARIZE: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+
+void main() {
+    vec4 c = color * texture2D(Texture, uv);
+    gl_FragColor = vec4(pow(c.rgb, vec3(2.2)), c.a);
+}"#;
+
+    // the target decodes itself when sampled, what's left is encoding for the screen
+    pub const ENCODE: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+
+void main() {
+    vec4 c = color * texture2D(Texture, uv);
+    gl_FragColor = vec4(pow(c.rgb, vec3(1.0 / 2.2)), c.a);
+}"#;
+}
@@ -1,18 +1,28 @@
-use super::{MSRenderTarget, Matrix, Point, NOTE_WIDTH_RATIO_BASE};
+use super::{
+    time_stretch, BackgroundBlur, Chart, MusicStream, InstancedNotes, LinearColor, LoadProgress, LoadStage, MSRenderTarget, Matrix, NoteAtlas, NoteInstance, NoteSwatches, NoteKind, Point,
+    NOTE_WIDTH_RATIO_BASE,
+};
 use crate::{
-    config::Config,
+    config::{Config, LineCap, NotePalette},
     core::tween::Tweenable,
     ext::{create_audio_manger, nalgebra_to_glm, SafeTexture},
-    fs::FileSystem,
+    fs::{spawn_task, FileSystem, LayeredFileSystem},
     info::ChartInfo,
-    particle::{AtlasConfig, ColorCurve, Curve, Emitter, EmitterConfig, Interpolation, ParticleShape}
+    judge::HitSound,
+    particle::{AtlasConfig, ColorCurve, Curve, Emitter, EmitterConfig, Interpolation, ParticleShape},
+    ui::{MemoryGuard, NoteStats},
 };
 use anyhow::{bail, Context, Result};
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder},
+    AnimationDecoder, DynamicImage, Frames,
+};
 use macroquad::prelude::*;
 use miniquad::{gl::{GLuint, GL_LINEAR}, Texture, TextureWrap};
-use sasa::{AudioClip, AudioManager, Sfx};
+use sasa::{AudioClip, AudioManager, Frame, Sfx};
 use serde::Deserialize;
-use std::{cell::RefCell, collections::{BTreeMap, HashMap, VecDeque}, ops::DerefMut, path::Path, sync::atomic::AtomicU32};
+use std::{cell::RefCell, collections::{BTreeMap, HashMap, VecDeque}, io::Cursor, ops::DerefMut, path::Path, sync::{atomic::AtomicU32, Arc}};
+use tracing::warn;
 use rand_pcg::{
     Pcg32,
     rand_core::SeedableRng
@@ -22,6 +32,10 @@ pub const MAX_SIZE: usize = 64; // needs tweaking
 pub static DPI_VALUE: AtomicU32 = AtomicU32::new(250);
 pub const BUFFER_SIZE: usize = 1024;
 pub const RNG_SEED: u64 = 0x7a_61_6b_6f;
+// tracks longer than this are streamed when nothing needs them decoded, and the beginning that's decoded
+// anyway for intro detection
+const STREAM_MIN_LENGTH: f64 = 180.;
+const STREAM_HEAD: f32 = 30.;
 
 #[inline]
 fn default_scale() -> f32 {
@@ -58,6 +72,10 @@ fn default_tinted() -> bool {
     true
 }
 
+fn default_line_width() -> f32 {
+    0.0075
+}
+
 #[inline]
 fn default_particle_count() -> usize {
     4
@@ -71,6 +89,9 @@ pub struct ResPackInfo {
     pub author: String,
 
     pub hit_fx: (u32, u32),
+    // atlas of hit_fx_good.png, shown for good judgements instead of the usual one
+    #[serde(default)]
+    pub hit_fx_good: Option<(u32, u32)>,
     #[serde(default = "default_duration")]
     pub hit_fx_duration: f32,
     #[serde(default = "default_scale")]
@@ -87,6 +108,10 @@ pub struct ResPackInfo {
     pub hit_fx_tinted: bool,
     #[serde(default = "default_tinted")]
     pub line_tinted: bool,
+    #[serde(default = "default_line_width")]
+    pub line_width: f32,
+    #[serde(default)]
+    pub line_cap: LineCap,
 
     pub hold_atlas: (u32, u32),
     #[serde(rename = "holdAtlasMH")]
@@ -147,6 +172,54 @@ impl ResPackInfo {
     }
 }
 
+// frames of an animated note texture, looping forever
+pub struct NoteAnimation {
+    frames: Vec<SafeTexture>,
+    // when each frame ends, in seconds from the start of the loop
+    ends: Vec<f32>,
+}
+
+impl NoteAnimation {
+    // browsers play frames shorter than this at a slower rate anyway
+    const MIN_DELAY: f32 = 0.02;
+
+    fn decode(frames: Frames) -> Result<Option<Self>> {
+        let frames = frames.collect_frames()?;
+        if frames.len() < 2 {
+            return Ok(None);
+        }
+        let mut end = 0.;
+        let mut res = Self {
+            frames: Vec::with_capacity(frames.len()),
+            ends: Vec::with_capacity(frames.len()),
+        };
+        for frame in frames {
+            let (num, den) = frame.delay().numer_denom_ms();
+            end += (num as f32 / den.max(1) as f32 / 1000.).max(Self::MIN_DELAY);
+            res.ends.push(end);
+            res.frames.push(SafeTexture::from(DynamicImage::ImageRgba8(frame.into_buffer())).with_filter(GL_LINEAR));
+        }
+        Ok(Some(res))
+    }
+
+    // an APNG in place of the usual png, or a gif next to it
+    async fn load(fs: &mut dyn FileSystem, name: &str, png: &[u8]) -> Result<Option<Self>> {
+        if let Ok(gif) = fs.load_file(&format!("{name}.gif")).await {
+            return Self::decode(GifDecoder::new(Cursor::new(gif))?.into_frames()).with_context(|| format!("Invalid {name}.gif"));
+        }
+        let decoder = PngDecoder::new(Cursor::new(png))?;
+        if !decoder.is_apng() {
+            return Ok(None);
+        }
+        Self::decode(decoder.apng().into_frames()).with_context(|| format!("Invalid {name}.png"))
+    }
+
+    fn frame(&self, t: f32) -> &SafeTexture {
+        let t = t.rem_euclid(*self.ends.last().unwrap());
+        &self.frames[self.ends.partition_point(|it| *it <= t).min(self.frames.len() - 1)]
+    }
+}
+
 pub struct NoteStyle {
     pub click: SafeTexture,
     pub hold: SafeTexture,
@@ -154,6 +227,8 @@ pub struct NoteStyle {
     pub drag: SafeTexture,
     pub hold_body: Option<SafeTexture>,
     pub hold_atlas: (u32, u32),
+    // click, drag and flick
+    pub animations: [Option<NoteAnimation>; 3],
 }
 
 impl NoteStyle {
@@ -188,6 +263,128 @@ impl NoteStyle {
         let ey = self.to_uv(self.hold_atlas.0);
         Rect::new(0., 0., 1., ey)
     }
+
+    // the repeating middle part of the hold texture, for packs with `hold_repeat`
+    fn load_hold_body(&mut self) {
+        let pixels = self.hold.get_texture_data();
+        let width = self.hold.width() as u16;
+        let height = self.hold.height() as u16;
+        let atlas = self.hold_atlas;
+        let res = Texture2D::from_rgba8(
+            width,
+            height - atlas.0 as u16 - atlas.1 as u16,
+            &pixels.bytes[(atlas.0 as usize * width as usize * 4)..(pixels.bytes.len() - atlas.1 as usize * width as usize * 4)],
+        );
+        let context = unsafe { get_internal_gl() }.quad_context;
+        res.raw_miniquad_texture_handle().set_wrap(context, TextureWrap::Repeat);
+        self.hold_body = Some(res.into());
+    }
+
+    // tint stage for the accessibility palettes: each texture keeps its shading but takes on the color of its kind
+    pub fn recolor(&mut self, palette: NotePalette, markers: bool) {
+        let Some([click, drag, flick, hold]) = palette_colors(palette) else {
+            return;
+        };
+        self.click = recolor_texture(&self.click, click, None);
+        self.drag = recolor_texture(&self.drag, drag, markers.then_some(Marker::Dot));
+        self.flick = recolor_texture(&self.flick, flick, markers.then_some(Marker::Arrow));
+        self.hold = recolor_texture(&self.hold, hold, None);
+        if self.hold_body.is_some() {
+            self.load_hold_body();
+        }
+        let tints = [(click, None), (drag, Some(Marker::Dot)), (flick, Some(Marker::Arrow))];
+        for (anim, (color, marker)) in self.animations.iter_mut().zip(tints) {
+            if let Some(anim) = anim {
+                for frame in &mut anim.frames {
+                    *frame = recolor_texture(frame, color, marker.filter(|_| markers));
+                }
+            }
+        }
+    }
+
+    // everything drawn through the note buffer; the repeating hold body can't be moved into an atlas
+    pub fn atlas_textures(&self) -> impl Iterator<Item = &SafeTexture> {
+        [&self.click, &self.hold, &self.flick, &self.drag]
+            .into_iter()
+            .chain(self.animations.iter().flatten().flat_map(|it| &it.frames))
+    }
+
+    // `t` is any steadily running clock, in seconds
+    pub fn animate(&mut self, t: f32) {
+        let [click, drag, flick] = &self.animations;
+        if let Some(anim) = click {
+            self.click = anim.frame(t).clone();
+        }
+        if let Some(anim) = drag {
+            self.drag = anim.frame(t).clone();
+        }
+        if let Some(anim) = flick {
+            self.flick = anim.frame(t).clone();
+        }
+    }
+}
+
+// click, drag, flick and hold
+fn palette_colors(palette: NotePalette) -> Option<[Color; 4]> {
+    Some(match palette {
+        NotePalette::Off => return None,
+        NotePalette::HighContrast => [
+            Color::new(0.3, 0.85, 1., 1.),
+            Color::new(1., 0.9, 0., 1.),
+            Color::new(1., 0.25, 0.85, 1.),
+            Color::new(1., 1., 1., 1.),
+        ],
+        // Okabe-Ito colors, told apart without red and green
+        NotePalette::RedGreen => [
+            Color::new(0.34, 0.71, 0.91, 1.),
+            Color::new(0.94, 0.89, 0.26, 1.),
+            Color::new(0.84, 0.37, 0., 1.),
+            Color::new(0.8, 0.47, 0.65, 1.),
+        ],
+        NotePalette::BlueYellow => [
+            Color::new(0.95, 0.95, 0.95, 1.),
+            Color::new(0.86, 0.15, 0.5, 1.),
+            Color::new(0., 0.6, 0.6, 1.),
+            Color::new(0.95, 0.45, 0.1, 1.),
+        ],
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Marker {
+    Dot,
+    Arrow,
+}
+
+impl Marker {
+    // `x` and `y` are relative to the center, in units of the texture height
+    fn contains(self, x: f32, y: f32) -> bool {
+        match self {
+            Self::Dot => x * x + y * y <= 0.3 * 0.3,
+            // a triangle pointing to the top of the image
+            Self::Arrow => (-0.3..=0.3).contains(&y) && x.abs() <= (y + 0.3) * 0.8,
+        }
+    }
+}
+
+fn recolor_texture(tex: &SafeTexture, color: Color, marker: Option<Marker>) -> SafeTexture {
+    let mut image = tex.get_texture_data();
+    let (w, h) = (image.width as usize, image.height as usize);
+    for (i, px) in image.bytes.chunks_exact_mut(4).enumerate() {
+        let luma = (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) / 255.;
+        let k = 0.3 + 0.7 * luma;
+        let mut rgb = [color.r * k, color.g * k, color.b * k];
+        if let Some(marker) = marker {
+            let (x, y) = ((i % w) as f32 - w as f32 / 2., (i / w) as f32 - h as f32 / 2.);
+            if px[3] > 0 && marker.contains(x / h as f32, y / h as f32) {
+                rgb = [0.08; 3];
+            }
+        }
+        for (dst, v) in px.iter_mut().zip(rgb) {
+            *dst = (v.clamp(0., 1.) * 255.) as u8;
+        }
+    }
+    SafeTexture::from(Texture2D::from_rgba8(w as u16, h as u16, &image.bytes)).with_filter(GL_LINEAR)
 }
 
 pub struct ResourcePack {
@@ -197,8 +394,14 @@ pub struct ResourcePack {
     pub sfx_click: AudioClip,
     pub sfx_drag: AudioClip,
     pub sfx_flick: AudioClip,
+    // optional click variants for good and bad judgements
+    pub sfx_click_good: Option<AudioClip>,
+    pub sfx_click_bad: Option<AudioClip>,
     pub endings: [AudioClip; 8],
     pub hit_fx: SafeTexture,
+    pub hit_fx_good: Option<SafeTexture>,
+    pub note_atlas: Option<NoteAtlas>,
+    pub note_swatches: NoteSwatches,
 }
 
 impl ResourcePack {
@@ -214,50 +417,74 @@ impl ResourcePack {
         .await
     }
 
+    // `top` over each of `fallbacks` in order, and the built-in pack under all of them, so that a pack
+    // may replace only some of the assets
+    pub async fn from_layers(top: Option<&str>, fallbacks: &[String]) -> Result<Self> {
+        let Some(top) = top else {
+            return Self::from_path(None::<&str>).await;
+        };
+        let mut layers: Vec<Box<dyn FileSystem>> = Vec::new();
+        for path in std::iter::once(top).chain(fallbacks.iter().map(String::as_str)) {
+            layers.push(crate::fs::fs_from_file(Path::new(path))?);
+        }
+        layers.push(crate::fs::fs_from_assets(format!("respack{}", std::path::MAIN_SEPARATOR))?);
+        Self::load(&mut LayeredFileSystem(layers)).await
+    }
+
     pub async fn load(fs: &mut dyn FileSystem) -> Result<Self> {
+        // decodes off the main thread, only the upload happens here
+        async fn decode(bytes: Vec<u8>) -> Result<DynamicImage> {
+            spawn_task(move || Ok(image::load_from_memory(&bytes)?)).await
+        }
         macro_rules! load_tex {
             ($path:literal) => {
-                SafeTexture::from(image::load_from_memory(&fs.load_file($path).await.with_context(|| format!("Missing {}", $path))?)?).with_filter(GL_LINEAR)
+                SafeTexture::from(decode(fs.load_file($path).await.with_context(|| format!("Missing {}", $path))?).await?).with_filter(GL_LINEAR)
             };
         }
+        macro_rules! load_note_tex {
+            ($name:literal) => {{
+                let png = fs.load_file(concat!($name, ".png")).await.with_context(|| format!("Missing {}.png", $name))?;
+                let anim = NoteAnimation::load(fs, $name, &png).await?;
+                (SafeTexture::from(decode(png).await?).with_filter(GL_LINEAR), anim)
+            }};
+        }
         let info: ResPackInfo = serde_yaml::from_str(&String::from_utf8(fs.load_file("info.yml").await.context("Missing info.yml")?)?)?;
+        let (click, click_anim) = load_note_tex!("click");
+        let (flick, flick_anim) = load_note_tex!("flick");
+        let (drag, drag_anim) = load_note_tex!("drag");
         let mut note_style = NoteStyle {
-            click: load_tex!("click.png"),
+            click,
             hold: load_tex!("hold.png"),
-            flick: load_tex!("flick.png"),
-            drag: load_tex!("drag.png"),
+            flick,
+            drag,
             hold_body: None,
             hold_atlas: info.hold_atlas,
+            animations: [click_anim, drag_anim, flick_anim],
         };
         note_style.verify()?;
+        let (click, click_anim) = load_note_tex!("click_mh");
+        let (flick, flick_anim) = load_note_tex!("flick_mh");
+        let (drag, drag_anim) = load_note_tex!("drag_mh");
         let mut note_style_mh = NoteStyle {
-            click: load_tex!("click_mh.png"),
+            click,
             hold: load_tex!("hold_mh.png"),
-            flick: load_tex!("flick_mh.png"),
-            drag: load_tex!("drag_mh.png"),
+            flick,
+            drag,
             hold_body: None,
             hold_atlas: info.hold_atlas_mh,
+            animations: [click_anim, drag_anim, flick_anim],
         };
         note_style_mh.verify()?;
         if info.hold_repeat {
-            fn get_body(style: &mut NoteStyle) {
-                let pixels = style.hold.get_texture_data();
-                let width = style.hold.width() as u16;
-                let height = style.hold.height() as u16;
-                let atlas = style.hold_atlas;
-                let res = Texture2D::from_rgba8(
-                    width,
-                    height - atlas.0 as u16 - atlas.1 as u16,
-                    &pixels.bytes[(atlas.0 as usize * width as usize * 4)..(pixels.bytes.len() - atlas.1 as usize * width as usize * 4)],
-                );
-                let context = unsafe { get_internal_gl() }.quad_context;
-                res.raw_miniquad_texture_handle().set_wrap(context, TextureWrap::Repeat);
-                style.hold_body = Some(res.into());
-            }
-            get_body(&mut note_style);
-            get_body(&mut note_style_mh);
+            note_style.load_hold_body();
+            note_style_mh.load_hold_body();
         }
-        let hit_fx = image::load_from_memory(&fs.load_file("hit_fx.png").await.context("Missing hit_fx.png")?)?.into();
+        let hit_fx = decode(fs.load_file("hit_fx.png").await.context("Missing hit_fx.png")?).await?.into();
+        let hit_fx_good = if info.hit_fx_good.is_some() {
+            Some(decode(fs.load_file("hit_fx_good.png").await.context("Missing hit_fx_good.png")?).await?.into())
+        } else {
+            None
+        };
 
         macro_rules! load_clip {
             ($path:literal) => {
@@ -273,6 +500,19 @@ impl ResourcePack {
             };
         }
 
+        macro_rules! load_clip_opt {
+            ($path:literal) => {{
+                let mut clip = None;
+                for ext in ["ogg", "wav", "mp3"] {
+                    if let Ok(file) = fs.load_file(&format!("{}.{ext}", $path)).await {
+                        clip = Some(AudioClip::new(file)?);
+                        break;
+                    }
+                }
+                clip
+            }};
+        }
+
         macro_rules! load_ending {
             ($suffix:literal) => {
                 if let Some(sfx) = fs.load_file(format!("ending{}.ogg", $suffix).as_str()).await.ok().map(|it| AudioClip::new(it)).transpose()? {
@@ -290,6 +530,8 @@ impl ResourcePack {
                 }
             };
         }
+        let note_atlas = NoteAtlas::new(&[&note_style, &note_style_mh]);
+        let note_swatches = NoteSwatches::new(&[&note_style, &note_style_mh]);
         Ok(Self {
             info,
             note_style,
@@ -297,6 +539,8 @@ impl ResourcePack {
             sfx_click: load_clip!("click"),
             sfx_drag: load_clip!("drag"),
             sfx_flick: load_clip!("flick"),
+            sfx_click_good: load_clip_opt!("click_good"),
+            sfx_click_bad: load_clip_opt!("click_bad"),
             endings: [
                 load_ending!("_ap"),
                 load_ending!("_fc"),
@@ -308,13 +552,33 @@ impl ResourcePack {
                 load_ending!("")
                 ],
             hit_fx,
+            hit_fx_good,
+            note_atlas,
+            note_swatches,
         })
     }
+
+    pub fn recolor_notes(&mut self, config: &Config) {
+        if palette_colors(config.note_palette).is_none() {
+            return;
+        }
+        self.note_style.recolor(config.note_palette, config.note_shape_markers);
+        self.note_style_mh.recolor(config.note_palette, config.note_shape_markers);
+        self.note_atlas = NoteAtlas::new(&[&self.note_style, &self.note_style_mh]);
+        self.note_swatches = NoteSwatches::new(&[&self.note_style, &self.note_style_mh]);
+    }
+
+    pub fn animate(&mut self, t: f32) {
+        self.note_style.animate(t);
+        self.note_style_mh.animate(t);
+    }
 }
 
 pub struct ParticleEmitter {
     pub scale: f32,
     pub emitter: Emitter,
+    // for good judgements, when the pack has a separate atlas for them
+    pub emitter_good: Option<Emitter>,
     pub emitter_square: Emitter,
     pub hide_particles: bool,
     pub particle_count: usize,
@@ -356,6 +620,13 @@ impl ParticleEmitter {
             colors_curve,
             ..Default::default()
         };
+        let emitter_good = res_pack.hit_fx_good.as_ref().zip(res_pack.info.hit_fx_good).map(|(tex, atlas)| {
+            Emitter::new(EmitterConfig {
+                texture: Some(**tex),
+                atlas: Some(AtlasConfig::new(atlas.0 as _, atlas.1 as _, ..)),
+                ..emitter_config.clone()
+            })
+        });
         let shape = if res_pack.info.circle_particles {
             ParticleShape::Circle { subdivisions: 16 }
         } else {
@@ -381,6 +652,7 @@ impl ParticleEmitter {
         let mut res = Self {
             scale: res_pack.info.hit_fx_scale,
             emitter: Emitter::new(emitter_config),
+            emitter_good,
             emitter_square: Emitter::new(emitter_square_config),
             hide_particles: res_pack.info.hide_particles,
             particle_count: res_pack.info.particle_count,
@@ -389,10 +661,14 @@ impl ParticleEmitter {
         res
     }
 
-    pub fn emit_at(&mut self, pt: Vec2, rotation: f32, color: Color) {
-        self.emitter.config.initial_rotation = rotation;
-        self.emitter.config.base_color = color;
-        self.emitter.emit(pt, 1);
+    pub fn emit_at(&mut self, pt: Vec2, rotation: f32, color: Color, good: bool) {
+        let emitter = match &mut self.emitter_good {
+            Some(emitter) if good => emitter,
+            _ => &mut self.emitter,
+        };
+        emitter.config.initial_rotation = rotation;
+        emitter.config.base_color = color;
+        emitter.emit(pt, 1);
         if !self.hide_particles {
             self.emitter_square.config.base_color = color;
             self.emitter_square.emit(pt, self.particle_count);
@@ -401,22 +677,125 @@ impl ParticleEmitter {
 
     pub fn draw(&mut self, dt: f32) {
         self.emitter.draw(vec2(0., 0.), dt);
+        if let Some(emitter) = &mut self.emitter_good {
+            emitter.draw(vec2(0., 0.), dt);
+        }
         self.emitter_square.draw(vec2(0., 0.), dt);
     }
 
     pub fn set_scale(&mut self, scale: f32) {
         self.emitter.config.size = self.scale * scale / 5.;
+        if let Some(emitter) = &mut self.emitter_good {
+            emitter.config.size = self.scale * scale / 5.;
+        }
         self.emitter_square.config.size = self.scale * scale / 44.;
     }
 }
 
 #[derive(Default)]
-pub struct NoteBuffer(BTreeMap<(i8, GLuint), Vec<(Vec<Vertex>, Vec<u16>)>>);
+pub struct NoteBuffer {
+    meshes: BTreeMap<(i8, GLuint), Vec<(Vec<Vertex>, Vec<u16>)>>,
+    instanced: Option<InstancedNotes>,
+    instances: BTreeMap<(i8, GLuint), Vec<NoteInstance>>,
+    stats: NoteStats,
+}
 pub type SfxMap = HashMap<String, Sfx>;
+// how long a hitsound is counted towards the bus level, roughly the loud part of a click
+const BUS_VOICE_TIME: f64 = 0.12;
+// the limiter leaves everything below this fraction of the ceiling untouched
+const BUS_KNEE: f32 = 0.7;
+// how long a voice holds its slot, including the tail
+const BUS_VOICE_LIFETIME: f64 = 0.4;
+
+// all hitsounds go through this bus, whose limiter estimates the summed level of the voices
+// still ringing and scales new ones down so that dense chords compress instead of clipping
+pub struct HitsoundBus {
+    enabled: bool,
+    ceiling: f32,
+    max_voices: usize,
+    voices: VecDeque<(f64, f32)>,
+}
+
+impl HitsoundBus {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.sfx_limiter,
+            ceiling: 1.,
+            max_voices: config.max_sfx_voices.max(1) as usize,
+            voices: VecDeque::new(),
+        }
+    }
+
+    fn limit(&self, x: f32) -> f32 {
+        let knee = self.ceiling * BUS_KNEE;
+        if x <= knee {
+            x
+        } else {
+            let range = self.ceiling - knee;
+            knee + range * ((x - knee) / range).tanh()
+        }
+    }
+
+    // returns the amplifier to play a new voice with, or None if it's dropped
+    pub fn admit(&mut self, now: f64, amplifier: f32) -> Option<f32> {
+        self.voices.retain(|(t, _)| (0.0..BUS_VOICE_LIFETIME).contains(&(now - t)));
+        // sasa can't cut a playing voice short, so once every slot is taken new voices are dropped until one frees up
+        if self.voices.len() >= self.max_voices {
+            return None;
+        }
+        let admitted = if self.enabled && amplifier > 0. {
+            let level: f32 = self
+                .voices
+                .iter()
+                .filter(|(t, _)| now - t < BUS_VOICE_TIME)
+                .map(|(t, amp)| amp * (1. - ((now - t) / BUS_VOICE_TIME) as f32))
+                .sum();
+            (self.limit(level + amplifier) - self.limit(level)).max(0.)
+        } else {
+            amplifier
+        };
+        self.voices.push_back((now, admitted));
+        Some(admitted)
+    }
+}
+
+// resampled hitsounds, keyed by the pitch in cents
+pub type PitchedSfxMap = HashMap<HitSound, HashMap<i32, Sfx>>;
+
+// linear interpolation is good enough for clips as short as hitsounds
+fn resample_frames(frames: &[Frame], step: f32) -> Vec<Frame> {
+    let len = (frames.len() as f32 / step) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f32 * step;
+            let index = (pos as usize).min(frames.len() - 1);
+            let f = pos - index as f32;
+            let a = frames[index];
+            let b = frames.get(index + 1).copied().unwrap_or(a);
+            Frame(a.0 + (b.0 - a.0) * f, a.1 + (b.1 - a.1) * f)
+        })
+        .collect()
+}
+
+fn resample(clip: &AudioClip, pitch: f32) -> AudioClip {
+    AudioClip::from_raw(resample_frames(clip.frames(), pitch), clip.sample_rate())
+}
 
 impl NoteBuffer {
+    // instancing is used when asked for and supported, plain meshes otherwise
+    pub fn new(instanced: bool) -> Self {
+        Self {
+            instanced: if instanced { InstancedNotes::new() } else { None },
+            ..Default::default()
+        }
+    }
+
     pub fn push(&mut self, key: (i8, GLuint), vertices: [Vertex; 4]) {
-        let meshes = self.0.entry(key).or_default();
+        if self.instanced.is_some() {
+            self.instances.entry(key).or_default().push(NoteInstance::new(&vertices));
+            return;
+        }
+        let meshes = self.meshes.entry(key).or_default();
         if meshes.last().map_or(true, |it| it.0.len() + 4 > MAX_SIZE * 4) {
             meshes.push(Default::default());
         }
@@ -427,17 +806,35 @@ impl NoteBuffer {
     }
 
     pub fn draw_all(&mut self) {
+        if let Some(instanced) = &mut self.instanced {
+            let instances = std::mem::take(&mut self.instances);
+            self.stats.flushes += 1;
+            self.stats.quads += instances.values().map(|it| it.len() as u32).sum::<u32>();
+            self.stats.draw_calls += instanced.draw(instances.iter().map(|((_, tex_id), it)| (*tex_id, &it[..])));
+            // keeps the allocations for the next frame
+            self.instances = instances;
+            self.instances.values_mut().for_each(Vec::clear);
+            return;
+        }
         let mut gl = unsafe { get_internal_gl() };
         gl.flush();
         let gl = gl.quad_gl;
         gl.draw_mode(DrawMode::Triangles);
-        for ((_, tex_id), meshes) in std::mem::take(&mut self.0).into_iter() {
+        self.stats.flushes += 1;
+        for ((_, tex_id), meshes) in std::mem::take(&mut self.meshes).into_iter() {
             gl.texture(Some(Texture2D::from_miniquad_texture(unsafe { Texture::from_raw_id(tex_id, miniquad::TextureFormat::RGBA8) })));
             for mesh in meshes {
+                self.stats.draw_calls += 1;
+                self.stats.quads += mesh.0.len() as u32 / 4;
                 gl.geometry(&mesh.0, &mesh.1);
             }
         }
     }
+
+    // counts since the last call, for the performance overlay
+    pub fn take_stats(&mut self) -> NoteStats {
+        std::mem::take(&mut self.stats)
+    }
 }
 
 pub struct Resource {
@@ -456,7 +853,10 @@ pub struct Resource {
     pub camera: Camera2D,
 
     pub background: SafeTexture,
+    pub blur: BackgroundBlur,
     pub illustration: SafeTexture,
+    // fills the bars around a forced aspect ratio, blurred by `letterbox_blur` of the info
+    pub letterbox: Option<BackgroundBlur>,
     pub icons: [SafeTexture; 8],
     pub challenge_icons: [SafeTexture; 6],
     pub res_pack: ResourcePack,
@@ -470,16 +870,34 @@ pub struct Resource {
 
     pub audio: AudioManager,
     pub music: AudioClip,
+    // the music as decoded, `music` is stretched to the chart speed
+    pub source_music: AudioClip,
+    // the compressed track while it's streamed, `source_music` and `music` only hold its beginning then
+    pub streamed_music: Option<Arc<[u8]>>,
     pub track_length: f32,
     pub sfx_click: Sfx,
     pub sfx_drag: Sfx,
     pub sfx_flick: Sfx,
+    pub sfx_click_good: Option<Sfx>,
+    pub sfx_click_bad: Option<Sfx>,
     pub extra_sfxs: SfxMap,
+    pub pitched_sfxs: PitchedSfxMap,
+    pub hitsound_bus: HitsoundBus,
+    // the music before hitsounds were mixed into it
+    pub dry_music: Option<AudioClip>,
+    pub hitsounds_premixed: bool,
+    _music_memory: Vec<MemoryGuard>,
     pub frame_times: VecDeque<f64>, // frame interval time
     pub disable_hit_fx: bool,
+    // raised by the miss ducking assist
+    pub hitsound_gain: f32,
+    // line picked in the line debug view
+    pub inspected_line: Option<usize>,
 
     pub chart_target: Option<MSRenderTarget>,
     pub no_effect: bool,
+    // set when linear color is on and the driver can do it
+    pub linear_color: Option<LinearColor>,
 
     pub note_buffer: RefCell<NoteBuffer>,
 
@@ -541,15 +959,24 @@ impl Resource {
         background: SafeTexture,
         illustration: SafeTexture,
         has_no_effect: bool,
+        progress: &LoadProgress,
     ) -> Result<Self> {
         macro_rules! load_tex {
             ($path:literal) => {
                 SafeTexture::from(Texture2D::from_image(&load_image($path).await?))
             };
         }
-        let res_pack = ResourcePack::from_path(config.res_pack_path.as_ref())
+        progress.enter(LoadStage::ResPack, 1);
+        let (top, fallbacks) = if config.safe_mode {
+            (None, &[][..])
+        } else {
+            (config.res_pack_path.as_deref(), &config.res_pack_fallbacks[..])
+        };
+        let mut res_pack = ResourcePack::from_layers(top, fallbacks)
             .await
             .context("Failed to load resource pack")?;
+        res_pack.recolor_notes(&config);
+        progress.step();
         let vec2_ratio = vec2(1.,-config.aspect_ratio.unwrap_or(info.aspect_ratio));
         let camera = Camera2D {
             target: vec2(0., 0.),
@@ -558,25 +985,68 @@ impl Resource {
         };
 
         let mut audio = create_audio_manger(&config)?;
-        let music = AudioClip::new(fs.load_file(&info.music).await?)?;
-        let music_length = music.length() as f32;
+        progress.enter(LoadStage::Audio, 2);
+        let music_file: Arc<[u8]> = fs.load_file(&info.music).await?.into();
+        let stream_length = if Self::can_stream(&config) {
+            MusicStream::new(music_file.clone()).ok().and_then(|it| it.length()).filter(|it| *it >= STREAM_MIN_LENGTH)
+        } else {
+            None
+        };
+        let (source_music, streamed_music) = if stream_length.is_some() {
+            let bytes = music_file.clone();
+            let (frames, sample_rate) = spawn_task(move || MusicStream::decode_range(bytes, 0., STREAM_HEAD)).await?;
+            (AudioClip::from_raw(frames, sample_rate), Some(music_file))
+        } else {
+            (spawn_task(move || AudioClip::new(music_file.to_vec())).await?, None)
+        };
+        progress.step();
+        let music = {
+            let (clip, speed) = (source_music.clone(), config.speed);
+            spawn_task(move || Ok(time_stretch(&clip, speed))).await?
+        };
+        progress.step();
+        let music_length = stream_length.map_or_else(|| source_music.length() as f32, |it| it as f32);
         let track_length = config.play_end_time.unwrap_or(music_length).min(music_length);
         let buffer_size = Some(BUFFER_SIZE);
         let sfx_click = audio.create_sfx(res_pack.sfx_click.clone(), buffer_size)?;
         let sfx_drag = audio.create_sfx(res_pack.sfx_drag.clone(), buffer_size)?;
         let sfx_flick = audio.create_sfx(res_pack.sfx_flick.clone(), buffer_size)?;
+        let sfx_click_good = res_pack.sfx_click_good.clone().map(|it| audio.create_sfx(it, buffer_size)).transpose()?;
+        let sfx_click_bad = res_pack.sfx_click_bad.clone().map(|it| audio.create_sfx(it, buffer_size)).transpose()?;
+        let hitsound_bus = HitsoundBus::new(&config);
         let frame_times: VecDeque<f64> = VecDeque::new();
 
+        let letterbox = match &info.letterbox {
+            _ if !info.force_aspect_ratio => None,
+            Some(path) => {
+                let art = async {
+                    let bytes = fs.load_file(path).await?;
+                    spawn_task(move || Ok(image::load_from_memory(&bytes)?)).await
+                };
+                match art.await {
+                    Ok(art) => Some(BackgroundBlur::new(SafeTexture::from(art))?),
+                    Err(err) => {
+                        warn!("failed to load letterbox {path}: {err:?}");
+                        None
+                    }
+                }
+            }
+            None if info.letterbox_blur.is_some() => Some(BackgroundBlur::new(illustration.clone())?),
+            None => None,
+        };
+
         let aspect_ratio = config.aspect_ratio.unwrap_or(info.aspect_ratio);
         let note_width = config.note_scale * NOTE_WIDTH_RATIO_BASE;
         let note_scale = config.note_scale;
 
         let no_effect = !config.render_extra || has_no_effect;
+        let linear_color = if config.linear_color { LinearColor::new() } else { None };
 
         let emitter = ParticleEmitter::new(&res_pack, note_scale, Some(config.clone()));
 
         macroquad::window::gl_set_drawcall_buffer_capacity(MAX_SIZE * 4, MAX_SIZE * 6);
-        Ok(Self {
+        let note_buffer = RefCell::new(NoteBuffer::new(config.instanced_notes));
+        let mut res = Self {
             config,
             info,
             aspect_ratio,
@@ -591,8 +1061,10 @@ impl Resource {
 
             camera,
 
+            blur: BackgroundBlur::new(background.clone())?,
             background,
             illustration,
+            letterbox,
             icons: Self::load_icons().await?,
             challenge_icons: Self::load_challenge_icons().await?,
             res_pack,
@@ -606,25 +1078,200 @@ impl Resource {
 
             audio,
             music,
+            source_music,
+            streamed_music,
             track_length,
             sfx_click,
             sfx_drag,
             sfx_flick,
+            sfx_click_good,
+            sfx_click_bad,
             extra_sfxs: SfxMap::new(),
+            pitched_sfxs: PitchedSfxMap::new(),
+            hitsound_bus,
+            dry_music: None,
+            hitsounds_premixed: false,
+            _music_memory: Vec::new(),
             frame_times,
             disable_hit_fx: false,
+            hitsound_gain: 1.,
+            inspected_line: None,
 
             chart_target: None,
             no_effect,
+            linear_color,
 
-            note_buffer: RefCell::new(NoteBuffer::default()),
+            note_buffer,
 
             model_stack: vec![Matrix::identity()],
             #[cfg(feature = "play")]
             shake_play_mode_deque: VecDeque::new(),
             #[cfg(feature = "play")]
             shake_play_paused: false,
-        })
+        };
+        res.track_music();
+        Ok(res)
+    }
+
+    // counts the music clips again after one was replaced, clones share their frames so they're counted once
+    fn track_music(&mut self) {
+        let mut seen = Vec::new();
+        self._music_memory = [Some(&self.music), Some(&self.source_music), self.dry_music.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|clip| {
+                let ptr = clip.frames().as_ptr();
+                !seen.contains(&ptr) && {
+                    seen.push(ptr);
+                    true
+                }
+            })
+            .map(MemoryGuard::audio)
+            .collect();
+    }
+
+    // must run before the chart's custom hitsound clips are drained into extra_sfxs
+    pub fn load_pitched_sfxs(&mut self, chart: &Chart) {
+        self.pitched_sfxs.clear();
+        for note in chart.lines.iter().flat_map(|it| it.notes.iter()) {
+            let cents = note.hitsound_tune.cents();
+            if cents == 0 {
+                continue;
+            }
+            let clip = match &note.hitsound {
+                HitSound::None => continue,
+                HitSound::Click => &self.res_pack.sfx_click,
+                HitSound::Flick => &self.res_pack.sfx_flick,
+                HitSound::Drag => &self.res_pack.sfx_drag,
+                HitSound::Custom(name) => match chart.hitsounds.get(name) {
+                    Some(clip) => clip,
+                    None => continue,
+                },
+            };
+            let sfxs = self.pitched_sfxs.entry(note.hitsound.clone()).or_default();
+            if sfxs.contains_key(&cents) || clip.frames().is_empty() {
+                continue;
+            }
+            let pitch = 2f32.powf(cents as f32 / 1200.);
+            if let Ok(sfx) = self.audio.create_sfx(resample(clip, pitch), Some(BUFFER_SIZE)) {
+                sfxs.insert(cents, sfx);
+            }
+        }
+    }
+
+    // a stream is played as it is, it can't be stretched or have hitsounds mixed into it
+    fn can_stream(config: &Config) -> bool {
+        config.speed == 1. && !config.premix_hitsounds
+    }
+
+    // has to be followed by `premix_hitsounds` since the premixed track is dropped
+    pub fn stretch_music(&mut self) {
+        if !Self::can_stream(&self.config) {
+            if let Some(bytes) = self.streamed_music.take() {
+                match AudioClip::new(bytes.to_vec()) {
+                    Ok(clip) => self.source_music = clip,
+                    Err(err) => {
+                        warn!("failed to decode the streamed music, it stays streamed: {err:?}");
+                        self.streamed_music = Some(bytes);
+                    }
+                }
+            }
+        }
+        self.music = time_stretch(&self.source_music, self.config.speed);
+        self.dry_music = None;
+        self.track_music();
+    }
+
+    // mixes the hitsounds that don't depend on the player into the music track, that's every
+    // note under autoplay and keysounds on fake notes otherwise; offset is in music time
+    pub fn premix_hitsounds(&mut self, chart: &Chart, offset: f32) {
+        self.hitsounds_premixed = false;
+        let dry = self.dry_music.take().unwrap_or_else(|| self.music.clone());
+        self.music = dry.clone();
+        self.dry_music = Some(dry);
+        let config = &self.config;
+        if !config.premix_hitsounds || config.volume_music <= 1e-2 {
+            return;
+        }
+        let autoplay = config.autoplay();
+        let dry = self.dry_music.as_ref().unwrap();
+        let sample_rate = dry.sample_rate();
+        let mut mixed = dry.frames().to_vec();
+        let mut cache: HashMap<(HitSound, i32), Vec<Frame>> = HashMap::new();
+        let mut any = false;
+        for note in chart.lines.iter().flat_map(|it| it.notes.iter()) {
+            let wanted = if autoplay {
+                !note.fake && note.time >= config.play_start_time && !(config.all_bad && matches!(note.kind, NoteKind::Click))
+            } else {
+                note.fake && matches!(note.hitsound, HitSound::Custom(_))
+            };
+            if !wanted {
+                continue;
+            }
+            let cents = note.hitsound_tune.cents();
+            let clip = match &note.hitsound {
+                HitSound::None => continue,
+                HitSound::Click => &self.res_pack.sfx_click,
+                HitSound::Flick => &self.res_pack.sfx_flick,
+                HitSound::Drag => &self.res_pack.sfx_drag,
+                HitSound::Custom(name) => match chart.hitsounds.get(name) {
+                    Some(clip) => clip,
+                    None => continue,
+                },
+            };
+            if clip.frames().is_empty() {
+                continue;
+            }
+            // the music is played back at the chart speed, so hitsounds are stretched the other way
+            let frames = cache.entry((note.hitsound.clone(), cents)).or_insert_with(|| {
+                let pitch = 2f32.powf(cents as f32 / 1200.);
+                resample_frames(clip.frames(), pitch * clip.sample_rate() as f32 / sample_rate as f32 / config.speed)
+            });
+            let gain = config.volume_sfx * note.hitsound_tune.volume / config.volume_music;
+            let start = ((note.time + offset) * sample_rate as f32).max(0.) as usize;
+            for (dst, src) in mixed.iter_mut().skip(start).zip(frames.iter()) {
+                dst.0 += src.0 * gain;
+                dst.1 += src.1 * gain;
+            }
+            any = true;
+        }
+        if any {
+            self.music = AudioClip::from_raw(mixed, sample_rate);
+            self.hitsounds_premixed = autoplay;
+            self.track_music();
+        }
+    }
+
+    // swaps the pack in the middle of a session; custom hitsounds are kept as they are, and a
+    // premixed track still carries the old sounds until `premix_hitsounds` runs again
+    pub fn set_res_pack(&mut self, mut res_pack: ResourcePack) -> Result<()> {
+        res_pack.recolor_notes(&self.config);
+        let buffer_size = Some(BUFFER_SIZE);
+        let audio = &mut self.audio;
+        self.sfx_click = audio.create_sfx(res_pack.sfx_click.clone(), buffer_size)?;
+        self.sfx_drag = audio.create_sfx(res_pack.sfx_drag.clone(), buffer_size)?;
+        self.sfx_flick = audio.create_sfx(res_pack.sfx_flick.clone(), buffer_size)?;
+        self.sfx_click_good = res_pack.sfx_click_good.clone().map(|it| audio.create_sfx(it, buffer_size)).transpose()?;
+        self.sfx_click_bad = res_pack.sfx_click_bad.clone().map(|it| audio.create_sfx(it, buffer_size)).transpose()?;
+        for (sound, sfxs) in &mut self.pitched_sfxs {
+            let clip = match sound {
+                HitSound::Click => &res_pack.sfx_click,
+                HitSound::Flick => &res_pack.sfx_flick,
+                HitSound::Drag => &res_pack.sfx_drag,
+                _ => continue,
+            };
+            if clip.frames().is_empty() {
+                continue;
+            }
+            for (cents, sfx) in sfxs.iter_mut() {
+                let pitch = 2f32.powf(*cents as f32 / 1200.);
+                *sfx = audio.create_sfx(resample(clip, pitch), buffer_size)?;
+            }
+        }
+        self.emitter = ParticleEmitter::new(&res_pack, self.config.note_scale, Some(self.config.clone()));
+        self.judge_line_color = res_pack.info.line_perfect();
+        self.res_pack = res_pack;
+        Ok(())
     }
 
     pub fn reset(&mut self) {
@@ -632,7 +1279,7 @@ impl Resource {
         self.emitter.emitter_square.config.rng = Some(Pcg32::seed_from_u64(RNG_SEED));
     }
 
-    pub fn emit_at_origin(&mut self, rotation: f32, color: Color) {
+    pub fn emit_at_origin(&mut self, rotation: f32, color: Color, good: bool) {
         if !self.config.particle {
             return;
         }
@@ -641,16 +1288,21 @@ impl Resource {
             vec2(if self.config.flip_x() { -pt.x } else { pt.x }, -pt.y),
             if self.res_pack.info.hit_fx_rotate { rotation.to_radians() } else { 0. },
             color,
+            good,
         );
     }
 
+    pub fn blur_radius(&self) -> f32 {
+        self.info.background_blur.unwrap_or(self.config.bg_blurriness)
+    }
+
     pub fn update_size(&mut self, vp: (i32, i32, i32, i32)) -> bool {
         if self.last_vp == vp {
             return false;
         }
         self.last_vp = vp;
-        if !self.no_effect || self.config.sample_count != 1 {
-            self.chart_target = Some(MSRenderTarget::new((vp.2 as u32, vp.3 as u32), self.config.sample_count));
+        if !self.no_effect || self.config.sample_count != 1 || self.linear_color.is_some() {
+            self.chart_target = Some(MSRenderTarget::new((vp.2 as u32, vp.3 as u32), self.config.sample_count, self.linear_color.is_some()));
         }
         fn viewport(aspect_ratio: f32, (x, y, w, h): (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
             let w = w as f32;
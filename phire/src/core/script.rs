@@ -0,0 +1,182 @@
+use super::{AnimFloat, AnimVector, JudgeLine, Matrix, Resource, Vector};
+use crate::judge::Judgement;
+use anyhow::{anyhow, Result};
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+// keeps a runaway callback from freezing the game
+const MAX_OPERATIONS: u64 = 200_000;
+
+enum Command {
+    Alpha(usize, f32),
+    Rotation(usize, f32),
+    Position(usize, f32, f32),
+    Restore(usize),
+    Particle(f32, f32),
+}
+
+// the animations a script replaced, restored when the chart restarts
+#[derive(Default)]
+struct Saved {
+    alpha: Option<AnimFloat>,
+    rotation: Option<AnimFloat>,
+    translation: Option<AnimVector>,
+}
+
+impl Saved {
+    fn restore(self, line: &mut JudgeLine) {
+        if let Some(alpha) = self.alpha {
+            line.object.alpha = alpha;
+        }
+        if let Some(rotation) = self.rotation {
+            line.object.rotation = rotation;
+        }
+        if let Some(translation) = self.translation {
+            line.object.translation = translation;
+        }
+    }
+}
+
+// script.rhai shipped with a chart; it may define on_update(time) and on_judge(line, note, result),
+// and drive lines and particles through set_alpha, set_rotation, set_position, restore and spawn_particle
+pub struct ChartScript {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    commands: Arc<Mutex<Vec<Command>>>,
+    saved: HashMap<usize, Saved>,
+    on_update: bool,
+    on_judge: bool,
+    failed: bool,
+}
+
+impl ChartScript {
+    pub fn new(source: &str) -> Result<Self> {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        macro_rules! register {
+            ($name:literal, |$($arg:ident: $ty:ty),*| $command:expr) => {{
+                let commands = Arc::clone(&commands);
+                engine.register_fn($name, move |$($arg: $ty),*| commands.lock().unwrap().push($command));
+            }};
+        }
+        register!("set_alpha", |line: i64, alpha: f64| Command::Alpha(line as usize, alpha as f32));
+        register!("set_rotation", |line: i64, degrees: f64| Command::Rotation(line as usize, degrees as f32));
+        register!("set_position", |line: i64, x: f64, y: f64| Command::Position(line as usize, x as f32, y as f32));
+        register!("restore", |line: i64| Command::Restore(line as usize));
+        register!("spawn_particle", |x: f64, y: f64| Command::Particle(x as f32, y as f32));
+
+        let ast = engine.compile(source).map_err(|err| anyhow!("{err}"))?;
+        let has = |name: &str| ast.iter_functions().any(|it| it.name == name);
+        let (on_update, on_judge) = (has("on_update"), has("on_judge"));
+        let mut script = Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            commands,
+            saved: HashMap::new(),
+            on_update,
+            on_judge,
+            failed: false,
+        };
+        script.run_top_level()?;
+        Ok(script)
+    }
+
+    fn run_top_level(&mut self) -> Result<()> {
+        self.scope.clear();
+        self.engine.run_ast_with_scope(&mut self.scope, &self.ast).map_err(|err| anyhow!("{err}"))
+    }
+
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) {
+        if let Err(err) = self.engine.call_fn::<Dynamic>(&mut self.scope, &self.ast, name, args) {
+            warn!("chart script stopped: {err}");
+            self.failed = true;
+        }
+    }
+
+    pub fn has_judge_callback(&self) -> bool {
+        self.on_judge
+    }
+
+    // `time` is in chart time
+    pub fn update(&mut self, res: &mut Resource, lines: &mut [JudgeLine], time: f32, judged: &[(u32, u32, Judgement)]) {
+        if self.failed {
+            return;
+        }
+        if self.on_judge {
+            for (line, note, what) in judged {
+                let what = match what {
+                    Judgement::Perfect => "perfect",
+                    Judgement::Good => "good",
+                    Judgement::Bad => "bad",
+                    Judgement::Miss => "miss",
+                };
+                self.call("on_judge", (*line as i64, *note as i64, what.to_owned()));
+            }
+        }
+        if self.on_update {
+            self.call("on_update", (time as f64,));
+        }
+        let commands = std::mem::take(&mut *self.commands.lock().unwrap());
+        for command in commands {
+            self.apply(res, lines, command);
+        }
+    }
+
+    fn apply(&mut self, res: &mut Resource, lines: &mut [JudgeLine], command: Command) {
+        if let Command::Particle(x, y) = command {
+            let color = res.res_pack.info.fx_perfect();
+            res.with_model(Matrix::new_translation(&Vector::new(x, y / res.aspect_ratio)), |res| res.emit_at_origin(0., color, false));
+            return;
+        }
+        let id = match command {
+            Command::Alpha(id, _) | Command::Rotation(id, _) | Command::Position(id, ..) | Command::Restore(id) => id,
+            Command::Particle(..) => unreachable!(),
+        };
+        let Some(line) = lines.get_mut(id) else {
+            return;
+        };
+        let object = &mut line.object;
+        let saved = self.saved.entry(id).or_default();
+        match command {
+            Command::Alpha(_, alpha) => {
+                let old = std::mem::replace(&mut object.alpha, AnimFloat::fixed(alpha));
+                saved.alpha.get_or_insert(old);
+            }
+            Command::Rotation(_, degrees) => {
+                let old = std::mem::replace(&mut object.rotation, AnimFloat::fixed(degrees));
+                saved.rotation.get_or_insert(old);
+            }
+            Command::Position(_, x, y) => {
+                let old = std::mem::replace(&mut object.translation, AnimVector::fixed(Vector::new(x, y)));
+                saved.translation.get_or_insert(old);
+            }
+            Command::Restore(_) => {
+                if let Some(saved) = self.saved.remove(&id) {
+                    saved.restore(line);
+                }
+            }
+            Command::Particle(..) => unreachable!(),
+        }
+    }
+
+    pub fn reset(&mut self, lines: &mut [JudgeLine]) {
+        for (id, saved) in self.saved.drain() {
+            if let Some(line) = lines.get_mut(id) {
+                saved.restore(line);
+            }
+        }
+        self.commands.lock().unwrap().clear();
+        self.failed = false;
+        if let Err(err) = self.run_top_level() {
+            warn!("chart script stopped: {err}");
+            self.failed = true;
+        }
+    }
+}
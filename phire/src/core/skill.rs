@@ -0,0 +1,96 @@
+// a rough idea of what a chart asks of the player, for comparing charts rather than rating them
+
+use super::{Chart, NoteKind};
+use serde::{Deserialize, Serialize};
+
+// notes per second that count as the top of each scale
+const PEAK_NPS: f32 = 20.;
+const SUSTAINED_NPS: f32 = 10.;
+const FLICK_NPS: f32 = 2.;
+
+const BURST_WINDOW: f32 = 1.;
+const STAMINA_WINDOW: f32 = 15.;
+
+// all components are between 0 and 1
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SkillProfile {
+    pub speed: f32,
+    pub stamina: f32,
+    pub flick: f32,
+    pub rhythm: f32,
+}
+
+impl SkillProfile {
+    pub fn values(&self) -> [f32; 4] {
+        [self.speed, self.stamina, self.flick, self.rhythm]
+    }
+}
+
+// most notes within any `window` seconds
+fn peak_count(times: &[f32], window: f32) -> usize {
+    let mut start = 0;
+    let mut best = 0;
+    for (end, t) in times.iter().enumerate() {
+        while times[start] < t - window {
+            start += 1;
+        }
+        best = best.max(end + 1 - start);
+    }
+    best
+}
+
+// how unpredictable the gaps between notes are, from 0 for a steady stream up to 1
+fn interval_entropy(times: &[f32]) -> f32 {
+    let gaps: Vec<f32> = times.windows(2).map(|it| it[1] - it[0]).filter(|it| *it > 1e-3).collect();
+    if gaps.len() < 2 {
+        return 0.;
+    }
+    // changes from one gap to the next, in half octaves
+    let mut bins = std::collections::HashMap::new();
+    for pair in gaps.windows(2) {
+        *bins.entry(((pair[1] / pair[0]).log2() * 2.).round() as i32).or_insert(0u32) += 1;
+    }
+    let n = (gaps.len() - 1) as f32;
+    let entropy: f32 = bins.values().map(|c| *c as f32 / n).map(|p| -p * p.log2()).sum();
+    (entropy / 3.).min(1.)
+}
+
+// share of notes off the quarter beat grid, triplets and the like
+fn off_grid(chart: &Chart, times: &[f32]) -> Option<f32> {
+    let mut bpm_list = chart.bpm_list.borrow_mut();
+    bpm_list.ranges()?;
+    let off = times
+        .iter()
+        .filter(|t| {
+            let beat = bpm_list.beat(**t) * 4.;
+            (beat - beat.round()).abs() > 0.08
+        })
+        .count();
+    Some(off as f32 / times.len() as f32)
+}
+
+pub fn estimate_skills(chart: &Chart) -> SkillProfile {
+    let notes: Vec<_> = chart.lines.iter().flat_map(|it| it.notes.iter()).filter(|it| !it.fake).collect();
+    if notes.is_empty() {
+        return SkillProfile::default();
+    }
+    let mut times: Vec<f32> = notes.iter().map(|it| it.time).collect();
+    times.sort_by(f32::total_cmp);
+    let span = (times[times.len() - 1] - times[0]).max(1.);
+    let flicks = notes.iter().filter(|it| matches!(it.kind, NoteKind::Flick)).count();
+
+    let speed = peak_count(&times, BURST_WINDOW) as f32 / BURST_WINDOW / PEAK_NPS;
+    let stamina = peak_count(&times, STAMINA_WINDOW) as f32 / STAMINA_WINDOW.min(span) / SUSTAINED_NPS;
+    let flick = flicks as f32 / span / FLICK_NPS;
+    let entropy = interval_entropy(&times);
+    let rhythm = match off_grid(chart, &times) {
+        Some(off) => (entropy + (off * 3.).min(1.)) / 2.,
+        None => entropy,
+    };
+    SkillProfile {
+        speed: speed.min(1.),
+        stamina: stamina.min(1.),
+        flick: flick.min(1.),
+        rhythm,
+    }
+}
@@ -0,0 +1,438 @@
+// decodes a music file a packet at a time instead of all at once, so that only the compressed file and
+// the packet being played stay in memory. A 10 minute track decoded up front is a couple hundred MB.
+
+use crate::{fs::spawn_task, task::Task};
+use anyhow::{Context, Result};
+use sasa::{AudioClip, AudioManager, Frame, Music, MusicParams};
+use std::{io::Cursor, sync::Arc};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+    units::Time,
+};
+
+pub struct MusicStream {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    length: Option<f64>,
+    // decoded frames of the current packet that haven't been read yet
+    pending: Vec<Frame>,
+    pending_pos: usize,
+    // frames to drop after a seek, since seeking only lands on a packet boundary
+    skip: u64,
+    ended: bool,
+}
+
+impl MusicStream {
+    pub fn new(bytes: impl AsRef<[u8]> + Send + Sync + 'static) -> Result<Self> {
+        let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(&Hint::new(), source, &FormatOptions::default(), &MetadataOptions::default())
+            .context("Unsupported audio format")?;
+        let format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|it| it.codec_params.codec != CODEC_TYPE_NULL)
+            .context("No audio track")?;
+        let params = &track.codec_params;
+        let sample_rate = params.sample_rate.context("Unknown sample rate")?;
+        let length = params.n_frames.map(|it| it as f64 / sample_rate as f64);
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .context("Unsupported codec")?;
+        Ok(Self {
+            track_id: track.id,
+            format,
+            decoder,
+            sample_rate,
+            length,
+            pending: Vec::new(),
+            pending_pos: 0,
+            skip: 0,
+            ended: false,
+        })
+    }
+
+    // decodes only `begin..end` (in seconds) of the file
+    pub fn decode_range(bytes: impl AsRef<[u8]> + Send + Sync + 'static, begin: f32, end: f32) -> Result<(Vec<Frame>, u32)> {
+        let mut stream = Self::new(bytes)?;
+        stream.seek(begin as f64)?;
+        let sample_rate = stream.sample_rate;
+        let mut frames = vec![Frame(0., 0.); ((end - begin).max(0.) * sample_rate as f32) as usize];
+        let len = stream.read(&mut frames)?;
+        frames.truncate(len);
+        Ok((frames, sample_rate))
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // in seconds, `None` when the container doesn't tell
+    pub fn length(&self) -> Option<f64> {
+        self.length
+    }
+
+    pub fn ended(&self) -> bool {
+        self.ended
+    }
+
+    pub fn seek(&mut self, time: f64) -> Result<()> {
+        let seeked = self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: Time::from(time.max(0.)),
+                track_id: Some(self.track_id),
+            },
+        )?;
+        self.decoder.reset();
+        self.pending.clear();
+        self.pending_pos = 0;
+        self.skip = seeked.required_ts.saturating_sub(seeked.actual_ts);
+        self.ended = false;
+        Ok(())
+    }
+
+    // fills `out` as far as the stream goes and returns how many frames were written
+    pub fn read(&mut self, out: &mut [Frame]) -> Result<usize> {
+        let mut written = 0;
+        while written < out.len() {
+            if self.pending_pos == self.pending.len() {
+                if self.ended || !self.decode_next()? {
+                    break;
+                }
+                continue;
+            }
+            let n = (self.pending.len() - self.pending_pos).min(out.len() - written);
+            out[written..written + n].clone_from_slice(&self.pending[self.pending_pos..self.pending_pos + n]);
+            self.pending_pos += n;
+            written += n;
+        }
+        Ok(written)
+    }
+
+    // decodes the next packet of our track into `pending`, false at the end of the stream
+    fn decode_next(&mut self) -> Result<bool> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    self.ended = true;
+                    return Ok(false);
+                }
+                Err(err) => return Err(err.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // a broken packet is skipped, like a full decode would
+                Err(Error::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let spec = *decoded.spec();
+            let channels = spec.channels.count();
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            self.pending.clear();
+            self.pending_pos = 0;
+            self.pending.extend(buffer.samples().chunks_exact(channels).map(|it| match it {
+                [mono] => Frame(*mono, *mono),
+                [left, right, ..] => Frame(*left, *right),
+                [] => unreachable!(),
+            }));
+            let skip = self.skip.min(self.pending.len() as u64);
+            self.pending_pos = skip as usize;
+            self.skip -= skip;
+            if self.pending_pos < self.pending.len() {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+// seconds of music in each clip handed to the mixer, and how much of the next clip's beginning each one repeats
+// so that there's something to switch over on
+const CHUNK_SECS: f64 = 20.;
+const OVERLAP_SECS: f64 = 0.5;
+// the next clip takes over this long before the current one runs out
+const SWITCH_LEAD: f64 = 0.25;
+
+struct Chunk {
+    music: Music,
+    // where the clip starts in the track, and how long it is
+    start: f64,
+    length: f64,
+}
+
+struct Decoded {
+    stream: MusicStream,
+    frames: Vec<Frame>,
+    start: f64,
+}
+
+// a decoded chunk waiting to take over, with what's needed to decode the one after it
+struct Ready {
+    chunk: Chunk,
+    stream: MusicStream,
+    carry: Vec<Frame>,
+}
+
+// a jump out of the current chunk, decoded on a worker thread while the current chunk stays paused,
+// and whether to play once it's there
+struct Seek {
+    time: f64,
+    play: bool,
+    task: Task<Result<Decoded>>,
+}
+
+// plays a track through a `MusicStream`, a chunk at a time. Each chunk is an ordinary `Music`, the next one is
+// decoded on a worker thread and started where the two overlap once the current one gets close to its end
+pub struct StreamedMusic {
+    bytes: Arc<[u8]>,
+    sample_rate: u32,
+    amplifier: f32,
+    // `None` until the first chunk is decoded
+    current: Option<Chunk>,
+    next: Option<Ready>,
+    decoding: Option<Task<Result<Decoded>>>,
+    seek: Option<Seek>,
+}
+
+impl StreamedMusic {
+    // only probes the file, the first chunk is decoded in the background like a seek to the beginning
+    pub fn new(bytes: Arc<[u8]>, amplifier: f32) -> Result<Self> {
+        let stream = MusicStream::new(bytes.clone())?;
+        let sample_rate = stream.sample_rate();
+        Ok(Self {
+            bytes,
+            sample_rate,
+            amplifier,
+            current: None,
+            next: None,
+            decoding: None,
+            seek: Some(Seek {
+                time: 0.,
+                play: false,
+                task: Task::new(spawn_task(move || Self::decode(stream, Vec::new(), 0.))),
+            }),
+        })
+    }
+
+    // `carry` is the overlap the previous chunk ended with, the rest of the chunk comes from the stream
+    fn decode(mut stream: MusicStream, mut frames: Vec<Frame>, start: f64) -> Result<Decoded> {
+        let len = ((CHUNK_SECS + OVERLAP_SECS) * stream.sample_rate() as f64) as usize;
+        let carried = frames.len();
+        frames.resize(len.max(carried), Frame(0., 0.));
+        let read = stream.read(&mut frames[carried..])?;
+        frames.truncate(carried + read);
+        Ok(Decoded { stream, frames, start })
+    }
+
+    // the chunk, paused, and the overlap the chunk after it starts with
+    fn chunk(audio: &mut AudioManager, frames: Vec<Frame>, start: f64, sample_rate: u32, amplifier: f32) -> Result<(Chunk, Vec<Frame>)> {
+        let overlap = ((OVERLAP_SECS * sample_rate as f64) as usize).min(frames.len());
+        let carry = frames[frames.len() - overlap..].to_vec();
+        let length = frames.len() as f64 / sample_rate as f64;
+        let mut music = audio.create_music(
+            AudioClip::from_raw(frames, sample_rate),
+            MusicParams {
+                amplifier: amplifier as _,
+                ..Default::default()
+            },
+        )?;
+        music.pause()?;
+        Ok((Chunk { music, start, length }, carry))
+    }
+
+    // `end` is where the chunk now playing ends in the track
+    fn decode_next(&mut self, stream: MusicStream, carry: Vec<Frame>, end: f64) {
+        self.next = None;
+        self.decoding = None;
+        if stream.ended() {
+            return;
+        }
+        let start = end - carry.len() as f64 / self.sample_rate as f64;
+        self.decoding = Some(Task::new(spawn_task(move || Self::decode(stream, carry, start))));
+    }
+
+    pub fn update(&mut self, audio: &mut AudioManager) -> Result<()> {
+        if let Some(seek) = &mut self.seek {
+            let Some(decoded) = seek.task.take() else {
+                return Ok(());
+            };
+            let play = seek.play;
+            self.seek = None;
+            let Decoded { stream, frames, start } = decoded?;
+            let (mut current, carry) = Self::chunk(audio, frames, start, self.sample_rate, self.amplifier)?;
+            if play {
+                current.music.play()?;
+            }
+            let end = current.start + current.length;
+            self.current = Some(current);
+            self.decode_next(stream, carry, end);
+            return Ok(());
+        }
+        if let Some(decoded) = self.decoding.as_mut().and_then(|it| it.take()) {
+            self.decoding = None;
+            let Decoded { stream, frames, start } = decoded?;
+            // nothing past the overlap, the current chunk already plays to the end
+            if frames.len() as f64 / self.sample_rate as f64 > OVERLAP_SECS {
+                let (chunk, carry) = Self::chunk(audio, frames, start, self.sample_rate, self.amplifier)?;
+                self.next = Some(Ready { chunk, stream, carry });
+            }
+        }
+        let Some(current) = &mut self.current else {
+            return Ok(());
+        };
+        let pos = current.music.position();
+        if !current.music.paused() && pos >= current.length - SWITCH_LEAD {
+            if let Some(Ready { mut chunk, stream, carry }) = self.next.take() {
+                chunk.music.seek_to(current.start + pos - chunk.start)?;
+                chunk.music.play()?;
+                current.music.pause()?;
+                let end = chunk.start + chunk.length;
+                self.current = Some(chunk);
+                self.decode_next(stream, carry, end);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn play(&mut self) -> Result<()> {
+        if let Some(seek) = &mut self.seek {
+            seek.play = true;
+            return Ok(());
+        }
+        match &mut self.current {
+            Some(current) => current.music.play(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn pause(&mut self) -> Result<()> {
+        if let Some(seek) = &mut self.seek {
+            seek.play = false;
+        }
+        match &mut self.current {
+            Some(current) => current.music.pause(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        match &self.seek {
+            Some(seek) => !seek.play,
+            None => self.current.as_ref().map_or(true, |it| it.music.paused()),
+        }
+    }
+
+    pub fn position(&self) -> f64 {
+        match (&self.seek, &self.current) {
+            (Some(seek), _) => seek.time,
+            (None, Some(current)) => current.start + current.music.position(),
+            (None, None) => 0.,
+        }
+    }
+
+    pub fn seek_to(&mut self, time: f64) -> Result<()> {
+        let time = time.max(0.);
+        let playing = !self.paused();
+        if let Some(current) = &mut self.current {
+            if (current.start..current.start + current.length).contains(&time) {
+                self.seek = None;
+                current.music.seek_to(time - current.start)?;
+                return if playing { current.music.play() } else { current.music.pause() };
+            }
+            current.music.pause()?;
+        }
+        let bytes = self.bytes.clone();
+        self.seek = Some(Seek {
+            time,
+            play: playing,
+            task: Task::new(spawn_task(move || {
+                let mut stream = MusicStream::new(bytes)?;
+                stream.seek(time)?;
+                Self::decode(stream, Vec::new(), time)
+            })),
+        });
+        Ok(())
+    }
+
+    pub fn set_amplifier(&mut self, amplifier: f32) -> Result<()> {
+        self.amplifier = amplifier;
+        if let Some(next) = &mut self.next {
+            next.chunk.music.set_amplifier(amplifier)?;
+        }
+        match &mut self.current {
+            Some(current) => current.music.set_amplifier(amplifier),
+            None => Ok(()),
+        }
+    }
+}
+
+// the gameplay track, decoded up front when it has to be stretched or premixed, streamed otherwise
+pub enum MusicTrack {
+    Decoded(Music),
+    Streamed(StreamedMusic),
+}
+
+impl MusicTrack {
+    pub fn update(&mut self, audio: &mut AudioManager) -> Result<()> {
+        match self {
+            Self::Decoded(_) => Ok(()),
+            Self::Streamed(it) => it.update(audio),
+        }
+    }
+
+    pub fn play(&mut self) -> Result<()> {
+        match self {
+            Self::Decoded(it) => it.play(),
+            Self::Streamed(it) => it.play(),
+        }
+    }
+
+    pub fn pause(&mut self) -> Result<()> {
+        match self {
+            Self::Decoded(it) => it.pause(),
+            Self::Streamed(it) => it.pause(),
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        match self {
+            Self::Decoded(it) => it.paused(),
+            Self::Streamed(it) => it.paused(),
+        }
+    }
+
+    pub fn position(&self) -> f64 {
+        match self {
+            Self::Decoded(it) => it.position(),
+            Self::Streamed(it) => it.position(),
+        }
+    }
+
+    pub fn seek_to(&mut self, time: f64) -> Result<()> {
+        match self {
+            Self::Decoded(it) => it.seek_to(time),
+            Self::Streamed(it) => it.seek_to(time),
+        }
+    }
+
+    pub fn set_amplifier(&mut self, amplifier: f32) -> Result<()> {
+        match self {
+            Self::Decoded(it) => it.set_amplifier(amplifier),
+            Self::Streamed(it) => it.set_amplifier(amplifier),
+        }
+    }
+}
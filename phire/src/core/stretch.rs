@@ -0,0 +1,70 @@
+// WSOLA time stretching, so that the music can follow the chart speed without its pitch following along
+
+use sasa::{AudioClip, Frame};
+use std::ops::RangeInclusive;
+
+// other speeds are left to plain resampling, stretching any further costs too much memory
+pub const STRETCH_RANGE: RangeInclusive<f32> = 0.5..=2.0;
+
+const WINDOW_SECS: f32 = 0.03;
+// only every few samples are compared when looking for the best overlap
+const CORR_STRIDE: usize = 4;
+
+fn mono(frame: Frame) -> f32 {
+    frame.0 + frame.1
+}
+
+// the start near `nominal` whose first `len` frames resemble those at `target` the most
+fn best_offset(frames: &[Frame], target: usize, nominal: usize, tol: usize, len: usize) -> usize {
+    let len = len.min(frames.len().saturating_sub(target));
+    let lo = nominal.saturating_sub(tol);
+    let hi = (nominal + tol).min(frames.len().saturating_sub(len));
+    if len == 0 || lo > hi {
+        return nominal;
+    }
+    let mut best = (f32::NEG_INFINITY, nominal);
+    for start in lo..=hi {
+        let (mut corr, mut energy) = (0., 0.);
+        for i in (0..len).step_by(CORR_STRIDE) {
+            let x = mono(frames[start + i]);
+            corr += x * mono(frames[target + i]);
+            energy += x * x;
+        }
+        let score = corr / (energy.sqrt() + 1e-6);
+        if score > best.0 {
+            best = (score, start);
+        }
+    }
+    best.1
+}
+
+// the result is meant to be played back at `speed`: it lasts as long as the original, but holds 1 / speed
+// as many samples per second, so that the playback rate brings them back to the original pitch
+pub fn time_stretch(clip: &AudioClip, speed: f32) -> AudioClip {
+    let frames = clip.frames();
+    let sample_rate = clip.sample_rate();
+    if (speed - 1.).abs() < 1e-3 || !STRETCH_RANGE.contains(&speed) || frames.is_empty() {
+        return clip.clone();
+    }
+    let n = ((sample_rate as f32 * WINDOW_SECS) as usize).max(64) & !1;
+    let hs = n / 2;
+    let ha = hs as f32 * speed;
+    let tol = n / 8;
+    // a hann window at half overlap adds up to one
+    let window: Vec<f32> = (0..n).map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / n as f32).cos()).collect();
+    let out_len = (frames.len() as f32 / speed) as usize;
+    let mut out = vec![Frame(0., 0.); out_len + n];
+    let mut prev = 0;
+    for k in 0..(out_len + hs - 1) / hs {
+        let nominal = (k as f32 * ha) as usize;
+        // continue from where the last window would naturally go on
+        let pos = if k == 0 { 0 } else { best_offset(frames, prev + hs, nominal, tol, hs) };
+        for ((dst, src), w) in out[k * hs..].iter_mut().zip(frames[pos.min(frames.len())..].iter()).zip(&window) {
+            dst.0 += src.0 * w;
+            dst.1 += src.1 * w;
+        }
+        prev = pos;
+    }
+    out.truncate(out_len);
+    AudioClip::from_raw(out, (sample_rate as f32 / speed).round() as u32)
+}
@@ -1,4 +1,4 @@
-use macroquad::prelude::{vec2, Color, Rect, Vec2};
+use macroquad::prelude::{vec2, vec3, Color, Rect, Vec2, Vec3};
 use once_cell::sync::Lazy;
 use std::{any::Any, ops::Range, rc::Rc};
 
@@ -310,6 +310,16 @@ impl Tweenable for Vec2 {
     }
 }
 
+impl Tweenable for Vec3 {
+    fn tween(x: &Self, y: &Self, t: f32) -> Self {
+        vec3(f32::tween(&x.x, &y.x, t), f32::tween(&x.y, &y.y, t), f32::tween(&x.z, &y.z, t))
+    }
+
+    fn add(x: &Self, y: &Self) -> Self {
+        *x + *y
+    }
+}
+
 impl Tweenable for Color {
     fn tween(x: &Self, y: &Self, t: f32) -> Self {
         Self::new(f32::tween(&x.r, &y.r, t), f32::tween(&x.g, &y.g, t), f32::tween(&x.b, &y.b, t), f32::tween(&x.a, &y.a, t))
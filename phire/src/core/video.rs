@@ -1,4 +1,4 @@
-use super::{Anim, Resource};
+use super::{use_base_material, Anim, Resource};
 use crate::ext::{source_of_image, ScaleType};
 use anyhow::{Ok, Result};
 use macroquad::prelude::*;
@@ -7,6 +7,9 @@ use prpr_avc::AVPixelFormat;
 use std::{cell::RefCell, io::Write};
 use tempfile::NamedTempFile;
 
+// jumps further ahead than this are seeked instead of decoded frame by frame
+const MAX_CATCH_UP: f64 = 1.;
+
 thread_local! {
     static VIDEO_BUFFERS: RefCell<[Vec<u8>; 3]> = RefCell::default();
 }
@@ -21,6 +24,8 @@ pub struct Video {
     tex_v: Texture2D,
 
     start_time: f32,
+    // index of the line the video follows, full screen when None
+    pub attach: Option<usize>,
     scale_type: ScaleType,
     alpha: Anim<f32>,
     dim: Anim<f32>,
@@ -43,7 +48,7 @@ fn new_tex(w: u32, h: u32) -> Texture2D {
 }
 
 impl Video {
-    pub fn new(data: Vec<u8>, start_time: f32, scale_type: ScaleType, alpha: Anim<f32>, dim: Anim<f32>) -> Result<Self> {
+    pub fn new(data: Vec<u8>, start_time: f32, attach: Option<usize>, scale_type: ScaleType, alpha: Anim<f32>, dim: Anim<f32>) -> Result<Self> {
         let mut video_file = NamedTempFile::new()?;
         video_file.write_all(&data)?;
         drop(data);
@@ -79,6 +84,7 @@ impl Video {
             tex_v,
 
             start_time,
+            attach,
             scale_type,
             alpha,
             dim,
@@ -89,12 +95,19 @@ impl Video {
     }
 
     pub fn update(&mut self, t: f32) -> Result<()> {
-        if t < self.start_time || self.ended {
+        if t < self.start_time {
+            return Ok(());
+        }
+        let that_frame = ((t - self.start_time) as f64 / self.frame_delta) as usize;
+        // the music jumped somewhere without telling us
+        if that_frame + 1 < self.next_frame || (!self.ended && that_frame.saturating_sub(self.next_frame) as f64 * self.frame_delta > MAX_CATCH_UP) {
+            self.seek_to(t);
+        }
+        if self.ended {
             return Ok(());
         }
         self.alpha.set_time(t);
         self.dim.set_time(t);
-        let that_frame = ((t - self.start_time) as f64 / self.frame_delta) as usize;
         if self.next_frame <= that_frame {
             VIDEO_BUFFERS.with(|it| {
                 let mut buf = it.borrow_mut();
@@ -125,16 +138,37 @@ impl Video {
         Ok(())
     }
 
+    fn visible(&self, res: &Resource) -> bool {
+        res.time >= self.start_time && !self.ended
+    }
+
+    fn color(&self, alpha: f32) -> Color {
+        let dim = 1. - self.dim.now();
+        Color::new(dim, dim, dim, self.alpha.now_opt().unwrap_or(1.) * alpha)
+    }
+
     pub fn render(&self, res: &Resource) {
-        if res.time < self.start_time || self.ended {
+        if !self.visible(res) {
             return;
         }
-        gl_use_material(self.material);
         let top = 1. / res.aspect_ratio;
         let r = Rect::new(-1., -top, 2., top * 2.);
         let s = source_of_image(&self.tex_y, r, self.scale_type).unwrap_or_else(|| Rect::new(0., 0., 1., 1.));
-        let dim = 1. - self.dim.now();
-        let color = Color::new(dim, dim, dim, self.alpha.now_opt().unwrap_or(1.));
+        self.draw(r, s, self.color(1.));
+    }
+
+    // drawn in the space of the attached line, sized by pixels like texture lines
+    pub fn render_attached(&self, res: &Resource, alpha: f32) {
+        if !self.visible(res) {
+            return;
+        }
+        let (w, h) = (self.tex_y.width(), self.tex_y.height());
+        // line space is flipped vertically
+        self.draw(Rect::new(-w / 2., -h / 2., w, h), Rect::new(0., 1., 1., -1.), self.color(alpha));
+    }
+
+    fn draw(&self, r: Rect, s: Rect, color: Color) {
+        gl_use_material(self.material);
         let vertices = [
             Vertex::new(r.x, r.y, 0., s.x, s.y, color),
             Vertex::new(r.right(), r.y, 0., s.right(), s.y, color),
@@ -144,14 +178,19 @@ impl Video {
         let gl = unsafe { get_internal_gl() }.quad_gl;
         gl.draw_mode(DrawMode::Triangles);
         gl.geometry(&vertices, &[0, 2, 3, 0, 1, 3]);
-        gl_use_default_material();
+        use_base_material();
     }
 
-    pub fn reset(&mut self) -> Result<()> {
-        self.next_frame = 0;
+    // the frame shown at `t` is decoded by the next `update`
+    pub fn seek_to(&mut self, t: f32) {
+        let frame = ((t - self.start_time).max(0.) as f64 / self.frame_delta) as usize;
+        self.video.seek(frame as f64 * self.frame_delta);
+        self.next_frame = frame;
         self.ended = false;
-        self.video = prpr_avc::Video::open(self.video_file.path().as_os_str().to_str().unwrap(), AVPixelFormat::YUV420P)?;
-        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.seek_to(self.start_time);
     }
 }
 
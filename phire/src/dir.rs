@@ -41,6 +41,11 @@ impl Dir {
         Ok(res)
     }
 
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
     #[inline]
     pub fn create_dir_all(&self, p: impl AsRef<Path>) -> Result<()> {
         std::fs::create_dir_all(self.join(p)?)?;
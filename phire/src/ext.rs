@@ -1,7 +1,7 @@
 use crate::{
     config::Config,
     core::{Matrix, Point, Vector},
-    ui::Ui,
+    ui::{MemoryGuard, MemoryKind, Ui},
 };
 use anyhow::{anyhow, Result};
 use image::DynamicImage;
@@ -14,10 +14,10 @@ use miniquad::{gl::GLenum, BlendFactor, BlendState, BlendValue, CompareFunc, Equ
 use once_cell::sync::Lazy;
 use ordered_float::{Float, NotNan};
 use regex::Regex;
-use sasa::AudioManager;
+use sasa::{AudioClip, AudioManager, Frame, MusicParams};
 use serde::Deserialize;
 use std::{
-    collections::VecDeque, future::Future, ops::Deref, pin::Pin, sync::{Arc, Mutex}, task::{Poll, RawWaker, RawWakerVTable, Waker}
+    collections::VecDeque, future::Future, ops::Deref, pin::Pin, sync::{Arc, Mutex}, task::{Poll, RawWaker, RawWakerVTable, Waker}, time::{Duration, Instant}
 };
 use tracing::{debug, info_span};
 use lazy_static::lazy_static;
@@ -79,7 +79,7 @@ impl RectExt for Rect {
     }
 }
 
-struct SafeTextureInner(Texture2D);
+struct SafeTextureInner(Texture2D, MemoryGuard);
 impl Drop for SafeTextureInner {
     fn drop(&mut self) {
         self.0.delete()
@@ -91,6 +91,8 @@ impl SafeTexture {
     pub fn into_inner(self) -> Texture2D {
         let arc = self.0;
         let res = arc.0;
+        // no longer ours to count
+        arc.1.release();
         std::mem::forget(arc);
         res
     }
@@ -142,7 +144,9 @@ impl Eq for SafeTexture {}
 
 impl From<Texture2D> for SafeTexture {
     fn from(tex: Texture2D) -> Self {
-        Self(Arc::new(SafeTextureInner(tex)))
+        // mipmaps and the GPU's own padding aren't counted
+        let bytes = tex.width() as usize * tex.height() as usize * 4;
+        Self(Arc::new(SafeTextureInner(tex, MemoryGuard::new(MemoryKind::Texture, bytes))))
     }
 }
 
@@ -413,6 +417,16 @@ pub fn poll_future<R>(future: Pin<&mut (impl Future<Output = R> + ?Sized)>) -> O
     }
 }
 
+// whatever has been drawn to the screen so far this frame, right side up
+pub fn screenshot() -> image::RgbaImage {
+    let data = get_screen_data();
+    let mut image = image::RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes).unwrap();
+    for pixel in image.pixels_mut() {
+        pixel[3] = 255;
+    }
+    image::imageops::flip_vertical(&image)
+}
+
 pub fn screen_aspect() -> f32 {
     let vp = get_viewport();
     vp.2 as f32 / vp.3 as f32
@@ -529,6 +543,66 @@ pub fn open_url(url: &str) -> Result<()> {
     Ok(())
 }
 
+pub fn share_file(path: &str) -> Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "android")] {
+            unsafe {
+                let env = miniquad::native::attach_jni_env();
+                let ctx = ndk_context::android_context().context();
+                let class = (**env).GetObjectClass.unwrap()(env, ctx);
+                let method =
+                    (**env).GetMethodID.unwrap()(env, class, b"shareFile\0".as_ptr() as _, b"(Ljava/lang/String;)V\0".as_ptr() as _);
+                // activities built before sharing was added don't have the method, and the lookup leaves a
+                // NoSuchMethodError pending that has to be cleared before the next JNI call
+                if method.is_null() {
+                    (**env).ExceptionClear.unwrap()(env);
+                    anyhow::bail!("sharing isn't supported by this build");
+                }
+                let path = std::ffi::CString::new(path.to_owned()).unwrap();
+                (**env).CallVoidMethod.unwrap()(
+                    env,
+                    ctx,
+                    method,
+                    (**env).NewStringUTF.unwrap()(env, path.as_ptr()),
+                );
+                if (**env).ExceptionCheck.unwrap()(env) != 0 {
+                    (**env).ExceptionClear.unwrap()(env);
+                    anyhow::bail!("failed to share file");
+                }
+            }
+        } else if #[cfg(target_os = "ios")] {
+            unsafe {
+                use crate::objc::*;
+
+                let url: ObjcId = msg_send![class!(NSURL), fileURLWithPath: str_to_ns(path)];
+                let items: ObjcId = msg_send![class!(NSArray), arrayWithObject: url];
+                let ctrl: ObjcId = msg_send![class!(UIActivityViewController), alloc];
+                let ctrl: ObjcId = msg_send![ctrl, initWithActivityItems: items applicationActivities: 0 as ObjcId];
+                let view_ctrl = *miniquad::native::ios::VIEW_CTRL_OBJ.lock().unwrap() as ObjcId;
+                // iPad presents the share sheet as a popover, which needs an anchor
+                let popover: ObjcId = msg_send![ctrl, popoverPresentationController];
+                if !popover.is_null() {
+                    let view: ObjcId = msg_send![view_ctrl, view];
+                    let _: () = msg_send![popover, setSourceView: view];
+                }
+                let _: () = msg_send![
+                    view_ctrl,
+                    presentViewController: ctrl
+                    animated: runtime::YES
+                    completion: 0 as ObjcId
+                ];
+            }
+        } else {
+            let name = std::path::Path::new(path).file_name().map(|it| it.to_string_lossy().into_owned()).unwrap_or_default();
+            if let Some(dest) = rfd::FileDialog::new().set_file_name(name).save_file() {
+                std::fs::copy(path, dest)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 
 pub fn unzip_into<R: std::io::Read + std::io::Seek>(reader: R, dir: &crate::dir::Dir, strip_root: bool) -> Result<()> {
     let mut zip = zip::ZipArchive::new(reader)?;
@@ -650,6 +724,22 @@ pub fn get_latency(audio: &AudioManager, frame_times: &VecDeque<f64>) -> f64 {
     audio.estimate_latency().max(0.) + avg_frame_time
 }
 
+// plays a second of silence and reads back the output latency the backend reports,
+// the stream has to be running for the estimate to mean anything
+pub async fn probe_latency(config: &Config) -> Result<f64> {
+    let mut audio = create_audio_manger(config)?;
+    let clip = AudioClip::from_raw(vec![Frame(0., 0.); 44100], 44100);
+    let mut music = audio.create_music(clip, MusicParams::default())?;
+    music.play()?;
+    let start = get_time();
+    while get_time() - start < 0.5 {
+        next_frame().await;
+    }
+    let latency = audio.estimate_latency().max(0.);
+    music.pause()?;
+    Ok(latency)
+}
+
 pub fn push_frame_time(frame_times: &mut VecDeque<f64>, real_time: f64) {
     frame_times.push_back(real_time);
     while frame_times.front().is_some_and(|it| real_time - it > 1.0) {
@@ -657,6 +747,18 @@ pub fn push_frame_time(frame_times: &mut VecDeque<f64>, real_time: f64) {
     }
 }
 
+// `thread::sleep` tends to oversleep by a millisecond or so, so the last bit is spun out
+pub fn precise_sleep(duration: f64) {
+    const SPIN: f64 = 0.002;
+    let start = Instant::now();
+    if duration > SPIN {
+        std::thread::sleep(Duration::from_secs_f64(duration - SPIN));
+    }
+    while start.elapsed().as_secs_f64() < duration {
+        std::thread::yield_now();
+    }
+}
+
 pub fn round_to_step(value: f32, step: f32) -> f32 {
     let aligned = (value / step).round() * step;
     let digits = (-step.log10()).ceil() as i32;
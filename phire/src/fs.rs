@@ -11,8 +11,9 @@ use std::{
     collections::HashMap,
     fs,
     io::{Cursor, Read, Seek, Write},
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 use tracing::warn;
 use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
@@ -209,6 +210,106 @@ impl FileSystem for PatchedFileSystem {
     }
 }
 
+// several file systems on top of each other, files missing from one are looked up in the next
+pub struct LayeredFileSystem(pub Vec<Box<dyn FileSystem>>);
+
+#[async_trait]
+impl FileSystem for LayeredFileSystem {
+    async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        let mut last_err = None;
+        for layer in &mut self.0 {
+            match layer.load_file(path).await {
+                Ok(data) => return Ok(data),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no layers")))
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        for layer in &mut self.0 {
+            if layer.exists(path).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn list_root(&self) -> Result<Vec<String>> {
+        let mut res = Vec::new();
+        for layer in &self.0 {
+            res.extend(layer.list_root()?);
+        }
+        res.sort();
+        res.dedup();
+        Ok(res)
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(Self(self.0.iter().map(|it| it.clone_box()).collect()))
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// polls modification time of files under a directory, used for hot reloading charts
+pub struct FileWatcher {
+    root: PathBuf,
+    files: Vec<(String, Option<SystemTime>)>,
+    last_check: Instant,
+}
+
+impl FileWatcher {
+    const INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: Vec::new(),
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn from_fs(fs: &mut dyn FileSystem) -> Option<Self> {
+        fs.as_any()
+            .downcast_ref::<ExternalFileSystem>()
+            .map(|it| Self::new(it.0.path()))
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(self.root.join(path)).and_then(|it| it.modified()).ok()
+    }
+
+    pub fn watch(&mut self, path: impl Into<String>) {
+        let path = path.into();
+        if path.is_empty() || self.files.iter().any(|it| it.0 == path) {
+            return;
+        }
+        let time = self.modified(&path);
+        self.files.push((path, time));
+    }
+
+    // returns files changed since last poll
+    pub fn poll(&mut self) -> Vec<String> {
+        if self.last_check.elapsed() < Self::INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+        let mut changed = Vec::new();
+        for index in 0..self.files.len() {
+            let time = self.modified(&self.files[index].0);
+            let (path, last) = &mut self.files[index];
+            if time != *last {
+                *last = time;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
 pub async fn spawn_task<R: Send + 'static>(f: impl FnOnce() -> Result<R> + Send + 'static) -> Result<R> {
     #[cfg(target_arch = "wasm32")]
     {
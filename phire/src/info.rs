@@ -11,6 +11,13 @@ pub enum ChartFormat {
     Pbc = 3,
 }
 
+// a named part of the song, `time` is in music time
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChartSection {
+    pub name: String,
+    pub time: f32,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
@@ -34,11 +41,18 @@ pub struct ChartInfo {
     pub preview_end: Option<f32>,
     pub aspect_ratio: f32,
     pub force_aspect_ratio: bool,
+    // what fills the bars a forced aspect ratio leaves: this image, or else the illustration, blurred by
+    // `letterbox_blur` pixels when set. Dimmed bars if neither is given
+    pub letterbox: Option<String>,
+    pub letterbox_blur: Option<f32>,
     pub background_dim: f32,
+    // overrides `bg_blurriness` from the config for this chart
+    pub background_blur: Option<f32>,
     pub line_length: f32,
     pub offset: f32,
     pub tip: Option<String>,
     pub tags: Vec<String>,
+    pub sections: Vec<ChartSection>,
 
     pub intro: String,
 
@@ -75,11 +89,15 @@ impl Default for ChartInfo {
             preview_end: None,
             aspect_ratio: 16. / 9.,
             force_aspect_ratio: false,
+            letterbox: None,
+            letterbox_blur: None,
             background_dim: 0.1,
+            background_blur: None,
             line_length: 6.,
             offset: 0.,
             tip: None,
             tags: Vec::new(),
+            sections: Vec::new(),
 
             intro: String::new(),
 
@@ -1,6 +1,6 @@
 use crate::{
     config::Config,
-    core::{BadNote, Chart, Note, NoteKind, Point, Resource, Vector, NOTE_WIDTH_RATIO_BASE},
+    core::{BadNote, Chart, HitsoundBus, Note, NoteKind, Point, Resource, Vector},
     ext::{get_viewport, NotNanExt},
 };
 use macroquad::prelude::{
@@ -10,13 +10,19 @@ use macroquad::prelude::{
 use miniquad::{EventHandler, MouseButton};
 use once_cell::sync::Lazy;
 use sasa::{PlaySfxParams, Sfx};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, num::FpCategory};
 
+pub mod hit;
+
+pub mod rules;
+use rules::NoteClass;
+
+pub mod trace;
+
+pub mod vector;
+
 pub const FLICK_SPEED_THRESHOLD: f32 = 0.8;
-pub const LIMIT_PERFECT: f32 = 0.08;
-pub const LIMIT_GOOD: f32 = 0.18;
-pub const LIMIT_BAD: f32 = 0.22;
 pub const UP_TOLERANCE: f32 = 0.05;
 pub const DIST_FACTOR: f32 = 0.2;
 const LATE_OFFSET: f32 = 0.13;
@@ -30,6 +36,15 @@ pub fn play_sfx(sfx: &mut Sfx, config: &Config) {
     });
 }
 
+fn play_hitsound(sfx: &mut Sfx, config: &Config, bus: &mut HitsoundBus, gain: f32) {
+    if config.volume_sfx <= 1e-2 {
+        return;
+    }
+    if let Some(amplifier) = bus.admit(get_time(), config.volume_sfx * gain) {
+        let _ = sfx.play(PlaySfxParams { amplifier });
+    }
+}
+
 #[cfg(all(not(target_os = "windows"), not(target_os = "ios")))]
 fn get_uptime() -> f64 {
     let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
@@ -47,7 +62,7 @@ fn get_uptime() -> f64 {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HitSound {
     None,
     Click,
@@ -56,21 +71,68 @@ pub enum HitSound {
     Custom(String),
 }
 
+// per-note hitsound volume and pitch, the pitch being a playback rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitSoundTune {
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+impl Default for HitSoundTune {
+    fn default() -> Self {
+        Self { volume: 1., pitch: 1. }
+    }
+}
+
+impl HitSoundTune {
+    // resampled clips are shared between notes whose pitch rounds to the same cent
+    pub fn cents(&self) -> i32 {
+        (self.pitch.clamp(0.25, 4.).log2() * 1200.).round() as i32
+    }
+}
+
 impl HitSound {
-    pub fn play(&self, res: &mut Resource) {
+    pub fn play(&self, res: &mut Resource, tune: HitSoundTune) {
+        let gain = res.hitsound_gain * tune.volume;
+        let cents = tune.cents();
+        let bus = &mut res.hitsound_bus;
+        if cents != 0 {
+            if let Some(sfx) = res.pitched_sfxs.get_mut(self).and_then(|it| it.get_mut(&cents)) {
+                play_hitsound(sfx, &res.config, bus, gain);
+                return;
+            }
+        }
         match self {
             HitSound::None => {}
-            HitSound::Click => play_sfx(&mut res.sfx_click, &res.config),
-            HitSound::Flick => play_sfx(&mut res.sfx_flick, &res.config),
-            HitSound::Drag => play_sfx(&mut res.sfx_drag, &res.config),
+            HitSound::Click => play_hitsound(&mut res.sfx_click, &res.config, bus, gain),
+            HitSound::Flick => play_hitsound(&mut res.sfx_flick, &res.config, bus, gain),
+            HitSound::Drag => play_hitsound(&mut res.sfx_drag, &res.config, bus, gain),
             HitSound::Custom(s) => {
                 if let Some(sfx) = res.extra_sfxs.get_mut(s) {
-                    play_sfx(sfx, &res.config);
+                    play_hitsound(sfx, &res.config, bus, gain);
                 }
             }
         }
     }
 
+    // a good plays the respack's click_good in place of the click, and a bad only makes a sound
+    // when the respack has a click_bad
+    pub fn play_judged(&self, res: &mut Resource, tune: HitSoundTune, judgement: Judgement) {
+        let variant = match (self, judgement) {
+            (_, Judgement::Miss) => return,
+            (HitSound::Click, Judgement::Good) => res.sfx_click_good.as_mut(),
+            (_, Judgement::Bad) => match res.sfx_click_bad.as_mut() {
+                Some(sfx) => Some(sfx),
+                None => return,
+            },
+            _ => None,
+        };
+        match variant {
+            Some(sfx) => play_hitsound(sfx, &res.config, &mut res.hitsound_bus, res.hitsound_gain * tune.volume),
+            None => self.play(res, tune),
+        }
+    }
+
     pub fn default_from_kind(kind: &NoteKind) -> Self {
         match kind {
             NoteKind::Click => HitSound::Click,
@@ -129,7 +191,7 @@ impl FlickTracker {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum JudgeStatus {
     NotJudged,
     PreJudge,
@@ -138,7 +200,7 @@ pub enum JudgeStatus {
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Judgement {
     Perfect,
     Good,
@@ -197,23 +259,15 @@ impl JudgeInner {
     }
 
     pub fn accuracy(&self) -> f64 {
-        (self.counts[0] as f64 + self.counts[1] as f64 * 0.65) / self.num_of_notes as f64
+        rules::accuracy(self.counts, self.num_of_notes)
     }
 
     pub fn real_time_accuracy(&self) -> f64 {
-        let cnt = self.counts.iter().sum::<u32>();
-        if cnt == 0 {
-            return 1.;
-        }
-        (self.counts[0] as f64 + self.counts[1] as f64 * 0.65) / cnt as f64
+        rules::real_time_accuracy(self.counts)
     }
 
     pub fn score(&self) -> f64 {
-        if self.counts[0] == self.num_of_notes {
-            1_000_000.0
-        } else {
-            (0.9 * self.accuracy() + self.max_combo as f64 / self.num_of_notes as f64 * 0.1) * 1_000_000.
-        }
+        rules::score(self.counts, self.max_combo, self.num_of_notes)
     }
 
     pub fn result(&self) -> PlayResult {
@@ -226,7 +280,7 @@ impl JudgeInner {
             counts: self.counts,
             early,
             late: self.diffs.len() as u32 - early,
-            std: 0.,
+            ..Default::default()
         }
     }
 
@@ -256,11 +310,23 @@ pub struct Judge {
 
     pub(crate) inner: JudgeInner,
     pub judgements: RefCell<Vec<(f32, u32, u32, Result<Judgement, bool>)>>,
+    // hit deltas (in seconds) of timed notes, negative means early
+    pub deltas: Vec<f32>,
+    // consecutive bads and misses, and when the last one happened
+    miss_streak: (u32, f32),
+    // line, note and result of every judgement, only collected when something consumes them
+    pub judged: Option<Vec<(u32, u32, Judgement)>>,
 }
 
 static SUBSCRIBER_ID: Lazy<usize> = Lazy::new(register_input_subscriber);
 thread_local! {
     static TOUCHES: RefCell<(Vec<Touch>, i32, u32)> = RefCell::default();
+    static INJECTED: RefCell<Vec<Touch>> = RefCell::default();
+}
+
+// a touch in screen pixels, delivered along with the real input on the next frame, for driving the UI from scripts
+pub fn inject_touch(touch: Touch) {
+    INJECTED.with(|it| it.borrow_mut().push(touch));
 }
 
 impl Judge {
@@ -274,6 +340,10 @@ impl Judge {
                 (idx, 0)
             })
             .collect();
+        #[cfg(feature = "script")]
+        let judged = chart.extra.script.as_ref().filter(|it| it.has_judge_callback()).map(|_| Vec::new());
+        #[cfg(not(feature = "script"))]
+        let judged = None;
         Self {
             notes,
             trackers: HashMap::new(),
@@ -283,6 +353,9 @@ impl Judge {
 
             inner: JudgeInner::new(chart.lines.iter().map(|it| it.notes.iter().filter(|it| !it.fake).count() as u32).sum()),
             judgements: RefCell::new(Vec::new()),
+            deltas: Vec::new(),
+            miss_streak: (0, f32::NEG_INFINITY),
+            judged,
         }
     }
 
@@ -291,11 +364,42 @@ impl Judge {
         self.trackers.clear();
         self.inner.reset();
         self.judgements.borrow_mut().clear();
+        self.deltas.clear();
+        self.miss_streak = (0, f32::NEG_INFINITY);
+        if let Some(judged) = &mut self.judged {
+            judged.clear();
+        }
+    }
+
+    // follows Chart::rewind, only the progress is rewound and the counts are kept
+    pub fn rewind(&mut self, chart: &Chart, t: f32) {
+        for (line, (idx, st)) in chart.lines.iter().zip(self.notes.iter_mut()) {
+            *st = idx
+                .iter()
+                .position(|id| !matches!(line.notes[*id as usize].judge, JudgeStatus::Judged))
+                .unwrap_or(idx.len());
+        }
+        self.trackers.clear();
+        self.judgements.borrow_mut().retain(|it| it.0 < t);
+        self.last_time = self.last_time.min(t);
     }
 
     pub fn commit(&mut self, t: f32, what: Judgement, line_id: u32, note_id: u32, diff: f32) {
         self.judgements.borrow_mut().push((t, line_id, note_id, Ok(what)));
+        if let Some(judged) = &mut self.judged {
+            judged.push((line_id, note_id, what));
+        }
         self.inner.commit(what, diff);
+        if matches!(what, Judgement::Bad | Judgement::Miss) {
+            self.miss_streak = (self.miss_streak.0 + 1, t);
+        } else {
+            self.miss_streak.0 = 0;
+        }
+    }
+
+    #[inline]
+    pub fn miss_streak(&self) -> (u32, f32) {
+        self.miss_streak
     }
 
     #[inline]
@@ -317,6 +421,7 @@ impl Judge {
         let mut handler = Handler(Vec::new(), 0, 0);
         repeat_all_miniquad_input(&mut handler, *SUBSCRIBER_ID);
         handler.finalize();
+        handler.0.extend(INJECTED.with(|it| std::mem::take(&mut *it.borrow_mut())));
         TOUCHES.with(|it| {
             *it.borrow_mut() = (handler.0, handler.1, handler.2);
         });
@@ -369,17 +474,13 @@ impl Judge {
             self.auto_play_update(res, chart);
             return;
         }
-        let x_diff_max: f32 = if res.config.full_scrrn_judge() {
-            2. / res.config.chart_ratio
-        } else {
-            0.21 / (16. / 9.) * 2.
-        };
+        let windows = res.config.judge_windows();
         let spd = res.config.speed;
 
         #[cfg(not(target_os = "windows"))]
         let uptime = get_uptime();
 
-        let t = res.time;
+        let t = res.time - res.config.touch_offset;
         // TODO optimize
         let mut touches: HashMap<u64, Touch> = {
             let mut touches = touches();
@@ -410,6 +511,9 @@ impl Judge {
                     time: f64::NEG_INFINITY,
                 });
             }
+            if res.config.keyboard_lanes {
+                touches.extend(lane_touches(&res.config.key_lanes));
+            }
             let tr = Self::touch_transform(res.config.flip_x(), res.config.chart_ratio, angle);
             touches
                 .into_iter()
@@ -424,6 +528,8 @@ impl Judge {
             (guard.0.clone(), guard.2)
         });
         self.key_down_count = self.key_down_count.saturating_add_signed(TOUCHES.with(|it| it.borrow().1));
+        // with lanes, keys act as touches instead of hitting the nearest note
+        let (keys_down, key_down_count) = if res.config.keyboard_lanes { (0, 0) } else { (keys_down, self.key_down_count) };
         {
             fn to_local(Vec2 { x, y }: Vec2) -> Point {
                 Point::new(x / screen_width() * 2. - 1., y / screen_height() * 2. - 1.)
@@ -512,283 +618,36 @@ impl Judge {
                 touch.time as f32
             }
         };
-        let mut judgements = Vec::new();
-        // clicks & flicks
-        for (id, touch) in touches.iter().enumerate() {
-            let click = touch.phase == TouchPhase::Started;
-            let flick =
-                matches!(touch.phase, TouchPhase::Moved | TouchPhase::Stationary) && self.trackers.get_mut(&touch.id).map_or(false, |it| it.flicked);
-            if !(click || flick) {
-                continue;
-            }
-            let t = time_of(touch);
-            let mut closest = (None, x_diff_max, LIMIT_BAD, LIMIT_BAD + (x_diff_max / NOTE_WIDTH_RATIO_BASE - 1.).max(0.) * DIST_FACTOR, 0.);
-            for (line_id, ((line, pos), (idx, st))) in chart.lines.iter_mut().zip(pos.iter()).zip(self.notes.iter_mut()).enumerate() {
-                let Some(pos) = pos[id] else { continue; };
-                for id in &idx[*st..] {
-                    let note = &mut line.notes[*id as usize];
-                    if !matches!(note.judge, JudgeStatus::NotJudged | JudgeStatus::PreJudge) {
-                        continue;
-                    }
-                    if !click && matches!(note.kind, NoteKind::Click | NoteKind::Hold { .. }) {
-                        continue;
-                    }
-                    let dt = (note.time - t) / spd;
-                    if dt.abs() >= closest.3.abs() {
-                        break;
-                    }
-                    // let dt = if dt < 0. { (dt + EARLY_OFFSET).min(0.).abs() } else { dt };
-                    let x = &mut note.object.translation.0;
-                    x.set_time(t);
-                    let posx = pos.x;
-                    let dist = (x.now() - posx).abs();
-                    if dist > (x_diff_max - NOTE_WIDTH_RATIO_BASE) + NOTE_WIDTH_RATIO_BASE * note.judge_scale {
-                        continue;
-                    }
-                    if dt.abs() >
-                        if matches!(note.kind, NoteKind::Click) {
-                            LIMIT_BAD // LIMIT_BAD - LIMIT_PERFECT * (dist - 0.9).max(0.)
-                        } else {
-                            LIMIT_GOOD
-                        }
-                    {
-                        continue;
-                    }
-                    let dist_key = if res.config.full_scrrn_judge() {
-                        (dist / NOTE_WIDTH_RATIO_BASE - 1.).max(0.) * 0.01
-                    } else {
-                        (dist / NOTE_WIDTH_RATIO_BASE - 1.).max(0.) * DIST_FACTOR
-                    };
-                    let key = if matches!(note.kind, NoteKind::Flick | NoteKind::Drag) { // Low Priority
-                        dt.abs() + LIMIT_BAD
-                    } else if dt < -LIMIT_GOOD { // Prevent Late Bad
-                        dt.abs()
-                    } else if dt < 0.0 {
-                        (dt + LATE_OFFSET).min(0.0).abs() // Protect Late Good
-                    } else {
-                        dt.abs()
-                    };
-                    let key = key + dist_key;
-                    if key < closest.3 {
-                        closest = (Some((line_id, *id)), dist, dt, key, posx);
-                    }
-                }
-            }
-            if let (Some((line_id, id)), _, dt, _, posx) = closest {
-                let can_protect_note = |note: &mut Note| {
-                    let x = &mut note.object.translation.0;
-                    x.set_time(t);
-                    let judge_time = t - note.time;
-                    matches!(note.kind, NoteKind::Drag | NoteKind::Flick)
-                        && judge_time >= -LIMIT_GOOD
-                        && judge_time <= LIMIT_BAD
-                        && (x.now() - posx).abs() <= (x_diff_max - NOTE_WIDTH_RATIO_BASE) + NOTE_WIDTH_RATIO_BASE * note.judge_scale // note_dist <= x_diff_max
-                        && !note.protected
-                        && !note.fake
-                };
-                let lines = &mut chart.lines;
-                if matches!(lines[line_id].notes[id as usize].kind, NoteKind::Drag) {
-                    // debug!("reject by drag");
-                    continue;
-                }
-                if click {
-                    if dt > LIMIT_PERFECT {
-                        let mut any = false;
-                        lines.iter_mut()
-                            .flat_map(|line| line.notes.iter_mut())
-                            .for_each(|note| {
-                                if can_protect_note(note) {
-                                    note.protected = true;
-                                    any = true;
-                                }
-                            });
-                        if any {
-                            continue;
-                        }
-                    }
-                    // click & hold
-                    let note = &mut lines[line_id].notes[id as usize];
-                    let dt = dt.abs();
-                    if matches!(note.kind, NoteKind::Flick) {
-                        // debug!("reject by flick");
-                        continue; // to next loop
-                    }
-                    if dt <= LIMIT_GOOD || matches!(note.kind, NoteKind::Hold { .. }) {
-                        match note.kind {
-                            NoteKind::Click => {
-                                note.judge = JudgeStatus::Judged;
-                                judgements.push((if dt <= LIMIT_PERFECT { Judgement::Perfect } else { Judgement::Good }, line_id, id, Some(t)));
-                            }
-                            NoteKind::Hold { .. } => {
-                                play_sfx(&mut res.sfx_click, &res.config);
-                                self.judgements.borrow_mut().push((t, line_id as _, id, Err(dt <= LIMIT_PERFECT)));
-                                note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, t, false, f32::INFINITY);
-                            }
-                            _ => unreachable!(),
-                        };
-                    } else {
-                        // prevent extra judgements
-                        if matches!(note.judge, JudgeStatus::NotJudged) {
-                            // keep the note after bad judgement
-                            note.judge = JudgeStatus::PreJudge;
-                            judgements.push((Judgement::Bad, line_id, id, None));
-                        }
-                    }
-                } else {
-                    // flick
-                    lines[line_id].notes[id as usize].judge = JudgeStatus::PreJudge;
-                    if let Some(tracker) = self.trackers.get_mut(&touch.id) {
-                        tracker.flicked = false;
-                    }
-                }
-            }
-        }
-        for _ in 0..keys_down {
-            // find the earliest not judged click / hold note
-            if let Some((line_id, id)) = chart
-                .lines
-                .iter()
-                .zip(self.notes.iter())
-                .enumerate()
-                .filter_map(|(line_id, (line, (idx, st)))| {
-                    idx[*st..]
-                        .iter()
-                        .cloned()
-                        .find(|id| {
-                            let note = &line.notes[*id as usize];
-                            matches!(note.judge, JudgeStatus::NotJudged) && matches!(note.kind, NoteKind::Click | NoteKind::Hold { .. })
-                        })
-                        .map(|id| (line_id, id))
-                })
-                .min_by_key(|(line_id, id)| chart.lines[*line_id].notes[*id as usize].time.not_nan())
-            {
-                let note = &mut chart.lines[line_id].notes[id as usize];
-                let dt = (t - note.time).abs() / spd;
-                if dt <= if matches!(note.kind, NoteKind::Click) { LIMIT_BAD } else { LIMIT_GOOD } {
-                    match note.kind {
-                        NoteKind::Click => {
-                            note.judge = JudgeStatus::Judged;
-                            judgements.push((
-                                if dt <= LIMIT_PERFECT {
-                                    Judgement::Perfect
-                                } else if dt <= LIMIT_GOOD {
-                                    Judgement::Good
-                                } else {
-                                    Judgement::Bad
-                                },
-                                line_id,
-                                id,
-                                None,
-                            ));
-                        }
-                        NoteKind::Hold { .. } => {
-                            note.hitsound.play(res);
-                            self.judgements.borrow_mut().push((t, line_id as _, id, Err(dt <= LIMIT_PERFECT)));
-                            note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, (t - note.time) / spd, false, f32::INFINITY);
-                        }
-                        _ => unreachable!(),
-                    };
-                }
+        let params = hit::HitParams {
+            windows,
+            reach: rules::Reach::new(&res.config),
+            spd,
+            hold_leniency: res.config.hold_release_leniency,
+            hold_no_penalty: res.config.hold_no_release_penalty,
+            keys_down,
+            key_down_count,
+        };
+        let touches: Vec<_> = touches
+            .iter()
+            .map(|touch| hit::HitTouch {
+                id: touch.id,
+                phase: touch.phase,
+                time: time_of(touch),
+            })
+            .collect();
+        let mut lines: Vec<&mut [Note]> = chart.lines.iter_mut().map(|it| &mut it.notes[..]).collect();
+        let frame = hit::hit_frame(&params, t, &mut lines, &mut self.notes, &touches, &pos, &mut self.trackers);
+        for hold in frame.holds {
+            let judgement = if hold.perfect { Judgement::Perfect } else { Judgement::Good };
+            if hold.key {
+                let note = &chart.lines[hold.line].notes[hold.note as usize];
+                note.hitsound.play_judged(res, note.hitsound_tune, judgement);
             } else {
-                break;
-            }
-        }
-        for (line_id, ((line, pos), (idx, st))) in chart.lines.iter_mut().zip(pos.iter()).zip(self.notes.iter()).enumerate() {
-            line.object.set_time(t);
-            for id in &idx[*st..] {
-                let note = &mut line.notes[*id as usize];
-                let x_diff_max = (x_diff_max - NOTE_WIDTH_RATIO_BASE) + NOTE_WIDTH_RATIO_BASE * note.judge_scale;
-                if let NoteKind::Hold { end_time, .. } = &note.kind {
-                    if let JudgeStatus::Hold(.., ref mut pre_judge, ref mut up_time) = note.judge {
-                        if (*end_time - t) / spd <= LIMIT_BAD {
-                            *pre_judge = true;
-                            continue;
-                        }
-                        let x = &mut note.object.translation.0;
-                        x.set_time(t);
-                        let x = x.now();
-                        if self.key_down_count == 0 && !pos.iter().any(|it| it.map_or(false, |it| (it.x - x).abs() <= x_diff_max)) {
-                            if t > *up_time + UP_TOLERANCE {
-                                note.judge = JudgeStatus::Judged;
-                                judgements.push((Judgement::Miss, line_id, *id, None));
-                            } else if up_time.is_infinite() {
-                                *up_time = t;
-                            }
-                        } else {
-                            *up_time = f32::INFINITY;
-                        }
-                        continue;
-                    }
-                }
-                if !matches!(note.judge, JudgeStatus::NotJudged) {
-                    continue;
-                }
-                // process miss
-                let dt = (t - note.time) / spd;
-                if dt > LIMIT_BAD {
-                    note.judge = JudgeStatus::Judged;
-                    judgements.push((Judgement::Miss, line_id, *id, None));
-                    continue;
-                }
-                if -dt > LIMIT_BAD {
-                    break;
-                }
-                if !matches!(note.kind, NoteKind::Drag) && (self.key_down_count == 0 || !matches!(note.kind, NoteKind::Flick)) {
-                    continue;
-                }
-                let dt = dt.abs();
-                let x = &mut note.object.translation.0;
-                x.set_time(t);
-                let x = x.now();
-                if self.key_down_count != 0
-                    || pos.iter().any(|it| {
-                        it.map_or(false, |it| {
-                            let dx = (it.x - x).abs();
-                            dx <= x_diff_max && dt <= (LIMIT_BAD - LIMIT_PERFECT * (dx - 0.9).max(0.))
-                        })
-                    })
-                {
-                    note.judge = JudgeStatus::PreJudge;
-                }
+                HitSound::Click.play_judged(res, HitSoundTune::default(), judgement);
             }
+            self.judgements.borrow_mut().push((hold.time, hold.line as _, hold.note, Err(hold.perfect)));
         }
-        // process pre-judge
-        for (line_id, (line, (idx, st))) in chart.lines.iter_mut().zip(self.notes.iter()).enumerate() {
-            line.object.set_time(t);
-            for id in &idx[*st..] {
-                let note = &mut line.notes[*id as usize];
-                if let JudgeStatus::Hold(perfect, .., diff, true, _) = note.judge {
-                    if let NoteKind::Hold { end_time, .. } = &note.kind {
-                        if *end_time <= t {
-                            note.judge = JudgeStatus::Judged;
-                            judgements.push((if perfect { Judgement::Perfect } else { Judgement::Good }, line_id, *id, Some(diff)));
-                            continue;
-                        }
-                    }
-                }
-                // TODO adjust
-                let ghost_t = t + LIMIT_GOOD;
-                if matches!(note.kind, NoteKind::Click) {
-                    if ghost_t < note.time {
-                        break;
-                    }
-                } else if t < note.time {
-                    continue;
-                }
-                if matches!(note.judge, JudgeStatus::PreJudge) {
-                    let diff = if let JudgeStatus::Hold(.., diff, _, _) = note.judge {
-                        Some(diff)
-                    } else {
-                        None
-                    };
-                    note.judge = JudgeStatus::Judged;
-                    if !matches!(note.kind, NoteKind::Click) {
-                        judgements.push((Judgement::Perfect, line_id, *id, diff));
-                    }
-                }
-            }
-        }
-        for (judgement, line_id, id, diff) in judgements {
+        for (judgement, line_id, id, diff) in frame.judgements {
             let line = &mut chart.lines[line_id];
             let note = &mut line.notes[id as usize];
             line.object.set_time(t);
@@ -796,19 +655,16 @@ impl Judge {
             let line = &chart.lines[line_id];
             let note = &line.notes[id as usize];
             let line_tr = line.now_transform(res, &chart.lines);
-            self.commit(
-                t,
-                judgement,
-                line_id as _,
-                id,
-                if matches!(judgement, Judgement::Miss) {
-                    0.25
-                } else if matches!(note.kind, NoteKind::Drag | NoteKind::Flick) {
-                    0.
-                } else {
-                    (diff.unwrap_or(t) - note.time) / spd
-                },
-            );
+            let diff = if matches!(judgement, Judgement::Miss) {
+                0.25
+            } else if matches!(note.kind, NoteKind::Drag | NoteKind::Flick) {
+                0.
+            } else {
+                let diff = (diff.unwrap_or(t) - note.time) / spd;
+                self.deltas.push(diff);
+                diff
+            };
+            self.commit(t, judgement, line_id as _, id, diff);
             if matches!(note.kind, NoteKind::Hold { .. }) {
                 continue;
             }
@@ -819,7 +675,7 @@ impl Judge {
                     } else {
                         res.res_pack.info.fx_perfect()
                     };
-                    res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), color));
+                    res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), color, false));
                     true
                 }
                 Judgement::Good => {
@@ -828,7 +684,7 @@ impl Judge {
                     } else {
                         res.res_pack.info.fx_good()
                     };
-                    res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), color));
+                    res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), color, true));
                     true
                 }
                 Judgement::Bad => {
@@ -853,35 +709,36 @@ impl Judge {
                             },
                         });
                     }
-                    false
+                    true
                 }
                 _ => false,
             } {
-                note.hitsound.play(res);
-            }
-        }
-        for (line, (idx, st)) in chart.lines.iter().zip(self.notes.iter_mut()) {
-            while idx
-                .get(*st)
-                .map_or(false, |id| matches!(line.notes[*id as usize].judge, JudgeStatus::Judged))
-            {
-                *st += 1;
+                note.hitsound.play_judged(res, note.hitsound_tune, judgement);
             }
         }
         self.last_time = t / spd;
     }
 
-    fn auto_play_update(&mut self, res: &mut Resource, chart: &mut Chart) {
-        let t = res.time - res.config.judge_offset;
-        let (judge_type, judge_type_hold, judge_time, fx_color) = if res.config.all_bad {
-            (Judgement::Bad, Judgement::Good, LIMIT_BAD, Color::new(0., 0., 0., 0.))
-        } else if res.config.all_good {
-            (Judgement::Good, Judgement::Good, LIMIT_GOOD, res.res_pack.info.fx_good())
+    // hits whatever is due like autoplay does regardless of the config, for replaying notes that were already judged
+    pub fn replay_update(&mut self, res: &mut Resource, chart: &mut Chart) {
+        self.auto_play_update(res, chart);
+    }
+
+    // the part of autoplay that needs neither the screen nor audio: marks and commits whatever is due at `t`,
+    // returns the notes committed and the holds started
+    pub fn auto_play_judge(&mut self, chart: &mut Chart, t: f32, config: &Config) -> (Vec<(usize, u32)>, Vec<(usize, u32)>) {
+        let windows = config.judge_windows();
+        let (all_good, all_bad) = (config.all_good, config.all_bad);
+        let judge_time = if all_bad {
+            windows.bad
+        } else if all_good {
+            windows.good
         } else {
-            (Judgement::Perfect, Judgement::Perfect, 0., res.res_pack.info.fx_perfect())
+            0.
         };
-        //let spd = res.config.speed;
+        //let spd = config.speed;
         let mut judgements = Vec::new();
+        let mut holds = Vec::new();
         for (line_id, (line, (idx, st))) in chart.lines.iter_mut().zip(self.notes.iter_mut()).enumerate() {
             for id in &idx[*st..] {
                 let note = &mut line.notes[*id as usize];
@@ -901,9 +758,7 @@ impl Judge {
                     break;
                 }
                 note.judge = if matches!(note.kind, NoteKind::Hold { .. }) {
-                    if note.time >= res.config.play_start_time && !res.disable_hit_fx {
-                        note.hitsound.play(res);
-                    }
+                    holds.push((line_id, *id));
                     self.judgements.borrow_mut().push((t, line_id as _, *id, Err(true)));
                     // AutoPlay 无需输出打击时间差
                     // JudgeStatus::Hold(true, t, (t - note.time) / spd, false, f32::INFINITY)
@@ -920,6 +775,31 @@ impl Judge {
                 *st += 1;
             }
         }
+        for &(line_id, id) in &judgements {
+            let class = NoteClass::from(&chart.lines[line_id].notes[id as usize].kind);
+            self.commit(t, rules::autoplay_judgement(class, all_good, all_bad), line_id as _, id, 0.);
+        }
+        (judgements, holds)
+    }
+
+    fn auto_play_update(&mut self, res: &mut Resource, chart: &mut Chart) {
+        let t = res.time - res.config.judge_offset;
+        let (all_good, all_bad) = (res.config.all_good, res.config.all_bad);
+        let judge_type = rules::autoplay_judgement(NoteClass::Click, all_good, all_bad);
+        let fx_color = if all_bad {
+            Color::new(0., 0., 0., 0.)
+        } else if all_good {
+            res.res_pack.info.fx_good()
+        } else {
+            res.res_pack.info.fx_perfect()
+        };
+        let (judgements, holds) = self.auto_play_judge(chart, t, &res.config);
+        for (line_id, id) in holds {
+            let note = &chart.lines[line_id].notes[id as usize];
+            if note.time >= res.config.play_start_time && !res.disable_hit_fx && !res.hitsounds_premixed {
+                note.hitsound.play(res, note.hitsound_tune);
+            }
+        }
         for (line_id, id) in judgements.into_iter() {
             let note_transform = {
                 let line = &mut chart.lines[line_id];
@@ -938,31 +818,29 @@ impl Judge {
                     } else {
                         fx_color
                     };
-                    self.commit(t, judge_type, line_id as _, id, 0.);
                     if note.time >= res.config.play_start_time && !res.disable_hit_fx {
                         res.with_model(line.now_transform(res, &chart.lines) * note_transform, |res| {
-                            res.emit_at_origin(line.notes[id as usize].rotation(line), color)
+                            res.emit_at_origin(line.notes[id as usize].rotation(line), color, all_good)
                         });
-                        if !res.config.all_bad {
-                            note.hitsound.play(res)
+                        if !res.hitsounds_premixed {
+                            note.hitsound.play_judged(res, note.hitsound_tune, judge_type)
                         }
                     }
                 }
-                NoteKind::Hold { .. } => {
-                    self.commit(t, judge_type_hold, line_id as _, id, 0.);
-                }
+                NoteKind::Hold { .. } => {}
                 _ => {
                     let color = if let Some(color) = note.hit_fx_color.now_opt() {
                         color
                     } else {
                         res.res_pack.info.fx_perfect()
                     };
-                    self.commit(t, Judgement::Perfect, line_id as _, id, 0.);
                     if note.time >= res.config.play_start_time && !res.disable_hit_fx {
                         res.with_model(line.now_transform(res, &chart.lines) * note_transform, |res| {
-                            res.emit_at_origin(line.notes[id as usize].rotation(line), color)
+                            res.emit_at_origin(line.notes[id as usize].rotation(line), color, false)
                         });
-                        note.hitsound.play(res)
+                        if !res.hitsounds_premixed {
+                            note.hitsound.play(res, note.hitsound_tune)
+                        }
                     }
                 },
             };
@@ -978,9 +856,16 @@ impl Judge {
         }
     }
 
-    #[inline]
     pub fn result(&self) -> PlayResult {
-        self.inner.result()
+        let mut result = self.inner.result();
+        if !self.deltas.is_empty() {
+            let n = self.deltas.len() as f32;
+            let mean = self.deltas.iter().sum::<f32>() / n;
+            result.mean = mean;
+            result.std = (self.deltas.iter().map(|it| (it - mean).powi(2)).sum::<f32>() / n).sqrt();
+        }
+        result.deltas = self.deltas.clone();
+        result
     }
 
     #[inline]
@@ -992,6 +877,16 @@ impl Judge {
     pub fn counts(&self) -> [u32; 4] {
         self.inner.counts()
     }
+
+    // whether anything short of a perfect has been given so far
+    pub fn flawed(&self) -> bool {
+        self.counts()[1..].iter().any(|it| *it != 0)
+    }
+
+    // early and late hits that fell outside the perfect window
+    pub fn early_late(&self, perfect: f32) -> (u32, u32) {
+        self.deltas.iter().filter(|it| it.abs() > perfect).fold((0, 0), |(early, late), it| if *it < 0. { (early + 1, late) } else { (early, late + 1) })
+    }
 }
 
 struct Handler(Vec<Touch>, i32, u32);
@@ -1008,6 +903,91 @@ impl Handler {
     }
 }
 
+// keyboard lanes get the ids right below the mouse buttons
+const LANE_ID_BASE: u64 = u64::MAX - 16;
+
+fn key_of(c: char) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match c.to_ascii_uppercase() {
+        'A' => A,
+        'B' => B,
+        'C' => C,
+        'D' => D,
+        'E' => E,
+        'F' => F,
+        'G' => G,
+        'H' => H,
+        'I' => I,
+        'J' => J,
+        'K' => K,
+        'L' => L,
+        'M' => M,
+        'N' => N,
+        'O' => O,
+        'P' => P,
+        'Q' => Q,
+        'R' => R,
+        'S' => S,
+        'T' => T,
+        'U' => U,
+        'V' => V,
+        'W' => W,
+        'X' => X,
+        'Y' => Y,
+        'Z' => Z,
+        '0' => Key0,
+        '1' => Key1,
+        '2' => Key2,
+        '3' => Key3,
+        '4' => Key4,
+        '5' => Key5,
+        '6' => Key6,
+        '7' => Key7,
+        '8' => Key8,
+        '9' => Key9,
+        ' ' => Space,
+        ';' => Semicolon,
+        ',' => Comma,
+        '.' => Period,
+        '/' => Slash,
+        '[' => LeftBracket,
+        ']' => RightBracket,
+        '\'' => Apostrophe,
+        _ => return None,
+    })
+}
+
+// each key owns an equal vertical strip of the screen, left to right
+pub fn key_lanes(lanes: &str) -> Vec<(char, KeyCode)> {
+    lanes.chars().filter_map(|c| key_of(c).map(|key| (c.to_ascii_uppercase(), key))).collect()
+}
+
+fn lane_touches(lanes: &str) -> Vec<Touch> {
+    let lanes = key_lanes(lanes);
+    let n = lanes.len() as f32;
+    lanes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, (_, key))| {
+            let phase = if is_key_pressed(key) {
+                TouchPhase::Started
+            } else if is_key_down(key) {
+                TouchPhase::Stationary
+            } else if is_key_released(key) {
+                TouchPhase::Ended
+            } else {
+                return None;
+            };
+            Some(Touch {
+                id: LANE_ID_BASE - i as u64,
+                phase,
+                position: vec2(screen_width() * (i as f32 + 0.5) / n, screen_height() / 2.),
+                time: f64::NEG_INFINITY,
+            })
+        })
+        .collect()
+}
+
 fn button_to_id(button: MouseButton) -> u64 {
     u64::MAX
         - match button {
@@ -1069,7 +1049,11 @@ pub struct PlayResult {
     pub counts: [u32; 4],
     pub early: u32,
     pub late: u32,
+    pub mean: f32,
     pub std: f32,
+    pub deltas: Vec<f32>,
+    // cut short by SUDDEN_DEATH
+    pub failed: bool,
 }
 
 pub fn icon_index(score: u32, full_combo: bool) -> usize {
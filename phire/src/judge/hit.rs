@@ -0,0 +1,349 @@
+// the part of the judge that decides hits from touches, shared by `Judge::update` and the test vector runner
+// everything tied to the screen, audio and effects stays with the caller, this only sees line space and times
+
+use super::{rules, FlickTracker, JudgeStatus, Judgement};
+use crate::{
+    config::JudgeWindows,
+    core::{Note, NoteKind, Point},
+};
+use macroquad::prelude::TouchPhase;
+use rules::{NoteClass, Reach};
+use std::collections::HashMap;
+
+// what the hit logic reads and writes of a note
+pub trait HitNote {
+    fn class(&self) -> NoteClass;
+    fn time(&self) -> f32;
+    fn end_time(&self) -> Option<f32>;
+    fn judge_scale(&self) -> f32;
+    fn fake(&self) -> bool;
+    // x in the space of its line at `t`
+    fn x_at(&mut self, t: f32) -> f32;
+    fn status(&mut self) -> &mut JudgeStatus;
+    fn protected(&mut self) -> &mut bool;
+}
+
+impl HitNote for Note {
+    fn class(&self) -> NoteClass {
+        NoteClass::from(&self.kind)
+    }
+
+    fn time(&self) -> f32 {
+        self.time
+    }
+
+    fn end_time(&self) -> Option<f32> {
+        match self.kind {
+            NoteKind::Hold { end_time, .. } => Some(end_time),
+            _ => None,
+        }
+    }
+
+    fn judge_scale(&self) -> f32 {
+        self.judge_scale
+    }
+
+    fn fake(&self) -> bool {
+        self.fake
+    }
+
+    fn x_at(&mut self, t: f32) -> f32 {
+        let x = &mut self.object.translation.0;
+        x.set_time(t);
+        x.now()
+    }
+
+    fn status(&mut self) -> &mut JudgeStatus {
+        &mut self.judge
+    }
+
+    fn protected(&mut self) -> &mut bool {
+        &mut self.protected
+    }
+}
+
+pub struct HitParams {
+    pub windows: JudgeWindows,
+    pub reach: Reach,
+    pub spd: f32,
+    pub hold_leniency: f32,
+    pub hold_no_penalty: bool,
+    // keys pressed this frame, and keys held down, both zero with keyboard lanes
+    pub keys_down: u32,
+    pub key_down_count: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct HitTouch {
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub time: f32,
+}
+
+// a hold that was just started, `key` when it was by a key press rather than a touch
+pub struct HoldStart {
+    pub line: usize,
+    pub note: u32,
+    pub time: f32,
+    pub perfect: bool,
+    pub key: bool,
+}
+
+#[derive(Default)]
+pub struct HitFrame {
+    // judgement, line, note and the hit time when it's known, in the order they're committed
+    pub judgements: Vec<(Judgement, usize, u32, Option<f32>)>,
+    pub holds: Vec<HoldStart>,
+}
+
+// one frame of judging at `t`; `pending` holds the unjudged notes of each line sorted by time, from the index
+// after it, and `pos[line][touch]` where each of `touches` is in line space
+pub fn hit_frame<N: HitNote>(
+    params: &HitParams,
+    t: f32,
+    lines: &mut [&mut [N]],
+    pending: &mut [(Vec<u32>, usize)],
+    touches: &[HitTouch],
+    pos: &[Vec<Option<Point>>],
+    trackers: &mut HashMap<u64, FlickTracker>,
+) -> HitFrame {
+    let HitParams {
+        windows,
+        reach,
+        spd,
+        hold_leniency,
+        hold_no_penalty,
+        keys_down,
+        key_down_count,
+    } = *params;
+    let JudgeWindows {
+        perfect: limit_perfect,
+        good: limit_good,
+        bad: limit_bad,
+    } = windows;
+    let mut frame = HitFrame::default();
+    // clicks & flicks
+    for (id, touch) in touches.iter().enumerate() {
+        let click = touch.phase == TouchPhase::Started;
+        let flick = matches!(touch.phase, TouchPhase::Moved | TouchPhase::Stationary) && trackers.get(&touch.id).map_or(false, |it| it.flicked);
+        if !(click || flick) {
+            continue;
+        }
+        let t = touch.time;
+        let mut closest = (None, reach.max(), limit_bad, rules::max_priority(reach.max(), &windows), 0.);
+        for (line_id, ((line, pos), (idx, st))) in lines.iter_mut().zip(pos.iter()).zip(pending.iter()).enumerate() {
+            let Some(pos) = pos[id] else { continue; };
+            for id in &idx[*st..] {
+                let note = &mut line[*id as usize];
+                if !matches!(note.status(), JudgeStatus::NotJudged | JudgeStatus::PreJudge) {
+                    continue;
+                }
+                let class = note.class();
+                if !click && matches!(class, NoteClass::Click | NoteClass::Hold) {
+                    continue;
+                }
+                let dt = (note.time() - t) / spd;
+                if dt.abs() >= closest.3.abs() {
+                    break;
+                }
+                let posx = pos.x;
+                let dist = (note.x_at(t) - posx).abs();
+                if dist > reach.width(class, note.judge_scale()) {
+                    continue;
+                }
+                if dt.abs() > rules::hit_limit(class, &windows) {
+                    continue;
+                }
+                let key = rules::tap_priority(class, dt, dist, &windows, reach.is_full_screen(class));
+                if key < closest.3 {
+                    closest = (Some((line_id, *id)), dist, dt, key, posx);
+                }
+            }
+        }
+        let (Some((line_id, id)), _, dt, _, posx) = closest else {
+            continue;
+        };
+        let class = lines[line_id][id as usize].class();
+        if class == NoteClass::Drag {
+            // debug!("reject by drag");
+            continue;
+        }
+        if !click {
+            // flick
+            *lines[line_id][id as usize].status() = JudgeStatus::PreJudge;
+            if let Some(tracker) = trackers.get_mut(&touch.id) {
+                tracker.flicked = false;
+            }
+            continue;
+        }
+        if dt > limit_perfect {
+            let mut any = false;
+            for note in lines.iter_mut().flat_map(|line| line.iter_mut()) {
+                let class = note.class();
+                let protect = rules::can_protect(class, t - note.time(), (note.x_at(t) - posx).abs(), reach.width(class, note.judge_scale()), &windows)
+                    && !*note.protected()
+                    && !note.fake();
+                if protect {
+                    *note.protected() = true;
+                    any = true;
+                }
+            }
+            if any {
+                continue;
+            }
+        }
+        // click & hold
+        if class == NoteClass::Flick {
+            // debug!("reject by flick");
+            continue;
+        }
+        let note = &mut lines[line_id][id as usize];
+        let dt = dt.abs();
+        if dt <= limit_good || class == NoteClass::Hold {
+            if class == NoteClass::Hold {
+                frame.holds.push(HoldStart {
+                    line: line_id,
+                    note: id,
+                    time: t,
+                    perfect: dt <= limit_perfect,
+                    key: false,
+                });
+                *note.status() = JudgeStatus::Hold(dt <= limit_perfect, t, t, false, f32::INFINITY);
+            } else {
+                *note.status() = JudgeStatus::Judged;
+                frame.judgements.push((rules::tap_judgement(dt, &windows), line_id, id, Some(t)));
+            }
+        } else if matches!(note.status(), JudgeStatus::NotJudged) {
+            // prevent extra judgements, and keep the note after bad judgement
+            *note.status() = JudgeStatus::PreJudge;
+            frame.judgements.push((Judgement::Bad, line_id, id, None));
+        }
+    }
+    for _ in 0..keys_down {
+        // find the earliest not judged click / hold note
+        let earliest = lines
+            .iter_mut()
+            .zip(pending.iter())
+            .enumerate()
+            .filter_map(|(line_id, (line, (idx, st)))| {
+                idx[*st..]
+                    .iter()
+                    .cloned()
+                    .find(|id| {
+                        let note = &mut line[*id as usize];
+                        matches!(note.class(), NoteClass::Click | NoteClass::Hold) && matches!(note.status(), JudgeStatus::NotJudged)
+                    })
+                    .map(|id| (line_id, id, line[id as usize].time()))
+            })
+            .min_by(|x, y| x.2.total_cmp(&y.2));
+        let Some((line_id, id, time)) = earliest else {
+            break;
+        };
+        let note = &mut lines[line_id][id as usize];
+        let class = note.class();
+        let dt = (t - time).abs() / spd;
+        if dt > rules::hit_limit(class, &windows) {
+            continue;
+        }
+        if class == NoteClass::Hold {
+            frame.holds.push(HoldStart {
+                line: line_id,
+                note: id,
+                time: t,
+                perfect: dt <= limit_perfect,
+                key: true,
+            });
+            *note.status() = JudgeStatus::Hold(dt <= limit_perfect, t, (t - time) / spd, false, f32::INFINITY);
+        } else {
+            *note.status() = JudgeStatus::Judged;
+            frame.judgements.push((rules::tap_judgement(dt, &windows), line_id, id, None));
+        }
+    }
+    for (line_id, ((line, pos), (idx, st))) in lines.iter_mut().zip(pos.iter()).zip(pending.iter()).enumerate() {
+        for id in &idx[*st..] {
+            let note = &mut line[*id as usize];
+            let class = note.class();
+            let x_diff_max = reach.width(class, note.judge_scale());
+            if let (Some(end_time), JudgeStatus::Hold(.., pre_judge, up_time)) = (note.end_time(), note.status().clone()) {
+                if rules::hold_settled(end_time, t, spd, &windows, hold_leniency) {
+                    set_hold(note.status(), true, up_time);
+                    continue;
+                }
+                let x = note.x_at(t);
+                if key_down_count == 0 && !pos.iter().any(|it| it.map_or(false, |it| (it.x - x).abs() <= x_diff_max)) {
+                    if rules::hold_released(t, up_time, hold_no_penalty) {
+                        *note.status() = JudgeStatus::Judged;
+                        frame.judgements.push((Judgement::Miss, line_id, *id, None));
+                    } else if up_time.is_infinite() {
+                        set_hold(note.status(), pre_judge, t);
+                    }
+                } else {
+                    set_hold(note.status(), pre_judge, f32::INFINITY);
+                }
+                continue;
+            }
+            if !matches!(note.status(), JudgeStatus::NotJudged) {
+                continue;
+            }
+            // process miss
+            let dt = (t - note.time()) / spd;
+            if rules::is_miss(-dt, &windows) {
+                *note.status() = JudgeStatus::Judged;
+                frame.judgements.push((Judgement::Miss, line_id, *id, None));
+                continue;
+            }
+            if -dt > limit_bad {
+                break;
+            }
+            if class != NoteClass::Drag && (key_down_count == 0 || class != NoteClass::Flick) {
+                continue;
+            }
+            let x = note.x_at(t);
+            if key_down_count != 0 || pos.iter().any(|it| it.map_or(false, |it| rules::drag_catches((it.x - x).abs(), dt, x_diff_max, &windows))) {
+                *note.status() = JudgeStatus::PreJudge;
+            }
+        }
+    }
+    // process pre-judge
+    for (line_id, (line, (idx, st))) in lines.iter_mut().zip(pending.iter()).enumerate() {
+        for id in &idx[*st..] {
+            let note = &mut line[*id as usize];
+            if let (JudgeStatus::Hold(perfect, .., diff, true, _), Some(end_time)) = (note.status().clone(), note.end_time()) {
+                if end_time <= t {
+                    *note.status() = JudgeStatus::Judged;
+                    frame.judgements.push((if perfect { Judgement::Perfect } else { Judgement::Good }, line_id, *id, Some(diff)));
+                    continue;
+                }
+            }
+            // TODO adjust
+            let ghost_t = t + limit_good;
+            let class = note.class();
+            if class == NoteClass::Click {
+                if ghost_t < note.time() {
+                    break;
+                }
+            } else if t < note.time() {
+                continue;
+            }
+            if matches!(note.status(), JudgeStatus::PreJudge) {
+                *note.status() = JudgeStatus::Judged;
+                if class != NoteClass::Click {
+                    frame.judgements.push((Judgement::Perfect, line_id, *id, None));
+                }
+            }
+        }
+    }
+    for (line, (idx, st)) in lines.iter_mut().zip(pending.iter_mut()) {
+        while idx.get(*st).map_or(false, |id| matches!(line[*id as usize].status(), JudgeStatus::Judged)) {
+            *st += 1;
+        }
+    }
+    frame
+}
+
+fn set_hold(status: &mut JudgeStatus, new_pre_judge: bool, new_up_time: f32) {
+    if let JudgeStatus::Hold(.., pre_judge, up_time) = status {
+        *pre_judge = new_pre_judge;
+        *up_time = new_up_time;
+    }
+}
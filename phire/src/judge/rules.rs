@@ -0,0 +1,219 @@
+// the decisions the judge makes, as plain functions of times and distances
+// `dt` is always note time minus hit time, already divided by the playback speed
+
+use super::{Judgement, DIST_FACTOR, LATE_OFFSET, UP_TOLERANCE};
+use crate::{
+    config::{Config, FullScreenNotes, JudgeWindows},
+    core::{NoteKind, NOTE_WIDTH_RATIO_BASE},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NoteClass {
+    Click,
+    Drag,
+    Flick,
+    Hold,
+}
+
+impl From<&NoteKind> for NoteClass {
+    fn from(kind: &NoteKind) -> Self {
+        match kind {
+            NoteKind::Click => Self::Click,
+            NoteKind::Drag => Self::Drag,
+            NoteKind::Flick => Self::Flick,
+            NoteKind::Hold { .. } => Self::Hold,
+        }
+    }
+}
+
+// horizontal reach of a touch, in line space
+pub fn x_diff_max(full_screen: bool, chart_ratio: f32) -> f32 {
+    if full_screen {
+        2. / chart_ratio
+    } else {
+        0.21 / (16. / 9.) * 2.
+    }
+}
+
+pub fn judge_width(x_diff_max: f32, judge_scale: f32) -> f32 {
+    (x_diff_max - NOTE_WIDTH_RATIO_BASE) + NOTE_WIDTH_RATIO_BASE * judge_scale
+}
+
+impl From<NoteClass> for FullScreenNotes {
+    fn from(class: NoteClass) -> Self {
+        match class {
+            NoteClass::Click => Self::TAP,
+            NoteClass::Drag => Self::DRAG,
+            NoteClass::Flick => Self::FLICK,
+            NoteClass::Hold => Self::HOLD,
+        }
+    }
+}
+
+// `x_diff_max` and `judge_width` with the player's hit area settings applied
+#[derive(Clone, Copy)]
+pub struct Reach {
+    x_diff_max: f32,
+    full_x_diff_max: f32,
+    scale: f32,
+    full_screen: FullScreenNotes,
+}
+
+impl Reach {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            x_diff_max: x_diff_max(false, config.chart_ratio),
+            full_x_diff_max: x_diff_max(true, config.chart_ratio),
+            scale: config.hit_area_scale.max(0.1),
+            full_screen: if config.full_scrrn_judge() {
+                FullScreenNotes::all()
+            } else {
+                config.full_screen_notes
+            },
+        }
+    }
+
+    // plain reach with every note class full screen or none of them, as test vectors describe it
+    pub fn fixed(full_screen: bool, chart_ratio: f32) -> Self {
+        Self {
+            x_diff_max: x_diff_max(false, chart_ratio),
+            full_x_diff_max: x_diff_max(true, chart_ratio),
+            scale: 1.,
+            full_screen: if full_screen { FullScreenNotes::all() } else { FullScreenNotes::empty() },
+        }
+    }
+
+    pub fn is_full_screen(&self, class: NoteClass) -> bool {
+        self.full_screen.contains(class.into())
+    }
+
+    pub fn width(&self, class: NoteClass, judge_scale: f32) -> f32 {
+        if self.is_full_screen(class) {
+            judge_width(self.full_x_diff_max, judge_scale)
+        } else {
+            judge_width(self.x_diff_max, judge_scale) * self.scale
+        }
+    }
+
+    // the widest reach of any note class, at the default judge scale
+    pub fn max(&self) -> f32 {
+        if self.full_screen.is_empty() {
+            self.x_diff_max * self.scale
+        } else {
+            self.full_x_diff_max
+        }
+    }
+}
+
+// taps further off than this from a note of the class don't hit it at all
+pub fn hit_limit(class: NoteClass, w: &JudgeWindows) -> f32 {
+    if class == NoteClass::Click {
+        w.bad
+    } else {
+        w.good
+    }
+}
+
+pub fn tap_judgement(dt: f32, w: &JudgeWindows) -> Judgement {
+    let dt = dt.abs();
+    if dt <= w.perfect {
+        Judgement::Perfect
+    } else if dt <= w.good {
+        Judgement::Good
+    } else {
+        Judgement::Bad
+    }
+}
+
+// upper bound for `tap_priority`, anything at or above it is out of reach
+pub fn max_priority(x_diff_max: f32, w: &JudgeWindows) -> f32 {
+    w.bad + (x_diff_max / NOTE_WIDTH_RATIO_BASE - 1.).max(0.) * DIST_FACTOR
+}
+
+// lower wins when a tap could hit several notes
+pub fn tap_priority(class: NoteClass, dt: f32, dist: f32, w: &JudgeWindows, full_screen: bool) -> f32 {
+    let dist_key = (dist / NOTE_WIDTH_RATIO_BASE - 1.).max(0.) * if full_screen { 0.01 } else { DIST_FACTOR };
+    let key = if matches!(class, NoteClass::Flick | NoteClass::Drag) {
+        // low priority
+        dt.abs() + w.bad
+    } else if dt < -w.good {
+        // prevent late bad
+        dt.abs()
+    } else if dt < 0. {
+        // protect late good
+        (dt + LATE_OFFSET).min(0.).abs()
+    } else {
+        dt.abs()
+    };
+    key + dist_key
+}
+
+// a tap that would be early on a click can be spent on a nearby drag or flick instead; `judge_time` is hit time minus note time
+pub fn can_protect(class: NoteClass, judge_time: f32, dx: f32, width: f32, w: &JudgeWindows) -> bool {
+    matches!(class, NoteClass::Drag | NoteClass::Flick) && judge_time >= -w.good && judge_time <= w.bad && dx <= width
+}
+
+pub fn drag_catches(dx: f32, dt: f32, width: f32, w: &JudgeWindows) -> bool {
+    dx <= width && dt.abs() <= (w.bad - w.perfect * (dx - 0.9).max(0.))
+}
+
+pub fn is_miss(dt: f32, w: &JudgeWindows) -> bool {
+    -dt > w.bad
+}
+
+// holds are decided once their end is this close, releasing afterwards is fine
+// `leniency` moves that point earlier, in seconds of real time
+pub fn hold_settled(end_time: f32, t: f32, spd: f32, w: &JudgeWindows, leniency: f32) -> bool {
+    (end_time - t) / spd <= w.bad + leniency.max(0.)
+}
+
+// with `no_penalty` a started hold is never lost to letting go
+pub fn hold_released(t: f32, up_time: f32, no_penalty: bool) -> bool {
+    !no_penalty && t > up_time + UP_TOLERANCE
+}
+
+// what autoplay gives a note, the `all_good` and `all_bad` debug options included
+pub fn autoplay_judgement(class: NoteClass, all_good: bool, all_bad: bool) -> Judgement {
+    match class {
+        NoteClass::Click if all_bad => Judgement::Bad,
+        NoteClass::Click | NoteClass::Hold if all_bad || all_good => Judgement::Good,
+        _ => Judgement::Perfect,
+    }
+}
+
+// counts and max combo of a sequence of judgements
+pub fn tally(judgements: impl IntoIterator<Item = Judgement>) -> ([u32; 4], u32) {
+    let (mut counts, mut combo, mut max_combo) = ([0; 4], 0, 0);
+    for judgement in judgements {
+        counts[judgement as usize] += 1;
+        if matches!(judgement, Judgement::Perfect | Judgement::Good) {
+            combo += 1;
+            max_combo = max_combo.max(combo);
+        } else {
+            combo = 0;
+        }
+    }
+    (counts, max_combo)
+}
+
+pub fn accuracy(counts: [u32; 4], num_of_notes: u32) -> f64 {
+    (counts[0] as f64 + counts[1] as f64 * 0.65) / num_of_notes as f64
+}
+
+pub fn real_time_accuracy(counts: [u32; 4]) -> f64 {
+    let cnt = counts.iter().sum::<u32>();
+    if cnt == 0 {
+        return 1.;
+    }
+    accuracy(counts, cnt)
+}
+
+pub fn score(counts: [u32; 4], max_combo: u32, num_of_notes: u32) -> f64 {
+    if counts[0] == num_of_notes {
+        1_000_000.0
+    } else {
+        (0.9 * accuracy(counts, num_of_notes) + max_combo as f64 / num_of_notes as f64 * 0.1) * 1_000_000.
+    }
+}
@@ -0,0 +1,114 @@
+// the judgements autoplay gets from the real judge, stepped at a fixed frame rate so the trace doesn't depend on
+// the machine. Converters can diff it between engine versions, and check that a chart still reaches the full score
+// nothing here needs a window, the chart is loaded without textures and the judge runs without a `Resource`
+
+use super::{Judge, Judgement};
+use crate::{
+    bin::BinaryReader,
+    config::Config,
+    core::{Chart, ChartExtra, NoteKind},
+    fs::FileSystem,
+    info::{ChartFormat, ChartInfo},
+    parse::{parse_pec, parse_phigros_bytes, parse_rpe_judging},
+    scene::GameScene,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+// frames per second autoplay is stepped at
+const FPS: f32 = 120.;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub line: u32,
+    pub note: u32,
+    // the frame the judgement was committed on
+    pub time: f32,
+    pub judgement: Judgement,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoplayReport {
+    pub num_of_notes: u32,
+    pub score: u32,
+    pub max_combo: u32,
+    pub full_score: bool,
+    pub trace: Vec<TraceEntry>,
+}
+
+// like `GameScene::load_chart` without extra, scripts and textures
+pub async fn load_chart(fs: &mut dyn FileSystem, info: &ChartInfo, config: &Config) -> Result<Chart> {
+    let bytes = GameScene::load_chart_bytes(fs, info).await.context("Failed to load chart")?;
+    let mut chart = match GameScene::chart_format(info, &bytes) {
+        ChartFormat::Rpe => parse_rpe_judging(&String::from_utf8_lossy(&bytes), fs).await,
+        ChartFormat::Pgr => parse_phigros_bytes(bytes, ChartExtra::default()).await,
+        ChartFormat::Pec => parse_pec(&String::from_utf8_lossy(&bytes), ChartExtra::default()),
+        ChartFormat::Pbc => {
+            let mut r = BinaryReader::new(Cursor::new(bytes));
+            r.read()
+        }
+    }?;
+    chart.apply_mods(config.chart_mods(), config.random_seed);
+    Ok(chart)
+}
+
+fn run_autoplay(chart: &mut Chart, config: &Config) -> Judge {
+    let mut judge = Judge::new(chart);
+    let end = chart
+        .lines
+        .iter()
+        .flat_map(|it| it.notes.iter())
+        .filter(|it| !it.fake)
+        .map(|it| match it.kind {
+            NoteKind::Hold { end_time, .. } => end_time,
+            _ => it.time,
+        })
+        .fold(0., f32::max);
+    let mut frame = 0;
+    loop {
+        let t = frame as f32 / FPS;
+        judge.auto_play_judge(chart, t, config);
+        if t > end {
+            break;
+        }
+        frame += 1;
+    }
+    judge
+}
+
+fn trace_of(judge: &Judge) -> Vec<TraceEntry> {
+    let mut trace: Vec<_> = judge
+        .judgements
+        .borrow()
+        .iter()
+        .filter_map(|&(time, line, note, judgement)| {
+            Some(TraceEntry {
+                line,
+                note,
+                time,
+                judgement: judgement.ok()?,
+            })
+        })
+        .collect();
+    trace.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.line.cmp(&b.line)).then(a.note.cmp(&b.note)));
+    trace
+}
+
+pub fn autoplay_trace(chart: &mut Chart, config: &Config) -> Vec<TraceEntry> {
+    trace_of(&run_autoplay(chart, config))
+}
+
+pub fn autoplay_report(chart: &mut Chart, config: &Config) -> AutoplayReport {
+    let judge = run_autoplay(chart, config);
+    let result = judge.result();
+    let score = if result.num_of_notes == 0 { 0 } else { result.score.round() as u32 };
+    AutoplayReport {
+        num_of_notes: result.num_of_notes,
+        score,
+        max_combo: result.max_combo,
+        full_score: score == 1_000_000,
+        trace: trace_of(&judge),
+    }
+}
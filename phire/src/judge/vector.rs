@@ -0,0 +1,269 @@
+// runs the judge's hit logic over a recorded input stream on a single static line
+// a vector pairs such a stream with the judgements it must produce, so that other builds can check they judge the same way
+
+use super::{
+    hit::{hit_frame, HitNote, HitParams, HitTouch},
+    rules::{self, NoteClass, Reach},
+    FlickTracker, JudgeStatus, Judgement,
+};
+use crate::{config::JudgeWindows, core::Point};
+use anyhow::{bail, Result};
+use macroquad::prelude::TouchPhase;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+fn one() -> f32 {
+    1.
+}
+
+fn default_fps() -> f32 {
+    60.
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorNote {
+    pub class: NoteClass,
+    pub time: f32,
+    // holds only
+    #[serde(default)]
+    pub end_time: Option<f32>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default = "one")]
+    pub judge_scale: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputPhase {
+    Down,
+    Move,
+    Up,
+}
+
+// positions are in line space
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputEvent {
+    pub time: f32,
+    pub id: u64,
+    pub phase: InputPhase,
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub note: usize,
+    pub judgement: Judgement,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestVector {
+    pub name: String,
+    #[serde(default)]
+    pub windows: JudgeWindows,
+    #[serde(default)]
+    pub full_screen: bool,
+    #[serde(default = "one")]
+    pub chart_ratio: f32,
+    // the judge runs once per frame, input in between is handled at its own timestamp
+    #[serde(default = "default_fps")]
+    pub fps: f32,
+    pub notes: Vec<VectorNote>,
+    pub events: Vec<InputEvent>,
+    // in the order the judge commits them
+    pub expected: Vec<Outcome>,
+    #[serde(default)]
+    pub score: Option<u32>,
+}
+
+// a note of the vector along with what the judge keeps on it
+struct VectorState<'a> {
+    note: &'a VectorNote,
+    status: JudgeStatus,
+    protected: bool,
+}
+
+impl HitNote for VectorState<'_> {
+    fn class(&self) -> NoteClass {
+        self.note.class
+    }
+
+    fn time(&self) -> f32 {
+        self.note.time
+    }
+
+    fn end_time(&self) -> Option<f32> {
+        self.note.end_time
+    }
+
+    fn judge_scale(&self) -> f32 {
+        self.note.judge_scale
+    }
+
+    fn fake(&self) -> bool {
+        false
+    }
+
+    fn x_at(&mut self, _t: f32) -> f32 {
+        self.note.x
+    }
+
+    fn status(&mut self) -> &mut JudgeStatus {
+        &mut self.status
+    }
+
+    fn protected(&mut self) -> &mut bool {
+        &mut self.protected
+    }
+}
+
+// feeds the events to the judge frame by frame, the way `Judge::update` gets them from the window
+fn run(vector: &TestVector) -> Vec<Outcome> {
+    let params = HitParams {
+        windows: vector.windows,
+        reach: Reach::fixed(vector.full_screen, vector.chart_ratio),
+        spd: 1.,
+        hold_leniency: 0.,
+        hold_no_penalty: false,
+        keys_down: 0,
+        key_down_count: 0,
+    };
+    let mut notes: Vec<_> = vector
+        .notes
+        .iter()
+        .map(|note| VectorState {
+            note,
+            status: JudgeStatus::NotJudged,
+            protected: false,
+        })
+        .collect();
+    let mut order: Vec<u32> = (0..vector.notes.len() as u32).collect();
+    order.sort_by(|a, b| vector.notes[*a as usize].time.total_cmp(&vector.notes[*b as usize].time));
+    let mut pending = [(order, 0)];
+    let end = vector
+        .notes
+        .iter()
+        .map(|it| it.end_time.unwrap_or(it.time))
+        .chain(vector.events.iter().map(|it| it.time))
+        .fold(0f32, f32::max)
+        + vector.windows.bad
+        + 1.;
+    let mut trackers = HashMap::new();
+    // touches that are down, with where they are and when they last moved
+    let mut down: BTreeMap<u64, (Point, f32)> = BTreeMap::new();
+    let mut events = vector.events.iter().peekable();
+    let step = 1. / vector.fps;
+    let mut outcomes = Vec::new();
+    let mut frame = 0;
+    loop {
+        let t = frame as f32 * step;
+        let mut started = BTreeMap::new();
+        let mut ended = BTreeMap::new();
+        while let Some(event) = events.next_if(|it| it.time <= t) {
+            let p = Point::new(event.x, event.y);
+            match event.phase {
+                InputPhase::Down => {
+                    trackers.insert(event.id, FlickTracker::new(0, event.time, p));
+                    down.insert(event.id, (p, event.time));
+                    started.insert(event.id, event.time);
+                }
+                InputPhase::Move => {
+                    if let Some(tracker) = trackers.get_mut(&event.id) {
+                        tracker.push(event.time, p);
+                    }
+                    if let Some(it) = down.get_mut(&event.id) {
+                        *it = (p, event.time);
+                    }
+                }
+                InputPhase::Up => {
+                    trackers.remove(&event.id);
+                    if let Some((_, time)) = down.remove(&event.id) {
+                        ended.insert(event.id, (p, time));
+                    }
+                }
+            }
+        }
+        // like the window's touches, one that ended this frame is still reported, as started if it also began in it
+        let mut touches = Vec::new();
+        let mut pos = Vec::new();
+        for (id, (p, time)) in down.iter().chain(ended.iter()) {
+            let phase = if started.contains_key(id) {
+                TouchPhase::Started
+            } else if down.contains_key(id) {
+                TouchPhase::Moved
+            } else {
+                TouchPhase::Ended
+            };
+            touches.push(HitTouch {
+                id: *id,
+                phase,
+                time: started.get(id).copied().unwrap_or(if phase == TouchPhase::Moved { *time } else { t }),
+            });
+            pos.push(Some(*p));
+        }
+        let judged = hit_frame(&params, t, &mut [&mut notes[..]], &mut pending, &touches, &[pos], &mut trackers);
+        outcomes.extend(judged.judgements.into_iter().map(|(judgement, _, note, _)| Outcome {
+            note: note as usize,
+            judgement,
+        }));
+        if t > end {
+            break;
+        }
+        frame += 1;
+    }
+    outcomes
+}
+
+impl TestVector {
+    pub fn load_all(json: &str) -> Result<Vec<Self>> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn run(&self) -> Vec<Outcome> {
+        run(self)
+    }
+
+    pub fn score_of(&self, outcomes: &[Outcome]) -> u32 {
+        let (counts, max_combo) = rules::tally(outcomes.iter().map(|it| it.judgement));
+        rules::score(counts, max_combo, self.notes.len() as u32).round() as u32
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        let outcomes = self.run();
+        if let Some(pos) = (0..outcomes.len().max(self.expected.len())).find(|i| outcomes.get(*i) != self.expected.get(*i)) {
+            bail!(
+                "{}: judgement #{pos} differs, expected {:?} but got {:?}",
+                self.name,
+                self.expected.get(pos),
+                outcomes.get(pos)
+            );
+        }
+        if let Some(score) = self.score {
+            let actual = self.score_of(&outcomes);
+            if actual != score {
+                bail!("{}: expected score {score} but got {actual}", self.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TestVector;
+
+    #[test]
+    fn judge_vectors() {
+        let vectors = TestVector::load_all(include_str!("../../judge-vectors.json")).unwrap();
+        assert!(!vectors.is_empty());
+        for vector in &vectors {
+            if let Err(err) = vector.verify() {
+                panic!("{err}");
+            }
+        }
+    }
+}
@@ -174,6 +174,21 @@ impl L10nLocal {
         unsafe { std::mem::transmute(self.bundles.inner[*id].format_pattern(pattern, args, errors)) }
     }
 
+    // for keys built at runtime, which may well be missing; not cached
+    pub fn try_format(&self, key: &str) -> Option<String> {
+        let guard = GLOBAL.order.lock().unwrap();
+        let (bundle, pattern) = guard
+            .iter()
+            .map(|id| &self.bundles.inner[*id])
+            .find_map(|bundle| bundle.get_message(key).and_then(|msg| msg.value()).map(|pattern| (bundle, pattern)))?;
+        let mut errors = Vec::new();
+        let res = bundle.format_pattern(pattern, None, &mut errors).into_owned();
+        for error in errors {
+            warn!("l10n error {key}: {error:?}");
+        }
+        Some(res)
+    }
+
     pub fn format<'s>(&mut self, key: &'static str, args: Option<&'s FluentArgs<'s>>) -> Cow<'s, str> {
         let mut errors = Vec::new();
         let res = self.format_with_errors(key, args, &mut errors);
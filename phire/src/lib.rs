@@ -7,6 +7,7 @@ pub mod fs;
 pub mod info;
 pub mod judge;
 pub mod l10n;
+pub mod live;
 pub mod parse;
 pub mod particle;
 pub mod scene;
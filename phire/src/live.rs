@@ -0,0 +1,233 @@
+// live play stats for streaming software, served over plain HTTP on localhost so that an OBS browser source
+// can show them. `/stats` answers with the latest snapshot, `/events` pushes every change as server-sent events
+// and `/` is a ready-made overlay built on the latter.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+// how often the listener checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// events are sent at most this often per connection, a browser source doesn't need every frame
+const EVENT_INTERVAL: Duration = Duration::from_millis(50);
+// a comment is sent when nothing changed for this long, which also notices closed connections
+const KEEP_ALIVE: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LiveState {
+    #[default]
+    Idle,
+    Playing,
+    Paused,
+    Ended,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveStats {
+    pub state: LiveState,
+    pub name: String,
+    pub level: String,
+    pub score: u32,
+    pub accuracy: f64,
+    pub combo: u32,
+    pub perfect: u32,
+    pub good: u32,
+    pub bad: u32,
+    pub miss: u32,
+    pub time: f32,
+    pub duration: f32,
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static SERVER: Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>> = Mutex::new(None);
+// the latest snapshot as JSON, with a counter bumped on every change
+static LATEST: Mutex<(u64, String)> = Mutex::new((0, String::new()));
+static CHANGED: Condvar = Condvar::new();
+
+pub fn serving() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+pub fn start(port: u16) -> Result<()> {
+    stop();
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    {
+        let mut latest = LATEST.lock().unwrap();
+        if latest.1.is_empty() {
+            latest.1 = serde_json::to_string(&LiveStats::default())?;
+        }
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let handle = thread::spawn({
+        let stop = Arc::clone(&stop);
+        move || accept_loop(listener, stop)
+    });
+    *SERVER.lock().unwrap() = Some((stop, handle));
+    ACTIVE.store(true, Ordering::Relaxed);
+    info!("live stats served on http://127.0.0.1:{port}");
+    Ok(())
+}
+
+pub fn stop() {
+    let Some((stop, handle)) = SERVER.lock().unwrap().take() else {
+        return;
+    };
+    ACTIVE.store(false, Ordering::Relaxed);
+    stop.store(true, Ordering::Relaxed);
+    CHANGED.notify_all();
+    let _ = handle.join();
+}
+
+pub fn publish(stats: &LiveStats) {
+    if !serving() {
+        return;
+    }
+    let json = match serde_json::to_string(stats) {
+        Ok(json) => json,
+        Err(err) => {
+            warn!("failed to serialize live stats: {err:?}");
+            return;
+        }
+    };
+    let mut latest = LATEST.lock().unwrap();
+    if latest.1 != json {
+        latest.0 += 1;
+        latest.1 = json;
+        CHANGED.notify_all();
+    }
+}
+
+fn accept_loop(listener: TcpListener, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || {
+                    if let Err(err) = handle(stream, &stop) {
+                        debug!("live stats connection closed: {err:?}");
+                    }
+                });
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(err) => {
+                warn!("live stats server stopped: {err:?}");
+                break;
+            }
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, stop: &AtomicBool) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(KEEP_ALIVE))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // nothing in the headers matters, but they have to be read before answering
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && !line.trim().is_empty() {
+        line.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    match path.split('?').next().unwrap_or_default() {
+        "/" | "/overlay" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", OVERLAY_HTML.as_bytes()),
+        "/stats" => {
+            let json = LATEST.lock().unwrap().1.clone();
+            respond(&mut stream, "200 OK", "application/json", json.as_bytes())
+        }
+        "/events" => stream_events(stream, stop),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"not found"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    Ok(())
+}
+
+fn stream_events(mut stream: TcpStream, stop: &AtomicBool) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    let mut seen = None;
+    while !stop.load(Ordering::Relaxed) {
+        let latest = LATEST.lock().unwrap();
+        let (latest, _) = CHANGED
+            .wait_timeout_while(latest, KEEP_ALIVE, |it| seen == Some(it.0) && !stop.load(Ordering::Relaxed))
+            .unwrap();
+        if seen == Some(latest.0) {
+            drop(latest);
+            stream.write_all(b": keep-alive\n\n")?;
+            continue;
+        }
+        seen = Some(latest.0);
+        let json = latest.1.clone();
+        drop(latest);
+        write!(stream, "data: {json}\n\n")?;
+        thread::sleep(EVENT_INTERVAL);
+    }
+    Ok(())
+}
+
+const OVERLAY_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  body { margin: 0; background: transparent; color: #fff; font-family: sans-serif; text-shadow: 0 0 6px #000; }
+  #panel { padding: 16px 24px; transition: opacity 0.3s; }
+  #panel.idle { opacity: 0; }
+  #title { font-size: 22px; }
+  #score { font-size: 48px; font-variant-numeric: tabular-nums; }
+  #detail { font-size: 20px; font-variant-numeric: tabular-nums; }
+  #progress { height: 4px; background: rgba(255, 255, 255, 0.3); margin-top: 8px; }
+  #progress div { height: 100%; background: #fff; width: 0; }
+  .perfect { color: #ffeca0; } .good { color: #b4e1ff; } .bad { color: #ff7b7b; } .miss { color: #aaa; }
+</style>
+</head>
+<body>
+<div id="panel" class="idle">
+  <div id="title"></div>
+  <div id="score">0000000</div>
+  <div id="detail">
+    <span id="acc">0.00%</span> · <span id="combo">0</span> combo ·
+    <span class="perfect" id="perfect">0</span> / <span class="good" id="good">0</span> /
+    <span class="bad" id="bad">0</span> / <span class="miss" id="miss">0</span>
+  </div>
+  <div id="progress"><div></div></div>
+</div>
+<script>
+  const $ = (id) => document.getElementById(id);
+  function show(s) {
+    $('panel').className = s.state;
+    $('title').textContent = s.level ? s.name + ' ' + s.level : s.name;
+    $('score').textContent = String(s.score).padStart(7, '0');
+    $('acc').textContent = (s.accuracy * 100).toFixed(2) + '%';
+    for (const key of ['combo', 'perfect', 'good', 'bad', 'miss']) $(key).textContent = s[key];
+    $('progress').firstElementChild.style.width = (s.duration > 0 ? Math.min(1, s.time / s.duration) * 100 : 0) + '%';
+  }
+  fetch('/stats').then((r) => r.json()).then(show).catch(() => {});
+  new EventSource('/events').onmessage = (e) => show(JSON.parse(e.data));
+</script>
+</body>
+</html>
+"#;
@@ -1,9 +1,18 @@
+use anyhow::Result;
 use chrono::Utc;
 use colored::Colorize;
 use miniquad::{debug, error, info, trace, warn};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Mutex,
+};
 use tracing::{field::Visit, Level, Subscriber};
 use tracing_subscriber::{prelude::*, Layer};
 
+// plain copies of the messages go here once `log_to_file` is called
+static LOG_FILE: Mutex<Option<(String, File)>> = Mutex::new(None);
+
 struct CustomLayer;
 
 impl<S> Layer<S> for CustomLayer
@@ -28,6 +37,19 @@ where
 
         let meta = event.metadata();
 
+        if let Some((_, file)) = LOG_FILE.lock().unwrap().as_mut() {
+            let mut line = format!("{} {:>5} {}", Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ"), meta.level(), meta.target());
+            if !v.1.is_empty() {
+                let fields: Vec<_> = v.1.iter().map(|(name, val)| format!("{name}={val}")).collect();
+                line += &format!("{{{}}}", fields.join(" "));
+            }
+            if let Some(content) = &v.0 {
+                line += ": ";
+                line += content;
+            }
+            let _ = writeln!(file, "{line}");
+        }
+
         #[cfg(not(target_os = "android"))]
         let mut msg = format!("{} ", Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ")).bright_black().to_string()
             + &match *meta.level() {
@@ -79,3 +101,16 @@ pub fn register_with_colorize(override_colorize: bool) {
     colored::control::set_override(override_colorize);
     tracing_subscriber::registry().with(CustomLayer).init();
 }
+
+// also writes everything logged from now on to a new file under `dir`, one per launch
+pub fn log_to_file(dir: &str) -> Result<()> {
+    let path = format!("{dir}/{}.log", Utc::now().format("%Y%m%d-%H%M%S"));
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    *LOG_FILE.lock().unwrap() = Some((path, file));
+    Ok(())
+}
+
+// the file `log_to_file` is writing to
+pub fn log_file() -> Option<String> {
+    LOG_FILE.lock().unwrap().as_ref().map(|(path, _)| path.clone())
+}
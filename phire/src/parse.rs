@@ -1,3 +1,6 @@
+pub mod edit;
+pub mod export;
+
 mod extra;
 pub use extra::parse_extra;
 
@@ -5,10 +8,10 @@ mod pec;
 pub use pec::parse_pec;
 
 mod pgr;
-pub use pgr::parse_phigros;
+pub use pgr::{parse_phigros, parse_phigros_bytes};
 
 mod rpe;
-pub use rpe::{parse_rpe, RPE_HEIGHT, RPE_WIDTH, RPEChart};
+pub use rpe::{parse_rpe, parse_rpe_bytes, parse_rpe_judging, RPE_HEIGHT, RPE_WIDTH, RPEChart};
 
 pub(crate) fn process_lines(v: &mut [crate::core::JudgeLine]) {
     use crate::ext::NotNanExt;
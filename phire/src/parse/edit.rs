@@ -0,0 +1,165 @@
+// An RPE chart kept as raw json so that notes can be placed and removed in place. Only the note lists are
+// rewritten, everything else (events, textures, fields from newer RPE versions) is saved exactly as it was read.
+
+use super::{export::triple, RPE_WIDTH};
+use crate::{
+    core::{BpmList, Triple},
+    judge::rules::NoteClass,
+};
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EditNote {
+    pub class: NoteClass,
+    pub beat: f32,
+    // same as `beat` except for holds
+    pub end_beat: f32,
+    // in RPE units, 0 is the middle of the line
+    pub x: f32,
+    pub above: bool,
+}
+
+impl EditNote {
+    fn kind(&self) -> u8 {
+        match self.class {
+            NoteClass::Click => 1,
+            NoteClass::Hold => 2,
+            NoteClass::Flick => 3,
+            NoteClass::Drag => 4,
+        }
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "type": self.kind(),
+            "above": if self.above { 1 } else { 2 },
+            "startTime": triple(self.beat),
+            "endTime": triple(self.end_beat.max(self.beat)),
+            "positionX": self.x.clamp(-RPE_WIDTH / 2., RPE_WIDTH / 2.),
+            "yOffset": 0.,
+            "alpha": 255,
+            "size": 1.,
+            "speed": 1.,
+            "isFake": 0,
+            "visibleTime": 999999.,
+        })
+    }
+}
+
+fn beats_of(value: &Value) -> Result<f32> {
+    Ok(serde_json::from_value::<Triple>(value.clone())?.beats())
+}
+
+fn note_of(value: &Value) -> Result<EditNote> {
+    let class = match value["type"].as_u64() {
+        Some(1) => NoteClass::Click,
+        Some(2) => NoteClass::Hold,
+        Some(3) => NoteClass::Flick,
+        Some(4) => NoteClass::Drag,
+        kind => return Err(anyhow!("unknown note type {kind:?}")),
+    };
+    Ok(EditNote {
+        class,
+        beat: beats_of(&value["startTime"])?,
+        end_beat: beats_of(&value["endTime"])?,
+        x: value["positionX"].as_f64().unwrap_or_default() as f32,
+        above: value["above"].as_u64() == Some(1),
+    })
+}
+
+pub struct RPEDocument {
+    root: Value,
+    bpm: BpmList,
+    // parsed copies of the note lists, kept in the same order as the json
+    notes: Vec<Vec<EditNote>>,
+}
+
+impl RPEDocument {
+    pub fn parse(source: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(source).context("invalid RPE json")?;
+        let ranges = root["BPMList"]
+            .as_array()
+            .context("missing BPMList")?
+            .iter()
+            .map(|it| Ok((beats_of(&it["startTime"])?, it["bpm"].as_f64().context("missing bpm")? as f32)))
+            .collect::<Result<Vec<_>>>()?;
+        if ranges.is_empty() {
+            return Err(anyhow!("empty BPMList"));
+        }
+        let notes = root["judgeLineList"]
+            .as_array()
+            .context("missing judgeLineList")?
+            .iter()
+            .enumerate()
+            .map(|(id, line)| {
+                line["notes"]
+                    .as_array()
+                    .map(|notes| notes.iter().map(note_of).collect::<Result<Vec<_>>>())
+                    .transpose()
+                    .with_context(|| format!("bad note on line #{id}"))
+                    .map(Option::unwrap_or_default)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            root,
+            bpm: BpmList::new(ranges),
+            notes,
+        })
+    }
+
+    // in seconds, as `Chart::offset`
+    pub fn offset(&self) -> f32 {
+        self.root["META"]["offset"].as_f64().unwrap_or_default() as f32 / 1000.
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn line_name(&self, line: usize) -> &str {
+        self.root["judgeLineList"][line]["Name"].as_str().unwrap_or_default()
+    }
+
+    pub fn notes(&self, line: usize) -> &[EditNote] {
+        &self.notes[line]
+    }
+
+    pub fn time(&mut self, beat: f32) -> f32 {
+        self.bpm.time_beats(beat)
+    }
+
+    pub fn beat(&mut self, time: f32) -> f32 {
+        self.bpm.beat(time)
+    }
+
+    pub fn bpm_at(&mut self, beat: f32) -> f32 {
+        let time = self.bpm.time_beats(beat);
+        self.bpm.now_bpm(time)
+    }
+
+    fn note_list(&mut self, line: usize) -> &mut Vec<Value> {
+        let line = &mut self.root["judgeLineList"][line];
+        if !line["notes"].is_array() {
+            line["notes"] = json!([]);
+        }
+        line["notes"].as_array_mut().unwrap()
+    }
+
+    // keeps the list sorted by beat, returns the index of the new note
+    pub fn add_note(&mut self, line: usize, note: EditNote) -> usize {
+        let index = self.notes[line].partition_point(|it| it.beat <= note.beat);
+        self.note_list(line).insert(index, note.to_json());
+        self.notes[line].insert(index, note);
+        index
+    }
+
+    pub fn remove_note(&mut self, line: usize, index: usize) -> EditNote {
+        self.note_list(line).remove(index);
+        self.notes[line].remove(index)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.root)?)
+    }
+}
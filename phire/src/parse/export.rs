@@ -0,0 +1,622 @@
+// Writes loaded charts back out as RPE or PEC. Everything is rebuilt from the in-memory animations, so whatever
+// the target format can't express (textures and parents in PEC, GIF progress in RPE) is dropped and tweens
+// without an equivalent are approximated.
+
+use super::{rpe::SPEED_RATIO, RPE_HEIGHT, RPE_TWEEN_MAP, RPE_WIDTH};
+use crate::{
+    core::{Anim, AnimFloat, BezierTween, BpmList, Chart, ClampedTween, JudgeLine, JudgeLineKind, Note, NoteKind, StaticTween, TweenFunction, TweenId, Tweenable},
+    info::ChartInfo,
+    judge::HitSound,
+};
+use anyhow::Result;
+use macroquad::prelude::{Color, WHITE};
+use serde_json::{json, Value};
+use std::{fmt::Write, rc::Rc};
+
+const RPE_VERSION: i32 = 150;
+// tweens PEC can't express are split into this many linear pieces
+const PEC_SUBDIVISION: usize = 16;
+
+fn bpm_list(chart: &Chart) -> Vec<(f32, f32)> {
+    // per-line pgr timing has no beat mapping, fall back to one beat per second
+    chart.bpm_list.borrow().ranges().unwrap_or_else(|| vec![(0., 60.)])
+}
+
+pub(super) fn triple(beats: f32) -> Value {
+    let whole = beats.floor();
+    let frac = beats - whole;
+    let (num, den) = (1..=64)
+        .map(|den| ((frac * den as f32).round() as i32, den))
+        .find(|(num, den)| (*num as f32 / *den as f32 - frac).abs() < 1e-4)
+        .unwrap_or_else(|| ((frac * 10000.).round() as i32, 10000));
+    json!([whole as i32, num, den])
+}
+
+fn rpe_easing_type(tween: TweenId) -> i32 {
+    RPE_TWEEN_MAP.iter().skip(1).position(|it| *it == tween).map_or(1, |it| it as i32 + 1)
+}
+
+fn static_tween(tween: &dyn TweenFunction) -> Option<TweenId> {
+    tween.as_any().downcast_ref::<StaticTween>().map(|it| it.0)
+}
+
+struct Easing {
+    kind: i32,
+    range: (f32, f32),
+    bezier: Option<[f32; 4]>,
+}
+
+impl Easing {
+    fn of(tween: &dyn TweenFunction) -> Self {
+        let any = tween.as_any();
+        let mut res = Self {
+            kind: 1,
+            range: (0., 1.),
+            bezier: None,
+        };
+        if let Some(StaticTween(id)) = any.downcast_ref::<StaticTween>() {
+            res.kind = rpe_easing_type(*id);
+        } else if let Some(ClampedTween(id, range, _)) = any.downcast_ref::<ClampedTween>() {
+            res.kind = rpe_easing_type(*id);
+            res.range = (range.start, range.end);
+        } else if let Some(bezier) = any.downcast_ref::<BezierTween>() {
+            res.bezier = Some([bezier.p1.0, bezier.p1.1, bezier.p2.0, bezier.p2.1]);
+        }
+        res
+    }
+}
+
+struct Segment<T> {
+    start: f32,
+    end: f32,
+    from: T,
+    to: T,
+    tween: Rc<dyn TweenFunction>,
+}
+
+// splits a single (unchained) anim into spans between keyframes
+fn segments<T: Tweenable>(anim: &Anim<T>) -> Vec<Segment<T>> {
+    let kfs = &anim.keyframes;
+    let point = |time: f32, value: &T| Segment {
+        start: time,
+        end: time,
+        from: value.clone(),
+        to: value.clone(),
+        tween: StaticTween::get_rc(2),
+    };
+    let Some(last) = kfs.last() else {
+        return Vec::new();
+    };
+    let mut res: Vec<_> = kfs
+        .windows(2)
+        .map(|w| {
+            let (a, b) = (&w[0], &w[1]);
+            let (from, to) = match static_tween(&*a.tween) {
+                Some(0) => (a.value.clone(), a.value.clone()),
+                Some(1) => (b.value.clone(), b.value.clone()),
+                _ => (a.value.clone(), b.value.clone()),
+            };
+            Segment {
+                start: a.time,
+                end: b.time,
+                from,
+                to,
+                tween: Rc::clone(&a.tween),
+            }
+        })
+        .collect();
+    let settled = kfs.len() >= 2 && {
+        let a = &kfs[kfs.len() - 2];
+        a.time != last.time && static_tween(&*a.tween) != Some(0)
+    };
+    if !settled {
+        res.push(point(last.time, &last.value));
+    }
+    res
+}
+
+fn chain<T: Tweenable>(anim: &Anim<T>) -> Vec<&Anim<T>> {
+    let mut res = Vec::new();
+    let mut cur = Some(anim);
+    while let Some(anim) = cur {
+        if !anim.keyframes.is_empty() {
+            res.push(anim);
+        }
+        cur = anim.next.as_deref();
+    }
+    res
+}
+
+fn rgb(color: &Color) -> Value {
+    let c = |v: f32| (v * 255.).round().clamp(0., 255.) as u8;
+    json!([c(color.r), c(color.g), c(color.b)])
+}
+
+fn first_value(anim: &AnimFloat) -> Option<f32> {
+    anim.keyframes.first().map(|it| it.value)
+}
+
+// bezier tweens are only registered by the parser for the basic event layers
+fn rpe_events<T: Tweenable>(r: &mut BpmList, anim: &Anim<T>, allow_bezier: bool, value: impl Fn(&T) -> Value) -> Value {
+    segments(anim)
+        .into_iter()
+        .map(|seg| {
+            let easing = Easing::of(&*seg.tween);
+            let bezier = easing.bezier.filter(|_| allow_bezier);
+            json!({
+                "startTime": triple(r.beat(seg.start)),
+                "endTime": triple(r.beat(seg.end)),
+                "start": value(&seg.from),
+                "end": value(&seg.to),
+                "easingType": easing.kind,
+                "easingLeft": easing.range.0,
+                "easingRight": easing.range.1,
+                "bezier": bezier.is_some() as u8,
+                "bezierPoints": bezier.unwrap_or_default(),
+                "linkgroup": 0,
+            })
+        })
+        .collect()
+}
+
+fn rpe_speed_events(r: &mut BpmList, height: &AnimFloat) -> Value {
+    const H: f32 = 1e-3;
+    segments(height)
+        .into_iter()
+        .filter(|seg| seg.end > seg.start)
+        .map(|seg| {
+            let avg = (seg.to - seg.from) / (seg.end - seg.start);
+            let start = avg * seg.tween.y(H) / H;
+            let end = avg * (1. - seg.tween.y(1. - H)) / H;
+            json!({
+                "startTime": triple(r.beat(seg.start)),
+                "endTime": triple(r.beat(seg.end)),
+                "start": start / SPEED_RATIO,
+                "end": end / SPEED_RATIO,
+                "easingType": 1,
+            })
+        })
+        .collect()
+}
+
+fn rpe_ctrl_events(anim: &AnimFloat, key: &str) -> Value {
+    anim.keyframes
+        .iter()
+        .map(|kf| {
+            json!({
+                "easing": static_tween(&*kf.tween).map_or(1, rpe_easing_type),
+                "x": kf.time,
+                key: kf.value,
+            })
+        })
+        .collect()
+}
+
+fn rpe_note(r: &mut BpmList, note: &Note) -> Value {
+    let (kind, end_time) = match note.kind {
+        NoteKind::Click => (1, note.time),
+        NoteKind::Hold { end_time, .. } => (2, end_time),
+        NoteKind::Flick => (3, note.time),
+        NoteKind::Drag => (4, note.time),
+    };
+    let alpha = &note.object.alpha.keyframes;
+    let (alpha, visible_time) = match alpha.len() {
+        0 => (1., 999999.),
+        1 => (alpha[0].value, 999999.),
+        _ => (alpha[alpha.len() - 1].value, note.time - alpha[1].time),
+    };
+    let y_offset = first_value(&note.object.translation.1).unwrap_or_default();
+    let hitsound = match (&note.hitsound, &note.kind) {
+        (HitSound::Custom(name), _) => Some(name.as_str()),
+        (HitSound::Click, NoteKind::Drag | NoteKind::Flick) => Some("tap.ogg"),
+        (HitSound::Drag, kind) if !matches!(kind, NoteKind::Drag) => Some("drag.ogg"),
+        (HitSound::Flick, kind) if !matches!(kind, NoteKind::Flick) => Some("flick.ogg"),
+        _ => None,
+    };
+    json!({
+        "type": kind,
+        "above": if note.above { 1 } else { 2 },
+        "startTime": triple(r.beat(note.time)),
+        "endTime": triple(r.beat(end_time)),
+        "positionX": first_value(&note.object.translation.0).unwrap_or_default() * RPE_WIDTH / 2.,
+        "yOffset": if note.speed == 0. { 0. } else { y_offset * RPE_HEIGHT / 2. / note.speed },
+        "alpha": (alpha * 255.).round() as u16,
+        "hitsound": hitsound,
+        "size": first_value(&note.object.scale.0).unwrap_or(1.),
+        "speed": note.speed,
+        "isFake": note.fake as u8,
+        "visibleTime": visible_time,
+        "tint": rgb(&note.color.keyframes.first().map_or(WHITE, |it| it.value)),
+        "tintHitEffects": note.hit_fx_color.keyframes.first().map(|it| rgb(&it.value)),
+        "judgeArea": note.judge_scale,
+        "hitsoundVolume": note.hitsound_tune.volume,
+        "hitsoundPitch": note.hitsound_tune.pitch,
+    })
+}
+
+fn rpe_line(r: &mut BpmList, line: &JudgeLine) -> Value {
+    let object = &line.object;
+    // chained anims come from stacked event layers
+    let tracks = [
+        (&object.alpha, 255.),
+        (&object.translation.0, RPE_WIDTH / 2.),
+        (&object.translation.1, RPE_HEIGHT / 2.),
+        (&object.rotation, -1.),
+    ]
+    .map(|(anim, factor)| (chain(anim), factor));
+    let layer_count = tracks.iter().map(|it| it.0.len()).max().unwrap_or_default().max(1);
+    let mut layers = Vec::new();
+    for i in 0..layer_count {
+        let [alpha, move_x, move_y, rotate] = tracks
+            .each_ref()
+            .map(|(anims, factor)| anims.get(i).map_or(Value::Null, |anim| rpe_events(r, anim, true, |v| json!(v * factor))));
+        let mut layer = json!({
+            "alphaEvents": alpha,
+            "moveXEvents": move_x,
+            "moveYEvents": move_y,
+            "rotateEvents": rotate,
+        });
+        if i == 0 {
+            layer["speedEvents"] = rpe_speed_events(r, &line.height);
+        }
+        layers.push(layer);
+    }
+
+    let texture = match &line.kind {
+        JudgeLineKind::Texture(_, path) | JudgeLineKind::TextureGif(_, _, path) => path.as_str(),
+        _ => "line.png",
+    };
+    let is_text = matches!(line.kind, JudgeLineKind::Text(_));
+    // inverse of the scale factors applied by the parser
+    let (factor_x, factor_y) = if texture == "line.png" {
+        (if !is_text && line.attach_ui.is_none() { 0.5 } else { 1. }, 1.)
+    } else {
+        (2. / RPE_WIDTH, 2. / RPE_WIDTH)
+    };
+    let mut scale_events = |anim: &AnimFloat, factor: f32| {
+        if anim.is_default() {
+            rpe_events(r, &AnimFloat::fixed(1. / factor), false, |v| json!(v))
+        } else {
+            rpe_events(r, anim, false, |v| json!(v / factor))
+        }
+    };
+    let mut extended = json!({
+        "scaleXEvents": scale_events(&object.scale.0, factor_x),
+        "scaleYEvents": scale_events(&object.scale.1, factor_y),
+    });
+    if !line.color.is_default() {
+        extended["colorEvents"] = rpe_events(r, &line.color, false, rgb);
+    }
+    if !line.incline.is_default() {
+        extended["inclineEvents"] = rpe_events(r, &line.incline, false, |v| json!(v));
+    }
+    match &line.kind {
+        JudgeLineKind::Text(anim) => extended["textEvents"] = rpe_events(r, anim, false, |v| json!(v)),
+        JudgeLineKind::Paint(anim, _) => extended["paintEvents"] = rpe_events(r, anim, false, |v| json!(v)),
+        _ => {}
+    }
+
+    let ctrl = line.ctrl_obj.borrow();
+    json!({
+        "Name": "Untitled",
+        "Texture": texture,
+        "father": line.parent.map_or(-1, |it| it as isize),
+        "rotateWithFather": line.rotate_with_parent,
+        "anchor": line.anchor,
+        "bpmfactor": 1.0,
+        "eventLayers": layers,
+        "extended": extended,
+        "notes": line.notes.iter().map(|note| rpe_note(r, note)).collect::<Vec<_>>(),
+        "isCover": if line.show_below { 0 } else { 1 },
+        "zOrder": line.z_index,
+        "attachUI": line.attach_ui,
+        "posControl": rpe_ctrl_events(&ctrl.pos, "pos"),
+        "sizeControl": rpe_ctrl_events(&ctrl.size, "size"),
+        "alphaControl": rpe_ctrl_events(&ctrl.alpha, "alpha"),
+        "yControl": rpe_ctrl_events(&ctrl.y, "y"),
+    })
+}
+
+pub fn to_rpe(chart: &Chart, info: &ChartInfo) -> Result<String> {
+    let ranges = bpm_list(chart);
+    let r = &mut BpmList::new(ranges.clone());
+    let rpe = json!({
+        "META": {
+            "RPEVersion": RPE_VERSION,
+            "offset": (chart.offset * 1000.).round() as i32,
+            "name": info.name,
+            "level": info.level,
+            "charter": info.charter,
+            "composer": info.composer,
+            "song": info.music,
+            "background": info.illustration,
+            "id": info.id.map(|it| it.to_string()).unwrap_or_default(),
+        },
+        "BPMList": ranges.iter().map(|(beats, bpm)| json!({ "bpm": bpm, "startTime": triple(*beats) })).collect::<Vec<_>>(),
+        "judgeLineList": chart.lines.iter().map(|line| rpe_line(r, line)).collect::<Vec<_>>(),
+    });
+    Ok(serde_json::to_string(&rpe)?)
+}
+
+enum Track {
+    // value jumps at the beat
+    Point(f32, Vec<f32>),
+    // tween from the previous value to the given one between the beats
+    Motion(f32, f32, Vec<f32>, i32),
+}
+
+// walks the keyframes of all anims together so their values can be written by the same commands
+fn pec_track(r: &mut BpmList, anims: &[&AnimFloat], easing_supported: bool) -> Vec<Track> {
+    let mut anims: Vec<AnimFloat> = anims.iter().map(|it| (*it).clone()).collect();
+    let mut times: Vec<f32> = anims.iter().flat_map(|it| chain(it).into_iter().flat_map(|it| it.keyframes.iter().map(|kf| kf.time))).collect();
+    times.sort_by(f32::total_cmp);
+    times.dedup();
+    let sample = |anims: &mut [AnimFloat], t: f32| -> Vec<f32> {
+        anims
+            .iter_mut()
+            .map(|it| {
+                it.set_time(t);
+                it.now()
+            })
+            .collect()
+    };
+    let Some(&first) = times.first() else {
+        return Vec::new();
+    };
+    let mut res = vec![Track::Point(r.beat(first), sample(&mut anims, first))];
+    for w in times.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let start = sample(&mut anims, a);
+        let end = sample(&mut anims, b.next_down());
+        if start != end {
+            // the span can be written as one command only if every changing anim uses the same plain tween
+            let mut tweens = anims.iter().zip(start.iter().zip(&end)).filter(|(_, (s, e))| s != e).map(|(it, _)| {
+                if it.next.is_some() || it.keyframes.is_empty() {
+                    None
+                } else {
+                    static_tween(&*it.keyframes[it.cursor].tween)
+                }
+            });
+            let tween = tweens.next().flatten();
+            let single = tween.filter(|id| tweens.all(|it| it == Some(*id)) && (easing_supported || *id == 2));
+            if let Some(id) = single {
+                res.push(Track::Motion(r.beat(a), r.beat(b), end.clone(), rpe_easing_type(id)));
+            } else {
+                let at = |i: usize| f32::tween(&a, &b, i as f32 / PEC_SUBDIVISION as f32);
+                for i in 1..=PEC_SUBDIVISION {
+                    let values = sample(&mut anims, if i == PEC_SUBDIVISION { b.next_down() } else { at(i) });
+                    res.push(Track::Motion(r.beat(at(i - 1)), r.beat(at(i)), values, 1));
+                }
+            }
+        }
+        let after = sample(&mut anims, b);
+        if after != end {
+            res.push(Track::Point(r.beat(b), after));
+        }
+    }
+    res
+}
+
+fn pec_line(out: &mut String, r: &mut BpmList, id: usize, line: &JudgeLine) -> Result<()> {
+    let mut height = line.height.clone();
+    for seg in segments(&line.height).into_iter().filter(|seg| seg.end > seg.start) {
+        let pieces = if static_tween(&*seg.tween) == Some(2) { 1 } else { PEC_SUBDIVISION };
+        for i in 0..pieces {
+            let t0 = f32::tween(&seg.start, &seg.end, i as f32 / pieces as f32);
+            let t1 = f32::tween(&seg.start, &seg.end, (i + 1) as f32 / pieces as f32);
+            height.set_time(t0);
+            let h0 = height.now();
+            height.set_time(t1.next_down());
+            let h1 = height.now();
+            writeln!(out, "cv {id} {} {}", r.beat(t0), (h1 - h0) / (t1 - t0) * 5.85)?;
+        }
+    }
+
+    let object = &line.object;
+    let pos = |v: &[f32]| ((v[0] + 1.) / 2. * 2048., (v[1] + 1.) / 2. * 1400.);
+    for track in pec_track(r, &[&object.translation.0, &object.translation.1], true) {
+        match track {
+            Track::Point(t, v) => {
+                let (x, y) = pos(&v);
+                writeln!(out, "cp {id} {t} {x} {y}")?;
+            }
+            Track::Motion(s, e, v, easing) => {
+                let (x, y) = pos(&v);
+                writeln!(out, "cm {id} {s} {e} {x} {y} {easing}")?;
+            }
+        }
+    }
+    for track in pec_track(r, &[&object.rotation], true) {
+        match track {
+            Track::Point(t, v) => writeln!(out, "cd {id} {t} {}", -v[0])?,
+            Track::Motion(s, e, v, easing) => writeln!(out, "cr {id} {s} {e} {} {easing}", -v[0])?,
+        }
+    }
+    let alpha = |v: f32| if v >= 0. { v * 255. } else { v };
+    for track in pec_track(r, &[&object.alpha], false) {
+        match track {
+            Track::Point(t, v) => writeln!(out, "ca {id} {t} {}", alpha(v[0]))?,
+            Track::Motion(s, e, v, _) => writeln!(out, "cf {id} {s} {e} {}", alpha(v[0]))?,
+        }
+    }
+
+    for note in &line.notes {
+        let x = first_value(&note.object.translation.0).unwrap_or_default() * 1024.;
+        let above = if note.above { 1 } else { 2 };
+        let fake = note.fake as u8;
+        let time = r.beat(note.time);
+        match note.kind {
+            NoteKind::Click => writeln!(out, "n1 {id} {time} {x} {above} {fake}")?,
+            NoteKind::Hold { end_time, .. } => writeln!(out, "n2 {id} {time} {} {x} {above} {fake}", r.beat(end_time))?,
+            NoteKind::Flick => writeln!(out, "n3 {id} {time} {x} {above} {fake}")?,
+            NoteKind::Drag => writeln!(out, "n4 {id} {time} {x} {above} {fake}")?,
+        }
+        writeln!(out, "# {}", note.speed)?;
+        writeln!(out, "& {}", first_value(&note.object.scale.0).unwrap_or(1.))?;
+    }
+    Ok(())
+}
+
+pub fn to_pec(chart: &Chart) -> Result<String> {
+    let ranges = bpm_list(chart);
+    let r = &mut BpmList::new(ranges.clone());
+    let mut out = String::new();
+    writeln!(out, "{}", ((chart.offset + 0.15) * 1000.).round() as i32)?;
+    for (beats, bpm) in ranges {
+        writeln!(out, "bp {beats} {bpm}")?;
+    }
+    for (id, line) in chart.lines.iter().enumerate() {
+        pec_line(&mut out, r, id, line)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_pec, to_rpe};
+    use crate::{
+        core::{AnimFloat, Chart, ChartExtra, NoteKind},
+        fs::FileSystem,
+        info::ChartInfo,
+        parse::{parse_pec, parse_rpe_judging},
+    };
+    use anyhow::{bail, Result};
+    use async_trait::async_trait;
+    use std::any::Any;
+
+    const PEC: &str = "150
+bp 0 120
+cv 0 0 11.7
+cp 0 0 1024 700
+cd 0 0 0
+ca 0 0 255
+cf 0 1 3 100
+cm 0 2 6 1536 400 4
+cr 0 4 8 90 1
+cv 0 6 5.85
+n1 0 1 512 1 0
+# 1
+& 1
+n2 0 2 4 -256 1 0
+# 1
+& 1
+n3 0 5 0 2 0
+# 2
+& 1
+n4 0 8 300 1 1
+# 1
+& 1.5
+cv 1 0 5.85
+cp 1 0 500 300
+";
+
+    const RPE: &str = r#"{
+  "META": { "RPEVersion": 150, "offset": 20 },
+  "BPMList": [{ "bpm": 120, "startTime": [0, 0, 1] }],
+  "judgeLineList": [{
+    "Name": "line",
+    "Texture": "line.png",
+    "father": -1,
+    "isCover": 1,
+    "eventLayers": [{
+      "alphaEvents": [{ "startTime": [0, 0, 1], "endTime": [4, 0, 1], "start": 255, "end": 128, "easingType": 1 }],
+      "moveXEvents": [{ "startTime": [0, 0, 1], "endTime": [6, 0, 1], "start": 0, "end": 300, "easingType": 2 }],
+      "moveYEvents": [{ "startTime": [0, 0, 1], "endTime": [1, 0, 1], "start": -200, "end": -200, "easingType": 1 }],
+      "rotateEvents": [{ "startTime": [2, 0, 1], "endTime": [6, 1, 2], "start": 0, "end": 45, "easingType": 1 }],
+      "speedEvents": [{ "startTime": [0, 0, 1], "endTime": [8, 0, 1], "start": 10, "end": 10, "easingType": 1 }]
+    }],
+    "notes": [
+      { "type": 1, "above": 1, "startTime": [1, 0, 1], "endTime": [1, 0, 1], "positionX": -200, "yOffset": 0, "alpha": 255, "size": 1, "speed": 1, "isFake": 0, "visibleTime": 999999 },
+      { "type": 2, "above": 1, "startTime": [2, 0, 1], "endTime": [3, 1, 2], "positionX": 0, "yOffset": 0, "alpha": 255, "size": 1, "speed": 1, "isFake": 0, "visibleTime": 999999 },
+      { "type": 3, "above": 2, "startTime": [4, 0, 1], "endTime": [4, 0, 1], "positionX": 150, "yOffset": 0, "alpha": 255, "size": 1, "speed": 1.5, "isFake": 0, "visibleTime": 999999 },
+      { "type": 4, "above": 1, "startTime": [8, 1, 4], "endTime": [8, 1, 4], "positionX": 300, "yOffset": 30, "alpha": 255, "size": 1, "speed": 1, "isFake": 1, "visibleTime": 999999 }
+    ]
+  }]
+}"#;
+
+    // textures aren't loaded when parsing for judging, and the charts here don't name any other file
+    struct NoFiles;
+
+    #[async_trait]
+    impl FileSystem for NoFiles {
+        async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+            bail!("no such file: {path}")
+        }
+
+        async fn exists(&mut self, _path: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn list_root(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        fn clone_box(&self) -> Box<dyn FileSystem> {
+            Box::new(NoFiles)
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn parse_rpe(source: &str) -> Chart {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(parse_rpe_judging(source, &mut NoFiles))
+            .unwrap()
+    }
+
+    fn assert_close(a: f32, b: f32, what: &str) {
+        assert!((a - b).abs() <= 1e-3 * a.abs().max(1.), "{what}: {a} != {b}");
+    }
+
+    fn value_at(anim: &AnimFloat, t: f32) -> f32 {
+        let mut anim = anim.clone();
+        anim.set_time(t);
+        anim.now()
+    }
+
+    // the exported chart may be laid out differently, only what it plays like has to match
+    fn assert_same(a: &Chart, b: &Chart) {
+        assert_close(a.offset, b.offset, "offset");
+        assert_eq!(a.lines.len(), b.lines.len());
+        for (id, (la, lb)) in a.lines.iter().zip(&b.lines).enumerate() {
+            assert_eq!(la.notes.len(), lb.notes.len(), "notes of line {id}");
+            for (na, nb) in la.notes.iter().zip(&lb.notes) {
+                assert_eq!(std::mem::discriminant(&na.kind), std::mem::discriminant(&nb.kind));
+                assert_close(na.time, nb.time, "note time");
+                if let (NoteKind::Hold { end_time: ea, .. }, NoteKind::Hold { end_time: eb, .. }) = (&na.kind, &nb.kind) {
+                    assert_close(*ea, *eb, "hold end");
+                }
+                assert_eq!((na.above, na.fake), (nb.above, nb.fake));
+                assert_close(na.speed, nb.speed, "note speed");
+                assert_close(value_at(&na.object.translation.0, 0.), value_at(&nb.object.translation.0, 0.), "note x");
+            }
+            // halfway between the beats, so that a jump doesn't land on a sample
+            for t in (0..40).map(|i| 0.05 + i as f32 * 0.1) {
+                let (oa, ob) = (&la.object, &lb.object);
+                assert_close(value_at(&oa.alpha, t), value_at(&ob.alpha, t), "alpha");
+                assert_close(value_at(&oa.translation.0, t), value_at(&ob.translation.0, t), "x");
+                assert_close(value_at(&oa.translation.1, t), value_at(&ob.translation.1, t), "y");
+                assert_close(value_at(&oa.rotation, t), value_at(&ob.rotation, t), "rotation");
+                assert_close(value_at(&la.height, t), value_at(&lb.height, t), "height");
+            }
+        }
+    }
+
+    #[test]
+    fn pec_round_trip() {
+        let chart = parse_pec(PEC, ChartExtra::default()).unwrap();
+        let exported = parse_pec(&to_pec(&chart).unwrap(), ChartExtra::default()).unwrap();
+        assert_same(&chart, &exported);
+    }
+
+    #[test]
+    fn rpe_round_trip() {
+        let chart = parse_rpe(RPE);
+        let exported = parse_rpe(&to_rpe(&chart, &ChartInfo::default()).unwrap());
+        assert_same(&chart, &exported);
+    }
+}
@@ -4,12 +4,14 @@ use super::RPE_TWEEN_MAP;
 #[cfg(feature = "video")]
 use crate::core::Video;
 use crate::{
-    core::{Anim, BpmList, ChartExtra, ClampedTween, Effect, Keyframe, StaticTween, Triple, Tweenable, Uniform, EPS},
+    core::{
+        extra_handler, Anim, Attachment, AttachmentKind, BpmList, ChartExtra, ClampedTween, Effect, Keyframe, StaticTween, Triple, Tweenable, Uniform, EPS,
+    },
     ext::ScaleType,
     fs::FileSystem,
 };
 use anyhow::{Context, Result};
-use macroquad::prelude::{Color, Vec2};
+use macroquad::prelude::{Color, Vec2, Vec3};
 use serde::Deserialize;
 use std::{collections::HashMap, rc::Rc};
 
@@ -109,6 +111,7 @@ impl From<BpmForm> for BpmList {
 enum Variable {
     Float(ExtAnim<f32>),
     Vec2(ExtAnim<(f32, f32)>),
+    Vec3(ExtAnim<(f32, f32, f32)>),
     Color(ExtAnim<[u8; 4]>),
 }
 
@@ -129,6 +132,8 @@ struct ExtVideo {
     #[serde(default)]
     time: Triple,
     #[serde(default)]
+    line: Option<usize>,
+    #[serde(default)]
     scale: ScaleType,
     #[serde(default)]
     alpha: ExtAnim<f32>,
@@ -136,6 +141,54 @@ struct ExtVideo {
     dim: ExtAnim<f32>,
 }
 
+// shared by images and texts, `end` defaults to the end of the chart
+#[derive(Deserialize)]
+struct ExtAttachment {
+    #[serde(default)]
+    start: Triple,
+    #[serde(default)]
+    end: Option<Triple>,
+    #[serde(default)]
+    line: Option<usize>,
+    #[serde(default)]
+    alpha: ExtAnim<f32>,
+    #[serde(default)]
+    position: ExtAnim<(f32, f32)>,
+    #[serde(default)]
+    scale: ExtAnim<f32>,
+    #[serde(default)]
+    color: ExtAnim<[u8; 4]>,
+}
+
+impl ExtAttachment {
+    fn into_attachment(self, r: &mut BpmList, kind: AttachmentKind, scale_type: ScaleType) -> Attachment {
+        let range = r.time(&self.start)..self.end.map_or(f32::INFINITY, |it| r.time(&it));
+        let mut attachment = Attachment::new(kind, range, self.line, scale_type);
+        attachment.alpha = self.alpha.into(r, Some(1.));
+        attachment.position = self.position.into(r, None);
+        attachment.scale = self.scale.into(r, Some(1.));
+        attachment.color = self.color.into(r, None);
+        attachment
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtImage {
+    path: String,
+    #[serde(default)]
+    scale_type: ScaleType,
+    #[serde(flatten)]
+    attachment: ExtAttachment,
+}
+
+#[derive(Deserialize)]
+struct ExtText {
+    text: String,
+    #[serde(flatten)]
+    attachment: ExtAttachment,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Extra {
@@ -144,6 +197,12 @@ struct Extra {
     effects: Vec<ExtEffect>,
     #[serde(default)]
     videos: Vec<ExtVideo>,
+    #[serde(default)]
+    images: Vec<ExtImage>,
+    #[serde(default)]
+    texts: Vec<ExtText>,
+    #[serde(flatten)]
+    sections: HashMap<String, serde_json::Value>,
 }
 
 async fn parse_effect(r: &mut BpmList, rpe: ExtEffect, fs: &mut dyn FileSystem) -> Result<Effect> {
@@ -155,6 +214,7 @@ async fn parse_effect(r: &mut BpmList, rpe: ExtEffect, fs: &mut dyn FileSystem)
             Ok(match var {
                 Variable::Float(events) => Box::new((name, events.into::<f32>(r, None))),
                 Variable::Vec2(events) => Box::new((name, events.into::<Vec2>(r, None))),
+                Variable::Vec3(events) => Box::new((name, events.into::<Vec3>(r, None))),
                 Variable::Color(events) => Box::new((name, events.into::<Color>(r, None))),
             })
         })
@@ -179,8 +239,13 @@ async fn parse_effect(r: &mut BpmList, rpe: ExtEffect, fs: &mut dyn FileSystem)
     )
 }
 
-pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem) -> Result<ChartExtra> {
-    let ext: Extra = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem, safe_mode: bool) -> Result<ChartExtra> {
+    let mut ext: Extra = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    // custom shaders and videos are the usual suspects when a device goes down, safe mode leaves them out
+    if safe_mode {
+        ext.effects.clear();
+        ext.videos.clear();
+    }
     let mut r: BpmList = ext.bpm.into();
     let mut effects = Vec::new();
     let mut global_effects = Vec::new();
@@ -201,6 +266,7 @@ pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem) -> Result<ChartE
                     .await
                     .with_context(|| ptl!("video-load-failed", "path" => video.path.clone()))?,
                 r.time(&video.time),
+                video.line,
                 video.scale,
                 video.alpha.into(&mut r, Some(1.)),
                 video.dim.into(&mut r, Some(0.)),
@@ -208,10 +274,40 @@ pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem) -> Result<ChartE
             .with_context(|| ptl!("video-load-failed", "path" => video.path))?,
         );
     }
+    let mut attachments = Vec::new();
+    for img in ext.images {
+        let bytes = fs
+            .load_file(&img.path)
+            .await
+            .with_context(|| ptl!("image-load-failed", "path" => img.path.clone()))?;
+        let tex = image::load_from_memory(&bytes).with_context(|| ptl!("image-load-failed", "path" => img.path))?;
+        attachments.push(img.attachment.into_attachment(&mut r, AttachmentKind::Image(tex.into()), img.scale_type));
+    }
+    for text in ext.texts {
+        attachments.push(text.attachment.into_attachment(&mut r, AttachmentKind::Text(text.text), ScaleType::default()));
+    }
+    let mut sections = ext.sections;
+    let mut extensions = Vec::new();
+    // sorted so that the handlers always load in the same order
+    let mut handled: Vec<_> = sections.keys().filter(|it| extra_handler(it).is_some()).cloned().collect();
+    handled.sort();
+    for name in handled {
+        let value = sections.remove(&name).unwrap();
+        extensions.push(
+            extra_handler(&name)
+                .unwrap()
+                .load(&value, &mut r)
+                .with_context(|| ptl!("extension-location", "name" => name.clone()))?,
+        );
+    }
     Ok(ChartExtra {
         effects,
         global_effects,
         #[cfg(feature = "video")]
         videos,
+        attachments,
+        sections,
+        extensions,
+        ..Default::default()
     })
 }
@@ -7,7 +7,7 @@ use crate::{
         Object, TweenId, EPS,
     },
     ext::NotNanExt,
-    judge::{HitSound, JudgeStatus},
+    judge::{HitSound, HitSoundTune, JudgeStatus},
 };
 use anyhow::{bail, Context, Result};
 use std::{cell::RefCell, collections::HashMap};
@@ -268,6 +268,7 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
                         },
                         kind,
                         hitsound,
+                        hitsound_tune: HitSoundTune::default(),
                         time,
                         height: 0.0,
                         speed: 1.0,
@@ -6,7 +6,8 @@ use crate::{
         Anim, AnimFloat, AnimVector, BpmList, Chart, ChartExtra, ChartSettings, JudgeLine, JudgeLineCache, JudgeLineKind, Keyframe, Note, NoteKind, Object, HEIGHT_RATIO
     },
     ext::NotNanExt,
-    judge::{HitSound, JudgeStatus},
+    fs::spawn_task,
+    judge::{HitSound, HitSoundTune, JudgeStatus},
 };
 use anyhow::{Context, Result};
 use serde::Deserialize;
@@ -220,6 +221,7 @@ fn parse_notes(r: f32, mut pgr: Vec<PgrNote>, _speed: &mut AnimFloat, height: &m
                 },
                 kind,
                 hitsound,
+                hitsound_tune: HitSoundTune::default(),
                 time,
                 speed: if pgr.kind == 3 {
                     1.
@@ -281,6 +283,17 @@ fn parse_judge_line(pgr: PgrJudgeLine, max_time: f32, format_version: u32) -> Re
 
 pub fn parse_phigros(source: &str, extra: ChartExtra) -> Result<Chart> {
     let pgr: PgrChart = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    build_phigros(pgr, extra)
+}
+
+// decodes the JSON on a blocking thread, see `parse_rpe_bytes`
+pub async fn parse_phigros_bytes(bytes: Vec<u8>, extra: ChartExtra) -> Result<Chart> {
+    let pgr: PgrChart =
+        spawn_task(move || serde_json::from_str(&String::from_utf8_lossy(&bytes)).with_context(|| ptl!("json-parse-failed"))).await?;
+    build_phigros(pgr, extra)
+}
+
+fn build_phigros(pgr: PgrChart, extra: ChartExtra) -> Result<Chart> {
     let format_version = pgr.format_version;
     let mut bpm_values = Vec::new();
     for (index, judge_line) in pgr.judge_line_list.iter().enumerate() {
@@ -8,8 +8,8 @@ use crate::{
         HEIGHT_RATIO,
     },
     ext::{NotNanExt, SafeTexture},
-    fs::FileSystem,
-    judge::{HitSound, JudgeStatus}
+    fs::{spawn_task, FileSystem},
+    judge::{HitSound, HitSoundTune, JudgeStatus}
 };
 use anyhow::{Context, Result};
 use image::{codecs::gif, AnimationDecoder, DynamicImage, ImageError};
@@ -22,7 +22,7 @@ use tracing::debug;
 
 pub const RPE_WIDTH: f32 = 1350.;
 pub const RPE_HEIGHT: f32 = 900.;
-const SPEED_RATIO: f32 = 10. / 45. / HEIGHT_RATIO;
+pub(super) const SPEED_RATIO: f32 = 10. / 45. / HEIGHT_RATIO;
 
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -135,7 +135,12 @@ pub struct RPENote {
     position_x: f32,
     y_offset: f32,
     alpha: u16,               // some alpha has 256...
-    hitsound: Option<String>, // TODO implement this feature
+    hitsound: Option<String>,
+    // our extension, not written by RPE itself
+    #[serde(default = "f32_one")]
+    hitsound_volume: f32,
+    #[serde(default = "f32_one")]
+    hitsound_pitch: f32,
     size: f32,
     speed: f32,
     is_fake: u8,
@@ -439,6 +444,10 @@ async fn parse_notes(
             },
             kind,
             hitsound,
+            hitsound_tune: HitSoundTune {
+                volume: note.hitsound_volume,
+                pitch: note.hitsound_pitch,
+            },
             time,
             height: note_height,
             speed: note.speed,
@@ -489,6 +498,7 @@ async fn parse_judge_line(
     fs: &mut dyn FileSystem,
     bezier_map: &BezierMap,
     hitsounds: &mut HitSoundMap,
+    textures: bool,
 ) -> Result<JudgeLine> {
     let mut line_texture_map: HashMap<String, SafeTexture> = Default::default();
     let event_layers: Vec<_> = rpe.event_layers.into_iter().flatten().collect();
@@ -600,6 +610,8 @@ async fn parse_judge_line(
             } else {
                 JudgeLineKind::Normal
             }
+        } else if !textures {
+            JudgeLineKind::Normal
         } else if let Some(extended) = rpe.extended.as_ref() {
             if let Some(events) = extended.gif_events.as_ref() {
                 let data = fs
@@ -707,6 +719,24 @@ fn get_bezier_map(rpe: &RPEChart) -> BezierMap {
 
 pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra) -> Result<Chart> {
     let rpe: RPEChart = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    build_rpe(rpe, fs, extra, true).await
+}
+
+// only what judging needs: texture lines are kept as plain lines, so this works without a GL context
+pub async fn parse_rpe_judging(source: &str, fs: &mut dyn FileSystem) -> Result<Chart> {
+    let rpe: RPEChart = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    build_rpe(rpe, fs, ChartExtra::default(), false).await
+}
+
+// same as `parse_rpe`, but the JSON is decoded on a blocking thread. The chart itself holds `Rc`s and is
+// built here afterwards
+pub async fn parse_rpe_bytes(bytes: Vec<u8>, fs: &mut dyn FileSystem, extra: ChartExtra) -> Result<Chart> {
+    let rpe: RPEChart =
+        spawn_task(move || serde_json::from_str(&String::from_utf8_lossy(&bytes)).with_context(|| ptl!("json-parse-failed"))).await?;
+    build_rpe(rpe, fs, extra, true).await
+}
+
+async fn build_rpe(rpe: RPEChart, fs: &mut dyn FileSystem, extra: ChartExtra, textures: bool) -> Result<Chart> {
     let bezier_map = get_bezier_map(&rpe);
     let bpm_list = rpe.bpm_list;
     let mut r = BpmList::new(bpm_list.clone().into_iter().map(|it| (it.start_time.beats(), it.bpm)).collect());
@@ -750,7 +780,7 @@ pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra)
     for (id, line) in rpe.judge_line_list.into_iter().enumerate() {
         let name = line.name.clone();
         lines.push(
-            parse_judge_line(bpm_list.clone(), line, max_time, fs, &bezier_map, &mut hitsounds)
+            parse_judge_line(bpm_list.clone(), line, max_time, fs, &bezier_map, &mut hitsounds, textures)
                 .await
                 .with_context(move || ptl!("judge-line-location-name", "jlid" => id, "name" => name))?,
         );
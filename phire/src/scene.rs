@@ -7,18 +7,33 @@ pub mod game;
 pub use game::{GameMode, GameScene, SimpleRecord};
 
 mod loading;
-pub use loading::{BasicPlayer, LoadingScene, UpdateFn, UploadFn};
+pub use loading::{BasicPlayer, LoadingScene, RenderFn, UpdateFn, UploadFn};
+
+mod preview;
+pub use preview::{ChartPreview, PREVIEW_LENGTH};
 
 use crate::{
-    ext::{draw_image, screen_aspect, LocalTask, SafeTexture, ScaleType},
+    ext::{draw_image, screen_aspect, source_of_image, LocalTask, SafeTexture, ScaleType},
+    fs::{fs_from_file, FileSystem},
     judge::Judge,
     time::TimeManager,
-    ui::{BillBoard, Dialog, Message, MessageHandle, MessageKind, TextPainter, Ui},
+    ui::{render_memory_overlay, render_perf_overlay, report_scene_exit, BillBoard, Dialog, MemorySnapshot, Message, MessageHandle, MessageKind, TextPainter, Ui},
 };
 use anyhow::{Error, Result};
 use cfg_if::cfg_if;
 use macroquad::prelude::*;
-use std::{any::Any, cell::RefCell, sync::Mutex};
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::RefCell,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    task::Poll,
+};
 use tracing::warn;
 
 #[derive(Default)]
@@ -211,8 +226,88 @@ pub fn return_input(id: String, text: String) {
     *INPUT_TEXT.lock().unwrap() = (Some(id), Some(text));
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Any,
+    // chart packages, as imported into the library
+    Chart,
+    ResPack,
+    // the chart file itself, as referenced by info.yml
+    ChartFile,
+    Image,
+    Audio,
+}
+
+impl FileKind {
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Any => &["zip", "pez", "jpg", "png", "jpeg", "json", "mp3", "ogg"],
+            Self::Chart => &["zip", "pez"],
+            Self::ResPack => &["zip"],
+            Self::ChartFile => &["json", "pec"],
+            Self::Image => &["jpg", "jpeg", "png"],
+            Self::Audio => &["mp3", "ogg", "wav", "flac"],
+        }
+    }
+
+    #[allow(dead_code)]
+    fn label(self) -> Cow<'static, str> {
+        match self {
+            Self::Any => ttl!("file-any"),
+            Self::Chart => ttl!("file-chart"),
+            Self::ResPack => ttl!("file-respack"),
+            Self::ChartFile => ttl!("file-chart-file"),
+            Self::Image => ttl!("file-image"),
+            Self::Audio => ttl!("file-audio"),
+        }
+    }
+}
+
+// a file the player picked; on mobile it's a private copy, so it stays readable after the picker is gone
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PickedFile(PathBuf);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PickedFile {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn read(&self) -> Result<Vec<u8>> {
+        Ok(std::fs::read(&self.0)?)
+    }
+
+    pub fn fs(&self) -> Result<Box<dyn FileSystem + Send + Sync + 'static>> {
+        fs_from_file(&self.0)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static NEXT_PICK: AtomicU32 = AtomicU32::new(0);
+
+// resolves to None when cancelled, which mobile pickers don't report: there it only happens once another file is requested
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_file(kind: FileKind) -> impl Future<Output = Option<PickedFile>> {
+    let id = format!("_pick_{}", NEXT_PICK.fetch_add(1, Ordering::Relaxed));
+    request_file(id.clone(), kind);
+    std::future::poll_fn(move |_| {
+        let mut chosen = CHOSEN_FILE.lock().unwrap();
+        if chosen.0.as_deref() != Some(id.as_str()) {
+            return Poll::Ready(None);
+        }
+        match chosen.1.take() {
+            Some(path) => {
+                chosen.0 = None;
+                Poll::Ready(Some(PickedFile(path.into())))
+            }
+            None => Poll::Pending,
+        }
+    })
+}
+
+// the answer arrives through `take_file` under the given id, which is how files opened from other apps come in too
 #[cfg(not(target_arch = "wasm32"))]
-pub fn request_file(id: impl Into<String>) {
+pub fn request_file(id: impl Into<String>, #[allow(unused_variables)] kind: FileKind) {
     *CHOSEN_FILE.lock().unwrap() = (Some(id.into()), None);
     cfg_if! {
         if #[cfg(target_os = "android")] {
@@ -232,28 +327,7 @@ pub fn request_file(id: impl Into<String>) {
                     extern "C" fn document_picker(_: &Object, _: Sel, _: ObjcId, documents: ObjcId) {
                         unsafe {
                             let url: ObjcId = msg_send![documents, firstObject];
-                            let need_close: bool = msg_send![url, startAccessingSecurityScopedResource];
-                            let mut error: ObjcId = std::ptr::null_mut();
-                            let data: ObjcId = msg_send![class!(NSData), dataWithContentsOfURL: url options: 2 error: &mut error as *mut ObjcId];
-                            if need_close {
-                                let _: () = msg_send![url, stopAccessingSecurityScopedResource];
-                            }
-                            if data.is_null() {
-                                show_message(ttl!("read-file-failed")).error();
-                                if !error.is_null() {
-                                    let msg: *const NSString = msg_send![error, localizedDescription];
-                                    show_error(Error::msg((*msg).as_str()).context(ttl!("read-file-failed")));
-                                }
-                            } else {
-                                extern "C" {
-                                    #[allow(improper_ctypes)]
-                                    pub fn NSTemporaryDirectory() -> *mut NSString;
-                                }
-                                let dir = NSTemporaryDirectory();
-                                let uuid: ObjcId = msg_send![class!(NSUUID), UUID];
-                                let uuid: *mut NSString = msg_send![uuid, UUIDString];
-                                let path = format!("{}{}", (*dir).as_str(), (*uuid).as_str());
-                                let _: () = msg_send![data, writeToFile: str_to_ns(&path) atomically: YES];
+                            if let Some(path) = copy_shared_file(url) {
                                 CHOSEN_FILE.lock().unwrap().1 = Some(path);
                             }
                         }
@@ -269,7 +343,8 @@ pub fn request_file(id: impl Into<String>) {
                         let tp: ObjcId = msg_send![tp_cls, typeWithFilenameExtension: str_to_ns(e)];
                         std::mem::transmute::<_, ShareId<NSObject>>(ShareId::from_ptr(tp))
                     };
-                    let types = NSArray::from_slice(&[ext("zip"), ext("pez"), ext("jpg"), ext("png"), ext("jpeg"), ext("json"), ext("mp3"), ext("ogg")]);
+                    let types = kind.extensions().iter().map(|it| ext(it)).collect::<Vec<_>>();
+                    let types = NSArray::from_slice(&types);
                     let types: ObjcId = std::mem::transmute(types);
                     msg_send![picker, initForOpeningContentTypes: types]
                 } else {
@@ -291,9 +366,54 @@ pub fn request_file(id: impl Into<String>) {
                 ];
             }
         } else { // desktop
-            CHOSEN_FILE.lock().unwrap().1 = rfd::FileDialog::new().pick_file().map(|it| it.display().to_string());
+            let mut dialog = rfd::FileDialog::new();
+            if kind != FileKind::Any {
+                dialog = dialog.add_filter(kind.label(), kind.extensions());
+            }
+            let picked = dialog.pick_file();
+            let mut chosen = CHOSEN_FILE.lock().unwrap();
+            match picked {
+                Some(path) => chosen.1 = Some(path.display().to_string()),
+                // nothing is going to arrive, so don't leave the request hanging
+                None => chosen.0 = None,
+            }
+        }
+    }
+}
+
+// iOS only lends access to files from the picker or other apps, so they are copied into the temporary directory;
+// the extension is kept for importers that go by it
+#[cfg(target_os = "ios")]
+pub unsafe fn copy_shared_file(url: crate::objc::ObjcId) -> Option<String> {
+    use crate::objc::*;
+    let need_close: bool = msg_send![url, startAccessingSecurityScopedResource];
+    let mut error: ObjcId = std::ptr::null_mut();
+    let data: ObjcId = msg_send![class!(NSData), dataWithContentsOfURL: url options: 2 error: &mut error as *mut ObjcId];
+    if need_close {
+        let _: () = msg_send![url, stopAccessingSecurityScopedResource];
+    }
+    if data.is_null() {
+        show_message(ttl!("read-file-failed")).error();
+        if !error.is_null() {
+            let msg: *const NSString = msg_send![error, localizedDescription];
+            show_error(Error::msg((*msg).as_str()).context(ttl!("read-file-failed")));
         }
+        return None;
+    }
+    extern "C" {
+        #[allow(improper_ctypes)]
+        pub fn NSTemporaryDirectory() -> *mut NSString;
     }
+    let dir = NSTemporaryDirectory();
+    let uuid: ObjcId = msg_send![class!(NSUUID), UUID];
+    let uuid: *mut NSString = msg_send![uuid, UUIDString];
+    let ext: *mut NSString = msg_send![url, pathExtension];
+    let mut path = format!("{}{}", (*dir).as_str(), (*uuid).as_str());
+    if !ext.is_null() && !(*ext).as_str().is_empty() {
+        path = format!("{path}.{}", (*ext).as_str());
+    }
+    let _: () = msg_send![data, writeToFile: str_to_ns(&path) atomically: YES];
+    Some(path)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -334,6 +454,18 @@ pub trait Scene {
     fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
         NextScene::None
     }
+    // nothing on screen moves without input, so the frame rate may drop
+    fn idle(&self) -> bool {
+        false
+    }
+    // whether an uncaught error may interrupt this scene with a dialog; otherwise it only shows up as a message
+    fn modal_errors(&self) -> bool {
+        true
+    }
+    // shown in the memory overlay
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>().rsplit("::").next().unwrap()
+    }
 }
 
 pub trait RenderTargetChooser {
@@ -353,6 +485,8 @@ impl<F: FnMut() -> Option<RenderTarget>> RenderTargetChooser for F {
 pub struct Main {
     pub scenes: Vec<Box<dyn Scene>>,
     times: Vec<f64>,
+    // memory use when each scene last came to the top
+    snapshots: Vec<MemorySnapshot>,
     target_chooser: Box<dyn RenderTargetChooser>,
     tm: TimeManager,
     paused: bool,
@@ -361,6 +495,8 @@ pub struct Main {
     pub top_level: bool,
     touches: Option<Vec<Touch>>,
     pub viewport: Option<(i32, i32, i32, i32)>,
+    // real time of the last touch or key press
+    last_input: f64,
 }
 
 impl Main {
@@ -378,6 +514,7 @@ impl Main {
         Ok(Self {
             scenes: vec![scene],
             times: Vec::new(),
+            snapshots: vec![MemorySnapshot::take()],
             target_chooser: Box::new(target_chooser),
             tm,
             paused: false,
@@ -386,6 +523,7 @@ impl Main {
             top_level: true,
             touches: None,
             viewport: None,
+            last_input: 0.,
         })
     }
 
@@ -393,6 +531,21 @@ impl Main {
         self.update_with_mutate(|_| {})
     }
 
+    // what's still around compared to when the scene below last came to the top is put on the closed scene, which
+    // includes what went into building it
+    fn pop_scene(&mut self) {
+        let name = self.scenes.pop().unwrap().name();
+        self.tm.seek_to(self.times.pop().unwrap());
+        self.snapshots.pop();
+        report_scene_exit(name, self.snapshots.last().unwrap());
+    }
+
+    fn reenter(&mut self) -> Result<()> {
+        self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+        *self.snapshots.last_mut().unwrap() = MemorySnapshot::take();
+        Ok(())
+    }
+
     pub fn update_with_mutate(&mut self, f: impl Fn(&mut Touch)) -> Result<()> {
         if self.paused {
             return Ok(());
@@ -400,30 +553,26 @@ impl Main {
         match self.scenes.last_mut().unwrap().next_scene(&mut self.tm) {
             NextScene::None => {}
             NextScene::Pop => {
-                self.scenes.pop();
-                self.tm.seek_to(self.times.pop().unwrap());
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+                self.pop_scene();
+                self.reenter()?;
             }
             NextScene::PopN(num) => {
                 for _ in 0..num {
-                    self.scenes.pop();
-                    self.tm.seek_to(self.times.pop().unwrap());
+                    self.pop_scene();
                 }
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+                self.reenter()?;
             }
             NextScene::PopWithResult(result) => {
-                self.scenes.pop();
-                self.tm.seek_to(self.times.pop().unwrap());
+                self.pop_scene();
                 self.scenes.last_mut().unwrap().on_result(&mut self.tm, result)?;
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+                self.reenter()?;
             }
             NextScene::PopNWithResult(num, result) => {
                 for _ in 0..num {
-                    self.scenes.pop();
-                    self.tm.seek_to(self.times.pop().unwrap());
+                    self.pop_scene();
                 }
                 self.scenes.last_mut().unwrap().on_result(&mut self.tm, result)?;
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+                self.reenter()?;
             }
             NextScene::Exit => {
                 self.should_exit = true;
@@ -432,15 +581,20 @@ impl Main {
                 self.times.push(self.tm.now());
                 scene.enter(&mut self.tm, self.target_chooser.choose())?;
                 self.scenes.push(scene);
+                self.snapshots.push(MemorySnapshot::take());
             }
             NextScene::Replace(mut scene) => {
                 scene.enter(&mut self.tm, self.target_chooser.choose())?;
                 *self.scenes.last_mut().unwrap() = scene;
+                *self.snapshots.last_mut().unwrap() = MemorySnapshot::take();
             }
         }
         Judge::on_new_frame();
         let mut touches = Judge::get_touches(1.0);
         touches.iter_mut().for_each(f);
+        if !touches.is_empty() || !get_keys_down().is_empty() {
+            self.last_input = self.tm.real_time();
+        }
         if !touches.is_empty() {
             let now = self.tm.now();
             let delta = (now - self.last_update_time) / touches.len() as f64;
@@ -513,6 +667,8 @@ impl Main {
                     dialog.render(&mut ui, self.tm.now() as _);
                 }
             });
+            render_perf_overlay(&mut ui);
+            render_memory_overlay(&mut ui);
             pop_camera_state();
         }
         Ok(())
@@ -536,9 +692,27 @@ impl Main {
         self.scenes.last_mut().unwrap().foucus_resume(&mut self.tm)
     }
 
+    pub fn show_error(&self, error: Error) {
+        if self.scenes.last().map_or(true, |it| it.modal_errors()) {
+            show_error(error);
+        } else {
+            warn!("uncaught error: {error:?}");
+            show_message(format!("{error:#}")).error();
+        }
+    }
+
     pub fn should_exit(&self) -> bool {
         self.should_exit
     }
+
+    // whether the app has been left alone on a still screen for a while
+    pub fn idle(&self) -> bool {
+        const IDLE_AFTER: f64 = 3.;
+        !self.paused
+            && self.tm.real_time() - self.last_input > IDLE_AFTER
+            && self.scenes.last().unwrap().idle()
+            && BILLBOARD.with(|it| it.borrow().0.is_empty())
+    }
 }
 
 fn draw_background(tex: Texture2D, dim: bool) {
@@ -550,4 +724,41 @@ fn draw_background(tex: Texture2D, dim: bool) {
     }
 }
 
+// `tex` laid out over the whole window like the background, but only drawn around the chart viewport `vp`, which
+// is centered in `window`
+fn draw_letterbox(tex: Texture2D, window: (i32, i32, i32, i32), vp: (i32, i32, i32, i32)) {
+    let top = window.3 as f32 / window.2 as f32;
+    let full = Rect::new(-1., -top, 2., top * 2.);
+    let Some(source) = source_of_image(&tex, full, ScaleType::CropCenter) else {
+        return;
+    };
+    let (hw, hh) = (vp.2 as f32 / window.2 as f32, vp.3 as f32 / window.2 as f32);
+    let bars = [
+        Rect::new(-1., -top, 1. - hw, top * 2.),
+        Rect::new(hw, -top, 1. - hw, top * 2.),
+        Rect::new(-hw, -top, hw * 2., top - hh),
+        Rect::new(-hw, hh, hw * 2., top - hh),
+    ];
+    let (w, h) = (tex.width(), tex.height());
+    for bar in bars.into_iter().filter(|it| it.w > 0. && it.h > 0.) {
+        let part = Rect::new(
+            source.x + (bar.x - full.x) / full.w * source.w,
+            source.y + (bar.y - full.y) / full.h * source.h,
+            bar.w / full.w * source.w,
+            bar.h / full.h * source.h,
+        );
+        draw_texture_ex(
+            tex,
+            bar.x,
+            bar.y,
+            WHITE,
+            DrawTextureParams {
+                source: Some(Rect::new(part.x * w, part.y * h, part.w * w, part.h * h)),
+                dest_size: Some(bar.size()),
+                ..Default::default()
+            },
+        );
+    }
+}
+
 pub type LocalSceneTask = LocalTask<Result<NextScene>>;
@@ -2,10 +2,11 @@ crate::tl_file!("ending");
 
 use super::{draw_background, game::{SimpleRecord, GameScene}, loading::UploadFn, NextScene, Scene};
 use crate::{
-    config::Config,
+    config::{Config, JudgeWindows, Mods},
+    core::BackgroundBlur,
     ext::{
-        create_audio_manger, draw_illustration, draw_parallelogram, draw_parallelogram_ex, draw_text_aligned, draw_text_aligned_opt_width, SafeTexture, ScaleType,
-        PARALLELOGRAM_SLOPE,
+        create_audio_manger, draw_illustration, draw_parallelogram, draw_parallelogram_ex, draw_text_aligned, draw_text_aligned_opt_width, screenshot,
+        SafeTexture, ScaleType, PARALLELOGRAM_SLOPE,
     },
     info::ChartInfo,
     judge::{icon_index, PlayResult},
@@ -18,7 +19,7 @@ use anyhow::Result;
 use macroquad::prelude::*;
 use sasa::{AudioClip, AudioManager, Music, MusicParams};
 use serde::Deserialize;
-use std::{cell::RefCell, ops::DerefMut};
+use std::{cell::RefCell, ops::DerefMut, sync::Arc};
 
 #[derive(Deserialize)]
 pub struct RecordUpdateState {
@@ -29,7 +30,7 @@ pub struct RecordUpdateState {
 }
 
 pub struct EndingScene {
-    background: SafeTexture,
+    background: BackgroundBlur,
     illustration: SafeTexture,
     player: SafeTexture,
     icon: SafeTexture,
@@ -59,9 +60,15 @@ pub struct EndingScene {
 
     btn_retry: RectButton,
     btn_proceed: RectButton,
+    btn_timing: RectButton,
+    show_timing: bool,
+    timing_bins: [u32; TIMING_BINS],
+    windows: JudgeWindows,
     config: Config,
 }
 
+const TIMING_BINS: usize = 33;
+
 impl EndingScene {
     pub const BPM_WAIT_TIME: f64 = 0.70;
     pub fn new(
@@ -91,11 +98,17 @@ impl EndingScene {
                 ..Default::default()
             },
         )?;
+        let windows = config.judge_windows();
+        let mut timing_bins = [0; TIMING_BINS];
+        for delta in &result.deltas {
+            let index = ((delta / windows.bad + 1.) / 2. * TIMING_BINS as f32) as usize;
+            timing_bins[index.min(TIMING_BINS - 1)] += 1;
+        }
         let upload_task = upload_fn
             .as_ref()
-            .and_then(|f| record_data.clone().map(|data| (f(data), show_message(tl!("uploading")).handle())));
+            .and_then(|f| Some((f(record_data.clone()?, record.as_ref()?), show_message(tl!("uploading")).handle())));
         Ok(Self {
-            background,
+            background: BackgroundBlur::new(background)?,
             illustration,
             player,
             icon: icons[index].clone(),
@@ -134,6 +147,10 @@ impl EndingScene {
 
             btn_retry: RectButton::new(),
             btn_proceed: RectButton::new(),
+            btn_timing: RectButton::new(),
+            show_timing: false,
+            timing_bins,
+            windows,
             config: config.clone()
         })
     }
@@ -180,6 +197,10 @@ impl Scene for EndingScene {
             }
             return Ok(true);
         }
+        if !self.result.deltas.is_empty() && self.btn_timing.touch(touch) {
+            self.show_timing ^= true;
+            return Ok(true);
+        }
         Ok(false)
     }
 
@@ -193,7 +214,8 @@ impl Scene for EndingScene {
             self.upload_task = self
                 .record_data
                 .clone()
-                .map(|data| ((self.upload_fn.as_ref().unwrap())(data), show_message(tl!("uploading")).handle()));
+                .zip(self.record.as_ref())
+                .map(|(data, record)| ((self.upload_fn.as_ref().unwrap())(data, record), show_message(tl!("uploading")).handle()));
         }
         if let Some((task, handle)) = &mut self.upload_task {
             if let Some(result) = task.take() {
@@ -256,6 +278,8 @@ impl Scene for EndingScene {
             text_miss,
             text_early,
             text_late,
+            text_mean,
+            text_std,
         ) = if self.config.chinese {
             (
                 "最大连击数",
@@ -268,6 +292,8 @@ impl Scene for EndingScene {
                 "错过",
                 "太早",
                 "太晚",
+                "平均偏移",
+                "标准差",
             )
         } else {
             (
@@ -281,6 +307,8 @@ impl Scene for EndingScene {
                 "Miss",
                 "Early",
                 "Late",
+                "Mean",
+                "Std Dev",
             )
         };
             
@@ -291,10 +319,15 @@ impl Scene for EndingScene {
         let t = tm.now() as f32;
         let gl = unsafe { get_internal_gl() }.quad_gl;
         let res = &self.result;
+        let background = if self.config.render_bg {
+            Some(self.background.texture(self.info.background_blur.unwrap_or(self.config.bg_blurriness)))
+        } else {
+            None
+        };
         cam.render_target = self.target;
         set_camera(&cam);
-        if self.config.render_bg {
-            draw_background(*self.background, self.config.render_bg_dim);
+        if let Some(background) = background {
+            draw_background(background, self.config.render_bg_dim);
         }
 
         fn ran(t: f32, l: f32, r: f32) -> f32 {
@@ -340,10 +373,18 @@ impl Scene for EndingScene {
             } else {
                 format!("")
             };
+            // the mods that change the chart itself, which also keep the play unrated
+            let chart_mods = self.config.chart_mods();
+            let mod_labels = [(Mods::FLIP_Y, "FLIP Y"), (Mods::RANDOM, "RANDOM"), (Mods::NOTE_SWAP, "NOTE SWAP")]
+                .into_iter()
+                .filter(|(m, _)| chart_mods.contains(*m))
+                .map(|(_, name)| name)
+                .collect::<Vec<_>>()
+                .join(" ");
             let text = if self.autoplay {
                 format!("{text_autoplay} {spd}")
             } else if !self.rated {
-                format!("{full_screen_judge} {spd}")
+                format!("{full_screen_judge} {mod_labels} {spd}")
             } else if let Some(state) = &self.update_state {
                 format!(
                     "{spd}  {}",
@@ -356,6 +397,11 @@ impl Scene for EndingScene {
             } else {
                 "Uploading…".to_owned()
             };
+            let text = if self.result.failed {
+                format!("{} {text}", if self.config.chinese { "挑战失败" } else { "FAILED" })
+            } else {
+                text
+            };
             let pa = ran(t, A_SCORE_ALPHA_START, A_SCORE_ALPHA_END);
             let r = draw_text_aligned(ui, &text, main.x + dx + 0.01, main.bottom() - 0.040, (0., 1.), 0.34, Color::new(1., 1., 1., pa)); // 分数下面的字
             let score = (res.score / 1_000_000. * self.info.score_total as f64).round() as u32;
@@ -410,31 +456,65 @@ impl Scene for EndingScene {
         tran(gl, (1. - ran(t, C_POS_START, C_POS_END)).powi(2) + p_main);
         let s2 = Rect::new(s1.x - d * 4. * slope, s1.bottom() + d, s1.w, s1.h); // 最下面的矩形
         draw_parallelogram(s2, None, c2, true);
+        self.btn_timing.set(ui, s2);
         {
             let dy = 0.028;
             let dy2 = 0.010; // y间隔
             let bg = 0.55; // Perfect Good Bad Miss 的值的大小
             let sm = 0.21; // Perfect Good Bad Miss 的文本的大小
             let pa = ran(t, C_ALPHA_START, C_ALPHA_END);
-            let draw_count = |ui: &mut Ui, ratio: f32, name: &str, count: u32| {
-                let r = draw_text_aligned(ui, name, s2.x + s2.w * ratio, s2.bottom() - dy, (0.5, 1.), sm, Color::new(1., 1., 1., pa)); // Perfect Good Bad Miss 的文本
-                let text = if self.config.roman {GameScene::int_to_roman(count)} else if self.config.chinese {GameScene::int_to_chinese(count)} else {count.to_string()};
-                draw_text_aligned_opt_width(ui, &text, r.center().x, r.y - dy2, (0.5, 1.), bg, Color::new(1., 1., 1., pa), 0.125); // Perfect Good Bad Miss 的值
-            };
-            draw_count(ui, 0.127, text_perfect, res.counts[0]);
-            draw_count(ui, 0.325, text_good, res.counts[1]);
-            draw_count(ui, 0.46, text_bad, res.counts[2]);
-            draw_count(ui, 0.595, text_miss, res.counts[3]);
-
-            let sm = 0.32; // Early Late 文本大小
-            let l = s2.x + s2.w * 0.72; // 文本x
-            let rt = s2.x + s2.w * 0.930; // 值x
-            let cy = s2.center().y; // 文本y中心
-            let (early, late) = if self.config.roman {(GameScene::int_to_roman(res.early), GameScene::int_to_roman(res.late))} else if self.config.chinese {(GameScene::int_to_chinese(res.early), GameScene::int_to_chinese(res.late))} else {(res.early.to_string(), res.late.to_string())};
-            let r = draw_text_aligned(ui, text_early, l, cy, (0., 1.), sm, Color::new(1., 1., 1., pa)); // Early
-            draw_text_aligned_opt_width(ui, &early, rt, r.bottom(), (1., 1.), sm, Color::new(1., 1., 1., pa), 0.1);
-            let r = draw_text_aligned(ui, text_late, l, cy + dy2 / 2.3, (0., 0.), sm, Color::new(1., 1., 1., pa)); // Late
-            draw_text_aligned_opt_width(ui, &late, rt, r.y, (1., 0.), sm, Color::new(1., 1., 1., pa), 0.1);
+            if self.show_timing {
+                // histogram of hit deltas, early on the left
+                let g = Rect::new(s2.x + s2.w * 0.09, s2.y + s2.h * 0.18, s2.w * 0.55, s2.h * 0.64);
+                let bw = g.w / TIMING_BINS as f32;
+                let max = self.timing_bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+                for (i, count) in self.timing_bins.iter().enumerate() {
+                    let center = ((i as f32 + 0.5) / TIMING_BINS as f32 * 2. - 1.) * self.windows.bad;
+                    let color = if center.abs() <= self.windows.perfect {
+                        Color::new(1., 0.92, 0.63, pa)
+                    } else {
+                        Color::new(0.63, 0.93, 1., pa)
+                    };
+                    let h = g.h * *count as f32 / max;
+                    draw_rectangle(g.x + bw * i as f32 + bw * 0.1, g.bottom() - h, bw * 0.8, h, color);
+                }
+                draw_rectangle(g.center().x - 0.001, g.y, 0.002, g.h, Color::new(1., 1., 1., pa * 0.6));
+                let mean_x = g.center().x + self.result.mean / self.windows.bad * g.w / 2.;
+                draw_rectangle(mean_x - 0.001, g.y, 0.002, g.h, Color::new(1., 0.4, 0.4, pa));
+                draw_text_aligned(ui, text_early, g.x, g.bottom() + 0.004, (0., 0.), 0.2, Color::new(1., 1., 1., pa * 0.7));
+                draw_text_aligned(ui, text_late, g.right(), g.bottom() + 0.004, (1., 0.), 0.2, Color::new(1., 1., 1., pa * 0.7));
+
+                let sm = 0.32;
+                let l = s2.x + s2.w * 0.70;
+                let rt = s2.x + s2.w * 0.930;
+                let cy = s2.center().y;
+                let mean = format!("{:+}ms", (self.result.mean * 1000.).round() as i32);
+                let std = format!("{}ms", (self.result.std * 1000.).round() as i32);
+                let r = draw_text_aligned(ui, text_mean, l, cy, (0., 1.), sm, Color::new(1., 1., 1., pa));
+                draw_text_aligned_opt_width(ui, &mean, rt, r.bottom(), (1., 1.), sm, Color::new(1., 1., 1., pa), 0.1);
+                let r = draw_text_aligned(ui, text_std, l, cy + dy2 / 2.3, (0., 0.), sm, Color::new(1., 1., 1., pa));
+                draw_text_aligned_opt_width(ui, &std, rt, r.y, (1., 0.), sm, Color::new(1., 1., 1., pa), 0.1);
+            } else {
+                let draw_count = |ui: &mut Ui, ratio: f32, name: &str, count: u32| {
+                    let r = draw_text_aligned(ui, name, s2.x + s2.w * ratio, s2.bottom() - dy, (0.5, 1.), sm, Color::new(1., 1., 1., pa)); // Perfect Good Bad Miss 的文本
+                    let text = if self.config.roman {GameScene::int_to_roman(count)} else if self.config.chinese {GameScene::int_to_chinese(count)} else {count.to_string()};
+                    draw_text_aligned_opt_width(ui, &text, r.center().x, r.y - dy2, (0.5, 1.), bg, Color::new(1., 1., 1., pa), 0.125); // Perfect Good Bad Miss 的值
+                };
+                draw_count(ui, 0.127, text_perfect, res.counts[0]);
+                draw_count(ui, 0.325, text_good, res.counts[1]);
+                draw_count(ui, 0.46, text_bad, res.counts[2]);
+                draw_count(ui, 0.595, text_miss, res.counts[3]);
+
+                let sm = 0.32; // Early Late 文本大小
+                let l = s2.x + s2.w * 0.72; // 文本x
+                let rt = s2.x + s2.w * 0.930; // 值x
+                let cy = s2.center().y; // 文本y中心
+                let (early, late) = if self.config.roman {(GameScene::int_to_roman(res.early), GameScene::int_to_roman(res.late))} else if self.config.chinese {(GameScene::int_to_chinese(res.early), GameScene::int_to_chinese(res.late))} else {(res.early.to_string(), res.late.to_string())};
+                let r = draw_text_aligned(ui, text_early, l, cy, (0., 1.), sm, Color::new(1., 1., 1., pa)); // Early
+                draw_text_aligned_opt_width(ui, &early, rt, r.bottom(), (1., 1.), sm, Color::new(1., 1., 1., pa), 0.1);
+                let r = draw_text_aligned(ui, text_late, l, cy + dy2 / 2.3, (0., 0.), sm, Color::new(1., 1., 1., pa)); // Late
+                draw_text_aligned_opt_width(ui, &late, rt, r.y, (1., 0.), sm, Color::new(1., 1., 1., pa), 0.1);
+            }
         }
         gl.pop_model_matrix();
 
@@ -537,6 +617,13 @@ impl Scene for EndingScene {
             .color(color)
             .draw();
 
+        // kept along with the record in case it turns out to be a personal best
+        if self.target.is_none() && !self.show_timing && t >= MAIN_POS_END + 0.5 {
+            if let Some(record) = self.record.as_mut().filter(|it| it.screenshot.is_none()) {
+                record.screenshot = Some(Arc::new(screenshot()));
+            }
+        }
+
         Ok(())
     }
 
@@ -4,28 +4,32 @@ crate::tl_file!("game");
 
 use chinese_number::{ChineseCase, ChineseCountMethod, ChineseVariant, NumberToChinese};
 use super::{
-    draw_background,
+    draw_background, draw_letterbox,
     ending::RecordUpdateState,
-    loading::{BasicPlayer, UpdateFn, UploadFn},
-    request_input, return_input, show_message, take_input, EndingScene, NextScene, Scene,
+    loading::{BasicPlayer, RenderFn, UpdateFn, UploadFn},
+    request_input, return_input, show_error, show_message, take_input, EndingScene, NextScene, Scene,
 };
+#[cfg(feature = "script")]
+use crate::core::ChartScript;
 use crate::{
     bin::BinaryReader,
-    config::{Config, Mods},
-    core::{BadNote, Chart, ChartExtra, Effect, Point, Resource, UIElement, BUFFER_SIZE},
-    ext::{draw_text_aligned, draw_text_aligned_opt_width, ease_in_out_quartic, get_latency, parse_time, push_frame_time, screen_aspect, semi_white, validate_combo, RectExt, SafeTexture},
-    fs::FileSystem,
+    config::{Config, HudPosition, HudTransform, Mods, ProgressBarStyle},
+    core::{first_note_lead_in, quiet_intro, BadNote, Chart, ChartExtra, Effect, LoadProgress, LoadStage, Matrix, MusicTrack, Point, Resource, ResourcePack, StreamedMusic, UIElement, Vector, BUFFER_SIZE},
+    ext::{draw_text_aligned, draw_text_aligned_opt_width, ease_in_out_quartic, get_latency, parse_time, poll_future, push_frame_time, screen_aspect, semi_white, validate_combo, LocalTask, RectExt, SafeTexture},
+    fs::{FileSystem, FileWatcher},
     gyro::GYRO,
     info::{ChartFormat, ChartInfo},
-    judge::Judge,
-    parse::{parse_extra, parse_pec, parse_phigros, parse_rpe},
+    judge::{key_lanes, Judge, JudgeStatus},
+    live::{self, LiveState, LiveStats},
+    parse::{parse_extra, parse_pec, parse_phigros_bytes, parse_rpe_bytes},
     time::TimeManager,
-    ui::{RectButton, Ui}
+    ui::{perf_overlay_enabled, report_clock_drift, report_note_stats, set_memory_overlay, set_perf_overlay, RectButton, Ui}
 };
 use anyhow::{bail, Context, Result};
 use concat_string::concat_string;
+use image::RgbaImage;
 use macroquad::{prelude::*, window::InternalGlContext};
-use sasa::{Music, MusicParams};
+use sasa::{AudioClip, MusicParams};
 use serde::{Deserialize, Serialize};
 use std::{
     io::Cursor,
@@ -35,22 +39,63 @@ use std::{
 use tracing::{debug, warn};
 
 const PAUSE_CLICK_INTERVAL: f32 = 0.7;
+const CHART_OFFSET_STEP: f32 = 0.005;
+const BLUR_STEP: f32 = 10.;
+const MAX_BLUR: f32 = 200.;
 
 #[cfg(feature = "closed")]
 mod inner;
 #[cfg(feature = "closed")]
 use inner::*;
 
+mod inspector;
+use inspector::LineInspector;
+
 pub const WAIT_TIME: f32 = 0.5;
 const AFTER_TIME: f32 = 0.7;
 const PAUSE_BACKGROUND_ALPHA: f32 = 0.6;
 
+// miss ducking assist: kicks in after this many bads/misses in a row, then recovers over `DUCK_RELEASE` seconds
+const DUCK_STREAK: u32 = 3;
+const DUCK_RELEASE: f32 = 2.;
+const DUCK_DEPTH: f32 = 0.6;
+const DUCK_HITSOUND_BOOST: f32 = 0.5;
+
+// intros quieter than this many seconds aren't worth skipping
+const INTRO_SKIP_MIN: f32 = 4.;
+
+// resuming from the pause menu plays this many seconds again with a countdown over them
+const RESUME_REWIND: f64 = 2.;
+const RESUME_COUNTDOWN: f64 = 3.;
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SimpleRecord {
     pub score: u32,
     pub accuracy: f32,
     pub full_combo: bool,
+    // hit deltas of the play, kept out of the record list and saved per chart by the launcher
+    #[serde(skip)]
+    pub deltas: Vec<f32>,
+    #[serde(default)]
+    pub max_combo: u32,
+    // perfect, good, bad and miss
+    #[serde(default)]
+    pub counts: [u32; 4],
+    // the chart speed, only when it wasn't 1x
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    // FLIP_Y, RANDOM and NOTE_SWAP, with the seed RANDOM used
+    #[serde(default, skip_serializing_if = "Mods::is_empty")]
+    pub chart_mods: Mods,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<u64>,
+    // the result screen, taken by the ending scene
+    #[serde(skip)]
+    pub screenshot: Option<Arc<RgbaImage>>,
+    // average frame rate while playing, only kept for achievements
+    #[serde(skip)]
+    pub fps: Option<f32>,
 }
 
 impl SimpleRecord {
@@ -58,6 +103,9 @@ impl SimpleRecord {
         let mut changed = false;
         if other.score > self.score {
             self.score = other.score;
+            self.speed = other.speed;
+            self.chart_mods = other.chart_mods;
+            self.random_seed = other.random_seed;
             changed = true;
         }
         if other.accuracy > self.accuracy {
@@ -112,6 +160,37 @@ pub struct PauseRewind {
     dim: bool,
 }
 
+// notes judged before pausing are played again by autoplay during the resume countdown, on a judge of its own so
+// that nothing counts twice, and get their real judge state back once the replay reaches where the pause was
+struct ResumeReplay {
+    until: f32,
+    judge: Judge,
+    snapshot: Vec<Vec<JudgeStatus>>,
+}
+
+// frames drawn while playing and the real time they took
+#[derive(Default)]
+struct FrameCounter {
+    frames: u32,
+    time: f64,
+    last: Option<f64>,
+}
+
+impl FrameCounter {
+    fn tick(&mut self, now: f64, counting: bool) {
+        let Some(last) = self.last.replace(now) else { return };
+        // longer gaps are loading or the app being in the background
+        if counting && now - last < 1. {
+            self.frames += 1;
+            self.time += now - last;
+        }
+    }
+
+    fn fps(&self) -> Option<f32> {
+        (self.time > 0.).then(|| (self.frames as f64 / self.time) as f32)
+    }
+}
+
 pub struct GameScene {
     should_exit: bool,
     next_scene: Option<NextScene>,
@@ -130,23 +209,41 @@ pub struct GameScene {
     exercise_press: Option<(i8, u64)>,
     exercise_btns: (RectButton, RectButton),
 
-    pub music: Music,
+    pub music: MusicTrack,
 
     state: State,
     pub last_update_time: f64,
     pause_rewind: PauseRewind,
+    replay: Option<ResumeReplay>,
     pause_first_time: f32,
+    failed: bool,
+    // seeked backwards outside exercise, notes played again would count twice so the run can't be recorded
+    rewound: bool,
+    frame_counter: FrameCounter,
+    // chart time the skip intro button jumps to, if the music starts quietly enough to offer it
+    intro_skip: Option<f32>,
 
     pub bad_notes: Vec<BadNote>,
+    duck: f32,
+    inspector: LineInspector,
 
     upload_fn: Option<UploadFn>,
     update_fn: Option<UpdateFn>,
+    render_fn: Option<RenderFn>,
 
     pub touch_points: Vec<(f32, f32)>,
+
+    hot_reload: Option<(FileWatcher, Box<dyn FileSystem>)>,
+    // the chart was swapped mid-play, the judge only saw the part after it so the run can't be recorded
+    hot_reloaded: bool,
+    reload_task: LocalTask<Result<(Chart, Option<AudioClip>)>>,
+    // pack picked from the pause menu, with the path it was loaded from
+    res_pack_task: LocalTask<Result<(Option<String>, ResourcePack)>>,
 }
 
 macro_rules! reset {
     ($self:ident, $res:expr, $tm:ident) => {{
+        $self.replay = None;
         $self.bad_notes.clear();
         $self.judge.reset();
         $self.chart.reset();
@@ -157,6 +254,10 @@ macro_rules! reset {
         $tm.reset();
         $self.last_update_time = $tm.now();
         $self.state = State::Starting;
+        $self.failed = false;
+        $self.rewound = false;
+        $self.hot_reloaded = false;
+        $self.frame_counter = FrameCounter::default();
         $self.pause_rewind = PauseRewind {
             time: None,
             duration: None,
@@ -165,17 +266,50 @@ macro_rules! reset {
     }};
 }
 
+// ends the resume replay, if any, putting back the judge state from before it
+macro_rules! finish_replay {
+    ($self:ident) => {{
+        if let Some(replay) = $self.replay.take() {
+            $self.chart.restore_judge(replay.snapshot);
+        }
+    }};
+}
+
+// `$time` is in music time
+macro_rules! seek_videos {
+    ($self:ident, $res:expr, $time:expr) => {{
+        let offset = $self.chart.offset + $self.info_offset + $res.config.offset;
+        $self.chart.seek_videos(($time as f32 - offset).max(0.));
+    }};
+}
+
+// `$time` is in music time; music, clock and videos move together, and seeking backwards brings the notes back
+macro_rules! seek {
+    ($self:ident, $res:expr, $tm:ident, $time:expr) => {{
+        let time = $time as f64;
+        finish_replay!($self);
+        let back = time < $tm.now();
+        $self.music.seek_to(time)?;
+        $tm.seek_to(time);
+        seek_videos!($self, $res, time);
+        if back {
+            let t = time as f32 - ($self.chart.offset + $self.info_offset + $res.config.offset);
+            $self.chart.rewind(t);
+            $self.judge.rewind(&$self.chart, t);
+            $self.bad_notes.clear();
+            if $self.mode != GameMode::Exercise {
+                $self.rewound = true;
+            }
+        }
+    }};
+}
+
 macro_rules! reset_music_speed {
     ($self:ident, $res:expr, $tm:ident) => {{
         debug!("recreate music");
-        $self.music = $res.audio.create_music(
-            $res.music.clone(),
-            MusicParams {
-                amplifier: $res.config.volume_music as _,
-                playback_rate: $res.config.speed as _,
-                ..Default::default()
-            },
-        ).expect("failed to create music");
+        $res.stretch_music();
+        $res.premix_hitsounds(&$self.chart, $self.chart.offset + $self.info_offset + $res.config.offset);
+        $self.music = GameScene::new_music(&mut $res).expect("failed to create music");
         $tm.pause();
         $self.music.pause().ok();
         let now = $tm.now();
@@ -204,6 +338,25 @@ impl GameScene {
         bail!("Cannot find chart file")
     }
 
+    // the format in the info, or a guess from the content when there's none
+    pub fn chart_format(info: &ChartInfo, bytes: &[u8]) -> ChartFormat {
+        info.format.clone().unwrap_or_else(|| {
+            if let Ok(text) = std::str::from_utf8(bytes) {
+                if text.starts_with('{') {
+                    if text.contains("\"META\"") {
+                        ChartFormat::Rpe
+                    } else {
+                        ChartFormat::Pgr
+                    }
+                } else {
+                    ChartFormat::Pec
+                }
+            } else {
+                ChartFormat::Pbc
+            }
+        })
+    }
+
     pub fn int_to_roman(mut num: u32) -> String {
         if num.to_string() == "0" {
             return "-".to_string()
@@ -244,6 +397,39 @@ impl GameScene {
         
     }
 
+    fn acc_hud_lines(judge: &Judge, config: &Config) -> Vec<String> {
+        let mut lines = vec![format!("{:05.2}%", judge.real_time_accuracy() * 100.)];
+        if config.acc_hud_counts {
+            let [perfect, good, bad, miss] = judge.counts();
+            lines.push(format!("P {perfect}  G {good}  B {bad}  M {miss}"));
+            let (early, late) = judge.early_late(config.judge_windows().perfect);
+            let (text_early, text_late) = if config.chinese { ("过早", "过晚") } else { ("Early", "Late") };
+            lines.push(format!("{text_early} {early}  {text_late} {late}"));
+        }
+        lines
+    }
+
+    // draws the lines stacked from (x, y), growing downwards or upwards
+    fn render_acc_hud(ui: &mut Ui, lines: &[String], x: f32, y: f32, anchor_x: f32, downwards: bool, size: f32, color: Color) {
+        let step = size * 0.09;
+        for (i, line) in lines.iter().enumerate() {
+            let (dy, anchor_y) = if downwards { (i as f32 * step, 0.) } else { (-((lines.len() - 1 - i) as f32) * step, 1.) };
+            ui.text(line).pos(x, y + dy).anchor(anchor_x, anchor_y).size(size).color(color).draw();
+        }
+    }
+
+    // moves and scales a HUD element about its anchor as set up in the layout editor
+    fn with_layout<R>(ui: &mut Ui, layout: HudTransform, anchor: (f32, f32), f: impl FnOnce(&mut Ui) -> R) -> R {
+        if layout == HudTransform::IDENTITY {
+            return f(ui);
+        }
+        let pt = Vector::new(anchor.0, anchor.1);
+        let matrix = Matrix::new_translation(&-pt)
+            .append_scaling(layout.scale)
+            .append_translation(&(pt + Vector::new(layout.dx, layout.dy)));
+        ui.with(matrix, f)
+    }
+
     pub fn int_to_chinese(num: u32) -> String {
         num.to_chinese(ChineseVariant::Simple, ChineseCase::Lower, ChineseCountMethod::TenThousand).unwrap()
     }
@@ -306,43 +492,49 @@ impl GameScene {
     
 
     pub async fn load_chart(fs: &mut dyn FileSystem, info: &ChartInfo, config: &Config) -> Result<(Chart, ChartFormat)> {
+        Self::load_chart_with_progress(fs, info, config, &LoadProgress::default()).await
+    }
+
+    pub async fn load_chart_with_progress(
+        fs: &mut dyn FileSystem,
+        info: &ChartInfo,
+        config: &Config,
+        progress: &LoadProgress,
+    ) -> Result<(Chart, ChartFormat)> {
+        progress.enter(LoadStage::Parse, 3);
         let extra = if config.render_extra {
             if let Some(extra) = fs.load_file("extra.json").await.ok().map(String::from_utf8).transpose()? {
-                parse_extra(&extra, fs).await.context("Failed to parse extra")?
+                parse_extra(&extra, fs, config.safe_mode).await.context("Failed to parse extra")?
             } else if let Some(extra) = fs.load_file("extra1.json").await.ok().map(String::from_utf8).transpose()? {
-                parse_extra(&extra, fs).await.context("Failed to parse extra1")?
+                parse_extra(&extra, fs, config.safe_mode).await.context("Failed to parse extra1")?
             } else {
                 ChartExtra::default()
             }
         } else {
             ChartExtra::default()
         };
+        progress.step();
         let bytes = Self::load_chart_bytes(fs, info).await.context("Failed to load chart")?;
-        let format = info.format.clone().unwrap_or_else(|| {
-            if let Ok(text) = std::str::from_utf8(&bytes) {
-                if text.starts_with('{') {
-                    if text.contains("\"META\"") {
-                        ChartFormat::Rpe
-                    } else {
-                        ChartFormat::Pgr
-                    }
-                } else {
-                    ChartFormat::Pec
-                }
-            } else {
-                ChartFormat::Pbc
-            }
-        });
+        progress.step();
+        let format = Self::chart_format(info, &bytes);
         let mut chart = match format {
-            ChartFormat::Rpe => parse_rpe(&String::from_utf8_lossy(&bytes), fs, extra).await,
-            ChartFormat::Pgr => parse_phigros(&String::from_utf8_lossy(&bytes), extra),
+            ChartFormat::Rpe => parse_rpe_bytes(bytes, fs, extra).await,
+            ChartFormat::Pgr => parse_phigros_bytes(bytes, extra).await,
             ChartFormat::Pec => parse_pec(&String::from_utf8_lossy(&bytes), extra),
             ChartFormat::Pbc => {
                 let mut r = BinaryReader::new(Cursor::new(bytes));
                 r.read()
             }
         }?;
-        chart.load_textures(fs).await?;
+        chart.apply_mods(config.chart_mods(), config.random_seed);
+        progress.step();
+        chart.load_textures(fs, progress).await?;
+        #[cfg(feature = "script")]
+        if config.render_extra {
+            if let Ok(source) = fs.load_file("script.rhai").await {
+                chart.extra.script = Some(ChartScript::new(&String::from_utf8_lossy(&source)).context("Failed to load script")?);
+            }
+        }
         Ok((chart, format))
     }
 
@@ -357,9 +549,13 @@ impl GameScene {
         illustration: SafeTexture,
         upload_fn: Option<UploadFn>,
         update_fn: Option<UpdateFn>,
+        render_fn: Option<RenderFn>,
+        progress: Arc<LoadProgress>,
     ) -> Result<Self> {
         match mode {
             GameMode::TweakOffset => {
+                // the tweaked value is saved into the chart info, keep it free of the stored one
+                config.chart_offset = 0.;
                 config.mods.insert(Mods::AUTOPLAY);
                 config.volume_music = config.volume_music.max(0.5);
                 config.volume_sfx = config.volume_sfx.max(0.5);
@@ -369,7 +565,7 @@ impl GameScene {
         let (mut chart, _) = if let Some((chart, format)) = preload_chart {
             (chart, format)
         } else {
-            Self::load_chart(fs.deref_mut(), &info, &config).await?
+            Self::load_chart_with_progress(fs.deref_mut(), &info, &config, &progress).await?
         };
         let effects = std::mem::take(&mut chart.extra.global_effects);
         if config.fxaa {
@@ -381,7 +577,19 @@ impl GameScene {
 
         let judge = Judge::new(&chart);
 
-        let info_offset = info.offset;
+        let hot_reload = if config.hot_reload {
+            FileWatcher::from_fs(fs.deref_mut()).map(|mut watcher| {
+                watcher.watch(info.chart.as_str());
+                watcher.watch(info.music.as_str());
+                watcher.watch("extra.json");
+                chart.texture_paths().for_each(|it| watcher.watch(it));
+                (watcher, fs.clone_box())
+            })
+        } else {
+            None
+        };
+
+        let info_offset = info.offset + config.chart_offset;
         let mut res = Resource::new(
             config,
             info,
@@ -390,19 +598,32 @@ impl GameScene {
             background,
             illustration,
             chart.extra.effects.is_empty() && effects.is_empty(),
+            &progress,
         )
         .await
         .context("Failed to load resources")?;
+        set_perf_overlay(res.config.perf_overlay);
+        set_memory_overlay(res.config.memory_overlay);
         let offset = chart.offset + info_offset + res.config.offset;
         let exercise_range = offset + res.config.play_start_time..res.track_length;
         
         // Prepare extra sfx from chart.hitsounds
+        res.load_pitched_sfxs(&chart);
+        res.premix_hitsounds(&chart, offset);
         chart.hitsounds.drain().for_each(|(name, clip)| {
             if let Ok(clip) = res.audio.create_sfx(clip, Some(BUFFER_SIZE)) {
                 res.extra_sfxs.insert(name, clip);
             }
         });
 
+        let intro_skip = first_note_lead_in(&chart).filter(|t| {
+            let t = t + offset;
+            t >= INTRO_SKIP_MIN && {
+                let quiet = quiet_intro(&res.source_music);
+                quiet >= INTRO_SKIP_MIN && quiet >= t / 2.
+            }
+        });
+
         let music = Self::new_music(&mut res)?;
         Ok(Self {
             should_exit: false,
@@ -431,26 +652,139 @@ impl GameScene {
                 duration: None,
                 dim: false
             },
+            replay: None,
             pause_first_time: f32::NEG_INFINITY,
+            failed: false,
+            rewound: false,
+            frame_counter: FrameCounter::default(),
+            intro_skip,
 
             bad_notes: Vec::new(),
+            duck: 0.,
+            inspector: LineInspector::default(),
 
             upload_fn,
             update_fn,
+            render_fn,
 
             touch_points: Vec::new(),
+
+            hot_reload,
+            hot_reloaded: false,
+            reload_task: None,
+            res_pack_task: None,
         })
     }
 
-    fn new_music(res: &mut Resource) -> Result<Music> {
-        res.audio.create_music(
+    fn new_music(res: &mut Resource) -> Result<MusicTrack> {
+        if let Some(bytes) = &res.streamed_music {
+            return Ok(MusicTrack::Streamed(StreamedMusic::new(bytes.clone(), res.config.volume_music)?));
+        }
+        Ok(MusicTrack::Decoded(res.audio.create_music(
             res.music.clone(),
             MusicParams {
                 amplifier: res.config.volume_music as _,
                 playback_rate: res.config.speed as _,
                 ..Default::default()
             },
-        )
+        )?))
+    }
+
+    fn update_hot_reload(&mut self, tm: &mut TimeManager) -> Result<()> {
+        if let Some(task) = &mut self.reload_task {
+            if let Some(result) = poll_future(task.as_mut()) {
+                self.reload_task = None;
+                match result {
+                    Err(err) => show_error(err.context(tl!("hot-reload-failed"))),
+                    Ok((mut chart, music)) => {
+                        let music_changed = music.is_some();
+                        if let Some(music) = music {
+                            let length = music.length() as f32;
+                            self.res.track_length = self.res.config.play_end_time.unwrap_or(length).min(length);
+                            self.res.streamed_music = None;
+                            self.res.source_music = music;
+                            self.res.stretch_music();
+                        }
+                        self.res.load_pitched_sfxs(&chart);
+                        let offset = chart.offset + self.info_offset + self.res.config.offset;
+                        self.res.premix_hitsounds(&chart, offset);
+                        if music_changed || self.res.config.premix_hitsounds {
+                            let playing = !self.music.paused();
+                            self.music = Self::new_music(&mut self.res)?;
+                            if playing {
+                                self.music.seek_to(tm.now())?;
+                                self.music.play()?;
+                            }
+                        }
+                        chart.hitsounds.drain().for_each(|(name, clip)| {
+                            if let Ok(clip) = self.res.audio.create_sfx(clip, Some(BUFFER_SIZE)) {
+                                self.res.extra_sfxs.insert(name, clip);
+                            }
+                        });
+                        self.effects = std::mem::take(&mut chart.extra.global_effects);
+                        if self.res.config.fxaa {
+                            chart
+                                .extra
+                                .effects
+                                .push(Effect::new(0.0..f32::INFINITY, include_str!("fxaa.glsl"), Vec::new(), false).unwrap());
+                        }
+                        if let Some((watcher, _)) = &mut self.hot_reload {
+                            chart.texture_paths().for_each(|it| watcher.watch(it));
+                        }
+                        self.chart.reload(chart, self.res.time);
+                        self.judge = Judge::new(&self.chart);
+                        self.hot_reloaded = true;
+                        self.bad_notes.clear();
+                        show_message(tl!("hot-reloaded")).ok();
+                    }
+                }
+            }
+            return Ok(());
+        }
+        let Some((watcher, fs)) = &mut self.hot_reload else {
+            return Ok(());
+        };
+        let changed = watcher.poll();
+        if changed.is_empty() {
+            return Ok(());
+        }
+        debug!("chart files changed: {changed:?}");
+        let reload_music = changed.contains(&self.res.info.music);
+        let mut fs = fs.clone_box();
+        let info = self.res.info.clone();
+        let config = self.res.config.clone();
+        self.reload_task = Some(Box::pin(async move {
+            let (chart, _) = Self::load_chart(fs.deref_mut(), &info, &config).await?;
+            let music = if reload_music {
+                Some(AudioClip::new(fs.load_file(&info.music).await?)?)
+            } else {
+                None
+            };
+            Ok((chart, music))
+        }));
+        Ok(())
+    }
+
+    fn update_res_pack(&mut self, tm: &mut TimeManager) -> Result<()> {
+        let Some(task) = &mut self.res_pack_task else {
+            return Ok(());
+        };
+        let Some(result) = poll_future(task.as_mut()) else {
+            return Ok(());
+        };
+        self.res_pack_task = None;
+        match result {
+            Err(err) => show_error(err.context(tl!("respack-load-failed"))),
+            Ok((path, pack)) => {
+                self.res.set_res_pack(pack)?;
+                self.res.config.res_pack_path = path;
+                // the old pack's clicks are baked into the premixed track
+                if tm.paused() && self.res.config.premix_hitsounds {
+                    reset_music_speed!(self, self.res, tm);
+                }
+            }
+        }
+        Ok(())
     }
 
     fn touch_scale(&self) -> f32 {
@@ -485,6 +819,9 @@ impl GameScene {
         let pause_w = 0.011 * scale_ratio;
         let pause_h = pause_w * 3.5;
         let pause_center = Point::new(-aspect_ratio + 0.0525 * scale_ratio, top + eps * 3.6454 - (1. - p) * 0.4 + pause_h / 2.);
+        let pause_anchor = Point::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h * 0.5);
+        let layout = res.config.hud_layout;
+        let pause_hit = pause_anchor + (pause_center - pause_anchor) * layout.pause.scale + Vector::new(layout.pause.dx, layout.pause.dy);
         if res.config.interactive
             && !tm.paused()
             && self.pause_rewind.time.is_none()
@@ -493,7 +830,7 @@ impl GameScene {
                 touch.phase == TouchPhase::Started && {
                     let p = touch.position;
                     let p = Point::new(p.x * screen_aspect, p.y * screen_aspect);
-                    (pause_center - p).norm() < 0.05
+                    (pause_hit - p).norm() < 0.05 * layout.pause.scale
                 }
             })
         {
@@ -509,7 +846,7 @@ impl GameScene {
             }
         }
         if tm.now() as f32 - self.pause_first_time <= PAUSE_CLICK_INTERVAL {
-            ui.fill_circle(pause_center.x, pause_center.y, 0.05 * scale_ratio, Color::new(1., 1., 1., 0.5));
+            ui.fill_circle(pause_hit.x, pause_hit.y, 0.05 * scale_ratio * layout.pause.scale, Color::new(1., 1., 1., 0.5));
         }
 
         let score = (self.judge.score() / 1_000_000. * res.info.score_total as f64).round() as u32;
@@ -532,7 +869,7 @@ impl GameScene {
         if text_width > max_width {
             text_size *= max_width / text_width
         }
-        self.chart.with_element(ui, res, UIElement::Score, Some((score_right, score_top)), Some((score_right, score_top)), |ui, color| {
+        Self::with_layout(ui, layout.score, (score_right, score_top), |ui| self.chart.with_element(ui, res, UIElement::Score, Some((score_right, score_top)), Some((score_right, score_top)), |ui, color| {
             if res.config.render_ui_score {
                 ui.text(score)
                     .pos(score_right, score_top)
@@ -541,17 +878,27 @@ impl GameScene {
                     .color(Color { a: color.a * c.a, ..color })
                     .draw();
             }
-            if res.config.show_acc {
-                ui.text(format!("{:05.2}%", self.judge.real_time_accuracy() * 100.))
-                    .pos(aspect_ratio - margin, top + eps * 2.2 - (1. - p) * 0.4 + 0.07 + 0.05)
-                    .anchor(1., 0.)
-                    .size(0.4 * scale_ratio)
-                    .color(Color { a: color.a * c.a * 0.7, ..color })
-                    .draw();
+            if res.config.show_acc && res.config.acc_hud_position == HudPosition::Score {
+                let lines = Self::acc_hud_lines(&self.judge, &res.config);
+                let color = Color { a: color.a * c.a * 0.7, ..color };
+                Self::render_acc_hud(ui, &lines[..1], aspect_ratio - margin, top + eps * 2.2 - (1. - p) * 0.4 + 0.12, 1., true, 0.4 * scale_ratio, color);
+                let y = top + eps * 2.2 - (1. - p) * 0.4 + 0.12 + 0.4 * scale_ratio * 0.09;
+                Self::render_acc_hud(ui, &lines[1..], aspect_ratio - margin, y, 1., true, 0.3 * scale_ratio, color);
             }
-        });
+        }));
+        if res.config.show_acc && res.config.acc_hud_position != HudPosition::Score {
+            let lines = Self::acc_hud_lines(&self.judge, &res.config);
+            let color = semi_white(0.7 * c.a);
+            let size = 0.3 * scale_ratio;
+            let (x, y, anchor_x, downwards) = match res.config.acc_hud_position {
+                HudPosition::TopLeft => (-aspect_ratio + margin, pause_center.y + pause_h / 2. + 0.04, 0., true),
+                HudPosition::BottomLeft => (-aspect_ratio + margin, -top - eps * 3.5 + (1. - p) * 0.4 - 0.1, 0., false),
+                _ => (aspect_ratio - margin, -top - eps * 3.5 + (1. - p) * 0.4 - 0.1, 1., false),
+            };
+            Self::render_acc_hud(ui, &lines, x, y, anchor_x, downwards, size, color);
+        }
         if res.config.render_ui_pause {
-            self.chart.with_element(ui, res, UIElement::Pause, Some((pause_center.x - pause_w * 1.5, pause_center.y - pause_h * 0.5)), Some((pause_center.x - pause_w * 1.5, pause_center.y - pause_h * 0.5)), |ui, color| {
+            Self::with_layout(ui, layout.pause, (pause_anchor.x, pause_anchor.y), |ui| self.chart.with_element(ui, res, UIElement::Pause, Some((pause_anchor.x, pause_anchor.y)), Some((pause_anchor.x, pause_anchor.y)), |ui, color| {
                 let mut r = Rect::new(pause_center.x - pause_w / 2., pause_center.y - pause_h / 2., pause_w, pause_h);
                 //let ct = pause_center.coords;
                 let c = Color { a: color.a * c.a, ..color };
@@ -560,7 +907,7 @@ impl GameScene {
                 ui.fill_rect(r, c);
                 r.x += pause_w * 2.;
                 ui.fill_rect(r, c);
-            });
+            }));
         }
         if self.judge.combo() >= 3 && res.config.render_ui_combo {
             let combo = if res.config.roman {
@@ -583,7 +930,7 @@ impl GameScene {
             }
             let combo_y = top + eps * 1.55 - (1. - p) * 0.4 + ct.y;
             let btm = text.anchor(0.5, 0.5).pos(0., combo_y).draw().bottom() + 0.015;
-            self.chart.with_element(ui, res, UIElement::ComboNumber, Some((0., combo_y)), Some((0., combo_y)), |ui, color| {
+            Self::with_layout(ui, layout.combo, (0., combo_y), |ui| self.chart.with_element(ui, res, UIElement::ComboNumber, Some((0., combo_y)), Some((0., combo_y)), |ui, color| {
                 ui.text(&combo)
                     .pos(0., combo_y)
                     .anchor(0.5, 0.5)
@@ -591,16 +938,16 @@ impl GameScene {
                     .size(text_size)
                     .multiline()
                     .draw();
-            });
+            }));
             let mut text = ui.text(&res.config.combo).size(0.34 * scale_ratio);
             let ct = text.measure().center();
-            self.chart.with_element(ui, res, UIElement::Combo, Some((0., btm + ct.y)), Some((0., btm + ct.y)), |ui, color| {
+            Self::with_layout(ui, layout.combo, (0., combo_y), |ui| self.chart.with_element(ui, res, UIElement::Combo, Some((0., btm + ct.y)), Some((0., btm + ct.y)), |ui, color| {
                 if (cfg!(feature = "play") && res.config.autoplay()) || validate_combo(&res.config.combo) || res.config.combo.len() > 50 {
                     draw_text_aligned(ui, "AUTOPLAY", 0., btm + ct.y, (0.5, 0.5), 0.34 * scale_ratio, Color { a: color.a * c.a, ..color });
                     return;
                 }
                 draw_text_aligned_opt_width(ui, &res.config.combo, 0., btm + ct.y, (0.5, 0.5), 0.34 * scale_ratio, Color { a: color.a * c.a, ..color }, 0.55 * aspect_ratio);
-            });
+            }));
         }
         let lf = -aspect_ratio + margin;
         let bt = -top - eps * 3.5 + (1. - p) * 0.4;
@@ -625,20 +972,119 @@ impl GameScene {
         let offset = self.chart.offset + self.info_offset + res.config.offset;
         let dest = (aspect_ratio * 2. * (res.time - self.exercise_range.start + offset) / (self.exercise_range.end - self.exercise_range.start)).max(0.).min(aspect_ratio * 2.);
         if res.config.render_ui_bar {
-            self.chart.with_element(ui, res, UIElement::Bar, Some((-aspect_ratio, top + height / 2.)), Some((-aspect_ratio, top + height / 2.)), |ui, color| {
-                //let ct = Vector::new(0., top + height / 2.);
-                ui.fill_rect(
-                    Rect::new(-aspect_ratio, top, dest, height),
-                    Color{ a: color.a * c.a, ..color },
-                );
-                ui.fill_rect(Rect::new(-aspect_ratio + dest - hw, top, hw * 2., height), Color::new(0.95, 0.95, 0.95, color.a * c.a));
-            });
+            let bar_color = |color: Color| Color { a: color.a * c.a, ..color };
+            let range = &self.exercise_range;
+            let marks: Vec<(f32, &str)> = res
+                .info
+                .sections
+                .iter()
+                .map(|it| ((it.time - range.start) / (range.end - range.start), it.name.as_str()))
+                .filter(|(p, _)| (0.0..1.0).contains(p))
+                .collect();
+            match res.config.progress_bar_style {
+                ProgressBarStyle::Top => {
+                    Self::with_layout(ui, layout.bar, (-aspect_ratio, top + height / 2.), |ui| self.chart.with_element(ui, res, UIElement::Bar, Some((-aspect_ratio, top + height / 2.)), Some((-aspect_ratio, top + height / 2.)), |ui, color| {
+                        //let ct = Vector::new(0., top + height / 2.);
+                        ui.fill_rect(
+                            Rect::new(-aspect_ratio, top, dest, height),
+                            bar_color(color),
+                        );
+                        ui.fill_rect(Rect::new(-aspect_ratio + dest - hw, top, hw * 2., height), Color::new(0.95, 0.95, 0.95, color.a * c.a));
+                        for (p, name) in &marks {
+                            let x = -aspect_ratio + p * aspect_ratio * 2.;
+                            ui.fill_rect(Rect::new(x - hw / 2., top, hw, height * 1.5), semi_white(0.6 * color.a * c.a));
+                            ui.text(*name).pos(x, top + height * 1.5).anchor(0.5, 0.).size(0.2 * scale_ratio).color(semi_white(0.6 * color.a * c.a)).draw();
+                        }
+                    }));
+                }
+                ProgressBarStyle::Circle => {
+                    let ct = (pause_center.x, pause_center.y);
+                    Self::with_layout(ui, layout.pause, (pause_anchor.x, pause_anchor.y), |ui| self.chart.with_element(ui, res, UIElement::Bar, Some(ct), Some(ct), |ui, color| {
+                        let r = pause_h * 0.9;
+                        let width = 0.006;
+                        ui.stroke_circle(ct.0, ct.1, r, width, semi_white(0.2 * c.a));
+                        if dest > 0. {
+                            ui.scope(|ui| {
+                                ui.dx(ct.0);
+                                ui.dy(ct.1);
+                                ui.stroke_path(&Ui::loading_path(std::f32::consts::PI, dest / (aspect_ratio * 2.) * std::f32::consts::PI * 2., r), width, bar_color(color));
+                            });
+                        }
+                    }));
+                }
+                ProgressBarStyle::Sections => {
+                    let y = -top - height * 2.;
+                    let anchor = (-aspect_ratio, y + height);
+                    let mut bounds = vec![0.];
+                    // named sections from the chart info take priority over bpm changes
+                    if marks.is_empty() {
+                        bounds.extend(
+                            self.chart
+                                .bpm_list
+                                .borrow()
+                                .change_times()
+                                .into_iter()
+                                .map(|it| (it + offset - range.start) / (range.end - range.start))
+                                .filter(|it| *it > 0. && *it < 1.),
+                        );
+                    } else {
+                        bounds.extend(marks.iter().map(|it| it.0).filter(|it| *it > 0.));
+                    }
+                    bounds.push(1.);
+                    Self::with_layout(ui, layout.bar, anchor, |ui| self.chart.with_element(ui, res, UIElement::Bar, Some(anchor), Some(anchor), |ui, color| {
+                        let gap = 0.008;
+                        for pair in bounds.windows(2) {
+                            let x = pair[0] * aspect_ratio * 2.;
+                            let w = ((pair[1] - pair[0]) * aspect_ratio * 2. - gap).max(0.);
+                            ui.fill_rect(Rect::new(x - aspect_ratio, y, w, height * 2.), semi_white(0.2 * c.a));
+                            let filled = (dest - x).clamp(0., w);
+                            if filled > 0. {
+                                ui.fill_rect(Rect::new(x - aspect_ratio, y, filled, height * 2.), bar_color(color));
+                            }
+                        }
+                        for (p, name) in &marks {
+                            ui.text(*name)
+                                .pos(-aspect_ratio + p * aspect_ratio * 2., y - 0.01)
+                                .anchor(0., 1.)
+                                .size(0.2 * scale_ratio)
+                                .color(semi_white(0.6 * color.a * c.a))
+                                .draw();
+                        }
+                    }));
+                }
+            }
         }
         Ok(())
     }
 
     fn overlay_ui(&mut self, ui: &mut Ui, tm: &mut TimeManager) -> Result<()> {
         let c = semi_white(self.res.alpha);
+        if let Some(target) = self.intro_skip.map(|it| it + self.offset()) {
+            if self.res.config.interactive
+                && !tm.paused()
+                && matches!(self.mode, GameMode::Normal | GameMode::NoRetry | GameMode::View)
+                && matches!(self.state, State::BeforeMusic | State::Playing)
+                && (tm.now() as f32) < target - 1.
+            {
+                let h = 1. / self.res.aspect_ratio;
+                let mut tx = ui.text(tl!("skip-intro")).pos(0.92, h - 0.1).anchor(1., 1.).size(0.5).color(c);
+                let r = tx.measure().feather(0.015);
+                tx.ui.fill_rect(r, Color::new(0., 0., 0., 0.4 * self.res.alpha));
+                tx.draw();
+                if Judge::get_touches(1.0)
+                    .iter()
+                    .any(|it| it.phase == TouchPhase::Started && r.contains(it.position))
+                {
+                    seek!(self, self.res, tm, target);
+                    self.pause_rewind = PauseRewind {
+                        time: Some(tm.now()),
+                        duration: Some(0.1),
+                        dim: false,
+                    };
+                    self.res.disable_hit_fx = true;
+                }
+            }
+        }
         let res = &mut self.res;
         for pos in &self.touch_points {
             ui.fill_circle(pos.0, pos.1, 0.04, Color { a: 0.4, ..BLUE });
@@ -707,14 +1153,86 @@ impl GameScene {
                     ..Default::default()
                 },
             );
+            let offset_row = matches!(self.mode, GameMode::Normal | GameMode::NoRetry).then_some(o + s * 2. + 0.08);
+            let offset_btn_x = 0.32;
+            if let Some(y) = offset_row {
+                ui.text(tl!("chart-offset", "offset" => format!("{:+.0}", res.config.chart_offset * 1000.)))
+                    .pos(0., y)
+                    .anchor(0.5, 0.5)
+                    .no_baseline()
+                    .size(0.5)
+                    .color(c)
+                    .draw();
+                for (x, label) in [(-offset_btn_x, "-"), (offset_btn_x, "+")] {
+                    ui.text(label).pos(x, y).anchor(0.5, 0.5).no_baseline().size(0.9).color(c).draw();
+                }
+            }
+            let respack_row = offset_row.filter(|_| !res.config.res_pack_choices.is_empty()).map(|y| y + 0.1);
+            if let Some(y) = respack_row {
+                let text = if self.res_pack_task.is_some() {
+                    tl!("respack-loading")
+                } else {
+                    tl!("respack", "name" => res.res_pack.info.name.clone())
+                };
+                ui.text(text).pos(0., y).anchor(0.5, 0.5).no_baseline().size(0.5).max_width(offset_btn_x * 1.6).color(c).draw();
+                for (x, label) in [(-offset_btn_x, "<"), (offset_btn_x, ">")] {
+                    ui.text(label).pos(x, y).anchor(0.5, 0.5).no_baseline().size(0.7).color(c).draw();
+                }
+            }
+            let blur_row = offset_row.filter(|_| res.config.render_bg).map(|y| respack_row.unwrap_or(y) + 0.1);
+            if let Some(y) = blur_row {
+                ui.text(tl!("bg-blur", "blur" => format!("{:.0}", res.blur_radius())))
+                    .pos(0., y)
+                    .anchor(0.5, 0.5)
+                    .no_baseline()
+                    .size(0.5)
+                    .color(c)
+                    .draw();
+                for (x, label) in [(-offset_btn_x, "-"), (offset_btn_x, "+")] {
+                    ui.text(label).pos(x, y).anchor(0.5, 0.5).no_baseline().size(0.9).color(c).draw();
+                }
+            }
+            let perf_row = offset_row.map(|y| blur_row.or(respack_row).unwrap_or(y) + 0.1);
+            if let Some(y) = perf_row {
+                let text = if perf_overlay_enabled() { tl!("perf-overlay-on") } else { tl!("perf-overlay-off") };
+                ui.text(text).pos(0., y).anchor(0.5, 0.5).no_baseline().size(0.5).color(c).draw();
+            }
             if res.config.interactive {
                 let mut clicked = None;
+                let mut offset_delta = 0.;
+                let mut respack_step = 0;
+                let mut blur_delta = 0.;
                 for touch in Judge::get_touches(1.0) {
                     if touch.phase != TouchPhase::Started {
                         continue;
                     }
                     let p = touch.position;
                     let p = Point::new(p.x, p.y);
+                    if let Some(y) = offset_row {
+                        if (p.y - y).abs() <= 0.05 && (p.x.abs() - offset_btn_x).abs() <= 0.06 {
+                            offset_delta += CHART_OFFSET_STEP * p.x.signum();
+                            continue;
+                        }
+                    }
+                    if let Some(y) = respack_row {
+                        if (p.y - y).abs() <= 0.05 && (p.x.abs() - offset_btn_x).abs() <= 0.06 {
+                            respack_step += p.x.signum() as i32;
+                            continue;
+                        }
+                    }
+                    if let Some(y) = blur_row {
+                        if (p.y - y).abs() <= 0.05 && (p.x.abs() - offset_btn_x).abs() <= 0.06 {
+                            blur_delta += BLUR_STEP * p.x.signum();
+                            continue;
+                        }
+                    }
+                    if let Some(y) = perf_row {
+                        if (p.y - y).abs() <= 0.05 && p.x.abs() <= offset_btn_x {
+                            res.config.perf_overlay = !perf_overlay_enabled();
+                            set_perf_overlay(res.config.perf_overlay);
+                            continue;
+                        }
+                    }
                     for i in -1..=1 {
                         let ct = Point::new((s * 2. + w) * i as f32, o);
                         let d = p - ct;
@@ -724,6 +1242,28 @@ impl GameScene {
                         }
                     }
                 }
+                if offset_delta != 0. {
+                    // the music is realigned with the new offset when the game resumes
+                    let old = res.config.chart_offset;
+                    res.config.chart_offset = ((old + offset_delta) * 1000.).round() / 1000.;
+                    self.info_offset += res.config.chart_offset - old;
+                }
+                if blur_delta != 0. {
+                    // kept as an override for this chart, the background is redrawn right away
+                    res.info.background_blur = Some((res.blur_radius() + blur_delta).clamp(0., MAX_BLUR));
+                }
+                if respack_step != 0 && self.res_pack_task.is_none() {
+                    // the built-in pack comes first, followed by the installed ones
+                    let choices = &res.config.res_pack_choices;
+                    let current = res.config.res_pack_path.as_ref().and_then(|it| choices.iter().position(|c| c == it)).map_or(0, |i| i + 1);
+                    let next = (current as i32 + respack_step).rem_euclid(choices.len() as i32 + 1) as usize;
+                    let path = next.checked_sub(1).map(|i| choices[i].clone());
+                    let fallbacks = res.config.res_pack_fallbacks.clone();
+                    self.res_pack_task = Some(Box::pin(async move {
+                        let pack = ResourcePack::from_layers(path.as_deref(), &fallbacks).await?;
+                        Ok((path, pack))
+                    }));
+                }
                 if no_retry && clicked == Some(0) {
                     clicked = None;
                 }
@@ -752,14 +1292,26 @@ impl GameScene {
                         let now = tm.now();
                         tm.speed = res.config.speed as _;
                         tm.resume();
-                        tm.seek_to(now - 1.);
-                        self.music.seek_to(now - 1.)?;
+                        let dst = now - RESUME_REWIND;
+                        tm.seek_to(dst);
+                        self.music.seek_to(dst)?;
+                        seek_videos!(self, res, dst);
+                        finish_replay!(self);
+                        let offset = self.chart.offset + self.info_offset + res.config.offset;
+                        let from = dst as f32 - offset;
+                        let snapshot = self.chart.judge_snapshot();
+                        self.chart.rewind(from);
+                        self.replay = Some(ResumeReplay {
+                            until: now as f32 - offset,
+                            judge: Judge::new(&self.chart),
+                            snapshot,
+                        });
                         self.pause_rewind = PauseRewind {
                             time: Some(tm.now()),
-                            duration: Some(1.0),
+                            duration: Some(RESUME_REWIND),
                             dim: true
                         };
-                        self.res.disable_hit_fx = true;
+                        self.res.disable_hit_fx = false;
                     }
                     _ => {}
                 }
@@ -806,6 +1358,26 @@ impl GameScene {
                         .find(|it| it.phase == TouchPhase::Started && r.contains(it.position))
                         .map(|it| (1, it.id));
                 }
+                // tapping a section name loops over that section
+                let mut chosen = None;
+                for (i, section) in self.res.info.sections.iter().enumerate() {
+                    let x = -hw + (section.time - sp) / (self.res.track_length - sp) * hw * 2.;
+                    ui.fill_rect(Rect::new(x, -h, 0., h * 2.).feather(0.002), Color::new(1., 1., 1., 0.5));
+                    let r = ui.text(&section.name).pos(x, h + 0.015).anchor(0.5, 0.).size(0.4).draw();
+                    if self.exercise_press.is_none() {
+                        let r = ui.rect_to_global(r.feather(0.01));
+                        if Judge::get_touches(1.0).iter().any(|it| it.phase == TouchPhase::Started && r.contains(it.position)) {
+                            chosen = Some(i);
+                        }
+                    }
+                }
+                if let Some(i) = chosen {
+                    let sections = &self.res.info.sections;
+                    let start = sections[i].time.max(sp);
+                    let end = sections.get(i + 1).map_or(self.res.track_length, |it| it.time).min(self.res.track_length);
+                    self.exercise_range = start..end;
+                    seek!(self, self.res, tm, start);
+                }
                 ui.fill_rect(Rect::new(cur, -h, 0., h * 2.).feather(0.005), Color::new(0.9, 0.9, 0.9, 1.));
                 ui.fill_circle(cur, 0., rad, Color::new(0.95, 0.95, 0.95, 1.));
                 if self.exercise_press.is_none() {
@@ -836,8 +1408,7 @@ impl GameScene {
                             )
                         };
                         if *ctrl == 0 {
-                            tm.seek_to(p as f64);
-                            self.music.seek_to(p as f64)?;
+                            seek!(self, self.res, tm, p);
                         } else {
                             *(if *ctrl == -1 {
                                 &mut self.exercise_range.start
@@ -897,7 +1468,8 @@ impl GameScene {
                 let a = (t / duration).clamp(0.0, 1.0) * PAUSE_BACKGROUND_ALPHA as f64;
                 let h = 1. / self.res.aspect_ratio;
                 draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., a as f32));
-                ui.text((t.ceil() as i32).to_string()).anchor(0.5, 0.5).size(1.).color(c).draw();
+                let count = (t / duration * RESUME_COUNTDOWN).ceil() as i32;
+                ui.text(count.to_string()).anchor(0.5, 0.5).size(1.).color(c).draw();
             }
         }
         Ok(())
@@ -992,6 +1564,12 @@ impl GameScene {
     }
 }
 
+impl Drop for GameScene {
+    fn drop(&mut self) {
+        live::publish(&LiveStats::default());
+    }
+}
+
 impl Scene for GameScene {
     fn enter(&mut self, tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
         #[cfg(target_arch = "wasm32")]
@@ -1054,9 +1632,15 @@ impl Scene for GameScene {
 
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
         self.res.audio.recover_if_needed()?;
+        self.music.update(&mut self.res.audio)?;
+        self.update_hot_reload(tm)?;
+        self.update_res_pack(tm)?;
+        self.res.res_pack.animate(tm.real_time() as f32);
         if matches!(self.state, State::Playing) {
             tm.update(self.music.position() as f64);
+            report_clock_drift(tm.drift() as f32);
         }
+        self.frame_counter.tick(tm.real_time(), matches!(self.state, State::Playing) && !tm.paused());
         if self.mode == GameMode::Exercise && tm.now() > self.exercise_range.end as f64 && self.exercise_range.end < self.res.track_length - 0.1 && !tm.paused() {
             let state = self.state.clone();
             reset!(self, self.res, tm);
@@ -1117,6 +1701,12 @@ impl Scene for GameScene {
                 if time >= self.res.track_length + WAIT_TIME {
                     self.music.pause()?;
                     self.state = State::Ending;
+                } else if self.res.config.sudden_death() && self.judge.flawed() && matches!(self.mode, GameMode::Normal | GameMode::NoRetry) {
+                    // straight to the result, everything left counts as missed
+                    self.music.pause()?;
+                    tm.seek_to((self.res.track_length + WAIT_TIME) as f64);
+                    self.failed = true;
+                    self.state = State::Ending;
                 }
                 time
             }
@@ -1130,7 +1720,15 @@ impl Scene for GameScene {
                     // TODO strengthen the protection
                     #[cfg(feature = "closed")]
                     if let Some(upload_fn) = &self.upload_fn {
-                        if !self.res.config.offline_mode && !self.res.config.autoplay() && self.res.config.speed >= 1.0 - 1e-3 {
+                        if !self.res.config.offline_mode
+                            && !self.failed
+                            && !self.hot_reloaded
+                            && !self.rewound
+                            && !self.res.config.autoplay()
+                            && self.res.config.speed >= 1.0 - 1e-3
+                            && self.res.config.standard_judge()
+                            && self.res.config.chart_mods().is_empty()
+                        {
                             if let Some(player) = &self.player {
                                 if let Some(chart) = &self.res.info.id {
                                     record_data = Some(encode_record(self, player.id, *chart));
@@ -1138,14 +1736,31 @@ impl Scene for GameScene {
                             }
                         }
                     }
-                    let result = self.judge.result();
-                    let record = if self.res.config.autoplay() || self.res.config.speed < 1.0 - 1e-3 {
+                    let mut result = self.judge.result();
+                    result.failed = self.failed;
+                    // a failed sudden death run isn't a complete play, after a hot reload the judge only counted the
+                    // notes played since, and after seeking back some were counted twice; keep all of them out of the records
+                    let record = if self.failed
+                        || self.hot_reloaded
+                        || self.rewound
+                        || self.res.config.autoplay()
+                        || self.res.config.speed < 1.0 - 1e-3
+                        || !self.res.config.standard_judge()
+                    {
                         None
                     } else {
                         Some(SimpleRecord {
                             score: result.score as _,
                             accuracy: result.accuracy as _,
                             full_combo: result.max_combo == result.num_of_notes,
+                            deltas: result.deltas.clone(),
+                            max_combo: result.max_combo,
+                            counts: result.counts,
+                            speed: Some(self.res.config.speed).filter(|it| (it - 1.).abs() > 1e-3),
+                            chart_mods: self.res.config.chart_mods(),
+                            random_seed: Some(self.res.config.random_seed).filter(|_| self.res.config.has_mod(Mods::RANDOM)),
+                            screenshot: None,
+                            fps: self.frame_counter.fps(),
                         })
                     };
                     self.next_scene = match self.mode {
@@ -1157,7 +1772,7 @@ impl Scene for GameScene {
                             self.res.icon_retry.clone(),
                             self.res.icon_proceed.clone(),
                             self.res.info.clone(),
-                            self.judge.result(),
+                            result,
                             self.res.challenge_icons[self.res.config.challenge_color.clone() as usize].clone(),
                             &self.res.config,
                             self.res.res_pack.endings.clone(),
@@ -1182,7 +1797,14 @@ impl Scene for GameScene {
             (time - self.offset()).max(0.)
         };
         self.res.time = time;
-        if !tm.paused() && (self.res.config.autoplay() || self.pause_rewind.time.is_none()) && self.mode != GameMode::View {
+        if self.replay.as_ref().map_or(false, |it| time >= it.until) {
+            finish_replay!(self);
+        }
+        if let Some(replay) = &mut self.replay {
+            if !tm.paused() {
+                replay.judge.replay_update(&mut self.res, &mut self.chart);
+            }
+        } else if !tm.paused() && (self.res.config.autoplay() || self.pause_rewind.time.is_none()) && self.mode != GameMode::View {
             self.gl.quad_gl.viewport(self.res.camera.viewport);
 
             let angle = GYRO.lock().unwrap().get_angle(&self.res.config);
@@ -1193,7 +1815,36 @@ impl Scene for GameScene {
         if let Some(update) = &mut self.update_fn {
             update(self.res.time, &mut self.res, &mut self.judge);
         }
+        if self.res.config.duck_on_miss {
+            let (streak, last) = self.judge.miss_streak();
+            let duck = if streak >= DUCK_STREAK { (1. - (self.res.time - last) / DUCK_RELEASE).clamp(0., 1.) } else { 0. };
+            if (duck - self.duck).abs() > 1e-3 {
+                self.duck = duck;
+                self.music.set_amplifier(self.res.config.volume_music * (1. - DUCK_DEPTH * duck))?;
+                self.res.hitsound_gain = 1. + DUCK_HITSOUND_BOOST * duck;
+            }
+        }
         let counts = self.judge.counts();
+        if live::serving() {
+            live::publish(&LiveStats {
+                state: match self.state {
+                    State::Ending => LiveState::Ended,
+                    _ if tm.paused() => LiveState::Paused,
+                    _ => LiveState::Playing,
+                },
+                name: self.res.info.name.clone(),
+                level: self.res.info.level.clone(),
+                score: self.judge.score().round() as u32,
+                accuracy: self.judge.real_time_accuracy(),
+                combo: self.judge.combo(),
+                perfect: counts[0],
+                good: counts[1],
+                bad: counts[2],
+                miss: counts[3],
+                time: self.res.time.min(self.res.track_length),
+                duration: self.res.track_length,
+            });
+        }
         self.res.judge_line_color = if counts[2] + counts[3] == 0 {
             if counts[1] == 0 {
                 self.res.res_pack.info.line_perfect()
@@ -1205,6 +1856,19 @@ impl Scene for GameScene {
         };
         self.res.judge_line_color.a *= self.res.alpha;
         self.chart.update(&mut self.res);
+        // taps are only free to pick lines when they aren't judged
+        if self.res.config.chart_debug_line > 0. && (tm.paused() || self.res.config.autoplay()) {
+            self.inspector.update(&self.res, &self.chart.lines);
+        }
+        self.res.inspected_line = self.inspector.selected.filter(|_| self.res.config.chart_debug_line > 0.);
+        #[cfg(feature = "script")]
+        if !tm.paused() {
+            if let Some(script) = &mut self.chart.extra.script {
+                let time = self.res.time;
+                let judged = self.judge.judged.as_mut().map(std::mem::take).unwrap_or_default();
+                script.update(&mut self.res, &mut self.chart.lines, time, &judged);
+            }
+        }
         let res = &mut self.res;
         #[cfg(feature = "video")]
         if !tm.paused() {
@@ -1248,14 +1912,12 @@ impl Scene for GameScene {
             if is_key_pressed(KeyCode::Left) {
                 res.time -= 2.;
                 let dst = (self.music.position() - 2.).max(0.);
-                self.music.seek_to(dst)?;
-                tm.seek_to(dst as f64);
+                seek!(self, res, tm, dst);
             }
             if is_key_pressed(KeyCode::Right) {
                 res.time += 5.;
                 let dst = (self.music.position() + 5.).min(res.track_length as f64);
-                self.music.seek_to(dst)?;
-                tm.seek_to(dst as f64);
+                seek!(self, res, tm, dst);
 
                 self.pause_rewind = PauseRewind {
                     time: Some(tm.now()),
@@ -1372,6 +2034,13 @@ impl Scene for GameScene {
             .map(|it| if msaa { it.input() } else { it.output() })
             .or(res.camera.render_target);
 
+        let background = if res.config.render_bg { Some(res.blur.texture(res.blur_radius())) } else { None };
+        let letterbox_blur = res.info.letterbox_blur.unwrap_or_default();
+        let letterbox = res.letterbox.as_mut().map(|it| it.texture(letterbox_blur));
+        // after the blur, which draws into targets of its own
+        if let Some(linear) = &res.linear_color {
+            linear.begin();
+        }
         let h = 1. / res.aspect_ratio;
         set_camera(&Camera2D {
             zoom: vec2(1., -asp2_window),
@@ -1379,9 +2048,12 @@ impl Scene for GameScene {
             render_target: chart_onto,
             ..Default::default()
         });
-        if res.config.render_bg {
+        if let Some(background) = background {
             clear_background(BLACK);
-            draw_background(*res.background, res.config.render_bg_dim);
+            draw_background(background, res.config.render_bg_dim);
+        }
+        if let Some(letterbox) = letterbox {
+            draw_letterbox(letterbox, ui.viewport, vp);
         }
 
         if res.config.render_bg_dim && res.config.chart_ratio >= 1. {
@@ -1390,10 +2062,12 @@ impl Scene for GameScene {
             let dim = Color::new(0.1, 0.1, 0.1, dim_alpha * res.alpha);
             let x_range = vp.0 as f32 / ui.viewport.2 as f32;
             let y_range =  vp.1 as f32 / vp.3 as f32;
-            draw_rectangle(-1., -h,x_range * 2., h * 2., dim); // Left
-            draw_rectangle(1., -h,-x_range * 2., h * 2., dim); // Right
-            draw_rectangle(-1., -h,2., -y_range * 2., dim); // Top
-            draw_rectangle(-1., h,2., y_range * 2., dim); // Bottom
+            if letterbox.is_none() {
+                draw_rectangle(-1., -h,x_range * 2., h * 2., dim); // Left
+                draw_rectangle(1., -h,-x_range * 2., h * 2., dim); // Right
+                draw_rectangle(-1., -h,2., -y_range * 2., dim); // Top
+                draw_rectangle(-1., h,2., y_range * 2., dim); // Bottom
+            }
             draw_rectangle(x_range * 2. - 1., -h, (1. - x_range * 2.) * 2., h * 2., Color::new(0., 0., 0., res.alpha * res.info.background_dim));
         }
 
@@ -1428,6 +2102,7 @@ impl Scene for GameScene {
         });
         self.gl.quad_gl.render_pass(chart_onto.map(|it| it.render_pass));
         self.chart.render(ui, res);
+        report_note_stats(res.note_buffer.borrow_mut().take_stats());
 
         self.gl.quad_gl.render_pass(
             res.chart_target
@@ -1499,6 +2174,29 @@ impl Scene for GameScene {
                 for touch in Judge::get_touches(1.0) {
                     ui.fill_circle(touch.position.x, touch.position.y, 0.04, Color { a: 0.4, ..RED });
                 }
+                if self.res.config.keyboard_lanes {
+                    let lanes = key_lanes(&self.res.config.key_lanes);
+                    let w = 2. / lanes.len() as f32;
+                    let bottom = 1. / asp2_window;
+                    for (i, (c, key)) in lanes.into_iter().enumerate() {
+                        let x = -1. + w * i as f32;
+                        if is_key_down(key) {
+                            ui.fill_rect(Rect::new(x, -bottom, w, bottom * 2.), Color { a: 0.15, ..RED });
+                        }
+                        ui.text(c.to_string())
+                            .pos(x + w / 2., bottom - 0.03)
+                            .anchor(0.5, 1.)
+                            .size(0.6)
+                            .color(semi_white(0.5))
+                            .draw();
+                    }
+                }
+            }
+            if self.res.inspected_line.is_some() {
+                self.inspector.render(ui, &self.res, &self.chart.lines, 1. / asp2_window);
+            }
+            if let Some(render) = &mut self.render_fn {
+                render(ui, self.res.alpha);
             }
         }
         
@@ -1512,11 +2210,17 @@ impl Scene for GameScene {
             self.overlay_ui(ui, tm)?;
         }
 
-        if msaa || !self.res.no_effect {
+        if let Some(linear) = &self.res.linear_color {
+            linear.end();
+        }
+        if msaa || !self.res.no_effect || self.res.linear_color.is_some() {
             // render the texture onto screen
             if let Some(target) = &self.res.chart_target {
                 self.gl.flush();
                 self.gl.quad_gl.viewport(None);
+                if let Some(linear) = &self.res.linear_color {
+                    gl_use_material(linear.output());
+                }
                 set_camera(&Camera2D {
                     zoom: vec2(1., asp2_window),
                     render_target: self.res.camera.render_target,
@@ -1533,6 +2237,7 @@ impl Scene for GameScene {
                         ..Default::default()
                     },
                 );
+                gl_use_default_material();
             }
         } else {
             self.gl.flush();
@@ -1564,4 +2269,8 @@ impl Scene for GameScene {
             NextScene::None
         }
     }
+
+    fn modal_errors(&self) -> bool {
+        !matches!(self.state, State::BeforeMusic | State::Playing)
+    }
 }
@@ -0,0 +1,115 @@
+// side panel of the line debug view: a tap near a line picks it, and its events are shown as they stand right now
+
+use crate::{
+    core::{Anim, AnimFloat, JudgeLine, JudgeLineKind, Point, Resource, Tweenable},
+    ext::{semi_black, semi_white},
+    judge::Judge,
+    ui::Ui,
+};
+use macroquad::prelude::*;
+
+// in line space
+const PICK_DISTANCE: f32 = 0.06;
+const PANEL_WIDTH: f32 = 0.64;
+// keyframes listed per event after the current value
+const UPCOMING: usize = 2;
+
+fn layer_count<T: Tweenable>(anim: &Anim<T>) -> usize {
+    if anim.is_default() {
+        return 0;
+    }
+    let mut count = 1;
+    let mut anim = anim;
+    while let Some(next) = &anim.next {
+        count += 1;
+        anim = next;
+    }
+    count
+}
+
+fn upcoming(anim: &AnimFloat, time: f32) -> Vec<(f32, f32)> {
+    let mut res = Vec::new();
+    let mut layer = Some(anim);
+    while let Some(anim) = layer {
+        res.extend(anim.keyframes.iter().filter(|it| it.time > time).take(UPCOMING).map(|it| (it.time, it.value)));
+        layer = anim.next.as_deref();
+    }
+    res.sort_by(|a, b| a.0.total_cmp(&b.0));
+    res.truncate(UPCOMING);
+    res
+}
+
+#[derive(Default)]
+pub struct LineInspector {
+    pub selected: Option<usize>,
+}
+
+impl LineInspector {
+    // taps away from every line clear the selection
+    pub fn update(&mut self, res: &Resource, lines: &[JudgeLine]) {
+        for touch in Judge::get_touches(res.config.chart_ratio) {
+            if !matches!(touch.phase, TouchPhase::Started) {
+                continue;
+            }
+            let x = if res.config.flip_x() { -touch.position.x } else { touch.position.x };
+            self.selected = lines
+                .iter()
+                .enumerate()
+                .filter_map(|(id, line)| {
+                    let p = line.now_transform(res, lines).try_inverse()?.transform_point(&Point::new(x, -touch.position.y));
+                    let dist = p.y.abs();
+                    (dist.is_finite() && dist <= PICK_DISTANCE).then_some((id, dist))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|it| it.0);
+        }
+    }
+
+    pub fn render(&self, ui: &mut Ui, res: &Resource, lines: &[JudgeLine], top: f32) {
+        let Some((id, line)) = self.selected.and_then(|id| Some((id, lines.get(id)?))) else {
+            return;
+        };
+        let r = Rect::new(1. - PANEL_WIDTH - 0.02, -top + 0.02, PANEL_WIDTH, top * 2. - 0.04);
+        ui.fill_rect(r, semi_black(0.6));
+        let x = r.x + 0.02;
+        let mut y = r.y + 0.02;
+        let mut row = |ui: &mut Ui, text: String, size: f32, color: Color| {
+            y += ui.text(text).pos(x, y).size(size).max_width(r.w - 0.04).color(color).draw().h + 0.008;
+        };
+        let kind = match &line.kind {
+            JudgeLineKind::Normal => String::new(),
+            JudgeLineKind::Text(text) => format!(" text:{}", text.now()),
+            JudgeLineKind::Texture(_, name) => format!(" img:{name}"),
+            JudgeLineKind::TextureGif(_, _, name) => format!(" gif:{name}"),
+            JudgeLineKind::Paint(..) => " paint".to_owned(),
+        };
+        row(ui, format!("#{id}{kind}"), 0.6, WHITE);
+        let mut misc = format!("t {:.3}s  z {}", res.time, line.z_index);
+        if let Some(parent) = line.parent {
+            misc += &format!("  parent #{parent}");
+        }
+        row(ui, misc, 0.38, semi_white(0.7));
+        let obj = &line.object;
+        let events: [(&str, &AnimFloat, f32); 8] = [
+            ("alpha", &obj.alpha, obj.now_alpha()),
+            ("x", &obj.translation.0, obj.translation.0.now()),
+            ("y", &obj.translation.1, obj.translation.1.now()),
+            ("rotate", &obj.rotation, obj.rotation.now()),
+            ("scale x", &obj.scale.0, obj.scale.0.now_opt().unwrap_or(1.)),
+            ("scale y", &obj.scale.1, obj.scale.1.now_opt().unwrap_or(1.)),
+            ("height", &line.height, line.height.now()),
+            ("incline", &line.incline, line.incline.now()),
+        ];
+        for (name, anim, now) in events {
+            let layers = layer_count(anim);
+            let color = if layers == 0 { semi_white(0.4) } else { WHITE };
+            row(ui, format!("{name}  {now:.3}  ({layers})"), 0.42, color);
+            for (time, value) in upcoming(anim, res.time) {
+                row(ui, format!("    → {value:.3} at {time:.2}s (+{:.2}s)", time - res.time), 0.34, semi_white(0.6));
+            }
+        }
+        if let Some(color) = line.color.now_opt() {
+            row(ui, format!("color  {:.2} {:.2} {:.2} {:.2}  ({})", color.r, color.g, color.b, color.a, layer_count(&line.color)), 0.42, WHITE);
+        }
+    }
+}
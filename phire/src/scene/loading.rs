@@ -1,14 +1,14 @@
-use super::{draw_background, ending::RecordUpdateState, game::GameMode, GameScene, NextScene, Scene};
+use super::{draw_background, ending::RecordUpdateState, game::{GameMode, SimpleRecord}, GameScene, NextScene, Scene};
 use crate::{
     config::Config,
-    core::{Chart, Resource},
-    ext::{draw_illustration, draw_parallelogram, draw_text_aligned, draw_text_aligned_opt, draw_text_aligned_opt_width, poll_future, LocalTask, SafeTexture, BLACK_TEXTURE},
+    core::{BackgroundBlur, Chart, LoadProgress, Resource},
+    ext::{draw_illustration, draw_parallelogram, draw_text_aligned, draw_text_aligned_opt, draw_text_aligned_opt_width, poll_future, LocalTask, RectExt, SafeTexture, BLACK_TEXTURE},
     fs::FileSystem,
     info::{ChartFormat, ChartInfo},
     judge::Judge,
     task::Task,
     time::TimeManager,
-    ui::Ui,
+    ui::{RectButton, Ui},
 };
 use ::rand::{rng, seq::IndexedRandom};
 use anyhow::{Context, Result};
@@ -21,8 +21,10 @@ const BEFORE_TIME: f32 = 1.;
 const TRANSITION_TIME: f32 = 1.4;
 const WAIT_TIME: f32 = 0.;
 
-pub type UploadFn = Arc<dyn Fn(Vec<u8>) -> Task<Result<RecordUpdateState>>>;
+// gets the encoded record data along with the record it was made from
+pub type UploadFn = Arc<dyn Fn(Vec<u8>, &SimpleRecord) -> Task<Result<RecordUpdateState>>>;
 pub type UpdateFn = Box<dyn FnMut(f32, &mut Resource, &mut Judge)>;
+pub type RenderFn = Box<dyn FnMut(&mut Ui, f32)>;
 
 pub struct BasicPlayer {
     pub avatar: Option<SafeTexture>,
@@ -33,43 +35,24 @@ pub struct BasicPlayer {
 pub struct LoadingScene {
     info: ChartInfo,
     config: Config,
-    background: SafeTexture,
+    background: BackgroundBlur,
     illustration: SafeTexture,
     pub load_task: LocalTask<Result<GameScene>>,
     next_scene: Option<NextScene>,
     finish_time: f32,
     target: Option<RenderTarget>,
     charter: String,
+    progress: Arc<LoadProgress>,
+    cancel_btn: RectButton,
 }
 
 impl LoadingScene {
     pub const TOTAL_TIME: f32 = BEFORE_TIME + TRANSITION_TIME + WAIT_TIME;
 
-    pub async fn load_background(fs: &mut Box<dyn FileSystem>, config: &Config, path: &str) -> Result<(Texture2D, Texture2D)> {
+    pub async fn load_background(fs: &mut Box<dyn FileSystem>, path: &str) -> Result<Texture2D> {
         let image = image::load_from_memory(&fs.load_file(path).await?).context("Failed to decode image")?;
         let (w, h) = (image.width(), image.height());
-        let size = w as usize * h as usize;
-
-        let mut blurred_rgb = image.to_rgb8();
-        let mut vec = unsafe { Vec::from_raw_parts(std::mem::transmute(blurred_rgb.as_mut_ptr()), size, size) };
-        fastblur::gaussian_blur(&mut vec, w as _, h as _, config.bg_blurriness);
-        std::mem::forget(vec);
-        let mut blurred = Vec::with_capacity(size * 4);
-        for input in blurred_rgb.chunks_exact(3) {
-            //blurred.extend_from_slice(input);
-            blurred.push(input[0]);
-            blurred.push(input[1]);
-            blurred.push(input[2]);
-            blurred.push(255);
-        }
-        Ok((
-            Texture2D::from_rgba8(w as _, h as _, &image.into_rgba8()),
-            Texture2D::from_image(&Image {
-                width: w as _,
-                height: h as _,
-                bytes: blurred,
-            }),
-        ))
+        Ok(Texture2D::from_rgba8(w as _, h as _, &image.into_rgba8()))
     }
 
     pub async fn new(
@@ -81,17 +64,17 @@ impl LoadingScene {
         player: Option<BasicPlayer>,
         upload_fn: Option<UploadFn>,
         update_fn: Option<UpdateFn>,
+        render_fn: Option<RenderFn>,
     ) -> Result<Self> {
-        let background = match Self::load_background(&mut fs, config, &info.illustration).await {
-            Ok((ill, bg)) => Some((ill, bg)),
+        // the background is blurred from the illustration while drawing
+        let illustration: SafeTexture = match Self::load_background(&mut fs, &info.illustration).await {
+            Ok(ill) => ill.into(),
             Err(err) => {
                 warn!("failed to load background: {err:?}");
-                None
+                BLACK_TEXTURE.clone()
             }
         };
-        let (illustration, background): (SafeTexture, SafeTexture) = background
-            .map(|(ill, back)| (ill.into(), back.into()))
-            .unwrap_or_else(|| (BLACK_TEXTURE.clone(), BLACK_TEXTURE.clone()));
+        let background = illustration.clone();
         if info.tip.is_none() {
             let tips_file = load_file(format!("tips.txt").as_str()).await?;
             let tips = String::from_utf8_lossy(&tips_file)
@@ -101,19 +84,35 @@ impl LoadingScene {
 
             info.tip = Some(tips.choose(&mut rng()).unwrap().to_owned());
         }
-        let future = Box::pin(GameScene::new(preload_chart, mode, info.clone(), config.clone(), fs, player, background.clone(), illustration.clone(), upload_fn, update_fn));
+        let progress = Arc::new(LoadProgress::default());
+        let future = Box::pin(GameScene::new(
+            preload_chart,
+            mode,
+            info.clone(),
+            config.clone(),
+            fs,
+            player,
+            background.clone(),
+            illustration.clone(),
+            upload_fn,
+            update_fn,
+            render_fn,
+            Arc::clone(&progress),
+        ));
         let charter = Regex::new(r"\[!:[0-9]+:([^:]*)\]").unwrap().replace_all(&info.charter, "$1").to_string();
 
         Ok(Self {
             info,
             config: config.clone(),
-            background,
+            background: BackgroundBlur::new(background)?,
             illustration,
             load_task: Some(future),
             next_scene: None,
             finish_time: f32::INFINITY,
             target: None,
             charter,
+            progress,
+            cancel_btn: RectButton::new(),
         })
     }
 }
@@ -124,6 +123,16 @@ impl Scene for LoadingScene {
         Ok(())
     }
 
+    fn touch(&mut self, _tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        if self.load_task.is_some() && self.cancel_btn.touch(touch) {
+            // dropping the task stops it at its next await, whatever is still decoding in the background is discarded
+            self.load_task = None;
+            self.next_scene = Some(NextScene::Pop);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
         if let Some(future) = self.load_task.as_mut() {
             loop {
@@ -154,13 +163,18 @@ impl Scene for LoadingScene {
         let now = tm.now() as f32;
         let intern = unsafe { get_internal_gl() };
         let gl = intern.quad_gl;
+        let background = if self.config.render_bg {
+            Some(self.background.texture(self.info.background_blur.unwrap_or(self.config.bg_blurriness)))
+        } else {
+            None
+        };
         set_camera(&Camera2D {
             zoom: vec2(1., -asp),
             render_target: self.target,
             ..Default::default()
         });
-        if self.config.render_bg {
-            draw_background(*self.background, self.config.render_bg_dim);
+        if let Some(background) = background {
+            draw_background(background, self.config.render_bg_dim);
         }
         let dx = if now > self.finish_time {
             let p = ((now - self.finish_time) / TRANSITION_TIME).min(1.);
@@ -226,11 +240,24 @@ impl Scene for LoadingScene {
         draw_text_aligned_opt_width(ui, &self.info.illustrator, t.x - 0.002, t.y + top / 22., (0., 0.), 0.415, WHITE, 0.58);
         let text_tip = self.info.tip.as_ref().unwrap();
         draw_text_aligned_opt_width(ui, &text_tip, -0.895, top * 0.88, (0., 1.), 0.47, WHITE, 1.55);
-        let text_loading = if self.config.chinese {"加载中..."} else {"Loading..."};
+        let loading = self.load_task.is_some();
+        let text_loading = if loading {
+            format!("{} {:.0}%", self.progress.stage().label(self.config.chinese), self.progress.ratio() * 100.)
+        } else {
+            (if self.config.chinese { "加载中..." } else { "Loading..." }).to_owned()
+        };
         let t = draw_text_aligned(ui, &text_loading, 0.865, top * 0.865, (1., 1.), 0.41, WHITE);
         let we = 0.19;
         let he = 0.35;
         let r = Rect::new(t.x - t.w * we, t.y - t.h * he, t.w * (1. + we * 2.2), t.h * (1. + he * 2.2));
+        let bar = Rect::new(r.x, r.bottom() + 0.012, r.w, 0.006);
+        ui.fill_rect(bar, Color::new(1., 1., 1., 0.3));
+        ui.fill_rect(Rect { w: bar.w * if loading { self.progress.ratio() } else { 1. }, ..bar }, WHITE);
+        if loading && self.target.is_none() {
+            let text_cancel = if self.config.chinese { "取消" } else { "Cancel" };
+            let c = draw_text_aligned(ui, text_cancel, 0.865, -top * 0.865, (1., 0.), 0.41, WHITE);
+            self.cancel_btn.set(ui, c.feather(0.02));
+        }
 
         let p = 0.6;
         let s = 0.2;
@@ -242,7 +269,7 @@ impl Scene for LoadingScene {
         ui.fill_rect(r, WHITE);
         r.x += dx;
         ui.scissor(Some(r));
-        draw_text_aligned(ui, text_loading, 0.865, top * 0.865, (1., 1.), 0.41, BLACK);
+        draw_text_aligned(ui, &text_loading, 0.865, top * 0.865, (1., 1.), 0.41, BLACK);
         ui.scissor(None);
 
         if dx != 0. {
@@ -252,7 +279,7 @@ impl Scene for LoadingScene {
     }
 
     fn next_scene(&mut self, tm: &mut TimeManager) -> NextScene {
-        if matches!(self.next_scene, Some(NextScene::PopWithResult(_))) {
+        if matches!(self.next_scene, Some(NextScene::Pop | NextScene::PopWithResult(_))) {
             return self.next_scene.take().unwrap();
         }
         if tm.now() as f32 > self.finish_time + TRANSITION_TIME + WAIT_TIME || !self.config.enter_animation {
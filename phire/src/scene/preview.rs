@@ -0,0 +1,111 @@
+use super::{GameMode, GameScene, Scene};
+use crate::{
+    config::{Config, Mods},
+    core::Chart,
+    ext::SafeTexture,
+    fs::FileSystem,
+    info::ChartInfo,
+    time::TimeManager,
+    ui::Ui,
+};
+use anyhow::Result;
+use macroquad::prelude::*;
+use std::{ops::DerefMut, sync::Arc};
+
+// seconds of the chart played in a preview
+pub const PREVIEW_LENGTH: f32 = 20.;
+const PREVIEW_SIZE: (u32, u32) = (640, 360);
+// lead-in before the first note of the slice
+const PREVIEW_LEAD: f32 = 1.;
+
+// start of the `length` long window holding the most notes, in chart time
+fn densest_start(chart: &Chart, length: f32) -> f32 {
+    let mut times: Vec<f32> = chart
+        .lines
+        .iter()
+        .flat_map(|line| line.notes.iter().filter(|it| !it.fake).map(|it| it.time))
+        .collect();
+    times.sort_by(f32::total_cmp);
+    let (mut best, mut best_count, mut first) = (0., 0, 0);
+    for (i, time) in times.iter().enumerate() {
+        while time - times[first] > length {
+            first += 1;
+        }
+        if i + 1 - first > best_count {
+            best_count = i + 1 - first;
+            best = times[first];
+        }
+    }
+    best
+}
+
+// an autoplayed slice of a chart drawn into its own target, so that menus can show it in a small viewport
+pub struct ChartPreview {
+    scene: GameScene,
+    tm: TimeManager,
+    target: RenderTarget,
+    end: f32,
+}
+
+impl ChartPreview {
+    pub async fn new(info: ChartInfo, mut config: Config, mut fs: Box<dyn FileSystem>, background: SafeTexture, illustration: SafeTexture) -> Result<Self> {
+        config.mods.insert(Mods::AUTOPLAY);
+        config.enter_animation = false;
+        config.hot_reload = false;
+        let (chart, format) = GameScene::load_chart(fs.deref_mut(), &info, &config).await?;
+        let start = (densest_start(&chart, PREVIEW_LENGTH - PREVIEW_LEAD) - PREVIEW_LEAD).max(0.);
+        config.play_start_time = start;
+        let mut scene = GameScene::new(Some((chart, format)), GameMode::Normal, info, config, fs, None, background, illustration, None, None, None, Arc::default()).await?;
+        let target = render_target(PREVIEW_SIZE.0, PREVIEW_SIZE.1);
+        target.texture.set_filter(FilterMode::Linear);
+        let mut tm = TimeManager::default();
+        scene.enter(&mut tm, Some(target))?;
+        Ok(Self {
+            scene,
+            tm,
+            target,
+            end: start + PREVIEW_LENGTH,
+        })
+    }
+
+    // false once the slice is over
+    pub fn update(&mut self) -> Result<bool> {
+        self.scene.update(&mut self.tm)?;
+        Ok(self.scene.res.time < self.end)
+    }
+
+    pub fn progress(&self) -> f32 {
+        1. - ((self.end - self.scene.res.time) / PREVIEW_LENGTH).clamp(0., 1.)
+    }
+
+    pub fn pause(&mut self) -> Result<()> {
+        self.scene.pause(&mut self.tm)
+    }
+
+    pub fn render(&mut self, ui: &mut Ui, r: Rect, alpha: f32) -> Result<()> {
+        push_camera_state();
+        let (viewport, top) = (ui.viewport, ui.top);
+        ui.viewport = (0, 0, PREVIEW_SIZE.0 as i32, PREVIEW_SIZE.1 as i32);
+        ui.top = PREVIEW_SIZE.1 as f32 / PREVIEW_SIZE.0 as f32;
+        let res = ui.abs_scope(|ui| self.scene.render(&mut self.tm, ui));
+        ui.viewport = viewport;
+        ui.top = top;
+        pop_camera_state();
+        set_camera(&ui.camera());
+        res?;
+        ui.apply(|_| {
+            draw_texture_ex(
+                self.target.texture,
+                r.x,
+                r.y,
+                Color::new(1., 1., 1., alpha),
+                DrawTextureParams {
+                    dest_size: Some(r.size()),
+                    flip_y: true,
+                    ..Default::default()
+                },
+            )
+        });
+        Ok(())
+    }
+}
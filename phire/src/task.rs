@@ -2,26 +2,27 @@ use std::{
     future::Future,
     sync::{Arc, Mutex, MutexGuard},
 };
+use tokio::task::AbortHandle;
 
-pub struct Task<T: Send + 'static>(Arc<Mutex<Option<T>>>);
+pub struct Task<T: Send + 'static>(Arc<Mutex<Option<T>>>, Arc<AbortHandle>);
 
 impl<T: Send + 'static> Clone for Task<T> {
     fn clone(&self) -> Self {
-        Self(Arc::clone(&self.0))
+        Self(Arc::clone(&self.0), Arc::clone(&self.1))
     }
 }
 
 impl<T: Send + 'static> Task<T> {
     pub fn new(future: impl Future<Output = T> + Send + 'static) -> Self {
         let arc = Arc::new(Mutex::new(None));
-        {
+        let handle = {
             let arc = Arc::clone(&arc);
             tokio::spawn(async move {
                 let result = future.await;
                 *arc.lock().unwrap() = Some(result);
-            });
-        }
-        Self(arc)
+            })
+        };
+        Self(arc, Arc::new(handle.abort_handle()))
     }
 
     pub fn pending() -> Self {
@@ -39,6 +40,16 @@ impl<T: Send + 'static> Task<T> {
     pub fn get(&self) -> MutexGuard<'_, Option<T>> {
         self.0.lock().unwrap()
     }
+
+    // drops the future at its next await, no result will arrive
+    pub fn abort(&self) {
+        self.1.abort();
+    }
+
+    // whether the future is gone, either done or aborted
+    pub fn finished(&self) -> bool {
+        self.1.is_finished()
+    }
 }
 
 impl<T: Send + Clone + 'static> Task<T> {
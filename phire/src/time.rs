@@ -1,5 +1,10 @@
 use crate::config::Config;
 
+// how quickly the measured drift follows new readings of the music position
+const DRIFT_SMOOTHING: f64 = 0.05;
+// past this the clock jumps to the music at once instead of easing towards it
+const RESYNC_THRESHOLD: f64 = 0.08;
+
 pub struct TimeManager {
     pub adjust_time: bool,
     pub start_time: f64,
@@ -7,6 +12,7 @@ pub struct TimeManager {
     pub speed: f64,
     pub force: f64,
     wait: f64,
+    drift: f64,
 
     get_time_fn: Box<dyn Fn() -> f64>,
 }
@@ -31,6 +37,7 @@ impl TimeManager {
             speed: 1.0,
             wait: f64::NEG_INFINITY,
             force: 1e-2,
+            drift: 0.,
 
             get_time_fn,
         }
@@ -57,6 +64,7 @@ impl TimeManager {
             speed,
             wait: f64::NEG_INFINITY,
             force: 1e-2,
+            drift: 0.,
 
             get_time_fn: Box::new(get_time_fn),
         }
@@ -70,6 +78,7 @@ impl TimeManager {
         self.start_time = self.real_time();
         self.pause_time = None;
         self.wait = f64::NEG_INFINITY;
+        self.drift = 0.;
     }
 
     pub fn wait(&mut self) {
@@ -85,12 +94,30 @@ impl TimeManager {
         (self.pause_time.unwrap_or_else(&self.get_time_fn) - self.start_time) * self.speed
     }
 
+    // `music_time` is where the audio device actually is. Its position moves in buffer sized steps, so
+    // the clock follows a smoothed drift rather than single readings
     pub fn update(&mut self, music_time: f64) {
-        if self.adjust_time && self.real_time() > self.wait && self.pause_time.is_none() {
-            self.start_time -= (music_time - self.now()) * self.force;
+        if self.real_time() <= self.wait || self.pause_time.is_some() {
+            return;
+        }
+        self.drift += (music_time - self.now() - self.drift) * DRIFT_SMOOTHING;
+        if !self.adjust_time {
+            return;
+        }
+        if self.drift.abs() > RESYNC_THRESHOLD {
+            self.start_time -= self.drift / self.speed;
+            self.drift = 0.;
+        } else {
+            self.start_time -= self.drift * self.force;
         }
     }
 
+    // how far the music is ahead of the clock, in seconds
+    #[must_use]
+    pub fn drift(&self) -> f64 {
+        self.drift
+    }
+
     #[must_use]
     pub fn paused(&self) -> bool {
         self.pause_time.is_some()
@@ -107,6 +134,7 @@ impl TimeManager {
 
     pub fn seek_to(&mut self, pos: f64) {
         self.start_time = self.pause_time.unwrap_or_else(&self.get_time_fn) - pos / self.speed;
+        self.drift = 0.;
         self.wait();
     }
 }
@@ -7,6 +7,15 @@ pub use chart_info::*;
 mod dialog;
 pub use dialog::Dialog;
 
+mod memory;
+pub use memory::{memory_overlay_enabled, render_memory_overlay, report_scene_exit, set_memory_overlay, MemoryGuard, MemoryKind, MemorySnapshot};
+
+mod perf;
+pub use perf::{perf_overlay_enabled, render_perf_overlay, report_clock_drift, report_note_stats, set_perf_overlay, NoteStats};
+
+mod retry;
+pub use retry::RetryPanel;
+
 mod scroll;
 pub use scroll::Scroll;
 
@@ -19,6 +28,9 @@ pub use shadow::*;
 mod text;
 pub use text::{DrawText, TextPainter};
 
+mod theme;
+pub use theme::{set_theme, theme, Theme, ThemeInfo};
+
 pub use glyph_brush::ab_glyph::FontArc;
 
 use crate::{
@@ -204,7 +216,10 @@ impl DRectButton {
             inner: RectButton::new(),
             last_touching: false,
             start_time: None,
-            config: ShadowConfig::default(),
+            config: ShadowConfig {
+                radius: theme().button_radius,
+                ..Default::default()
+            },
             delta: -0.004,
             play_sound: true,
         }
@@ -250,14 +265,14 @@ impl DRectButton {
         let oh = r.h;
         let (r, path) = self.build(ui, t, r);
         let ct = r.center();
-        ui.fill_path(&path, if chosen { semi_white(alpha) } else { semi_black(alpha * 0.4) });
+        ui.fill_path(&path, theme().button_fill(chosen, alpha));
         ui.text(text)
             .pos(ct.x, ct.y)
             .anchor(0.5, 0.5)
             .no_baseline()
             .size(size * (1. - (1. - r.h / oh).powf(1.3)))
             .max_width(r.w)
-            .color(if chosen { Color::new(0.3, 0.3, 0.3, alpha) } else { semi_white(alpha) })
+            .color(theme().button_text(chosen, alpha))
             .draw();
         (r, path)
     }
@@ -274,14 +289,14 @@ impl DRectButton {
     ) -> (Rect, Path) {
         let oh = r.h;
         let (r, path) = self.build(ui, t, r);
-        ui.fill_path(&path, if chosen { semi_white(alpha) } else { semi_black(alpha * 0.4) });
+        ui.fill_path(&path, theme().button_fill(chosen, alpha));
         ui.text(text)
             .pos(r.x + 0.02, r.center().y)
             .anchor(0., 0.5)
             .max_width(r.w - 0.04)
             .no_baseline()
             .size(size * r.h / oh)
-            .color(if chosen { Color::new(0.3, 0.3, 0.3, alpha) } else { semi_white(alpha) })
+            .color(theme().button_text(chosen, alpha))
             .draw();
         (r, path)
     }
@@ -831,11 +846,11 @@ impl<'a> Ui<'a> {
     }
 
     pub fn accent(&self) -> Color {
-        Color::new(0.85, 0.85, 0.85, 1.0)
+        theme().accent
     }
 
     pub fn background(&self) -> Color {
-        Color::new(0.13, 0.13, 0.13, 1.0)
+        theme().background
     }
 
     pub fn button(&mut self, id: &str, rect: Rect, text: impl Into<String>) -> bool {
@@ -1030,6 +1045,25 @@ impl<'a> Ui<'a> {
         rect
     }
 
+    fn badge_pad(size: f32) -> f32 {
+        0.03 * size
+    }
+
+    pub fn badge_width(&mut self, text: &str, size: f32) -> f32 {
+        self.text(text).no_baseline().size(size).measure().w + Self::badge_pad(size) * 2.
+    }
+
+    // a pill with `text` in it, starting at `x` and vertically centered at `cy`; returns the pill
+    pub fn badge(&mut self, text: &str, x: f32, cy: f32, size: f32, color: Color) -> Rect {
+        let pad = Self::badge_pad(size);
+        let r = self.text(text).pos(x + pad, cy).anchor(0., 0.5).no_baseline().size(size).measure();
+        let pill = Rect::new(x, r.y - pad / 2., r.w + pad * 2., r.h + pad);
+        self.fill_path(&pill.rounded(pill.h / 2.), Color { a: color.a * 0.2, ..color });
+        self.stroke_path(&pill.rounded(pill.h / 2.), 0.003, color);
+        self.text(text).pos(x + pad, cy).anchor(0., 0.5).no_baseline().size(size).color(color).draw();
+        pill
+    }
+
     pub fn loading_path(start: f32, len: f32, r: f32) -> Path {
         use lyon::math::{point, vector, Angle};
         let mut path = Path::svg_builder();
@@ -97,6 +97,10 @@ impl BillBoard {
         self.icons = Some(icons);
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
     pub fn add(&mut self, mut msg: Message) {
         msg.position = self.messages.len() as f32;
         msg.target_position = msg.position;
@@ -1,7 +1,7 @@
 crate::tl_file!("chart_info");
 
 use super::Ui;
-use crate::{ext::parse_time, info::ChartInfo, scene::show_message};
+use crate::{config::Config, ext::parse_time, info::ChartInfo, scene::show_message};
 use anyhow::Result;
 use std::{borrow::Cow, collections::HashMap};
 
@@ -11,6 +11,7 @@ pub struct ChartInfoEdit {
     pub chart: Option<String>,
     pub music: Option<String>,
     pub illustration: Option<String>,
+    pub letterbox: Option<String>,
 }
 
 impl ChartInfoEdit {
@@ -20,6 +21,7 @@ impl ChartInfoEdit {
             chart: None,
             music: None,
             illustration: None,
+            letterbox: None,
         }
     }
 
@@ -37,6 +39,9 @@ impl ChartInfoEdit {
             if let Some(illustration) = &self.illustration {
                 res.insert(self.info.illustration.clone(), tokio::fs::read(illustration).await?);
             }
+            if let (Some(letterbox), Some(name)) = (&self.letterbox, &self.info.letterbox) {
+                res.insert(name.clone(), tokio::fs::read(letterbox).await?);
+            }
         }
         Ok(res)
     }
@@ -188,28 +193,53 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
         dy!(r.h + s);
         let r = ui.checkbox(tl!("note-uniform-scale"), &mut info.note_uniform_scale);
         dy!(r.h + s);
+        let mut custom_blur = info.background_blur.is_some();
+        let r = ui.checkbox(tl!("custom-blur"), &mut custom_blur);
+        dy!(r.h + s);
+        if custom_blur != info.background_blur.is_some() {
+            info.background_blur = custom_blur.then(|| Config::default().bg_blurriness);
+        }
+        if info.force_aspect_ratio {
+            let mut letterbox_blur = info.letterbox_blur.is_some();
+            let r = ui.checkbox(tl!("letterbox-blur"), &mut letterbox_blur);
+            dy!(r.h + s);
+            if letterbox_blur != info.letterbox_blur.is_some() {
+                info.letterbox_blur = letterbox_blur.then(|| Config::default().bg_blurriness);
+            }
+        }
         ui.dx(-0.01);
 
         ui.dx(-rt);
         let r = ui.slider(tl!("dim"), 0.0..1.0, 0.05, &mut info.background_dim, Some(width - 0.2));
         dy!(r.h + s + 0.01);
+        if let Some(blur) = &mut info.background_blur {
+            let r = ui.slider(tl!("blur"), 0.0..200.0, 10., blur, Some(width - 0.2));
+            dy!(r.h + s + 0.01);
+        }
+        if let (true, Some(blur)) = (info.force_aspect_ratio, &mut info.letterbox_blur) {
+            let r = ui.slider(tl!("letterbox-blur-radius"), 0.0..200.0, 10., blur, Some(width - 0.2));
+            dy!(r.h + s + 0.01);
+        }
         ui.dx(rt);
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            use crate::scene::{request_file, return_file, take_file};
+            use crate::scene::{request_file, return_file, take_file, FileKind};
             use macroquad::prelude::Rect;
-            let mut choose_file = |id: &str, label: Cow<'static, str>, value: &str| {
+            let mut choose_file = |id: &str, kind: FileKind, label: Cow<'static, str>, value: &str| {
                 let r = ui.text(label).size(0.47).anchor(1., 0.).draw();
                 let r = Rect::new(0.02, r.y - 0.01, len, r.h + 0.02);
                 if ui.button(id, r, value) {
-                    request_file(id);
+                    request_file(id, kind);
                 }
                 dy!(r.h + s);
             };
-            choose_file("chart", tl!("chart-file"), &info.chart);
-            choose_file("music", tl!("music-file"), &info.music);
-            choose_file("illustration", tl!("illu-file"), &info.illustration);
+            choose_file("chart", FileKind::ChartFile, tl!("chart-file"), &info.chart);
+            choose_file("music", FileKind::Audio, tl!("music-file"), &info.music);
+            choose_file("illustration", FileKind::Image, tl!("illu-file"), &info.illustration);
+            if info.force_aspect_ratio {
+                choose_file("letterbox", FileKind::Image, tl!("letterbox-file"), info.letterbox.as_deref().unwrap_or("-"));
+            }
             if let Some((id, file)) = take_file() {
                 match id.as_str() {
                     "chart" => {
@@ -221,6 +251,13 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
                     "illustration" => {
                         edit.illustration = Some(file);
                     }
+                    "letterbox" => {
+                        if info.letterbox.is_none() {
+                            let ext = std::path::Path::new(&file).extension().map_or("png".into(), |it| it.to_string_lossy());
+                            info.letterbox = Some(format!("letterbox.{ext}"));
+                        }
+                        edit.letterbox = Some(file);
+                    }
                     _ => return_file(id, file),
                 }
             }
@@ -0,0 +1,172 @@
+// rough accounting of what stays in memory, drawn over everything else when enabled. Only what's registered
+// through a `MemoryGuard` is counted: textures behind `SafeTexture`, the audio of the chart being played and
+// of previews, and parsed charts. Snapshots taken when scenes are entered tell what a closed scene left behind.
+
+use super::Ui;
+use crate::ext::{semi_black, semi_white};
+use macroquad::prelude::*;
+use sasa::{AudioClip, Frame};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, AtomicI64, Ordering},
+};
+use tracing::warn;
+
+// scene exits kept for the overlay
+const HISTORY: usize = 4;
+// what a closed scene may leave behind before it's logged, caches filling up account for some of it
+const LEAK_THRESHOLD: i64 = 4 << 20;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COUNTS: [AtomicI64; 3] = [AtomicI64::new(0), AtomicI64::new(0), AtomicI64::new(0)];
+static BYTES: [AtomicI64; 3] = [AtomicI64::new(0), AtomicI64::new(0), AtomicI64::new(0)];
+
+pub fn memory_overlay_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_memory_overlay(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy)]
+pub enum MemoryKind {
+    Texture,
+    Audio,
+    Chart,
+}
+
+impl MemoryKind {
+    const ALL: [Self; 3] = [Self::Texture, Self::Audio, Self::Chart];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Texture => "textures",
+            Self::Audio => "audio",
+            Self::Chart => "charts",
+        }
+    }
+}
+
+// counts `bytes` of `kind` for as long as it lives
+pub struct MemoryGuard {
+    kind: MemoryKind,
+    bytes: i64,
+}
+
+impl MemoryGuard {
+    pub fn new(kind: MemoryKind, bytes: usize) -> Self {
+        COUNTS[kind as usize].fetch_add(1, Ordering::Relaxed);
+        BYTES[kind as usize].fetch_add(bytes as i64, Ordering::Relaxed);
+        Self { kind, bytes: bytes as i64 }
+    }
+
+    pub fn audio(clip: &AudioClip) -> Self {
+        Self::new(MemoryKind::Audio, clip.frames().len() * std::mem::size_of::<Frame>())
+    }
+
+    // for guards that are about to be forgotten instead of dropped
+    pub(crate) fn release(&self) {
+        COUNTS[self.kind as usize].fetch_sub(1, Ordering::Relaxed);
+        BYTES[self.kind as usize].fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+impl Drop for MemoryGuard {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct MemorySnapshot {
+    counts: [i64; 3],
+    bytes: [i64; 3],
+}
+
+impl MemorySnapshot {
+    pub fn take() -> Self {
+        Self {
+            counts: std::array::from_fn(|i| COUNTS[i].load(Ordering::Relaxed)),
+            bytes: std::array::from_fn(|i| BYTES[i].load(Ordering::Relaxed)),
+        }
+    }
+
+    fn since(&self, earlier: &Self) -> Self {
+        Self {
+            counts: std::array::from_fn(|i| self.counts[i] - earlier.counts[i]),
+            bytes: std::array::from_fn(|i| self.bytes[i] - earlier.bytes[i]),
+        }
+    }
+
+    fn total_bytes(&self) -> i64 {
+        self.bytes.iter().sum()
+    }
+
+    fn describe(&self) -> String {
+        MemoryKind::ALL
+            .iter()
+            .map(|kind| {
+                let i = *kind as usize;
+                format!("{:+} {} {:+.1} MB", self.counts[i], kind.label(), mb(self.bytes[i]))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn mb(bytes: i64) -> f64 {
+    bytes as f64 / (1 << 20) as f64
+}
+
+struct SceneExit {
+    scene: &'static str,
+    diff: MemorySnapshot,
+}
+
+thread_local! {
+    static EXITS: RefCell<VecDeque<SceneExit>> = RefCell::default();
+}
+
+// `before` was taken right before the scene was entered, by now it's gone along with everything it owned
+pub fn report_scene_exit(scene: &'static str, before: &MemorySnapshot) {
+    let diff = MemorySnapshot::take().since(before);
+    if diff.total_bytes() > LEAK_THRESHOLD {
+        warn!("{scene} left {} behind", diff.describe());
+    }
+    EXITS.with(|it| {
+        let mut exits = it.borrow_mut();
+        exits.push_back(SceneExit { scene, diff });
+        if exits.len() > HISTORY {
+            exits.pop_front();
+        }
+    });
+}
+
+pub fn render_memory_overlay(ui: &mut Ui) {
+    if !memory_overlay_enabled() {
+        return;
+    }
+    let now = MemorySnapshot::take();
+    let mut lines: Vec<(String, Color)> = MemoryKind::ALL
+        .iter()
+        .map(|kind| {
+            let i = *kind as usize;
+            (format!("{} {}  {:.1} MB", now.counts[i], kind.label(), mb(now.bytes[i])), semi_white(0.9))
+        })
+        .collect();
+    EXITS.with(|it| {
+        for exit in it.borrow().iter().rev() {
+            let color = if exit.diff.total_bytes() > LEAK_THRESHOLD { ORANGE } else { semi_white(0.6) };
+            lines.push((format!("{}: {:+.1} MB", exit.scene, mb(exit.diff.total_bytes())), color));
+        }
+    });
+    let r = Rect::new(0.5, -ui.top + 0.02, 0.48, 0.03 + lines.len() as f32 * 0.04);
+    ui.fill_rect(r, semi_black(0.6));
+    let mut y = r.y + 0.01;
+    for (line, color) in lines {
+        y += ui.text(line).pos(r.x + 0.01, y).size(0.35).color(color).draw().h + 0.005;
+    }
+}
@@ -0,0 +1,145 @@
+// frame timing and note batching stats, drawn over everything else when enabled
+
+use super::Ui;
+use crate::ext::{semi_black, semi_white};
+use macroquad::prelude::*;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+// frames kept for the graph and the 1% low
+const HISTORY: usize = 240;
+// frame time at the top of the graph
+const GRAPH_MAX: f32 = 1. / 20.;
+// longer gaps mean the app was in the background, not a slow frame
+const MAX_FRAME_TIME: f32 = 1.;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn perf_overlay_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_perf_overlay(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct NoteStats {
+    pub flushes: u32,
+    pub draw_calls: u32,
+    pub quads: u32,
+}
+
+#[derive(Default)]
+struct PerfStats {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<f32>,
+    notes: NoteStats,
+    last_notes: NoteStats,
+    // music position minus the game clock, only while a chart is playing
+    drift: Option<f32>,
+    last_drift: Option<f32>,
+}
+
+thread_local! {
+    static PERF: RefCell<PerfStats> = RefCell::default();
+}
+
+pub fn report_note_stats(stats: NoteStats) {
+    PERF.with(|it| {
+        let notes = &mut it.borrow_mut().notes;
+        notes.flushes += stats.flushes;
+        notes.draw_calls += stats.draw_calls;
+        notes.quads += stats.quads;
+    });
+}
+
+pub fn report_clock_drift(drift: f32) {
+    PERF.with(|it| it.borrow_mut().drift = Some(drift));
+}
+
+impl PerfStats {
+    fn frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            let dt = (now - last).as_secs_f32();
+            if dt < MAX_FRAME_TIME {
+                self.frame_times.push_back(dt);
+                if self.frame_times.len() > HISTORY {
+                    self.frame_times.pop_front();
+                }
+            }
+        }
+        self.last_notes = std::mem::take(&mut self.notes);
+        self.last_drift = self.drift.take();
+    }
+
+    // average frame rate over the slowest 1% of frames
+    fn one_percent_low(&self) -> f32 {
+        let mut sorted: Vec<f32> = self.frame_times.iter().copied().collect();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        let worst = &sorted[..(sorted.len() / 100).max(1).min(sorted.len())];
+        if worst.is_empty() {
+            return 0.;
+        }
+        worst.len() as f32 / worst.iter().sum::<f32>()
+    }
+
+    fn render(&self, ui: &mut Ui) {
+        let r = Rect::new(-0.98, -ui.top + 0.02, 0.48, 0.27);
+        ui.fill_rect(r, semi_black(0.6));
+        let avg = if self.frame_times.is_empty() {
+            0.
+        } else {
+            self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+        };
+        let notes = self.last_notes;
+        let lines = [
+            format!("{:.0} FPS  {:.2} ms", if avg > 0. { 1. / avg } else { 0. }, avg * 1000.),
+            format!("1% low {:.0} FPS", self.one_percent_low()),
+            format!("notes {} quads, {} draws, {} flushes", notes.quads, notes.draw_calls, notes.flushes),
+            match self.last_drift {
+                Some(drift) => format!("audio drift {:+.1} ms", drift * 1000.),
+                None => "audio drift -".to_owned(),
+            },
+        ];
+        let mut y = r.y + 0.01;
+        for line in lines {
+            y += ui.text(line).pos(r.x + 0.01, y).size(0.35).color(semi_white(0.9)).draw().h + 0.005;
+        }
+
+        let graph = Rect::new(r.x + 0.01, y + 0.01, r.w - 0.02, r.bottom() - y - 0.02);
+        let bar = graph.w / HISTORY as f32;
+        for (i, dt) in self.frame_times.iter().enumerate() {
+            let h = (dt / GRAPH_MAX).min(1.) * graph.h;
+            let color = if *dt <= 1. / 55. {
+                GREEN
+            } else if *dt <= 1. / 28. {
+                YELLOW
+            } else {
+                RED
+            };
+            ui.fill_rect(Rect::new(graph.x + bar * i as f32, graph.bottom() - h, bar, h), color);
+        }
+        // 60 and 30 FPS marks
+        for target in [1. / 60., 1. / 30.] {
+            let y = graph.bottom() - target / GRAPH_MAX * graph.h;
+            ui.fill_rect(Rect::new(graph.x, y, graph.w, 0.002), semi_white(0.4));
+        }
+    }
+}
+
+// called once per frame, the history keeps filling while the overlay is hidden
+pub fn render_perf_overlay(ui: &mut Ui) {
+    PERF.with(|it| {
+        let mut perf = it.borrow_mut();
+        perf.frame();
+        if perf_overlay_enabled() {
+            perf.render(ui);
+        }
+    });
+}
@@ -0,0 +1,67 @@
+crate::tl_file!("dialog");
+
+use super::{DRectButton, Ui};
+use crate::ext::semi_white;
+use anyhow::Error;
+use macroquad::prelude::*;
+use tracing::warn;
+
+// stands in for a widget whose content failed to load, so that the failure stays local instead of becoming a dialog
+#[derive(Default)]
+pub struct RetryPanel {
+    error: Option<String>,
+    btn: DRectButton,
+    retry: bool,
+}
+
+impl RetryPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fail(&mut self, error: Error) {
+        warn!("recovered from error: {error:?}");
+        self.error = Some(error.to_string());
+    }
+
+    pub fn failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.error = None;
+        self.retry = false;
+    }
+
+    pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
+        if self.error.is_some() && self.btn.touch(touch, t) {
+            self.error = None;
+            self.retry = true;
+            return true;
+        }
+        false
+    }
+
+    // whether the player asked to try again since the last call
+    pub fn take_retry(&mut self) -> bool {
+        std::mem::take(&mut self.retry)
+    }
+
+    pub fn render(&mut self, ui: &mut Ui, r: Rect, t: f32, alpha: f32) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let ct = r.center();
+        ui.text(error)
+            .pos(ct.x, ct.y - 0.01)
+            .anchor(0.5, 1.)
+            .size(0.45)
+            .max_width(r.w * 0.9)
+            .multiline()
+            .color(semi_white(alpha * 0.8))
+            .draw();
+        let w = 0.16_f32.min(r.w * 0.8);
+        let br = Rect::new(ct.x - w / 2., ct.y + 0.01, w, 0.06);
+        self.btn.render_text(ui, br, t, alpha, tl!("retry"), 0.45, true);
+    }
+}
@@ -235,6 +235,11 @@ impl Scroller {
     pub fn size(&mut self, size: f32) {
         self.size = size;
     }
+
+    // how far the content can still be scrolled forward
+    pub fn remaining(&self) -> f32 {
+        self.size - self.offset
+    }
 }
 
 pub struct Scroll {
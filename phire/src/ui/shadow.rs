@@ -1,4 +1,5 @@
 use super::Ui;
+use crate::core::use_base_material;
 use macroquad::prelude::*;
 use miniquad::{BlendFactor, BlendState, BlendValue, Equation};
 use once_cell::sync::Lazy;
@@ -72,7 +73,7 @@ pub fn rounded_rect_shadow(ui: &mut Ui, r: Rect, config: &ShadowConfig) {
     gl_use_material(mat);
     let r3 = config.elevation * 3.0;
     draw_rectangle(gr.x - r3, gr.y - r3, gr.w + r3 * 2., gr.h + r3 * 2., WHITE);
-    gl_use_default_material();
+    use_base_material();
 }
 
 pub fn rounded_rect<R>(ui: &mut Ui, r: Rect, radius: f32, f: impl FnOnce(&mut Ui) -> R) -> R {
@@ -83,7 +84,7 @@ pub fn rounded_rect<R>(ui: &mut Ui, r: Rect, radius: f32, f: impl FnOnce(&mut Ui
     mat.set_uniform("radius", radius);
     gl_use_material(mat);
     let res = f(ui);
-    gl_use_default_material();
+    use_base_material();
     res
 }
 
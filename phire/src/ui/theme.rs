@@ -0,0 +1,130 @@
+// colors and shapes of the launcher widgets, read from a theme file the same way a respack describes the game
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+use std::cell::Cell;
+
+type Rgba = (f32, f32, f32, f32);
+
+fn rgba((r, g, b, a): Rgba) -> Color {
+    Color::new(r, g, b, a)
+}
+
+fn default_accent() -> Rgba {
+    (0.85, 0.85, 0.85, 1.)
+}
+
+fn default_background() -> Rgba {
+    (0.13, 0.13, 0.13, 1.)
+}
+
+fn default_button() -> Rgba {
+    (0., 0., 0., 0.4)
+}
+
+fn default_white() -> Rgba {
+    (1., 1., 1., 1.)
+}
+
+fn default_chosen_text() -> Rgba {
+    (0.3, 0.3, 0.3, 1.)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+
+    #[serde(default = "default_accent")]
+    pub accent: Rgba,
+    #[serde(default = "default_background")]
+    pub background: Rgba,
+    // panels and cards behind the content
+    #[serde(default = "default_button")]
+    pub card: Rgba,
+
+    #[serde(default = "default_button")]
+    pub button: Rgba,
+    #[serde(default = "default_white")]
+    pub button_chosen: Rgba,
+    #[serde(default = "default_white")]
+    pub text: Rgba,
+    #[serde(default = "default_chosen_text")]
+    pub text_chosen: Rgba,
+    #[serde(default)]
+    pub button_radius: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub accent: Color,
+    pub background: Color,
+    pub card: Color,
+    pub button: Color,
+    pub button_chosen: Color,
+    pub text: Color,
+    pub text_chosen: Color,
+    pub button_radius: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: rgba(default_accent()),
+            background: rgba(default_background()),
+            card: rgba(default_button()),
+            button: rgba(default_button()),
+            button_chosen: rgba(default_white()),
+            text: rgba(default_white()),
+            text_chosen: rgba(default_chosen_text()),
+            button_radius: 0.,
+        }
+    }
+}
+
+impl From<&ThemeInfo> for Theme {
+    fn from(info: &ThemeInfo) -> Self {
+        Self {
+            accent: rgba(info.accent),
+            background: rgba(info.background),
+            card: rgba(info.card),
+            button: rgba(info.button),
+            button_chosen: rgba(info.button_chosen),
+            text: rgba(info.text),
+            text_chosen: rgba(info.text_chosen),
+            button_radius: info.button_radius,
+        }
+    }
+}
+
+fn faded(color: Color, alpha: f32) -> Color {
+    Color { a: color.a * alpha, ..color }
+}
+
+impl Theme {
+    pub fn card(&self, alpha: f32) -> Color {
+        faded(self.card, alpha)
+    }
+
+    pub fn button_fill(&self, chosen: bool, alpha: f32) -> Color {
+        faded(if chosen { self.button_chosen } else { self.button }, alpha)
+    }
+
+    pub fn button_text(&self, chosen: bool, alpha: f32) -> Color {
+        faded(if chosen { self.text_chosen } else { self.text }, alpha)
+    }
+}
+
+thread_local! {
+    static THEME: Cell<Theme> = Cell::new(Theme::default());
+}
+
+pub fn theme() -> Theme {
+    THEME.with(Cell::get)
+}
+
+pub fn set_theme(theme: Theme) {
+    THEME.with(|it| it.set(theme));
+}
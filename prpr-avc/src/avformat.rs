@@ -49,6 +49,11 @@ impl AVFormatContext {
             }
         }
     }
+
+    // seeks to the closest keyframe at or before `timestamp`, in the stream's time base
+    pub fn seek_frame(&mut self, stream_index: i32, timestamp: i64) -> AVResult<()> {
+        unsafe { handle(ffi::av_seek_frame(self.0 .0, stream_index, timestamp, ffi::AVSEEK_FLAG_BACKWARD).min(0)) }
+    }
 }
 
 unsafe impl Send for AVFormatContext {}
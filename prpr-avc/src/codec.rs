@@ -110,6 +110,10 @@ impl AVCodecContext {
             }
         }
     }
+
+    pub fn flush(&mut self) {
+        unsafe { ffi::avcodec_flush_buffers(self.0 .0) }
+    }
 }
 
 impl Drop for AVCodecContext {
@@ -12,6 +12,7 @@ extern "C" {
     ) -> ::std::os::raw::c_int;
     pub fn avformat_find_stream_info(ic: *mut AVFormatContext, options: *mut *mut c_void) -> ::std::os::raw::c_int;
     pub fn av_read_frame(s: *mut AVFormatContext, pkt: *mut AVPacket) -> ::std::os::raw::c_int;
+    pub fn av_seek_frame(s: *mut AVFormatContext, stream_index: ::std::os::raw::c_int, timestamp: i64, flags: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
 }
 
 #[link(name = "avutil", kind = "static")]
@@ -33,6 +34,7 @@ extern "C" {
     pub fn avcodec_send_packet(avctx: *mut AVCodecContext, avpkt: *const AVPacket) -> ::std::os::raw::c_int;
     pub fn avcodec_receive_frame(avctx: *mut AVCodecContext, frame: *mut AVFrame) -> ::std::os::raw::c_int;
     pub fn avcodec_default_get_format(s: *mut AVCodecContext, fmt: *const AVPixelFormat) -> AVPixelFormat;
+    pub fn avcodec_flush_buffers(avctx: *mut AVCodecContext);
 }
 
 #[link(name = "swscale", kind = "static")]
@@ -80,6 +82,8 @@ pub type AVCodec = c_void;
 pub type SwsContext = c_void;
 
 pub const AV_ERROR_MAX_STRING_SIZE: u32 = 64;
+pub const AV_NOPTS_VALUE: i64 = i64::MIN;
+pub const AVSEEK_FLAG_BACKWARD: ::std::os::raw::c_int = 1;
 pub const SWS_BICUBIC: u32 = 4;
 
 #[repr(C)]
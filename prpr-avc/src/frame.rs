@@ -34,6 +34,11 @@ impl AVFrame {
     pub fn line_size(&self) -> i32 {
         unsafe { self.0.as_ref().linesize[0] }
     }
+
+    pub fn timestamp(&self) -> Option<i64> {
+        let ts = unsafe { self.0.as_ref().best_effort_timestamp };
+        (ts != ffi::AV_NOPTS_VALUE).then_some(ts)
+    }
 }
 
 impl Drop for AVFrame {
@@ -13,6 +13,19 @@ impl AVStreamRef {
         unsafe { (*self.0).r_frame_rate.into() }
     }
 
+    pub fn time_base(&self) -> AVRational {
+        unsafe { (*self.0).time_base.into() }
+    }
+
+    pub fn start_time(&self) -> i64 {
+        let start = unsafe { (*self.0).start_time };
+        if start == ffi::AV_NOPTS_VALUE {
+            0
+        } else {
+            start
+        }
+    }
+
     pub fn is_video(&self) -> bool {
         unsafe { (*(*self.0).codecpar).codec_type == 0 }
     }
@@ -17,6 +17,7 @@ pub struct Video {
     ended: AtomicBool,
 
     mutex: Arc<(Mutex<Option<Option<&'static AVFrame>>>, Condvar)>,
+    seek_target: Arc<Mutex<Option<f64>>>,
     decode_thread: Option<JoinHandle<()>>,
 }
 
@@ -47,24 +48,58 @@ impl Video {
 
         let dropped = Arc::new(AtomicBool::default());
 
+        let seek_target = Arc::new(Mutex::new(None));
+
         let decode_thread = std::thread::spawn({
             let mut packet = AVPacket::new()?;
             let video_index = video_stream.index();
+            let time_base = video_stream.time_base().to_f64();
+            let start_time = video_stream.start_time();
             let mutex = Arc::clone(&mutex);
             let dropped = Arc::clone(&dropped);
+            let seek_target = Arc::clone(&seek_target);
             move || {
                 let mut decode_main = {
                     let mutex = Arc::clone(&mutex);
                     move || -> Result<()> {
-                        while !dropped.load(Ordering::Relaxed) && format_ctx.read_frame(&mut packet)? {
+                        // frames before this timestamp are decoded but not handed out, so seeking lands on the exact frame
+                        let mut skip_until = i64::MIN;
+                        loop {
+                            if let Some(time) = seek_target.lock().unwrap().take() {
+                                let ts = start_time + (time / time_base) as i64;
+                                format_ctx.seek_frame(video_index, ts)?;
+                                codec_ctx.flush();
+                                skip_until = ts;
+                            }
+                            if dropped.load(Ordering::Relaxed) {
+                                return Ok(());
+                            }
+                            if !format_ctx.read_frame(&mut packet)? {
+                                // wait at the end of stream until we are dropped or asked to seek back
+                                let mut frame = mutex.0.lock().unwrap();
+                                if seek_target.lock().unwrap().is_none() {
+                                    *frame = Some(None);
+                                    mutex.1.notify_one();
+                                    while !dropped.load(Ordering::Relaxed) && seek_target.lock().unwrap().is_none() {
+                                        frame = mutex.1.wait(frame).unwrap();
+                                    }
+                                }
+                                continue;
+                            }
                             if packet.stream_index() != video_index {
                                 continue;
                             }
                             codec_ctx.send_packet(&packet)?;
 
                             while codec_ctx.receive_frame(&mut in_frame)? {
+                                if in_frame.timestamp().map_or(false, |it| it < skip_until) {
+                                    continue;
+                                }
                                 sws.scale(&in_frame, &mut out_frame);
                                 let mut frame = mutex.0.lock().unwrap();
+                                if seek_target.lock().unwrap().is_some() {
+                                    break;
+                                }
                                 *frame = Some(Some(unsafe { std::mem::transmute(&out_frame) }));
                                 mutex.1.notify_one();
                                 while frame.is_some() {
@@ -75,10 +110,6 @@ impl Video {
                                 }
                             }
                         }
-                        let mut frame = mutex.0.lock().unwrap();
-                        *frame = Some(None);
-                        mutex.1.notify_one();
-                        Ok(())
                     }
                 };
                 if let Err(err) = decode_main() {
@@ -98,6 +129,7 @@ impl Video {
             ended: AtomicBool::default(),
 
             mutex,
+            seek_target,
             decode_thread: Some(decode_thread),
         })
     }
@@ -110,6 +142,16 @@ impl Video {
         self.video_stream.frame_rate()
     }
 
+    // the next frame handed out by `with_frame` will be the first one at or after `time` (in seconds)
+    pub fn seek(&self, time: f64) {
+        *self.seek_target.lock().unwrap() = Some(time);
+        let mut frame = self.mutex.0.lock().unwrap();
+        // discard the pending frame, which also wakes the decoder up
+        *frame = None;
+        self.ended.store(false, Ordering::SeqCst);
+        self.mutex.1.notify_one();
+    }
+
     pub fn with_frame<R>(&self, f: impl FnOnce(&AVFrame) -> R) -> Option<R> {
         let mut frame = self.mutex.0.lock().unwrap();
         loop {